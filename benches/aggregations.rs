@@ -9,8 +9,10 @@
 //!
 //! Run with: cargo bench --bench aggregations
 
+use arrow::array::{Array, Float64Array};
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
 use trueno::Vector;
+use trueno_db::reduce;
 
 const SMALL_SIZE: usize = 1_000; // 1K rows
 const MEDIUM_SIZE: usize = 1_000_000; // 1M rows
@@ -164,5 +166,36 @@ fn bench_avg(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, bench_sum, bench_min, bench_max, bench_avg);
+/// Benchmark the executor's Float64 SUM/MIN kernels against the per-row
+/// loops they replaced (null check + `value(i)` per element)
+fn bench_executor_f64(c: &mut Criterion) {
+    let mut group = c.benchmark_group("executor_aggregation_f64");
+
+    let array = Float64Array::from((0..MEDIUM_SIZE).map(|i| i as f64 * 0.25).collect::<Vec<_>>());
+    group.bench_with_input(BenchmarkId::new("sum_scalar_loop", MEDIUM_SIZE), &array, |b, array| {
+        b.iter(|| {
+            let array = black_box(array);
+            (0..array.len()).filter(|&i| !array.is_null(i)).map(|i| array.value(i)).sum::<f64>()
+        });
+    });
+    group.bench_with_input(BenchmarkId::new("sum_simd_kahan", MEDIUM_SIZE), &array, |b, array| {
+        b.iter(|| reduce::sum_f64(black_box(array).values()));
+    });
+    group.bench_with_input(BenchmarkId::new("min_scalar_loop", MEDIUM_SIZE), &array, |b, array| {
+        b.iter(|| {
+            let array = black_box(array);
+            (0..array.len())
+                .filter(|&i| !array.is_null(i))
+                .map(|i| array.value(i))
+                .min_by(|a, b| a.partial_cmp(b).unwrap())
+        });
+    });
+    group.bench_with_input(BenchmarkId::new("min_simd", MEDIUM_SIZE), &array, |b, array| {
+        b.iter(|| reduce::min_f64(black_box(array).values()));
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_sum, bench_min, bench_max, bench_avg, bench_executor_f64);
 criterion_main!(benches);
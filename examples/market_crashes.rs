@@ -40,10 +40,12 @@
 //!
 //! Run with: cargo run --example `market_crashes` --release
 
-use arrow::array::{Float64Array, Int32Array, RecordBatch, StringArray};
+use arrow::array::{Array, Float64Array, Int32Array, Int64Array, RecordBatch, StringArray};
 use arrow::datatypes::{DataType, Field, Schema};
 use std::sync::Arc;
 use std::time::Instant;
+use trueno_db::query::{QueryEngine, QueryExecutor};
+use trueno_db::storage::StorageEngine;
 use trueno_db::topk::{SortOrder, TopKSelection};
 
 fn main() {
@@ -96,6 +98,9 @@ fn main() {
         SortOrder::Descending,
     );
 
+    // Query 5: Realized volatility (Welford STDDEV) by market regime
+    run_volatility_query(&trading_days);
+
     print_analysis();
 }
 
@@ -310,6 +315,56 @@ fn run_crash_query(
     println!();
 }
 
+fn run_volatility_query(batch: &RecordBatch) {
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("📐 Realized Volatility by Market Regime (STDDEV of Daily Returns)");
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+
+    let storage = StorageEngine::new(vec![batch.clone()]);
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    println!("  Regime          Days     STDDEV_SAMP   VAR_POP");
+    println!("  ─────────────   ──────   ───────────   ──────────");
+    for (regime, filter) in
+        [("Calm markets", "volatility < 40"), ("Crisis days", "volatility >= 40")]
+    {
+        let sql = format!(
+            "SELECT COUNT(*) AS days, STDDEV_SAMP(daily_return) AS realized_vol, \
+             VAR_POP(daily_return) AS variance FROM market_data WHERE {filter}"
+        );
+        let plan = engine.parse(&sql).expect("Example should work with valid test data");
+        let result =
+            executor.execute(&plan, &storage).expect("Example should work with valid test data");
+
+        let days = result
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .expect("Example should work with valid test data")
+            .value(0);
+        let stats = |index: usize| {
+            let column = result
+                .column(index)
+                .as_any()
+                .downcast_ref::<Float64Array>()
+                .expect("Example should work with valid test data");
+            // NULL when undefined (fewer than two crisis days)
+            if column.is_null(0) {
+                "n/a".to_string()
+            } else {
+                format!("{:.3}", column.value(0))
+            }
+        };
+        println!("  {regime:14}  {days:6}   {:>10}%   {:>10}", stats(1), stats(2));
+    }
+
+    println!();
+    println!("  Welford's single-pass update keeps the variance stable even when");
+    println!("  the mean is large relative to the spread (Welford 1962).");
+    println!();
+}
+
 fn identify_crash_event(date: &str, return_pct: f64) -> String {
     if return_pct < -20.0 {
         "Black Monday 1987".to_string()
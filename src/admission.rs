@@ -0,0 +1,373 @@
+//! Admission control: bound concurrent queries, queue the overflow
+//!
+//! A dashboard refresh can fire dozens of queries at once. Running them all
+//! concurrently multiplies working memory (and GPU transfers) until the
+//! whole process thrashes. [`AdmissionController`] caps the number of
+//! queries in flight; excess queries wait in a FIFO queue for a bounded
+//! time, and once the queue itself is full new queries are shed immediately
+//! with [`Error::Overloaded`].
+//!
+//! Every admitted query holds an [`AdmissionPermit`]; dropping it frees the
+//! slot for the next waiter. [`AdmissionMetrics`] reports queue depth, wait
+//! time, and how many queries were shed so callers can see saturation
+//! before users do.
+//!
+//! Toyota Way Principles:
+//! - Heijunka: Level the load instead of running everything at once
+//! - Poka-Yoke: A full queue rejects work rather than exhausting memory
+//! - Visual management: Queueing is measured, not guessed
+
+use crate::query::stats::Stopwatch;
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Default maximum number of queries executing at once
+pub const DEFAULT_MAX_CONCURRENT_QUERIES: usize = 8;
+
+/// Default maximum number of queries waiting for a slot
+pub const DEFAULT_MAX_QUEUED_QUERIES: usize = 64;
+
+/// Default time a query may wait for a slot before it is rejected
+pub const DEFAULT_QUEUE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Admission limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdmissionConfig {
+    /// Maximum queries executing at once (minimum 1)
+    pub max_concurrent: usize,
+    /// Maximum queries waiting for a slot (0 = reject as soon as all slots are busy)
+    pub max_queued: usize,
+    /// Maximum time a query waits for a slot
+    pub queue_timeout: Duration,
+}
+
+impl Default for AdmissionConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent: DEFAULT_MAX_CONCURRENT_QUERIES,
+            max_queued: DEFAULT_MAX_QUEUED_QUERIES,
+            queue_timeout: DEFAULT_QUEUE_TIMEOUT,
+        }
+    }
+}
+
+/// Snapshot of admission counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct AdmissionMetrics {
+    /// Queries currently executing
+    pub in_flight: usize,
+    /// Queries currently waiting for a slot
+    pub queued: usize,
+    /// Largest queue depth observed
+    pub peak_queued: usize,
+    /// Queries admitted (immediately or after waiting)
+    pub admitted: u64,
+    /// Queries rejected because the queue was full
+    pub rejected: u64,
+    /// Queries rejected after waiting `queue_timeout`
+    pub timed_out: u64,
+    /// Total time admitted queries spent queued, in milliseconds
+    pub total_wait_ms: f64,
+}
+
+impl AdmissionMetrics {
+    /// Mean queue wait per admitted query in milliseconds
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn mean_wait_ms(&self) -> f64 {
+        if self.admitted == 0 {
+            0.0
+        } else {
+            self.total_wait_ms / self.admitted as f64
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    in_flight: usize,
+    /// Tickets of waiting queries, oldest first
+    waiting: VecDeque<u64>,
+    next_ticket: u64,
+    metrics: AdmissionMetrics,
+}
+
+#[derive(Debug)]
+struct Shared {
+    config: AdmissionConfig,
+    state: Mutex<State>,
+    slot_freed: Condvar,
+}
+
+/// Concurrency limiter shared by every query against a database
+///
+/// Cloning is cheap; clones share the same slots and metrics.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use trueno_db::admission::{AdmissionConfig, AdmissionController};
+///
+/// let admission = AdmissionController::new(AdmissionConfig {
+///     max_concurrent: 1,
+///     max_queued: 0,
+///     queue_timeout: Duration::from_millis(10),
+/// });
+///
+/// let permit = admission.acquire().unwrap();
+/// assert!(admission.acquire().is_err()); // slot busy, no queue: shed
+/// drop(permit);
+/// assert!(admission.acquire().is_ok());
+/// ```
+#[derive(Debug, Clone)]
+pub struct AdmissionController {
+    shared: Arc<Shared>,
+}
+
+impl Default for AdmissionController {
+    fn default() -> Self {
+        Self::new(AdmissionConfig::default())
+    }
+}
+
+impl AdmissionController {
+    /// Create a controller with the given limits
+    #[must_use]
+    pub fn new(config: AdmissionConfig) -> Self {
+        let config = AdmissionConfig { max_concurrent: config.max_concurrent.max(1), ..config };
+        Self {
+            shared: Arc::new(Shared {
+                config,
+                state: Mutex::new(State::default()),
+                slot_freed: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Configured limits
+    #[must_use]
+    pub fn config(&self) -> AdmissionConfig {
+        self.shared.config
+    }
+
+    /// Current counters
+    #[must_use]
+    pub fn metrics(&self) -> AdmissionMetrics {
+        let state = self.lock();
+        AdmissionMetrics {
+            in_flight: state.in_flight,
+            queued: state.waiting.len(),
+            ..state.metrics
+        }
+    }
+
+    /// Admit a query, waiting up to `queue_timeout` for a free slot
+    ///
+    /// Waiters are admitted in arrival order. Blocks the calling thread;
+    /// async callers should run this on a blocking thread.
+    ///
+    /// # Errors
+    /// Returns [`Error::Overloaded`] if the queue is full or no slot frees
+    /// up within `queue_timeout`
+    pub fn acquire(&self) -> Result<AdmissionPermit> {
+        let config = self.shared.config;
+        let mut state = self.lock();
+
+        if state.waiting.is_empty() && state.in_flight < config.max_concurrent {
+            return Ok(self.admit(&mut state, 0.0));
+        }
+
+        if state.waiting.len() >= config.max_queued {
+            state.metrics.rejected += 1;
+            return Err(Error::Overloaded(format!(
+                "{} queries running and {} queued (limits {}/{})",
+                state.in_flight,
+                state.waiting.len(),
+                config.max_concurrent,
+                config.max_queued
+            )));
+        }
+
+        let ticket = state.next_ticket;
+        state.next_ticket += 1;
+        state.waiting.push_back(ticket);
+        state.metrics.peak_queued = state.metrics.peak_queued.max(state.waiting.len());
+
+        let stopwatch = Stopwatch::start();
+        let timeout_ms = config.queue_timeout.as_secs_f64() * 1000.0;
+        loop {
+            if state.waiting.front() == Some(&ticket) && state.in_flight < config.max_concurrent {
+                state.waiting.pop_front();
+                let permit = self.admit(&mut state, stopwatch.elapsed_ms());
+                // The next waiter may also fit if several slots are free
+                self.shared.slot_freed.notify_all();
+                return Ok(permit);
+            }
+
+            let remaining_ms = timeout_ms - stopwatch.elapsed_ms();
+            if remaining_ms <= 0.0 {
+                state.waiting.retain(|&t| t != ticket);
+                state.metrics.timed_out += 1;
+                // Our departure may put another waiter at the front
+                self.shared.slot_freed.notify_all();
+                return Err(Error::Overloaded(format!(
+                    "no query slot freed within {:?} ({} running, {} queued)",
+                    config.queue_timeout,
+                    state.in_flight,
+                    state.waiting.len()
+                )));
+            }
+
+            state = self
+                .shared
+                .slot_freed
+                .wait_timeout(state, Duration::from_secs_f64(remaining_ms / 1000.0))
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+                .0;
+        }
+    }
+
+    /// Admit a query only if a slot is free right now (never queues)
+    ///
+    /// # Errors
+    /// Returns [`Error::Overloaded`] if every slot is busy or others are queued
+    pub fn try_acquire(&self) -> Result<AdmissionPermit> {
+        let mut state = self.lock();
+        if state.waiting.is_empty() && state.in_flight < self.shared.config.max_concurrent {
+            return Ok(self.admit(&mut state, 0.0));
+        }
+        state.metrics.rejected += 1;
+        let (running, queued) = (state.in_flight, state.waiting.len());
+        drop(state);
+        Err(Error::Overloaded(format!("{running} queries running and {queued} queued")))
+    }
+
+    fn admit(&self, state: &mut State, wait_ms: f64) -> AdmissionPermit {
+        state.in_flight += 1;
+        state.metrics.admitted += 1;
+        state.metrics.total_wait_ms += wait_ms;
+        AdmissionPermit { shared: Arc::clone(&self.shared) }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // Counters stay consistent even if a holder panicked
+        self.shared.state.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// A query slot; released when dropped
+#[derive(Debug)]
+#[must_use = "the slot is released as soon as the permit is dropped"]
+pub struct AdmissionPermit {
+    shared: Arc<Shared>,
+}
+
+impl Drop for AdmissionPermit {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        state.in_flight -= 1;
+        drop(state);
+        self.shared.slot_freed.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn controller(
+        max_concurrent: usize,
+        max_queued: usize,
+        timeout_ms: u64,
+    ) -> AdmissionController {
+        AdmissionController::new(AdmissionConfig {
+            max_concurrent,
+            max_queued,
+            queue_timeout: Duration::from_millis(timeout_ms),
+        })
+    }
+
+    #[test]
+    fn test_permits_released_on_drop() {
+        let admission = controller(2, 0, 10);
+        let a = admission.acquire().unwrap();
+        let _b = admission.acquire().unwrap();
+        assert_eq!(admission.metrics().in_flight, 2);
+        assert!(matches!(admission.try_acquire(), Err(Error::Overloaded(_))));
+
+        drop(a);
+        assert_eq!(admission.metrics().in_flight, 1);
+        assert!(admission.try_acquire().is_ok());
+    }
+
+    #[test]
+    fn test_full_queue_sheds_load() {
+        let admission = controller(1, 0, 10);
+        let _permit = admission.acquire().unwrap();
+        assert!(matches!(admission.acquire(), Err(Error::Overloaded(_))));
+
+        let metrics = admission.metrics();
+        assert_eq!(metrics.admitted, 1);
+        assert_eq!(metrics.rejected, 1);
+        assert_eq!(metrics.timed_out, 0);
+    }
+
+    #[test]
+    fn test_queued_query_times_out() {
+        let admission = controller(1, 4, 20);
+        let _permit = admission.acquire().unwrap();
+        assert!(matches!(admission.acquire(), Err(Error::Overloaded(_))));
+
+        let metrics = admission.metrics();
+        assert_eq!(metrics.timed_out, 1);
+        assert_eq!(metrics.queued, 0);
+        assert_eq!(metrics.peak_queued, 1);
+    }
+
+    #[test]
+    fn test_queued_query_admitted_when_slot_frees() {
+        let admission = controller(1, 4, 5_000);
+        let permit = admission.acquire().unwrap();
+
+        let waiter = {
+            let admission = admission.clone();
+            thread::spawn(move || admission.acquire().map(drop))
+        };
+        while admission.metrics().queued == 0 {
+            thread::yield_now();
+        }
+        drop(permit);
+        waiter.join().unwrap().unwrap();
+
+        let metrics = admission.metrics();
+        assert_eq!(metrics.admitted, 2);
+        assert_eq!(metrics.in_flight, 0);
+        assert!(metrics.total_wait_ms > 0.0);
+    }
+
+    #[test]
+    fn test_concurrency_never_exceeds_limit() {
+        let admission = controller(3, 64, 5_000);
+        let handles: Vec<_> = (0..16)
+            .map(|_| {
+                let admission = admission.clone();
+                thread::spawn(move || {
+                    let _permit = admission.acquire().unwrap();
+                    assert!(admission.metrics().in_flight <= 3);
+                    thread::sleep(Duration::from_millis(2));
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let metrics = admission.metrics();
+        assert_eq!(metrics.admitted, 16);
+        assert_eq!(metrics.in_flight, 0);
+    }
+}
@@ -0,0 +1,194 @@
+//! Measured hardware numbers for the cost model
+//!
+//! The presets in [`profile`](super::profile) assume nominal figures (32 GB/s
+//! for `PCIe` Gen4 x16, 100 GFLOP/s for a discrete GPU). Real machines miss
+//! them in both directions: an iGPU on a laptop may copy at a few GB/s and
+//! compute at a fraction of the preset, a workstation card far faster. A
+//! [`Calibration`] holds what was actually measured on a device:
+//!
+//! - **Host-to-device bandwidth**: time to upload a buffer and see it land
+//! - **Kernel throughput**: a SUM reduction's time minus its upload
+//!
+//! The first `GpuEngine` created in a process measures its device and
+//! [`install`]s the result; from then on
+//! [`BackendDispatcher::select`](super::BackendDispatcher::select) decides
+//! with the measured numbers instead of the constants. Measurements are
+//! persisted per device in a [`CalibrationStore`]; engines created with
+//! `GpuEngine::with_calibration_file` reuse them instead of measuring again.
+//!
+//! Toyota Way: Genchi Genbutsu (measure the machine the query runs on)
+
+use super::profile::{GpuLink, HardwareProfile};
+use super::units::{GigabytesPerSecond, GigaflopsPerSecond};
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{PoisonError, RwLock};
+
+/// Calibration used by [`BackendDispatcher::select`](super::BackendDispatcher::select)
+static INSTALLED: RwLock<Option<Calibration>> = RwLock::new(None);
+
+/// Bandwidth and throughput measured on one device
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Calibration {
+    /// Adapter identity (name, vendor/device id, backend)
+    pub device: String,
+    /// Measured host-to-device upload bandwidth
+    pub host_to_device: GigabytesPerSecond,
+    /// Measured sustained kernel throughput
+    pub gpu_throughput: GigaflopsPerSecond,
+}
+
+impl Calibration {
+    /// Whether both measurements are usable (finite and positive)
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        [self.host_to_device.0, self.gpu_throughput.0]
+            .iter()
+            .all(|value| value.is_finite() && *value > 0.0)
+    }
+}
+
+impl HardwareProfile {
+    /// This profile with the link bandwidth and GPU throughput replaced by
+    /// measured values (invalid calibrations are ignored)
+    #[must_use]
+    pub fn with_calibration(self, calibration: &Calibration) -> Self {
+        if !calibration.is_valid() {
+            return self;
+        }
+        Self {
+            link: GpuLink::Measured(calibration.host_to_device),
+            gpu_throughput: calibration.gpu_throughput,
+            ..self
+        }
+    }
+
+    /// [`discrete_desktop`](Self::discrete_desktop) with the [`installed`]
+    /// calibration applied, if any
+    #[must_use]
+    pub fn calibrated() -> Self {
+        let profile = Self::discrete_desktop();
+        installed().map_or(profile, |calibration| profile.with_calibration(&calibration))
+    }
+}
+
+/// Use `calibration` for [`BackendDispatcher::select`](super::BackendDispatcher::select)
+/// in this process (replacing any previous one)
+pub fn install(calibration: Calibration) {
+    *INSTALLED.write().unwrap_or_else(PoisonError::into_inner) = Some(calibration);
+}
+
+/// Calibration currently used by
+/// [`BackendDispatcher::select`](super::BackendDispatcher::select)
+#[must_use]
+pub fn installed() -> Option<Calibration> {
+    INSTALLED.read().unwrap_or_else(PoisonError::into_inner).clone()
+}
+
+/// Go back to the nominal constants
+pub fn uninstall() {
+    *INSTALLED.write().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+/// Calibrations persisted per device
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationStore {
+    devices: BTreeMap<String, Calibration>,
+}
+
+impl CalibrationStore {
+    /// Create an empty store
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calibration recorded for a device
+    #[must_use]
+    pub fn get(&self, device_key: &str) -> Option<&Calibration> {
+        self.devices.get(device_key)
+    }
+
+    /// Record a calibration (replacing the one for the same device)
+    pub fn insert(&mut self, calibration: Calibration) {
+        self.devices.insert(calibration.device.clone(), calibration);
+    }
+
+    /// Load a store from JSON (missing file yields an empty store)
+    ///
+    /// # Errors
+    /// Returns error if the file exists but cannot be read or parsed
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        match std::fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| {
+                Error::StorageError(format!("Invalid calibration file {}: {e}", path.display()))
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(e) => Err(Error::Io(e)),
+        }
+    }
+
+    /// Save the store as JSON
+    ///
+    /// # Errors
+    /// Returns error if the file cannot be written
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let json = serde_json::to_vec_pretty(self)
+            .map_err(|e| Error::Other(format!("Failed to encode calibrations: {e}")))?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{CostModel, PhysicsCostModel};
+    use crate::Backend;
+
+    fn laptop_igpu() -> Calibration {
+        Calibration {
+            device: "iGPU|8086:46a6|Vulkan".to_string(),
+            host_to_device: GigabytesPerSecond(2.0),
+            gpu_throughput: GigaflopsPerSecond(80.0),
+        }
+    }
+
+    #[test]
+    fn test_calibration_changes_dispatch() {
+        // 100 MB at 100 FLOPs/byte: GPU by the desktop constants
+        let (bytes, flops) = (100_000_000, 1e10);
+        let nominal = HardwareProfile::discrete_desktop();
+        assert_eq!(PhysicsCostModel::new(nominal).select(bytes, flops), Backend::Gpu);
+
+        // On a 2 GB/s link the upload costs more than the kernel saves
+        let measured = nominal.with_calibration(&laptop_igpu());
+        assert!((measured.link.bandwidth().0 - 2.0).abs() < f64::EPSILON);
+        assert_eq!(PhysicsCostModel::new(measured).select(bytes, flops), Backend::Simd);
+
+        let broken = Calibration { gpu_throughput: GigaflopsPerSecond(f64::NAN), ..laptop_igpu() };
+        assert_eq!(nominal.with_calibration(&broken), nominal);
+    }
+
+    #[test]
+    fn test_store_round_trip() {
+        let path = std::env::temp_dir()
+            .join(format!("trueno_test_calibration_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(CalibrationStore::load(&path).unwrap(), CalibrationStore::new());
+
+        let mut store = CalibrationStore::new();
+        store.insert(laptop_igpu());
+        store.save(&path).unwrap();
+        let loaded = CalibrationStore::load(&path).unwrap();
+        assert_eq!(loaded.get("iGPU|8086:46a6|Vulkan"), Some(&laptop_igpu()));
+        assert_eq!(loaded.get("other"), None);
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(CalibrationStore::load(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -3,6 +3,23 @@
 //! Toyota Way Principles:
 //! - Genchi Genbutsu: Physics-based cost model (`PCIe` Gen4 x16 = 32 GB/s)
 //! - Muda elimination: GPU only if compute > 5x transfer time
+//!
+//! Dispatch decisions go through the [`CostModel`] trait. The default
+//! [`PhysicsCostModel`] is parameterized by a [`HardwareProfile`], so an
+//! integrated laptop GPU, an A100 server, and a WASM build each get their
+//! own thresholds. Once a GPU has been calibrated (see [`calibration`]),
+//! [`BackendDispatcher::select`] uses its measured bandwidth and throughput.
+//! A [`DispatchPolicy`] overrides either, and the thresholds, per executor.
+
+pub mod calibration;
+pub mod policy;
+pub mod profile;
+pub mod units;
+
+pub use calibration::{Calibration, CalibrationStore};
+pub use policy::DispatchPolicy;
+pub use profile::{GpuClass, GpuLink, HardwareProfile, PcieGeneration, SimdWidth};
+pub use units::{GigabytesPerSecond, GigaflopsPerSecond, Seconds};
 
 /// Cost-based backend selection
 ///
@@ -13,23 +30,94 @@ pub struct BackendDispatcher {
     _private: (),
 }
 
-/// `PCIe` Gen4 x16 bandwidth: 32 GB/s
-const PCIE_BANDWIDTH_GBPS: f64 = 32.0;
+/// Estimated cost of running a workload on each backend
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CostEstimate {
+    /// Host-to-GPU transfer time
+    pub transfer: Seconds,
+    /// GPU compute time (excluding transfer)
+    pub gpu_compute: Seconds,
+    /// SIMD (CPU) compute time
+    pub simd_compute: Seconds,
+}
+
+/// Backend selection strategy
+pub trait CostModel {
+    /// Estimate per-backend cost for a workload
+    fn estimate(&self, total_bytes: usize, estimated_flops: f64) -> CostEstimate;
+
+    /// Select the backend for a workload
+    fn select(&self, total_bytes: usize, estimated_flops: f64) -> super::Backend;
+}
+
+/// Physics-based cost model driven by a [`HardwareProfile`]
+///
+/// # Algorithm
+/// 1. No GPU in profile, or data below `min_gpu_bytes` → SIMD
+/// 2. Transfer time: bytes / link bandwidth
+/// 3. GPU compute time: FLOPs / GPU throughput
+/// 4. GPU only if compute > `transfer_overhead_multiplier` × transfer
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PhysicsCostModel {
+    profile: HardwareProfile,
+}
+
+impl PhysicsCostModel {
+    /// Create a cost model for a hardware profile
+    #[must_use]
+    pub const fn new(profile: HardwareProfile) -> Self {
+        Self { profile }
+    }
 
-/// Minimum data size for GPU consideration (10 MB)
-const MIN_GPU_DATA_SIZE_BYTES: usize = 10_000_000;
+    /// Create a cost model for the current machine
+    #[must_use]
+    pub fn detect() -> Self {
+        Self::new(HardwareProfile::detect())
+    }
+
+    /// Hardware profile backing this model
+    #[must_use]
+    pub const fn profile(&self) -> &HardwareProfile {
+        &self.profile
+    }
+}
+
+impl CostModel for PhysicsCostModel {
+    fn estimate(&self, total_bytes: usize, estimated_flops: f64) -> CostEstimate {
+        CostEstimate {
+            transfer: self.profile.link.bandwidth().transfer_time(total_bytes),
+            gpu_compute: self.profile.gpu_throughput.compute_time(estimated_flops),
+            simd_compute: self.profile.simd.estimated_throughput().compute_time(estimated_flops),
+        }
+    }
+
+    fn select(&self, total_bytes: usize, estimated_flops: f64) -> super::Backend {
+        // Rule 1: GPU present and data above the minimum size threshold
+        if !self.profile.has_gpu() || total_bytes < self.profile.min_gpu_bytes {
+            return super::Backend::Simd;
+        }
 
-/// GPU compute throughput (conservative estimate: 100 GFLOP/s)
-/// Modern GPUs can achieve 1-10 TFLOP/s, but we use conservative estimate
-const GPU_THROUGHPUT_GFLOPS: f64 = 100.0;
+        // Rules 2-3: transfer and compute time (Genchi Genbutsu - physics-based decision)
+        let cost = self.estimate(total_bytes, estimated_flops);
 
-/// Transfer overhead multiplier (5x rule from spec)
-/// GPU compute must be > 5x transfer time to be worthwhile
-const TRANSFER_OVERHEAD_MULTIPLIER: f64 = 5.0;
+        // Rule 4: Apply the transfer overhead rule (5x by default)
+        if cost.gpu_compute > cost.transfer * self.profile.transfer_overhead_multiplier {
+            super::Backend::Gpu
+        } else {
+            super::Backend::Simd
+        }
+    }
+}
 
 impl BackendDispatcher {
     /// Select backend based on arithmetic intensity (FLOPs/Byte)
     ///
+    /// Uses [`HardwareProfile::calibrated`]: the
+    /// [`discrete_desktop`](HardwareProfile::discrete_desktop) constants until
+    /// a GPU has been calibrated, then the measured link bandwidth and GPU
+    /// throughput. Use a [`CostModel`] such as [`PhysicsCostModel::detect`]
+    /// for other hardware.
+    ///
     /// # Arguments
     /// * `total_bytes` - Total data size in bytes
     /// * `estimated_flops` - Estimated floating point operations
@@ -39,30 +127,43 @@ impl BackendDispatcher {
     ///
     /// # Algorithm
     /// 1. Check minimum data size threshold (10 MB)
-    /// 2. Calculate `PCIe` transfer time: bytes / 32 GB/s
-    /// 3. Estimate GPU compute time: FLOPs / 100 GFLOP/s
+    /// 2. Calculate `PCIe` transfer time: bytes / 32 GB/s (or measured)
+    /// 3. Estimate GPU compute time: FLOPs / 100 GFLOP/s (or measured)
     /// 4. Apply 5x rule: GPU only if compute > 5x transfer
     #[must_use]
-    #[allow(clippy::cast_precision_loss)]
     pub fn select(total_bytes: usize, estimated_flops: f64) -> super::Backend {
-        // Rule 1: Minimum data size threshold (10 MB)
-        if total_bytes < MIN_GPU_DATA_SIZE_BYTES {
-            return super::Backend::Simd;
-        }
-
-        // Rule 2: Calculate transfer time (PCIe Gen4 x16 = 32 GB/s)
-        let pcie_transfer_time_ms =
-            (total_bytes as f64 / (PCIE_BANDWIDTH_GBPS * 1_000_000_000.0)) * 1000.0;
+        Self::select_with(&DispatchPolicy::default(), total_bytes, estimated_flops)
+    }
 
-        // Rule 3: Estimate GPU compute time
-        let estimated_gpu_compute_ms =
-            (estimated_flops / (GPU_THROUGHPUT_GFLOPS * 1_000_000_000.0)) * 1000.0;
+    /// Select backend like [`select`](Self::select), with `policy`'s
+    /// thresholds, bandwidth, throughput or forced backend where it sets them
+    #[must_use]
+    pub fn select_with(
+        policy: &DispatchPolicy,
+        total_bytes: usize,
+        estimated_flops: f64,
+    ) -> super::Backend {
+        policy.select(total_bytes, estimated_flops)
+    }
 
-        // Rule 4: Apply 5x rule (Toyota Way: Genchi Genbutsu - physics-based decision)
-        if estimated_gpu_compute_ms > pcie_transfer_time_ms * TRANSFER_OVERHEAD_MULTIPLIER {
-            super::Backend::Gpu
-        } else {
-            super::Backend::Simd
+    /// Estimate bytes crossing `PCIe` to run a workload on `backend`
+    ///
+    /// A GPU run ships its whole input to the device; CPU backends move
+    /// nothing. Results read back are small next to the input and ignored.
+    ///
+    /// # Example
+    /// ```
+    /// use trueno_db::backend::BackendDispatcher;
+    /// use trueno_db::Backend;
+    ///
+    /// assert_eq!(BackendDispatcher::pcie_transfer_bytes(Backend::Gpu, 4096), 4096);
+    /// assert_eq!(BackendDispatcher::pcie_transfer_bytes(Backend::Simd, 4096), 0);
+    /// ```
+    #[must_use]
+    pub const fn pcie_transfer_bytes(backend: super::Backend, total_bytes: usize) -> usize {
+        match backend {
+            super::Backend::Gpu => total_bytes,
+            super::Backend::Simd | super::Backend::CostBased => 0,
         }
     }
 
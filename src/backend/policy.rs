@@ -0,0 +1,122 @@
+//! Embedder-tunable dispatch thresholds
+//!
+//! [`BackendDispatcher::select`](super::BackendDispatcher::select) decides
+//! from the desktop constants, or the measured numbers once a GPU has been
+//! calibrated. A [`DispatchPolicy`] overrides any of them (or the decision
+//! itself) for one executor, so an embedder who knows the hardware better
+//! than the cost model can tune dispatch without forking. Set it with
+//! [`DatabaseBuilder::dispatch_policy`](crate::DatabaseBuilder::dispatch_policy)
+//! or [`QueryExecutor::with_dispatch_policy`](crate::query::QueryExecutor::with_dispatch_policy).
+
+use super::profile::{GpuLink, HardwareProfile};
+use super::units::{GigabytesPerSecond, GigaflopsPerSecond};
+use super::{CostModel, PhysicsCostModel};
+use crate::Backend;
+
+/// Overrides for cost-based backend selection (`None` keeps the cost
+/// model's value)
+///
+/// # Example
+/// ```
+/// use trueno_db::backend::{DispatchPolicy, GigabytesPerSecond};
+/// use trueno_db::Backend;
+///
+/// // A laptop iGPU: small inputs already pay off, but the link is slow
+/// let policy = DispatchPolicy {
+///     min_gpu_bytes: Some(1_000_000),
+///     pcie_gbps: Some(GigabytesPerSecond(8.0)),
+///     ..DispatchPolicy::default()
+/// };
+/// assert_eq!(policy.select(100_000, 1e6), Backend::Simd);
+///
+/// let cpu_only = DispatchPolicy { force_backend: Some(Backend::Simd), ..policy };
+/// assert_eq!(cpu_only.select(1_000_000_000, 1e12), Backend::Simd);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DispatchPolicy {
+    /// Below this input size the GPU is never considered (default 10 MB)
+    pub min_gpu_bytes: Option<usize>,
+    /// GPU compute must exceed transfer time by this factor (default 5)
+    pub transfer_multiplier: Option<f64>,
+    /// Sustained GPU throughput (default measured, else 100 GFLOP/s)
+    pub gpu_gflops: Option<GigaflopsPerSecond>,
+    /// Host-to-GPU bandwidth (default measured, else 32 GB/s)
+    pub pcie_gbps: Option<GigabytesPerSecond>,
+    /// Return this backend without consulting the cost model
+    /// ([`Backend::CostBased`] is the same as `None`)
+    pub force_backend: Option<Backend>,
+}
+
+impl DispatchPolicy {
+    /// Policy without overrides (the cost model decides)
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            min_gpu_bytes: None,
+            transfer_multiplier: None,
+            gpu_gflops: None,
+            pcie_gbps: None,
+            force_backend: None,
+        }
+    }
+
+    /// [`HardwareProfile::calibrated`] with this policy's overrides applied
+    #[must_use]
+    pub fn profile(&self) -> HardwareProfile {
+        let profile = HardwareProfile::calibrated();
+        HardwareProfile {
+            min_gpu_bytes: self.min_gpu_bytes.unwrap_or(profile.min_gpu_bytes),
+            transfer_overhead_multiplier: self
+                .transfer_multiplier
+                .unwrap_or(profile.transfer_overhead_multiplier),
+            gpu_throughput: self.gpu_gflops.unwrap_or(profile.gpu_throughput),
+            link: self.pcie_gbps.map_or(profile.link, GpuLink::Measured),
+            ..profile
+        }
+    }
+
+    /// Select the backend for a workload under this policy
+    #[must_use]
+    pub fn select(&self, total_bytes: usize, estimated_flops: f64) -> Backend {
+        match self.force_backend {
+            Some(forced) if forced != Backend::CostBased => forced,
+            _ => PhysicsCostModel::new(self.profile()).select(total_bytes, estimated_flops),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_policy_keeps_cost_model() {
+        let policy = DispatchPolicy::default();
+        assert_eq!(policy.profile().gpu, HardwareProfile::calibrated().gpu);
+        assert_eq!(policy.select(1_000_000, 1e6), Backend::Simd);
+        let cost_based = DispatchPolicy { force_backend: Some(Backend::CostBased), ..policy };
+        assert_eq!(cost_based.select(1_000_000, 1e6), Backend::Simd);
+    }
+
+    #[test]
+    fn test_overrides_change_decision() {
+        // 1 MB of compute-heavy work: below the default 10 MB threshold
+        let (bytes, flops) = (1_000_000, 1e9);
+        assert_eq!(DispatchPolicy::default().select(bytes, flops), Backend::Simd);
+
+        let small_inputs = DispatchPolicy {
+            min_gpu_bytes: Some(0),
+            gpu_gflops: Some(GigaflopsPerSecond(100.0)),
+            pcie_gbps: Some(GigabytesPerSecond(32.0)),
+            ..DispatchPolicy::default()
+        };
+        assert_eq!(small_inputs.select(bytes, flops), Backend::Gpu);
+
+        // Demanding 10_000x compute over transfer rules it out again
+        let strict = DispatchPolicy { transfer_multiplier: Some(10_000.0), ..small_inputs };
+        assert_eq!(strict.select(bytes, flops), Backend::Simd);
+
+        let forced = DispatchPolicy { force_backend: Some(Backend::Gpu), ..strict };
+        assert_eq!(forced.select(0, 0.0), Backend::Gpu);
+    }
+}
@@ -0,0 +1,362 @@
+//! Hardware profiles for the cost model
+//!
+//! A [`HardwareProfile`] captures the three numbers that decide GPU dispatch:
+//! how fast data reaches the GPU, how fast the GPU computes, and how wide the
+//! CPU's SIMD fallback is. Profiles can be built by hand, taken from a preset
+//! (laptop iGPU, A100 server, WASM), or detected at runtime.
+//!
+//! Toyota Way: Genchi Genbutsu (decide from measured hardware, not a single
+//! hard-coded desktop configuration)
+
+use super::units::{GigabytesPerSecond, GigaflopsPerSecond};
+
+/// `PCIe` generation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PcieGeneration {
+    /// `PCIe` 3.0 (~1 GB/s per lane)
+    Gen3,
+    /// `PCIe` 4.0 (~2 GB/s per lane)
+    Gen4,
+    /// `PCIe` 5.0 (~4 GB/s per lane)
+    Gen5,
+}
+
+impl PcieGeneration {
+    /// Nominal per-lane bandwidth
+    #[must_use]
+    pub const fn lane_bandwidth(self) -> GigabytesPerSecond {
+        match self {
+            Self::Gen3 => GigabytesPerSecond(1.0),
+            Self::Gen4 => GigabytesPerSecond(2.0),
+            Self::Gen5 => GigabytesPerSecond(4.0),
+        }
+    }
+}
+
+/// How data reaches the GPU
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GpuLink {
+    /// Discrete GPU behind a `PCIe` link
+    Pcie {
+        /// Link generation
+        generation: PcieGeneration,
+        /// Number of lanes (typically 16)
+        lanes: u8,
+    },
+    /// Integrated GPU sharing system memory (copy bound by memory bandwidth)
+    SharedMemory(GigabytesPerSecond),
+    /// Bandwidth measured on the device (see [`Calibration`](super::Calibration))
+    Measured(GigabytesPerSecond),
+}
+
+impl GpuLink {
+    /// Effective host-to-GPU bandwidth
+    #[must_use]
+    pub fn bandwidth(self) -> GigabytesPerSecond {
+        match self {
+            Self::Pcie { generation, lanes } => {
+                GigabytesPerSecond(generation.lane_bandwidth().0 * f64::from(lanes))
+            }
+            Self::SharedMemory(bandwidth) | Self::Measured(bandwidth) => bandwidth,
+        }
+    }
+}
+
+/// Class of GPU available for dispatch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpuClass {
+    /// No usable GPU
+    None,
+    /// Integrated GPU (laptops, APUs)
+    Integrated,
+    /// Consumer discrete GPU
+    Discrete,
+    /// Datacenter accelerator (A100, H100)
+    Datacenter,
+    /// Browser WebGPU (slow readback, shared with rendering)
+    WebGpu,
+}
+
+impl GpuClass {
+    /// Conservative effective throughput for memory-bound analytics kernels
+    ///
+    /// Peak FP32 numbers are 10-100x higher; database operators rarely get
+    /// close, so the cost model uses sustained estimates.
+    #[must_use]
+    pub const fn effective_throughput(self) -> GigaflopsPerSecond {
+        match self {
+            Self::None => GigaflopsPerSecond(0.0),
+            Self::WebGpu => GigaflopsPerSecond(25.0),
+            Self::Integrated => GigaflopsPerSecond(50.0),
+            Self::Discrete => GigaflopsPerSecond(100.0),
+            Self::Datacenter => GigaflopsPerSecond(1000.0),
+        }
+    }
+}
+
+/// CPU SIMD width used by the fallback backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimdWidth {
+    /// No vector unit
+    Scalar,
+    /// 128-bit vectors (SSE2, NEON, WASM SIMD128)
+    Bits128,
+    /// 256-bit vectors (AVX2)
+    Bits256,
+    /// 512-bit vectors (AVX-512)
+    Bits512,
+}
+
+impl SimdWidth {
+    /// Number of `f32` lanes per vector
+    #[must_use]
+    pub const fn f32_lanes(self) -> u32 {
+        match self {
+            Self::Scalar => 1,
+            Self::Bits128 => 4,
+            Self::Bits256 => 8,
+            Self::Bits512 => 16,
+        }
+    }
+
+    /// Rough single-core throughput (one vector op per cycle at ~3 GHz)
+    #[must_use]
+    pub fn estimated_throughput(self) -> GigaflopsPerSecond {
+        GigaflopsPerSecond(3.0 * f64::from(self.f32_lanes()))
+    }
+}
+
+/// Hardware description consumed by [`PhysicsCostModel`](super::PhysicsCostModel)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HardwareProfile {
+    /// GPU class (`GpuClass::None` disables GPU dispatch)
+    pub gpu: GpuClass,
+    /// Host-to-GPU link
+    pub link: GpuLink,
+    /// Sustained GPU throughput
+    pub gpu_throughput: GigaflopsPerSecond,
+    /// CPU SIMD width
+    pub simd: SimdWidth,
+    /// Below this size GPU dispatch is never considered
+    pub min_gpu_bytes: usize,
+    /// GPU compute must exceed transfer time by this factor (the "5x rule")
+    pub transfer_overhead_multiplier: f64,
+}
+
+impl Default for HardwareProfile {
+    fn default() -> Self {
+        Self::discrete_desktop()
+    }
+}
+
+impl HardwareProfile {
+    /// Desktop with a discrete GPU on `PCIe` Gen4 x16 (the original cost model)
+    #[must_use]
+    pub const fn discrete_desktop() -> Self {
+        Self {
+            gpu: GpuClass::Discrete,
+            link: GpuLink::Pcie { generation: PcieGeneration::Gen4, lanes: 16 },
+            gpu_throughput: GpuClass::Discrete.effective_throughput(),
+            simd: SimdWidth::Bits256,
+            min_gpu_bytes: 10_000_000,
+            transfer_overhead_multiplier: 5.0,
+        }
+    }
+
+    /// Laptop with an integrated GPU sharing system memory
+    #[must_use]
+    pub const fn integrated_laptop() -> Self {
+        Self {
+            gpu: GpuClass::Integrated,
+            link: GpuLink::SharedMemory(GigabytesPerSecond(50.0)),
+            gpu_throughput: GpuClass::Integrated.effective_throughput(),
+            simd: SimdWidth::Bits256,
+            min_gpu_bytes: 1_000_000,
+            transfer_overhead_multiplier: 2.0,
+        }
+    }
+
+    /// Server with a datacenter accelerator (A100-class) on `PCIe` Gen4 x16
+    #[must_use]
+    pub const fn datacenter_server() -> Self {
+        Self {
+            gpu: GpuClass::Datacenter,
+            link: GpuLink::Pcie { generation: PcieGeneration::Gen4, lanes: 16 },
+            gpu_throughput: GpuClass::Datacenter.effective_throughput(),
+            simd: SimdWidth::Bits512,
+            min_gpu_bytes: 10_000_000,
+            transfer_overhead_multiplier: 5.0,
+        }
+    }
+
+    /// Browser (WASM SIMD128, WebGPU behind a slow readback path)
+    #[must_use]
+    pub const fn wasm() -> Self {
+        Self {
+            gpu: GpuClass::WebGpu,
+            link: GpuLink::SharedMemory(GigabytesPerSecond(4.0)),
+            gpu_throughput: GpuClass::WebGpu.effective_throughput(),
+            simd: SimdWidth::Bits128,
+            min_gpu_bytes: 50_000_000,
+            transfer_overhead_multiplier: 5.0,
+        }
+    }
+
+    /// CPU-only machine (GPU dispatch disabled)
+    #[must_use]
+    pub const fn cpu_only(simd: SimdWidth) -> Self {
+        Self {
+            gpu: GpuClass::None,
+            link: GpuLink::Pcie { generation: PcieGeneration::Gen4, lanes: 16 },
+            gpu_throughput: GpuClass::None.effective_throughput(),
+            simd,
+            min_gpu_bytes: usize::MAX,
+            transfer_overhead_multiplier: 5.0,
+        }
+    }
+
+    /// Detect the profile of the current machine
+    ///
+    /// SIMD width comes from runtime CPU feature detection. The GPU is only
+    /// probed when the `gpu` feature is enabled; otherwise GPU dispatch is
+    /// disabled.
+    #[must_use]
+    pub fn detect() -> Self {
+        #[cfg(target_arch = "wasm32")]
+        {
+            Self::wasm()
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let simd = detect_simd_width();
+            match detect_gpu_class() {
+                GpuClass::Integrated => Self { simd, ..Self::integrated_laptop() },
+                GpuClass::Discrete => Self { simd, ..Self::discrete_desktop() },
+                GpuClass::Datacenter => Self { simd, ..Self::datacenter_server() },
+                GpuClass::None | GpuClass::WebGpu => Self::cpu_only(simd),
+            }
+        }
+    }
+
+    /// Whether this profile can dispatch to a GPU at all
+    #[must_use]
+    pub fn has_gpu(&self) -> bool {
+        self.gpu != GpuClass::None && self.gpu_throughput.0 > 0.0
+    }
+}
+
+/// Widest SIMD instruction set supported by the running CPU
+#[cfg(not(target_arch = "wasm32"))]
+fn detect_simd_width() -> SimdWidth {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if std::arch::is_x86_feature_detected!("avx512f") {
+            SimdWidth::Bits512
+        } else if std::arch::is_x86_feature_detected!("avx2") {
+            SimdWidth::Bits256
+        } else {
+            SimdWidth::Bits128
+        }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        SimdWidth::Bits128
+    }
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    {
+        SimdWidth::Scalar
+    }
+}
+
+/// Best GPU class among the adapters wgpu can see
+#[cfg(all(feature = "gpu", not(target_arch = "wasm32")))]
+fn detect_gpu_class() -> GpuClass {
+    let instance = wgpu::Instance::default();
+    instance
+        .enumerate_adapters(wgpu::Backends::all())
+        .iter()
+        .map(|adapter| classify_adapter(&adapter.get_info()))
+        .max_by_key(|class| match class {
+            GpuClass::None => 0,
+            GpuClass::WebGpu => 1,
+            GpuClass::Integrated => 2,
+            GpuClass::Discrete => 3,
+            GpuClass::Datacenter => 4,
+        })
+        .unwrap_or(GpuClass::None)
+}
+
+#[cfg(all(not(feature = "gpu"), not(target_arch = "wasm32")))]
+const fn detect_gpu_class() -> GpuClass {
+    GpuClass::None
+}
+
+#[cfg(all(feature = "gpu", not(target_arch = "wasm32")))]
+fn classify_adapter(info: &wgpu::AdapterInfo) -> GpuClass {
+    const DATACENTER_MARKERS: [&str; 5] = ["A100", "H100", "H200", "L40", "MI300"];
+
+    match info.device_type {
+        wgpu::DeviceType::DiscreteGpu | wgpu::DeviceType::VirtualGpu => {
+            if DATACENTER_MARKERS.iter().any(|marker| info.name.contains(marker)) {
+                GpuClass::Datacenter
+            } else {
+                GpuClass::Discrete
+            }
+        }
+        wgpu::DeviceType::IntegratedGpu => GpuClass::Integrated,
+        wgpu::DeviceType::Cpu | wgpu::DeviceType::Other => GpuClass::None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pcie_gen4_x16_is_32_gbps() {
+        let link = GpuLink::Pcie { generation: PcieGeneration::Gen4, lanes: 16 };
+        assert!((link.bandwidth().0 - 32.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_pcie_bandwidth_scales_with_generation_and_lanes() {
+        let gen3_x8 = GpuLink::Pcie { generation: PcieGeneration::Gen3, lanes: 8 };
+        let gen5_x16 = GpuLink::Pcie { generation: PcieGeneration::Gen5, lanes: 16 };
+        assert!((gen3_x8.bandwidth().0 - 8.0).abs() < f64::EPSILON);
+        assert!((gen5_x16.bandwidth().0 - 64.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_default_profile_matches_original_constants() {
+        let profile = HardwareProfile::default();
+        assert_eq!(profile.min_gpu_bytes, 10_000_000);
+        assert!((profile.gpu_throughput.0 - 100.0).abs() < f64::EPSILON);
+        assert!((profile.transfer_overhead_multiplier - 5.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_cpu_only_profile_has_no_gpu() {
+        assert!(!HardwareProfile::cpu_only(SimdWidth::Bits256).has_gpu());
+        assert!(HardwareProfile::integrated_laptop().has_gpu());
+    }
+
+    #[test]
+    fn test_simd_lanes() {
+        assert_eq!(SimdWidth::Scalar.f32_lanes(), 1);
+        assert_eq!(SimdWidth::Bits512.f32_lanes(), 16);
+        assert!(
+            SimdWidth::Bits512.estimated_throughput() > SimdWidth::Bits128.estimated_throughput()
+        );
+    }
+
+    #[test]
+    fn test_detect_returns_consistent_profile() {
+        let profile = HardwareProfile::detect();
+        if !profile.has_gpu() {
+            assert_eq!(profile.min_gpu_bytes, usize::MAX);
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! Unit-carrying quantities for the cost model
+//!
+//! Bandwidths and throughputs are easy to mix up when they are all `f64`
+//! (GB/s vs GiB/s, GFLOP/s vs FLOP/s, ms vs s). These newtypes keep the unit
+//! in the type so conversions happen in exactly one place.
+
+use serde::{Deserialize, Serialize};
+
+/// Data transfer bandwidth in gigabytes (10^9 bytes) per second
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct GigabytesPerSecond(pub f64);
+
+/// Compute throughput in GFLOP/s (10^9 floating point operations per second)
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct GigaflopsPerSecond(pub f64);
+
+/// Duration in seconds
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Seconds(pub f64);
+
+impl GigabytesPerSecond {
+    /// Time to move `bytes` at this bandwidth
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn transfer_time(self, bytes: usize) -> Seconds {
+        Seconds(bytes as f64 / (self.0 * 1e9))
+    }
+}
+
+impl GigaflopsPerSecond {
+    /// Time to execute `flops` floating point operations at this throughput
+    #[must_use]
+    pub fn compute_time(self, flops: f64) -> Seconds {
+        Seconds(flops / (self.0 * 1e9))
+    }
+}
+
+impl Seconds {
+    /// Value in milliseconds
+    #[must_use]
+    pub fn as_millis(self) -> f64 {
+        self.0 * 1000.0
+    }
+}
+
+impl std::ops::Add for Seconds {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl std::ops::Mul<f64> for Seconds {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self {
+        Self(self.0 * rhs)
+    }
+}
@@ -0,0 +1,265 @@
+//! truenodb: query Parquet and CSV files from the command line.
+//!
+//! Each file is registered as a table named after its stem
+//! (`sales.parquet` → `sales`). With `-e` one query runs and the result is
+//! printed; otherwise an interactive prompt reads statements ending in `;`.
+//! Each result is followed (on stderr) by its row count, the rows scanned,
+//! the execution time and the backend that ran it.
+//!
+//! Usage:
+//!   truenodb data/sales.parquet -e "SELECT region, SUM(amount) FROM sales GROUP BY region"
+//!   truenodb --backend gpu data/sales.parquet data/regions.csv
+//!   truenodb data/sales.parquet --format jsonl --precision 2 -e "SELECT * FROM sales"
+//!
+//! Prompt commands: `.tables`, `.schema <table>`, `.help`, `.quit`.
+
+use anyhow::Context;
+use arrow::array::{ArrayRef, AsArray, StringArray};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Float64Type, Schema};
+use arrow::record_batch::RecordBatch;
+use arrow::util::pretty::pretty_format_batches;
+use clap::{Parser, ValueEnum};
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+use trueno_db::output::{self, OutputOptions};
+use trueno_db::query::QueryStats;
+use trueno_db::storage::StorageEngine;
+use trueno_db::{Backend, Database};
+
+/// Query Parquet and CSV files with trueno-db.
+#[derive(Parser)]
+#[command(name = "truenodb", version, about)]
+struct Cli {
+    /// Parquet or CSV files, each registered as a table named after its stem.
+    #[arg(required = true, value_name = "FILE")]
+    files: Vec<PathBuf>,
+
+    /// Run one SQL query, print the result, and exit.
+    #[arg(short = 'e', long, value_name = "SQL")]
+    execute: Option<String>,
+
+    /// Execution backend.
+    #[arg(long, value_enum, default_value_t = BackendArg::Auto)]
+    backend: BackendArg,
+
+    /// How results are printed.
+    #[arg(long, value_enum, default_value_t = Format::Table)]
+    format: Format,
+
+    /// Digits after the decimal point for floating-point results.
+    #[arg(long, value_name = "DIGITS")]
+    precision: Option<usize>,
+}
+
+/// Result format selected on the command line.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// Aligned text table.
+    Table,
+    /// CSV with a header row.
+    Csv,
+    /// JSON array of row objects.
+    Json,
+    /// One JSON object per line.
+    Jsonl,
+}
+
+/// Backend selected on the command line.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum BackendArg {
+    /// Cost-based dispatch (GPU when available and worth the transfer).
+    Auto,
+    /// Force GPU execution (needs the `gpu` feature and an adapter).
+    Gpu,
+    /// Force SIMD execution.
+    Simd,
+}
+
+impl From<BackendArg> for Backend {
+    fn from(backend: BackendArg) -> Self {
+        match backend {
+            BackendArg::Auto => Self::CostBased,
+            BackendArg::Gpu => Self::Gpu,
+            BackendArg::Simd => Self::Simd,
+        }
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    let mut db =
+        Database::builder().backend(cli.backend.into()).query_stats(true).build().with_context(
+            || format!("cannot start the {} backend", Backend::from(cli.backend).name()),
+        )?;
+    for path in &cli.files {
+        let name = register_file(&mut db, path)?;
+        eprintln!("{name}: {}", path.display());
+    }
+
+    match &cli.execute {
+        Some(sql) => run(&mut db, &cli, sql),
+        None => repl(&mut db, &cli),
+    }
+}
+
+/// Load `path` by extension and register it under its stem.
+fn register_file(db: &mut Database, path: &Path) -> anyhow::Result<String> {
+    let name = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .with_context(|| format!("cannot name a table after {}", path.display()))?
+        .to_string();
+    let extension = path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase);
+    let storage = match extension.as_deref() {
+        Some("parquet") => StorageEngine::load_parquet(path),
+        Some("csv") => StorageEngine::load_csv(path),
+        _ => anyhow::bail!("{}: expected a .parquet or .csv file", path.display()),
+    }
+    .with_context(|| format!("cannot load {}", path.display()))?;
+    db.register_table(name.as_str(), storage)?;
+    Ok(name)
+}
+
+/// Run `sql` and print the result, then its row count and query stats
+/// (rows scanned, execution time, backend).
+fn run(db: &mut Database, cli: &Cli, sql: &str) -> anyhow::Result<()> {
+    let started = Instant::now();
+    let result = db.sql(sql)?;
+    print_result(&result, cli)?;
+    let rows =
+        format!("{} row{}", result.num_rows(), if result.num_rows() == 1 { "" } else { "s" });
+    match QueryStats::from_batch(&result) {
+        Some(stats) => eprintln!(
+            "{rows} ({} scanned, {:.1} ms, {})",
+            stats.rows_scanned,
+            stats.elapsed_ms,
+            stats.backend.name()
+        ),
+        // Results the executor did not produce (e.g. EXPLAIN) carry no stats
+        None => eprintln!("{rows} ({:.1} ms)", started.elapsed().as_secs_f64() * 1000.0),
+    }
+    Ok(())
+}
+
+fn print_result(result: &RecordBatch, cli: &Cli) -> anyhow::Result<()> {
+    let options = OutputOptions { float_precision: cli.precision, ..OutputOptions::default() };
+    match cli.format {
+        Format::Table => {
+            let result = match cli.precision {
+                Some(digits) => round_floats(result, digits)?,
+                None => result.clone(),
+            };
+            println!("{}", pretty_format_batches(&[result])?);
+        }
+        Format::Csv => print!("{}", output::to_csv(result, &options)?),
+        Format::Json => println!("{}", output::to_json_rows(result, &options)?),
+        Format::Jsonl => print!("{}", output::to_json_lines(result, &options)?),
+    }
+    Ok(())
+}
+
+/// `result` with float columns rendered as text with `digits` decimals (the
+/// table printer has no precision option).
+fn round_floats(result: &RecordBatch, digits: usize) -> anyhow::Result<RecordBatch> {
+    let schema = result.schema();
+    let mut fields = Vec::with_capacity(schema.fields().len());
+    let mut columns = Vec::with_capacity(schema.fields().len());
+    for (field, column) in schema.fields().iter().zip(result.columns()) {
+        if !matches!(field.data_type(), DataType::Float32 | DataType::Float64) {
+            fields.push(field.as_ref().clone());
+            columns.push(Arc::clone(column));
+            continue;
+        }
+        let values = cast(column, &DataType::Float64)?;
+        let text: StringArray = values
+            .as_primitive::<Float64Type>()
+            .iter()
+            .map(|v| v.map(|v| format!("{v:.digits$}")))
+            .collect();
+        fields.push(field.as_ref().clone().with_data_type(DataType::Utf8));
+        columns.push(Arc::new(text) as ArrayRef);
+    }
+    Ok(RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)?)
+}
+
+/// Read statements until `.quit` or end of input; errors are reported and
+/// the prompt continues.
+fn repl(db: &mut Database, cli: &Cli) -> anyhow::Result<()> {
+    let mut editor = DefaultEditor::new()?;
+    let mut statement = String::new();
+    loop {
+        let prompt = if statement.is_empty() { "truenodb> " } else { "     ...> " };
+        let line = match editor.readline(prompt) {
+            Ok(line) => line,
+            // Ctrl-C drops the statement being typed
+            Err(ReadlineError::Interrupted) => {
+                statement.clear();
+                continue;
+            }
+            Err(ReadlineError::Eof) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        let line = line.trim();
+        if statement.is_empty() && line.starts_with('.') {
+            let _ = editor.add_history_entry(line);
+            match command(db, line) {
+                Ok(true) => {}
+                Ok(false) => return Ok(()),
+                Err(e) => eprintln!("error: {e:#}"),
+            }
+            continue;
+        }
+
+        if !statement.is_empty() {
+            statement.push('\n');
+        }
+        statement.push_str(line);
+        if !statement.ends_with(';') {
+            continue;
+        }
+        let _ = editor.add_history_entry(statement.as_str());
+        let sql = statement.trim_end_matches(';').trim();
+        if !sql.is_empty() {
+            if let Err(e) = run(db, cli, sql) {
+                eprintln!("error: {e:#}");
+            }
+        }
+        statement.clear();
+    }
+}
+
+/// Run a prompt command; `false` means quit.
+fn command(db: &mut Database, line: &str) -> anyhow::Result<bool> {
+    let mut words = line.split_whitespace();
+    match (words.next(), words.next()) {
+        (Some(".quit" | ".exit"), _) => return Ok(false),
+        (Some(".tables"), _) => {
+            for name in db.catalog().table_names() {
+                println!("{name}");
+            }
+        }
+        (Some(".schema"), Some(name)) => {
+            let storage = db.table(name)?.with_context(|| format!("no table named {name}"))?;
+            let Some(batch) = storage.batches().first() else {
+                println!("{name}: empty");
+                return Ok(true);
+            };
+            for field in batch.schema().fields() {
+                let null = if field.is_nullable() { "" } else { " NOT NULL" };
+                println!("{}  {}{null}", field.name(), field.data_type());
+            }
+        }
+        (Some(".help"), _) => println!(
+            "SQL statements end with ';'\n\
+             .tables          list tables\n\
+             .schema <table>  show a table's columns\n\
+             .quit            exit"
+        ),
+        _ => anyhow::bail!("unknown command {line} (try .help)"),
+    }
+    Ok(true)
+}
@@ -0,0 +1,37 @@
+//! Cooperative cancellation shared by queries and the GPU work they start
+//!
+//! A [`CancellationToken`] is checked at safe points: between morsels and
+//! operators in
+//! [`QueryExecutor::execute_async`](crate::query::QueryExecutor::execute_async)
+//! and between device polls while waiting on GPU submissions. Work already
+//! running on a CPU thread finishes its current morsel before noticing.
+//!
+//! Toyota Way: Jidoka (any caller can pull the cord and stop the line)
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Cooperative cancellation flag shared between a query and its GPU work
+///
+/// Clones share the flag; cancelling any clone cancels all.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// New, not yet cancelled token
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation of every wait holding this token
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    /// Whether [`cancel`](Self::cancel) has been called
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
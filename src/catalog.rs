@@ -0,0 +1,593 @@
+//! Table catalog: named tables and attached data directories
+//!
+//! `attach_dir` is the fast on-ramp for exploring a folder of data files:
+//! every supported file becomes a table named after its file stem, and every
+//! subdirectory becomes one table made of all the files beneath it
+//! (Hive-style partitioned datasets).
+//!
+//! Temporary tables ([`register_temporary`](Catalog::register_temporary))
+//! are queryable like any other table but belong to the session: they are
+//! never persisted in snapshots and can be dropped all at once.
+//!
+//! With a memory limit (`with_memory_limit`, `ipc-io` feature), cold tables
+//! are evicted to disk and reloaded by [`table`](Catalog::table); see
+//! `storage::eviction`.
+//!
+//! Supported formats depend on enabled features:
+//! - `parquet-io`: `.parquet`
+//! - `ipc-io`: `.arrow`, `.ipc`, `.feather`
+//! - `csv-io`: `.csv`
+
+#[cfg(feature = "ipc-io")]
+use crate::storage::eviction::{EvictionConfig, EvictionMetrics, MemoryManager};
+use crate::storage::StorageEngine;
+use crate::{Error, Result};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Catalog of named tables
+#[derive(Default)]
+pub struct Catalog {
+    tables: HashMap<String, StorageEngine>,
+    /// Attached directories and the table names they registered
+    attached: Vec<(PathBuf, Vec<String>)>,
+    /// Inject provenance columns into tables loaded by `attach_dir`
+    provenance: bool,
+    /// Names of session-scoped temporary tables
+    temporary: HashSet<String>,
+    /// Evicts cold tables to disk (set by `with_memory_limit`)
+    #[cfg(feature = "ipc-io")]
+    memory: Option<MemoryManager>,
+}
+
+impl Catalog {
+    /// Create an empty catalog
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tag rows loaded by [`attach_dir`](Self::attach_dir) with provenance
+    /// columns (`_source_file`, `_batch_id`, `_ingest_ts`)
+    #[must_use]
+    pub const fn with_provenance(mut self, enabled: bool) -> Self {
+        self.provenance = enabled;
+        self
+    }
+
+    /// Keep resident tables within a memory limit by evicting cold ones to disk
+    ///
+    /// Least recently used tables are evicted when [`table`](Self::table),
+    /// [`attach_dir`](Self::attach_dir), or [`evict_cold`](Self::evict_cold)
+    /// finds the limit exceeded, and reloaded by [`table`](Self::table).
+    /// [`get`](Self::get) and [`iter`](Self::iter) only see resident tables.
+    #[cfg(feature = "ipc-io")]
+    #[must_use]
+    pub fn with_memory_limit(mut self, config: EvictionConfig) -> Self {
+        let mut memory = MemoryManager::new(config);
+        for name in self.table_names() {
+            memory.touch(name);
+        }
+        self.memory = Some(memory);
+        self
+    }
+
+    /// Evict least recently used tables until resident tables fit the memory limit
+    ///
+    /// Returns the number of tables evicted (0 without a memory limit).
+    ///
+    /// # Errors
+    /// Returns error if a spill file cannot be written
+    #[cfg(feature = "ipc-io")]
+    pub fn evict_cold(&mut self) -> Result<usize> {
+        self.memory.as_mut().map_or(Ok(0), |memory| memory.enforce(&mut self.tables, None))
+    }
+
+    /// Resident/spilled sizes and eviction counters (`None` without a memory limit)
+    #[cfg(feature = "ipc-io")]
+    #[must_use]
+    pub fn eviction_metrics(&self) -> Option<EvictionMetrics> {
+        self.memory.as_ref().map(|memory| memory.metrics(&self.tables))
+    }
+
+    /// Check whether `name` is currently evicted to disk
+    #[cfg(feature = "ipc-io")]
+    #[must_use]
+    pub fn is_evicted(&self, name: &str) -> bool {
+        self.memory.as_ref().is_some_and(|memory| memory.is_evicted(name))
+    }
+
+    /// Register a table, returning the previous resident table with that name (if any)
+    ///
+    /// Registering never evicts (it cannot fail); a memory limit is
+    /// enforced on the next [`table`](Self::table) or `evict_cold`.
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        storage: StorageEngine,
+    ) -> Option<StorageEngine> {
+        let name = name.into();
+        self.temporary.remove(&name);
+        self.replaced(&name);
+        self.tables.insert(name, storage)
+    }
+
+    /// Register a session-scoped temporary table
+    ///
+    /// Replaces an existing temporary table of the same name, returning it.
+    ///
+    /// # Errors
+    /// Returns error if a persistent table already uses `name` (temporary
+    /// tables never shadow persistent ones)
+    pub fn register_temporary(
+        &mut self,
+        name: impl Into<String>,
+        storage: StorageEngine,
+    ) -> Result<Option<StorageEngine>> {
+        let name = name.into();
+        if self.contains(&name) && !self.temporary.contains(&name) {
+            return Err(Error::InvalidInput(format!(
+                "Table '{name}' already exists and is not temporary"
+            )));
+        }
+
+        self.temporary.insert(name.clone());
+        self.replaced(&name);
+        Ok(self.tables.insert(name, storage))
+    }
+
+    /// Check whether `name` is a temporary table
+    #[must_use]
+    pub fn is_temporary(&self, name: &str) -> bool {
+        self.temporary.contains(name)
+    }
+
+    /// Drop every temporary table (end of session)
+    pub fn clear_temporary(&mut self) {
+        for name in std::mem::take(&mut self.temporary) {
+            self.remove(&name);
+        }
+    }
+
+    /// Remove a table from the catalog (an evicted table is read back first)
+    pub fn deregister(&mut self, name: &str) -> Option<StorageEngine> {
+        self.temporary.remove(name);
+        #[cfg(feature = "ipc-io")]
+        if let Some(memory) = self.memory.as_mut().filter(|memory| memory.is_evicted(name)) {
+            let storage = memory.read(name).ok().flatten();
+            memory.forget(name);
+            return storage;
+        }
+        self.remove(name)
+    }
+
+    /// Look up a resident table by name
+    ///
+    /// Returns `None` for a table evicted to disk; use [`table`](Self::table)
+    /// to reload it.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&StorageEngine> {
+        self.tables.get(name)
+    }
+
+    /// Look up a table, reloading it from disk if it was evicted
+    ///
+    /// With a memory limit, the table becomes the most recently used and
+    /// colder tables are evicted to make room (never this one). Without a
+    /// limit this is [`get`](Self::get).
+    ///
+    /// # Errors
+    /// Returns error if the table cannot be reloaded or others cannot be evicted
+    #[cfg_attr(not(feature = "ipc-io"), allow(clippy::unnecessary_wraps))]
+    pub fn table(&mut self, name: &str) -> Result<Option<&StorageEngine>> {
+        #[cfg(feature = "ipc-io")]
+        if let Some(memory) = self.memory.as_mut() {
+            memory.reload(&mut self.tables, name)?;
+            if self.tables.contains_key(name) {
+                memory.touch(name);
+                memory.enforce(&mut self.tables, Some(name))?;
+            }
+        }
+        Ok(self.tables.get(name))
+    }
+
+    /// Mutable resident table (to append to), reloading it if evicted
+    ///
+    /// Returns `Ok(None)` if no table is registered under `name`. Growth is
+    /// charged to the memory limit on the next [`table`](Self::table).
+    ///
+    /// # Errors
+    /// Returns error if the table cannot be reloaded or others cannot be evicted
+    pub fn table_mut(&mut self, name: &str) -> Result<Option<&mut StorageEngine>> {
+        self.table(name)?;
+        Ok(self.tables.get_mut(name))
+    }
+
+    /// Run `f` on a table, reading an evicted table from disk without
+    /// making it resident (snapshots, exports)
+    ///
+    /// Returns `Ok(None)` if no table is registered under `name`.
+    ///
+    /// # Errors
+    /// Returns error if an evicted table cannot be read
+    #[cfg_attr(not(feature = "ipc-io"), allow(clippy::unnecessary_wraps))]
+    pub fn with_table<R>(
+        &self,
+        name: &str,
+        f: impl FnOnce(&StorageEngine) -> R,
+    ) -> Result<Option<R>> {
+        if let Some(storage) = self.tables.get(name) {
+            return Ok(Some(f(storage)));
+        }
+        #[cfg(feature = "ipc-io")]
+        if let Some(memory) = &self.memory {
+            return Ok(memory.read(name)?.map(|storage| f(&storage)));
+        }
+        Ok(None)
+    }
+
+    /// Check whether a table (resident or evicted) is registered under `name`
+    #[must_use]
+    pub fn contains(&self, name: &str) -> bool {
+        #[cfg(feature = "ipc-io")]
+        if self.is_evicted(name) {
+            return true;
+        }
+        self.tables.contains_key(name)
+    }
+
+    /// Sorted list of registered table names (including evicted tables)
+    #[must_use]
+    pub fn table_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.tables.keys().map(String::as_str).collect();
+        #[cfg(feature = "ipc-io")]
+        if let Some(memory) = &self.memory {
+            names.extend(memory.evicted_names());
+        }
+        names.sort_unstable();
+        names
+    }
+
+    /// Sorted names of persistent (non-temporary) tables, including evicted ones
+    #[must_use]
+    pub fn persistent_table_names(&self) -> Vec<&str> {
+        let mut names = self.table_names();
+        names.retain(|name| !self.temporary.contains(*name));
+        names
+    }
+
+    /// Iterate over resident (name, table) pairs in name order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &StorageEngine)> {
+        let mut entries: Vec<(&str, &StorageEngine)> =
+            self.tables.iter().map(|(name, storage)| (name.as_str(), storage)).collect();
+        entries.sort_unstable_by_key(|(name, _)| *name);
+        entries.into_iter()
+    }
+
+    /// Iterate over resident persistent (non-temporary) tables in name order
+    pub fn persistent_iter(&self) -> impl Iterator<Item = (&str, &StorageEngine)> {
+        self.iter().filter(|(name, _)| !self.temporary.contains(*name))
+    }
+
+    /// Number of registered tables (including evicted tables)
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.table_names().len()
+    }
+
+    /// Check if the catalog has no tables
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Scan a directory and register each data file or subdirectory as a table
+    ///
+    /// Files are named by stem (`events.parquet` → `events`); subdirectories
+    /// by directory name, combining every supported file beneath them.
+    /// Hidden entries (leading `.`) and unsupported extensions are skipped.
+    ///
+    /// # Returns
+    /// Sorted names of the tables registered from this directory
+    ///
+    /// # Errors
+    /// Returns error if the directory cannot be read, a file fails to load,
+    /// two entries map to the same table name, an entry clashes with a table
+    /// registered elsewhere, or files in one subdirectory have different
+    /// schemas. The catalog is then unchanged: re-attaching a directory keeps
+    /// the tables of its previous scan.
+    pub fn attach_dir<P: AsRef<Path>>(&mut self, path: P) -> Result<Vec<String>> {
+        let path = path.as_ref().to_path_buf();
+        let tables = scan_dir(&path, self.provenance)?;
+
+        // Only the tables this directory registered last time may be replaced
+        let previous =
+            self.attached.iter().find(|(dir, _)| *dir == path).map(|(_, names)| names.as_slice());
+        let clash = tables.iter().find(|(name, _)| {
+            self.contains(name) && !previous.is_some_and(|names| names.contains(name))
+        });
+        if let Some((name, _)) = clash {
+            return Err(Error::InvalidInput(format!(
+                "Table '{name}' from {} already registered",
+                path.display()
+            )));
+        }
+
+        self.detach_dir(&path);
+        let mut names = Vec::with_capacity(tables.len());
+        for (name, storage) in tables {
+            self.replaced(&name);
+            self.tables.insert(name.clone(), storage);
+            names.push(name);
+        }
+
+        self.attached.push((path, names.clone()));
+        #[cfg(feature = "ipc-io")]
+        self.evict_cold()?;
+        Ok(names)
+    }
+
+    /// Re-scan all attached directories (picks up added, changed, removed files)
+    ///
+    /// # Returns
+    /// Sorted names of all tables registered from attached directories
+    ///
+    /// # Errors
+    /// Returns error if any attached directory fails to re-scan; that
+    /// directory keeps the tables of its previous scan
+    pub fn refresh(&mut self) -> Result<Vec<String>> {
+        let dirs: Vec<PathBuf> = self.attached.iter().map(|(dir, _)| dir.clone()).collect();
+
+        let mut names = Vec::new();
+        for dir in dirs {
+            names.extend(self.attach_dir(dir)?);
+        }
+        names.sort_unstable();
+        Ok(names)
+    }
+
+    /// Directories currently attached
+    #[must_use]
+    pub fn attached_dirs(&self) -> Vec<&Path> {
+        self.attached.iter().map(|(dir, _)| dir.as_path()).collect()
+    }
+
+    /// Drop the tables previously registered from `path`
+    fn detach_dir(&mut self, path: &Path) {
+        if let Some(pos) = self.attached.iter().position(|(dir, _)| dir == path) {
+            let (_, names) = self.attached.remove(pos);
+            for name in names {
+                self.remove(&name);
+            }
+        }
+    }
+
+    /// Drop a table, deleting its spill file if it was evicted
+    ///
+    /// Returns the table only if it was resident.
+    fn remove(&mut self, name: &str) -> Option<StorageEngine> {
+        #[cfg(feature = "ipc-io")]
+        if let Some(memory) = self.memory.as_mut() {
+            memory.forget(name);
+        }
+        self.tables.remove(name)
+    }
+
+    /// Note that `name` is about to be (re)registered as a fresh resident table
+    #[cfg_attr(
+        not(feature = "ipc-io"),
+        allow(clippy::unused_self, clippy::needless_pass_by_ref_mut)
+    )]
+    fn replaced(&mut self, name: &str) {
+        #[cfg(feature = "ipc-io")]
+        if let Some(memory) = self.memory.as_mut() {
+            memory.forget(name);
+            memory.touch(name);
+        }
+        #[cfg(not(feature = "ipc-io"))]
+        let _ = name;
+    }
+}
+
+/// Scan one directory level into (table name, storage) pairs, sorted by name
+fn scan_dir(path: &Path, provenance: bool) -> Result<Vec<(String, StorageEngine)>> {
+    let mut tables: Vec<(String, StorageEngine)> = Vec::new();
+
+    for entry_path in sorted_entries(path)? {
+        let Some(name) = table_name(&entry_path) else {
+            continue;
+        };
+
+        let storage = if entry_path.is_dir() {
+            load_partitioned_dir(&entry_path, provenance)?
+        } else {
+            load_file(&entry_path)?
+        };
+
+        let Some(mut storage) = storage else {
+            continue;
+        };
+        if provenance && !storage.has_provenance() {
+            storage = storage.with_provenance(Some(&entry_path.to_string_lossy()))?;
+        }
+
+        if tables.iter().any(|(existing, _)| existing == &name) {
+            return Err(Error::InvalidInput(format!(
+                "Duplicate table name '{name}' in {}",
+                path.display()
+            )));
+        }
+        tables.push((name, storage));
+    }
+
+    tables.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(tables)
+}
+
+/// Directory entries sorted by path (deterministic load order)
+fn sorted_entries(path: &Path) -> Result<Vec<PathBuf>> {
+    let mut entries = std::fs::read_dir(path)
+        .map_err(|e| Error::StorageError(format!("Failed to read {}: {e}", path.display())))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort();
+    Ok(entries)
+}
+
+/// Table name for a directory entry (`None` for hidden entries)
+fn table_name(path: &Path) -> Option<String> {
+    let file_name = path.file_name()?.to_str()?;
+    if file_name.starts_with('.') {
+        return None;
+    }
+    if path.is_dir() {
+        Some(file_name.to_string())
+    } else {
+        path.file_stem()?.to_str().map(str::to_string)
+    }
+}
+
+/// Load every supported file under `path` (recursively) into one table
+///
+/// With `provenance`, each row is tagged with the file it came from.
+fn load_partitioned_dir(path: &Path, provenance: bool) -> Result<Option<StorageEngine>> {
+    let mut files = Vec::new();
+    collect_files(path, &mut files)?;
+
+    let mut combined: Option<StorageEngine> = None;
+    for file in files {
+        let Some(part) = load_file(&file)? else {
+            continue;
+        };
+
+        let mut storage = match combined.take() {
+            Some(storage) => storage,
+            None if provenance => StorageEngine::new(Vec::new()).with_provenance(None)?,
+            None => {
+                combined = Some(part);
+                continue;
+            }
+        };
+
+        let source = file.to_string_lossy();
+        for batch in part.batches() {
+            storage
+                .append_batch_from(batch.clone(), &source)
+                .map_err(|e| Error::StorageError(format!("{}: {e}", file.display())))?;
+        }
+        combined = Some(storage);
+    }
+
+    Ok(combined)
+}
+
+/// Recursively collect non-hidden files under `path` in sorted order
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry_path in sorted_entries(path)? {
+        if table_name(&entry_path).is_none() {
+            continue;
+        }
+        if entry_path.is_dir() {
+            collect_files(&entry_path, files)?;
+        } else {
+            files.push(entry_path);
+        }
+    }
+    Ok(())
+}
+
+/// Load a single data file by extension (`None` if the format is unsupported)
+#[allow(clippy::unnecessary_wraps)]
+pub(crate) fn load_file(path: &Path) -> Result<Option<StorageEngine>> {
+    let extension = path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase);
+
+    match extension.as_deref() {
+        #[cfg(feature = "parquet-io")]
+        Some("parquet") => StorageEngine::load_parquet(path).map(Some),
+        #[cfg(feature = "ipc-io")]
+        Some("arrow" | "ipc" | "feather") => StorageEngine::load_ipc(path).map(Some),
+        #[cfg(feature = "csv-io")]
+        Some("csv") => StorageEngine::load_csv(path).map(Some),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Int32Array, RecordBatch};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    fn create_storage(values: Vec<i32>) -> StorageEngine {
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(values))]).unwrap();
+        StorageEngine::new(vec![batch])
+    }
+
+    #[test]
+    fn test_catalog_register_and_get() {
+        let mut catalog = Catalog::new();
+        assert!(catalog.is_empty());
+
+        assert!(catalog.register("events", create_storage(vec![1, 2])).is_none());
+        assert!(catalog.register("users", create_storage(vec![3])).is_none());
+
+        assert_eq!(catalog.len(), 2);
+        assert_eq!(catalog.table_names(), vec!["events", "users"]);
+        assert_eq!(catalog.get("events").unwrap().batches()[0].num_rows(), 2);
+        assert!(catalog.get("missing").is_none());
+    }
+
+    #[test]
+    fn test_catalog_register_replaces() {
+        let mut catalog = Catalog::new();
+        catalog.register("t", create_storage(vec![1]));
+        let previous = catalog.register("t", create_storage(vec![1, 2, 3]));
+
+        assert!(previous.is_some());
+        assert_eq!(catalog.get("t").unwrap().batches()[0].num_rows(), 3);
+    }
+
+    #[test]
+    fn test_catalog_deregister() {
+        let mut catalog = Catalog::new();
+        catalog.register("t", create_storage(vec![1]));
+
+        assert!(catalog.deregister("t").is_some());
+        assert!(catalog.deregister("t").is_none());
+        assert!(catalog.is_empty());
+    }
+
+    #[test]
+    fn test_temporary_tables() {
+        let mut catalog = Catalog::new();
+        catalog.register("events", create_storage(vec![1]));
+
+        assert!(catalog.register_temporary("events", create_storage(vec![2])).is_err());
+        assert!(catalog.register_temporary("scratch", create_storage(vec![3])).unwrap().is_none());
+        assert!(catalog.register_temporary("scratch", create_storage(vec![4])).unwrap().is_some());
+
+        assert!(catalog.is_temporary("scratch"));
+        assert!(!catalog.is_temporary("events"));
+        let persistent: Vec<&str> = catalog.persistent_iter().map(|(name, _)| name).collect();
+        assert_eq!(persistent, vec!["events"]);
+
+        catalog.clear_temporary();
+        assert_eq!(catalog.table_names(), vec!["events"]);
+    }
+
+    #[test]
+    fn test_table_name_skips_hidden_files() {
+        assert_eq!(table_name(Path::new("/data/events.parquet")), Some("events".to_string()));
+        assert_eq!(table_name(Path::new("/data/.DS_Store")), None);
+    }
+
+    #[test]
+    fn test_attach_missing_dir_fails() {
+        let mut catalog = Catalog::new();
+        let result = catalog.attach_dir("/nonexistent/trueno_catalog_dir");
+        assert!(result.is_err());
+        assert!(catalog.attached_dirs().is_empty());
+    }
+}
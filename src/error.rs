@@ -20,6 +20,19 @@ pub enum Error {
     )]
     VramExhausted(String),
 
+    /// Input does not fit the GPU memory budget, even split into chunks
+    #[error(
+        "VRAM budget exceeded: {requested} bytes requested, {available} bytes available\nSplit the input into morsels of at most {suggested_morsel_bytes} bytes or use the SIMD backend"
+    )]
+    VramExceeded {
+        /// Bytes the operation needed at once
+        requested: u64,
+        /// Bytes of the budget unreserved when it was checked
+        available: u64,
+        /// Largest morsel that would have fit
+        suggested_morsel_bytes: u64,
+    },
+
     /// Backend equivalence test failed (critical bug)
     #[error("Backend equivalence failed: GPU result != SIMD result\nGPU: {gpu_result}\nSIMD: {simd_result}")]
     BackendMismatch {
@@ -29,6 +42,18 @@ pub enum Error {
         simd_result: String,
     },
 
+    /// GPU device was lost (driver reset, removal); re-initialize or use SIMD
+    #[error("GPU device lost: {0}\nFalling back to SIMD backend")]
+    GpuDeviceLost(String),
+
+    /// Operation exceeded its time budget
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
+    /// Operation was cancelled by its caller
+    #[error("Cancelled: {0}")]
+    Cancelled(String),
+
     /// Query parsing error
     #[error("SQL parse error: {0}")]
     ParseError(String),
@@ -41,6 +66,26 @@ pub enum Error {
     #[error("GPU transfer queue closed (receiver dropped)")]
     QueueClosed,
 
+    /// Query exceeded a limit set with
+    /// [`QueryExecutor::with_limits`](crate::query::QueryExecutor::with_limits)
+    #[error("Resource limit exceeded: {limit}: {detail}")]
+    ResourceLimitExceeded {
+        /// The limit that tripped
+        limit: crate::query::limits::ResourceLimit,
+        /// Usage when the limit was checked
+        detail: String,
+    },
+
+    /// Query rejected by admission control (too many concurrent queries)
+    #[error(
+        "Query rejected, database overloaded: {0}\nRetry later or raise the concurrency limits"
+    )]
+    Overloaded(String),
+
+    /// Query references a table or column the session may not read
+    #[error("Access denied: {0}")]
+    AccessDenied(String),
+
     /// Invalid input parameter
     #[error("Invalid input: {0}")]
     InvalidInput(String),
@@ -53,6 +53,17 @@ impl ArtifactRecord {
         }
     }
 
+    /// Create a builder for constructing an artifact record with optional fields.
+    #[must_use]
+    pub fn builder(
+        run_id: impl Into<String>,
+        key: impl Into<String>,
+        cas_hash: impl Into<String>,
+        size_bytes: u64,
+    ) -> ArtifactRecordBuilder {
+        ArtifactRecordBuilder::new(run_id, key, cas_hash, size_bytes)
+    }
+
     /// Get the run ID.
     #[must_use]
     pub fn run_id(&self) -> &str {
@@ -84,6 +95,54 @@ impl ArtifactRecord {
     }
 }
 
+/// Builder for `ArtifactRecord`.
+#[derive(Debug)]
+pub struct ArtifactRecordBuilder {
+    run_id: String,
+    key: String,
+    cas_hash: String,
+    size_bytes: u64,
+    created_at: DateTime<Utc>,
+}
+
+impl ArtifactRecordBuilder {
+    /// Create a new builder with required fields.
+    #[must_use]
+    pub fn new(
+        run_id: impl Into<String>,
+        key: impl Into<String>,
+        cas_hash: impl Into<String>,
+        size_bytes: u64,
+    ) -> Self {
+        Self {
+            run_id: run_id.into(),
+            key: key.into(),
+            cas_hash: cas_hash.into(),
+            size_bytes,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Set a custom creation timestamp (useful for deserialization/testing).
+    #[must_use]
+    pub const fn created_at(mut self, created_at: DateTime<Utc>) -> Self {
+        self.created_at = created_at;
+        self
+    }
+
+    /// Build the `ArtifactRecord`.
+    #[must_use]
+    pub fn build(self) -> ArtifactRecord {
+        ArtifactRecord {
+            run_id: self.run_id,
+            key: self.key,
+            cas_hash: self.cas_hash,
+            size_bytes: self.size_bytes,
+            created_at: self.created_at,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -34,11 +34,20 @@
 mod artifact_record;
 mod experiment_record;
 mod metric_record;
+#[cfg(feature = "parquet-io")]
+pub mod persist;
 mod run_record;
+pub mod series;
 mod store;
+pub mod tables;
 
-pub use artifact_record::ArtifactRecord;
+pub use artifact_record::{ArtifactRecord, ArtifactRecordBuilder};
 pub use experiment_record::{ExperimentRecord, ExperimentRecordBuilder};
 pub use metric_record::{MetricRecord, MetricRecordBuilder};
 pub use run_record::{RunRecord, RunRecordBuilder, RunStatus};
+pub use series::WindowStats;
 pub use store::ExperimentStore;
+pub use tables::{ARTIFACTS_TABLE, EXPERIMENTS_TABLE, METRICS_TABLE, RUNS_TABLE};
+
+#[cfg(feature = "parquet-io")]
+pub use persist::{ARTIFACTS_FILE, EXPERIMENTS_FILE, METRICS_FILE, RUNS_FILE};
@@ -0,0 +1,51 @@
+//! Parquet persistence for [`ExperimentStore`]
+//!
+//! Each table of [`ExperimentStore::as_record_batches`] (see
+//! [`tables`](super::tables)) is written to `<table>.parquet` in the target
+//! directory, so the files can be loaded back into an [`ExperimentStore`] or
+//! queried directly with [`StorageEngine::load_parquet`]. Each file is
+//! written to a temporary name and renamed into place, so a crash mid-flush
+//! leaves the previous file intact.
+
+use super::tables::{self, TABLES};
+use super::ExperimentStore;
+use crate::storage::{ParquetSink, ParquetWriteOptions, StorageEngine};
+use crate::Result;
+use arrow::record_batch::RecordBatch;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File holding the `experiments` table
+pub const EXPERIMENTS_FILE: &str = "experiments.parquet";
+/// File holding the `runs` table
+pub const RUNS_FILE: &str = "runs.parquet";
+/// File holding the `metrics` table
+pub const METRICS_FILE: &str = "metrics.parquet";
+/// File holding the `artifacts` table
+pub const ARTIFACTS_FILE: &str = "artifacts.parquet";
+
+/// Write each table into `dir`, creating it if needed
+pub(super) fn flush(dir: &Path, tables: &[(&str, RecordBatch)]) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    for (table, batch) in tables {
+        let mut sink = ParquetSink::create(file(dir, table), ParquetWriteOptions::default())?;
+        sink.write(batch)?;
+        sink.finish()?;
+    }
+    Ok(())
+}
+
+/// Rebuild a store from the tables [`flush`] wrote to `dir`
+pub(super) fn load(dir: &Path) -> Result<ExperimentStore> {
+    let mut store = ExperimentStore::new();
+    for table in TABLES {
+        for batch in StorageEngine::load_parquet(file(dir, table))?.batches() {
+            tables::add_rows(&mut store, table, batch)?;
+        }
+    }
+    Ok(store)
+}
+
+fn file(dir: &Path, table: &str) -> PathBuf {
+    dir.join(format!("{table}.parquet"))
+}
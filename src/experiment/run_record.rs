@@ -1,7 +1,9 @@
 //! Run Record - execution instance of an experiment
 
+use crate::Error;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 
 /// Status of a run.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -18,6 +20,36 @@ pub enum RunStatus {
     Cancelled,
 }
 
+impl RunStatus {
+    /// Get the lowercase status name (e.g. `"running"`).
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Running => "running",
+            Self::Success => "success",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl FromStr for RunStatus {
+    type Err = Error;
+
+    /// Parse a status name as produced by [`RunStatus::as_str`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(Self::Pending),
+            "running" => Ok(Self::Running),
+            "success" => Ok(Self::Success),
+            "failed" => Ok(Self::Failed),
+            "cancelled" => Ok(Self::Cancelled),
+            _ => Err(Error::InvalidInput(format!("Unknown run status: {s}"))),
+        }
+    }
+}
+
 /// Run Record represents a single execution of an experiment.
 ///
 /// Each experiment can have multiple runs. A run tracks the execution
@@ -123,6 +155,9 @@ impl RunRecord {
 pub struct RunRecordBuilder {
     run_id: String,
     experiment_id: String,
+    status: RunStatus,
+    started_at: Option<DateTime<Utc>>,
+    ended_at: Option<DateTime<Utc>>,
     renacer_span_id: Option<String>,
 }
 
@@ -130,7 +165,35 @@ impl RunRecordBuilder {
     /// Create a new builder with required fields.
     #[must_use]
     pub fn new(run_id: impl Into<String>, experiment_id: impl Into<String>) -> Self {
-        Self { run_id: run_id.into(), experiment_id: experiment_id.into(), renacer_span_id: None }
+        Self {
+            run_id: run_id.into(),
+            experiment_id: experiment_id.into(),
+            status: RunStatus::Pending,
+            started_at: None,
+            ended_at: None,
+            renacer_span_id: None,
+        }
+    }
+
+    /// Set the run status (useful for deserialization/testing).
+    #[must_use]
+    pub const fn status(mut self, status: RunStatus) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Set the start timestamp (useful for deserialization/testing).
+    #[must_use]
+    pub const fn started_at(mut self, started_at: DateTime<Utc>) -> Self {
+        self.started_at = Some(started_at);
+        self
+    }
+
+    /// Set the end timestamp (useful for deserialization/testing).
+    #[must_use]
+    pub const fn ended_at(mut self, ended_at: DateTime<Utc>) -> Self {
+        self.ended_at = Some(ended_at);
+        self
     }
 
     /// Set the renacer span ID for distributed tracing.
@@ -146,9 +209,9 @@ impl RunRecordBuilder {
         RunRecord {
             run_id: self.run_id,
             experiment_id: self.experiment_id,
-            status: RunStatus::Pending,
-            started_at: None,
-            ended_at: None,
+            status: self.status,
+            started_at: self.started_at,
+            ended_at: self.ended_at,
             renacer_span_id: self.renacer_span_id,
         }
     }
@@ -172,4 +235,18 @@ mod tests {
         run.complete(RunStatus::Success);
         assert_eq!(run.status(), RunStatus::Success);
     }
+
+    #[test]
+    fn test_run_status_round_trips_through_str() {
+        for status in [
+            RunStatus::Pending,
+            RunStatus::Running,
+            RunStatus::Success,
+            RunStatus::Failed,
+            RunStatus::Cancelled,
+        ] {
+            assert_eq!(status.as_str().parse::<RunStatus>().unwrap(), status);
+        }
+        assert!("done".parse::<RunStatus>().is_err());
+    }
 }
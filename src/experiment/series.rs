@@ -0,0 +1,197 @@
+//! Time-series reduction for metric curves
+//!
+//! Runs with a million steps are too dense to plot or scan point by point.
+//! Two reductions keep curves usable:
+//!
+//! - **Downsampling** with Largest-Triangle-Three-Buckets ([`lttb`]): keeps
+//!   the points that preserve the curve's visual shape, always including the
+//!   first and last.
+//! - **Tumbling windows** ([`WindowStats`]): fixed-width, non-overlapping
+//!   windows over step or timestamp, each summarized by count, min, max, sum
+//!   and mean. Windows are summarized with [`SIMD_LANES`] independent
+//!   accumulators, laid out so the compiler vectorizes the loop (as in
+//!   [`welford_simd`](crate::variance::welford_simd)).
+//!
+//! References:
+//! - Steinarsson (2013): Downsampling Time Series for Visual Representation
+//!
+//! Toyota Way: Muda elimination (send the plot only the points it can show)
+
+use crate::variance::SIMD_LANES;
+
+/// Summary of the metric values in one tumbling window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowStats<K> {
+    /// Start of the window (inclusive): a step, or a timestamp
+    pub start: K,
+    /// Number of values in the window (never 0; empty windows are omitted)
+    pub count: usize,
+    /// Smallest value (NaN only if every value is NaN)
+    pub min: f64,
+    /// Largest value (NaN only if every value is NaN)
+    pub max: f64,
+    /// Sum of the values
+    pub sum: f64,
+    /// Mean of the values
+    pub mean: f64,
+}
+
+/// Indices of the points LTTB keeps to draw `points` with `max_points`
+///
+/// `points` are `(x, y)` pairs sorted by `x`. Returns every index when
+/// `points.len() <= max_points`; otherwise the first and last points plus
+/// one point per bucket in between. With `max_points` below 3 only the
+/// endpoints fit, so 2 keeps both, 1 keeps the first and 0 keeps nothing.
+#[must_use]
+#[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn lttb(points: &[(f64, f64)], max_points: usize) -> Vec<usize> {
+    let n = points.len();
+    if n <= max_points {
+        return (0..n).collect();
+    }
+    match max_points {
+        0 => return Vec::new(),
+        1 => return vec![0],
+        2 => return vec![0, n - 1],
+        _ => {}
+    }
+
+    // Interior points split into `max_points - 2` buckets of `every` points
+    let every = (n - 2) as f64 / (max_points - 2) as f64;
+    let bucket = |i: usize| ((i as f64 * every) as usize + 1).min(n - 1);
+    let mut selected = Vec::with_capacity(max_points);
+    selected.push(0);
+    let mut previous = 0;
+    for i in 0..max_points - 2 {
+        // Average of the next bucket (the last point for the final bucket)
+        let next = bucket(i + 1)..bucket(i + 2).max(bucket(i + 1) + 1).min(n);
+        let len = next.len() as f64;
+        let (sum_x, sum_y) =
+            points[next].iter().fold((0.0, 0.0), |(sx, sy), &(x, y)| (sx + x, sy + y));
+        let (avg_x, avg_y) = (sum_x / len, sum_y / len);
+
+        // Keep the point forming the largest triangle with its neighbours
+        let (ax, ay) = points[previous];
+        let mut best = (bucket(i), f64::NEG_INFINITY);
+        for (j, &(x, y)) in points.iter().enumerate().take(bucket(i + 1)).skip(bucket(i)) {
+            let area = (ax - avg_x).mul_add(y - ay, -((ax - x) * (avg_y - ay))).abs();
+            if area > best.1 {
+                best = (j, area);
+            }
+        }
+        selected.push(best.0);
+        previous = best.0;
+    }
+    selected.push(n - 1);
+    selected
+}
+
+/// Group `(key, value)` pairs sorted by key into tumbling windows
+///
+/// `window_of` maps a key to its window start; consecutive pairs with the
+/// same start form one window.
+pub(super) fn tumbling<K: Copy + PartialEq>(
+    pairs: &[(K, f64)],
+    window_of: impl Fn(K) -> K,
+) -> Vec<WindowStats<K>> {
+    let mut windows = Vec::new();
+    let mut values = Vec::new();
+    let mut current = None;
+    for &(key, value) in pairs {
+        let start = window_of(key);
+        if current.is_some_and(|current| current != start) {
+            windows.extend(current.map(|current| summarize(current, &values)));
+            values.clear();
+        }
+        current = Some(start);
+        values.push(value);
+    }
+    windows.extend(current.map(|current| summarize(current, &values)));
+    windows
+}
+
+/// Summarize one window's values with [`SIMD_LANES`] accumulators per statistic
+#[allow(clippy::cast_precision_loss)]
+fn summarize<K>(start: K, values: &[f64]) -> WindowStats<K> {
+    let mut mins = [f64::INFINITY; SIMD_LANES];
+    let mut maxs = [f64::NEG_INFINITY; SIMD_LANES];
+    let mut sums = [0.0_f64; SIMD_LANES];
+
+    let chunks = values.chunks_exact(SIMD_LANES);
+    let tail = chunks.remainder();
+    for chunk in chunks {
+        for lane in 0..SIMD_LANES {
+            mins[lane] = mins[lane].min(chunk[lane]);
+            maxs[lane] = maxs[lane].max(chunk[lane]);
+            sums[lane] += chunk[lane];
+        }
+    }
+    for (lane, &value) in tail.iter().enumerate() {
+        mins[lane] = mins[lane].min(value);
+        maxs[lane] = maxs[lane].max(value);
+        sums[lane] += value;
+    }
+
+    // f64::min/max skip NaN, so a lane only stays infinite if it saw no numbers
+    let min = mins.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = maxs.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let all_nan = values.iter().all(|value| value.is_nan());
+    let sum: f64 = sums.iter().sum();
+    WindowStats {
+        start,
+        count: values.len(),
+        min: if all_nan { f64::NAN } else { min },
+        max: if all_nan { f64::NAN } else { max },
+        sum,
+        mean: sum / values.len() as f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lttb_keeps_endpoints_and_peaks() {
+        // Flat line with one spike: the spike must survive downsampling
+        let points: Vec<(f64, f64)> =
+            (0..1000).map(|i| (f64::from(i), if i == 437 { 50.0 } else { 1.0 })).collect();
+        let kept = lttb(&points, 20);
+
+        assert_eq!(kept.len(), 20);
+        assert_eq!(kept[0], 0);
+        assert_eq!(kept[19], 999);
+        assert!(kept.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(kept.contains(&437));
+    }
+
+    #[test]
+    fn test_lttb_small_inputs() {
+        let points = [(0.0, 1.0), (1.0, 2.0), (2.0, 3.0), (3.0, 4.0)];
+        assert_eq!(lttb(&points, 10), vec![0, 1, 2, 3]);
+        assert_eq!(lttb(&points, 3).len(), 3);
+        assert_eq!(lttb(&points, 2), vec![0, 3]);
+        assert_eq!(lttb(&points, 1), vec![0]);
+        assert!(lttb(&points, 0).is_empty());
+    }
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn test_tumbling_windows_match_scalar() {
+        let pairs: Vec<(u64, f64)> = (0..103).map(|step| (step, (step % 17) as f64)).collect();
+        let windows = tumbling(&pairs, |step| step - step % 25);
+
+        assert_eq!(windows.iter().map(|w| w.start).collect::<Vec<_>>(), vec![0, 25, 50, 75, 100]);
+        for window in &windows {
+            let values: Vec<f64> =
+                pairs.iter().filter(|(s, _)| s - s % 25 == window.start).map(|p| p.1).collect();
+            assert_eq!(window.count, values.len());
+            let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+            let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            assert!((window.min - min).abs() < f64::EPSILON);
+            assert!((window.max - max).abs() < f64::EPSILON);
+            assert!((window.sum - values.iter().sum::<f64>()).abs() < 1e-9);
+            assert!((window.mean - window.sum / values.len() as f64).abs() < 1e-12);
+        }
+    }
+}
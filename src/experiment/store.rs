@@ -1,11 +1,22 @@
 //! Experiment Store - in-memory storage for experiment tracking data
 //!
 //! This module provides the storage layer for experiment tracking,
-//! optimized for time-series metric queries.
+//! optimized for time-series metric queries. Its records can be exposed to
+//! SQL as Arrow tables (see [`tables`](super::tables)) and, with the
+//! `parquet-io` feature, flushed to and reloaded from Parquet files.
 
 use std::collections::HashMap;
+#[cfg(feature = "parquet-io")]
+use std::path::Path;
 
-use super::{ExperimentRecord, MetricRecord, RunRecord};
+use arrow::record_batch::RecordBatch;
+use chrono::{DateTime, TimeDelta, Utc};
+
+use super::series::{self, WindowStats};
+use super::{tables, ArtifactRecord, ExperimentRecord, MetricRecord, RunRecord};
+use crate::catalog::Catalog;
+use crate::storage::StorageEngine;
+use crate::{Error, Result};
 
 /// In-memory store for experiment tracking data.
 ///
@@ -23,6 +34,7 @@ pub struct ExperimentStore {
     experiments: HashMap<String, ExperimentRecord>,
     runs: HashMap<String, RunRecord>,
     metrics: Vec<MetricRecord>,
+    artifacts: Vec<ArtifactRecord>,
 }
 
 impl ExperimentStore {
@@ -32,10 +44,13 @@ impl ExperimentStore {
         Self::default()
     }
 
-    /// Check if the store is empty (no experiments, runs, or metrics).
+    /// Check if the store is empty (no experiments, runs, metrics, or artifacts).
     #[must_use]
     pub fn is_empty(&self) -> bool {
-        self.experiments.is_empty() && self.runs.is_empty() && self.metrics.is_empty()
+        self.experiments.is_empty()
+            && self.runs.is_empty()
+            && self.metrics.is_empty()
+            && self.artifacts.is_empty()
     }
 
     /// Get the number of experiments in the store.
@@ -56,6 +71,12 @@ impl ExperimentStore {
         self.metrics.len()
     }
 
+    /// Get the number of artifacts in the store.
+    #[must_use]
+    pub fn artifact_count(&self) -> usize {
+        self.artifacts.len()
+    }
+
     /// Add an experiment to the store.
     pub fn add_experiment(&mut self, experiment: ExperimentRecord) {
         self.experiments.insert(experiment.experiment_id().to_string(), experiment);
@@ -134,6 +155,209 @@ impl ExperimentStore {
 
         metrics
     }
+
+    /// Get at most `max_points` metrics for a run and key, ordered by step.
+    ///
+    /// Dense curves are downsampled with Largest-Triangle-Three-Buckets (see
+    /// [`series::lttb`]), which keeps the first and last steps and the
+    /// points that shape the curve, such as spikes. Curves with at most
+    /// `max_points` points are returned whole.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use trueno_db::experiment::{ExperimentStore, MetricRecord};
+    ///
+    /// let mut store = ExperimentStore::new();
+    /// for step in 0..100_000 {
+    ///     store.add_metric(MetricRecord::new("run-001", "loss", step, 1.0 / (step as f64 + 1.0)));
+    /// }
+    ///
+    /// let curve = store.get_metrics_downsampled("run-001", "loss", 500);
+    /// assert_eq!(curve.len(), 500);
+    /// assert_eq!(curve.last().map(MetricRecord::step), Some(99_999));
+    /// ```
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn get_metrics_downsampled(
+        &self,
+        run_id: &str,
+        key: &str,
+        max_points: usize,
+    ) -> Vec<MetricRecord> {
+        let metrics = self.get_metrics_for_run(run_id, key);
+        let points: Vec<(f64, f64)> =
+            metrics.iter().map(|m| (m.step() as f64, m.value())).collect();
+        let keep = series::lttb(&points, max_points);
+        if keep.len() == metrics.len() {
+            return metrics;
+        }
+        keep.into_iter().map(|i| metrics[i].clone()).collect()
+    }
+
+    /// Summarize a run's metric in tumbling windows of `width` steps.
+    ///
+    /// Window `k` covers steps `k * width .. (k + 1) * width`; windows with
+    /// no metrics are omitted. Statistics are computed with the SIMD
+    /// accumulators described in [`series`].
+    ///
+    /// # Errors
+    /// Returns error if `width` is zero
+    pub fn aggregate_metrics_by_step(
+        &self,
+        run_id: &str,
+        key: &str,
+        width: u64,
+    ) -> Result<Vec<WindowStats<u64>>> {
+        if width == 0 {
+            return Err(Error::InvalidInput("Step window width must be positive".to_string()));
+        }
+        let pairs: Vec<(u64, f64)> =
+            self.get_metrics_for_run(run_id, key).iter().map(|m| (m.step(), m.value())).collect();
+        Ok(series::tumbling(&pairs, |step| step - step % width))
+    }
+
+    /// Summarize a run's metric in tumbling windows of `width` wall-clock time.
+    ///
+    /// Windows are aligned to the Unix epoch (so hourly windows start on the
+    /// hour) and keyed by their start time; windows with no metrics are
+    /// omitted.
+    ///
+    /// # Errors
+    /// Returns error if `width` is not positive
+    pub fn aggregate_metrics_by_time(
+        &self,
+        run_id: &str,
+        key: &str,
+        width: TimeDelta,
+    ) -> Result<Vec<WindowStats<DateTime<Utc>>>> {
+        let width = width
+            .num_microseconds()
+            .filter(|&micros| micros > 0)
+            .ok_or_else(|| Error::InvalidInput(format!("Invalid time window width: {width}")))?;
+        let mut pairs: Vec<(DateTime<Utc>, f64)> = self
+            .metrics
+            .iter()
+            .filter(|m| m.run_id() == run_id && m.key() == key)
+            .map(|m| (m.timestamp(), m.value()))
+            .collect();
+        pairs.sort_by_key(|&(timestamp, _)| timestamp);
+        Ok(series::tumbling(&pairs, |timestamp| {
+            let micros = timestamp.timestamp_micros();
+            let start = micros - micros.rem_euclid(width);
+            DateTime::from_timestamp_micros(start).unwrap_or(timestamp)
+        }))
+    }
+
+    /// Add an artifact to the store.
+    pub fn add_artifact(&mut self, artifact: ArtifactRecord) {
+        self.artifacts.push(artifact);
+    }
+
+    /// Get all artifacts for a run, in the order they were added.
+    #[must_use]
+    pub fn get_artifacts_for_run(&self, run_id: &str) -> Vec<&ArtifactRecord> {
+        self.artifacts.iter().filter(|artifact| artifact.run_id() == run_id).collect()
+    }
+
+    /// Get the store's records as Arrow tables, named for SQL.
+    ///
+    /// Returns the `experiments`, `runs`, `metrics`, and `artifacts` tables
+    /// in that order (see [`tables`](super::tables) for their columns).
+    /// Experiments and runs are sorted by ID; metrics and artifacts keep
+    /// insertion order.
+    ///
+    /// # Errors
+    /// Returns error if a table cannot be assembled
+    pub fn as_record_batches(&self) -> Result<Vec<(&'static str, RecordBatch)>> {
+        let mut experiments: Vec<&ExperimentRecord> = self.experiments.values().collect();
+        experiments.sort_by(|a, b| a.experiment_id().cmp(b.experiment_id()));
+        let mut runs: Vec<&RunRecord> = self.runs.values().collect();
+        runs.sort_by(|a, b| a.run_id().cmp(b.run_id()));
+        Ok(vec![
+            (tables::EXPERIMENTS_TABLE, tables::experiments_batch(&experiments)?),
+            (tables::RUNS_TABLE, tables::runs_batch(&runs)?),
+            (tables::METRICS_TABLE, tables::metrics_batch(&self.metrics)?),
+            (tables::ARTIFACTS_TABLE, tables::artifacts_batch(&self.artifacts)?),
+        ])
+    }
+
+    /// Register the store's tables in `catalog` for SQL queries.
+    ///
+    /// Tables of the same name are replaced, so calling this again refreshes
+    /// them with records added since.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use trueno_db::experiment::{ExperimentStore, MetricRecord};
+    /// use trueno_db::query::{QueryEngine, QueryExecutor};
+    /// use trueno_db::Catalog;
+    ///
+    /// # fn main() -> trueno_db::Result<()> {
+    /// let mut store = ExperimentStore::new();
+    /// store.add_metric(MetricRecord::new("run-001", "loss", 0, 0.9));
+    /// store.add_metric(MetricRecord::new("run-001", "loss", 1, 0.4));
+    ///
+    /// let mut catalog = Catalog::new();
+    /// store.register_tables(&mut catalog)?;
+    ///
+    /// let sql = "SELECT run_id, MIN(value) FROM metrics WHERE key = 'loss' GROUP BY run_id";
+    /// let plan = QueryEngine::new().parse(sql)?;
+    /// let best = QueryExecutor::new().execute_catalog(&plan, &catalog)?;
+    /// assert_eq!(best.num_rows(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns error if a table cannot be assembled
+    pub fn register_tables(&self, catalog: &mut Catalog) -> Result<()> {
+        for (name, batch) in self.as_record_batches()? {
+            catalog.register(name, StorageEngine::new(vec![batch]));
+        }
+        Ok(())
+    }
+
+    /// Write the store to Parquet files in `dir`, one per record type.
+    ///
+    /// Existing files are replaced atomically. The files can be read back
+    /// with [`ExperimentStore::load_from_parquet`] or queried directly with
+    /// [`StorageEngine::load_parquet`](crate::storage::StorageEngine::load_parquet).
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// use trueno_db::experiment::{ExperimentRecord, ExperimentStore, MetricRecord};
+    ///
+    /// # fn main() -> trueno_db::Result<()> {
+    /// let mut store = ExperimentStore::new();
+    /// store.add_experiment(ExperimentRecord::new("exp-001", "baseline"));
+    /// store.add_metric(MetricRecord::new("run-001", "loss", 0, 0.5));
+    /// store.flush_to_parquet("experiments/")?;
+    ///
+    /// let reloaded = ExperimentStore::load_from_parquet("experiments/")?;
+    /// assert_eq!(reloaded.metric_count(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns error if `dir` cannot be created or a file cannot be written
+    #[cfg(feature = "parquet-io")]
+    pub fn flush_to_parquet<P: AsRef<Path>>(&self, dir: P) -> Result<()> {
+        super::persist::flush(dir.as_ref(), &self.as_record_batches()?)
+    }
+
+    /// Load a store written by [`ExperimentStore::flush_to_parquet`].
+    ///
+    /// # Errors
+    /// Returns error if a file is missing or unreadable, or its columns do
+    /// not match the experiment schema
+    #[cfg(feature = "parquet-io")]
+    pub fn load_from_parquet<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        super::persist::load(dir.as_ref())
+    }
 }
 
 #[cfg(test)]
@@ -167,6 +391,48 @@ mod tests {
         assert!(store.get_run("run-1").is_some());
     }
 
+    #[test]
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_wrap)]
+    fn test_metric_windows_by_step_and_time() {
+        let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let mut store = ExperimentStore::new();
+        for step in (0..10).rev() {
+            let metric = MetricRecord::builder("run-1", "loss", step, step as f64)
+                .timestamp(start + TimeDelta::seconds(step as i64 * 20))
+                .build();
+            store.add_metric(metric);
+        }
+        store.add_metric(MetricRecord::new("run-1", "acc", 0, 99.0));
+
+        let by_step = store.aggregate_metrics_by_step("run-1", "loss", 4).unwrap();
+        let summary: Vec<(u64, usize, f64, f64)> =
+            by_step.iter().map(|w| (w.start, w.count, w.min, w.max)).collect();
+        assert_eq!(summary, vec![(0, 4, 0.0, 3.0), (4, 4, 4.0, 7.0), (8, 2, 8.0, 9.0)]);
+        assert!((by_step[1].mean - 5.5).abs() < f64::EPSILON);
+
+        // 1700000000 is 20s past a minute boundary
+        let by_minute = store.aggregate_metrics_by_time("run-1", "loss", TimeDelta::minutes(1));
+        let counts: Vec<usize> = by_minute.unwrap().iter().map(|w| w.count).collect();
+        assert_eq!(counts, vec![2, 3, 3, 2]);
+
+        assert!(store.aggregate_metrics_by_step("run-1", "loss", 0).is_err());
+        assert!(store.aggregate_metrics_by_time("run-1", "loss", TimeDelta::zero()).is_err());
+    }
+
+    #[test]
+    fn test_store_artifacts_by_run() {
+        let mut store = ExperimentStore::new();
+        store.add_artifact(ArtifactRecord::new("run-1", "model.pt", "sha256:aa", 10));
+        store.add_artifact(ArtifactRecord::new("run-2", "model.pt", "sha256:bb", 20));
+        store.add_artifact(ArtifactRecord::new("run-1", "log.txt", "sha256:cc", 30));
+
+        assert!(!store.is_empty());
+        assert_eq!(store.artifact_count(), 3);
+        let keys: Vec<&str> =
+            store.get_artifacts_for_run("run-1").iter().map(|a| a.key()).collect();
+        assert_eq!(keys, vec!["model.pt", "log.txt"]);
+    }
+
     #[test]
     fn test_get_metrics_for_run_ordering() {
         let mut store = ExperimentStore::new();
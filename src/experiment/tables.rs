@@ -0,0 +1,300 @@
+//! Arrow tables for experiment tracking records
+//!
+//! [`ExperimentStore::as_record_batches`] maps each record type to one table
+//! so the query engine can run SQL over experiment data:
+//!
+//! | Table         | Columns                                                                      |
+//! |---------------|------------------------------------------------------------------------------|
+//! | `experiments` | `experiment_id`, `name`, `created_at`, `config`                              |
+//! | `runs`        | `run_id`, `experiment_id`, `status`, `started_at`, `ended_at`, `renacer_span_id` |
+//! | `metrics`     | `run_id`, `key`, `step`, `value`, `timestamp`                                |
+//! | `artifacts`   | `run_id`, `key`, `cas_hash`, `size_bytes`, `created_at`                      |
+//!
+//! Timestamps are UTC microseconds, run status is its lowercase name (see
+//! [`RunStatus::as_str`]) and experiment config is JSON text. The same
+//! tables are what [`ExperimentStore::flush_to_parquet`] writes.
+
+// Only Parquet persistence reads tables back into records
+#![cfg_attr(not(feature = "parquet-io"), allow(dead_code))]
+
+use super::{
+    ArtifactRecord, ExperimentRecord, ExperimentStore, MetricRecord, RunRecord, RunStatus,
+};
+use crate::{Error, Result};
+use arrow::array::{
+    Array, ArrayRef, AsArray, Float64Array, StringArray, TimestampMicrosecondArray, UInt64Array,
+};
+use arrow::datatypes::{
+    DataType, Field, Float64Type, Schema, TimeUnit, TimestampMicrosecondType, UInt64Type,
+};
+use arrow::record_batch::RecordBatch;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+
+/// Table of [`ExperimentRecord`]s
+pub const EXPERIMENTS_TABLE: &str = "experiments";
+/// Table of [`RunRecord`]s
+pub const RUNS_TABLE: &str = "runs";
+/// Table of [`MetricRecord`]s
+pub const METRICS_TABLE: &str = "metrics";
+/// Table of [`ArtifactRecord`]s
+pub const ARTIFACTS_TABLE: &str = "artifacts";
+
+/// Every table, in the order [`ExperimentStore::as_record_batches`] returns them
+pub(super) const TABLES: [&str; 4] =
+    [EXPERIMENTS_TABLE, RUNS_TABLE, METRICS_TABLE, ARTIFACTS_TABLE];
+
+/// Add the records of one table's `batch` to `store`
+pub(super) fn add_rows(
+    store: &mut ExperimentStore,
+    table: &str,
+    batch: &RecordBatch,
+) -> Result<()> {
+    match table {
+        EXPERIMENTS_TABLE => {
+            experiments_from(batch)?.into_iter().for_each(|e| store.add_experiment(e));
+        }
+        RUNS_TABLE => runs_from(batch)?.into_iter().for_each(|r| store.add_run(r)),
+        METRICS_TABLE => metrics_from(batch)?.into_iter().for_each(|m| store.add_metric(m)),
+        ARTIFACTS_TABLE => artifacts_from(batch)?.into_iter().for_each(|a| store.add_artifact(a)),
+        _ => return Err(Error::InvalidInput(format!("Unknown experiment table: {table}"))),
+    }
+    Ok(())
+}
+
+fn timestamp_type() -> DataType {
+    DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()))
+}
+
+fn utf8(name: &str, nullable: bool) -> Field {
+    Field::new(name, DataType::Utf8, nullable)
+}
+
+fn timestamp(name: &str, nullable: bool) -> Field {
+    Field::new(name, timestamp_type(), nullable)
+}
+
+fn timestamps(values: impl IntoIterator<Item = Option<DateTime<Utc>>>) -> ArrayRef {
+    let micros = values.into_iter().map(|t| t.map(|t| t.timestamp_micros()));
+    Arc::new(micros.collect::<TimestampMicrosecondArray>().with_timezone("UTC"))
+}
+
+pub(super) fn experiments_batch(experiments: &[&ExperimentRecord]) -> Result<RecordBatch> {
+    let schema = Schema::new(vec![
+        utf8("experiment_id", false),
+        utf8("name", false),
+        timestamp("created_at", false),
+        utf8("config", true),
+    ]);
+    let configs = experiments.iter().map(|e| e.config().map(ToString::to_string));
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(experiments.iter().map(|e| e.experiment_id()))),
+        Arc::new(StringArray::from_iter_values(experiments.iter().map(|e| e.name()))),
+        timestamps(experiments.iter().map(|e| Some(e.created_at()))),
+        Arc::new(configs.collect::<StringArray>()),
+    ];
+    Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+}
+
+pub(super) fn runs_batch(runs: &[&RunRecord]) -> Result<RecordBatch> {
+    let schema = Schema::new(vec![
+        utf8("run_id", false),
+        utf8("experiment_id", false),
+        utf8("status", false),
+        timestamp("started_at", true),
+        timestamp("ended_at", true),
+        utf8("renacer_span_id", true),
+    ]);
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(runs.iter().map(|r| r.run_id()))),
+        Arc::new(StringArray::from_iter_values(runs.iter().map(|r| r.experiment_id()))),
+        Arc::new(StringArray::from_iter_values(runs.iter().map(|r| r.status().as_str()))),
+        timestamps(runs.iter().map(|r| r.started_at())),
+        timestamps(runs.iter().map(|r| r.ended_at())),
+        Arc::new(runs.iter().map(|r| r.renacer_span_id()).collect::<StringArray>()),
+    ];
+    Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+}
+
+pub(super) fn metrics_batch(metrics: &[MetricRecord]) -> Result<RecordBatch> {
+    let schema = Schema::new(vec![
+        utf8("run_id", false),
+        utf8("key", false),
+        Field::new("step", DataType::UInt64, false),
+        Field::new("value", DataType::Float64, false),
+        timestamp("timestamp", false),
+    ]);
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(metrics.iter().map(MetricRecord::run_id))),
+        Arc::new(StringArray::from_iter_values(metrics.iter().map(MetricRecord::key))),
+        Arc::new(UInt64Array::from(metrics.iter().map(MetricRecord::step).collect::<Vec<_>>())),
+        Arc::new(Float64Array::from(metrics.iter().map(MetricRecord::value).collect::<Vec<_>>())),
+        timestamps(metrics.iter().map(|m| Some(m.timestamp()))),
+    ];
+    Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+}
+
+pub(super) fn artifacts_batch(artifacts: &[ArtifactRecord]) -> Result<RecordBatch> {
+    let schema = Schema::new(vec![
+        utf8("run_id", false),
+        utf8("key", false),
+        utf8("cas_hash", false),
+        Field::new("size_bytes", DataType::UInt64, false),
+        timestamp("created_at", false),
+    ]);
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(artifacts.iter().map(ArtifactRecord::run_id))),
+        Arc::new(StringArray::from_iter_values(artifacts.iter().map(ArtifactRecord::key))),
+        Arc::new(StringArray::from_iter_values(artifacts.iter().map(ArtifactRecord::cas_hash))),
+        Arc::new(UInt64Array::from(
+            artifacts.iter().map(ArtifactRecord::size_bytes).collect::<Vec<_>>(),
+        )),
+        timestamps(artifacts.iter().map(|a| Some(a.created_at()))),
+    ];
+    Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+}
+
+/// Typed access to the columns of a loaded table
+struct Columns<'a> {
+    batch: &'a RecordBatch,
+}
+
+impl<'a> Columns<'a> {
+    fn column(&self, name: &str) -> Result<&'a ArrayRef> {
+        self.batch.column_by_name(name).ok_or_else(|| {
+            Error::StorageError(format!("Experiment table is missing column '{name}'"))
+        })
+    }
+
+    fn mismatch(&self, name: &str, expected: &str) -> Error {
+        let actual = self.column(name).map(|c| c.data_type().to_string()).unwrap_or_default();
+        Error::StorageError(format!("Column '{name}' has type {actual}, expected {expected}"))
+    }
+
+    fn strings(&self, name: &str) -> Result<&'a StringArray> {
+        self.column(name)?.as_string_opt::<i32>().ok_or_else(|| self.mismatch(name, "Utf8"))
+    }
+
+    fn u64s(&self, name: &str) -> Result<&'a UInt64Array> {
+        self.column(name)?
+            .as_primitive_opt::<UInt64Type>()
+            .ok_or_else(|| self.mismatch(name, "UInt64"))
+    }
+
+    fn f64s(&self, name: &str) -> Result<&'a Float64Array> {
+        self.column(name)?
+            .as_primitive_opt::<Float64Type>()
+            .ok_or_else(|| self.mismatch(name, "Float64"))
+    }
+
+    fn timestamps(&self, name: &str) -> Result<&'a TimestampMicrosecondArray> {
+        self.column(name)?
+            .as_primitive_opt::<TimestampMicrosecondType>()
+            .ok_or_else(|| self.mismatch(name, "Timestamp(Microsecond)"))
+    }
+}
+
+/// The string at `row`, or `None` if NULL
+fn optional(array: &StringArray, row: usize) -> Option<&str> {
+    array.is_valid(row).then(|| array.value(row))
+}
+
+/// The timestamp at `row`, or `None` if NULL
+fn optional_time(array: &TimestampMicrosecondArray, row: usize) -> Result<Option<DateTime<Utc>>> {
+    if array.is_null(row) {
+        return Ok(None);
+    }
+    let micros = array.value(row);
+    DateTime::from_timestamp_micros(micros)
+        .map(Some)
+        .ok_or_else(|| Error::StorageError(format!("Timestamp out of range: {micros}us")))
+}
+
+/// The timestamp at `row` of a non-nullable column
+fn required_time(array: &TimestampMicrosecondArray, row: usize) -> Result<DateTime<Utc>> {
+    optional_time(array, row)?
+        .ok_or_else(|| Error::StorageError(format!("Unexpected NULL timestamp at row {row}")))
+}
+
+fn experiments_from(batch: &RecordBatch) -> Result<Vec<ExperimentRecord>> {
+    let columns = Columns { batch };
+    let (ids, names) = (columns.strings("experiment_id")?, columns.strings("name")?);
+    let created = columns.timestamps("created_at")?;
+    let configs = columns.strings("config")?;
+    (0..batch.num_rows())
+        .map(|row| {
+            let mut builder = ExperimentRecord::builder(ids.value(row), names.value(row))
+                .created_at(required_time(created, row)?);
+            if let Some(config) = optional(configs, row) {
+                let config = serde_json::from_str(config).map_err(|e| {
+                    Error::StorageError(format!("Invalid experiment config JSON: {e}"))
+                })?;
+                builder = builder.config(config);
+            }
+            Ok(builder.build())
+        })
+        .collect()
+}
+
+fn runs_from(batch: &RecordBatch) -> Result<Vec<RunRecord>> {
+    let columns = Columns { batch };
+    let (ids, experiments) = (columns.strings("run_id")?, columns.strings("experiment_id")?);
+    let statuses = columns.strings("status")?;
+    let (started, ended) = (columns.timestamps("started_at")?, columns.timestamps("ended_at")?);
+    let spans = columns.strings("renacer_span_id")?;
+    (0..batch.num_rows())
+        .map(|row| {
+            let status: RunStatus = statuses.value(row).parse()?;
+            let mut builder =
+                RunRecord::builder(ids.value(row), experiments.value(row)).status(status);
+            if let Some(started_at) = optional_time(started, row)? {
+                builder = builder.started_at(started_at);
+            }
+            if let Some(ended_at) = optional_time(ended, row)? {
+                builder = builder.ended_at(ended_at);
+            }
+            if let Some(span) = optional(spans, row) {
+                builder = builder.renacer_span_id(span);
+            }
+            Ok(builder.build())
+        })
+        .collect()
+}
+
+fn metrics_from(batch: &RecordBatch) -> Result<Vec<MetricRecord>> {
+    let columns = Columns { batch };
+    let (runs, keys) = (columns.strings("run_id")?, columns.strings("key")?);
+    let (steps, values) = (columns.u64s("step")?, columns.f64s("value")?);
+    let times = columns.timestamps("timestamp")?;
+    (0..batch.num_rows())
+        .map(|row| {
+            Ok(MetricRecord::builder(
+                runs.value(row),
+                keys.value(row),
+                steps.value(row),
+                values.value(row),
+            )
+            .timestamp(required_time(times, row)?)
+            .build())
+        })
+        .collect()
+}
+
+fn artifacts_from(batch: &RecordBatch) -> Result<Vec<ArtifactRecord>> {
+    let columns = Columns { batch };
+    let (runs, keys) = (columns.strings("run_id")?, columns.strings("key")?);
+    let (hashes, sizes) = (columns.strings("cas_hash")?, columns.u64s("size_bytes")?);
+    let created = columns.timestamps("created_at")?;
+    (0..batch.num_rows())
+        .map(|row| {
+            Ok(ArtifactRecord::builder(
+                runs.value(row),
+                keys.value(row),
+                hashes.value(row),
+                sizes.value(row),
+            )
+            .created_at(required_time(created, row)?)
+            .build())
+        })
+        .collect()
+}
@@ -0,0 +1,661 @@
+//! External merge sort for inputs larger than memory
+//!
+//! **Problem**: `ORDER BY` without `LIMIT` sorts every row; an in-memory sort
+//! needs the whole input (plus sort indices and the output) resident at once
+//! and fails on multi-GB tables.
+//!
+//! **Solution**: Classic two-phase external sort.
+//! 1. Buffer incoming batches until the memory budget is reached, sort the
+//!    buffer with Arrow's sort kernel, and spill it to an Arrow IPC file
+//!    (a *sorted run*).
+//! 2. K-way merge the runs with a min-heap, streaming one batch per run at a
+//!    time and emitting fixed-size output batches.
+//!
+//! Comparisons use [`arrow::row`] encodings, so every sortable Arrow type
+//! (including strings and dictionaries) works without per-type code.
+//!
+//! The sort is stable: equal keys keep input order (or follow the
+//! [`TieBreak`] columns), matching in-memory Top-K.
+//!
+//! Toyota Way Principles:
+//! - **Jidoka**: Bounded memory instead of an out-of-memory abort
+//! - **Heijunka**: Fixed-size output batches level the downstream load
+
+use crate::topk::{self, SortOrder, TieBreak};
+use crate::{Error, Result};
+use arrow::array::{Array, ArrayRef, RecordBatch};
+use arrow::compute::{concat_batches, interleave, take_record_batch};
+use arrow::datatypes::SchemaRef;
+use arrow::ipc::reader::FileReader;
+use arrow::ipc::writer::FileWriter;
+use arrow::row::{OwnedRow, RowConverter, Rows, SortField};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Default number of rows per output (and spilled) batch
+pub const DEFAULT_SORT_BATCH_SIZE: usize = 8192;
+
+/// Unique suffix for spill directories within this process
+static SPILL_DIR_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Sorts a stream of record batches by one column within a memory budget
+///
+/// # Example
+/// ```
+/// use arrow::array::{Int32Array, RecordBatch};
+/// use arrow::datatypes::{DataType, Field, Schema};
+/// use std::sync::Arc;
+/// use trueno_db::external_sort::ExternalSorter;
+/// use trueno_db::topk::SortOrder;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let schema = Arc::new(Schema::new(vec![Field::new("v", DataType::Int32, false)]));
+/// let mut sorter = ExternalSorter::new(schema.clone(), 0, SortOrder::Ascending, 1024)?;
+/// for chunk in [vec![5, 3], vec![4, 1], vec![2]] {
+///     sorter.push(RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(chunk))])?)?;
+/// }
+///
+/// let sorted: Vec<RecordBatch> = sorter.finish()?.collect::<Result<_, _>>()?;
+/// assert_eq!(sorted.iter().map(RecordBatch::num_rows).sum::<usize>(), 5);
+/// # Ok(())
+/// # }
+/// ```
+pub struct ExternalSorter {
+    schema: SchemaRef,
+    column_index: usize,
+    order: SortOrder,
+    ties: TieBreak,
+    memory_budget: usize,
+    batch_size: usize,
+    spill_root: PathBuf,
+    spill_dir: Option<SpillDir>,
+    runs: Vec<PathBuf>,
+    buffered: Vec<RecordBatch>,
+    buffered_bytes: usize,
+    peak_bytes: usize,
+}
+
+impl ExternalSorter {
+    /// Create a sorter for `schema`, ordering by `column_index`
+    ///
+    /// `memory_budget` bounds the bytes of input buffered at once: a sorted
+    /// run is spilled before a batch would exceed it, and larger batches are
+    /// split first.
+    ///
+    /// # Errors
+    /// Returns error if `column_index` is out of bounds or `memory_budget` is 0
+    pub fn new(
+        schema: SchemaRef,
+        column_index: usize,
+        order: SortOrder,
+        memory_budget: usize,
+    ) -> Result<Self> {
+        if column_index >= schema.fields().len() {
+            return Err(Error::InvalidInput(format!(
+                "Sort column index {column_index} out of bounds (schema has {} columns)",
+                schema.fields().len()
+            )));
+        }
+        if memory_budget == 0 {
+            return Err(Error::InvalidInput("Sort memory budget must be > 0".to_string()));
+        }
+
+        Ok(Self {
+            schema,
+            column_index,
+            order,
+            ties: TieBreak::RowIndex,
+            memory_budget,
+            batch_size: DEFAULT_SORT_BATCH_SIZE,
+            spill_root: std::env::temp_dir(),
+            spill_dir: None,
+            runs: Vec::new(),
+            buffered: Vec::new(),
+            buffered_bytes: 0,
+            peak_bytes: 0,
+        })
+    }
+
+    /// Directory under which spill files are created (default: system temp dir)
+    #[must_use]
+    pub fn with_spill_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.spill_root = dir.as_ref().to_path_buf();
+        self
+    }
+
+    /// Order rows with equal keys by `ties` (default: input order)
+    ///
+    /// # Errors
+    /// Returns error if a tie-break column is out of bounds
+    pub fn with_tie_break(mut self, ties: TieBreak) -> Result<Self> {
+        ties.validate(self.schema.fields().len())?;
+        self.ties = ties;
+        Ok(self)
+    }
+
+    /// Rows per output batch (default: [`DEFAULT_SORT_BATCH_SIZE`])
+    #[must_use]
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Number of sorted runs spilled to disk so far
+    #[must_use]
+    pub fn spilled_runs(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// Most bytes of input buffered at once so far
+    ///
+    /// Stays within the memory budget unless a single row exceeds it.
+    #[must_use]
+    pub const fn peak_memory(&self) -> usize {
+        self.peak_bytes
+    }
+
+    /// Add a batch, first spilling a sorted run if it would overflow the
+    /// memory budget
+    ///
+    /// # Errors
+    /// Returns error if the batch schema does not match or spilling fails
+    pub fn push(&mut self, batch: RecordBatch) -> Result<()> {
+        if batch.schema().fields() != self.schema.fields() {
+            return Err(Error::InvalidInput(
+                "Batch schema does not match sorter schema".to_string(),
+            ));
+        }
+        if batch.num_rows() == 0 {
+            return Ok(());
+        }
+
+        let bytes = batch_memory_size(&batch);
+        if bytes > self.memory_budget && batch.num_rows() > 1 {
+            // Split into slices that fit the budget on their own
+            let rows = (self.memory_budget / bytes.div_ceil(batch.num_rows())).max(1);
+            let mut offset = 0;
+            while offset < batch.num_rows() {
+                let len = rows.min(batch.num_rows() - offset);
+                self.push(batch.slice(offset, len))?;
+                offset += len;
+            }
+            return Ok(());
+        }
+
+        if self.buffered_bytes + bytes > self.memory_budget {
+            self.spill()?;
+        }
+        self.buffered_bytes += bytes;
+        self.peak_bytes = self.peak_bytes.max(self.buffered_bytes);
+        self.buffered.push(batch);
+        Ok(())
+    }
+
+    /// Finish input and return the sorted output as a stream of batches
+    ///
+    /// If nothing was spilled the sort happens entirely in memory.
+    ///
+    /// # Errors
+    /// Returns error if the final run cannot be sorted or spilled, or a run
+    /// file cannot be reopened
+    pub fn finish(mut self) -> Result<SortedBatches> {
+        if self.runs.is_empty() {
+            let sorted = self.sort_buffered()?;
+            return Ok(SortedBatches::in_memory(sorted, self.batch_size));
+        }
+
+        self.spill()?;
+        let spill_dir = self.spill_dir.take();
+        let mut keys = vec![(self.column_index, self.order)];
+        keys.extend_from_slice(self.ties.columns());
+        let merge = RunMerge::open(&self.runs, keys, self.batch_size)?;
+        Ok(SortedBatches { source: Source::Merge(merge), _spill_dir: spill_dir })
+    }
+
+    /// Sort the buffered batches into one batch and clear the buffer
+    fn sort_buffered(&mut self) -> Result<Option<RecordBatch>> {
+        if self.buffered.is_empty() {
+            return Ok(None);
+        }
+
+        let combined = concat_batches(&self.schema, &self.buffered)
+            .map_err(|e| Error::StorageError(format!("Failed to combine sort input: {e}")))?;
+        self.buffered.clear();
+        self.buffered_bytes = 0;
+
+        let indices =
+            topk::sorted_indices(&combined, self.column_index, self.order, &self.ties, None)?;
+        take_record_batch(&combined, &indices)
+            .map(Some)
+            .map_err(|e| Error::StorageError(format!("Failed to reorder rows: {e}")))
+    }
+
+    /// Sort the buffer and write it as a new run file
+    fn spill(&mut self) -> Result<()> {
+        let Some(sorted) = self.sort_buffered()? else {
+            return Ok(());
+        };
+
+        if self.spill_dir.is_none() {
+            self.spill_dir = Some(SpillDir::create(&self.spill_root, "trueno_sort")?);
+        }
+        let dir = self.spill_dir.as_ref().map_or(self.spill_root.as_path(), |d| d.path.as_path());
+        let path = dir.join(format!("run_{:06}.arrow", self.runs.len()));
+
+        let file = File::create(&path)
+            .map_err(|e| Error::StorageError(format!("Failed to create sort run: {e}")))?;
+        let mut writer = FileWriter::try_new(BufWriter::new(file), &self.schema)
+            .map_err(|e| Error::StorageError(format!("Failed to write sort run: {e}")))?;
+
+        let mut offset = 0;
+        while offset < sorted.num_rows() {
+            let len = self.batch_size.min(sorted.num_rows() - offset);
+            writer
+                .write(&sorted.slice(offset, len))
+                .map_err(|e| Error::StorageError(format!("Failed to write sort run: {e}")))?;
+            offset += len;
+        }
+        writer
+            .finish()
+            .map_err(|e| Error::StorageError(format!("Failed to write sort run: {e}")))?;
+
+        tracing::debug!(run = self.runs.len(), rows = sorted.num_rows(), "spilled sorted run");
+        self.runs.push(path);
+        Ok(())
+    }
+}
+
+/// Sorted output of an [`ExternalSorter`]
+///
+/// Spill files are deleted when this is dropped.
+pub struct SortedBatches {
+    source: Source,
+    _spill_dir: Option<SpillDir>,
+}
+
+impl std::fmt::Debug for SortedBatches {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.source {
+            Source::Memory { .. } => f.write_str("SortedBatches(in memory)"),
+            Source::Merge(merge) => write!(f, "SortedBatches({} runs)", merge.cursors.len()),
+        }
+    }
+}
+
+enum Source {
+    Memory { batch: Option<RecordBatch>, offset: usize, batch_size: usize },
+    Merge(RunMerge),
+}
+
+impl SortedBatches {
+    const fn in_memory(batch: Option<RecordBatch>, batch_size: usize) -> Self {
+        Self { source: Source::Memory { batch, offset: 0, batch_size }, _spill_dir: None }
+    }
+}
+
+impl Iterator for SortedBatches {
+    type Item = Result<RecordBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.source {
+            Source::Memory { batch, offset, batch_size } => {
+                let sorted = batch.as_ref()?;
+                if *offset >= sorted.num_rows() {
+                    return None;
+                }
+                let len = (*batch_size).min(sorted.num_rows() - *offset);
+                let out = sorted.slice(*offset, len);
+                *offset += len;
+                Some(Ok(out))
+            }
+            Source::Merge(merge) => merge.next_batch().transpose(),
+        }
+    }
+}
+
+/// Read position within one sorted run
+struct RunCursor {
+    reader: FileReader<BufReader<File>>,
+    rows: Rows,
+    row: usize,
+    /// Index of the current batch in the output chunk's source list
+    source: usize,
+}
+
+/// K-way merge over sorted run files
+struct RunMerge {
+    cursors: Vec<RunCursor>,
+    /// Smallest unread row of each live run (ties broken by run index,
+    /// which keeps the merge stable since runs are spilled in input order)
+    heap: BinaryHeap<Reverse<(OwnedRow, usize)>>,
+    /// Batches referenced by the output chunk being built
+    sources: Vec<RecordBatch>,
+    converter: RowConverter,
+    /// Sort key columns: the sort column, then tie-break columns
+    key_columns: Vec<usize>,
+    batch_size: usize,
+}
+
+impl RunMerge {
+    fn open(runs: &[PathBuf], keys: Vec<(usize, SortOrder)>, batch_size: usize) -> Result<Self> {
+        let readers = runs
+            .iter()
+            .map(|path| {
+                let file = File::open(path)
+                    .map_err(|e| Error::StorageError(format!("Failed to open sort run: {e}")))?;
+                FileReader::try_new(BufReader::new(file), None)
+                    .map_err(|e| Error::StorageError(format!("Failed to read sort run: {e}")))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let first = readers
+            .first()
+            .ok_or_else(|| Error::InvalidInput("No sorted runs to merge".to_string()))?;
+        let schema = first.schema();
+        let fields = keys
+            .iter()
+            .map(|&(index, order)| {
+                SortField::new_with_options(schema.field(index).data_type().clone(), order.into())
+            })
+            .collect();
+        let converter = RowConverter::new(fields)
+            .map_err(|e| Error::StorageError(format!("Unsortable column: {e}")))?;
+
+        let mut merge = Self {
+            cursors: Vec::with_capacity(readers.len()),
+            heap: BinaryHeap::with_capacity(readers.len()),
+            sources: Vec::new(),
+            converter,
+            key_columns: keys.into_iter().map(|(index, _)| index).collect(),
+            batch_size,
+        };
+
+        for mut reader in readers {
+            let Some((batch, rows)) =
+                read_run_batch(&merge.converter, &merge.key_columns, &mut reader)?
+            else {
+                continue;
+            };
+            merge.heap.push(Reverse((rows.row(0).owned(), merge.cursors.len())));
+            merge.sources.push(batch);
+            let source = merge.sources.len() - 1;
+            merge.cursors.push(RunCursor { reader, rows, row: 0, source });
+        }
+
+        Ok(merge)
+    }
+
+    /// Merge up to `batch_size` rows into the next output batch
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+        if self.heap.is_empty() {
+            return Ok(None);
+        }
+
+        let mut picks: Vec<(usize, usize)> = Vec::with_capacity(self.batch_size);
+        while picks.len() < self.batch_size {
+            let Some(Reverse((_, run))) = self.heap.pop() else {
+                break;
+            };
+
+            let (source, row) = (self.cursors[run].source, self.cursors[run].row);
+            picks.push((source, row));
+            self.cursors[run].row += 1;
+
+            let cursor = &mut self.cursors[run];
+            if cursor.row == cursor.rows.num_rows() {
+                let Some((batch, rows)) =
+                    read_run_batch(&self.converter, &self.key_columns, &mut cursor.reader)?
+                else {
+                    continue;
+                };
+                self.sources.push(batch);
+                cursor.rows = rows;
+                cursor.row = 0;
+                cursor.source = self.sources.len() - 1;
+            }
+
+            self.heap.push(Reverse((cursor.rows.row(cursor.row).owned(), run)));
+        }
+
+        let batch = self.interleave(&picks)?;
+        self.compact_sources();
+        Ok(Some(batch))
+    }
+
+    /// Gather picked rows from the source batches into one batch
+    fn interleave(&self, picks: &[(usize, usize)]) -> Result<RecordBatch> {
+        let schema = self.sources[0].schema();
+        let columns = (0..schema.fields().len())
+            .map(|col| {
+                let arrays: Vec<&dyn Array> =
+                    self.sources.iter().map(|batch| batch.column(col).as_ref()).collect();
+                interleave(&arrays, picks)
+            })
+            .collect::<std::result::Result<Vec<ArrayRef>, _>>()
+            .map_err(|e| Error::StorageError(format!("Failed to merge sort runs: {e}")))?;
+
+        RecordBatch::try_new(schema, columns)
+            .map_err(|e| Error::StorageError(format!("Failed to merge sort runs: {e}")))
+    }
+
+    /// Drop source batches that no run still points into
+    fn compact_sources(&mut self) {
+        let mut sources = Vec::with_capacity(self.cursors.len());
+        for cursor in &mut self.cursors {
+            let batch = self.sources[cursor.source].clone();
+            cursor.source = sources.len();
+            sources.push(batch);
+        }
+        self.sources = sources;
+    }
+}
+
+/// Next non-empty batch of a run, with its sort-key row encodings
+fn read_run_batch(
+    converter: &RowConverter,
+    key_columns: &[usize],
+    reader: &mut FileReader<BufReader<File>>,
+) -> Result<Option<(RecordBatch, Rows)>> {
+    for batch in reader.by_ref() {
+        let batch =
+            batch.map_err(|e| Error::StorageError(format!("Failed to read sort run: {e}")))?;
+        if batch.num_rows() == 0 {
+            continue;
+        }
+        let columns: Vec<ArrayRef> =
+            key_columns.iter().map(|&index| batch.column(index).clone()).collect();
+        let rows = converter
+            .convert_columns(&columns)
+            .map_err(|e| Error::StorageError(format!("Failed to encode sort keys: {e}")))?;
+        return Ok(Some((batch, rows)));
+    }
+    Ok(None)
+}
+
+/// Temporary spill directory, removed on drop (also used by table eviction)
+pub(crate) struct SpillDir {
+    pub(crate) path: PathBuf,
+}
+
+impl SpillDir {
+    /// Create `<root>/<prefix>_<pid>_<n>`, unique within this process
+    pub(crate) fn create(root: &Path, prefix: &str) -> Result<Self> {
+        let n = SPILL_DIR_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = root.join(format!("{prefix}_{}_{n}", std::process::id()));
+        std::fs::create_dir_all(&path).map_err(|e| {
+            Error::StorageError(format!("Failed to create spill dir {}: {e}", path.display()))
+        })?;
+        Ok(Self { path })
+    }
+}
+
+impl Drop for SpillDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Bytes held by a batch's visible rows (slices are not charged for the
+/// whole parent buffer)
+fn batch_memory_size(batch: &RecordBatch) -> usize {
+    batch
+        .columns()
+        .iter()
+        .map(|column| {
+            column
+                .to_data()
+                .get_slice_memory_size()
+                .unwrap_or_else(|_| column.get_array_memory_size())
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Int32Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("key", DataType::Int32, true),
+            Field::new("label", DataType::Utf8, false),
+        ]))
+    }
+
+    fn batch(keys: Vec<Option<i32>>) -> RecordBatch {
+        let labels: Vec<String> = keys.iter().map(|k| format!("{k:?}")).collect();
+        RecordBatch::try_new(
+            schema(),
+            vec![Arc::new(Int32Array::from(keys)), Arc::new(StringArray::from(labels))],
+        )
+        .unwrap()
+    }
+
+    fn keys(batches: &[RecordBatch]) -> Vec<Option<i32>> {
+        batches
+            .iter()
+            .flat_map(|b| {
+                let col = b.column(0).as_any().downcast_ref::<Int32Array>().unwrap();
+                col.iter().collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_in_memory_sort_when_under_budget() {
+        let mut sorter = ExternalSorter::new(schema(), 0, SortOrder::Ascending, usize::MAX)
+            .unwrap()
+            .with_batch_size(2);
+        sorter.push(batch(vec![Some(3), Some(1), Some(2)])).unwrap();
+        assert_eq!(sorter.spilled_runs(), 0);
+
+        let out: Vec<RecordBatch> = sorter.finish().unwrap().collect::<Result<_>>().unwrap();
+        assert_eq!(out.len(), 2);
+        assert_eq!(keys(&out), vec![Some(1), Some(2), Some(3)]);
+    }
+
+    #[test]
+    fn test_spilled_runs_merge_in_order() {
+        let dir = std::env::temp_dir().join("trueno_test_external_sort_merge");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // Budget of 1 byte: every row becomes its own run
+        let mut sorter = ExternalSorter::new(schema(), 0, SortOrder::Descending, 1)
+            .unwrap()
+            .with_spill_dir(&dir)
+            .with_batch_size(3);
+        for chunk in [vec![5, 1, 9], vec![2, 8], vec![7, 3, 6, 4], vec![0]] {
+            sorter.push(batch(chunk.into_iter().map(Some).collect())).unwrap();
+        }
+        assert_eq!(sorter.spilled_runs(), 9);
+
+        let out: Vec<RecordBatch> = sorter.finish().unwrap().collect::<Result<_>>().unwrap();
+        assert!(out.iter().all(|b| b.num_rows() <= 3));
+        assert_eq!(keys(&out), (0..10).rev().map(Some).collect::<Vec<_>>());
+
+        // Rows stay intact across columns
+        let labels = out[0].column(1).as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(labels.value(0), "Some(9)");
+
+        // Spill files are removed once the output is dropped
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_buffered_input_stays_within_budget() {
+        let budget = 4096;
+        let mut sorter = ExternalSorter::new(schema(), 0, SortOrder::Ascending, budget).unwrap();
+
+        // One batch several times the budget, then many small ones
+        sorter.push(batch((0..2000).rev().map(Some).collect())).unwrap();
+        for start in (2000..3000).step_by(10) {
+            sorter.push(batch((start..start + 10).map(Some).collect())).unwrap();
+        }
+        assert!(sorter.spilled_runs() > 1);
+        assert!(sorter.peak_memory() > 0);
+        assert!(sorter.peak_memory() <= budget);
+
+        let out: Vec<RecordBatch> = sorter.finish().unwrap().collect::<Result<_>>().unwrap();
+        assert_eq!(keys(&out), (0..3000).map(Some).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_merge_places_nulls_last() {
+        let mut sorter = ExternalSorter::new(schema(), 0, SortOrder::Ascending, 1).unwrap();
+        sorter.push(batch(vec![None, Some(2)])).unwrap();
+        sorter.push(batch(vec![Some(1), None])).unwrap();
+
+        let out: Vec<RecordBatch> = sorter.finish().unwrap().collect::<Result<_>>().unwrap();
+        assert_eq!(keys(&out), vec![Some(1), Some(2), None, None]);
+    }
+
+    #[test]
+    fn test_equal_keys_are_stable_or_tie_broken() {
+        let labeled = |keys: Vec<i32>, labels: Vec<&str>| {
+            RecordBatch::try_new(
+                schema(),
+                vec![Arc::new(Int32Array::from(keys)), Arc::new(StringArray::from(labels))],
+            )
+            .unwrap()
+        };
+        let labels = |batches: &[RecordBatch]| -> Vec<String> {
+            batches
+                .iter()
+                .flat_map(|b| {
+                    let col = b.column(1).as_any().downcast_ref::<StringArray>().unwrap();
+                    col.iter().map(|label| label.unwrap().to_string()).collect::<Vec<_>>()
+                })
+                .collect()
+        };
+        let sort = |ties: TieBreak| {
+            let mut sorter = ExternalSorter::new(schema(), 0, SortOrder::Ascending, 1)
+                .unwrap()
+                .with_tie_break(ties)
+                .unwrap();
+            sorter.push(labeled(vec![1, 0, 1], vec!["b", "x", "a"])).unwrap();
+            sorter.push(labeled(vec![1, 1], vec!["d", "c"])).unwrap();
+            sorter.finish().unwrap().collect::<Result<Vec<_>>>().unwrap()
+        };
+
+        // Spilled runs keep input order among equal keys
+        assert_eq!(labels(&sort(TieBreak::RowIndex)), vec!["x", "b", "a", "d", "c"]);
+        let by_label = TieBreak::Columns(vec![(1, SortOrder::Descending)]);
+        assert_eq!(labels(&sort(by_label)), vec!["x", "d", "c", "b", "a"]);
+    }
+
+    #[test]
+    fn test_rejects_bad_column_and_budget() {
+        assert!(ExternalSorter::new(schema(), 5, SortOrder::Ascending, 1024).is_err());
+        assert!(ExternalSorter::new(schema(), 0, SortOrder::Ascending, 0).is_err());
+        let sorter = ExternalSorter::new(schema(), 0, SortOrder::Ascending, 1024).unwrap();
+        assert!(sorter.with_tie_break(TieBreak::Columns(vec![(2, SortOrder::Ascending)])).is_err());
+    }
+}
@@ -0,0 +1,451 @@
+//! Arrow Flight server over a [`Database`]
+//!
+//! Remote clients (pyarrow, the R arrow package, any Flight client) read
+//! tables and run SQL over gRPC, without exchanging files:
+//!
+//! - **`ListFlights` / `GetFlightInfo` / `GetSchema`**: registered tables,
+//!   addressed by a path descriptor holding the table name
+//! - **`DoGet`**: scan a table; the ticket is the table name
+//! - **`DoAction("query")`**: run the SQL in the action body; the result
+//!   comes back as one Arrow IPC stream
+//! - **`DoExchange`**: run the SQL in the first message's command descriptor
+//!   and stream the result back. Batches the client sends are queryable as
+//!   the temporary table [`EXCHANGE_TABLE`] while the query runs (hiding, not
+//!   replacing, a temporary table of that name).
+//!
+//! Queries run on the blocking thread pool and hold the database lock, so
+//! clients are served one query at a time, as with [`Database::sql`].
+//!
+//! The server turns on query stats: query results carry
+//! [`QueryStats`](crate::query::QueryStats) (rows scanned, elapsed time,
+//! backend) in their schema metadata, which Flight clients receive with the
+//! schema.
+//!
+//! # Example
+//! ```rust,no_run
+//! use trueno_db::flight::FlightServer;
+//! use trueno_db::Database;
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut db = Database::builder().build()?;
+//! db.attach_dir("data/")?;
+//! tonic::transport::Server::builder()
+//!     .add_service(FlightServer::new(db).into_service())
+//!     .serve("0.0.0.0:50051".parse()?)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! From Python:
+//! ```python
+//! import pyarrow.flight as flight
+//! client = flight.connect("grpc://localhost:50051")
+//! events = client.do_get(flight.Ticket(b"events")).read_all()
+//! ```
+
+use crate::storage::StorageEngine;
+use crate::{Database, Error, Result};
+use arrow::datatypes::{Schema, SchemaRef};
+use arrow::ipc::writer::{IpcWriteOptions, StreamWriter};
+use arrow::record_batch::RecordBatch;
+use arrow_flight::decode::FlightRecordBatchStream;
+use arrow_flight::encode::FlightDataEncoderBuilder;
+use arrow_flight::error::FlightError;
+use arrow_flight::flight_descriptor::DescriptorType;
+use arrow_flight::flight_service_server::{FlightService, FlightServiceServer};
+use arrow_flight::{
+    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightEndpoint, FlightInfo,
+    HandshakeRequest, HandshakeResponse, PollInfo, PutResult, SchemaAsIpc, SchemaResult, Ticket,
+};
+use futures::stream::{self, BoxStream};
+use futures::{StreamExt, TryStreamExt};
+use std::sync::{Arc, Mutex, PoisonError};
+use tonic::{Request, Response, Status, Streaming};
+
+/// Temporary table holding the batches a client sends with `DoExchange`
+pub const EXCHANGE_TABLE: &str = "exchange";
+
+/// `DoAction` type running the SQL in the action body
+pub const QUERY_ACTION: &str = "query";
+
+type FlightResult<T> = std::result::Result<T, Status>;
+
+/// Flight service serving the tables and queries of one [`Database`]
+#[derive(Clone)]
+pub struct FlightServer {
+    db: Arc<Mutex<Database>>,
+}
+
+impl FlightServer {
+    /// Serve `db`, with query stats turned on
+    #[must_use]
+    pub fn new(db: Database) -> Self {
+        Self::from_shared(Arc::new(Mutex::new(db)))
+    }
+
+    /// Serve a database the application keeps using (e.g. to register
+    /// tables while the server runs), with query stats turned on
+    #[must_use]
+    pub fn from_shared(db: Arc<Mutex<Database>>) -> Self {
+        db.lock().unwrap_or_else(PoisonError::into_inner).set_query_stats(true);
+        Self { db }
+    }
+
+    /// gRPC service to add to a `tonic` server
+    #[must_use]
+    pub fn into_service(self) -> FlightServiceServer<Self> {
+        FlightServiceServer::new(self)
+    }
+
+    /// Run `f` on the database on the blocking thread pool
+    async fn with_db<R, F>(&self, f: F) -> FlightResult<R>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut Database) -> Result<R> + Send + 'static,
+    {
+        let db = Arc::clone(&self.db);
+        tokio::task::spawn_blocking(move || {
+            f(&mut db.lock().unwrap_or_else(PoisonError::into_inner))
+        })
+        .await
+        .map_err(|e| Status::internal(format!("query task: {e}")))?
+        .map_err(|e| status(&e))
+    }
+}
+
+#[tonic::async_trait]
+impl FlightService for FlightServer {
+    type HandshakeStream = BoxStream<'static, FlightResult<HandshakeResponse>>;
+    type ListFlightsStream = BoxStream<'static, FlightResult<FlightInfo>>;
+    type DoGetStream = BoxStream<'static, FlightResult<FlightData>>;
+    type DoPutStream = BoxStream<'static, FlightResult<PutResult>>;
+    type DoActionStream = BoxStream<'static, FlightResult<arrow_flight::Result>>;
+    type ListActionsStream = BoxStream<'static, FlightResult<ActionType>>;
+    type DoExchangeStream = BoxStream<'static, FlightResult<FlightData>>;
+
+    async fn handshake(
+        &self,
+        _request: Request<Streaming<HandshakeRequest>>,
+    ) -> FlightResult<Response<Self::HandshakeStream>> {
+        Err(Status::unimplemented("Handshake is not required"))
+    }
+
+    async fn list_flights(
+        &self,
+        _request: Request<Criteria>,
+    ) -> FlightResult<Response<Self::ListFlightsStream>> {
+        let infos: Vec<_> = self
+            .with_db(|db| {
+                let names: Vec<String> =
+                    db.catalog().table_names().into_iter().map(str::to_string).collect();
+                names.iter().filter_map(|name| table_info(db, name).transpose()).collect()
+            })
+            .await?;
+        Ok(Response::new(stream::iter(infos.into_iter().map(Ok)).boxed()))
+    }
+
+    async fn get_flight_info(
+        &self,
+        request: Request<FlightDescriptor>,
+    ) -> FlightResult<Response<FlightInfo>> {
+        let name = table_name(request.get_ref())?;
+        let info = self.with_db(move |db| table_info(db, &name)).await?;
+        info.map(Response::new).ok_or_else(|| Status::not_found("Table not found"))
+    }
+
+    async fn poll_flight_info(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> FlightResult<Response<PollInfo>> {
+        Err(Status::unimplemented("Flights complete immediately; use GetFlightInfo"))
+    }
+
+    async fn get_schema(
+        &self,
+        request: Request<FlightDescriptor>,
+    ) -> FlightResult<Response<SchemaResult>> {
+        let name = table_name(request.get_ref())?;
+        let schema = self
+            .with_db(move |db| db.catalog().with_table(&name, table_schema))
+            .await?
+            .ok_or_else(|| Status::not_found("Table not found"))?;
+        let schema = SchemaAsIpc::new(&schema, &IpcWriteOptions::default())
+            .try_into()
+            .map_err(|e: arrow::error::ArrowError| Status::internal(e.to_string()))?;
+        Ok(Response::new(schema))
+    }
+
+    async fn do_get(&self, request: Request<Ticket>) -> FlightResult<Response<Self::DoGetStream>> {
+        let name = String::from_utf8(request.into_inner().ticket.to_vec())
+            .map_err(|_| Status::invalid_argument("Ticket must be a UTF-8 table name"))?;
+        let (schema, batches) = self
+            .with_db(move |db| {
+                Ok(db
+                    .table(&name)?
+                    .map(|storage| (table_schema(storage), storage.batches().to_vec())))
+            })
+            .await?
+            .ok_or_else(|| Status::not_found("Table not found"))?;
+        Ok(Response::new(flight_data(schema, batches)))
+    }
+
+    async fn do_put(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> FlightResult<Response<Self::DoPutStream>> {
+        Err(Status::unimplemented("Tables are read-only over Flight; use DoExchange"))
+    }
+
+    async fn do_action(
+        &self,
+        request: Request<Action>,
+    ) -> FlightResult<Response<Self::DoActionStream>> {
+        let action = request.into_inner();
+        if action.r#type != QUERY_ACTION {
+            return Err(Status::invalid_argument(format!("Unknown action: {}", action.r#type)));
+        }
+        let sql = String::from_utf8(action.body.to_vec())
+            .map_err(|_| Status::invalid_argument("Query must be UTF-8 SQL"))?;
+        let body = self.with_db(move |db| ipc_stream(&db.sql(&sql)?)).await?;
+        let result = arrow_flight::Result { body: body.into() };
+        Ok(Response::new(stream::once(async { Ok(result) }).boxed()))
+    }
+
+    async fn list_actions(
+        &self,
+        _request: Request<Empty>,
+    ) -> FlightResult<Response<Self::ListActionsStream>> {
+        let query = ActionType {
+            r#type: QUERY_ACTION.to_string(),
+            description: "Run the SQL in the body; returns the result as an Arrow IPC stream"
+                .to_string(),
+        };
+        Ok(Response::new(stream::once(async { Ok(query) }).boxed()))
+    }
+
+    async fn do_exchange(
+        &self,
+        request: Request<Streaming<FlightData>>,
+    ) -> FlightResult<Response<Self::DoExchangeStream>> {
+        let mut messages = request.into_inner();
+        let first = messages.message().await?.ok_or_else(|| {
+            Status::invalid_argument("DoExchange needs a command descriptor holding the SQL")
+        })?;
+        let sql = match &first.flight_descriptor {
+            Some(descriptor) if descriptor.r#type() == DescriptorType::Cmd => {
+                String::from_utf8(descriptor.cmd.to_vec())
+                    .map_err(|_| Status::invalid_argument("Query must be UTF-8 SQL"))?
+            }
+            _ => {
+                return Err(Status::invalid_argument(
+                    "The first message must carry a command descriptor holding the SQL",
+                ))
+            }
+        };
+        // A descriptor-only first message carries no IPC data for the decoder
+        let first = (!first.data_header.is_empty()).then_some(first);
+        let messages = stream::iter(first.map(Ok)).chain(messages).map_err(FlightError::from);
+        let input: Vec<RecordBatch> = FlightRecordBatchStream::new_from_flight_data(messages)
+            .try_collect()
+            .await
+            .map_err(Status::from)?;
+
+        let result = self.with_db(move |db| exchange(db, &sql, input)).await?;
+        Ok(Response::new(flight_data(result.schema(), vec![result])))
+    }
+}
+
+/// Table name of a path descriptor
+#[allow(clippy::result_large_err)] // `Status` is the service's error type
+fn table_name(descriptor: &FlightDescriptor) -> FlightResult<String> {
+    match (descriptor.r#type(), descriptor.path.as_slice()) {
+        (DescriptorType::Path, [name]) => Ok(name.clone()),
+        _ => Err(Status::invalid_argument("Expected a path descriptor holding one table name")),
+    }
+}
+
+/// Schema of a table (empty if it has no batches)
+fn table_schema(storage: &StorageEngine) -> SchemaRef {
+    storage.batches().first().map_or_else(|| Arc::new(Schema::empty()), RecordBatch::schema)
+}
+
+/// Flight info of a table, read without making an evicted table resident
+#[allow(clippy::cast_possible_wrap)]
+fn table_info(db: &Database, name: &str) -> Result<Option<FlightInfo>> {
+    let info = db.catalog().with_table(name, |storage| {
+        let batches = storage.batches();
+        FlightInfo::new().try_with_schema(&table_schema(storage)).map(|info| {
+            info.with_descriptor(FlightDescriptor::new_path(vec![name.to_string()]))
+                .with_endpoint(FlightEndpoint::new().with_ticket(Ticket::new(name.to_string())))
+                .with_total_records(batches.iter().map(RecordBatch::num_rows).sum::<usize>() as i64)
+                .with_total_bytes(storage.memory_size() as i64)
+        })
+    })?;
+    info.transpose().map_err(Error::from)
+}
+
+/// Run `sql` with `input` registered as [`EXCHANGE_TABLE`] (if any)
+///
+/// A temporary table the session already had under that name is hidden
+/// for the query and restored afterwards.
+fn exchange(db: &mut Database, sql: &str, input: Vec<RecordBatch>) -> Result<RecordBatch> {
+    if input.is_empty() {
+        return db.sql(sql);
+    }
+    let previous =
+        db.catalog_mut().register_temporary(EXCHANGE_TABLE, StorageEngine::new(input))?;
+    let result = db.sql(sql);
+    match previous {
+        Some(previous) => {
+            db.catalog_mut().register_temporary(EXCHANGE_TABLE, previous)?;
+        }
+        None => {
+            db.drop_temporary(EXCHANGE_TABLE);
+        }
+    }
+    result
+}
+
+/// `batch` as an Arrow IPC stream
+fn ipc_stream(batch: &RecordBatch) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut writer = StreamWriter::try_new(&mut bytes, &batch.schema())?;
+    writer.write(batch)?;
+    writer.finish()?;
+    drop(writer);
+    Ok(bytes)
+}
+
+/// `batches` encoded as Flight data (schema first, even without batches)
+fn flight_data(
+    schema: SchemaRef,
+    batches: Vec<RecordBatch>,
+) -> BoxStream<'static, FlightResult<FlightData>> {
+    FlightDataEncoderBuilder::new()
+        .with_schema(schema)
+        .build(stream::iter(batches.into_iter().map(Ok)))
+        .map_err(Status::from)
+        .boxed()
+}
+
+/// gRPC status for a database error
+fn status(e: &Error) -> Status {
+    let message = e.to_string();
+    match e {
+        Error::ParseError(_) | Error::InvalidInput(_) => Status::invalid_argument(message),
+        Error::Overloaded(_) => Status::resource_exhausted(message),
+        Error::AccessDenied(_) => Status::permission_denied(message),
+        Error::Timeout(_) => Status::deadline_exceeded(message),
+        Error::Cancelled(_) => Status::cancelled(message),
+        _ => Status::internal(message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::Int32Array;
+    use arrow::datatypes::{DataType, Field};
+    use arrow::ipc::reader::StreamReader;
+
+    fn server() -> FlightServer {
+        let schema = Arc::new(Schema::new(vec![Field::new("score", DataType::Int32, false)]));
+        let batch =
+            RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![3, 1, 2]))]).unwrap();
+        let mut db = Database::builder().backend(crate::Backend::Simd).build().unwrap();
+        db.register_batch("scores", batch).unwrap();
+        FlightServer::new(db)
+    }
+
+    async fn decode(data: BoxStream<'static, FlightResult<FlightData>>) -> Vec<RecordBatch> {
+        FlightRecordBatchStream::new_from_flight_data(data.map_err(FlightError::from))
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_do_get_scans_table() {
+        let server = server();
+        let response = server.do_get(Request::new(Ticket::new("scores"))).await.unwrap();
+        let batches = decode(response.into_inner()).await;
+        assert_eq!(batches.iter().map(RecordBatch::num_rows).sum::<usize>(), 3);
+
+        let missing = server.do_get(Request::new(Ticket::new("missing"))).await;
+        assert_eq!(missing.err().unwrap().code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_list_and_describe_tables() {
+        let server = server();
+        let infos: Vec<FlightInfo> = server
+            .list_flights(Request::new(Criteria::default()))
+            .await
+            .unwrap()
+            .into_inner()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(infos.len(), 1);
+        assert_eq!(infos[0].total_records, 3);
+
+        let descriptor = FlightDescriptor::new_path(vec!["scores".to_string()]);
+        let info = server.get_flight_info(Request::new(descriptor)).await.unwrap().into_inner();
+        assert_eq!(info.endpoint[0].ticket, Some(Ticket::new("scores")));
+        let bad = server.get_flight_info(Request::new(FlightDescriptor::new_cmd("scores"))).await;
+        assert_eq!(bad.err().unwrap().code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_query_action_returns_ipc_stream() {
+        let server = server();
+        let action = Action::new(QUERY_ACTION, "SELECT COUNT(*) FROM scores WHERE score > 1");
+        let results: Vec<arrow_flight::Result> = server
+            .do_action(Request::new(action))
+            .await
+            .unwrap()
+            .into_inner()
+            .try_collect()
+            .await
+            .unwrap();
+        let batch =
+            StreamReader::try_new(results[0].body.as_ref(), None).unwrap().next().unwrap().unwrap();
+        let count = batch.column(0).as_any().downcast_ref::<arrow::array::Int64Array>();
+        assert_eq!(count.map(|count| count.value(0)), Some(2));
+        let stats = crate::query::QueryStats::from_batch(&batch).unwrap();
+        assert_eq!(stats.rows_scanned, 3);
+        assert_eq!(stats.backend, crate::Backend::Simd);
+
+        let parse_error = Action::new(QUERY_ACTION, "SELEC nothing");
+        let error = server.do_action(Request::new(parse_error)).await.err().unwrap();
+        assert_eq!(error.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[test]
+    fn test_exchange_registers_input_for_one_query() {
+        let server = server();
+        let mut db = server.db.lock().unwrap();
+        let input = db.sql("SELECT score FROM scores WHERE score > 1").unwrap();
+        let result = exchange(&mut db, "SELECT COUNT(*) FROM exchange", vec![input]).unwrap();
+        assert_eq!(result.num_rows(), 1);
+        assert!(!db.catalog().contains(EXCHANGE_TABLE));
+        assert!(exchange(&mut db, "SELECT COUNT(*) FROM exchange", Vec::new()).is_err());
+        drop(db);
+    }
+
+    #[test]
+    fn test_exchange_restores_existing_temporary_table() {
+        let server = server();
+        let mut db = server.db.lock().unwrap();
+        let own = db.sql("SELECT score FROM scores").unwrap();
+        db.register_batch(EXCHANGE_TABLE, own).unwrap();
+        let input = db.sql("SELECT score FROM scores WHERE score > 1").unwrap();
+
+        let result = exchange(&mut db, "SELECT score FROM exchange", vec![input]).unwrap();
+        assert_eq!(result.num_rows(), 2);
+        assert!(db.catalog().is_temporary(EXCHANGE_TABLE));
+        assert_eq!(db.sql("SELECT score FROM exchange").unwrap().num_rows(), 3);
+        drop(db);
+    }
+}
@@ -0,0 +1,391 @@
+//! Workgroup size auto-tuning
+//!
+//! 256 threads per workgroup is a good fit for NVIDIA (8 warps) but not
+//! universal: AMD wavefronts are 64 wide, and many mobile/integrated GPUs
+//! prefer 64 or 128. At warmup the tuner benchmarks each candidate size per
+//! kernel on the actual device, keeps the fastest size that produces the
+//! correct result, and records it in a [`WorkgroupConfig`] that can be saved
+//! and reloaded so tuning runs once per device.
+//!
+//! Toyota Way: Genchi Genbutsu (measure on the real device), Jidoka
+//! (candidates producing wrong results are rejected, never selected)
+
+use super::buffers::BufferPool;
+use super::kernels::{self, ReduceOp};
+use super::pipeline::PipelineCache;
+use super::submit::PollBudget;
+use crate::query::stats::Stopwatch;
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+/// Workgroup size used when no tuned value is available
+pub const DEFAULT_WORKGROUP_SIZE: u32 = 256;
+
+/// Workgroup sizes benchmarked by the tuner (powers of two, required by the
+/// tree reduction in the kernels)
+pub const CANDIDATE_WORKGROUP_SIZES: [u32; 4] = [64, 128, 256, 512];
+
+/// Kernels whose workgroup size can be tuned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TunableKernel {
+    /// SUM reduction (i32)
+    SumI32,
+    /// MIN reduction (i32)
+    MinI32,
+    /// MAX reduction (i32)
+    MaxI32,
+    /// JIT-compiled fused filter + SUM
+    FusedFilterSum,
+}
+
+impl TunableKernel {
+    /// All tunable kernels
+    pub const ALL: [Self; 4] = [Self::SumI32, Self::MinI32, Self::MaxI32, Self::FusedFilterSum];
+
+    /// Stable name used as the key in persisted configurations
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::SumI32 => "sum_i32",
+            Self::MinI32 => "min_i32",
+            Self::MaxI32 => "max_i32",
+            Self::FusedFilterSum => "fused_filter_sum",
+        }
+    }
+}
+
+/// Rewrite a 256-thread reduction shader for another workgroup size
+///
+/// Adjusts `@workgroup_size`, the shared memory array length, and the
+/// initial reduction stride. `workgroup_size` must be a power of two.
+#[must_use]
+pub fn specialize_workgroup_size(shader: &str, workgroup_size: u32) -> String {
+    if workgroup_size == DEFAULT_WORKGROUP_SIZE {
+        return shader.to_string();
+    }
+
+    shader
+        .replace("@workgroup_size(256)", &format!("@workgroup_size({workgroup_size})"))
+        .replace("array<i32, 256>", &format!("array<i32, {workgroup_size}>"))
+        .replace("array<f32, 256>", &format!("array<f32, {workgroup_size}>"))
+        .replace("array<vec4<u32>, 256>", &format!("array<vec4<u32>, {workgroup_size}>"))
+        .replace("var stride = 128u;", &format!("var stride = {}u;", workgroup_size / 2))
+}
+
+/// Identify an adapter across runs (name, vendor/device id, backend)
+#[must_use]
+pub fn device_key(info: &wgpu::AdapterInfo) -> String {
+    format!("{}|{:04x}:{:04x}|{:?}", info.name, info.vendor, info.device, info.backend)
+}
+
+/// Tuned workgroup sizes per device and kernel
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkgroupConfig {
+    devices: BTreeMap<String, BTreeMap<String, u32>>,
+}
+
+impl WorkgroupConfig {
+    /// Create an empty configuration (every kernel uses the default size)
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Workgroup size for a kernel on a device (default if not tuned)
+    #[must_use]
+    pub fn get(&self, device_key: &str, kernel: TunableKernel) -> u32 {
+        self.devices
+            .get(device_key)
+            .and_then(|kernels| kernels.get(kernel.name()))
+            .copied()
+            .unwrap_or(DEFAULT_WORKGROUP_SIZE)
+    }
+
+    /// Record the workgroup size for a kernel on a device
+    pub fn set(&mut self, device_key: &str, kernel: TunableKernel, workgroup_size: u32) {
+        self.devices
+            .entry(device_key.to_string())
+            .or_default()
+            .insert(kernel.name().to_string(), workgroup_size);
+    }
+
+    /// Whether any kernel has been tuned for a device
+    #[must_use]
+    pub fn is_tuned(&self, device_key: &str) -> bool {
+        self.devices.contains_key(device_key)
+    }
+
+    /// Load a configuration from JSON (missing file yields an empty config)
+    ///
+    /// # Errors
+    /// Returns error if the file exists but cannot be read or parsed
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        match std::fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| {
+                Error::StorageError(format!("Invalid workgroup config {}: {e}", path.display()))
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(e) => Err(Error::Io(e)),
+        }
+    }
+
+    /// Save the configuration as JSON
+    ///
+    /// # Errors
+    /// Returns error if the file cannot be written
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let json = serde_json::to_vec_pretty(self)
+            .map_err(|e| Error::Other(format!("Failed to encode workgroup config: {e}")))?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+/// Outcome of tuning one kernel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TuningResult {
+    /// Kernel that was tuned
+    pub kernel: TunableKernel,
+    /// Median time per candidate size (candidates with wrong results omitted)
+    pub timings: Vec<(u32, Duration)>,
+    /// Fastest correct workgroup size
+    pub best: u32,
+}
+
+/// Candidate sizes the device can actually launch
+#[must_use]
+pub fn supported_candidates(limits: &wgpu::Limits) -> Vec<u32> {
+    CANDIDATE_WORKGROUP_SIZES
+        .into_iter()
+        .filter(|&size| {
+            size <= limits.max_compute_workgroup_size_x
+                && size <= limits.max_compute_invocations_per_workgroup
+                && size * 4 <= limits.max_compute_workgroup_storage_size
+        })
+        .collect()
+}
+
+/// Benchmark every supported candidate size for one kernel
+///
+/// Each candidate runs once to warm up, then `iterations` timed runs; the
+/// median is compared. Results are checked against a CPU reference.
+///
+/// # Errors
+/// Returns error if no candidate size produces the correct result
+#[allow(clippy::too_many_arguments)]
+pub async fn tune_kernel(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    pipelines: &PipelineCache,
+    buffers: &BufferPool,
+    kernel: TunableKernel,
+    sample: &[i32],
+    iterations: usize,
+    budget: &PollBudget,
+) -> Result<TuningResult> {
+    let expected = cpu_reference(kernel, sample);
+    let iterations = iterations.max(1);
+    let mut timings = Vec::new();
+
+    for size in supported_candidates(&device.limits()) {
+        let (shader, entry_point, identity) = kernel_source(kernel, size);
+
+        // Warmup (pipeline compilation, driver caches); also the correctness check
+        let result = kernels::run_i32_reduction(
+            device,
+            queue,
+            pipelines,
+            buffers,
+            &shader,
+            entry_point,
+            identity,
+            sample,
+            size,
+            budget,
+        )
+        .await?;
+        if result != expected {
+            tracing::warn!(
+                kernel = kernel.name(),
+                workgroup_size = size,
+                result,
+                expected,
+                "Workgroup size produced wrong result, skipping"
+            );
+            continue;
+        }
+
+        let mut samples = Vec::with_capacity(iterations);
+        for _ in 0..iterations {
+            let start = Stopwatch::start();
+            kernels::run_i32_reduction(
+                device,
+                queue,
+                pipelines,
+                buffers,
+                &shader,
+                entry_point,
+                identity,
+                sample,
+                size,
+                budget,
+            )
+            .await?;
+            samples.push(start.elapsed());
+        }
+        samples.sort_unstable();
+        timings.push((size, samples[samples.len() / 2]));
+    }
+
+    let best =
+        timings.iter().min_by_key(|(_, time)| *time).map(|(size, _)| *size).ok_or_else(|| {
+            Error::Other(format!("No workgroup size produced a correct {}", kernel.name()))
+        })?;
+
+    Ok(TuningResult { kernel, timings, best })
+}
+
+/// Shader source, entry point, and output identity for a kernel at a size
+fn kernel_source(kernel: TunableKernel, workgroup_size: u32) -> (String, &'static str, i32) {
+    match kernel {
+        TunableKernel::SumI32 => ReduceOp::Sum.shader(workgroup_size),
+        TunableKernel::MinI32 => ReduceOp::Min.shader(workgroup_size),
+        TunableKernel::MaxI32 => ReduceOp::Max.shader(workgroup_size),
+        TunableKernel::FusedFilterSum => {
+            let source = super::jit::JitCompiler::new().generate_fused_filter_sum(0, "gt");
+            (specialize_workgroup_size(&source, workgroup_size), "fused_filter_sum", 0)
+        }
+    }
+}
+
+/// Expected result computed on the CPU (wrapping, like the GPU atomics)
+fn cpu_reference(kernel: TunableKernel, sample: &[i32]) -> i32 {
+    match kernel {
+        TunableKernel::SumI32 => sample.iter().fold(0i32, |acc, &v| acc.wrapping_add(v)),
+        TunableKernel::MinI32 => sample.iter().copied().min().unwrap_or(i32::MAX),
+        TunableKernel::MaxI32 => sample.iter().copied().max().unwrap_or(i32::MIN),
+        TunableKernel::FusedFilterSum => {
+            sample.iter().filter(|&&v| v > 0).fold(0i32, |acc, &v| acc.wrapping_add(v))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_specialize_default_is_identity() {
+        let shader = ReduceOp::Sum.shader(DEFAULT_WORKGROUP_SIZE).0;
+        assert_eq!(specialize_workgroup_size(&shader, 256), shader);
+    }
+
+    #[test]
+    fn test_specialize_rewrites_all_size_dependent_constants() {
+        let (shader, _, _) = ReduceOp::Min.shader(64);
+        assert!(shader.contains("@workgroup_size(64)"));
+        assert!(shader.contains("array<i32, 64>"));
+        assert!(shader.contains("var stride = 32u;"));
+        assert!(!shader.contains("256"));
+    }
+
+    #[test]
+    fn test_specialize_fused_kernel() {
+        let source = crate::gpu::jit::JitCompiler::new().generate_fused_filter_sum(10, "gt");
+        let shader = specialize_workgroup_size(&source, 128);
+        assert!(shader.contains("@workgroup_size(128)"));
+        assert!(shader.contains("var stride = 64u;"));
+    }
+
+    #[test]
+    fn test_config_defaults_and_overrides() {
+        let mut config = WorkgroupConfig::new();
+        assert_eq!(config.get("dev", TunableKernel::SumI32), DEFAULT_WORKGROUP_SIZE);
+        assert!(!config.is_tuned("dev"));
+
+        config.set("dev", TunableKernel::SumI32, 64);
+        assert_eq!(config.get("dev", TunableKernel::SumI32), 64);
+        assert_eq!(config.get("dev", TunableKernel::MaxI32), DEFAULT_WORKGROUP_SIZE);
+        assert_eq!(config.get("other", TunableKernel::SumI32), DEFAULT_WORKGROUP_SIZE);
+    }
+
+    #[test]
+    fn test_config_save_load_roundtrip() {
+        let path = std::env::temp_dir().join("trueno_test_workgroup_config.json");
+        let mut config = WorkgroupConfig::new();
+        config.set("dev", TunableKernel::FusedFilterSum, 128);
+        config.save(&path).unwrap();
+
+        assert_eq!(WorkgroupConfig::load(&path).unwrap(), config);
+    }
+
+    #[test]
+    fn test_config_load_missing_file_is_empty() {
+        let config = WorkgroupConfig::load("/nonexistent/trueno_workgroups.json").unwrap();
+        assert_eq!(config, WorkgroupConfig::new());
+    }
+
+    #[test]
+    fn test_supported_candidates_respect_limits() {
+        let limits = wgpu::Limits {
+            max_compute_workgroup_size_x: 256,
+            max_compute_invocations_per_workgroup: 256,
+            ..wgpu::Limits::default()
+        };
+        assert_eq!(supported_candidates(&limits), vec![64, 128, 256]);
+    }
+
+    #[test]
+    fn test_cpu_reference() {
+        let sample = [3, -1, 5];
+        assert_eq!(cpu_reference(TunableKernel::SumI32, &sample), 7);
+        assert_eq!(cpu_reference(TunableKernel::MinI32, &sample), -1);
+        assert_eq!(cpu_reference(TunableKernel::MaxI32, &sample), 5);
+        assert_eq!(cpu_reference(TunableKernel::FusedFilterSum, &sample), 8);
+    }
+
+    #[tokio::test]
+    async fn test_tune_sum_kernel_picks_candidate() {
+        let instance = wgpu::Instance::default();
+        let Some(adapter) = instance.request_adapter(&wgpu::RequestAdapterOptions::default()).await
+        else {
+            eprintln!("Skipping GPU test (no GPU available)");
+            return;
+        };
+        let Ok((device, queue)) =
+            adapter.request_device(&wgpu::DeviceDescriptor::default(), None).await
+        else {
+            eprintln!("Skipping GPU test (failed to create device)");
+            return;
+        };
+
+        let sample: Vec<i32> = (0..10_000).collect();
+        let (pipelines, buffers) = (PipelineCache::new(), BufferPool::new());
+        let budget = PollBudget::default();
+        let result = tune_kernel(
+            &device,
+            &queue,
+            &pipelines,
+            &buffers,
+            TunableKernel::SumI32,
+            &sample,
+            2,
+            &budget,
+        )
+        .await
+        .unwrap();
+
+        assert!(CANDIDATE_WORKGROUP_SIZES.contains(&result.best));
+        assert!(!result.timings.is_empty());
+        // Warmup compiles each candidate once; the timed runs reuse it
+        let stats = pipelines.stats();
+        assert!(stats.misses as usize >= result.timings.len());
+        assert_eq!(stats.hits as usize, result.timings.len() * 2);
+        // Timed runs reuse the warmup's buffers
+        assert!(buffers.stats().hits > 0);
+    }
+}
@@ -0,0 +1,350 @@
+//! Reusable device buffers
+//!
+//! Kernels used to create fresh input, output and staging buffers on every
+//! call. Allocating device memory (and, for staging buffers, host-visible
+//! memory) is a driver round trip that costs more than uploading a small
+//! column. A [`BufferPool`] keeps released buffers and hands them out again:
+//!
+//! - **Buckets**: sizes are rounded up to a power of two (at least
+//!   [`MIN_BUCKET_BYTES`]), so a buffer serves every request of its usage
+//!   whose size falls in the same bucket. Bind groups and readbacks use the
+//!   requested size ([`PooledBuffer::binding`], [`PooledBuffer::slice`]),
+//!   never the rounded one, so shaders see the same `arrayLength` as before.
+//! - **Uploads**: contents are written with `Queue::write_buffer` into a
+//!   pooled buffer instead of a new buffer mapped at creation.
+//! - **Eviction**: idle buffers are kept up to a high-water mark
+//!   ([`DEFAULT_POOL_HIGH_WATER`]); beyond it the least recently released
+//!   ones are freed.
+//!
+//! A [`PooledBuffer`] returns to its pool when dropped. Buffers dropped
+//! while mapped (a readback stopped by a timeout) are freed instead, as
+//! they cannot be bound again until the map completes.
+//!
+//! Toyota Way: Muda elimination (allocate once, reuse across dispatches)
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use super::submit::{self, PollBudget};
+use crate::metrics::{self, Counter};
+use crate::{Error, Result};
+
+/// Default bytes of idle buffers a [`BufferPool`] keeps (256 MB)
+pub const DEFAULT_POOL_HIGH_WATER: u64 = 256 * 1024 * 1024;
+
+/// Smallest bucket; readback and parameter buffers share it
+pub const MIN_BUCKET_BYTES: u64 = 256;
+
+/// Allocation counts of a [`BufferPool`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BufferPoolStats {
+    /// Requests served by an idle buffer
+    pub hits: u64,
+    /// Requests that allocated a new buffer
+    pub misses: u64,
+    /// Idle buffers freed to stay under the high-water mark
+    pub evictions: u64,
+    /// Bytes held by idle buffers
+    pub idle_bytes: u64,
+}
+
+struct IdleBuffer {
+    usage: wgpu::BufferUsages,
+    buffer: wgpu::Buffer,
+}
+
+#[derive(Default)]
+struct Idle {
+    /// Least recently released first
+    buffers: Vec<IdleBuffer>,
+    bytes: u64,
+}
+
+struct Shared {
+    idle: Mutex<Idle>,
+    high_water: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+/// Device buffers shared by the kernels of one engine
+///
+/// Cheap to clone; clones share the same buffers.
+#[derive(Clone)]
+pub struct BufferPool {
+    shared: Arc<Shared>,
+}
+
+impl Default for BufferPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BufferPool {
+    /// Create an empty pool with [`DEFAULT_POOL_HIGH_WATER`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                idle: Mutex::new(Idle::default()),
+                high_water: AtomicU64::new(DEFAULT_POOL_HIGH_WATER),
+                hits: AtomicU64::new(0),
+                misses: AtomicU64::new(0),
+                evictions: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Keep at most `bytes` of idle buffers (`0` disables reuse), freeing
+    /// the least recently released ones now if over
+    pub fn set_high_water(&self, bytes: u64) {
+        self.shared.high_water.store(bytes, Ordering::Release);
+        self.shared.evict();
+    }
+
+    /// Most bytes of idle buffers kept
+    #[must_use]
+    pub fn high_water(&self) -> u64 {
+        self.shared.high_water.load(Ordering::Acquire)
+    }
+
+    /// Current counters
+    #[must_use]
+    pub fn stats(&self) -> BufferPoolStats {
+        BufferPoolStats {
+            hits: self.shared.hits.load(Ordering::Relaxed),
+            misses: self.shared.misses.load(Ordering::Relaxed),
+            evictions: self.shared.evictions.load(Ordering::Relaxed),
+            idle_bytes: self.shared.idle().bytes,
+        }
+    }
+
+    /// A buffer of at least `size` bytes with exactly `usage`
+    ///
+    /// Contents are unspecified (a reused buffer holds its last contents);
+    /// use [`upload`](Self::upload) for buffers the shader reads before
+    /// writing.
+    pub fn acquire(
+        &self,
+        device: &wgpu::Device,
+        label: &str,
+        size: u64,
+        usage: wgpu::BufferUsages,
+    ) -> PooledBuffer {
+        // Copies and maps work in 4-byte units
+        let size = size.max(4).next_multiple_of(4);
+        let bucket = bucket_size(size, device.limits().max_buffer_size);
+
+        let reused = {
+            let mut idle = self.shared.idle();
+            let index = idle
+                .buffers
+                .iter()
+                .rposition(|entry| entry.usage == usage && entry.buffer.size() == bucket);
+            let reused = index.map(|index| {
+                idle.bytes -= bucket;
+                idle.buffers.remove(index).buffer
+            });
+            drop(idle);
+            reused
+        };
+        let buffer = reused.map_or_else(
+            || {
+                self.shared.misses.fetch_add(1, Ordering::Relaxed);
+                device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some(label),
+                    size: bucket,
+                    usage,
+                    mapped_at_creation: false,
+                })
+            },
+            |buffer| {
+                self.shared.hits.fetch_add(1, Ordering::Relaxed);
+                buffer
+            },
+        );
+
+        PooledBuffer {
+            buffer: Some(buffer),
+            usage,
+            size,
+            mapped: false,
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    /// A buffer holding `contents` (`usage` gains `COPY_DST`)
+    ///
+    /// The write is queued and lands before the next submission on `queue`.
+    pub fn upload(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        label: &str,
+        contents: &[u8],
+        usage: wgpu::BufferUsages,
+    ) -> PooledBuffer {
+        let buffer = self.acquire(
+            device,
+            label,
+            contents.len() as u64,
+            usage | wgpu::BufferUsages::COPY_DST,
+        );
+        metrics::count(Counter::PcieBytes, contents.len());
+        let aligned = contents.len() - contents.len() % 4;
+        if aligned > 0 {
+            queue.write_buffer(buffer.buffer(), 0, &contents[..aligned]);
+        }
+        if aligned < contents.len() {
+            // Pad the ragged tail to a full word
+            let mut tail = [0u8; 4];
+            tail[..contents.len() - aligned].copy_from_slice(&contents[aligned..]);
+            queue.write_buffer(buffer.buffer(), aligned as u64, &tail);
+        }
+        buffer
+    }
+}
+
+impl Shared {
+    fn idle(&self) -> MutexGuard<'_, Idle> {
+        self.idle.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Free the least recently released idle buffers while over the
+    /// high-water mark
+    fn evict(&self) {
+        let high_water = self.high_water.load(Ordering::Acquire);
+        let mut idle = self.idle();
+        let mut evicted = 0;
+        while idle.bytes > high_water && evicted < idle.buffers.len() {
+            idle.bytes -= idle.buffers[evicted].buffer.size();
+            evicted += 1;
+        }
+        idle.buffers.drain(..evicted);
+        drop(idle);
+        self.evictions.fetch_add(evicted as u64, Ordering::Relaxed);
+    }
+
+    fn release(&self, usage: wgpu::BufferUsages, buffer: wgpu::Buffer) {
+        {
+            let mut idle = self.idle();
+            idle.bytes += buffer.size();
+            idle.buffers.push(IdleBuffer { usage, buffer });
+        }
+        self.evict();
+    }
+}
+
+/// A buffer borrowed from a [`BufferPool`], returned to it when dropped
+pub struct PooledBuffer {
+    buffer: Option<wgpu::Buffer>,
+    usage: wgpu::BufferUsages,
+    /// Requested size (the buffer itself may be larger)
+    size: u64,
+    /// Mapped or mapping: not reusable until unmapped
+    mapped: bool,
+    shared: Arc<Shared>,
+}
+
+impl PooledBuffer {
+    /// Underlying wgpu buffer (its size is the bucket size)
+    ///
+    /// # Panics
+    /// Never: the buffer is only taken when dropped
+    #[must_use]
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        self.buffer.as_ref().expect("pooled buffer is present until dropped")
+    }
+
+    /// Requested size in bytes (rounded up to a multiple of 4)
+    #[must_use]
+    pub const fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Binding over the requested size
+    #[must_use]
+    pub fn binding(&self) -> wgpu::BindingResource<'_> {
+        wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+            buffer: self.buffer(),
+            offset: 0,
+            size: wgpu::BufferSize::new(self.size),
+        })
+    }
+
+    /// Slice over the requested size
+    #[must_use]
+    pub fn slice(&self) -> wgpu::BufferSlice<'_> {
+        self.buffer().slice(..self.size)
+    }
+
+    /// Map the requested range for reading, polling within `budget`
+    ///
+    /// The queue must already have been submitted. Read with
+    /// [`get_mapped_range`](wgpu::BufferSlice::get_mapped_range) on
+    /// [`slice`](Self::slice), then [`unmap`](Self::unmap).
+    ///
+    /// # Errors
+    /// Returns error if the range does not fit in host memory, `budget`
+    /// stops the wait or mapping fails
+    pub async fn map_read(&mut self, device: &wgpu::Device, budget: &PollBudget) -> Result<()> {
+        let bytes = usize::try_from(self.size).map_err(|_| {
+            Error::Other(format!("Cannot read back {} bytes on this target", self.size))
+        })?;
+        self.mapped = true;
+        submit::map_read(device, &self.slice(), budget).await?;
+        tracing::debug!(bytes = self.size, "GPU readback");
+        metrics::count(Counter::GpuDispatches, 1);
+        metrics::count(Counter::PcieBytes, bytes);
+        Ok(())
+    }
+
+    /// Unmap after [`map_read`](Self::map_read), making the buffer reusable
+    pub fn unmap(&mut self) {
+        self.buffer().unmap();
+        self.mapped = false;
+    }
+}
+
+impl Drop for PooledBuffer {
+    fn drop(&mut self) {
+        if let Some(buffer) = self.buffer.take() {
+            if !self.mapped {
+                self.shared.release(self.usage, buffer);
+            }
+        }
+    }
+}
+
+/// Bucket holding `size` bytes: the next power of two, at least
+/// [`MIN_BUCKET_BYTES`], at most `max_buffer_size` (but never below `size`)
+#[must_use]
+pub fn bucket_size(size: u64, max_buffer_size: u64) -> u64 {
+    size.max(MIN_BUCKET_BYTES).next_power_of_two().min(max_buffer_size).max(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_size() {
+        assert_eq!(bucket_size(4, 1 << 28), MIN_BUCKET_BYTES);
+        assert_eq!(bucket_size(256, 1 << 28), 256);
+        assert_eq!(bucket_size(257, 1 << 28), 512);
+        assert_eq!(bucket_size(100_000_000, 1 << 28), 1 << 27);
+        // Capped by the device limit, never below the request
+        assert_eq!(bucket_size(200_000_000, 1 << 28), 1 << 28);
+        assert_eq!(bucket_size(200_000_000, 150_000_000), 200_000_000);
+    }
+
+    #[test]
+    fn test_empty_pool_stats() {
+        let pool = BufferPool::new();
+        assert_eq!(pool.stats(), BufferPoolStats::default());
+        pool.set_high_water(0);
+        assert_eq!(pool.high_water(), 0);
+    }
+}
@@ -0,0 +1,135 @@
+//! Cost model calibration on the real device
+//!
+//! Times the two quantities the cost model needs (see
+//! [`backend::calibration`](crate::backend::calibration)):
+//!
+//! - **Bandwidth**: upload a [`CALIBRATION_SAMPLE_LEN`]-element buffer the
+//!   way the kernels do, and wait until a 4-byte readback from it lands
+//! - **Throughput**: a SUM reduction over the same sample, minus the upload
+//!   time just measured, at one FLOP per element
+//!
+//! Each is the median of [`CALIBRATION_ITERATIONS`] runs after one warmup.
+//!
+//! Toyota Way: Genchi Genbutsu (measure on the real device)
+
+use super::autotune::DEFAULT_WORKGROUP_SIZE;
+use super::buffers::BufferPool;
+use super::kernels::{self, ReduceOp};
+use super::pipeline::PipelineCache;
+use super::submit::PollBudget;
+use crate::backend::{Calibration, GigabytesPerSecond, GigaflopsPerSecond};
+use crate::query::stats::Stopwatch;
+use crate::Result;
+use arrow::array::Int32Array;
+use std::time::Duration;
+
+/// Elements in the calibration sample (16 MB of `i32`)
+pub const CALIBRATION_SAMPLE_LEN: usize = 4 * 1024 * 1024;
+
+/// Timed runs per measurement
+pub const CALIBRATION_ITERATIONS: usize = 5;
+
+/// Measure host-to-device bandwidth and reduction throughput of `device`
+///
+/// # Errors
+/// Returns error if GPU execution fails, or if `budget` stops a wait
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+#[allow(clippy::cast_precision_loss)]
+pub async fn measure(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    pipelines: &PipelineCache,
+    buffers: &BufferPool,
+    device_key: &str,
+    budget: &PollBudget,
+) -> Result<Calibration> {
+    let sample = Int32Array::from((0..CALIBRATION_SAMPLE_LEN as i32).collect::<Vec<_>>());
+    let bytes = CALIBRATION_SAMPLE_LEN * std::mem::size_of::<i32>();
+
+    // Run 0 of each loop is the warmup (pipeline compilation, driver caches)
+    let mut uploads = Vec::with_capacity(CALIBRATION_ITERATIONS);
+    for run in 0..=CALIBRATION_ITERATIONS {
+        let start = Stopwatch::start();
+        upload(device, queue, buffers, sample.values(), budget).await?;
+        if run > 0 {
+            uploads.push(start.elapsed());
+        }
+    }
+    let upload_time = median(uploads);
+
+    let mut reductions = Vec::with_capacity(CALIBRATION_ITERATIONS);
+    for run in 0..=CALIBRATION_ITERATIONS {
+        let start = Stopwatch::start();
+        kernels::reduce_i32(
+            device,
+            queue,
+            pipelines,
+            buffers,
+            ReduceOp::Sum,
+            &sample,
+            DEFAULT_WORKGROUP_SIZE,
+            budget,
+        )
+        .await?;
+        if run > 0 {
+            reductions.push(start.elapsed());
+        }
+    }
+    let reduction_time = median(reductions);
+
+    // Launch and readback overhead can swamp the kernel on fast links; count
+    // the whole call then, which only underestimates the GPU
+    let compute_time = match reduction_time.saturating_sub(upload_time) {
+        Duration::ZERO => reduction_time,
+        compute => compute,
+    };
+
+    Ok(Calibration {
+        device: device_key.to_string(),
+        host_to_device: GigabytesPerSecond(bytes as f64 / upload_time.as_secs_f64() / 1e9),
+        gpu_throughput: GigaflopsPerSecond(
+            CALIBRATION_SAMPLE_LEN as f64 / compute_time.as_secs_f64() / 1e9,
+        ),
+    })
+}
+
+/// Upload `values` and wait until the device has them
+///
+/// Goes through the pool like the kernels, so warm runs time the transfer
+/// rather than the allocation.
+async fn upload(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    buffers: &BufferPool,
+    values: &[i32],
+    budget: &PollBudget,
+) -> Result<()> {
+    let buffer = buffers.upload(
+        device,
+        queue,
+        "Calibration Buffer",
+        bytemuck::cast_slice(values),
+        wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+    );
+    let mut staging = buffers.acquire(
+        device,
+        "Calibration Staging Buffer",
+        4,
+        wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+    );
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Calibration Encoder"),
+    });
+    encoder.copy_buffer_to_buffer(buffer.buffer(), 0, staging.buffer(), 0, 4);
+    queue.submit(Some(encoder.finish()));
+
+    staging.map_read(device, budget).await?;
+    staging.unmap();
+    Ok(())
+}
+
+fn median(mut samples: Vec<Duration>) -> Duration {
+    samples.sort_unstable();
+    samples[samples.len() / 2].max(Duration::from_nanos(1))
+}
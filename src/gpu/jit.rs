@@ -9,75 +9,202 @@
 //! - Non-fused: Filter → intermediate buffer → SUM (2 GPU passes, 1 memory write)
 //! - Fused: Filter + SUM in single pass (1 GPU pass, 0 intermediate writes)
 //!
+//! [`FusedGroupBy`] lowers a whole `WHERE` + `GROUP BY` + aggregates plan the
+//! same way: each thread evaluates the predicate on its row, inserts the key
+//! into the GPU hash table and updates every aggregate, so the filtered rows
+//! never exist as a buffer and the table is read back once. Without GROUP BY
+//! the same kernel computes multi-column predicates and aggregates
+//! (`SUM(b) WHERE a > 10 AND c < 5`) into a single slot.
+//!
+//! Compiled kernels live in a [`ShaderCache`] keyed by normalized kernel
+//! signature and bounded by LRU eviction, since every distinct literal is a
+//! distinct kernel. [`JitCompiler::precompile`] compiles a plan's kernel
+//! ahead of time so its first execution skips the shader compiler.
+//!
 //! References:
 //! - Wu et al. (2012): Kernel fusion execution model
 //! - Neumann (2011): JIT compilation for queries
 //! - MonetDB/X100 (2005): Vectorized query execution
 
+use super::autotune::DEFAULT_WORKGROUP_SIZE;
+use super::kernels::GroupByOp;
+use crate::query::binder::{column_name, parse_filter};
+use crate::query::{AggregateFunction, QueryPlan};
+use crate::{Error, Result};
+use arrow::array::{Array, ArrayRef, AsArray, Int32Array, Int64Array, RecordBatch};
+use arrow::datatypes::{DataType, Field, Int32Type, Schema};
+use sqlparser::ast::{BinaryOperator, Expr, UnaryOperator, Value};
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// Kernels kept by a [`JitCompiler`] when no capacity is given
+pub const DEFAULT_KERNEL_CACHE_CAPACITY: usize = 128;
+
+/// Lookup counts of a [`ShaderCache`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KernelCacheStats {
+    /// Compiled kernels held
+    pub entries: usize,
+    /// Most kernels held
+    pub capacity: usize,
+    /// Compilations served from the cache
+    pub hits: u64,
+    /// Compilations that ran the shader compiler
+    pub misses: u64,
+    /// Kernels dropped to stay within capacity
+    pub evictions: u64,
+}
+
+impl KernelCacheStats {
+    /// Fraction of lookups served from the cache (`0.0` before any lookup)
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+struct CachedKernel {
+    module: Arc<wgpu::ShaderModule>,
+    /// Tick of the last lookup (larger is more recent)
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Kernels {
+    modules: HashMap<String, CachedKernel>,
+    tick: u64,
+    /// Keys evicted since the last [`ShaderCache::take_evicted`]
+    evicted: Vec<String>,
+}
 
 /// Shader compilation cache for JIT-compiled kernels
 ///
-/// Caches compiled shaders by query signature to avoid recompilation.
+/// Caches compiled shaders by normalized kernel signature (every input that
+/// shapes the generated source, nothing else) to avoid recompilation, and
+/// evicts the least recently used kernel once `capacity` is reached, so
+/// workloads with many distinct literals cannot grow it without bound.
 /// Thread-safe via Mutex for concurrent query execution.
 pub struct ShaderCache {
-    cache: Mutex<HashMap<String, Arc<wgpu::ShaderModule>>>,
+    kernels: Mutex<Kernels>,
+    capacity: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
 }
 
 impl ShaderCache {
-    /// Create a new shader cache
+    /// Create a shader cache holding [`DEFAULT_KERNEL_CACHE_CAPACITY`] kernels
     #[must_use]
     pub fn new() -> Self {
-        Self { cache: Mutex::new(HashMap::new()) }
+        Self::with_capacity(DEFAULT_KERNEL_CACHE_CAPACITY)
+    }
+
+    /// Create a shader cache holding at most `capacity` kernels (`0` caches
+    /// nothing)
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            kernels: Mutex::new(Kernels::default()),
+            capacity,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
     }
 
     /// Get cached shader or insert new one
     ///
     /// # Arguments
-    /// * `key` - Query signature (e.g., `"filter_gt_1000_sum"`)
+    /// * `key` - Normalized kernel signature (e.g., `"filter_gt_1000_sum"`)
     /// * `device` - GPU device for shader compilation
     /// * `shader_source` - WGSL shader source code
     ///
     /// # Returns
     /// Arc reference to compiled shader module (either cached or newly compiled)
-    ///
-    /// # Panics
-    /// Panics if the cache mutex is poisoned (should never happen in normal operation)
     pub fn get_or_insert(
         &self,
         key: &str,
         device: &wgpu::Device,
         shader_source: &str,
     ) -> Arc<wgpu::ShaderModule> {
-        let mut cache = self
-            .cache
-            .lock()
-            .expect("Shader cache mutex poisoned (should never happen in normal operation)");
-
-        if !cache.contains_key(key) {
-            let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-                label: Some(key),
-                source: wgpu::ShaderSource::Wgsl(shader_source.into()),
-            });
-            cache.insert(key.to_string(), Arc::new(shader));
+        let mut kernels = self.kernels();
+        kernels.tick += 1;
+        let tick = kernels.tick;
+        if let Some(kernel) = kernels.modules.get_mut(key) {
+            kernel.last_used = tick;
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            // Clone the Arc (cheap), not the ShaderModule
+            return Arc::clone(&kernel.module);
         }
+        self.misses.fetch_add(1, Ordering::Relaxed);
 
-        // Clone the Arc (cheap), not the ShaderModule
-        Arc::clone(cache.get(key).expect("Shader must exist in cache after insertion"))
+        let module = Arc::new(device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(key),
+            source: wgpu::ShaderSource::Wgsl(shader_source.into()),
+        }));
+        if self.capacity == 0 {
+            kernels.evicted.push(key.to_string());
+            return module;
+        }
+        if kernels.modules.len() >= self.capacity {
+            let oldest = kernels
+                .modules
+                .iter()
+                .min_by_key(|(_, kernel)| kernel.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                kernels.modules.remove(&oldest);
+                kernels.evicted.push(oldest);
+                self.evictions.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        kernels
+            .modules
+            .insert(key.to_string(), CachedKernel { module: Arc::clone(&module), last_used: tick });
+        module
+    }
+
+    /// Whether the kernel with signature `key` is compiled and cached
+    #[must_use]
+    pub fn contains(&self, key: &str) -> bool {
+        self.kernels().modules.contains_key(key)
     }
 
     /// Get cache statistics
-    ///
-    /// # Panics
-    /// Panics if the cache mutex is poisoned (should never happen in normal operation)
     #[must_use]
-    pub fn stats(&self) -> (usize, usize) {
-        let cache = self
-            .cache
-            .lock()
-            .expect("Shader cache mutex poisoned (should never happen in normal operation)");
-        (cache.len(), cache.capacity())
+    pub fn stats(&self) -> KernelCacheStats {
+        KernelCacheStats {
+            entries: self.kernels().modules.len(),
+            capacity: self.capacity,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Drop every cached kernel (counters are kept)
+    pub fn clear(&self) {
+        let mut kernels = self.kernels();
+        let keys: Vec<String> = kernels.modules.drain().map(|(key, _)| key).collect();
+        kernels.evicted.extend(keys);
+    }
+
+    /// Keys of the kernels evicted (or cleared) since the last call, so
+    /// state derived from them, such as their pipelines, can be dropped too
+    pub fn take_evicted(&self) -> Vec<String> {
+        std::mem::take(&mut self.kernels().evicted)
+    }
+
+    fn kernels(&self) -> MutexGuard<'_, Kernels> {
+        self.kernels.lock().unwrap_or_else(PoisonError::into_inner)
     }
 }
 
@@ -102,6 +229,12 @@ impl JitCompiler {
         Self { cache: ShaderCache::new() }
     }
 
+    /// Create a JIT compiler keeping at most `capacity` compiled kernels
+    #[must_use]
+    pub fn with_cache_capacity(capacity: usize) -> Self {
+        Self { cache: ShaderCache::with_capacity(capacity) }
+    }
+
     /// Generate fused filter+sum kernel
     ///
     /// Fuses WHERE clause with SUM aggregation in single GPU pass.
@@ -120,15 +253,7 @@ impl JitCompiler {
     /// ```
     #[must_use]
     pub fn generate_fused_filter_sum(&self, filter_threshold: i32, filter_op: &str) -> String {
-        // Convert operator to WGSL
-        let wgsl_op = match filter_op {
-            "lt" => "<",
-            "eq" => "==",
-            "gte" => ">=",
-            "lte" => "<=",
-            "ne" => "!=",
-            _ => ">", // Default to greater-than (handles "gt" and unknown ops)
-        };
+        let (_, wgsl_op) = filter_op_wgsl(filter_op);
 
         format!(
             r"
@@ -192,7 +317,7 @@ fn fused_filter_sum(@builtin(global_invocation_id) global_id: vec3<u32>,
         filter_op: &str,
     ) -> Arc<wgpu::ShaderModule> {
         // Generate cache key from query signature
-        let cache_key = format!("filter_{filter_op}_{filter_threshold}_sum");
+        let cache_key = fused_filter_sum_key(filter_threshold, filter_op, DEFAULT_WORKGROUP_SIZE);
 
         // Generate WGSL source
         let shader_source = self.generate_fused_filter_sum(filter_threshold, filter_op);
@@ -201,11 +326,86 @@ fn fused_filter_sum(@builtin(global_invocation_id) global_id: vec3<u32>,
         self.cache.get_or_insert(&cache_key, device, &shader_source)
     }
 
-    /// Get cache statistics (size, capacity)
+    /// Compile and cache fused filter+sum kernel for a tuned workgroup size
+    ///
+    /// # Arguments
+    /// * `device` - GPU device for compilation
+    /// * `filter_threshold` - Filter threshold value
+    /// * `filter_op` - Filter operator
+    /// * `workgroup_size` - Threads per workgroup (power of two)
+    ///
+    /// # Returns
+    /// Arc reference to compiled shader module (cached per size)
+    pub fn compile_fused_filter_sum_sized(
+        &self,
+        device: &wgpu::Device,
+        filter_threshold: i32,
+        filter_op: &str,
+        workgroup_size: u32,
+    ) -> Arc<wgpu::ShaderModule> {
+        if workgroup_size == DEFAULT_WORKGROUP_SIZE {
+            return self.compile_fused_filter_sum(device, filter_threshold, filter_op);
+        }
+
+        let cache_key = fused_filter_sum_key(filter_threshold, filter_op, workgroup_size);
+        let shader_source = super::autotune::specialize_workgroup_size(
+            &self.generate_fused_filter_sum(filter_threshold, filter_op),
+            workgroup_size,
+        );
+        self.cache.get_or_insert(&cache_key, device, &shader_source)
+    }
+
+    /// Compile and cache the fused kernel of a filter + GROUP BY plan
+    ///
+    /// # Arguments
+    /// * `device` - GPU device for compilation
+    /// * `fused` - Plan lowered by [`FusedGroupBy::from_plan`]
+    /// * `workgroup_size` - Threads per workgroup (power of two)
+    ///
+    /// # Returns
+    /// Arc reference to compiled shader module (cached per plan and size)
+    pub fn compile_fused_group_by(
+        &self,
+        device: &wgpu::Device,
+        fused: &FusedGroupBy,
+        workgroup_size: u32,
+    ) -> Arc<wgpu::ShaderModule> {
+        let cache_key = fused.cache_key(workgroup_size);
+        let shader_source = fused.generate(workgroup_size);
+        self.cache.get_or_insert(&cache_key, device, &shader_source)
+    }
+
+    /// Compile the fused kernel of `plan` ahead of its first execution
+    ///
+    /// Lowers `plan` against `schema` (the schema of the batches it will
+    /// run on) and caches the kernel under the same signature
+    /// [`compile_fused_group_by`](Self::compile_fused_group_by) looks up, so
+    /// the first query skips the shader compiler.
+    ///
+    /// # Errors
+    /// Returns error if the plan cannot be fused (see [`FusedGroupBy::from_plan`])
+    pub fn precompile(
+        &self,
+        device: &wgpu::Device,
+        plan: &QueryPlan,
+        schema: &Schema,
+        workgroup_size: u32,
+    ) -> Result<(FusedGroupBy, Arc<wgpu::ShaderModule>)> {
+        let fused = FusedGroupBy::from_plan(plan, schema)?;
+        let shader = self.compile_fused_group_by(device, &fused, workgroup_size);
+        Ok((fused, shader))
+    }
+
+    /// Kernels cached, and how often compilations reused one
     #[must_use]
-    pub fn cache_stats(&self) -> (usize, usize) {
+    pub fn cache_stats(&self) -> KernelCacheStats {
         self.cache.stats()
     }
+
+    /// Signatures of the kernels evicted since the last call
+    pub(crate) fn take_evicted(&self) -> Vec<String> {
+        self.cache.take_evicted()
+    }
 }
 
 impl Default for JitCompiler {
@@ -214,6 +414,506 @@ impl Default for JitCompiler {
     }
 }
 
+/// Canonical name and WGSL operator of filter operator `filter_op`
+///
+/// Unknown operators compile as greater-than, so they share its name and
+/// with it the cached kernel.
+fn filter_op_wgsl(filter_op: &str) -> (&'static str, &'static str) {
+    match filter_op {
+        "lt" => ("lt", "<"),
+        "eq" => ("eq", "=="),
+        "gte" => ("gte", ">="),
+        "lte" => ("lte", "<="),
+        "ne" => ("ne", "!="),
+        _ => ("gt", ">"), // Default to greater-than (handles "gt" and unknown ops)
+    }
+}
+
+/// Shader cache key of the fused filter+sum kernel for `workgroup_size`
+///
+/// Normalized: operators that generate the same kernel share a key.
+pub(crate) fn fused_filter_sum_key(
+    filter_threshold: i32,
+    filter_op: &str,
+    workgroup_size: u32,
+) -> String {
+    let (filter_op, _) = filter_op_wgsl(filter_op);
+    if workgroup_size == DEFAULT_WORKGROUP_SIZE {
+        format!("filter_{filter_op}_{filter_threshold}_sum")
+    } else {
+        format!("filter_{filter_op}_{filter_threshold}_sum_wg{workgroup_size}")
+    }
+}
+
+/// WGSL shader template for [`FusedGroupBy`]
+///
+/// Same hash table as the GROUP BY kernel in [`kernels`](super::kernels)
+/// (open addressing, slots claimed by compare-exchange, one reserved slot
+/// for the empty-slot marker key), with the predicate and the aggregate
+/// updates spliced in. Without GROUP BY the key is the marker itself, so
+/// every row lands in the reserved slot. Input columns are concatenated in
+/// one buffer;
+/// aggregate `j` owns the slot value words `2j` (low) and `2j + 1` (high).
+/// SUM accumulates an exact i64 as two 32-bit atomics, carrying into the
+/// high word.
+const FUSED_GROUP_BY_SHADER: &str = r"
+@group(0) @binding(0) var<storage, read> columns: array<i32>;
+@group(0) @binding(1) var<storage, read_write> slot_keys: array<atomic<i32>>;
+@group(0) @binding(2) var<storage, read_write> slot_counts: array<atomic<u32>>;
+@group(0) @binding(3) var<storage, read_write> slot_values: array<atomic<i32>>;
+@group(0) @binding(4) var<storage, read_write> overflow: array<atomic<u32>>;
+
+const EMPTY_KEY: i32 = -2147483648; // i32::MIN
+const NUM_COLUMNS: u32 = @NUM_COLUMNS@u;
+
+// Murmur3 finalizer
+fn hash_key(key: i32) -> u32 {
+    var h = bitcast<u32>(key);
+    h = h ^ (h >> 16u);
+    h = h * 0x85ebca6bu;
+    h = h ^ (h >> 13u);
+    h = h * 0xc2b2ae35u;
+    h = h ^ (h >> 16u);
+    return h;
+}
+
+fn column(index: u32, row: u32) -> i32 {
+    return columns[index * (arrayLength(&columns) / NUM_COLUMNS) + row];
+}
+
+// Add a sign-extended i32 to the i64 held in words (lo, hi)
+fn add_i64(lo: u32, hi: u32, value: i32) {
+    let old = bitcast<u32>(atomicAdd(&slot_values[lo], value));
+    var carry = select(0u, 1u, old + bitcast<u32>(value) < old);
+    if (value < 0) {
+        carry = carry + 0xffffffffu;
+    }
+    if (carry != 0u) {
+        atomicAdd(&slot_values[hi], bitcast<i32>(carry));
+    }
+}
+
+@compute @workgroup_size(256)
+fn fused_group_by(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let row = global_id.x;
+    if (row >= arrayLength(&columns) / NUM_COLUMNS) {
+        return;
+    }
+    // Fused filter: rows that fail never touch the table
+    if (!(@PREDICATE@)) {
+        return;
+    }
+
+    let key = @KEY@;
+    let capacity = arrayLength(&slot_keys) - 1u;
+    let stride = capacity + 1u;
+
+    var slot = capacity;
+    if (key != EMPTY_KEY) {
+        slot = hash_key(key) & (capacity - 1u);
+        var probes = 0u;
+        loop {
+            let claim = atomicCompareExchangeWeak(&slot_keys[slot], EMPTY_KEY, key);
+            if (claim.exchanged || claim.old_value == key) {
+                break;
+            }
+            // Spurious failure: the slot is still empty, try it again
+            if (claim.old_value == EMPTY_KEY) {
+                continue;
+            }
+            probes = probes + 1u;
+            if (probes == capacity) {
+                atomicStore(&overflow[0], 1u);
+                return;
+            }
+            slot = (slot + 1u) & (capacity - 1u);
+        }
+    }
+
+    atomicAdd(&slot_counts[slot], 1u);
+@AGGREGATES@}
+";
+
+/// One aggregate of a [`FusedGroupBy`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FusedAggregate {
+    op: GroupByOp,
+    /// Input column (index into [`FusedGroupBy::columns`]); unused by COUNT
+    column: usize,
+    /// Output column name
+    name: String,
+}
+
+/// One occupied slot read back from a fused kernel: key, row count and the
+/// `(low, high)` words of each aggregate
+pub(crate) type FusedGroup = (i32, u32, Vec<(u32, u32)>);
+
+/// A `WHERE` + `GROUP BY` + aggregate plan lowered to one fused GPU kernel
+///
+/// Supported plans read one table (no JOINs or CTEs), group by at most one
+/// `Int32` column and compute `SUM`, `MIN`, `MAX` and `COUNT` over `Int32`
+/// columns. The predicate may combine comparisons between `Int32` columns
+/// and integer literals with `AND`, `OR`, `NOT`, `BETWEEN` and `IN`, so
+/// `SUM(b) WHERE a > 10 AND c < 5` filters on two columns and sums a third
+/// in one pass. Columns the kernel reads must not contain NULLs.
+///
+/// Results match the SIMD path's types (`SUM` and `COUNT` as `Int64`,
+/// `MIN`/`MAX` as `Int32`), with groups sorted by key. Without GROUP BY the
+/// result is one row; when no row passes the filter, COUNT and SUM are 0
+/// and MIN and MAX are NULL.
+///
+/// # Example
+/// ```
+/// use arrow::datatypes::{DataType, Field, Schema};
+/// use trueno_db::gpu::jit::FusedGroupBy;
+/// use trueno_db::query::QueryEngine;
+///
+/// let schema = Schema::new(vec![
+///     Field::new("store", DataType::Int32, false),
+///     Field::new("qty", DataType::Int32, false),
+/// ]);
+/// let plan = QueryEngine::new()
+///     .parse("SELECT store, SUM(qty) FROM sales WHERE qty > 10 GROUP BY store")
+///     .unwrap();
+/// let fused = FusedGroupBy::from_plan(&plan, &schema).unwrap();
+/// assert!(fused.generate(256).contains("(column(1u, row) > 10)"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FusedGroupBy {
+    /// Input columns, in the order they are uploaded
+    columns: Vec<String>,
+    /// GROUP BY key (index into `columns`), `None` for one group of all rows
+    key: Option<usize>,
+    /// WGSL boolean expression of the WHERE clause (`true` without one)
+    predicate: String,
+    aggregates: Vec<FusedAggregate>,
+}
+
+impl FusedGroupBy {
+    /// Lower `plan` over a table with `schema`
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidInput`] if the plan uses anything the fused
+    /// kernel does not support (see the type documentation)
+    pub fn from_plan(plan: &QueryPlan, schema: &Schema) -> Result<Self> {
+        if plan.explain {
+            return Err(unsupported("EXPLAIN (nothing to run)"));
+        }
+        if !plan.joins.is_empty() || !plan.ctes.is_empty() || !plan.subqueries.is_empty() {
+            return Err(unsupported("JOINs, CTEs and subqueries"));
+        }
+        if plan.aggregations.is_empty() {
+            return Err(unsupported("plans without aggregates"));
+        }
+
+        let mut fused = Self {
+            columns: Vec::new(),
+            key: None,
+            predicate: "true".to_string(),
+            aggregates: Vec::new(),
+        };
+        match plan.group_by.as_slice() {
+            [] => {}
+            [key_name] => fused.key = Some(fused.column(schema, key_name)?),
+            _ => return Err(unsupported("GROUP BY on more than one column")),
+        }
+        if let Some(filter) = &plan.filter {
+            fused.predicate = fused.lower(schema, &parse_filter(filter)?)?;
+        }
+        for (func, col_name, alias) in &plan.aggregations {
+            let op = match func {
+                AggregateFunction::Sum => GroupByOp::Sum,
+                AggregateFunction::Min => GroupByOp::Min,
+                AggregateFunction::Max => GroupByOp::Max,
+                AggregateFunction::Count => GroupByOp::Count,
+                other => return Err(unsupported(&format!("aggregate {}", other.sql_name()))),
+            };
+            let column = match (col_name.as_str(), fused.key) {
+                ("*", Some(key)) => key,
+                // COUNT reads no column; checked below that one is uploaded
+                ("*", None) => 0,
+                _ => fused.column(schema, col_name)?,
+            };
+            let name = alias.clone().unwrap_or_else(|| col_name.clone());
+            fused.aggregates.push(FusedAggregate { op, column, name });
+        }
+        if fused.columns.is_empty() {
+            // The row count comes from the column lengths
+            return Err(unsupported("plans that read no column"));
+        }
+        Ok(fused)
+    }
+
+    /// Whether the plan has a GROUP BY key (otherwise the result is one row)
+    #[must_use]
+    pub const fn is_grouped(&self) -> bool {
+        self.key.is_some()
+    }
+
+    /// Names of the input columns, in upload order
+    #[must_use]
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// Index of `name` in `columns`, added on first use (must be `Int32`)
+    fn column(&mut self, schema: &Schema, name: &str) -> Result<usize> {
+        let field = schema
+            .field_with_name(name)
+            .map_err(|_| Error::InvalidInput(format!("Column not found: {name}")))?;
+        if field.data_type() != &DataType::Int32 {
+            return Err(unsupported(&format!(
+                "column {name} of type {:?} (Int32 only)",
+                field.data_type()
+            )));
+        }
+        if let Some(index) = self.columns.iter().position(|column| column == name) {
+            return Ok(index);
+        }
+        self.columns.push(name.to_string());
+        Ok(self.columns.len() - 1)
+    }
+
+    /// WGSL for a WHERE clause expression
+    fn lower(&mut self, schema: &Schema, expr: &Expr) -> Result<String> {
+        Ok(match expr {
+            Expr::Nested(inner) => self.lower(schema, inner)?,
+            Expr::Value(Value::Boolean(value)) => value.to_string(),
+            Expr::BinaryOp { left, op: BinaryOperator::And, right } => {
+                format!("({} && {})", self.lower(schema, left)?, self.lower(schema, right)?)
+            }
+            Expr::BinaryOp { left, op: BinaryOperator::Or, right } => {
+                format!("({} || {})", self.lower(schema, left)?, self.lower(schema, right)?)
+            }
+            Expr::BinaryOp { left, op, right } => {
+                let op = match op {
+                    BinaryOperator::Eq => "==",
+                    BinaryOperator::NotEq => "!=",
+                    BinaryOperator::Lt => "<",
+                    BinaryOperator::LtEq => "<=",
+                    BinaryOperator::Gt => ">",
+                    BinaryOperator::GtEq => ">=",
+                    _ => return Err(unsupported(&format!("filter operator {op}"))),
+                };
+                format!("({} {op} {})", self.operand(schema, left)?, self.operand(schema, right)?)
+            }
+            Expr::UnaryOp { op: UnaryOperator::Not, expr } => {
+                format!("!{}", self.lower(schema, expr)?)
+            }
+            Expr::Between { expr, negated, low, high } => {
+                let value = self.operand(schema, expr)?;
+                let within = format!(
+                    "({value} >= {} && {value} <= {})",
+                    self.operand(schema, low)?,
+                    self.operand(schema, high)?
+                );
+                if *negated {
+                    format!("!{within}")
+                } else {
+                    within
+                }
+            }
+            Expr::InList { expr, list, negated } => {
+                let value = self.operand(schema, expr)?;
+                let mut any = "(false".to_string();
+                for item in list {
+                    let item = self.operand(schema, item)?;
+                    let _ = write!(any, " || {value} == {item}");
+                }
+                any.push(')');
+                if *negated {
+                    format!("!{any}")
+                } else {
+                    any
+                }
+            }
+            _ => return Err(unsupported(&format!("filter {expr}"))),
+        })
+    }
+
+    /// WGSL for a comparison operand: an `Int32` column or an i32 literal
+    fn operand(&mut self, schema: &Schema, expr: &Expr) -> Result<String> {
+        match expr {
+            Expr::Nested(inner) => self.operand(schema, inner),
+            Expr::Identifier(_) | Expr::CompoundIdentifier(_) => {
+                Ok(format!("column({}u, row)", self.column(schema, &column_name(expr))?))
+            }
+            _ => Ok(integer_literal(expr)?.to_string()),
+        }
+    }
+
+    /// WGSL source specialized for `workgroup_size` (a power of two)
+    #[must_use]
+    pub fn generate(&self, workgroup_size: u32) -> String {
+        let mut updates = String::new();
+        for (j, aggregate) in self.aggregates.iter().enumerate() {
+            let (value, lo, hi) = (format!("column({}u, row)", aggregate.column), 2 * j, 2 * j + 1);
+            let update = match aggregate.op {
+                GroupByOp::Sum => {
+                    format!("add_i64({lo}u * stride + slot, {hi}u * stride + slot, {value})")
+                }
+                GroupByOp::Min => {
+                    format!("atomicMin(&slot_values[{lo}u * stride + slot], {value})")
+                }
+                GroupByOp::Max => {
+                    format!("atomicMax(&slot_values[{lo}u * stride + slot], {value})")
+                }
+                // Read back from the slot counts
+                GroupByOp::Count => continue,
+            };
+            let _ = writeln!(updates, "    {update};");
+        }
+        let key =
+            self.key.map_or_else(|| "EMPTY_KEY".to_string(), |key| format!("column({key}u, row)"));
+        let source = FUSED_GROUP_BY_SHADER
+            .replace("@NUM_COLUMNS@", &self.columns.len().to_string())
+            .replace("@KEY@", &key)
+            .replace("@PREDICATE@", &self.predicate)
+            .replace("@AGGREGATES@", &updates);
+        super::autotune::specialize_workgroup_size(&source, workgroup_size)
+    }
+
+    /// Shader cache key: every input that shapes the generated source
+    fn signature(&self) -> String {
+        let aggregates: Vec<String> = self
+            .aggregates
+            .iter()
+            .map(|aggregate| format!("{:?}({})", aggregate.op, aggregate.column))
+            .collect();
+        format!(
+            "fused_group_by_{}cols_key{}_{}_where{}",
+            self.columns.len(),
+            self.key.map_or_else(|| "none".to_string(), |key| key.to_string()),
+            aggregates.join(","),
+            self.predicate
+        )
+    }
+
+    /// Shader cache key of the kernel generated for `workgroup_size`
+    pub(crate) fn cache_key(&self, workgroup_size: u32) -> String {
+        format!("{}_wg{workgroup_size}", self.signature())
+    }
+
+    /// Number of input columns uploaded per row
+    pub(crate) fn num_columns(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Number of aggregates (each owns two value words per slot)
+    pub(crate) fn num_aggregates(&self) -> usize {
+        self.aggregates.len()
+    }
+
+    /// Initial value words of each aggregate (identity in the low word)
+    pub(crate) fn identities(&self) -> Vec<i32> {
+        self.aggregates
+            .iter()
+            .map(|aggregate| match aggregate.op {
+                GroupByOp::Min => i32::MAX,
+                GroupByOp::Max => i32::MIN,
+                GroupByOp::Sum | GroupByOp::Count => 0,
+            })
+            .collect()
+    }
+
+    /// Input columns of `batch`, in upload order
+    ///
+    /// # Errors
+    /// Returns error if a column is missing, not `Int32`, or has NULLs
+    pub(crate) fn inputs(&self, batch: &RecordBatch) -> Result<Vec<Int32Array>> {
+        self.columns
+            .iter()
+            .map(|name| {
+                let column = batch
+                    .column_by_name(name)
+                    .ok_or_else(|| Error::InvalidInput(format!("Column not found: {name}")))?;
+                let values = column.as_primitive_opt::<Int32Type>().ok_or_else(|| {
+                    unsupported(&format!("column {name} of type {:?}", column.data_type()))
+                })?;
+                if values.null_count() > 0 {
+                    return Err(Error::InvalidInput(format!(
+                        "GPU fused GROUP BY does not support NULLs (column {name})"
+                    )));
+                }
+                Ok(values.clone())
+            })
+            .collect()
+    }
+
+    /// Result batch from the occupied slots: `(key, count, value words)` per
+    /// group, in key order
+    ///
+    /// Without GROUP BY there is no key column and exactly one row: the
+    /// reserved slot's, or, when no row was aggregated, 0 for COUNT and SUM
+    /// and NULL for MIN and MAX (as on the CPU path).
+    ///
+    /// # Errors
+    /// Returns error if the batch cannot be assembled
+    pub(crate) fn output(&self, groups: &[FusedGroup]) -> Result<RecordBatch> {
+        let Some(key) = self.key else {
+            // No row passed: an empty slot, whose count of 0 nulls MIN/MAX
+            let empty = [(i32::MIN, 0, vec![(0, 0); self.aggregates.len()])];
+            let groups = if groups.is_empty() { &empty[..] } else { groups };
+            return self.output_columns(groups, Vec::new(), Vec::new());
+        };
+        let fields = vec![Field::new(&self.columns[key], DataType::Int32, false)];
+        let columns: Vec<ArrayRef> =
+            vec![Arc::new(Int32Array::from_iter_values(groups.iter().map(|group| group.0)))];
+        self.output_columns(groups, fields, columns)
+    }
+
+    /// `fields` and `columns` followed by one column per aggregate
+    #[allow(clippy::cast_possible_wrap)]
+    fn output_columns(
+        &self,
+        groups: &[FusedGroup],
+        mut fields: Vec<Field>,
+        mut columns: Vec<ArrayRef>,
+    ) -> Result<RecordBatch> {
+        for (j, aggregate) in self.aggregates.iter().enumerate() {
+            let column: ArrayRef = match aggregate.op {
+                GroupByOp::Count => Arc::new(Int64Array::from_iter_values(
+                    groups.iter().map(|(_, count, _)| i64::from(*count)),
+                )),
+                GroupByOp::Sum => {
+                    Arc::new(Int64Array::from_iter_values(groups.iter().map(|(_, _, words)| {
+                        ((u64::from(words[j].1) << 32) | u64::from(words[j].0)) as i64
+                    })))
+                }
+                GroupByOp::Min | GroupByOp::Max => Arc::new(
+                    groups
+                        .iter()
+                        .map(|(_, count, words)| (*count > 0).then_some(words[j].0 as i32))
+                        .collect::<Int32Array>(),
+                ),
+            };
+            let nullable = column.null_count() > 0;
+            fields.push(Field::new(&aggregate.name, column.data_type().clone(), nullable));
+            columns.push(column);
+        }
+        RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)
+            .map_err(|e| Error::StorageError(format!("Failed to create result batch: {e}")))
+    }
+}
+
+/// Value of an integer literal operand (`42`, `-7`)
+fn integer_literal(expr: &Expr) -> Result<i32> {
+    let text = match expr {
+        Expr::Value(Value::Number(number, _)) => number.clone(),
+        Expr::UnaryOp { op: UnaryOperator::Minus, expr } => match expr.as_ref() {
+            Expr::Value(Value::Number(number, _)) => format!("-{number}"),
+            _ => return Err(unsupported(&format!("filter operand {expr}"))),
+        },
+        Expr::UnaryOp { op: UnaryOperator::Plus, expr } => return integer_literal(expr),
+        _ => return Err(unsupported(&format!("filter operand {expr}"))),
+    };
+    text.parse().map_err(|_| unsupported(&format!("literal {text} (i32 only)")))
+}
+
+fn unsupported(what: &str) -> Error {
+    Error::InvalidInput(format!("Not supported by the fused GPU GROUP BY kernel: {what}"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -221,15 +921,13 @@ mod tests {
     #[test]
     fn test_shader_cache_new() {
         let cache = ShaderCache::new();
-        let (size, _capacity) = cache.stats();
-        assert_eq!(size, 0);
+        assert_eq!(cache.stats().entries, 0);
     }
 
     #[test]
     fn test_jit_compiler_new() {
         let compiler = JitCompiler::new();
-        let (size, _capacity) = compiler.cache_stats();
-        assert_eq!(size, 0);
+        assert_eq!(compiler.cache_stats().entries, 0);
     }
 
     #[test]
@@ -287,15 +985,13 @@ mod tests {
     #[test]
     fn test_shader_cache_default() {
         let cache = ShaderCache::default();
-        let (size, _capacity) = cache.stats();
-        assert_eq!(size, 0);
+        assert_eq!(cache.stats().entries, 0);
     }
 
     #[test]
     fn test_jit_compiler_default() {
         let compiler = JitCompiler::default();
-        let (size, _capacity) = compiler.cache_stats();
-        assert_eq!(size, 0);
+        assert_eq!(compiler.cache_stats().entries, 0);
     }
 
     #[test]
@@ -312,6 +1008,19 @@ mod tests {
         assert_ne!(shader1, shader3);
     }
 
+    #[test]
+    fn test_cache_key_normalization() {
+        assert_eq!(fused_filter_sum_key(100, "unknown", 256), fused_filter_sum_key(100, "gt", 256));
+        assert_ne!(fused_filter_sum_key(100, "gt", 256), fused_filter_sum_key(100, "gte", 256));
+        assert_ne!(fused_filter_sum_key(100, "gt", 256), fused_filter_sum_key(100, "gt", 128));
+
+        let stats = ShaderCache::with_capacity(4).stats();
+        assert_eq!(stats, KernelCacheStats { capacity: 4, ..KernelCacheStats::default() });
+        assert!(stats.hit_rate().abs() < f64::EPSILON);
+        let stats = KernelCacheStats { hits: 3, misses: 1, ..stats };
+        assert!((stats.hit_rate() - 0.75).abs() < f64::EPSILON);
+    }
+
     #[test]
     fn test_wgsl_syntax_valid() {
         let compiler = JitCompiler::new();
@@ -349,4 +1058,132 @@ mod tests {
         // Verify Toyota Way: Muda elimination comment exists
         assert!(shader.contains("Eliminates intermediate buffer write"));
     }
+
+    fn sales_schema() -> Schema {
+        Schema::new(vec![
+            Field::new("store", DataType::Int32, false),
+            Field::new("qty", DataType::Int32, false),
+            Field::new("price", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, false),
+        ])
+    }
+
+    fn lower(sql: &str) -> Result<FusedGroupBy> {
+        let plan = crate::query::QueryEngine::new().parse(sql)?;
+        FusedGroupBy::from_plan(&plan, &sales_schema())
+    }
+
+    #[test]
+    fn test_fused_group_by_lowers_plan() {
+        let fused = lower(
+            "SELECT store, SUM(price), MIN(qty), COUNT(*) FROM sales \
+             WHERE qty BETWEEN 1 AND 10 AND NOT price IN (-5, 7) GROUP BY store",
+        )
+        .unwrap();
+        assert_eq!(fused.columns(), ["store", "qty", "price"]);
+
+        let shader = fused.generate(256);
+        assert!(shader.contains("const NUM_COLUMNS: u32 = 3u;"));
+        assert!(shader.contains("let key = column(0u, row);"));
+        assert!(shader.contains("(column(1u, row) >= 1 && column(1u, row) <= 10)"));
+        assert!(shader.contains("!(false || column(2u, row) == -5 || column(2u, row) == 7)"));
+        assert!(
+            shader.contains("add_i64(0u * stride + slot, 1u * stride + slot, column(2u, row));")
+        );
+        assert!(shader.contains("atomicMin(&slot_values[2u * stride + slot], column(1u, row));"));
+        assert!(!shader.contains("@"), "unfilled placeholder");
+        assert_eq!(fused.identities(), vec![0, i32::MAX, 0]);
+
+        let resized = fused.generate(64);
+        assert!(resized.contains("@workgroup_size(64)"));
+        assert_ne!(
+            fused.signature(),
+            lower("SELECT store, SUM(price) FROM sales GROUP BY store").unwrap().signature()
+        );
+    }
+
+    #[test]
+    fn test_fused_group_by_without_filter() {
+        let fused = lower("SELECT store, MAX(qty) AS top FROM sales GROUP BY store").unwrap();
+        assert!(fused.generate(256).contains("if (!(true))"));
+
+        let output = fused.output(&[(1, 2, vec![(9, 0)]), (4, 1, vec![(u32::MAX, 0)])]).unwrap();
+        assert_eq!(output.schema().field(1).name(), "top");
+        let top = output.column(1).as_primitive::<Int32Type>();
+        assert_eq!(top.values(), &[9, -1]);
+    }
+
+    #[test]
+    fn test_fused_group_by_output_sums_carry() {
+        let fused = lower("SELECT store, SUM(qty), COUNT(qty) FROM sales GROUP BY store").unwrap();
+        // -1 as (lo, hi) words, and 2^32 + 5
+        let output = fused
+            .output(&[(1, 3, vec![(u32::MAX, u32::MAX), (0, 0)]), (2, 1, vec![(5, 1), (0, 0)])])
+            .unwrap();
+        let sums = output.column(1).as_primitive::<arrow::datatypes::Int64Type>();
+        assert_eq!(sums.values(), &[-1, (1 << 32) + 5]);
+        let counts = output.column(2).as_primitive::<arrow::datatypes::Int64Type>();
+        assert_eq!(counts.values(), &[3, 1]);
+        assert_eq!(fused.output(&[]).unwrap().num_rows(), 0);
+    }
+
+    #[test]
+    fn test_fused_group_by_without_group_by() {
+        let fused = lower(
+            "SELECT SUM(price), COUNT(*), MAX(price) AS top FROM sales \
+             WHERE store > 10 AND qty < 5",
+        )
+        .unwrap();
+        assert!(!fused.is_grouped());
+        assert_eq!(fused.columns(), ["store", "qty", "price"]);
+
+        let shader = fused.generate(256);
+        assert!(shader.contains("((column(0u, row) > 10) && (column(1u, row) < 5))"));
+        assert!(shader.contains("let key = EMPTY_KEY;"));
+        assert!(
+            shader.contains("add_i64(0u * stride + slot, 1u * stride + slot, column(2u, row));")
+        );
+        assert_ne!(
+            fused.signature(),
+            lower(
+                "SELECT store, SUM(price), COUNT(*), MAX(price) AS top FROM sales \
+                   WHERE store > 10 AND qty < 5 GROUP BY store"
+            )
+            .unwrap()
+            .signature()
+        );
+
+        // One row without a key column; when no row passed the filter COUNT
+        // and SUM are 0 and MAX is NULL
+        let output = fused.output(&[(i32::MIN, 2, vec![(12, 0), (0, 0), (7, 0)])]).unwrap();
+        assert_eq!(output.num_columns(), 3);
+        assert_eq!(output.schema().field(2).name(), "top");
+        let sums = output.column(0).as_primitive::<arrow::datatypes::Int64Type>();
+        assert_eq!(sums.values(), &[12]);
+        let counts = output.column(1).as_primitive::<arrow::datatypes::Int64Type>();
+        assert_eq!(counts.values(), &[2]);
+        let empty = fused.output(&[]).unwrap();
+        assert_eq!(empty.num_rows(), 1);
+        assert_eq!(empty.column(0).as_primitive::<arrow::datatypes::Int64Type>().values(), &[0]);
+        assert_eq!(empty.column(1).as_primitive::<arrow::datatypes::Int64Type>().values(), &[0]);
+        assert!(empty.column(2).is_null(0));
+        assert!(empty.schema().field(2).is_nullable());
+        assert!(!output.schema().field(2).is_nullable());
+    }
+
+    #[test]
+    fn test_fused_group_by_rejects_unsupported_plans() {
+        for sql in [
+            "SELECT store, AVG(qty) FROM sales GROUP BY store",
+            "SELECT COUNT(*) FROM sales",
+            "SELECT store, qty, SUM(price) FROM sales GROUP BY store, qty",
+            "SELECT name, SUM(qty) FROM sales GROUP BY name",
+            "SELECT store, SUM(qty) FROM sales WHERE name = 'a' GROUP BY store",
+            "SELECT store, SUM(qty) FROM sales WHERE qty > 2.5 GROUP BY store",
+            "SELECT store, SUM(qty) FROM sales WHERE qty IS NULL GROUP BY store",
+            "SELECT store, SUM(qty) FROM sales WHERE qty > 5000000000 GROUP BY store",
+        ] {
+            assert!(matches!(lower(sql), Err(Error::InvalidInput(_))), "{sql}");
+        }
+    }
 }
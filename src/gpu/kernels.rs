@@ -6,14 +6,41 @@
 //! 3. Global reduction of workgroup results
 //!
 //! Performance: O(N/P + log P) where P = num threads
+//!
+//! Float SUM has no atomics to lean on, so [`reduce_sum_f32`] and
+//! [`reduce_sum_f64`] stop after step 2 and add the per-workgroup partials on
+//! the host in f64. f64 input travels as split `(hi, lo)` f32 pairs.
+//!
+//! Hash GROUP BY ([`group_by_i32`]) builds the groups in a GPU hash table
+//! instead: one thread per row, slots claimed with atomic compare-exchange,
+//! aggregates updated with atomics, one readback of the whole table.
+//! [`group_by_top_k_i32`] keeps that table on the GPU and radix-selects the
+//! best K groups there, so only K groups are read back. [`fused_group_by`]
+//! runs a JIT-generated variant that filters rows and updates several
+//! aggregates in the same pass.
+//!
+//! [`multi_aggregate_i32`] computes COUNT, SUM, MIN and MAX of one column in
+//! a single dispatch, so `SELECT MIN(x), MAX(x), SUM(x)` reads the column
+//! once and reads back one buffer. [`multi_aggregate_wide`] does the same
+//! for `Int64`, `UInt64` and `UInt32` columns, emulating 64-bit arithmetic
+//! with 96-bit accumulators split across u32 words.
 
 use crate::{Error, Result};
-use arrow::array::{Array, Float32Array, Int32Array};
+use arrow::array::{
+    Array, Float32Array, Float64Array, Int32Array, Int64Array, RecordBatch, UInt32Array,
+    UInt64Array,
+};
+use arrow::datatypes::DataType;
+use std::sync::Arc;
 use wgpu;
-use wgpu::util::DeviceExt;
 
-/// Workgroup size (256 threads = 8 warps on NVIDIA, optimal for most GPUs)
-const WORKGROUP_SIZE: u32 = 256;
+use super::autotune::{specialize_workgroup_size, DEFAULT_WORKGROUP_SIZE};
+use super::buffers::{BufferPool, PooledBuffer};
+use super::jit::{FusedGroup, FusedGroupBy};
+use super::pipeline::{CachedPipeline, PipelineCache};
+use super::submit::PollBudget;
+use crate::topk::SortOrder;
+use crate::variance::WelfordState;
 
 /// WGSL shader for parallel SUM reduction (i32)
 const SUM_I32_SHADER: &str = r"
@@ -55,22 +82,26 @@ fn sum_reduce(@builtin(global_invocation_id) global_id: vec3<u32>,
 }
 ";
 
-/// WGSL shader for parallel SUM reduction (f32)
-#[allow(dead_code)]
+/// WGSL shader for per-workgroup SUM partials (f32)
+///
+/// Stage 1 of the float SUM: each workgroup tree-reduces its slice in shared
+/// memory and thread 0 writes `partials[workgroup_id]`. WGSL has no float
+/// atomics, so stage 2 (adding the partials) runs on the host in f64.
 const SUM_F32_SHADER: &str = r"
 @group(0) @binding(0) var<storage, read> input: array<f32>;
-@group(0) @binding(1) var<storage, read_write> output: array<f32>;
+@group(0) @binding(1) var<storage, read_write> partials: array<f32>;
 
 var<workgroup> shared_data: array<f32, 256>;
 
 @compute @workgroup_size(256)
-fn sum_reduce(@builtin(global_invocation_id) global_id: vec3<u32>,
-               @builtin(local_invocation_id) local_id: vec3<u32>) {
+fn sum_partial(@builtin(global_invocation_id) global_id: vec3<u32>,
+               @builtin(local_invocation_id) local_id: vec3<u32>,
+               @builtin(workgroup_id) workgroup_id: vec3<u32>) {
     let tid = local_id.x;
     let gid = global_id.x;
     let input_size = arrayLength(&input);
 
-    // Load data into shared memory
+    // Load data into shared memory (padding is the identity)
     if (gid < input_size) {
         shared_data[tid] = input[gid];
     } else {
@@ -81,16 +112,72 @@ fn sum_reduce(@builtin(global_invocation_id) global_id: vec3<u32>,
     // Parallel reduction in shared memory
     var stride = 128u;
     while (stride > 0u) {
-        if (tid < stride && gid + stride < input_size) {
+        if (tid < stride) {
             shared_data[tid] += shared_data[tid + stride];
         }
         workgroupBarrier();
         stride = stride / 2u;
     }
 
-    // First thread writes workgroup result
     if (tid == 0u) {
-        output[0] += shared_data[0];
+        partials[workgroup_id.x] = shared_data[0];
+    }
+}
+";
+
+/// WGSL shader for per-workgroup SUM partials (f64 as split f32 pairs)
+///
+/// Most adapters lack `shader-f64`, so each f64 arrives as `(hi, lo)` with
+/// `hi = f32(x)` and `lo = f32(x - hi)`. The workgroup adds pairs with
+/// error-free two-sum (double-single arithmetic, ~48 mantissa bits) and
+/// thread 0 writes its `(hi, lo)` partial; the host adds `hi + lo` in f64.
+const SUM_F64_SPLIT_SHADER: &str = r"
+@group(0) @binding(0) var<storage, read> input: array<vec2<f32>>;
+@group(0) @binding(1) var<storage, read_write> partials: array<vec2<f32>>;
+
+var<workgroup> shared_hi: array<f32, 256>;
+var<workgroup> shared_lo: array<f32, 256>;
+
+// (a_hi, a_lo) + (b_hi, b_lo) without losing the rounding error of the hi add
+fn add_split(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    let s = a.x + b.x;
+    let v = s - a.x;
+    let e = (a.x - (s - v)) + (b.x - v) + a.y + b.y;
+    let hi = s + e;
+    return vec2<f32>(hi, e - (hi - s));
+}
+
+@compute @workgroup_size(256)
+fn sum_split_partial(@builtin(global_invocation_id) global_id: vec3<u32>,
+                     @builtin(local_invocation_id) local_id: vec3<u32>,
+                     @builtin(workgroup_id) workgroup_id: vec3<u32>) {
+    let tid = local_id.x;
+    let gid = global_id.x;
+    let input_size = arrayLength(&input);
+
+    if (gid < input_size) {
+        shared_hi[tid] = input[gid].x;
+        shared_lo[tid] = input[gid].y;
+    } else {
+        shared_hi[tid] = 0.0;
+        shared_lo[tid] = 0.0;
+    }
+    workgroupBarrier();
+
+    var stride = 128u;
+    while (stride > 0u) {
+        if (tid < stride) {
+            let sum = add_split(vec2<f32>(shared_hi[tid], shared_lo[tid]),
+                                vec2<f32>(shared_hi[tid + stride], shared_lo[tid + stride]));
+            shared_hi[tid] = sum.x;
+            shared_lo[tid] = sum.y;
+        }
+        workgroupBarrier();
+        stride = stride / 2u;
+    }
+
+    if (tid == 0u) {
+        partials[workgroup_id.x] = vec2<f32>(shared_hi[0], shared_lo[0]);
     }
 }
 ";
@@ -111,7 +198,6 @@ fn count_kernel(@builtin(global_invocation_id) global_id: vec3<u32>) {
 ";
 
 /// WGSL shader for MIN reduction (i32)
-#[allow(dead_code)]
 const MIN_I32_SHADER: &str = r"
 @group(0) @binding(0) var<storage, read> input: array<i32>;
 @group(0) @binding(1) var<storage, read_write> output: array<atomic<i32>>;
@@ -151,7 +237,6 @@ fn min_reduce(@builtin(global_invocation_id) global_id: vec3<u32>,
 ";
 
 /// WGSL shader for MAX reduction (i32)
-#[allow(dead_code)]
 const MAX_I32_SHADER: &str = r"
 @group(0) @binding(0) var<storage, read> input: array<i32>;
 @group(0) @binding(1) var<storage, read_write> output: array<atomic<i32>>;
@@ -190,93 +275,408 @@ fn max_reduce(@builtin(global_invocation_id) global_id: vec3<u32>,
 }
 ";
 
+/// WGSL shader for per-workgroup Welford state (f32)
+///
+/// Stage 1 of the parallel variance: each thread starts from a one-element
+/// state, the workgroup tree-merges states in shared memory (Chan et al.),
+/// and thread 0 writes `(count, mean, m2)` to `partials[workgroup_id]`.
+/// Stage 2 (merging the partials) runs on the host in f64.
+const WELFORD_F32_SHADER: &str = r"
+struct Welford {
+    count: f32,
+    mean: f32,
+    m2: f32,
+    pad: f32,
+}
+
+@group(0) @binding(0) var<storage, read> input: array<f32>;
+@group(0) @binding(1) var<storage, read_write> partials: array<Welford>;
+
+var<workgroup> shared_count: array<f32, 256>;
+var<workgroup> shared_mean: array<f32, 256>;
+var<workgroup> shared_m2: array<f32, 256>;
+
+@compute @workgroup_size(256)
+fn welford_partial(@builtin(global_invocation_id) global_id: vec3<u32>,
+                   @builtin(local_invocation_id) local_id: vec3<u32>,
+                   @builtin(workgroup_id) workgroup_id: vec3<u32>) {
+    let tid = local_id.x;
+    let gid = global_id.x;
+    let input_size = arrayLength(&input);
+
+    // Each thread starts from the state of (at most) one element
+    if (gid < input_size) {
+        shared_count[tid] = 1.0;
+        shared_mean[tid] = input[gid];
+    } else {
+        shared_count[tid] = 0.0;
+        shared_mean[tid] = 0.0;
+    }
+    shared_m2[tid] = 0.0;
+    workgroupBarrier();
+
+    // Pairwise merge in shared memory
+    var stride = 128u;
+    while (stride > 0u) {
+        if (tid < stride) {
+            let n_a = shared_count[tid];
+            let n_b = shared_count[tid + stride];
+            let n = n_a + n_b;
+            if (n_b > 0.0) {
+                let delta = shared_mean[tid + stride] - shared_mean[tid];
+                shared_mean[tid] = shared_mean[tid] + delta * n_b / n;
+                shared_m2[tid] = shared_m2[tid] + shared_m2[tid + stride]
+                    + delta * delta * n_a * n_b / n;
+                shared_count[tid] = n;
+            }
+        }
+        workgroupBarrier();
+        stride = stride / 2u;
+    }
+
+    if (tid == 0u) {
+        partials[workgroup_id.x] = Welford(shared_count[0], shared_mean[0], shared_m2[0], 0.0);
+    }
+}
+";
+
+/// WGSL shader for hash GROUP BY (i32 keys and values)
+///
+/// Open addressing with linear probing: each thread hashes its row's key,
+/// claims an empty slot (or finds the key's slot) with a compare-exchange,
+/// then updates that slot's count and aggregate atomically. The slot tables
+/// hold a power-of-two number of slots plus one reserved for rows whose key
+/// equals the empty-slot marker. `@AGGREGATE@` is the atomic update
+/// (`atomicAdd`, `atomicMin`, `atomicMax`).
+const GROUP_BY_I32_SHADER: &str = r"
+@group(0) @binding(0) var<storage, read> keys: array<i32>;
+@group(0) @binding(1) var<storage, read> values: array<i32>;
+@group(0) @binding(2) var<storage, read_write> slot_keys: array<atomic<i32>>;
+@group(0) @binding(3) var<storage, read_write> slot_counts: array<atomic<u32>>;
+@group(0) @binding(4) var<storage, read_write> slot_values: array<atomic<i32>>;
+@group(0) @binding(5) var<storage, read_write> overflow: array<atomic<u32>>;
+
+const EMPTY_KEY: i32 = -2147483648; // i32::MIN
+
+// Murmur3 finalizer
+fn hash_key(key: i32) -> u32 {
+    var h = bitcast<u32>(key);
+    h = h ^ (h >> 16u);
+    h = h * 0x85ebca6bu;
+    h = h ^ (h >> 13u);
+    h = h * 0xc2b2ae35u;
+    h = h ^ (h >> 16u);
+    return h;
+}
+
+@compute @workgroup_size(256)
+fn group_by_aggregate(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let gid = global_id.x;
+    if (gid >= arrayLength(&keys)) {
+        return;
+    }
+    let key = keys[gid];
+    let capacity = arrayLength(&slot_keys) - 1u;
+
+    var slot = capacity;
+    if (key != EMPTY_KEY) {
+        slot = hash_key(key) & (capacity - 1u);
+        var probes = 0u;
+        loop {
+            let claim = atomicCompareExchangeWeak(&slot_keys[slot], EMPTY_KEY, key);
+            if (claim.exchanged || claim.old_value == key) {
+                break;
+            }
+            // Spurious failure: the slot is still empty, try it again
+            if (claim.old_value == EMPTY_KEY) {
+                continue;
+            }
+            probes = probes + 1u;
+            if (probes == capacity) {
+                atomicStore(&overflow[0], 1u);
+                return;
+            }
+            slot = (slot + 1u) & (capacity - 1u);
+        }
+    }
+
+    atomicAdd(&slot_counts[slot], 1u);
+    @AGGREGATE@(&slot_values[slot], values[gid]);
+}
+";
+
+/// WGSL shader for Top-K over a GROUP BY hash table (radix select)
+///
+/// Each occupied slot gets a unique 64-bit rank `(order value, key)` as two
+/// u32 words, smallest first: the aggregate mapped to an order-preserving
+/// u32 (inverted for descending), then the key, so ties go to the smaller
+/// key. Eight rounds of `topk_histogram` + `topk_select` fix the rank of the
+/// K-th group one byte at a time, entirely on the GPU; `topk_emit` then
+/// writes every group ranked at or before it. `@ORDER_VALUE@` reads the
+/// ranked aggregate and `@DESCENDING@` is `true` or `false`.
+const GROUP_BY_TOP_K_SHADER: &str = r"
+struct SelectState {
+    prefix_hi: u32,
+    prefix_lo: u32,
+    mask_hi: u32,
+    mask_lo: u32,
+    remaining: u32,
+    round: u32,
+}
+
+@group(0) @binding(0) var<storage, read> slot_keys: array<i32>;
+@group(0) @binding(1) var<storage, read> slot_counts: array<u32>;
+@group(0) @binding(2) var<storage, read> slot_values: array<i32>;
+@group(0) @binding(3) var<storage, read_write> state: SelectState;
+@group(0) @binding(4) var<storage, read_write> histogram: array<atomic<u32>, 256>;
+@group(0) @binding(5) var<storage, read_write> emitted: array<atomic<u32>>;
+@group(0) @binding(6) var<storage, read_write> top: array<u32>;
+
+const DESCENDING: bool = @DESCENDING@;
+
+fn rank(slot: u32) -> vec2<u32> {
+    var value = @ORDER_VALUE@;
+    if (DESCENDING) {
+        value = ~value;
+    }
+    return vec2<u32>(value, bitcast<u32>(slot_keys[slot]) ^ 0x80000000u);
+}
+
+fn digit(r: vec2<u32>, round: u32) -> u32 {
+    if (round < 4u) {
+        return (r.x >> (24u - 8u * round)) & 0xffu;
+    }
+    return (r.y >> (24u - 8u * (round - 4u))) & 0xffu;
+}
+
+@compute @workgroup_size(256)
+fn topk_histogram(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let slot = global_id.x;
+    if (slot >= arrayLength(&slot_counts) || slot_counts[slot] == 0u) {
+        return;
+    }
+    let r = rank(slot);
+    if ((r.x & state.mask_hi) != state.prefix_hi || (r.y & state.mask_lo) != state.prefix_lo) {
+        return;
+    }
+    atomicAdd(&histogram[digit(r, state.round)], 1u);
+}
+
+@compute @workgroup_size(1)
+fn topk_select() {
+    if (state.round == 0u) {
+        var total = 0u;
+        for (var b = 0u; b < 256u; b = b + 1u) {
+            total = total + atomicLoad(&histogram[b]);
+        }
+        state.remaining = min(state.remaining, total);
+    }
+
+    // Bin holding the remaining-th smallest candidate
+    var below = 0u;
+    var bin = 255u;
+    for (var b = 0u; b < 256u; b = b + 1u) {
+        let n = atomicLoad(&histogram[b]);
+        if (below + n >= state.remaining) {
+            bin = b;
+            break;
+        }
+        below = below + n;
+    }
+    state.remaining = state.remaining - below;
+
+    if (state.round < 4u) {
+        let shift = 24u - 8u * state.round;
+        state.prefix_hi = state.prefix_hi | (bin << shift);
+        state.mask_hi = state.mask_hi | (0xffu << shift);
+    } else {
+        let shift = 24u - 8u * (state.round - 4u);
+        state.prefix_lo = state.prefix_lo | (bin << shift);
+        state.mask_lo = state.mask_lo | (0xffu << shift);
+    }
+    state.round = state.round + 1u;
+
+    for (var b = 0u; b < 256u; b = b + 1u) {
+        atomicStore(&histogram[b], 0u);
+    }
+}
+
+@compute @workgroup_size(256)
+fn topk_emit(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let slot = global_id.x;
+    if (slot >= arrayLength(&slot_counts) || slot_counts[slot] == 0u) {
+        return;
+    }
+    let r = rank(slot);
+    if (r.x > state.prefix_hi || (r.x == state.prefix_hi && r.y > state.prefix_lo)) {
+        return;
+    }
+    let i = atomicAdd(&emitted[0], 1u);
+    if (3u * i + 2u < arrayLength(&top)) {
+        top[3u * i] = bitcast<u32>(slot_keys[slot]);
+        top[3u * i + 1u] = bitcast<u32>(slot_values[slot]);
+        top[3u * i + 2u] = slot_counts[slot];
+    }
+}
+";
+
+/// Tree-reduction operations sharing one bind group layout (input, atomic output)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReduceOp {
+    /// SUM (wrapping on overflow)
+    Sum,
+    /// MIN
+    Min,
+    /// MAX
+    Max,
+}
+
+impl ReduceOp {
+    /// Shader source specialized for `workgroup_size`, entry point, and output identity
+    #[must_use]
+    pub fn shader(self, workgroup_size: u32) -> (String, &'static str, i32) {
+        let (source, entry_point) = match self {
+            Self::Sum => (SUM_I32_SHADER, "sum_reduce"),
+            Self::Min => (MIN_I32_SHADER, "min_reduce"),
+            Self::Max => (MAX_I32_SHADER, "max_reduce"),
+        };
+        (specialize_workgroup_size(source, workgroup_size), entry_point, self.identity())
+    }
+
+    /// Result of the operation over empty input
+    #[must_use]
+    pub const fn identity(self) -> i32 {
+        match self {
+            Self::Sum => 0,
+            Self::Min => i32::MAX,
+            Self::Max => i32::MIN,
+        }
+    }
+
+    /// Combine the results of two disjoint inputs (as the shader does)
+    #[must_use]
+    pub fn combine(self, a: i32, b: i32) -> i32 {
+        match self {
+            Self::Sum => a.wrapping_add(b),
+            Self::Min => a.min(b),
+            Self::Max => a.max(b),
+        }
+    }
+}
+
+/// Execute a reduction on GPU (i32) with an explicit workgroup size
+///
+/// Empty input returns the operation's identity (0, `i32::MAX`, `i32::MIN`).
+///
+/// # Errors
+/// Returns error if GPU execution fails, or if `budget` stops the readback
+/// (timeout, cancellation, lost device)
+#[allow(clippy::too_many_arguments)]
+pub async fn reduce_i32(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    pipelines: &PipelineCache,
+    buffers: &BufferPool,
+    op: ReduceOp,
+    data: &Int32Array,
+    workgroup_size: u32,
+    budget: &PollBudget,
+) -> Result<i32> {
+    let (shader, entry_point, identity) = op.shader(workgroup_size);
+    let values = data.values();
+    run_i32_reduction(
+        device,
+        queue,
+        pipelines,
+        buffers,
+        &shader,
+        entry_point,
+        identity,
+        values,
+        workgroup_size,
+        budget,
+    )
+    .await
+}
+
 /// Execute SUM aggregation on GPU (i32)
 ///
 /// # Errors
 /// Returns error if GPU execution fails
+pub async fn sum_i32(device: &wgpu::Device, queue: &wgpu::Queue, data: &Int32Array) -> Result<i32> {
+    let (budget, pipelines, buffers) =
+        (PollBudget::default(), PipelineCache::new(), BufferPool::new());
+    reduce_i32(
+        device,
+        queue,
+        &pipelines,
+        &buffers,
+        ReduceOp::Sum,
+        data,
+        DEFAULT_WORKGROUP_SIZE,
+        &budget,
+    )
+    .await
+}
+
+/// Run an i32 reduction shader (binding 0: input, binding 1: atomic output)
+///
+/// The output is initialized to `identity`, which is also returned for
+/// empty input.
+///
+/// # Errors
+/// Returns error if GPU execution fails, or if `budget` stops the readback
+/// (timeout, cancellation, lost device)
 ///
 /// # Panics
 /// May panic if buffer mapping fails (internal GPU error)
-#[allow(clippy::too_many_lines)]
+#[allow(clippy::too_many_lines, clippy::too_many_arguments)]
 #[allow(clippy::cast_possible_truncation)]
-pub async fn sum_i32(device: &wgpu::Device, queue: &wgpu::Queue, data: &Int32Array) -> Result<i32> {
-    let input_data: Vec<i32> = data.values().to_vec();
+pub(crate) async fn run_i32_reduction(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    pipelines: &PipelineCache,
+    buffers: &BufferPool,
+    shader_source: &str,
+    entry_point: &str,
+    identity: i32,
+    input_data: &[i32],
+    workgroup_size: u32,
+    budget: &PollBudget,
+) -> Result<i32> {
     let input_size = input_data.len();
 
     if input_size == 0 {
-        return Ok(0);
+        return Ok(identity);
     }
 
     // Create input buffer
-    let input_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some("Input Buffer"),
-        contents: bytemuck::cast_slice(&input_data),
-        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-    });
-
-    // Create output buffer (initialized to 0)
-    let output_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some("Output Buffer"),
-        contents: bytemuck::cast_slice(&[0i32]),
-        usage: wgpu::BufferUsages::STORAGE
-            | wgpu::BufferUsages::COPY_SRC
-            | wgpu::BufferUsages::COPY_DST,
-    });
-
-    // Create compute pipeline
-    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-        label: Some("SUM i32 Shader"),
-        source: wgpu::ShaderSource::Wgsl(SUM_I32_SHADER.into()),
-    });
-
-    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-        label: Some("Bind Group Layout"),
-        entries: &[
-            wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::COMPUTE,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Storage { read_only: true },
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            },
-            wgpu::BindGroupLayoutEntry {
-                binding: 1,
-                visibility: wgpu::ShaderStages::COMPUTE,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Storage { read_only: false },
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            },
-        ],
-    });
+    let input_buffer = buffers.upload(
+        device,
+        queue,
+        "Input Buffer",
+        bytemuck::cast_slice(input_data),
+        wgpu::BufferUsages::STORAGE,
+    );
 
-    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-        label: Some("Pipeline Layout"),
-        bind_group_layouts: &[&bind_group_layout],
-        push_constant_ranges: &[],
-    });
+    // Create output buffer (initialized to the reduction identity)
+    let output_buffer = buffers.upload(
+        device,
+        queue,
+        "Output Buffer",
+        bytemuck::cast_slice(&[identity]),
+        wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+    );
 
-    let compute_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-        label: Some("SUM i32 Pipeline"),
-        layout: Some(&pipeline_layout),
-        module: &shader,
-        entry_point: "sum_reduce",
-        compilation_options: wgpu::PipelineCompilationOptions::default(),
-        cache: None,
-    });
+    let kernel = pipelines.get_or_compile(device, shader_source, &[entry_point], &[true, false]);
 
     // Create bind group
     let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
         label: Some("Bind Group"),
-        layout: &bind_group_layout,
+        layout: kernel.bind_group_layout(),
         entries: &[
-            wgpu::BindGroupEntry { binding: 0, resource: input_buffer.as_entire_binding() },
-            wgpu::BindGroupEntry { binding: 1, resource: output_buffer.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 0, resource: input_buffer.binding() },
+            wgpu::BindGroupEntry { binding: 1, resource: output_buffer.binding() },
         ],
     });
 
@@ -289,37 +689,27 @@ pub async fn sum_i32(device: &wgpu::Device, queue: &wgpu::Queue, data: &Int32Arr
             label: Some("Compute Pass"),
             timestamp_writes: None,
         });
-        compute_pass.set_pipeline(&compute_pipeline);
+        compute_pass.set_pipeline(kernel.pipeline());
         compute_pass.set_bind_group(0, &bind_group, &[]);
 
-        let workgroup_count = (input_size as u32).div_ceil(WORKGROUP_SIZE);
+        let workgroup_count = (input_size as u32).div_ceil(workgroup_size);
         compute_pass.dispatch_workgroups(workgroup_count, 1, 1);
     }
 
     // Read result buffer
-    let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("Staging Buffer"),
-        size: 4, // i32 = 4 bytes
-        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
-        mapped_at_creation: false,
-    });
+    let mut staging_buffer = buffers.acquire(
+        device,
+        "Staging Buffer",
+        4,
+        wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+    );
 
-    encoder.copy_buffer_to_buffer(&output_buffer, 0, &staging_buffer, 0, 4);
+    encoder.copy_buffer_to_buffer(output_buffer.buffer(), 0, staging_buffer.buffer(), 0, 4);
     queue.submit(Some(encoder.finish()));
 
     // Map buffer and read result
-    let buffer_slice = staging_buffer.slice(..);
-    let (sender, receiver) = futures_intrusive::channel::shared::oneshot_channel();
-    buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-        sender.send(result).expect("Failed to send buffer mapping result through channel");
-    });
-    device.poll(wgpu::Maintain::Wait);
-
-    receiver
-        .receive()
-        .await
-        .ok_or_else(|| Error::Other("Failed to receive mapping result".to_string()))?
-        .map_err(|e| Error::Other(format!("Buffer mapping failed: {e:?}")))?;
+    staging_buffer.map_read(device, budget).await?;
+    let buffer_slice = staging_buffer.slice();
 
     let data = buffer_slice.get_mapped_range();
     let result = i32::from_le_bytes(
@@ -331,19 +721,222 @@ pub async fn sum_i32(device: &wgpu::Device, queue: &wgpu::Queue, data: &Int32Arr
     Ok(result)
 }
 
+/// SUM of an f32 column on GPU (two-stage: workgroup partials, host merge)
+///
+/// Partials are f32 over at most `workgroup_size` elements; the host adds
+/// them in f64, so the result is returned in f64. NaN and infinities
+/// propagate as in IEEE addition. Empty input sums to 0.
+///
+/// # Errors
+/// Returns error if GPU execution fails, or if `budget` stops the readback
+/// (timeout, cancellation, lost device)
+pub async fn reduce_sum_f32(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    pipelines: &PipelineCache,
+    buffers: &BufferPool,
+    data: &Float32Array,
+    workgroup_size: u32,
+    budget: &PollBudget,
+) -> Result<f64> {
+    let values = data.values();
+    let shader = specialize_workgroup_size(SUM_F32_SHADER, workgroup_size);
+    let partials = run_partials::<f32>(
+        device,
+        queue,
+        pipelines,
+        buffers,
+        &shader,
+        "sum_partial",
+        bytemuck::cast_slice(values),
+        values.len(),
+        1,
+        workgroup_size,
+        budget,
+    )
+    .await?;
+    Ok(partials.iter().copied().map(f64::from).sum())
+}
+
+/// SUM of an f64 column on GPU (split-accumulator double-single reduction)
+///
+/// The host splits each value into an `(hi, lo)` f32 pair, the GPU adds
+/// pairs with error-free transforms, and the host adds the per-workgroup
+/// partials in f64. The result carries roughly 48 significant bits, against
+/// 24 for a plain f32 reduction. Columns containing NaN or infinities are
+/// resolved on the host (IEEE rules decide the sum without the finite part).
+///
+/// # Errors
+/// Returns [`Error::InvalidInput`] if a finite value is outside the f32
+/// range (it cannot be split), or an error if GPU execution fails or
+/// `budget` stops the readback (timeout, cancellation, lost device)
+#[allow(clippy::cast_possible_truncation)]
+pub async fn reduce_sum_f64(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    pipelines: &PipelineCache,
+    buffers: &BufferPool,
+    data: &Float64Array,
+    workgroup_size: u32,
+    budget: &PollBudget,
+) -> Result<f64> {
+    let values = data.values();
+    if values.iter().any(|value| !value.is_finite()) {
+        // NaN, +inf, -inf or +inf + -inf: the finite values do not matter
+        return Ok(values.iter().filter(|value| !value.is_finite()).sum());
+    }
+    if let Some(value) = values.iter().find(|value| value.abs() > f64::from(f32::MAX)) {
+        return Err(Error::InvalidInput(format!(
+            "f64 value {value} is outside the f32 range of the GPU split accumulator; \
+             use the SIMD backend"
+        )));
+    }
+
+    let split: Vec<[f32; 2]> = values
+        .iter()
+        .map(|&value| {
+            let hi = value as f32;
+            [hi, (value - f64::from(hi)) as f32]
+        })
+        .collect();
+    let shader = specialize_workgroup_size(SUM_F64_SPLIT_SHADER, workgroup_size);
+    let partials = run_partials::<f32>(
+        device,
+        queue,
+        pipelines,
+        buffers,
+        &shader,
+        "sum_split_partial",
+        bytemuck::cast_slice(&split),
+        split.len(),
+        2,
+        workgroup_size,
+        budget,
+    )
+    .await?;
+    Ok(partials.iter().copied().map(f64::from).sum())
+}
+
 /// Execute SUM aggregation on GPU (f32)
-/// Placeholder - not yet implemented
 ///
 /// # Errors
-/// Returns error (not yet implemented)
-#[allow(clippy::unused_async)]
+/// Returns error if GPU execution fails
+#[allow(clippy::cast_possible_truncation)]
 pub async fn sum_f32(
-    _device: &wgpu::Device,
-    _queue: &wgpu::Queue,
-    _data: &Float32Array,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    data: &Float32Array,
 ) -> Result<f32> {
-    // Placeholder implementation
-    Err(Error::Other("f32 SUM not yet implemented".to_string()))
+    let (budget, pipelines, buffers) =
+        (PollBudget::default(), PipelineCache::new(), BufferPool::new());
+    Ok(reduce_sum_f32(device, queue, &pipelines, &buffers, data, DEFAULT_WORKGROUP_SIZE, &budget)
+        .await? as f32)
+}
+
+/// Execute SUM aggregation on GPU (f64, split accumulator)
+///
+/// # Errors
+/// Returns error if a value is outside the f32 range or GPU execution fails
+pub async fn sum_f64(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    data: &Float64Array,
+) -> Result<f64> {
+    let (budget, pipelines, buffers) =
+        (PollBudget::default(), PipelineCache::new(), BufferPool::new());
+    reduce_sum_f64(device, queue, &pipelines, &buffers, data, DEFAULT_WORKGROUP_SIZE, &budget).await
+}
+
+/// Run a stage-1 shader (binding 0: input, binding 1: per-workgroup partials)
+///
+/// `input` holds `input_len` elements; the shader writes one partial of
+/// `components` 4-byte words `T` per workgroup. Returns the partials
+/// flattened (empty for empty input).
+///
+/// # Errors
+/// Returns error if GPU execution fails, or if `budget` stops the readback
+/// (timeout, cancellation, lost device)
+#[allow(clippy::too_many_lines, clippy::too_many_arguments)]
+#[allow(clippy::cast_possible_truncation)]
+async fn run_partials<T: bytemuck::Pod>(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    pipelines: &PipelineCache,
+    buffers: &BufferPool,
+    shader_source: &str,
+    entry_point: &str,
+    input: &[u8],
+    input_len: usize,
+    components: u64,
+    workgroup_size: u32,
+    budget: &PollBudget,
+) -> Result<Vec<T>> {
+    if input_len == 0 {
+        return Ok(Vec::new());
+    }
+
+    let workgroup_count = (input_len as u32).div_ceil(workgroup_size);
+    let partials_size = u64::from(workgroup_count) * components * 4;
+
+    let input_buffer =
+        buffers.upload(device, queue, "Sum Input", input, wgpu::BufferUsages::STORAGE);
+
+    let partials_buffer = buffers.acquire(
+        device,
+        "Sum Partials",
+        partials_size,
+        wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+    );
+
+    let kernel = pipelines.get_or_compile(device, shader_source, &[entry_point], &[true, false]);
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Sum Bind Group"),
+        layout: kernel.bind_group_layout(),
+        entries: &[
+            wgpu::BindGroupEntry { binding: 0, resource: input_buffer.binding() },
+            wgpu::BindGroupEntry { binding: 1, resource: partials_buffer.binding() },
+        ],
+    });
+
+    let mut encoder = device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("Sum Encoder") });
+
+    {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Sum Pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(kernel.pipeline());
+        compute_pass.set_bind_group(0, &bind_group, &[]);
+        compute_pass.dispatch_workgroups(workgroup_count, 1, 1);
+    }
+
+    let mut staging_buffer = buffers.acquire(
+        device,
+        "Sum Staging Buffer",
+        partials_size,
+        wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+    );
+
+    encoder.copy_buffer_to_buffer(
+        partials_buffer.buffer(),
+        0,
+        staging_buffer.buffer(),
+        0,
+        partials_size,
+    );
+    queue.submit(Some(encoder.finish()));
+
+    staging_buffer.map_read(device, budget).await?;
+    let buffer_slice = staging_buffer.slice();
+
+    let mapped = buffer_slice.get_mapped_range();
+    let partials = bytemuck::cast_slice::<u8, T>(&mapped).to_vec();
+    drop(mapped);
+    staging_buffer.unmap();
+
+    Ok(partials)
 }
 
 /// Execute COUNT aggregation on GPU
@@ -365,284 +958,1367 @@ pub async fn count(
 ///
 /// # Errors
 /// Returns error if GPU execution fails
+pub async fn min_i32(device: &wgpu::Device, queue: &wgpu::Queue, data: &Int32Array) -> Result<i32> {
+    let (budget, pipelines, buffers) =
+        (PollBudget::default(), PipelineCache::new(), BufferPool::new());
+    reduce_i32(
+        device,
+        queue,
+        &pipelines,
+        &buffers,
+        ReduceOp::Min,
+        data,
+        DEFAULT_WORKGROUP_SIZE,
+        &budget,
+    )
+    .await
+}
+
+/// Execute MAX aggregation on GPU (i32)
+///
+/// # Errors
+/// Returns error if GPU execution fails
+pub async fn max_i32(device: &wgpu::Device, queue: &wgpu::Queue, data: &Int32Array) -> Result<i32> {
+    let (budget, pipelines, buffers) =
+        (PollBudget::default(), PipelineCache::new(), BufferPool::new());
+    reduce_i32(
+        device,
+        queue,
+        &pipelines,
+        &buffers,
+        ReduceOp::Max,
+        data,
+        DEFAULT_WORKGROUP_SIZE,
+        &budget,
+    )
+    .await
+}
+
+/// Welford summary of an f32 column on GPU (two-stage parallel variance)
+///
+/// Stage 1 runs the `welford_partial` shader: one `(count, mean, m2)` state per
+/// workgroup. Stage 2 reads the partials back and merges them on the host
+/// in f64 with [`WelfordState::merge`], the same rule the SIMD path uses,
+/// so precision loss is confined to workgroup-sized f32 partials.
+///
+/// # Errors
+/// Returns error if GPU execution fails, or if `budget` stops the readback
+/// (timeout, cancellation, lost device)
 ///
 /// # Panics
 /// May panic if buffer mapping fails (internal GPU error)
 #[allow(clippy::too_many_lines)]
-#[allow(clippy::cast_possible_truncation)]
-pub async fn min_i32(device: &wgpu::Device, queue: &wgpu::Queue, data: &Int32Array) -> Result<i32> {
-    let input_data: Vec<i32> = data.values().to_vec();
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub async fn welford_f32(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    pipelines: &PipelineCache,
+    buffers: &BufferPool,
+    data: &Float32Array,
+    workgroup_size: u32,
+    budget: &PollBudget,
+) -> Result<WelfordState> {
+    let input_data = data.values();
     let input_size = input_data.len();
 
     if input_size == 0 {
-        return Ok(i32::MAX); // Empty array minimum is i32::MAX
+        return Ok(WelfordState::default());
     }
 
-    // Create input buffer
-    let input_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some("MIN Input Buffer"),
-        contents: bytemuck::cast_slice(&input_data),
-        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-    });
-
-    // Create output buffer (initialized to i32::MAX)
-    let output_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some("MIN Output Buffer"),
-        contents: bytemuck::cast_slice(&[i32::MAX]),
-        usage: wgpu::BufferUsages::STORAGE
-            | wgpu::BufferUsages::COPY_SRC
-            | wgpu::BufferUsages::COPY_DST,
-    });
-
-    // Create compute pipeline
-    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-        label: Some("MIN i32 Shader"),
-        source: wgpu::ShaderSource::Wgsl(MIN_I32_SHADER.into()),
-    });
+    let workgroup_count = (input_size as u32).div_ceil(workgroup_size);
+    // 4 x f32 per partial (count, mean, m2, padding)
+    let partials_size = u64::from(workgroup_count) * 16;
 
-    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-        label: Some("MIN Bind Group Layout"),
-        entries: &[
-            wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::COMPUTE,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Storage { read_only: true },
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            },
-            wgpu::BindGroupLayoutEntry {
-                binding: 1,
-                visibility: wgpu::ShaderStages::COMPUTE,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Storage { read_only: false },
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            },
-        ],
-    });
+    let input_buffer = buffers.upload(
+        device,
+        queue,
+        "Welford Input",
+        bytemuck::cast_slice(input_data),
+        wgpu::BufferUsages::STORAGE,
+    );
 
-    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-        label: Some("MIN Pipeline Layout"),
-        bind_group_layouts: &[&bind_group_layout],
-        push_constant_ranges: &[],
-    });
+    let partials_buffer = buffers.acquire(
+        device,
+        "Welford Partials",
+        partials_size,
+        wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+    );
 
-    let compute_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-        label: Some("MIN i32 Pipeline"),
-        layout: Some(&pipeline_layout),
-        module: &shader,
-        entry_point: "min_reduce",
-        compilation_options: wgpu::PipelineCompilationOptions::default(),
-        cache: None,
-    });
+    let shader_source = specialize_workgroup_size(WELFORD_F32_SHADER, workgroup_size);
+    let kernel =
+        pipelines.get_or_compile(device, &shader_source, &["welford_partial"], &[true, false]);
 
-    // Create bind group
     let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        label: Some("MIN Bind Group"),
-        layout: &bind_group_layout,
+        label: Some("Welford Bind Group"),
+        layout: kernel.bind_group_layout(),
         entries: &[
-            wgpu::BindGroupEntry { binding: 0, resource: input_buffer.as_entire_binding() },
-            wgpu::BindGroupEntry { binding: 1, resource: output_buffer.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 0, resource: input_buffer.binding() },
+            wgpu::BindGroupEntry { binding: 1, resource: partials_buffer.binding() },
         ],
     });
 
-    // Execute compute shader
-    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-        label: Some("MIN Compute Encoder"),
-    });
+    let mut encoder = device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("Welford Encoder") });
 
     {
         let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-            label: Some("MIN Compute Pass"),
+            label: Some("Welford Pass"),
             timestamp_writes: None,
         });
-        compute_pass.set_pipeline(&compute_pipeline);
+        compute_pass.set_pipeline(kernel.pipeline());
         compute_pass.set_bind_group(0, &bind_group, &[]);
-
-        let workgroup_count = (input_size as u32).div_ceil(WORKGROUP_SIZE);
         compute_pass.dispatch_workgroups(workgroup_count, 1, 1);
     }
 
-    // Read result buffer
-    let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("MIN Staging Buffer"),
-        size: 4, // i32 = 4 bytes
-        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
-        mapped_at_creation: false,
-    });
+    let mut staging_buffer = buffers.acquire(
+        device,
+        "Welford Staging Buffer",
+        partials_size,
+        wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+    );
 
-    encoder.copy_buffer_to_buffer(&output_buffer, 0, &staging_buffer, 0, 4);
+    encoder.copy_buffer_to_buffer(
+        partials_buffer.buffer(),
+        0,
+        staging_buffer.buffer(),
+        0,
+        partials_size,
+    );
     queue.submit(Some(encoder.finish()));
 
-    // Map buffer and read result
-    let buffer_slice = staging_buffer.slice(..);
-    let (sender, receiver) = futures_intrusive::channel::shared::oneshot_channel();
-    buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-        sender.send(result).expect("Failed to send buffer mapping result through channel");
+    staging_buffer.map_read(device, budget).await?;
+    let buffer_slice = staging_buffer.slice();
+
+    // Stage 2: merge per-workgroup partials in f64
+    let mapped = buffer_slice.get_mapped_range();
+    let partials: &[f32] = bytemuck::cast_slice(&mapped);
+    let state = partials.chunks_exact(4).fold(WelfordState::default(), |acc, partial| {
+        acc.merge(WelfordState {
+            count: partial[0] as u64,
+            mean: f64::from(partial[1]),
+            m2: f64::from(partial[2]),
+        })
     });
-    device.poll(wgpu::Maintain::Wait);
+    drop(mapped);
+    staging_buffer.unmap();
 
-    receiver
-        .receive()
-        .await
-        .ok_or_else(|| Error::Other("Failed to receive mapping result".to_string()))?
-        .map_err(|e| Error::Other(format!("Buffer mapping failed: {e:?}")))?;
+    Ok(state)
+}
 
-    let data = buffer_slice.get_mapped_range();
-    let result = i32::from_le_bytes(
-        data[0..4].try_into().expect("Buffer must contain at least 4 bytes for i32 result"),
-    );
-    drop(data);
-    staging_buffer.unmap();
+/// WGSL shader for per-workgroup SUM/MIN/MAX partials in one pass (i32)
+///
+/// Each workgroup tree-reduces its slice once, carrying four accumulators,
+/// and thread 0 writes `(sum_lo, sum_hi, min, max)` to
+/// `partials[workgroup_id]`. The sum is split into the low 16 bits and the
+/// arithmetic high half of each value so neither half overflows an i32 within
+/// a workgroup; the host recombines them exactly in i64.
+const MULTI_AGGREGATE_I32_SHADER: &str = r"
+@group(0) @binding(0) var<storage, read> input: array<i32>;
+@group(0) @binding(1) var<storage, read_write> partials: array<vec4<i32>>;
 
-    Ok(result)
+var<workgroup> shared_lo: array<i32, 256>;
+var<workgroup> shared_hi: array<i32, 256>;
+var<workgroup> shared_min: array<i32, 256>;
+var<workgroup> shared_max: array<i32, 256>;
+
+@compute @workgroup_size(256)
+fn multi_aggregate(@builtin(global_invocation_id) global_id: vec3<u32>,
+                   @builtin(local_invocation_id) local_id: vec3<u32>,
+                   @builtin(workgroup_id) workgroup_id: vec3<u32>) {
+    let tid = local_id.x;
+    let gid = global_id.x;
+    let input_size = arrayLength(&input);
+
+    // Load data into shared memory (padding is each accumulator's identity)
+    if (gid < input_size) {
+        let value = input[gid];
+        shared_lo[tid] = value & 65535;
+        shared_hi[tid] = value >> 16u;
+        shared_min[tid] = value;
+        shared_max[tid] = value;
+    } else {
+        shared_lo[tid] = 0;
+        shared_hi[tid] = 0;
+        shared_min[tid] = 2147483647; // i32::MAX
+        shared_max[tid] = -2147483648; // i32::MIN
+    }
+    workgroupBarrier();
+
+    // Parallel reduction of all four accumulators in shared memory
+    var stride = 128u;
+    while (stride > 0u) {
+        if (tid < stride) {
+            shared_lo[tid] += shared_lo[tid + stride];
+            shared_hi[tid] += shared_hi[tid + stride];
+            shared_min[tid] = min(shared_min[tid], shared_min[tid + stride]);
+            shared_max[tid] = max(shared_max[tid], shared_max[tid + stride]);
+        }
+        workgroupBarrier();
+        stride = stride / 2u;
+    }
+
+    if (tid == 0u) {
+        partials[workgroup_id.x] =
+            vec4<i32>(shared_lo[0], shared_hi[0], shared_min[0], shared_max[0]);
+    }
 }
+";
 
-/// Execute MAX aggregation on GPU (i32)
+/// COUNT, exact SUM, MIN and MAX of an i32 column, from one GPU pass
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct I32Aggregates {
+    /// Rows aggregated
+    pub count: u64,
+    /// Exact (non-wrapping) sum
+    pub sum: i64,
+    /// Smallest value (`i32::MAX` for empty input)
+    pub min: i32,
+    /// Largest value (`i32::MIN` for empty input)
+    pub max: i32,
+}
+
+impl Default for I32Aggregates {
+    fn default() -> Self {
+        Self { count: 0, sum: 0, min: i32::MAX, max: i32::MIN }
+    }
+}
+
+impl I32Aggregates {
+    /// Combine the aggregates of two disjoint inputs
+    #[must_use]
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            count: self.count + other.count,
+            sum: self.sum + other.sum,
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+}
+
+/// COUNT, SUM, MIN and MAX of an i32 column in one dispatch and one readback
 ///
-/// # Errors
-/// Returns error if GPU execution fails
+/// The `multi_aggregate` shader writes one `(sum_lo, sum_hi, min, max)`
+/// partial per workgroup; the partials are read back together and combined
+/// on the host, the sum in i64 so it does not wrap. NULL slots are read as
+/// their underlying values; callers reject NULLs first.
 ///
-/// # Panics
-/// May panic if buffer mapping fails (internal GPU error)
+/// # Errors
+/// Returns error if GPU execution fails, or if `budget` stops the readback
+/// (timeout, cancellation, lost device)
 #[allow(clippy::too_many_lines)]
 #[allow(clippy::cast_possible_truncation)]
-pub async fn max_i32(device: &wgpu::Device, queue: &wgpu::Queue, data: &Int32Array) -> Result<i32> {
-    let input_data: Vec<i32> = data.values().to_vec();
+pub async fn multi_aggregate_i32(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    pipelines: &PipelineCache,
+    buffers: &BufferPool,
+    data: &Int32Array,
+    workgroup_size: u32,
+    budget: &PollBudget,
+) -> Result<I32Aggregates> {
+    let input_data = data.values();
     let input_size = input_data.len();
 
     if input_size == 0 {
-        return Ok(i32::MIN); // Empty array maximum is i32::MIN
+        return Ok(I32Aggregates::default());
     }
 
-    // Create input buffer
-    let input_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some("MAX Input Buffer"),
-        contents: bytemuck::cast_slice(&input_data),
-        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-    });
+    let workgroup_count = (input_size as u32).div_ceil(workgroup_size);
+    // 4 x i32 per partial (sum_lo, sum_hi, min, max)
+    let partials_size = u64::from(workgroup_count) * 16;
 
-    // Create output buffer (initialized to i32::MIN)
-    let output_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some("MAX Output Buffer"),
-        contents: bytemuck::cast_slice(&[i32::MIN]),
-        usage: wgpu::BufferUsages::STORAGE
-            | wgpu::BufferUsages::COPY_SRC
-            | wgpu::BufferUsages::COPY_DST,
-    });
+    let input_buffer = buffers.upload(
+        device,
+        queue,
+        "Multi-Aggregate Input",
+        bytemuck::cast_slice(input_data),
+        wgpu::BufferUsages::STORAGE,
+    );
 
-    // Create compute pipeline
-    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-        label: Some("MAX i32 Shader"),
-        source: wgpu::ShaderSource::Wgsl(MAX_I32_SHADER.into()),
-    });
+    let partials_buffer = buffers.acquire(
+        device,
+        "Multi-Aggregate Partials",
+        partials_size,
+        wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+    );
+
+    let shader_source = specialize_workgroup_size(MULTI_AGGREGATE_I32_SHADER, workgroup_size);
+    let kernel =
+        pipelines.get_or_compile(device, &shader_source, &["multi_aggregate"], &[true, false]);
 
-    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-        label: Some("MAX Bind Group Layout"),
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Multi-Aggregate Bind Group"),
+        layout: kernel.bind_group_layout(),
         entries: &[
-            wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::COMPUTE,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Storage { read_only: true },
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            },
-            wgpu::BindGroupLayoutEntry {
-                binding: 1,
-                visibility: wgpu::ShaderStages::COMPUTE,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Storage { read_only: false },
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            },
+            wgpu::BindGroupEntry { binding: 0, resource: input_buffer.binding() },
+            wgpu::BindGroupEntry { binding: 1, resource: partials_buffer.binding() },
         ],
     });
 
-    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-        label: Some("MAX Pipeline Layout"),
-        bind_group_layouts: &[&bind_group_layout],
-        push_constant_ranges: &[],
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Multi-Aggregate Encoder"),
     });
 
-    let compute_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-        label: Some("MAX i32 Pipeline"),
-        layout: Some(&pipeline_layout),
-        module: &shader,
-        entry_point: "max_reduce",
-        compilation_options: wgpu::PipelineCompilationOptions::default(),
-        cache: None,
+    {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Multi-Aggregate Pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(kernel.pipeline());
+        compute_pass.set_bind_group(0, &bind_group, &[]);
+        compute_pass.dispatch_workgroups(workgroup_count, 1, 1);
+    }
+
+    let mut staging_buffer = buffers.acquire(
+        device,
+        "Multi-Aggregate Staging Buffer",
+        partials_size,
+        wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+    );
+
+    encoder.copy_buffer_to_buffer(
+        partials_buffer.buffer(),
+        0,
+        staging_buffer.buffer(),
+        0,
+        partials_size,
+    );
+    queue.submit(Some(encoder.finish()));
+
+    staging_buffer.map_read(device, budget).await?;
+    let buffer_slice = staging_buffer.slice();
+
+    let mapped = buffer_slice.get_mapped_range();
+    let partials: &[i32] = bytemuck::cast_slice(&mapped);
+    let aggregates = partials.chunks_exact(4).fold(
+        I32Aggregates { count: input_size as u64, ..I32Aggregates::default() },
+        |acc, partial| I32Aggregates {
+            count: acc.count,
+            sum: acc.sum + (i64::from(partial[1]) << 16) + i64::from(partial[0]),
+            min: acc.min.min(partial[2]),
+            max: acc.max.max(partial[3]),
+        },
+    );
+    drop(mapped);
+    staging_buffer.unmap();
+
+    Ok(aggregates)
+}
+
+/// WGSL shader for per-workgroup SUM/MIN/MAX partials of 64-bit and
+/// unsigned integers
+///
+/// WGSL has no 64-bit integers, so each value is widened to a 96-bit two's
+/// complement number held in three u32 words `(lo, mid, hi)`: `Int64` as
+/// its `(lo, hi)` word pair plus a sign-extension word, `UInt64` and `UInt32`
+/// zero-extended. Sums add word by word with explicit carries; MIN/MAX
+/// compare the high word signed and the others unsigned. 96 bits hold the
+/// sum of a whole workgroup of 64-bit values without overflow, so the host
+/// adds the partials exactly in i128. `@ELEMENT@` is the input element type
+/// and `@WIDEN@` the body of the widening function.
+const WIDE_AGGREGATE_SHADER: &str = r"
+@group(0) @binding(0) var<storage, read> input: array<@ELEMENT@>;
+@group(0) @binding(1) var<storage, read_write> partials: array<vec4<u32>>;
+
+var<workgroup> shared_sum: array<vec4<u32>, 256>;
+var<workgroup> shared_min: array<vec4<u32>, 256>;
+var<workgroup> shared_max: array<vec4<u32>, 256>;
+
+fn widen(value: @ELEMENT@) -> vec4<u32> {
+    @WIDEN@
+}
+
+fn add96(a: vec4<u32>, b: vec4<u32>) -> vec4<u32> {
+    let lo = a.x + b.x;
+    let mid = a.y + b.y;
+    let mid_total = mid + select(0u, 1u, lo < a.x);
+    let hi_carry = select(0u, 1u, mid < a.y) + select(0u, 1u, mid_total < mid);
+    return vec4<u32>(lo, mid_total, a.z + b.z + hi_carry, 0u);
+}
+
+fn less96(a: vec4<u32>, b: vec4<u32>) -> bool {
+    if (a.z != b.z) {
+        return bitcast<i32>(a.z) < bitcast<i32>(b.z);
+    }
+    if (a.y != b.y) {
+        return a.y < b.y;
+    }
+    return a.x < b.x;
+}
+
+@compute @workgroup_size(256)
+fn wide_aggregate(@builtin(global_invocation_id) global_id: vec3<u32>,
+                  @builtin(local_invocation_id) local_id: vec3<u32>,
+                  @builtin(workgroup_id) workgroup_id: vec3<u32>) {
+    let tid = local_id.x;
+    let gid = global_id.x;
+    let input_size = arrayLength(&input);
+
+    // Load data into shared memory (padding is each accumulator's identity)
+    if (gid < input_size) {
+        let value = widen(input[gid]);
+        shared_sum[tid] = value;
+        shared_min[tid] = value;
+        shared_max[tid] = value;
+    } else {
+        shared_sum[tid] = vec4<u32>(0u, 0u, 0u, 0u);
+        shared_min[tid] = vec4<u32>(0xffffffffu, 0xffffffffu, 0x7fffffffu, 0u);
+        shared_max[tid] = vec4<u32>(0u, 0u, 0x80000000u, 0u);
+    }
+    workgroupBarrier();
+
+    // Parallel reduction of all three accumulators in shared memory
+    var stride = 128u;
+    while (stride > 0u) {
+        if (tid < stride) {
+            shared_sum[tid] = add96(shared_sum[tid], shared_sum[tid + stride]);
+            if (less96(shared_min[tid + stride], shared_min[tid])) {
+                shared_min[tid] = shared_min[tid + stride];
+            }
+            if (less96(shared_max[tid], shared_max[tid + stride])) {
+                shared_max[tid] = shared_max[tid + stride];
+            }
+        }
+        workgroupBarrier();
+        stride = stride / 2u;
+    }
+
+    if (tid == 0u) {
+        partials[3u * workgroup_id.x] = shared_sum[0];
+        partials[3u * workgroup_id.x + 1u] = shared_min[0];
+        partials[3u * workgroup_id.x + 2u] = shared_max[0];
+    }
+}
+";
+
+/// COUNT, exact SUM, MIN and MAX of an `Int64`, `UInt64` or `UInt32`
+/// column, from one GPU pass
+///
+/// Values are widened to `i128`, so one type covers signed and unsigned
+/// input and sums never wrap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WideAggregates {
+    /// Rows aggregated
+    pub count: u64,
+    /// Exact sum
+    pub sum: i128,
+    /// Smallest value (`i128::MAX` for empty input)
+    pub min: i128,
+    /// Largest value (`i128::MIN` for empty input)
+    pub max: i128,
+}
+
+impl Default for WideAggregates {
+    fn default() -> Self {
+        Self { count: 0, sum: 0, min: i128::MAX, max: i128::MIN }
+    }
+}
+
+impl WideAggregates {
+    /// Combine the aggregates of two disjoint inputs
+    #[must_use]
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            count: self.count + other.count,
+            sum: self.sum + other.sum,
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+}
+
+/// Element type and widening body of the wide shader for `data_type`
+const fn wide_element(data_type: &DataType) -> Option<(&'static str, &'static str)> {
+    match data_type {
+        DataType::Int64 => Some((
+            "vec2<u32>",
+            "let sign = select(0u, 0xffffffffu, (value.y & 0x80000000u) != 0u);
+    return vec4<u32>(value.x, value.y, sign, 0u);",
+        )),
+        DataType::UInt64 => Some(("vec2<u32>", "return vec4<u32>(value.x, value.y, 0u, 0u);")),
+        DataType::UInt32 => Some(("u32", "return vec4<u32>(value, 0u, 0u, 0u);")),
+        _ => None,
+    }
+}
+
+/// Whether [`multi_aggregate_wide`] reads columns of `data_type`
+#[must_use]
+pub const fn wide_supports(data_type: &DataType) -> bool {
+    wide_element(data_type).is_some()
+}
+
+/// 96-bit two's complement words `(lo, mid, hi)` as an i128
+#[allow(clippy::cast_possible_wrap)]
+fn from_words(words: &[u32]) -> i128 {
+    let bits = u128::from(words[0]) | (u128::from(words[1]) << 32) | (u128::from(words[2]) << 64);
+    // Sign-extend from bit 95
+    ((bits << 32) as i128) >> 32
+}
+
+/// COUNT, SUM, MIN and MAX of an `Int64`, `UInt64` or `UInt32` column in one
+/// dispatch and one readback
+///
+/// 64-bit values are uploaded as they are stored (little-endian `(lo, hi)`
+/// word pairs) and widened to 96 bits on the GPU, so sums cannot wrap. NULL
+/// slots are read as their underlying values; callers reject NULLs first.
+///
+/// # Errors
+/// Returns [`Error::InvalidInput`] for other column types, or error if GPU
+/// execution fails or `budget` stops the readback (timeout, cancellation,
+/// lost device)
+pub async fn multi_aggregate_wide(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    pipelines: &PipelineCache,
+    buffers: &BufferPool,
+    data: &dyn Array,
+    workgroup_size: u32,
+    budget: &PollBudget,
+) -> Result<WideAggregates> {
+    let Some((element, widen)) = wide_element(data.data_type()) else {
+        return Err(Error::InvalidInput(format!(
+            "GPU 64-bit kernels do not support {:?} columns",
+            data.data_type()
+        )));
+    };
+    let input: &[u8] = if let Some(array) = data.as_any().downcast_ref::<Int64Array>() {
+        bytemuck::cast_slice(array.values())
+    } else if let Some(array) = data.as_any().downcast_ref::<UInt64Array>() {
+        bytemuck::cast_slice(array.values())
+    } else if let Some(array) = data.as_any().downcast_ref::<UInt32Array>() {
+        bytemuck::cast_slice(array.values())
+    } else {
+        return Err(Error::Other(format!("Failed to downcast {:?} column", data.data_type())));
+    };
+
+    let shader = specialize_workgroup_size(WIDE_AGGREGATE_SHADER, workgroup_size)
+        .replace("@ELEMENT@", element)
+        .replace("@WIDEN@", widen);
+    // Three (lo, mid, hi, padding) partials per workgroup: sum, min, max
+    let partials = run_partials::<u32>(
+        device,
+        queue,
+        pipelines,
+        buffers,
+        &shader,
+        "wide_aggregate",
+        input,
+        data.len(),
+        12,
+        workgroup_size,
+        budget,
+    )
+    .await?;
+
+    Ok(partials.chunks_exact(12).fold(
+        WideAggregates { count: data.len() as u64, ..WideAggregates::default() },
+        |acc, partial| WideAggregates {
+            count: acc.count,
+            sum: acc.sum + from_words(&partial[0..3]),
+            min: acc.min.min(from_words(&partial[4..7])),
+            max: acc.max.max(from_words(&partial[8..11])),
+        },
+    ))
+}
+
+/// Most hash slots a GPU GROUP BY allocates (48 MB of slot tables)
+///
+/// Inputs with more distinct keys than this overflow the table and fail;
+/// run those on the SIMD path.
+pub const GROUP_BY_MAX_SLOTS: usize = 1 << 22;
+
+/// Per-group aggregates for [`group_by_i32`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupByOp {
+    /// SUM (wrapping on overflow)
+    Sum,
+    /// MIN
+    Min,
+    /// MAX
+    Max,
+    /// COUNT(*) (`values` are ignored)
+    Count,
+}
+
+impl GroupByOp {
+    /// Shader source specialized for `workgroup_size`, and the aggregate's identity
+    #[must_use]
+    pub fn shader(self, workgroup_size: u32) -> (String, i32) {
+        let (update, identity) = match self {
+            Self::Sum | Self::Count => ("atomicAdd", 0),
+            Self::Min => ("atomicMin", i32::MAX),
+            Self::Max => ("atomicMax", i32::MIN),
+        };
+        let source = GROUP_BY_I32_SHADER.replace("@AGGREGATE@", update);
+        (specialize_workgroup_size(&source, workgroup_size), identity)
+    }
+}
+
+/// Result of a GPU GROUP BY: one entry per distinct key, sorted by key
+/// ([`group_by_top_k_i32`]: the selected groups, in rank order)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GroupedI32 {
+    /// Distinct keys
+    pub keys: Vec<i32>,
+    /// Aggregate per key (the row count for [`GroupByOp::Count`])
+    pub values: Vec<i32>,
+    /// Rows per key
+    pub counts: Vec<u32>,
+}
+
+impl GroupedI32 {
+    /// Unzip `(key, aggregate, count)` groups, keeping their order
+    #[allow(clippy::cast_possible_wrap)]
+    fn collect(op: GroupByOp, groups: Vec<(i32, i32, u32)>) -> Self {
+        let mut grouped = Self {
+            keys: Vec::with_capacity(groups.len()),
+            values: Vec::with_capacity(groups.len()),
+            counts: Vec::with_capacity(groups.len()),
+        };
+        for (key, value, count) in groups {
+            grouped.keys.push(key);
+            grouped.values.push(if op == GroupByOp::Count { count as i32 } else { value });
+            grouped.counts.push(count);
+        }
+        grouped
+    }
+}
+
+/// Device bytes a GROUP BY over `rows` rows allocates: `columns` i32 input
+/// columns and a hash table of `words` i32 per slot
+pub(crate) fn group_by_bytes(rows: usize, columns: usize, words: usize) -> u64 {
+    let slots = group_by_slots(rows) + 1;
+    ((rows * columns + slots * words) * 4) as u64
+}
+
+/// Hash slots for `rows` input rows: a power of two at least twice the
+/// worst-case group count, capped at [`GROUP_BY_MAX_SLOTS`]
+fn group_by_slots(rows: usize) -> usize {
+    (rows * 2).next_power_of_two().clamp(2, GROUP_BY_MAX_SLOTS)
+}
+
+/// Hash GROUP BY on GPU: aggregate `values` per distinct `keys` value
+///
+/// Groups are built in a GPU hash table (open addressing, claimed with
+/// atomic compare-exchange) and read back once; the host only compacts
+/// occupied slots and sorts by key, so output order is deterministic. NULLs
+/// are not supported (filter them, or use the SIMD path).
+///
+/// # Errors
+/// Returns error if `keys` and `values` differ in length or contain NULLs,
+/// the input has more than [`GROUP_BY_MAX_SLOTS`] distinct keys, GPU
+/// execution fails, or `budget` stops the readback
+///
+/// # Panics
+/// May panic if buffer mapping fails (internal GPU error)
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+#[allow(clippy::too_many_arguments)]
+pub async fn group_by_i32(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    pipelines: &PipelineCache,
+    buffers: &BufferPool,
+    op: GroupByOp,
+    keys: &Int32Array,
+    values: &Int32Array,
+    workgroup_size: u32,
+    budget: &PollBudget,
+) -> Result<GroupedI32> {
+    check_group_by_input(keys, values)?;
+    if keys.is_empty() {
+        return Ok(GroupedI32::default());
+    }
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Group By Encoder"),
     });
+    let table = encode_group_by(
+        device,
+        queue,
+        pipelines,
+        buffers,
+        &mut encoder,
+        op,
+        keys,
+        values,
+        workgroup_size,
+    );
+    let (slots, table_size) = (table.slots, (table.slots * 4) as u64);
+
+    // One readback: slot keys, counts, values, then the overflow flag
+    let mut staging_buffer = buffers.acquire(
+        device,
+        "Group By Staging Buffer",
+        table_size * 3 + 4,
+        wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+    );
+    encoder.copy_buffer_to_buffer(
+        table.slot_keys.buffer(),
+        0,
+        staging_buffer.buffer(),
+        0,
+        table_size,
+    );
+    encoder.copy_buffer_to_buffer(
+        table.slot_counts.buffer(),
+        0,
+        staging_buffer.buffer(),
+        table_size,
+        table_size,
+    );
+    encoder.copy_buffer_to_buffer(
+        table.slot_values.buffer(),
+        0,
+        staging_buffer.buffer(),
+        table_size * 2,
+        table_size,
+    );
+    encoder.copy_buffer_to_buffer(
+        table.overflow.buffer(),
+        0,
+        staging_buffer.buffer(),
+        table_size * 3,
+        4,
+    );
+    queue.submit(Some(encoder.finish()));
+
+    staging_buffer.map_read(device, budget).await?;
+    let buffer_slice = staging_buffer.slice();
+
+    let mapped = buffer_slice.get_mapped_range();
+    let words: &[u32] = bytemuck::cast_slice(&mapped);
+    let (read_keys, rest) = words.split_at(slots);
+    let (read_counts, rest) = rest.split_at(slots);
+    let (read_values, read_overflow) = rest.split_at(slots);
+
+    let mut groups: Vec<(i32, i32, u32)> = (0..slots)
+        .filter(|&slot| read_counts[slot] > 0)
+        .map(|slot| {
+            // The reserved last slot holds the rows keyed by the marker itself
+            let key = if slot == slots - 1 { i32::MIN } else { read_keys[slot] as i32 };
+            (key, read_values[slot] as i32, read_counts[slot])
+        })
+        .collect();
+    let overflowed = read_overflow[0] != 0;
+    drop(mapped);
+    staging_buffer.unmap();
+
+    if overflowed {
+        return Err(table.overflow_error());
+    }
+
+    groups.sort_unstable_by_key(|&(key, _, _)| key);
+    Ok(GroupedI32::collect(op, groups))
+}
+
+/// Top-K groups of a hash GROUP BY, selected on the GPU
+///
+/// Equivalent to `SELECT key, op(value) FROM t GROUP BY key ORDER BY
+/// op(value) [ASC|DESC] LIMIT k`, with ties broken by ascending key. The
+/// hash table never leaves the GPU: a radix select over the aggregates
+/// finds the K-th group in eight histogram rounds and only the selected
+/// groups (12 bytes each) are read back, instead of the whole table. Groups
+/// come back in rank order, best first. [`GroupByOp::Count`] ranks by row
+/// count.
+///
+/// # Errors
+/// As [`group_by_i32`]
+///
+/// # Panics
+/// May panic if buffer mapping fails (internal GPU error)
+#[allow(clippy::too_many_lines)]
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+#[allow(clippy::too_many_arguments)]
+pub async fn group_by_top_k_i32(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    pipelines: &PipelineCache,
+    buffers: &BufferPool,
+    op: GroupByOp,
+    keys: &Int32Array,
+    values: &Int32Array,
+    k: usize,
+    order: SortOrder,
+    workgroup_size: u32,
+    budget: &PollBudget,
+) -> Result<GroupedI32> {
+    check_group_by_input(keys, values)?;
+    if keys.is_empty() || k == 0 {
+        return Ok(GroupedI32::default());
+    }
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Group By Top-K Encoder"),
+    });
+    let table = encode_group_by(
+        device,
+        queue,
+        pipelines,
+        buffers,
+        &mut encoder,
+        op,
+        keys,
+        values,
+        workgroup_size,
+    );
+    let slots = table.slots;
+    // No more groups than slots, so K past that selects everything
+    let k = k.min(slots);
+
+    let order_value = if op == GroupByOp::Count {
+        "slot_counts[slot]"
+    } else {
+        "bitcast<u32>(slot_values[slot]) ^ 0x80000000u"
+    };
+    let descending = matches!(order, SortOrder::Descending);
+    let shader_source = specialize_workgroup_size(
+        &GROUP_BY_TOP_K_SHADER
+            .replace("@ORDER_VALUE@", order_value)
+            .replace("@DESCENDING@", if descending { "true" } else { "false" }),
+        workgroup_size,
+    );
+    // Table (read-only): slot keys, counts, values; selection: state,
+    // histogram, emitted count, selected groups
+    let kernel = pipelines.get_or_compile(
+        device,
+        &shader_source,
+        &["topk_histogram", "topk_select", "topk_emit"],
+        &[true, true, true, false, false, false, false],
+    );
+
+    // State: prefix (hi, lo), mask (hi, lo), remaining = k, round = 0
+    let state = buffers.upload(
+        device,
+        queue,
+        "Top-K Select State",
+        bytemuck::cast_slice(&[0u32, 0, 0, 0, k as u32, 0]),
+        wgpu::BufferUsages::STORAGE,
+    );
+    let histogram = buffers.upload(
+        device,
+        queue,
+        "Top-K Histogram",
+        bytemuck::cast_slice(&[0u32; 256]),
+        wgpu::BufferUsages::STORAGE,
+    );
+    let emitted = buffers.upload(
+        device,
+        queue,
+        "Top-K Emitted",
+        bytemuck::cast_slice(&[0u32]),
+        wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+    );
+    let top_size = (k * 12) as u64;
+    let top = buffers.acquire(
+        device,
+        "Top-K Groups",
+        top_size,
+        wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+    );
 
-    // Create bind group
     let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        label: Some("MAX Bind Group"),
-        layout: &bind_group_layout,
+        label: Some("Top-K Bind Group"),
+        layout: kernel.bind_group_layout(),
         entries: &[
-            wgpu::BindGroupEntry { binding: 0, resource: input_buffer.as_entire_binding() },
-            wgpu::BindGroupEntry { binding: 1, resource: output_buffer.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 0, resource: table.slot_keys.binding() },
+            wgpu::BindGroupEntry { binding: 1, resource: table.slot_counts.binding() },
+            wgpu::BindGroupEntry { binding: 2, resource: table.slot_values.binding() },
+            wgpu::BindGroupEntry { binding: 3, resource: state.binding() },
+            wgpu::BindGroupEntry { binding: 4, resource: histogram.binding() },
+            wgpu::BindGroupEntry { binding: 5, resource: emitted.binding() },
+            wgpu::BindGroupEntry { binding: 6, resource: top.binding() },
         ],
     });
 
-    // Execute compute shader
-    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-        label: Some("MAX Compute Encoder"),
+    // One pass per dispatch so each round sees the previous round's writes
+    let slot_workgroups = (slots as u32).div_ceil(workgroup_size);
+    let mut dispatch = |pipeline: &wgpu::ComputePipeline, workgroups: u32| {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Top-K Pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(pipeline);
+        compute_pass.set_bind_group(0, &bind_group, &[]);
+        compute_pass.dispatch_workgroups(workgroups, 1, 1);
+    };
+    let (histogram_pipeline, select_pipeline, emit_pipeline) =
+        (kernel.entry(0), kernel.entry(1), kernel.entry(2));
+    for _ in 0..8 {
+        dispatch(histogram_pipeline, slot_workgroups);
+        dispatch(select_pipeline, 1);
+    }
+    dispatch(emit_pipeline, slot_workgroups);
+
+    // One readback: emitted count, overflow flag, then the K groups
+    let mut staging_buffer = buffers.acquire(
+        device,
+        "Top-K Staging Buffer",
+        8 + top_size,
+        wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+    );
+    encoder.copy_buffer_to_buffer(emitted.buffer(), 0, staging_buffer.buffer(), 0, 4);
+    encoder.copy_buffer_to_buffer(table.overflow.buffer(), 0, staging_buffer.buffer(), 4, 4);
+    encoder.copy_buffer_to_buffer(top.buffer(), 0, staging_buffer.buffer(), 8, top_size);
+    queue.submit(Some(encoder.finish()));
+
+    staging_buffer.map_read(device, budget).await?;
+    let buffer_slice = staging_buffer.slice();
+
+    let mapped = buffer_slice.get_mapped_range();
+    let words: &[u32] = bytemuck::cast_slice(&mapped);
+    let selected = (words[0] as usize).min(k);
+    let overflowed = words[1] != 0;
+    let mut groups: Vec<(i32, i32, u32)> = words[2..]
+        .chunks_exact(3)
+        .take(selected)
+        .map(|group| (group[0] as i32, group[1] as i32, group[2]))
+        .collect();
+    drop(mapped);
+    staging_buffer.unmap();
+
+    if overflowed {
+        return Err(table.overflow_error());
+    }
+
+    // Emitted in arbitrary order; rank them as the shader did
+    groups.sort_unstable_by(|&(a_key, a_value, a_count), &(b_key, b_value, b_count)| {
+        let by_value =
+            if op == GroupByOp::Count { a_count.cmp(&b_count) } else { a_value.cmp(&b_value) };
+        let by_value = if descending { by_value.reverse() } else { by_value };
+        by_value.then(a_key.cmp(&b_key))
+    });
+    Ok(GroupedI32::collect(op, groups))
+}
+
+/// Validate GROUP BY input: equal lengths, no NULLs
+fn check_group_by_input(keys: &Int32Array, values: &Int32Array) -> Result<()> {
+    if keys.len() != values.len() {
+        return Err(Error::InvalidInput(format!(
+            "GROUP BY keys and values differ in length ({} vs {})",
+            keys.len(),
+            values.len()
+        )));
+    }
+    if keys.null_count() > 0 || values.null_count() > 0 {
+        return Err(Error::InvalidInput("GPU GROUP BY does not support NULLs".to_string()));
+    }
+    Ok(())
+}
+
+/// GPU hash table of a GROUP BY, aggregated but not read back
+struct GroupTable {
+    /// Slots, including the reserved one for the empty-slot marker key
+    slots: usize,
+    slot_keys: PooledBuffer,
+    slot_counts: PooledBuffer,
+    slot_values: PooledBuffer,
+    overflow: PooledBuffer,
+}
+
+impl GroupTable {
+    fn overflow_error(&self) -> Error {
+        Error::Other(format!(
+            "GPU GROUP BY hash table overflow (more than {} distinct keys); use the SIMD backend",
+            self.slots - 1
+        ))
+    }
+}
+
+/// Record the hash aggregation pass over non-empty input into `encoder`
+#[allow(clippy::cast_possible_truncation, clippy::too_many_arguments)]
+fn encode_group_by(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    pipelines: &PipelineCache,
+    buffers: &BufferPool,
+    encoder: &mut wgpu::CommandEncoder,
+    op: GroupByOp,
+    keys: &Int32Array,
+    values: &Int32Array,
+    workgroup_size: u32,
+) -> GroupTable {
+    let input_size = keys.len();
+    let (shader_source, identity) = op.shader(workgroup_size);
+    // One extra slot for rows whose key is the empty-slot marker (i32::MIN)
+    let slots = group_by_slots(input_size) + 1;
+    let slot_table = |label: &str, init: &[u8]| {
+        buffers.upload(
+            device,
+            queue,
+            label,
+            init,
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        )
+    };
+
+    let keys_buffer = buffers.upload(
+        device,
+        queue,
+        "Group By Keys",
+        bytemuck::cast_slice(keys.values()),
+        wgpu::BufferUsages::STORAGE,
+    );
+    let values_buffer = buffers.upload(
+        device,
+        queue,
+        "Group By Values",
+        bytemuck::cast_slice(values.values()),
+        wgpu::BufferUsages::STORAGE,
+    );
+    let slot_keys = slot_table("Group By Slot Keys", bytemuck::cast_slice(&vec![i32::MIN; slots]));
+    let slot_counts = slot_table("Group By Slot Counts", bytemuck::cast_slice(&vec![0u32; slots]));
+    let slot_values =
+        slot_table("Group By Slot Values", bytemuck::cast_slice(&vec![identity; slots]));
+    let overflow = slot_table("Group By Overflow", bytemuck::cast_slice(&[0u32]));
+
+    // Keys, values (read-only); slot keys, counts, values, overflow flag
+    let kernel = pipelines.get_or_compile(
+        device,
+        &shader_source,
+        &["group_by_aggregate"],
+        &[true, true, false, false, false, false],
+    );
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Group By Bind Group"),
+        layout: kernel.bind_group_layout(),
+        entries: &[
+            wgpu::BindGroupEntry { binding: 0, resource: keys_buffer.binding() },
+            wgpu::BindGroupEntry { binding: 1, resource: values_buffer.binding() },
+            wgpu::BindGroupEntry { binding: 2, resource: slot_keys.binding() },
+            wgpu::BindGroupEntry { binding: 3, resource: slot_counts.binding() },
+            wgpu::BindGroupEntry { binding: 4, resource: slot_values.binding() },
+            wgpu::BindGroupEntry { binding: 5, resource: overflow.binding() },
+        ],
     });
 
     {
         let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-            label: Some("MAX Compute Pass"),
+            label: Some("Group By Pass"),
             timestamp_writes: None,
         });
-        compute_pass.set_pipeline(&compute_pipeline);
+        compute_pass.set_pipeline(kernel.pipeline());
         compute_pass.set_bind_group(0, &bind_group, &[]);
-
-        let workgroup_count = (input_size as u32).div_ceil(WORKGROUP_SIZE);
+        let workgroup_count = (input_size as u32).div_ceil(workgroup_size);
         compute_pass.dispatch_workgroups(workgroup_count, 1, 1);
     }
 
-    // Read result buffer
-    let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("MAX Staging Buffer"),
-        size: 4, // i32 = 4 bytes
-        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
-        mapped_at_creation: false,
-    });
+    GroupTable { slots, slot_keys, slot_counts, slot_values, overflow }
+}
 
-    encoder.copy_buffer_to_buffer(&output_buffer, 0, &staging_buffer, 0, 4);
-    queue.submit(Some(encoder.finish()));
+/// Pipeline running `shader`, the kernel compiled from `fused`
+pub(crate) fn fused_group_by_pipeline(
+    device: &wgpu::Device,
+    pipelines: &PipelineCache,
+    shader: &wgpu::ShaderModule,
+    fused: &FusedGroupBy,
+    workgroup_size: u32,
+) -> Arc<CachedPipeline> {
+    // Columns (read-only); slot keys, counts, value words, overflow flag
+    pipelines.get_or_create(
+        device,
+        &fused.cache_key(workgroup_size),
+        shader,
+        &["fused_group_by"],
+        &[true, false, false, false, false],
+    )
+}
 
-    // Map buffer and read result
-    let buffer_slice = staging_buffer.slice(..);
-    let (sender, receiver) = futures_intrusive::channel::shared::oneshot_channel();
-    buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-        sender.send(result).expect("Failed to send buffer mapping result through channel");
+/// Filter + hash GROUP BY + aggregates of `batch` in one GPU pass
+///
+/// Runs `shader`, compiled from `fused` by
+/// [`JitCompiler::compile_fused_group_by`](super::jit::JitCompiler::compile_fused_group_by):
+/// the input columns are uploaded once, every thread filters, groups and
+/// aggregates its row, and the hash table is read back once. Groups come
+/// back sorted by key.
+///
+/// # Errors
+/// Returns error if an input column is missing, not `Int32` or has NULLs,
+/// the input exceeds the device's buffer or dispatch limits, the input has
+/// more than [`GROUP_BY_MAX_SLOTS`] distinct keys, GPU execution fails, or
+/// `budget` stops the readback
+///
+/// # Panics
+/// May panic if buffer mapping fails (internal GPU error)
+#[allow(clippy::too_many_lines)]
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+#[allow(clippy::too_many_arguments)]
+pub async fn fused_group_by(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    pipelines: &PipelineCache,
+    buffers: &BufferPool,
+    shader: &wgpu::ShaderModule,
+    fused: &FusedGroupBy,
+    batch: &RecordBatch,
+    workgroup_size: u32,
+    budget: &PollBudget,
+) -> Result<RecordBatch> {
+    let inputs = fused.inputs(batch)?;
+    let rows = batch.num_rows();
+    if rows == 0 {
+        return fused.output(&[]);
+    }
+
+    let limits = device.limits();
+    let input_bytes = rows * inputs.len() * 4;
+    let workgroup_count = rows.div_ceil(workgroup_size as usize);
+    if input_bytes > limits.max_storage_buffer_binding_size as usize
+        || workgroup_count > limits.max_compute_workgroups_per_dimension as usize
+    {
+        return Err(Error::InvalidInput(format!(
+            "{rows} rows x {} columns exceed the GPU's limits for one fused pass",
+            inputs.len()
+        )));
+    }
+
+    let mut columns: Vec<i32> = Vec::with_capacity(rows * inputs.len());
+    for input in &inputs {
+        columns.extend_from_slice(input.values());
+    }
+    // One extra slot for rows whose key is the empty-slot marker (i32::MIN);
+    // without GROUP BY every row goes there
+    let slots = if fused.is_grouped() { group_by_slots(rows) + 1 } else { 1 };
+    let words = 2 * fused.num_aggregates();
+    let mut initial_values = vec![0i32; (words * slots).max(1)];
+    for (j, identity) in fused.identities().into_iter().enumerate() {
+        initial_values[2 * j * slots..(2 * j + 1) * slots].fill(identity);
+    }
+
+    let slot_table = |label: &str, init: &[u8]| {
+        buffers.upload(
+            device,
+            queue,
+            label,
+            init,
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        )
+    };
+    let columns_buffer = buffers.upload(
+        device,
+        queue,
+        "Fused Group By Columns",
+        bytemuck::cast_slice(&columns),
+        wgpu::BufferUsages::STORAGE,
+    );
+    let table = GroupTable {
+        slots,
+        slot_keys: slot_table(
+            "Fused Group By Slot Keys",
+            bytemuck::cast_slice(&vec![i32::MIN; slots]),
+        ),
+        slot_counts: slot_table(
+            "Fused Group By Slot Counts",
+            bytemuck::cast_slice(&vec![0u32; slots]),
+        ),
+        slot_values: slot_table(
+            "Fused Group By Slot Values",
+            bytemuck::cast_slice(&initial_values),
+        ),
+        overflow: slot_table("Fused Group By Overflow", bytemuck::cast_slice(&[0u32])),
+    };
+
+    let kernel = fused_group_by_pipeline(device, pipelines, shader, fused, workgroup_size);
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Fused Group By Bind Group"),
+        layout: kernel.bind_group_layout(),
+        entries: &[
+            wgpu::BindGroupEntry { binding: 0, resource: columns_buffer.binding() },
+            wgpu::BindGroupEntry { binding: 1, resource: table.slot_keys.binding() },
+            wgpu::BindGroupEntry { binding: 2, resource: table.slot_counts.binding() },
+            wgpu::BindGroupEntry { binding: 3, resource: table.slot_values.binding() },
+            wgpu::BindGroupEntry { binding: 4, resource: table.overflow.binding() },
+        ],
     });
-    device.poll(wgpu::Maintain::Wait);
 
-    receiver
-        .receive()
-        .await
-        .ok_or_else(|| Error::Other("Failed to receive mapping result".to_string()))?
-        .map_err(|e| Error::Other(format!("Buffer mapping failed: {e:?}")))?;
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Fused Group By Encoder"),
+    });
+    {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Fused Group By Pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(kernel.pipeline());
+        compute_pass.set_bind_group(0, &bind_group, &[]);
+        compute_pass.dispatch_workgroups(workgroup_count as u32, 1, 1);
+    }
 
-    let data = buffer_slice.get_mapped_range();
-    let result = i32::from_le_bytes(
-        data[0..4].try_into().expect("Buffer must contain at least 4 bytes for i32 result"),
+    // One readback: slot keys, counts, aggregate words, then the overflow flag
+    let table_size = (slots * 4) as u64;
+    let values_size = (initial_values.len() * 4) as u64;
+    let mut staging_buffer = buffers.acquire(
+        device,
+        "Fused Group By Staging Buffer",
+        table_size * 2 + values_size + 4,
+        wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
     );
-    drop(data);
+    encoder.copy_buffer_to_buffer(
+        table.slot_keys.buffer(),
+        0,
+        staging_buffer.buffer(),
+        0,
+        table_size,
+    );
+    encoder.copy_buffer_to_buffer(
+        table.slot_counts.buffer(),
+        0,
+        staging_buffer.buffer(),
+        table_size,
+        table_size,
+    );
+    encoder.copy_buffer_to_buffer(
+        table.slot_values.buffer(),
+        0,
+        staging_buffer.buffer(),
+        table_size * 2,
+        values_size,
+    );
+    encoder.copy_buffer_to_buffer(
+        table.overflow.buffer(),
+        0,
+        staging_buffer.buffer(),
+        table_size * 2 + values_size,
+        4,
+    );
+    queue.submit(Some(encoder.finish()));
+
+    staging_buffer.map_read(device, budget).await?;
+    let buffer_slice = staging_buffer.slice();
+
+    let mapped = buffer_slice.get_mapped_range();
+    let read: &[u32] = bytemuck::cast_slice(&mapped);
+    let (read_keys, rest) = read.split_at(slots);
+    let (read_counts, rest) = rest.split_at(slots);
+    let (read_values, read_overflow) = rest.split_at(initial_values.len());
+
+    let mut groups: Vec<FusedGroup> = (0..slots)
+        .filter(|&slot| read_counts[slot] > 0)
+        .map(|slot| {
+            // The reserved last slot holds the rows keyed by the marker itself
+            let key = if slot == slots - 1 { i32::MIN } else { read_keys[slot] as i32 };
+            let aggregates = (0..fused.num_aggregates())
+                .map(|j| {
+                    (read_values[2 * j * slots + slot], read_values[(2 * j + 1) * slots + slot])
+                })
+                .collect();
+            (key, read_counts[slot], aggregates)
+        })
+        .collect();
+    let overflowed = read_overflow[0] != 0;
+    drop(mapped);
     staging_buffer.unmap();
 
-    Ok(result)
+    if overflowed {
+        return Err(table.overflow_error());
+    }
+
+    groups.sort_unstable_by_key(|&(key, _, _)| key);
+    fused.output(&groups)
+}
+
+/// Execute SUM ... GROUP BY on GPU (i32)
+///
+/// # Errors
+/// Returns error if GPU execution fails (see [`group_by_i32`])
+pub async fn group_by_sum_i32(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    keys: &Int32Array,
+    values: &Int32Array,
+) -> Result<GroupedI32> {
+    let (budget, pipelines, buffers) =
+        (PollBudget::default(), PipelineCache::new(), BufferPool::new());
+    group_by_i32(
+        device,
+        queue,
+        &pipelines,
+        &buffers,
+        GroupByOp::Sum,
+        keys,
+        values,
+        DEFAULT_WORKGROUP_SIZE,
+        &budget,
+    )
+    .await
+}
+
+/// Execute MIN ... GROUP BY on GPU (i32)
+///
+/// # Errors
+/// Returns error if GPU execution fails (see [`group_by_i32`])
+pub async fn group_by_min_i32(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    keys: &Int32Array,
+    values: &Int32Array,
+) -> Result<GroupedI32> {
+    let (budget, pipelines, buffers) =
+        (PollBudget::default(), PipelineCache::new(), BufferPool::new());
+    group_by_i32(
+        device,
+        queue,
+        &pipelines,
+        &buffers,
+        GroupByOp::Min,
+        keys,
+        values,
+        DEFAULT_WORKGROUP_SIZE,
+        &budget,
+    )
+    .await
+}
+
+/// Execute MAX ... GROUP BY on GPU (i32)
+///
+/// # Errors
+/// Returns error if GPU execution fails (see [`group_by_i32`])
+pub async fn group_by_max_i32(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    keys: &Int32Array,
+    values: &Int32Array,
+) -> Result<GroupedI32> {
+    let (budget, pipelines, buffers) =
+        (PollBudget::default(), PipelineCache::new(), BufferPool::new());
+    group_by_i32(
+        device,
+        queue,
+        &pipelines,
+        &buffers,
+        GroupByOp::Max,
+        keys,
+        values,
+        DEFAULT_WORKGROUP_SIZE,
+        &budget,
+    )
+    .await
+}
+
+/// Execute COUNT(*) ... GROUP BY on GPU (i32 keys)
+///
+/// # Errors
+/// Returns error if GPU execution fails (see [`group_by_i32`])
+pub async fn group_by_count_i32(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    keys: &Int32Array,
+) -> Result<GroupedI32> {
+    let (budget, pipelines, buffers) =
+        (PollBudget::default(), PipelineCache::new(), BufferPool::new());
+    group_by_i32(
+        device,
+        queue,
+        &pipelines,
+        &buffers,
+        GroupByOp::Count,
+        keys,
+        keys,
+        DEFAULT_WORKGROUP_SIZE,
+        &budget,
+    )
+    .await
 }
 
 #[cfg(test)]
@@ -695,10 +2371,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_sum_f32_not_implemented() {
-        // sum_f32 is placeholder - should return error
-        let data = Float32Array::from(vec![1.0, 2.0, 3.0]);
-
+    async fn test_sum_f64_resolves_non_finite_on_host() {
         let instance = wgpu::Instance::default();
         let Some(adapter) = instance.request_adapter(&wgpu::RequestAdapterOptions::default()).await
         else {
@@ -712,8 +2385,28 @@ mod tests {
             return;
         };
 
-        let result = sum_f32(&device, &queue, &data).await;
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("not yet implemented"));
+        let infinite = Float64Array::from(vec![1.0, f64::INFINITY]);
+        let sum = sum_f64(&device, &queue, &infinite).await.unwrap();
+        assert!(sum.is_infinite() && sum.is_sign_positive());
+        for values in [vec![f64::INFINITY, f64::NEG_INFINITY], vec![1.0, f64::NAN]] {
+            let data = Float64Array::from(values);
+            assert!(sum_f64(&device, &queue, &data).await.unwrap().is_nan());
+        }
+
+        let too_wide = Float64Array::from(vec![1e300]);
+        assert!(matches!(sum_f64(&device, &queue, &too_wide).await, Err(Error::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_wide_words_round_trip_through_i128() {
+        let words = |value: i128| {
+            let bits = value as u128;
+            [bits as u32, (bits >> 32) as u32, (bits >> 64) as u32]
+        };
+        for value in [0, 1, -1, i128::from(i64::MIN), i128::from(u64::MAX), -(1 << 90)] {
+            assert_eq!(from_words(&words(value)), value);
+        }
+        assert!(wide_supports(&DataType::UInt32));
+        assert!(!wide_supports(&DataType::Int32));
     }
 }
@@ -6,8 +6,18 @@
 //!
 //! Architecture:
 //! - WGSL compute shaders for parallel reduction
-//! - Workgroup size: 256 threads (GPU warp size optimization)
+//! - Workgroup size: 256 threads by default, auto-tuned per device (see [`autotune`])
 //! - Two-stage reduction: workgroup-local + global
+//! - Hash GROUP BY: atomic open-addressing table on the device
+//! - VRAM budget: oversized columns are reduced in chunks (see [`vram`])
+//! - Buffer pool: device buffers are reused across dispatches (see [`buffers`])
+//! - Scheduling: concurrent queries take turns by priority (see [`scheduler`])
+//! - Cost model: the first engine measures its device (see [`calibrate`])
+//!
+//! On `wasm32` the same engine runs on the browser's WebGPU (build with
+//! `--cfg=web_sys_unstable_apis`): the adapter comes from `navigator.gpu`,
+//! and readbacks wait on browser timers instead of blocking. Only the async
+//! methods can be used there, and [`multigpu`] is native-only.
 //!
 //! References:
 //! - `HeavyDB` (2017): GPU aggregation patterns
@@ -15,13 +25,45 @@
 //! - Leis et al. (2014): Morsel-driven parallelism
 
 use crate::{Error, Result};
-use arrow::array::{Array, Float32Array, Int32Array};
+use arrow::array::{
+    Array, ArrayRef, Float32Array, Float64Array, Int32Array, Int64Array, UInt32Array, UInt64Array,
+};
 use wgpu;
-use wgpu::util::DeviceExt;
 
+pub mod autotune;
+pub mod buffers;
+pub mod calibrate;
 pub mod jit;
 pub mod kernels;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod multigpu;
+pub mod pipeline;
+pub mod scheduler;
+pub mod submit;
+pub mod vram;
+
+use crate::backend::calibration::{self, Calibration, CalibrationStore};
+use crate::query::AggregateFunction;
+use crate::topk::SortOrder;
+use crate::variance::{VarianceKind, WelfordState};
+use autotune::{TunableKernel, TuningResult, WorkgroupConfig, DEFAULT_WORKGROUP_SIZE};
+use buffers::{BufferPool, BufferPoolStats, PooledBuffer};
+use kernels::{GroupByOp, GroupedI32, I32Aggregates, ReduceOp, WideAggregates};
+use pipeline::{CachedPipeline, PipelineCache, PipelineCacheStats};
+use scheduler::{GpuPriority, GpuScheduler};
+use std::future::Future;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use submit::{CancellationToken, DeviceLostFlag, PollBudget};
+use vram::{VramBudget, DEFAULT_VRAM_BUDGET};
+
+/// Elements in the synthetic warmup sample used by [`GpuEngine::autotune`] (16 MB)
+const AUTOTUNE_SAMPLE_LEN: usize = 4 * 1024 * 1024;
+
+/// Timed runs per candidate workgroup size during auto-tuning
+const AUTOTUNE_ITERATIONS: usize = 5;
 
 /// GPU compute engine for aggregations
 pub struct GpuEngine {
@@ -31,14 +73,80 @@ pub struct GpuEngine {
     pub queue: wgpu::Queue,
     /// JIT compiler for kernel fusion
     jit: jit::JitCompiler,
+    /// Adapter identity used to key tuned workgroup sizes
+    device_key: String,
+    /// Tuned workgroup sizes (defaults until tuned or loaded)
+    workgroups: WorkgroupConfig,
+    /// Set by the device-lost callback; checked while waiting on submissions
+    device_lost: DeviceLostFlag,
+    /// Limits for waiting on each GPU submission
+    budget: PollBudget,
+    /// Device memory shared by this engine's dispatches
+    vram: VramBudget,
+    /// Compiled pipelines reused across calls
+    pipelines: PipelineCache,
+    /// Device buffers reused across calls
+    buffers: BufferPool,
+    /// Permits serializing dispatches of concurrent callers
+    scheduler: GpuScheduler,
+    /// Priority this engine's dispatches wait at
+    priority: GpuPriority,
+    /// Measured bandwidth and throughput (`None` if measuring failed)
+    calibration: Option<Calibration>,
 }
 
 impl GpuEngine {
     /// Initialize GPU engine
     ///
+    /// The first engine in the process also calibrates the cost model (see
+    /// [`calibrate`](Self::calibrate)); later engines on the same device
+    /// reuse its numbers.
+    ///
     /// # Errors
     /// Returns error if GPU initialization fails (no GPU available, driver issues, etc.)
     pub async fn new() -> Result<Self> {
+        let mut engine = Self::open().await?;
+        match calibration::installed() {
+            Some(installed) if installed.device == engine.device_key => {
+                engine.calibration = Some(installed);
+            }
+            installed => engine.calibrate_on_init(installed.is_none()).await,
+        }
+        Ok(engine)
+    }
+
+    /// Initialize GPU engine, reading the cost model calibration from `path`
+    ///
+    /// Like [`new`](Self::new), but a calibration saved in `path` for this
+    /// device is used (and installed) instead of measuring. If there is none,
+    /// the device is measured and the result saved to `path` for the next run.
+    ///
+    /// # Errors
+    /// Returns error if GPU initialization fails, or `path` exists but cannot
+    /// be parsed
+    pub async fn with_calibration_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut engine = Self::open().await?;
+        if !engine.load_calibration(&path)? {
+            engine.calibrate_on_init(true).await;
+            if engine.calibration.is_some() {
+                engine.save_calibration(&path)?;
+            }
+        }
+        Ok(engine)
+    }
+
+    /// Measure the device during initialization; failures only cost the
+    /// calibrated dispatch decisions
+    async fn calibrate_on_init(&mut self, install: bool) {
+        match self.calibrate().await {
+            Ok(measured) if install => calibration::install(measured),
+            Ok(_) => {}
+            Err(e) => tracing::warn!(error = %e, "GPU calibration failed, using constants"),
+        }
+    }
+
+    /// Acquire the adapter and device (uncalibrated)
+    async fn open() -> Result<Self> {
         // Request GPU adapter
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
             backends: wgpu::Backends::all(),
@@ -68,7 +176,329 @@ impl GpuEngine {
             .await
             .map_err(|e| Error::GpuInitFailed(format!("Failed to create device: {e}")))?;
 
-        Ok(Self { device, queue, jit: jit::JitCompiler::new() })
+        let device_key = autotune::device_key(&adapter.get_info());
+        let device_lost = DeviceLostFlag::register(&device);
+        let budget = PollBudget::default().with_device_lost(device_lost.clone());
+        let vram = VramBudget::new(DEFAULT_VRAM_BUDGET, &device.limits());
+
+        Ok(Self {
+            device,
+            queue,
+            jit: jit::JitCompiler::new(),
+            device_key,
+            workgroups: WorkgroupConfig::new(),
+            device_lost,
+            budget,
+            vram,
+            pipelines: PipelineCache::new(),
+            buffers: BufferPool::new(),
+            scheduler: GpuScheduler::default(),
+            priority: GpuPriority::default(),
+            calibration: None,
+        })
+    }
+
+    /// Fail GPU submissions that take longer than `timeout` with
+    /// [`Error::Timeout`] (default [`submit::DEFAULT_SUBMISSION_TIMEOUT`])
+    #[must_use]
+    pub fn with_submission_timeout(mut self, timeout: Duration) -> Self {
+        self.budget = self.budget.with_timeout(timeout);
+        self
+    }
+
+    /// Abort pending GPU submissions with [`Error::Cancelled`] once `token`
+    /// is cancelled (e.g. when the owning query times out)
+    #[must_use]
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.budget = self.budget.with_cancellation(token);
+        self
+    }
+
+    /// Limits applied while waiting on GPU submissions
+    #[must_use]
+    pub const fn submission_budget(&self) -> &PollBudget {
+        &self.budget
+    }
+
+    /// Cap the device memory this engine's dispatches may hold at once
+    /// (default [`vram::DEFAULT_VRAM_BUDGET`])
+    ///
+    /// Columns larger than the budget are reduced in chunks; GROUP BY inputs
+    /// whose hash table does not fit fail with [`Error::VramExceeded`].
+    #[must_use]
+    pub fn with_vram_budget(mut self, bytes: u64) -> Self {
+        self.vram = self.vram.with_capacity(bytes);
+        self
+    }
+
+    /// Device memory budget of this engine
+    #[must_use]
+    pub const fn vram_budget(&self) -> &VramBudget {
+        &self.vram
+    }
+
+    /// Compiled pipelines held, and how often calls reused one
+    #[must_use]
+    pub fn pipeline_cache_stats(&self) -> PipelineCacheStats {
+        self.pipelines.stats()
+    }
+
+    /// Keep at most `capacity` JIT-compiled kernels (default
+    /// [`jit::DEFAULT_KERNEL_CACHE_CAPACITY`]), evicting the least recently
+    /// used one, and its pipeline, when a new kernel is compiled
+    #[must_use]
+    pub fn with_kernel_cache_capacity(mut self, capacity: usize) -> Self {
+        self.jit = jit::JitCompiler::with_cache_capacity(capacity);
+        self
+    }
+
+    /// JIT-compiled kernels held, and how often queries reused one
+    #[must_use]
+    pub fn kernel_cache_stats(&self) -> jit::KernelCacheStats {
+        self.jit.cache_stats()
+    }
+
+    /// Compile the fused kernel and pipeline of `plan` before it first runs
+    ///
+    /// Warms the caches [`fused_group_by`](Self::fused_group_by) uses, so
+    /// latency-sensitive queries do not pay for shader compilation on their
+    /// first execution. `schema` is the schema of the batches the plan will
+    /// run on.
+    ///
+    /// # Errors
+    /// Returns error if the plan cannot be fused (see [`jit::FusedGroupBy`])
+    pub fn precompile(
+        &self,
+        plan: &crate::query::QueryPlan,
+        schema: &arrow::datatypes::Schema,
+    ) -> Result<()> {
+        let (fused, shader) =
+            self.jit.precompile(&self.device, plan, schema, DEFAULT_WORKGROUP_SIZE)?;
+        self.drop_evicted_pipelines();
+        kernels::fused_group_by_pipeline(
+            &self.device,
+            &self.pipelines,
+            &shader,
+            &fused,
+            DEFAULT_WORKGROUP_SIZE,
+        );
+        Ok(())
+    }
+
+    /// Drop the pipelines of kernels the JIT cache has evicted
+    fn drop_evicted_pipelines(&self) {
+        for key in self.jit.take_evicted() {
+            self.pipelines.remove_named(&key);
+        }
+    }
+
+    /// Take turns on the GPU through `scheduler` (shared with other engines
+    /// on the same device) instead of this engine's own
+    #[must_use]
+    pub fn with_scheduler(mut self, scheduler: GpuScheduler) -> Self {
+        self.scheduler = scheduler;
+        self
+    }
+
+    /// Wait for the GPU at `priority` (default [`GpuPriority::Normal`])
+    ///
+    /// Each dispatch (one chunk of a reduction, one GROUP BY pass) holds a
+    /// [`scheduler::GpuPermit`] from upload to readback, so concurrent
+    /// callers never interleave inside a dispatch and interactive engines
+    /// go ahead of batch ones between dispatches.
+    #[must_use]
+    pub const fn with_priority(mut self, priority: GpuPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Scheduler this engine's dispatches take permits from
+    #[must_use]
+    pub const fn scheduler(&self) -> &GpuScheduler {
+        &self.scheduler
+    }
+
+    /// Keep at most `bytes` of idle buffers for reuse (default
+    /// [`buffers::DEFAULT_POOL_HIGH_WATER`]; `0` allocates on every call)
+    #[must_use]
+    pub fn with_buffer_pool_limit(self, bytes: u64) -> Self {
+        self.buffers.set_high_water(bytes);
+        self
+    }
+
+    /// Buffers reused and allocated by this engine's dispatches
+    #[must_use]
+    pub fn buffer_pool_stats(&self) -> BufferPoolStats {
+        self.buffers.stats()
+    }
+
+    /// Upload the values of a fixed-width column into a pooled storage buffer
+    ///
+    /// The buffer returns to this engine's pool when dropped, so uploading
+    /// batches of the same shape one after another reuses device memory
+    /// (see [`GpuTransferQueue::dequeue_to_gpu`](crate::storage::GpuTransferQueue::dequeue_to_gpu)).
+    /// The validity bitmap is not uploaded.
+    ///
+    /// # Errors
+    /// Returns error if the column is not of a fixed-width primitive type
+    pub fn upload_column(&self, data: &dyn Array) -> Result<PooledBuffer> {
+        let width = data
+            .data_type()
+            .primitive_width()
+            .filter(|_| data.data_type().is_primitive())
+            .ok_or_else(|| {
+                Error::InvalidInput(format!(
+                    "Only fixed-width columns can be uploaded, got {:?}",
+                    data.data_type()
+                ))
+            })?;
+        let array_data = data.to_data();
+        let start = array_data.offset() * width;
+        let values = &array_data.buffers()[0].as_slice()[start..start + data.len() * width];
+        Ok(self.buffers.upload(
+            &self.device,
+            &self.queue,
+            "Column Upload",
+            values,
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        ))
+    }
+
+    /// Reason the device was lost, if it has been
+    ///
+    /// Once set, every GPU call fails with [`Error::GpuDeviceLost`]; create a
+    /// new engine or fall back to SIMD.
+    #[must_use]
+    pub fn device_lost_reason(&self) -> Option<String> {
+        self.device_lost.reason()
+    }
+
+    /// Benchmark candidate workgroup sizes for every tunable kernel
+    ///
+    /// Run once at warmup; the fastest correct size per kernel is used by
+    /// subsequent calls. Persist with
+    /// [`save_workgroup_config`](Self::save_workgroup_config) to skip tuning
+    /// on the next start.
+    ///
+    /// # Errors
+    /// Returns error if GPU execution fails or no candidate is correct
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_possible_wrap)]
+    pub async fn autotune(&mut self) -> Result<Vec<TuningResult>> {
+        // Mixed-sign values so MIN/MAX/filter paths are exercised
+        let sample: Vec<i32> = (0..AUTOTUNE_SAMPLE_LEN).map(|i| (i as i32 % 2001) - 1000).collect();
+
+        let mut results = Vec::with_capacity(TunableKernel::ALL.len());
+        for kernel in TunableKernel::ALL {
+            let result = autotune::tune_kernel(
+                &self.device,
+                &self.queue,
+                &self.pipelines,
+                &self.buffers,
+                kernel,
+                &sample,
+                AUTOTUNE_ITERATIONS,
+                &self.budget,
+            )
+            .await?;
+            self.workgroups.set(&self.device_key, kernel, result.best);
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    /// Workgroup size currently used for a kernel on this device
+    #[must_use]
+    pub fn workgroup_size(&self, kernel: TunableKernel) -> u32 {
+        self.workgroups.get(&self.device_key, kernel)
+    }
+
+    /// Whether tuned sizes are available for this device
+    #[must_use]
+    pub fn is_tuned(&self) -> bool {
+        self.workgroups.is_tuned(&self.device_key)
+    }
+
+    /// Load tuned workgroup sizes (entries for other devices are kept but unused)
+    ///
+    /// # Errors
+    /// Returns error if the file exists but cannot be parsed
+    pub fn load_workgroup_config<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        self.workgroups = WorkgroupConfig::load(path)?;
+        Ok(())
+    }
+
+    /// Save tuned workgroup sizes
+    ///
+    /// # Errors
+    /// Returns error if the file cannot be written
+    pub fn save_workgroup_config<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.workgroups.save(path)
+    }
+
+    /// Measure this device's host-to-device bandwidth and kernel throughput
+    ///
+    /// [`new`](Self::new) already does this for the first engine in the
+    /// process, [`with_calibration_file`](Self::with_calibration_file) when
+    /// nothing was saved. The result is kept by this engine; pass it to
+    /// [`calibration::install`] to have
+    /// [`BackendDispatcher::select`](crate::backend::BackendDispatcher::select)
+    /// use it. Persist with [`save_calibration`](Self::save_calibration).
+    ///
+    /// # Errors
+    /// Returns error if GPU execution fails
+    pub async fn calibrate(&mut self) -> Result<Calibration> {
+        let measured = calibrate::measure(
+            &self.device,
+            &self.queue,
+            &self.pipelines,
+            &self.buffers,
+            &self.device_key,
+            &self.budget,
+        )
+        .await?;
+        self.calibration = Some(measured.clone());
+        Ok(measured)
+    }
+
+    /// Measured bandwidth and throughput of this device, if calibrated
+    #[must_use]
+    pub const fn calibration(&self) -> Option<&Calibration> {
+        self.calibration.as_ref()
+    }
+
+    /// Use the calibration saved for this device, and install it for
+    /// [`BackendDispatcher::select`](crate::backend::BackendDispatcher::select)
+    ///
+    /// Returns whether the file had an entry for this device (if not, the
+    /// current calibration is kept).
+    ///
+    /// # Errors
+    /// Returns error if the file exists but cannot be parsed
+    pub fn load_calibration<P: AsRef<Path>>(&mut self, path: P) -> Result<bool> {
+        let store = CalibrationStore::load(path)?;
+        let Some(saved) = store.get(&self.device_key) else {
+            return Ok(false);
+        };
+        calibration::install(saved.clone());
+        self.calibration = Some(saved.clone());
+        Ok(true)
+    }
+
+    /// Save this device's calibration (entries for other devices are kept)
+    ///
+    /// # Errors
+    /// Returns error if the engine is not calibrated, or the file cannot be
+    /// read or written
+    pub fn save_calibration<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let calibration = self
+            .calibration
+            .clone()
+            .ok_or_else(|| Error::Other("GPU engine is not calibrated".to_string()))?;
+        let path = path.as_ref();
+        let mut store = CalibrationStore::load(path)?;
+        store.insert(calibration);
+        store.save(path)
     }
 
     /// Execute SUM aggregation on GPU
@@ -82,15 +512,70 @@ impl GpuEngine {
     /// # Errors
     /// Returns error if GPU execution fails
     pub async fn sum_i32(&self, data: &Int32Array) -> Result<i32> {
-        kernels::sum_i32(&self.device, &self.queue, data).await
+        self.reduce_i32(ReduceOp::Sum, TunableKernel::SumI32, data).await
     }
 
     /// Execute SUM aggregation on GPU (f32)
     ///
+    /// Workgroup partials are added on the host in f64 before rounding back
+    /// to f32.
+    ///
     /// # Errors
     /// Returns error if GPU execution fails
+    #[allow(clippy::cast_possible_truncation)]
     pub async fn sum_f32(&self, data: &Float32Array) -> Result<f32> {
-        kernels::sum_f32(&self.device, &self.queue, data).await
+        Ok(self.sum_f32_wide(data).await? as f32)
+    }
+
+    /// Execute SUM aggregation on GPU (f64, split accumulator)
+    ///
+    /// # Errors
+    /// Returns error if a finite value is outside the f32 range, or if GPU
+    /// execution fails
+    pub async fn sum_f64(&self, data: &Float64Array) -> Result<f64> {
+        let (device, queue, pipelines) = (&self.device, &self.queue, &self.pipelines);
+        let buffers = &self.buffers;
+        let budget = &self.budget;
+        // Each value is uploaded as an (hi, lo) f32 pair
+        let partials = self
+            .chunked(data.len(), 8, DEFAULT_WORKGROUP_SIZE, |rows| async move {
+                let chunk = data.slice(rows.start, rows.len());
+                kernels::reduce_sum_f64(
+                    device,
+                    queue,
+                    pipelines,
+                    buffers,
+                    &chunk,
+                    DEFAULT_WORKGROUP_SIZE,
+                    budget,
+                )
+                .await
+            })
+            .await?;
+        Ok(partials.into_iter().sum())
+    }
+
+    /// f32 SUM as the unrounded f64 total of the workgroup partials
+    async fn sum_f32_wide(&self, data: &Float32Array) -> Result<f64> {
+        let (device, queue, pipelines) = (&self.device, &self.queue, &self.pipelines);
+        let buffers = &self.buffers;
+        let budget = &self.budget;
+        let partials = self
+            .chunked(data.len(), 4, DEFAULT_WORKGROUP_SIZE, |rows| async move {
+                let chunk = data.slice(rows.start, rows.len());
+                kernels::reduce_sum_f32(
+                    device,
+                    queue,
+                    pipelines,
+                    buffers,
+                    &chunk,
+                    DEFAULT_WORKGROUP_SIZE,
+                    budget,
+                )
+                .await
+            })
+            .await?;
+        Ok(partials.into_iter().sum())
     }
 
     /// Execute COUNT aggregation on GPU
@@ -106,7 +591,7 @@ impl GpuEngine {
     /// # Errors
     /// Returns error if GPU execution fails
     pub async fn min_i32(&self, data: &Int32Array) -> Result<i32> {
-        kernels::min_i32(&self.device, &self.queue, data).await
+        self.reduce_i32(ReduceOp::Min, TunableKernel::MinI32, data).await
     }
 
     /// Execute MAX aggregation on GPU
@@ -114,22 +599,476 @@ impl GpuEngine {
     /// # Errors
     /// Returns error if GPU execution fails
     pub async fn max_i32(&self, data: &Int32Array) -> Result<i32> {
-        kernels::max_i32(&self.device, &self.queue, data).await
+        self.reduce_i32(ReduceOp::Max, TunableKernel::MaxI32, data).await
+    }
+
+    /// i32 reduction over chunks that fit the VRAM budget, combined on the host
+    async fn reduce_i32(
+        &self,
+        op: ReduceOp,
+        kernel: TunableKernel,
+        data: &Int32Array,
+    ) -> Result<i32> {
+        let workgroup_size = self.workgroup_size(kernel);
+        let (device, queue, pipelines) = (&self.device, &self.queue, &self.pipelines);
+        let buffers = &self.buffers;
+        let budget = &self.budget;
+        let partials = self
+            .chunked(data.len(), 4, workgroup_size, |rows| async move {
+                let chunk = data.slice(rows.start, rows.len());
+                kernels::reduce_i32(
+                    device,
+                    queue,
+                    pipelines,
+                    buffers,
+                    op,
+                    &chunk,
+                    workgroup_size,
+                    budget,
+                )
+                .await
+            })
+            .await?;
+        Ok(partials.into_iter().fold(op.identity(), |a, b| op.combine(a, b)))
     }
 
     /// Execute AVG aggregation on GPU (reuses sum + count)
     ///
+    /// The division happens in f64 on the unrounded sum. Empty input returns 0.
+    ///
     /// # Errors
     /// Returns error if GPU execution fails
-    #[allow(clippy::cast_precision_loss)]
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
     pub async fn avg_f32(&self, data: &Float32Array) -> Result<f32> {
-        let sum = self.sum_f32(data).await?;
+        let sum = self.sum_f32_wide(data).await?;
+        let count = self.count(data).await?;
+        if count == 0 {
+            Ok(0.0)
+        } else {
+            Ok((sum / count as f64) as f32)
+        }
+    }
+
+    /// Execute AVG aggregation on GPU (f64, split accumulator)
+    ///
+    /// Empty input returns 0.
+    ///
+    /// # Errors
+    /// Returns error if a finite value is outside the f32 range, or if GPU
+    /// execution fails
+    #[allow(clippy::cast_precision_loss)]
+    pub async fn avg_f64(&self, data: &Float64Array) -> Result<f64> {
+        let sum = self.sum_f64(data).await?;
         let count = self.count(data).await?;
         if count == 0 {
             Ok(0.0)
         } else {
-            Ok(sum / count as f32)
+            Ok(sum / count as f64)
+        }
+    }
+
+    /// Execute VARIANCE aggregation on GPU (f32, two-stage Welford)
+    ///
+    /// Returns `None` for empty input, or a single value with
+    /// [`VarianceKind::Sample`].
+    ///
+    /// # Errors
+    /// Returns error if GPU execution fails
+    pub async fn variance_f32(
+        &self,
+        data: &Float32Array,
+        kind: VarianceKind,
+    ) -> Result<Option<f64>> {
+        let (device, queue, pipelines) = (&self.device, &self.queue, &self.pipelines);
+        let buffers = &self.buffers;
+        let budget = &self.budget;
+        let partials = self
+            .chunked(data.len(), 4, DEFAULT_WORKGROUP_SIZE, |rows| async move {
+                let chunk = data.slice(rows.start, rows.len());
+                kernels::welford_f32(
+                    device,
+                    queue,
+                    pipelines,
+                    buffers,
+                    &chunk,
+                    DEFAULT_WORKGROUP_SIZE,
+                    budget,
+                )
+                .await
+            })
+            .await?;
+        let state = partials.into_iter().fold(WelfordState::default(), WelfordState::merge);
+        Ok(state.variance(kind))
+    }
+
+    /// Execute STDDEV aggregation on GPU (f32, two-stage Welford)
+    ///
+    /// # Errors
+    /// Returns error if GPU execution fails
+    pub async fn stddev_f32(&self, data: &Float32Array, kind: VarianceKind) -> Result<Option<f64>> {
+        Ok(self.variance_f32(data, kind).await?.map(f64::sqrt))
+    }
+
+    /// Execute several aggregates of one column in a single GPU pass
+    ///
+    /// `SELECT MIN(x), MAX(x), SUM(x)` otherwise costs one dispatch and one
+    /// readback per aggregate; here every requested aggregate comes from the
+    /// same [`kernels::multi_aggregate_i32`] dispatch (`Int32`) or
+    /// [`kernels::multi_aggregate_wide`] dispatch (`Int64`, `UInt64`,
+    /// `UInt32`), one per chunk when the column exceeds the VRAM budget.
+    /// Returns one single-row column per function, in order, typed as the
+    /// SIMD executor types it: SUM as `Int64` (`UInt64` for unsigned input),
+    /// COUNT as `Int64`, AVG as `Float64`, MIN and MAX as the input type.
+    /// Empty input yields 0 for every aggregate.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidInput`] if `data` has NULLs or an unsupported
+    /// type, a function is not SUM, AVG, COUNT, MIN or MAX, or the SUM
+    /// overflows its result type; or error if GPU execution fails
+    ///
+    /// # Example
+    /// ```ignore
+    /// use trueno_db::query::AggregateFunction::{Max, Min, Sum};
+    /// // SELECT MIN(x), MAX(x), SUM(x): one dispatch, one readback
+    /// let columns = engine.multi_aggregate(&data, &[Min, Max, Sum]).await?;
+    /// ```
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_wrap)]
+    pub async fn multi_aggregate(
+        &self,
+        data: &dyn Array,
+        functions: &[AggregateFunction],
+    ) -> Result<Vec<ArrayRef>> {
+        if !multi_aggregate_reads(data.data_type()) {
+            return Err(Error::InvalidInput(format!(
+                "GPU multi-aggregate does not support {:?} columns",
+                data.data_type()
+            )));
+        }
+        if data.null_count() > 0 {
+            return Err(Error::InvalidInput(
+                "GPU multi-aggregate does not support NULLs".to_string(),
+            ));
+        }
+        if let Some(func) = functions.iter().find(|&&func| !multi_aggregate_supports(func)) {
+            return Err(Error::InvalidInput(format!(
+                "GPU multi-aggregate does not support {}",
+                func.sql_name()
+            )));
+        }
+
+        let aggregates = self.wide_aggregates(data).await?;
+        let data_type = data.data_type();
+        let empty = aggregates.count == 0;
+        functions
+            .iter()
+            .map(|func| -> Result<ArrayRef> {
+                Ok(match func {
+                    AggregateFunction::Sum => {
+                        let sum = aggregates.sum;
+                        let overflow = || Error::InvalidInput(format!("SUM overflow: {sum}"));
+                        if data_type.is_signed_integer() {
+                            Arc::new(Int64Array::from(vec![
+                                i64::try_from(sum).map_err(|_| overflow())?
+                            ]))
+                        } else {
+                            Arc::new(UInt64Array::from(vec![
+                                u64::try_from(sum).map_err(|_| overflow())?
+                            ]))
+                        }
+                    }
+                    AggregateFunction::Count => {
+                        Arc::new(Int64Array::from(vec![aggregates.count as i64]))
+                    }
+                    AggregateFunction::Avg => {
+                        let avg = if empty {
+                            0.0
+                        } else {
+                            aggregates.sum as f64 / aggregates.count as f64
+                        };
+                        Arc::new(Float64Array::from(vec![avg]))
+                    }
+                    AggregateFunction::Min => {
+                        scalar_of_type(data_type, if empty { 0 } else { aggregates.min })
+                    }
+                    // MAX (the other functions were rejected above)
+                    _ => scalar_of_type(data_type, if empty { 0 } else { aggregates.max }),
+                })
+            })
+            .collect()
+    }
+
+    /// Execute SUM aggregation on GPU (i64, emulated with u32 words)
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidInput`] if the sum overflows i64, or error if
+    /// GPU execution fails
+    pub async fn sum_i64(&self, data: &Int64Array) -> Result<i64> {
+        let sum = self.wide_aggregates(data).await?.sum;
+        i64::try_from(sum).map_err(|_| Error::InvalidInput(format!("SUM overflow: {sum}")))
+    }
+
+    /// Execute MIN aggregation on GPU (i64); `i64::MAX` for empty input
+    ///
+    /// # Errors
+    /// Returns error if GPU execution fails
+    pub async fn min_i64(&self, data: &Int64Array) -> Result<i64> {
+        Ok(i64::try_from(self.wide_aggregates(data).await?.min).unwrap_or(i64::MAX))
+    }
+
+    /// Execute MAX aggregation on GPU (i64); `i64::MIN` for empty input
+    ///
+    /// # Errors
+    /// Returns error if GPU execution fails
+    pub async fn max_i64(&self, data: &Int64Array) -> Result<i64> {
+        Ok(i64::try_from(self.wide_aggregates(data).await?.max).unwrap_or(i64::MIN))
+    }
+
+    /// Execute SUM aggregation on GPU (u64, emulated with u32 words)
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidInput`] if the sum overflows u64, or error if
+    /// GPU execution fails
+    pub async fn sum_u64(&self, data: &UInt64Array) -> Result<u64> {
+        let sum = self.wide_aggregates(data).await?.sum;
+        u64::try_from(sum).map_err(|_| Error::InvalidInput(format!("SUM overflow: {sum}")))
+    }
+
+    /// Execute MIN aggregation on GPU (u64); `u64::MAX` for empty input
+    ///
+    /// # Errors
+    /// Returns error if GPU execution fails
+    pub async fn min_u64(&self, data: &UInt64Array) -> Result<u64> {
+        Ok(u64::try_from(self.wide_aggregates(data).await?.min).unwrap_or(u64::MAX))
+    }
+
+    /// Execute MAX aggregation on GPU (u64); 0 for empty input
+    ///
+    /// # Errors
+    /// Returns error if GPU execution fails
+    pub async fn max_u64(&self, data: &UInt64Array) -> Result<u64> {
+        Ok(u64::try_from(self.wide_aggregates(data).await?.max).unwrap_or(0))
+    }
+
+    /// Execute SUM aggregation on GPU (u32, widened to u64)
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidInput`] if the sum overflows u64, or error if
+    /// GPU execution fails
+    pub async fn sum_u32(&self, data: &UInt32Array) -> Result<u64> {
+        let sum = self.wide_aggregates(data).await?.sum;
+        u64::try_from(sum).map_err(|_| Error::InvalidInput(format!("SUM overflow: {sum}")))
+    }
+
+    /// Execute MIN aggregation on GPU (u32); `u32::MAX` for empty input
+    ///
+    /// # Errors
+    /// Returns error if GPU execution fails
+    pub async fn min_u32(&self, data: &UInt32Array) -> Result<u32> {
+        Ok(u32::try_from(self.wide_aggregates(data).await?.min).unwrap_or(u32::MAX))
+    }
+
+    /// Execute MAX aggregation on GPU (u32); 0 for empty input
+    ///
+    /// # Errors
+    /// Returns error if GPU execution fails
+    pub async fn max_u32(&self, data: &UInt32Array) -> Result<u32> {
+        Ok(u32::try_from(self.wide_aggregates(data).await?.max).unwrap_or(0))
+    }
+
+    /// COUNT, SUM, MIN and MAX of an integer column, over chunks that fit
+    /// the VRAM budget, combined on the host
+    async fn wide_aggregates(&self, data: &dyn Array) -> Result<WideAggregates> {
+        let (device, queue, pipelines) = (&self.device, &self.queue, &self.pipelines);
+        let buffers = &self.buffers;
+        let budget = &self.budget;
+        if let Some(data) = data.as_any().downcast_ref::<Int32Array>() {
+            let partials = self
+                .chunked(data.len(), 4, DEFAULT_WORKGROUP_SIZE, |rows| async move {
+                    let chunk = data.slice(rows.start, rows.len());
+                    kernels::multi_aggregate_i32(
+                        device,
+                        queue,
+                        pipelines,
+                        buffers,
+                        &chunk,
+                        DEFAULT_WORKGROUP_SIZE,
+                        budget,
+                    )
+                    .await
+                })
+                .await?;
+            let total = partials.into_iter().fold(I32Aggregates::default(), I32Aggregates::merge);
+            return Ok(WideAggregates {
+                count: total.count,
+                sum: i128::from(total.sum),
+                min: if total.count == 0 { i128::MAX } else { i128::from(total.min) },
+                max: if total.count == 0 { i128::MIN } else { i128::from(total.max) },
+            });
+        }
+
+        let row_bytes = data.data_type().primitive_width().unwrap_or(8) as u64;
+        let partials = self
+            .chunked(data.len(), row_bytes, DEFAULT_WORKGROUP_SIZE, |rows| async move {
+                let chunk = data.slice(rows.start, rows.len());
+                kernels::multi_aggregate_wide(
+                    device,
+                    queue,
+                    pipelines,
+                    buffers,
+                    chunk.as_ref(),
+                    DEFAULT_WORKGROUP_SIZE,
+                    budget,
+                )
+                .await
+            })
+            .await?;
+        Ok(partials.into_iter().fold(WideAggregates::default(), WideAggregates::merge))
+    }
+
+    /// Execute a grouped aggregation on GPU (hash GROUP BY, i32)
+    ///
+    /// Equivalent to `SELECT key, op(value) FROM t GROUP BY key`; groups come
+    /// back sorted by key. Pick this path with
+    /// [`QueryExecutor::group_by_backend`](crate::query::QueryExecutor::group_by_backend).
+    ///
+    /// # Errors
+    /// Returns error if the input has NULLs or too many distinct keys (see
+    /// [`kernels::group_by_i32`]), [`Error::VramExceeded`] if the input and
+    /// its hash table do not fit the VRAM budget, or if GPU execution fails
+    pub async fn group_by_i32(
+        &self,
+        op: GroupByOp,
+        keys: &Int32Array,
+        values: &Int32Array,
+    ) -> Result<GroupedI32> {
+        let _reservation = self.vram.reserve(kernels::group_by_bytes(keys.len(), 2, 3))?;
+        let _permit = self.scheduler.acquire(self.priority).await;
+        let (device, queue, pipelines) = (&self.device, &self.queue, &self.pipelines);
+        let buffers = &self.buffers;
+        kernels::group_by_i32(
+            device,
+            queue,
+            pipelines,
+            buffers,
+            op,
+            keys,
+            values,
+            DEFAULT_WORKGROUP_SIZE,
+            &self.budget,
+        )
+        .await
+    }
+
+    /// Top-K groups of a grouped aggregation, selected on GPU
+    ///
+    /// Equivalent to `SELECT key, op(value) FROM t GROUP BY key ORDER BY
+    /// op(value) DESC LIMIT k` (or `ASC`); ties go to the smaller key and
+    /// groups come back best first. The group table stays on the GPU, so
+    /// only the K selected groups are transferred back.
+    ///
+    /// # Errors
+    /// As [`group_by_i32`](Self::group_by_i32)
+    pub async fn group_by_top_k_i32(
+        &self,
+        op: GroupByOp,
+        keys: &Int32Array,
+        values: &Int32Array,
+        k: usize,
+        order: SortOrder,
+    ) -> Result<GroupedI32> {
+        let _reservation = self.vram.reserve(kernels::group_by_bytes(keys.len(), 2, 3))?;
+        let _permit = self.scheduler.acquire(self.priority).await;
+        let (device, queue, pipelines) = (&self.device, &self.queue, &self.pipelines);
+        let buffers = &self.buffers;
+        kernels::group_by_top_k_i32(
+            device,
+            queue,
+            pipelines,
+            buffers,
+            op,
+            keys,
+            values,
+            k,
+            order,
+            DEFAULT_WORKGROUP_SIZE,
+            &self.budget,
+        )
+        .await
+    }
+
+    /// Execute a `WHERE` + `GROUP BY` + aggregates plan in one GPU pass
+    ///
+    /// The plan is lowered to a JIT-compiled kernel (cached per plan shape)
+    /// that filters, groups and aggregates each row where it is loaded, so
+    /// the batch is uploaded once and only the group table comes back; see
+    /// [`jit::FusedGroupBy`] for the supported plans. Groups come back
+    /// sorted by key (one row without GROUP BY); ORDER BY and LIMIT are not
+    /// applied.
+    ///
+    /// # Errors
+    /// Returns error if the plan cannot be fused, [`Error::VramExceeded`] if
+    /// the batch and its hash table do not fit the VRAM budget, or as
+    /// [`kernels::fused_group_by`]
+    ///
+    /// # Example
+    /// ```ignore
+    /// let plan = QueryEngine::new()
+    ///     .parse("SELECT store, SUM(qty), COUNT(*) FROM sales WHERE qty > 10 GROUP BY store")?;
+    /// let grouped = engine.fused_group_by(&plan, &batch).await?;
+    /// ```
+    pub async fn fused_group_by(
+        &self,
+        plan: &crate::query::QueryPlan,
+        batch: &arrow::record_batch::RecordBatch,
+    ) -> Result<arrow::record_batch::RecordBatch> {
+        let fused = jit::FusedGroupBy::from_plan(plan, &batch.schema())?;
+        let words = 2 + 2 * fused.num_aggregates();
+        let bytes = kernels::group_by_bytes(batch.num_rows(), fused.num_columns(), words);
+        let _reservation = self.vram.reserve(bytes)?;
+        let shader = self.jit.compile_fused_group_by(&self.device, &fused, DEFAULT_WORKGROUP_SIZE);
+        self.drop_evicted_pipelines();
+        let _permit = self.scheduler.acquire(self.priority).await;
+        let (device, queue, pipelines) = (&self.device, &self.queue, &self.pipelines);
+        let buffers = &self.buffers;
+        kernels::fused_group_by(
+            device,
+            queue,
+            pipelines,
+            buffers,
+            &shader,
+            &fused,
+            batch,
+            DEFAULT_WORKGROUP_SIZE,
+            &self.budget,
+        )
+        .await
+    }
+
+    /// Run `dispatch` over `rows` rows split into chunks that fit the VRAM
+    /// budget, one chunk at a time
+    ///
+    /// Each chunk holds a reservation for `row_bytes` per row and a
+    /// scheduler permit while it runs; the per-chunk partial results are
+    /// returned in row order.
+    async fn chunked<T, F, Fut>(
+        &self,
+        rows: usize,
+        row_bytes: u64,
+        workgroup_size: u32,
+        mut dispatch: F,
+    ) -> Result<Vec<T>>
+    where
+        F: FnMut(Range<usize>) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let chunks = self.vram.chunks(rows, row_bytes, workgroup_size)?;
+        let mut partials = Vec::with_capacity(chunks.len());
+        for chunk in chunks {
+            let _reservation = self.vram.reserve(chunk.len() as u64 * row_bytes)?;
+            let _permit = self.scheduler.acquire(self.priority).await;
+            partials.push(dispatch(chunk).await?);
         }
+        Ok(partials)
     }
 
     /// Execute fused filter+sum aggregation on GPU (JIT-compiled kernel)
@@ -153,8 +1092,6 @@ impl GpuEngine {
     /// // Equivalent to: SELECT SUM(value) FROM data WHERE value > 1000
     /// let result = engine.fused_filter_sum(&data, 1000, "gt").await?;
     /// ```
-    #[allow(clippy::too_many_lines)]
-    #[allow(clippy::cast_possible_truncation)]
     pub async fn fused_filter_sum(
         &self,
         data: &Int32Array,
@@ -162,87 +1099,70 @@ impl GpuEngine {
         filter_op: &str,
     ) -> Result<i32> {
         // JIT compile the fused kernel (cached automatically)
-        let shader_module =
-            self.jit.compile_fused_filter_sum(&self.device, filter_threshold, filter_op);
+        let workgroup_size = self.workgroup_size(TunableKernel::FusedFilterSum);
+        let shader_module = self.jit.compile_fused_filter_sum_sized(
+            &self.device,
+            filter_threshold,
+            filter_op,
+            workgroup_size,
+        );
+        self.drop_evicted_pipelines();
+        let kernel = self.pipelines.get_or_create(
+            &self.device,
+            &jit::fused_filter_sum_key(filter_threshold, filter_op, workgroup_size),
+            &shader_module,
+            &["fused_filter_sum"],
+            &[true, false],
+        );
+
+        // Filtered sums of chunks that fit the VRAM budget (wrapping, as on GPU)
+        let (kernel, values): (&CachedPipeline, &[i32]) = (&kernel, data.values());
+        let partials = self
+            .chunked(values.len(), 4, workgroup_size, move |rows| {
+                self.fused_filter_sum_chunk(kernel, &values[rows], workgroup_size)
+            })
+            .await?;
+        Ok(partials.into_iter().fold(0, i32::wrapping_add))
+    }
 
-        // Prepare input data
-        let input_data: Vec<i32> = data.values().to_vec();
+    /// Run the fused filter+sum kernel over one non-empty chunk
+    #[allow(clippy::too_many_lines)]
+    #[allow(clippy::cast_possible_truncation)]
+    async fn fused_filter_sum_chunk(
+        &self,
+        kernel: &CachedPipeline,
+        input_data: &[i32],
+        workgroup_size: u32,
+    ) -> Result<i32> {
         let input_size = input_data.len();
 
-        if input_size == 0 {
-            return Ok(0);
-        }
-
         // Create GPU buffers
-        let input_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Fused Filter+Sum Input"),
-            contents: bytemuck::cast_slice(&input_data),
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-        });
-
-        let output_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Fused Filter+Sum Output"),
-            contents: bytemuck::cast_slice(&[0i32]),
-            usage: wgpu::BufferUsages::STORAGE
-                | wgpu::BufferUsages::COPY_SRC
-                | wgpu::BufferUsages::COPY_DST,
-        });
-
-        // Create bind group layout
-        let bind_group_layout =
-            self.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("Fused Filter+Sum Bind Group Layout"),
-                entries: &[
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStages::COMPUTE,
-                        ty: wgpu::BindingType::Buffer {
-                            ty: wgpu::BufferBindingType::Storage { read_only: true },
-                            has_dynamic_offset: false,
-                            min_binding_size: None,
-                        },
-                        count: None,
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: wgpu::ShaderStages::COMPUTE,
-                        ty: wgpu::BindingType::Buffer {
-                            ty: wgpu::BufferBindingType::Storage { read_only: false },
-                            has_dynamic_offset: false,
-                            min_binding_size: None,
-                        },
-                        count: None,
-                    },
-                ],
-            });
+        let input_buffer = self.buffers.upload(
+            &self.device,
+            &self.queue,
+            "Fused Filter+Sum Input",
+            bytemuck::cast_slice(input_data),
+            wgpu::BufferUsages::STORAGE,
+        );
+
+        let output_buffer = self.buffers.upload(
+            &self.device,
+            &self.queue,
+            "Fused Filter+Sum Output",
+            bytemuck::cast_slice(&[0i32]),
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        );
 
         // Create bind group
         let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Fused Filter+Sum Bind Group"),
-            layout: &bind_group_layout,
+            layout: kernel.bind_group_layout(),
             entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: input_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 1, resource: output_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 0, resource: input_buffer.binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: output_buffer.binding() },
             ],
         });
 
-        // Create compute pipeline
-        let pipeline_layout = self.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Fused Filter+Sum Pipeline Layout"),
-            bind_group_layouts: &[&bind_group_layout],
-            push_constant_ranges: &[],
-        });
-
-        let compute_pipeline =
-            self.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-                label: Some("Fused Filter+Sum Pipeline"),
-                layout: Some(&pipeline_layout),
-                module: &shader_module,
-                entry_point: "fused_filter_sum",
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                cache: None,
-            });
-
         // Create command encoder and execute
         let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Fused Filter+Sum Encoder"),
@@ -253,40 +1173,30 @@ impl GpuEngine {
                 label: Some("Fused Filter+Sum Pass"),
                 timestamp_writes: None,
             });
-            compute_pass.set_pipeline(&compute_pipeline);
+            compute_pass.set_pipeline(kernel.pipeline());
             compute_pass.set_bind_group(0, &bind_group, &[]);
 
-            // Dispatch workgroups (256 threads per workgroup)
-            let workgroup_count = (input_size as u32).div_ceil(256);
+            // Dispatch workgroups (tuned threads per workgroup)
+            let workgroup_count = (input_size as u32).div_ceil(workgroup_size);
             compute_pass.dispatch_workgroups(workgroup_count, 1, 1);
         }
 
         // Copy output to staging buffer
-        let staging_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Fused Filter+Sum Staging Buffer"),
-            size: 4,
-            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+        let mut staging_buffer = self.buffers.acquire(
+            &self.device,
+            "Fused Filter+Sum Staging Buffer",
+            4,
+            wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        );
 
-        encoder.copy_buffer_to_buffer(&output_buffer, 0, &staging_buffer, 0, 4);
+        encoder.copy_buffer_to_buffer(output_buffer.buffer(), 0, staging_buffer.buffer(), 0, 4);
 
         // Submit commands
         self.queue.submit(Some(encoder.finish()));
 
         // Read result
-        let buffer_slice = staging_buffer.slice(..);
-        let (tx, rx) = futures_intrusive::channel::shared::oneshot_channel();
-        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-            tx.send(result).ok();
-        });
-
-        self.device.poll(wgpu::Maintain::Wait);
-
-        rx.receive()
-            .await
-            .ok_or_else(|| Error::Other("Failed to receive buffer map result".to_string()))?
-            .map_err(|e| Error::Other(format!("Buffer mapping failed: {e}")))?;
+        staging_buffer.map_read(&self.device, &self.budget).await?;
+        let buffer_slice = staging_buffer.slice();
 
         let data_view = buffer_slice.get_mapped_range();
         let result = i32::from_le_bytes([data_view[0], data_view[1], data_view[2], data_view[3]]);
@@ -298,6 +1208,65 @@ impl GpuEngine {
     }
 }
 
+/// Whether [`GpuEngine::multi_aggregate`] reads columns of `data_type`
+/// (`Int32`, `Int64`, `UInt32`, `UInt64`)
+#[must_use]
+pub fn multi_aggregate_reads(data_type: &arrow::datatypes::DataType) -> bool {
+    data_type == &arrow::datatypes::DataType::Int32 || kernels::wide_supports(data_type)
+}
+
+/// One-row column of `data_type` (an integer type the kernels read) holding `value`
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn scalar_of_type(data_type: &arrow::datatypes::DataType, value: i128) -> ArrayRef {
+    use arrow::datatypes::DataType;
+    match data_type {
+        DataType::Int64 => Arc::new(Int64Array::from(vec![value as i64])),
+        DataType::UInt64 => Arc::new(UInt64Array::from(vec![value as u64])),
+        DataType::UInt32 => Arc::new(UInt32Array::from(vec![value as u32])),
+        _ => Arc::new(Int32Array::from(vec![value as i32])),
+    }
+}
+
+/// Whether [`GpuEngine::multi_aggregate`] computes `func`
+#[must_use]
+pub const fn multi_aggregate_supports(func: AggregateFunction) -> bool {
+    matches!(
+        func,
+        AggregateFunction::Sum
+            | AggregateFunction::Avg
+            | AggregateFunction::Count
+            | AggregateFunction::Min
+            | AggregateFunction::Max
+    )
+}
+
+/// Drive `future` to completion on the current thread
+///
+/// Never call this on `wasm32`: the browser's main thread cannot block, and
+/// GPU work only completes once control returns to its event loop.
+pub(crate) fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    use std::task::{Context, Poll, Wake, Waker};
+    use std::thread::{self, Thread};
+
+    struct Unpark(Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: std::sync::Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(std::sync::Arc::new(Unpark(thread::current())));
+    let mut context = Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -341,6 +1310,273 @@ mod tests {
         assert_eq!(result, 0);
     }
 
+    #[tokio::test]
+    async fn test_gpu_variance_matches_scalar() {
+        let Ok(engine) = GpuEngine::new().await else {
+            eprintln!("Skipping GPU test (no GPU available)");
+            return;
+        };
+
+        // Several workgroups plus a partial one
+        let values: Vec<f32> = (0..1000_u16).map(|i| 1000.0 + f32::from(i % 13) * 0.25).collect();
+        let expected = crate::variance::welford_scalar(&values);
+        let data = Float32Array::from(values);
+
+        for kind in [VarianceKind::Population, VarianceKind::Sample] {
+            let gpu = engine.variance_f32(&data, kind).await.unwrap().unwrap();
+            let scalar = expected.variance(kind).unwrap();
+            assert!((gpu - scalar).abs() < 1e-3 * scalar.max(1.0), "{gpu} vs {scalar}");
+        }
+
+        let empty = Float32Array::from(Vec::<f32>::new());
+        assert_eq!(engine.variance_f32(&empty, VarianceKind::Population).await.unwrap(), None);
+    }
+
+    /// Scalar reference for GROUP BY: (key, aggregate, count) sorted by key
+    fn scalar_group_by(op: GroupByOp, keys: &[i32], values: &[i32]) -> GroupedI32 {
+        let mut groups = std::collections::BTreeMap::<i32, (i32, u32)>::new();
+        for (&key, &value) in keys.iter().zip(values) {
+            let (acc, count) = groups.entry(key).or_insert(match op {
+                GroupByOp::Min => (i32::MAX, 0),
+                GroupByOp::Max => (i32::MIN, 0),
+                GroupByOp::Sum | GroupByOp::Count => (0, 0),
+            });
+            *acc = match op {
+                GroupByOp::Sum => acc.wrapping_add(value),
+                GroupByOp::Min => (*acc).min(value),
+                GroupByOp::Max => (*acc).max(value),
+                GroupByOp::Count => acc.wrapping_add(1),
+            };
+            *count += 1;
+        }
+        GroupedI32 {
+            keys: groups.keys().copied().collect(),
+            values: groups.values().map(|&(acc, _)| acc).collect(),
+            counts: groups.values().map(|&(_, count)| count).collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_gpu_group_by_matches_scalar() {
+        let Ok(engine) = GpuEngine::new().await else {
+            eprintln!("Skipping GPU test (no GPU available)");
+            return;
+        };
+
+        // Several workgroups, many rows per key, and the empty-slot marker as a key
+        let mut keys: Vec<i32> = (0..5000).map(|i| (i * 7919) % 97 - 48).collect();
+        keys.extend([i32::MIN, i32::MIN, i32::MAX]);
+        let values: Vec<i32> = (0..).zip(&keys).map(|(i, _)| (i % 2001) - 1000).collect();
+        let key_array = Int32Array::from(keys.clone());
+        let value_array = Int32Array::from(values.clone());
+
+        for op in [GroupByOp::Sum, GroupByOp::Min, GroupByOp::Max, GroupByOp::Count] {
+            let gpu = engine.group_by_i32(op, &key_array, &value_array).await.unwrap();
+            assert_eq!(gpu, scalar_group_by(op, &keys, &values), "{op:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_gpu_group_by_edge_cases() {
+        let Ok(engine) = GpuEngine::new().await else {
+            eprintln!("Skipping GPU test (no GPU available)");
+            return;
+        };
+
+        let empty = Int32Array::from(Vec::<i32>::new());
+        let grouped = engine.group_by_i32(GroupByOp::Sum, &empty, &empty).await.unwrap();
+        assert_eq!(grouped, GroupedI32::default());
+
+        // Every key distinct: the table must hold one group per row
+        let keys = Int32Array::from((0..1024).rev().collect::<Vec<i32>>());
+        let grouped = engine.group_by_i32(GroupByOp::Count, &keys, &keys).await.unwrap();
+        assert_eq!(grouped.keys, (0..1024).collect::<Vec<i32>>());
+        assert!(grouped.counts.iter().all(|&count| count == 1));
+
+        let nulls = Int32Array::from(vec![Some(1), None]);
+        assert!(engine.group_by_i32(GroupByOp::Sum, &nulls, &nulls).await.is_err());
+        let short = Int32Array::from(vec![1]);
+        assert!(engine.group_by_i32(GroupByOp::Sum, &nulls, &short).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_gpu_fused_group_by_matches_executor() {
+        use crate::query::{QueryEngine, QueryExecutor};
+        use crate::storage::StorageEngine;
+        use arrow::datatypes::{DataType, Field, Schema};
+        use arrow::record_batch::RecordBatch;
+        use std::sync::Arc;
+
+        let Ok(engine) = GpuEngine::new().await else {
+            eprintln!("Skipping GPU test (no GPU available)");
+            return;
+        };
+
+        // Several workgroups, the empty-slot marker as a key, sums past i32
+        let mut stores: Vec<i32> = (0..5000).map(|i| (i * 7919) % 61 - 30).collect();
+        stores.extend([i32::MIN, i32::MIN]);
+        let qty: Vec<i32> = (0..).zip(&stores).map(|(i, _)| (i % 2001) - 1000).collect();
+        let price: Vec<i32> = (0..).zip(&stores).map(|(i, _)| i32::MAX - i % 7).collect();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("store", DataType::Int32, false),
+            Field::new("qty", DataType::Int32, false),
+            Field::new("price", DataType::Int32, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(stores)),
+                Arc::new(Int32Array::from(qty)),
+                Arc::new(Int32Array::from(price)),
+            ],
+        )
+        .unwrap();
+        let storage = StorageEngine::new(vec![batch.clone()]);
+
+        for sql in [
+            "SELECT store, SUM(price), MIN(qty), MAX(qty), COUNT(*) FROM t \
+             WHERE qty > -500 AND store <> 3 GROUP BY store ORDER BY store",
+            "SELECT store, SUM(qty) FROM t GROUP BY store ORDER BY store",
+            "SELECT store, COUNT(*) FROM t WHERE qty > 5000 GROUP BY store ORDER BY store",
+        ] {
+            let plan = QueryEngine::new().parse(sql).unwrap();
+            let gpu = engine.fused_group_by(&plan, &batch).await.unwrap();
+            let simd = QueryExecutor::new().execute(&plan, &storage).unwrap();
+            assert_eq!(gpu.columns(), simd.columns(), "{sql}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_gpu_multi_aggregate_matches_scalar() {
+        use crate::query::AggregateFunction::{Avg, Count, Max, Min, Sum, VarPop};
+
+        let Ok(engine) = GpuEngine::new().await else {
+            eprintln!("Skipping GPU test (no GPU available)");
+            return;
+        };
+
+        // Several workgroups and chunks (4 KB budget), sums past i32
+        let engine = engine.with_vram_budget(4000);
+        let values: Vec<i32> =
+            (0..5000).map(|i| if i % 3 == 0 { i32::MAX - i } else { -i }).collect();
+        let sum: i64 = values.iter().map(|&v| i64::from(v)).sum();
+        let data = Int32Array::from(values.clone());
+        let columns =
+            engine.multi_aggregate(&data, &[Min, Max, Sum, Avg, Count, Min]).await.unwrap();
+        let expected: [ArrayRef; 6] = [
+            Arc::new(Int32Array::from(vec![-4999])),
+            Arc::new(Int32Array::from(vec![i32::MAX])),
+            Arc::new(Int64Array::from(vec![sum])),
+            Arc::new(Float64Array::from(vec![sum as f64 / 5000.0])),
+            Arc::new(Int64Array::from(vec![5000])),
+            Arc::new(Int32Array::from(vec![-4999])),
+        ];
+        assert_eq!(columns, expected);
+        assert_eq!(engine.vram_budget().allocated(), 0);
+
+        let empty = Int32Array::from(Vec::<i32>::new());
+        let expected: [ArrayRef; 2] =
+            [Arc::new(Int32Array::from(vec![0])), Arc::new(Float64Array::from(vec![0.0]))];
+        assert_eq!(engine.multi_aggregate(&empty, &[Min, Avg]).await.unwrap(), expected);
+
+        let nulls = Int32Array::from(vec![Some(1), None]);
+        let err = engine.multi_aggregate(&nulls, &[Sum]).await.unwrap_err();
+        assert!(matches!(err, Error::InvalidInput(_)));
+        let err = engine.multi_aggregate(&data, &[VarPop]).await.unwrap_err();
+        assert!(matches!(err, Error::InvalidInput(_)));
+    }
+
+    #[tokio::test]
+    async fn test_gpu_64bit_and_unsigned_kernels_match_scalar() {
+        use crate::query::AggregateFunction::{Avg, Max, Min, Sum};
+
+        let Ok(engine) = GpuEngine::new().await else {
+            eprintln!("Skipping GPU test (no GPU available)");
+            return;
+        };
+
+        // Values past 32 bits in both words, sums that carry between words
+        let wide: Vec<i64> = (0..3000_i64).map(|i| (i - 1500) * 6_000_000_000 + i).collect();
+        let data = Int64Array::from(wide.clone());
+        assert_eq!(engine.sum_i64(&data).await.unwrap(), wide.iter().sum::<i64>());
+        assert_eq!(engine.min_i64(&data).await.unwrap(), *wide.iter().min().unwrap());
+        assert_eq!(engine.max_i64(&data).await.unwrap(), *wide.iter().max().unwrap());
+        let overflow = Int64Array::from(vec![i64::MAX, 1]);
+        assert!(matches!(engine.sum_i64(&overflow).await, Err(Error::InvalidInput(_))));
+
+        let unsigned: Vec<u64> = (0..3000_u64).map(|i| u64::MAX / 65536 * (i % 7) + i).collect();
+        let data = UInt64Array::from(unsigned.clone());
+        assert_eq!(engine.sum_u64(&data).await.unwrap(), unsigned.iter().sum::<u64>());
+        assert_eq!(engine.min_u64(&data).await.unwrap(), 0);
+        assert_eq!(engine.max_u64(&data).await.unwrap(), *unsigned.iter().max().unwrap());
+
+        let narrow: Vec<u32> = (0..3000_u32).map(|i| u32::MAX - i * 7).collect();
+        let data = UInt32Array::from(narrow.clone());
+        let sum: u64 = narrow.iter().map(|&v| u64::from(v)).sum();
+        assert_eq!(engine.sum_u32(&data).await.unwrap(), sum);
+        assert_eq!(engine.min_u32(&data).await.unwrap(), u32::MAX - 2999 * 7);
+        assert_eq!(engine.max_u32(&data).await.unwrap(), u32::MAX);
+        assert_eq!(engine.min_u32(&UInt32Array::from(Vec::<u32>::new())).await.unwrap(), u32::MAX);
+
+        let columns = engine.multi_aggregate(&data, &[Sum, Min, Max, Avg]).await.unwrap();
+        let expected: [ArrayRef; 4] = [
+            Arc::new(UInt64Array::from(vec![sum])),
+            Arc::new(UInt32Array::from(vec![u32::MAX - 2999 * 7])),
+            Arc::new(UInt32Array::from(vec![u32::MAX])),
+            Arc::new(Float64Array::from(vec![sum as f64 / 3000.0])),
+        ];
+        assert_eq!(columns, expected);
+    }
+
+    #[tokio::test]
+    async fn test_gpu_group_by_top_k_matches_full_group_by() {
+        let Ok(engine) = GpuEngine::new().await else {
+            eprintln!("Skipping GPU test (no GPU available)");
+            return;
+        };
+
+        // Many groups with tied aggregates, negative values, and the marker key
+        let mut keys: Vec<i32> = (0..20_000).map(|i| (i * 7919) % 3001 - 1500).collect();
+        keys.extend([i32::MIN, i32::MIN]);
+        let values: Vec<i32> = (0..).zip(&keys).map(|(i, _)| (i % 41) - 20).collect();
+        let key_array = Int32Array::from(keys.clone());
+        let value_array = Int32Array::from(values.clone());
+
+        for op in [GroupByOp::Sum, GroupByOp::Min, GroupByOp::Max, GroupByOp::Count] {
+            let all = scalar_group_by(op, &keys, &values);
+            for order in [SortOrder::Descending, SortOrder::Ascending] {
+                let mut ranked: Vec<(i32, i32, u32)> = (0..all.keys.len())
+                    .map(|i| (all.keys[i], all.values[i], all.counts[i]))
+                    .collect();
+                ranked.sort_by(|a, b| {
+                    let by_value = a.1.cmp(&b.1);
+                    let by_value =
+                        if order == SortOrder::Descending { by_value.reverse() } else { by_value };
+                    by_value.then(a.0.cmp(&b.0))
+                });
+
+                for k in [1, 10, 5000] {
+                    let top = engine
+                        .group_by_top_k_i32(op, &key_array, &value_array, k, order)
+                        .await
+                        .unwrap();
+                    let expected: Vec<(i32, i32, u32)> = ranked.iter().take(k).copied().collect();
+                    let actual: Vec<(i32, i32, u32)> = (0..top.keys.len())
+                        .map(|i| (top.keys[i], top.values[i], top.counts[i]))
+                        .collect();
+                    assert_eq!(actual, expected, "{op:?} {order:?} k={k}");
+                }
+            }
+        }
+
+        let empty = Int32Array::from(Vec::<i32>::new());
+        let top = engine
+            .group_by_top_k_i32(GroupByOp::Sum, &empty, &empty, 10, SortOrder::Descending)
+            .await
+            .unwrap();
+        assert_eq!(top, GroupedI32::default());
+    }
+
     #[tokio::test]
     async fn test_gpu_min_i32() {
         let Ok(engine) = GpuEngine::new().await else {
@@ -402,29 +1638,179 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_gpu_sum_f32_not_implemented() {
+    async fn test_gpu_sum_avg_f32_match_scalar() {
+        let Ok(engine) = GpuEngine::new().await else {
+            eprintln!("Skipping GPU test (no GPU available)");
+            return;
+        };
+
+        // Several workgroups plus a partial one
+        let values: Vec<f32> = (0..1000_u16).map(|i| f32::from(i % 17) * 0.5 - 3.0).collect();
+        let expected: f64 = values.iter().copied().map(f64::from).sum();
+        let data = Float32Array::from(values);
+
+        let sum = f64::from(engine.sum_f32(&data).await.unwrap());
+        assert!((sum - expected).abs() < 1e-3, "{sum} vs {expected}");
+        let avg = f64::from(engine.avg_f32(&data).await.unwrap());
+        assert!((avg - expected / 1000.0).abs() < 1e-6, "{avg}");
+
+        let empty = Float32Array::from(Vec::<f32>::new());
+        assert!(engine.sum_f32(&empty).await.unwrap().abs() < f32::EPSILON);
+        assert!(engine.avg_f32(&empty).await.unwrap().abs() < f32::EPSILON);
+        let nan = Float32Array::from(vec![1.0, f32::NAN]);
+        assert!(engine.sum_f32(&nan).await.unwrap().is_nan());
+    }
+
+    #[tokio::test]
+    async fn test_gpu_pipeline_cache_reuses_pipelines() {
+        let Ok(engine) = GpuEngine::new().await else {
+            eprintln!("Skipping GPU test (no GPU available)");
+            return;
+        };
+
+        let data = Int32Array::from((0..1000).collect::<Vec<i32>>());
+        for _ in 0..3 {
+            assert_eq!(engine.sum_i32(&data).await.unwrap(), 499_500);
+            assert_eq!(engine.fused_filter_sum(&data, 500, "gt").await.unwrap(), 374_250);
+        }
+        let stats = engine.pipeline_cache_stats();
+        assert_eq!((stats.pipelines, stats.misses, stats.hits), (2, 2, 4));
+
+        // A different operation is a different pipeline
+        assert_eq!(engine.max_i32(&data).await.unwrap(), 999);
+        assert_eq!(engine.pipeline_cache_stats().pipelines, 3);
+    }
+
+    #[tokio::test]
+    async fn test_gpu_kernel_cache_evicts_and_precompiles() {
+        use crate::query::QueryEngine;
+        use arrow::datatypes::{DataType, Field, Schema};
+        use arrow::record_batch::RecordBatch;
+        use std::sync::Arc;
+
+        let Ok(engine) = GpuEngine::new().await else {
+            eprintln!("Skipping GPU test (no GPU available)");
+            return;
+        };
+        let engine = engine.with_kernel_cache_capacity(2);
+
+        // One kernel per threshold; the oldest is evicted with its pipeline
+        let data = Int32Array::from((0..1000).collect::<Vec<i32>>());
+        for threshold in [100, 200, 300] {
+            engine.fused_filter_sum(&data, threshold, "gt").await.unwrap();
+        }
+        let stats = engine.kernel_cache_stats();
+        assert_eq!((stats.entries, stats.misses, stats.evictions), (2, 3, 1));
+        assert_eq!(engine.pipeline_cache_stats().pipelines, 2);
+
+        // Unknown operators compile as "gt" and share its kernel
+        engine.fused_filter_sum(&data, 300, "unknown").await.unwrap();
+        assert_eq!(engine.kernel_cache_stats().hits, 1);
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("store", DataType::Int32, false),
+            Field::new("qty", DataType::Int32, false),
+        ]));
+        let plan = QueryEngine::new()
+            .parse("SELECT store, SUM(qty) FROM t WHERE qty > 10 GROUP BY store")
+            .unwrap();
+        engine.precompile(&plan, &schema).unwrap();
+        let misses = engine.kernel_cache_stats().misses;
+        let pipelines = engine.pipeline_cache_stats();
+
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 1])),
+                Arc::new(Int32Array::from(vec![20, 30, 40])),
+            ],
+        )
+        .unwrap();
+        engine.fused_group_by(&plan, &batch).await.unwrap();
+        assert_eq!(engine.kernel_cache_stats().misses, misses);
+        assert_eq!(engine.pipeline_cache_stats().misses, pipelines.misses);
+
+        let unsupported = QueryEngine::new().parse("SELECT store FROM t").unwrap();
+        assert!(engine.precompile(&unsupported, &batch.schema()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_gpu_buffer_pool_reuses_buffers() {
         let Ok(engine) = GpuEngine::new().await else {
             eprintln!("Skipping GPU test (no GPU available)");
             return;
         };
 
-        let data = Float32Array::from(vec![1.0, 2.0, 3.0]);
-        let result = engine.sum_f32(&data).await;
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("not yet implemented"));
+        let data = Int32Array::from((0..1000).collect::<Vec<i32>>());
+        assert_eq!(engine.sum_i32(&data).await.unwrap(), 499_500);
+        let first = engine.buffer_pool_stats();
+        // Input, output and staging buffers all come back for the same call
+        assert_eq!(engine.sum_i32(&data).await.unwrap(), 499_500);
+        let second = engine.buffer_pool_stats();
+        assert_eq!(second.misses, first.misses);
+        assert_eq!(second.hits, first.hits + 3);
+
+        // A ragged slice lands in the same buckets and reads only its rows
+        assert_eq!(engine.sum_i32(&data.slice(0, 999)).await.unwrap(), 498_501);
+        assert_eq!(engine.buffer_pool_stats().misses, first.misses);
+
+        let uploaded = engine.upload_column(&data).unwrap();
+        assert_eq!(uploaded.size(), 4000);
+        assert!(engine.upload_column(&arrow::array::StringArray::from(vec!["a"])).is_err());
+
+        let engine = engine.with_buffer_pool_limit(0);
+        assert_eq!(engine.buffer_pool_stats().idle_bytes, 0);
     }
 
     #[tokio::test]
-    async fn test_gpu_avg_f32_not_implemented() {
+    async fn test_gpu_vram_budget_chunks_reductions() {
         let Ok(engine) = GpuEngine::new().await else {
             eprintln!("Skipping GPU test (no GPU available)");
             return;
         };
+        // 4 KB budget: 1000 i32 rows per chunk
+        let engine = engine.with_vram_budget(4000);
+
+        let values: Vec<i32> = (0..10_000).map(|i| (i % 2001) - 1000).collect();
+        let data = Int32Array::from(values.clone());
+        assert_eq!(engine.sum_i32(&data).await.unwrap(), values.iter().sum::<i32>());
+        assert_eq!(engine.min_i32(&data).await.unwrap(), -1000);
+        assert_eq!(engine.max_i32(&data).await.unwrap(), 1000);
+        assert_eq!(
+            engine.fused_filter_sum(&data, 0, "gt").await.unwrap(),
+            values.iter().filter(|&&v| v > 0).sum::<i32>()
+        );
+
+        let floats = Float32Array::from(values.iter().map(|&v| v as f32).collect::<Vec<_>>());
+        let variance = engine.variance_f32(&floats, VarianceKind::Population).await.unwrap();
+        let expected = crate::variance::welford_simd(floats.values())
+            .variance(VarianceKind::Population)
+            .unwrap();
+        assert!((variance.unwrap() - expected).abs() < expected * 1e-4);
+        assert_eq!(engine.vram_budget().allocated(), 0);
+
+        // The hash table cannot be chunked: too large a GROUP BY is rejected
+        let keys = Int32Array::from(vec![1; 10_000]);
+        let err = engine.group_by_i32(GroupByOp::Sum, &keys, &data).await.unwrap_err();
+        assert!(matches!(err, Error::VramExceeded { available: 4000, .. }), "{err}");
+    }
 
-        let data = Float32Array::from(vec![2.0, 4.0, 6.0]);
-        let result = engine.avg_f32(&data).await;
-        // avg_f32 calls sum_f32 which returns error
-        assert!(result.is_err());
+    #[tokio::test]
+    async fn test_gpu_sum_avg_f64_match_scalar() {
+        let Ok(engine) = GpuEngine::new().await else {
+            eprintln!("Skipping GPU test (no GPU available)");
+            return;
+        };
+
+        // Values f32 cannot represent exactly; a plain f32 sum drifts by ~1
+        let values: Vec<f64> = (0..5000_u32).map(|i| 1.0e6 + f64::from(i % 7) * 0.1).collect();
+        let expected: f64 = values.iter().sum();
+        let data = Float64Array::from(values);
+
+        let sum = engine.sum_f64(&data).await.unwrap();
+        assert!((sum - expected).abs() < 1e-3, "{sum} vs {expected}");
+        let avg = engine.avg_f64(&data).await.unwrap();
+        assert!((avg - expected / 5000.0).abs() < 1e-6, "{avg}");
     }
 
     #[tokio::test]
@@ -496,4 +1882,40 @@ mod tests {
         let result = engine.fused_filter_sum(&data, 100, "gt").await.unwrap();
         assert_eq!(result, 0);
     }
+
+    #[tokio::test]
+    async fn test_gpu_autotune_preserves_results() {
+        let Ok(mut engine) = GpuEngine::new().await else {
+            eprintln!("Skipping GPU test (no GPU available)");
+            return;
+        };
+
+        let results = engine.autotune().await.unwrap();
+        assert_eq!(results.len(), TunableKernel::ALL.len());
+        assert!(engine.is_tuned());
+
+        let data = Int32Array::from((1..=1000).collect::<Vec<i32>>());
+        assert_eq!(engine.sum_i32(&data).await.unwrap(), 500_500);
+        assert_eq!(engine.min_i32(&data).await.unwrap(), 1);
+        assert_eq!(engine.max_i32(&data).await.unwrap(), 1000);
+        assert_eq!(engine.fused_filter_sum(&data, 990, "gt").await.unwrap(), 9_955);
+    }
+
+    #[tokio::test]
+    async fn test_gpu_calibration_persists_per_device() {
+        let Ok(engine) = GpuEngine::new().await else {
+            eprintln!("Skipping GPU test (no GPU available)");
+            return;
+        };
+        let measured = engine.calibration().cloned().unwrap();
+        assert!(measured.is_valid(), "{measured:?}");
+        assert!(calibration::installed().is_some());
+
+        let path = std::env::temp_dir()
+            .join(format!("trueno_test_gpu_calibration_{}.json", std::process::id()));
+        engine.save_calibration(&path).unwrap();
+        let reloaded = GpuEngine::with_calibration_file(&path).await.unwrap();
+        assert_eq!(reloaded.calibration(), Some(&measured));
+        std::fs::remove_file(&path).unwrap();
+    }
 }
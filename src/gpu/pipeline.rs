@@ -0,0 +1,234 @@
+//! Compiled compute pipeline cache
+//!
+//! Compiling a shader module and building its bind group layout and compute
+//! pipeline costs far more than dispatching a small kernel (the driver
+//! compiles WGSL down to device code), yet kernels used to rebuild all three
+//! on every call. A [`PipelineCache`] keeps them per shader, entry point and
+//! binding layout for the engine's lifetime, so each kernel variant
+//! (operation, workgroup size, JIT plan shape) is compiled once and later
+//! calls only create buffers and bind groups.
+//!
+//! wgpu's driver-level `PipelineCache` (serialized to disk between runs) is
+//! not used: loading one is `unsafe`, which the crate denies, and only
+//! Vulkan supports it.
+//!
+//! Toyota Way: Muda elimination (compile once, dispatch many times)
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Compute pipelines for the entry points of one shader, sharing one bind
+/// group layout of storage buffers
+pub struct CachedPipeline {
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipelines: Vec<wgpu::ComputePipeline>,
+}
+
+impl CachedPipeline {
+    /// Layout for the bind groups of every entry point
+    #[must_use]
+    pub const fn bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.bind_group_layout
+    }
+
+    /// Pipeline of the first entry point
+    #[must_use]
+    pub fn pipeline(&self) -> &wgpu::ComputePipeline {
+        &self.pipelines[0]
+    }
+
+    /// Pipeline of the `index`-th entry point, in the order they were requested
+    ///
+    /// # Panics
+    /// Panics if `index` is not below the number of entry points
+    #[must_use]
+    pub fn entry(&self, index: usize) -> &wgpu::ComputePipeline {
+        &self.pipelines[index]
+    }
+}
+
+/// Shader identity: its full WGSL source, or the caller's key for a module
+/// compiled elsewhere (JIT)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ShaderKey {
+    Source(String),
+    Named(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct PipelineKey {
+    shader: ShaderKey,
+    entry_points: Vec<String>,
+    /// Read-only flag of each storage buffer binding, from binding 0
+    bindings: Vec<bool>,
+}
+
+/// Lookup counts of a [`PipelineCache`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PipelineCacheStats {
+    /// Distinct pipelines held
+    pub pipelines: usize,
+    /// Lookups served from the cache
+    pub hits: u64,
+    /// Lookups that compiled a pipeline
+    pub misses: u64,
+}
+
+/// Compiled pipelines shared by the kernels of one device
+///
+/// Thread-safe; compilation happens under the cache lock, so concurrent
+/// first calls of a kernel compile it once.
+pub struct PipelineCache {
+    pipelines: Mutex<HashMap<PipelineKey, Arc<CachedPipeline>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl PipelineCache {
+    /// Create an empty cache
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            pipelines: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Pipelines for `entry_points` of the WGSL `source`, compiled on first use
+    ///
+    /// `bindings` holds one flag per storage buffer binding (from binding 0):
+    /// `true` for read-only.
+    pub fn get_or_compile(
+        &self,
+        device: &wgpu::Device,
+        source: &str,
+        entry_points: &[&str],
+        bindings: &[bool],
+    ) -> Arc<CachedPipeline> {
+        let key = PipelineKey::new(ShaderKey::Source(source.to_string()), entry_points, bindings);
+        self.get_or_insert(key, || {
+            let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: entry_points.first().copied(),
+                source: wgpu::ShaderSource::Wgsl(source.into()),
+            });
+            build(device, &module, entry_points, bindings)
+        })
+    }
+
+    /// Pipelines for `entry_points` of an already compiled `module`,
+    /// identified by `shader_key` (e.g. a JIT cache key)
+    pub fn get_or_create(
+        &self,
+        device: &wgpu::Device,
+        shader_key: &str,
+        module: &wgpu::ShaderModule,
+        entry_points: &[&str],
+        bindings: &[bool],
+    ) -> Arc<CachedPipeline> {
+        let key =
+            PipelineKey::new(ShaderKey::Named(shader_key.to_string()), entry_points, bindings);
+        self.get_or_insert(key, || build(device, module, entry_points, bindings))
+    }
+
+    /// Number of pipelines held, and lookup hits and misses so far
+    #[must_use]
+    pub fn stats(&self) -> PipelineCacheStats {
+        PipelineCacheStats {
+            pipelines: self.lock().len(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Drop every cached pipeline (they are recompiled on next use)
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Drop the pipelines created by [`get_or_create`](Self::get_or_create)
+    /// for `shader_key`, e.g. once the JIT cache has evicted its module
+    pub fn remove_named(&self, shader_key: &str) {
+        self.lock()
+            .retain(|key, _| !matches!(&key.shader, ShaderKey::Named(name) if name == shader_key));
+    }
+
+    fn get_or_insert(
+        &self,
+        key: PipelineKey,
+        build: impl FnOnce() -> CachedPipeline,
+    ) -> Arc<CachedPipeline> {
+        let mut pipelines = self.lock();
+        if let Some(cached) = pipelines.get(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Arc::clone(cached);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        Arc::clone(pipelines.entry(key).or_insert_with(|| Arc::new(build())))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<PipelineKey, Arc<CachedPipeline>>> {
+        self.pipelines.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for PipelineCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PipelineKey {
+    fn new(shader: ShaderKey, entry_points: &[&str], bindings: &[bool]) -> Self {
+        Self {
+            shader,
+            entry_points: entry_points.iter().map(ToString::to_string).collect(),
+            bindings: bindings.to_vec(),
+        }
+    }
+}
+
+/// Build the bind group layout and one pipeline per entry point of `module`
+fn build(
+    device: &wgpu::Device,
+    module: &wgpu::ShaderModule,
+    entry_points: &[&str],
+    bindings: &[bool],
+) -> CachedPipeline {
+    let entries: Vec<wgpu::BindGroupLayoutEntry> = (0..)
+        .zip(bindings)
+        .map(|(binding, &read_only)| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        })
+        .collect();
+    let label = entry_points.first().copied();
+    let bind_group_layout = device
+        .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor { label, entries: &entries });
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label,
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+    });
+    let pipelines = entry_points
+        .iter()
+        .map(|&entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module,
+                entry_point,
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                cache: None,
+            })
+        })
+        .collect();
+    CachedPipeline { bind_group_layout, pipelines }
+}
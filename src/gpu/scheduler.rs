@@ -0,0 +1,415 @@
+//! Prioritized scheduling of GPU submissions across concurrent queries
+//!
+//! A [`GpuEngine`](super::GpuEngine) shared by several queries used to let
+//! each of them encode, submit and read back whenever it was polled, so one
+//! large scan could sit in front of every dashboard query behind it. A
+//! [`GpuScheduler`] hands out [`GpuPermit`]s: a dispatch holds one from
+//! upload to readback, at most `max_in_flight` are out at once, and waiters
+//! are served by [`GpuPriority`] (oldest first within a priority).
+//!
+//! Priorities alone would let a steady stream of interactive queries starve
+//! batch work, so a waiter passed over [`STARVATION_LIMIT`] times is served
+//! next regardless of priority.
+//!
+//! Permits are granted from the releasing thread and the waiter is woken
+//! through its [`Waker`], so the scheduler works under any executor
+//! (tokio, the crate's blocking tiers, the browser).
+//!
+//! References:
+//! - Kato et al. (2011): `TimeGraph`, GPU scheduling for real-time
+//!   multi-tasking environments
+//!
+//! Toyota Way: Heijunka (level GPU access instead of first-come, first-hogged)
+
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll, Waker};
+
+/// Default number of dispatches holding the GPU at once (fully serialized)
+pub const DEFAULT_MAX_IN_FLIGHT: usize = 1;
+
+/// Times a waiter may be passed over by higher-priority waiters before it
+/// is served next
+pub const STARVATION_LIMIT: u32 = 16;
+
+/// Scheduling class of a query's GPU dispatches
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum GpuPriority {
+    /// Background work (reports, backfills): served last
+    Batch,
+    /// Default class
+    #[default]
+    Normal,
+    /// Latency-sensitive queries (dashboards, user requests): served first
+    Interactive,
+}
+
+/// Snapshot of scheduler counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GpuSchedulerStats {
+    /// Permits currently held (or granted and not yet picked up)
+    pub in_flight: usize,
+    /// Dispatches waiting for a permit
+    pub queued: usize,
+    /// Permits granted in total
+    pub granted: u64,
+    /// Permits granted after waiting in the queue
+    pub waited: u64,
+    /// Permits granted to a starved waiter ahead of higher priorities
+    pub promoted: u64,
+}
+
+#[derive(Debug)]
+struct Waiter {
+    ticket: u64,
+    priority: GpuPriority,
+    /// Grants that went to a younger waiter while this one waited
+    passed_over: u32,
+    /// Set when the permit is handed over; the future picks it up
+    granted: bool,
+    waker: Waker,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    in_flight: usize,
+    /// Waiting and granted-but-not-yet-polled dispatches, oldest first
+    waiters: Vec<Waiter>,
+    next_ticket: u64,
+    stats: GpuSchedulerStats,
+}
+
+impl State {
+    /// Whether a new dispatch may take a permit without queueing
+    fn has_free_permit(&self, max_in_flight: usize) -> bool {
+        self.in_flight < max_in_flight && self.waiters.iter().all(|waiter| waiter.granted)
+    }
+
+    /// Hand free permits to the next waiters
+    fn grant(&mut self, max_in_flight: usize) {
+        while self.in_flight < max_in_flight {
+            let pending = || self.waiters.iter().enumerate().filter(|(_, waiter)| !waiter.granted);
+            // The oldest starved waiter, else the oldest of the highest priority
+            let starved = pending().find(|(_, waiter)| waiter.passed_over >= STARVATION_LIMIT);
+            let next = starved.or_else(|| {
+                pending()
+                    .min_by_key(|(_, waiter)| (std::cmp::Reverse(waiter.priority), waiter.ticket))
+            });
+            let Some((index, _)) = next else {
+                return;
+            };
+            if starved.is_some() {
+                self.stats.promoted += 1;
+            }
+
+            let ticket = self.waiters[index].ticket;
+            for waiter in &mut self.waiters {
+                if !waiter.granted && waiter.ticket < ticket {
+                    waiter.passed_over += 1;
+                }
+            }
+            let waiter = &mut self.waiters[index];
+            waiter.granted = true;
+            waiter.waker.wake_by_ref();
+            self.in_flight += 1;
+            self.stats.granted += 1;
+            self.stats.waited += 1;
+        }
+    }
+
+    /// Return a permit and pass it on
+    fn release(&mut self, max_in_flight: usize) {
+        self.in_flight -= 1;
+        self.grant(max_in_flight);
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    max_in_flight: usize,
+    state: Mutex<State>,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Prioritized permits for GPU dispatches, shared by every query on a device
+///
+/// Cloning is cheap; clones share the same permits, so engines opened on
+/// the same GPU can share one scheduler too.
+///
+/// # Example
+/// ```
+/// use trueno_db::gpu::scheduler::{GpuPriority, GpuScheduler};
+///
+/// let scheduler = GpuScheduler::new(1);
+/// let permit = scheduler.try_acquire(GpuPriority::Batch).unwrap();
+/// assert!(scheduler.try_acquire(GpuPriority::Interactive).is_none());
+/// drop(permit);
+/// assert!(scheduler.try_acquire(GpuPriority::Interactive).is_some());
+/// ```
+#[derive(Debug, Clone)]
+pub struct GpuScheduler {
+    shared: Arc<Shared>,
+}
+
+impl Default for GpuScheduler {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_IN_FLIGHT)
+    }
+}
+
+impl GpuScheduler {
+    /// Scheduler letting `max_in_flight` dispatches (minimum 1) hold the GPU
+    #[must_use]
+    pub fn new(max_in_flight: usize) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                max_in_flight: max_in_flight.max(1),
+                state: Mutex::new(State::default()),
+            }),
+        }
+    }
+
+    /// Maximum number of permits out at once
+    #[must_use]
+    pub fn max_in_flight(&self) -> usize {
+        self.shared.max_in_flight
+    }
+
+    /// Current counters
+    #[must_use]
+    pub fn stats(&self) -> GpuSchedulerStats {
+        let state = self.shared.lock();
+        GpuSchedulerStats {
+            in_flight: state.in_flight,
+            queued: state.waiters.iter().filter(|waiter| !waiter.granted).count(),
+            ..state.stats
+        }
+    }
+
+    /// Wait for a permit at `priority`
+    ///
+    /// Dropping the future gives up its place in the queue (or passes on a
+    /// permit granted but not yet picked up).
+    pub fn acquire(&self, priority: GpuPriority) -> Acquire {
+        Acquire { shared: Arc::clone(&self.shared), priority, ticket: None }
+    }
+
+    /// A permit at `priority` if one is free and nobody is waiting
+    #[must_use]
+    pub fn try_acquire(&self, priority: GpuPriority) -> Option<GpuPermit> {
+        let mut state = self.shared.lock();
+        if !state.has_free_permit(self.shared.max_in_flight) {
+            return None;
+        }
+        state.in_flight += 1;
+        state.stats.granted += 1;
+        drop(state);
+        Some(GpuPermit { shared: Arc::clone(&self.shared), priority })
+    }
+}
+
+/// Future returned by [`GpuScheduler::acquire`]
+#[derive(Debug)]
+#[must_use = "futures do nothing unless awaited"]
+pub struct Acquire {
+    shared: Arc<Shared>,
+    priority: GpuPriority,
+    /// Queue ticket once waiting
+    ticket: Option<u64>,
+}
+
+impl Future for Acquire {
+    type Output = GpuPermit;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<GpuPermit> {
+        let this = &mut *self;
+        let mut state = this.shared.lock();
+        let Some(ticket) = this.ticket else {
+            if state.has_free_permit(this.shared.max_in_flight) {
+                state.in_flight += 1;
+                state.stats.granted += 1;
+                drop(state);
+                return Poll::Ready(GpuPermit {
+                    shared: Arc::clone(&this.shared),
+                    priority: this.priority,
+                });
+            }
+            let ticket = state.next_ticket;
+            state.next_ticket += 1;
+            state.waiters.push(Waiter {
+                ticket,
+                priority: this.priority,
+                passed_over: 0,
+                granted: false,
+                waker: cx.waker().clone(),
+            });
+            this.ticket = Some(ticket);
+            return Poll::Pending;
+        };
+
+        let index = state
+            .waiters
+            .iter()
+            .position(|waiter| waiter.ticket == ticket)
+            .expect("a waiter stays queued until its future picks it up or is dropped");
+        if state.waiters[index].granted {
+            state.waiters.remove(index);
+            this.ticket = None;
+            drop(state);
+            return Poll::Ready(GpuPermit {
+                shared: Arc::clone(&this.shared),
+                priority: this.priority,
+            });
+        }
+        state.waiters[index].waker.clone_from(cx.waker());
+        Poll::Pending
+    }
+}
+
+impl Drop for Acquire {
+    fn drop(&mut self) {
+        let Some(ticket) = self.ticket else {
+            return;
+        };
+        let mut state = self.shared.lock();
+        if let Some(index) = state.waiters.iter().position(|waiter| waiter.ticket == ticket) {
+            if state.waiters.remove(index).granted {
+                state.release(self.shared.max_in_flight);
+            }
+        }
+    }
+}
+
+/// Right to use the GPU for one dispatch; dropping it serves the next waiter
+#[derive(Debug)]
+pub struct GpuPermit {
+    shared: Arc<Shared>,
+    priority: GpuPriority,
+}
+
+impl GpuPermit {
+    /// Priority the permit was requested at
+    #[must_use]
+    pub const fn priority(&self) -> GpuPriority {
+        self.priority
+    }
+}
+
+impl Drop for GpuPermit {
+    fn drop(&mut self) {
+        self.shared.lock().release(self.shared.max_in_flight);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::Wake;
+
+    #[derive(Default)]
+    struct CountWakes(AtomicUsize);
+
+    impl Wake for CountWakes {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Poll `future` once
+    fn poll(future: &mut Pin<Box<Acquire>>, wakes: &Arc<CountWakes>) -> Option<GpuPermit> {
+        let waker = Waker::from(Arc::clone(wakes));
+        match future.as_mut().poll(&mut Context::from_waker(&waker)) {
+            Poll::Ready(permit) => Some(permit),
+            Poll::Pending => None,
+        }
+    }
+
+    #[test]
+    fn test_waiters_served_by_priority_then_age() {
+        let scheduler = GpuScheduler::new(1);
+        let wakes = Arc::new(CountWakes::default());
+        let running = scheduler.try_acquire(GpuPriority::Normal).unwrap();
+
+        let mut batch = Box::pin(scheduler.acquire(GpuPriority::Batch));
+        let mut first = Box::pin(scheduler.acquire(GpuPriority::Interactive));
+        let mut second = Box::pin(scheduler.acquire(GpuPriority::Interactive));
+        for future in [&mut batch, &mut first, &mut second] {
+            assert!(poll(future, &wakes).is_none());
+        }
+        assert_eq!(scheduler.stats().queued, 3);
+
+        drop(running);
+        assert_eq!(wakes.0.load(Ordering::SeqCst), 1);
+        assert!(poll(&mut second, &wakes).is_none());
+        assert!(poll(&mut batch, &wakes).is_none());
+        let permit = poll(&mut first, &wakes).unwrap();
+        assert_eq!(permit.priority(), GpuPriority::Interactive);
+
+        drop(permit);
+        let permit = poll(&mut second, &wakes).unwrap();
+        drop(permit);
+        assert!(poll(&mut batch, &wakes).is_some());
+
+        let stats = scheduler.stats();
+        assert_eq!((stats.in_flight, stats.queued), (0, 0));
+        assert_eq!((stats.granted, stats.waited), (4, 3));
+    }
+
+    #[test]
+    fn test_starved_waiter_is_promoted() {
+        let scheduler = GpuScheduler::new(1);
+        let wakes = Arc::new(CountWakes::default());
+        let mut permit = scheduler.try_acquire(GpuPriority::Interactive).unwrap();
+        let mut batch = Box::pin(scheduler.acquire(GpuPriority::Batch));
+        assert!(poll(&mut batch, &wakes).is_none());
+
+        // A fresh interactive waiter is always queued when the permit frees
+        for _ in 0..STARVATION_LIMIT {
+            let mut interactive = Box::pin(scheduler.acquire(GpuPriority::Interactive));
+            assert!(poll(&mut interactive, &wakes).is_none());
+            drop(permit);
+            permit = poll(&mut interactive, &wakes).unwrap();
+        }
+        let mut interactive = Box::pin(scheduler.acquire(GpuPriority::Interactive));
+        assert!(poll(&mut interactive, &wakes).is_none());
+        drop(permit);
+        assert!(poll(&mut interactive, &wakes).is_none());
+        assert!(poll(&mut batch, &wakes).is_some());
+        assert_eq!(scheduler.stats().promoted, 1);
+    }
+
+    #[test]
+    fn test_dropped_waiters_pass_permits_on() {
+        let scheduler = GpuScheduler::new(2);
+        let wakes = Arc::new(CountWakes::default());
+        let a = scheduler.try_acquire(GpuPriority::Normal).unwrap();
+        let b = scheduler.try_acquire(GpuPriority::Normal).unwrap();
+        assert!(scheduler.try_acquire(GpuPriority::Interactive).is_none());
+
+        let mut abandoned = Box::pin(scheduler.acquire(GpuPriority::Interactive));
+        let mut granted = Box::pin(scheduler.acquire(GpuPriority::Normal));
+        let mut waiting = Box::pin(scheduler.acquire(GpuPriority::Batch));
+        for future in [&mut abandoned, &mut granted, &mut waiting] {
+            assert!(poll(future, &wakes).is_none());
+        }
+
+        // Leaving the queue frees nothing; leaving with a permit passes it on
+        drop(abandoned);
+        drop(a);
+        assert_eq!(scheduler.stats().queued, 1);
+        drop(granted);
+        assert!(poll(&mut waiting, &wakes).is_some());
+        drop(b);
+        assert_eq!(scheduler.stats().in_flight, 0);
+    }
+}
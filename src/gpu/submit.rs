@@ -0,0 +1,252 @@
+//! Deadline-bounded waits for GPU submissions
+//!
+//! `device.poll(Maintain::Wait)` blocks the calling thread until the GPU
+//! finishes, with no upper bound: a hung driver or lost device would park
+//! an async runtime worker forever. Instead, readbacks go through
+//! `map_read`, which polls the device without blocking, yields to the
+//! executor between polls, and gives up when the [`PollBudget`] runs out:
+//!
+//! - **Timeout**: elapsed time exceeds the budget → [`Error::Timeout`]
+//! - **Cancellation**: the query's [`CancellationToken`] fired → [`Error::Cancelled`]
+//! - **Device lost**: the driver reported loss → [`Error::GpuDeviceLost`]
+//!   (distinct so callers can re-initialize or fall back to SIMD instead of
+//!   retrying)
+//!
+//! Toyota Way: Jidoka (stop and surface the fault instead of hanging)
+
+pub use crate::cancel::CancellationToken;
+use crate::query::stats::Stopwatch;
+use crate::{Error, Result};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// Default upper bound for one GPU submission to complete
+pub const DEFAULT_SUBMISSION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Sleep between device polls once a submission outlives [`SPIN_WINDOW`]
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Poll back-to-back (only yielding) for this long before sleeping, so short
+/// kernels are not delayed by timer granularity
+pub const SPIN_WINDOW: Duration = Duration::from_millis(2);
+
+/// Records why a device was lost (set from wgpu's device-lost callback)
+#[derive(Debug, Clone, Default)]
+pub struct DeviceLostFlag(Arc<Mutex<Option<String>>>);
+
+impl DeviceLostFlag {
+    /// Install the flag as `device`'s lost callback
+    pub(crate) fn register(device: &wgpu::Device) -> Self {
+        let flag = Self::default();
+        let shared = flag.clone();
+        device.set_device_lost_callback(move |reason, message| {
+            *shared.0.lock().unwrap_or_else(PoisonError::into_inner) =
+                Some(format!("{reason:?}: {message}"));
+        });
+        flag
+    }
+
+    /// Loss reason, if the device has been lost
+    #[must_use]
+    pub fn reason(&self) -> Option<String> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+}
+
+/// Limits for waiting on one GPU submission
+#[derive(Debug, Clone)]
+pub struct PollBudget {
+    timeout: Duration,
+    poll_interval: Duration,
+    cancellation: Option<CancellationToken>,
+    device_lost: Option<DeviceLostFlag>,
+}
+
+impl Default for PollBudget {
+    fn default() -> Self {
+        Self::new(DEFAULT_SUBMISSION_TIMEOUT)
+    }
+}
+
+impl PollBudget {
+    /// Budget that times out after `timeout`
+    #[must_use]
+    pub const fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            cancellation: None,
+            device_lost: None,
+        }
+    }
+
+    /// Replace the maximum wait per submission
+    #[must_use]
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Also stop waiting when `token` is cancelled
+    #[must_use]
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Sleep between polls (tokio runtimes and the browser; other executors
+    /// just yield)
+    #[must_use]
+    pub const fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Report loss of the device tracked by `flag` as [`Error::GpuDeviceLost`]
+    #[must_use]
+    pub fn with_device_lost(mut self, flag: DeviceLostFlag) -> Self {
+        self.device_lost = Some(flag);
+        self
+    }
+
+    /// Maximum wait per submission
+    #[must_use]
+    pub const fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Error if the wait must stop now (lost device, cancellation, deadline)
+    fn check(&self, started: &Stopwatch) -> Result<()> {
+        if let Some(reason) = self.device_lost.as_ref().and_then(DeviceLostFlag::reason) {
+            return Err(Error::GpuDeviceLost(reason));
+        }
+        if self.cancellation.as_ref().is_some_and(CancellationToken::is_cancelled) {
+            return Err(Error::Cancelled("GPU submission cancelled".to_string()));
+        }
+        if started.elapsed() >= self.timeout {
+            return Err(Error::Timeout(format!(
+                "GPU submission did not complete within {:?}",
+                self.timeout
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Map `slice` for reading, polling `device` within `budget`
+///
+/// The queue must already have been submitted. On error the buffer may
+/// still complete mapping later; callers simply drop it.
+///
+/// # Errors
+/// Returns [`Error::GpuDeviceLost`], [`Error::Cancelled`], or
+/// [`Error::Timeout`] when the budget stops the wait, or an error if
+/// mapping itself fails
+pub(crate) async fn map_read(
+    device: &wgpu::Device,
+    slice: &wgpu::BufferSlice<'_>,
+    budget: &PollBudget,
+) -> Result<()> {
+    let mapped: Arc<Mutex<Option<std::result::Result<(), wgpu::BufferAsyncError>>>> =
+        Arc::default();
+    let callback_slot = Arc::clone(&mapped);
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        *callback_slot.lock().unwrap_or_else(PoisonError::into_inner) = Some(result);
+    });
+
+    let started = Stopwatch::start();
+    loop {
+        device.poll(wgpu::Maintain::Poll);
+        let taken = mapped.lock().unwrap_or_else(PoisonError::into_inner).take();
+        if let Some(result) = taken {
+            return result.map_err(|e| Error::Other(format!("Buffer mapping failed: {e:?}")));
+        }
+        budget.check(&started)?;
+        // In the browser the map completes from a task, which never runs
+        // while microtasks keep re-polling: always pause there
+        if cfg!(not(target_arch = "wasm32")) && started.elapsed() < SPIN_WINDOW {
+            YieldNow(false).await;
+        } else {
+            pause(budget.poll_interval).await;
+        }
+    }
+}
+
+/// Sleep between polls (inside tokio), or just yield on other executors
+#[cfg(not(target_arch = "wasm32"))]
+async fn pause(interval: Duration) {
+    #[cfg(feature = "tokio")]
+    if tokio::runtime::Handle::try_current().is_ok() {
+        tokio::time::sleep(interval).await;
+        return;
+    }
+    let _ = interval;
+    YieldNow(false).await;
+}
+
+/// Resolve after `interval` via the global `setTimeout` (window or worker)
+#[cfg(target_arch = "wasm32")]
+async fn pause(interval: Duration) {
+    use wasm_bindgen::prelude::*;
+
+    #[wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(js_name = setTimeout)]
+        fn set_timeout(handler: &js_sys::Function, millis: i32) -> JsValue;
+    }
+
+    let millis = i32::try_from(interval.as_millis()).unwrap_or(i32::MAX);
+    let timer = js_sys::Promise::new(&mut |resolve, _reject| {
+        set_timeout(&resolve, millis);
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(timer).await;
+}
+
+/// Future that is pending exactly once (re-scheduling itself immediately)
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_reports_cancellation_before_deadline() {
+        let token = CancellationToken::new();
+        let budget = PollBudget::new(Duration::from_secs(60)).with_cancellation(token.clone());
+        assert!(budget.check(&Stopwatch::start()).is_ok());
+
+        token.cancel();
+        assert!(matches!(budget.check(&Stopwatch::start()), Err(Error::Cancelled(_))));
+    }
+
+    #[test]
+    fn test_budget_times_out() {
+        let budget = PollBudget::new(Duration::ZERO);
+        assert!(matches!(budget.check(&Stopwatch::start()), Err(Error::Timeout(_))));
+    }
+
+    #[test]
+    fn test_device_lost_takes_precedence() {
+        let flag = DeviceLostFlag::default();
+        *flag.0.lock().unwrap() = Some("Destroyed: test".to_string());
+        let budget = PollBudget::new(Duration::ZERO).with_device_lost(flag);
+        assert!(matches!(budget.check(&Stopwatch::start()), Err(Error::GpuDeviceLost(_))));
+    }
+}
@@ -0,0 +1,211 @@
+//! Device memory budget for GPU dispatches
+//!
+//! Kernels allocate their buffers per call, so without a budget one large
+//! column (or several queries at once) could ask the driver for more VRAM
+//! than the device has. A [`VramBudget`] tracks the bytes reserved by
+//! in-flight dispatches against a capacity and the adapter's limits:
+//!
+//! - **Chunking**: [`VramBudget::chunks`] splits an input into row ranges
+//!   that each fit one storage binding, one dispatch and the free budget.
+//!   The column stays in host memory and is uploaded one chunk at a time;
+//!   per-chunk partial results are read back and combined on the host.
+//! - **Reservation**: each dispatch holds a [`VramReservation`] for its
+//!   input bytes until it completes, so concurrent queries share the budget.
+//! - **Rejection**: work that cannot fit even when chunked (or that cannot
+//!   be chunked, like a GROUP BY hash table) fails with
+//!   [`Error::VramExceeded`], carrying the largest morsel that would fit, so
+//!   the caller can re-split the input or fall back to SIMD.
+//!
+//! Toyota Way: Poka-Yoke (make VRAM exhaustion impossible, not just unlikely)
+
+use crate::{Error, Result};
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Default device memory available to one [`GpuEngine`](super::GpuEngine) (1 GB)
+///
+/// wgpu does not report how much VRAM an adapter has; raise this with
+/// [`GpuEngine::with_vram_budget`](super::GpuEngine::with_vram_budget) on
+/// larger devices.
+pub const DEFAULT_VRAM_BUDGET: u64 = 1024 * 1024 * 1024;
+
+/// Device memory shared by the dispatches of one engine
+#[derive(Debug, Clone)]
+pub struct VramBudget {
+    capacity: u64,
+    /// Largest storage buffer one binding may hold
+    max_binding: u64,
+    /// Largest dispatch along one dimension, in workgroups
+    max_workgroups: u32,
+    allocated: Arc<AtomicU64>,
+}
+
+impl VramBudget {
+    /// Budget of `capacity` bytes on a device with `limits`
+    #[must_use]
+    pub fn new(capacity: u64, limits: &wgpu::Limits) -> Self {
+        Self {
+            capacity,
+            max_binding: limits
+                .max_buffer_size
+                .min(u64::from(limits.max_storage_buffer_binding_size)),
+            max_workgroups: limits.max_compute_workgroups_per_dimension,
+            allocated: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Same limits, with `capacity` bytes (reservations are not carried over)
+    #[must_use]
+    pub fn with_capacity(self, capacity: u64) -> Self {
+        Self { capacity, allocated: Arc::new(AtomicU64::new(0)), ..self }
+    }
+
+    /// Total bytes the budget allows
+    #[must_use]
+    pub const fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    /// Bytes currently reserved by in-flight dispatches
+    #[must_use]
+    pub fn allocated(&self) -> u64 {
+        self.allocated.load(Ordering::Acquire)
+    }
+
+    /// Bytes not currently reserved
+    #[must_use]
+    pub fn available(&self) -> u64 {
+        self.capacity.saturating_sub(self.allocated())
+    }
+
+    /// Largest buffer one dispatch may bind
+    #[must_use]
+    pub const fn max_binding_bytes(&self) -> u64 {
+        self.max_binding
+    }
+
+    /// Reserve `bytes` until the returned guard is dropped
+    ///
+    /// # Errors
+    /// Returns [`Error::VramExceeded`] if `bytes` exceeds the unreserved budget
+    pub fn reserve(&self, bytes: u64) -> Result<VramReservation> {
+        let reserved =
+            self.allocated.fetch_update(Ordering::AcqRel, Ordering::Acquire, |allocated| {
+                allocated.checked_add(bytes).filter(|&total| total <= self.capacity)
+            });
+        match reserved {
+            Ok(_) => Ok(VramReservation { allocated: Arc::clone(&self.allocated), bytes }),
+            Err(allocated) => Err(self.exceeded(bytes, self.capacity.saturating_sub(allocated))),
+        }
+    }
+
+    /// Split `rows` rows of `row_bytes` each into ranges that fit one dispatch
+    ///
+    /// Each range fits a storage binding, `workgroup_size`-thread workgroups
+    /// within the dispatch limit, and the budget unreserved right now. Empty
+    /// input yields no ranges.
+    ///
+    /// # Errors
+    /// Returns [`Error::VramExceeded`] if not even one row fits
+    pub fn chunks(
+        &self,
+        rows: usize,
+        row_bytes: u64,
+        workgroup_size: u32,
+    ) -> Result<Vec<Range<usize>>> {
+        if rows == 0 {
+            return Ok(Vec::new());
+        }
+        let available = self.available();
+        let row_bytes = row_bytes.max(1);
+        let max_rows = (self.max_binding.min(available) / row_bytes)
+            .min(u64::from(self.max_workgroups) * u64::from(workgroup_size));
+        if max_rows == 0 {
+            return Err(self.exceeded(rows as u64 * row_bytes, available));
+        }
+        let max_rows = usize::try_from(max_rows).unwrap_or(usize::MAX);
+        Ok((0..rows).step_by(max_rows).map(|start| start..rows.min(start + max_rows)).collect())
+    }
+
+    fn exceeded(&self, requested: u64, available: u64) -> Error {
+        Error::VramExceeded {
+            requested,
+            available,
+            suggested_morsel_bytes: available.min(self.max_binding),
+        }
+    }
+}
+
+/// Bytes held by one dispatch; released to the [`VramBudget`] on drop
+#[derive(Debug)]
+#[must_use = "the bytes are released as soon as the reservation is dropped"]
+pub struct VramReservation {
+    allocated: Arc<AtomicU64>,
+    bytes: u64,
+}
+
+impl VramReservation {
+    /// Bytes held
+    #[must_use]
+    pub const fn bytes(&self) -> u64 {
+        self.bytes
+    }
+}
+
+impl Drop for VramReservation {
+    fn drop(&mut self) {
+        self.allocated.fetch_sub(self.bytes, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn budget(capacity: u64) -> VramBudget {
+        let limits = wgpu::Limits {
+            max_buffer_size: 1024,
+            max_storage_buffer_binding_size: 1024,
+            max_compute_workgroups_per_dimension: 4,
+            ..wgpu::Limits::default()
+        };
+        VramBudget::new(capacity, &limits)
+    }
+
+    #[test]
+    fn test_reservations_are_released_on_drop() {
+        let budget = budget(1000);
+        let first = budget.reserve(600).unwrap();
+        assert_eq!(budget.allocated(), 600);
+
+        let Err(Error::VramExceeded { requested, available, suggested_morsel_bytes }) =
+            budget.reserve(500)
+        else {
+            panic!("reservation over the budget must fail");
+        };
+        assert_eq!((requested, available, suggested_morsel_bytes), (500, 400, 400));
+
+        drop(first);
+        assert_eq!(budget.available(), 1000);
+        assert_eq!(budget.reserve(1000).unwrap().bytes(), 1000);
+    }
+
+    #[test]
+    fn test_chunks_respect_binding_dispatch_and_budget() {
+        // Binding limit: 1024 / 4 = 256 rows per chunk
+        let chunks = budget(1 << 20).chunks(600, 4, 256).unwrap();
+        assert_eq!(chunks, vec![0..256, 256..512, 512..600]);
+
+        // Dispatch limit: 4 workgroups x 16 threads = 64 rows per chunk
+        assert_eq!(budget(1 << 20).chunks(100, 4, 16).unwrap(), vec![0..64, 64..100]);
+
+        // Free budget: 200 bytes left = 50 rows per chunk
+        let budget = budget(1000);
+        let _held = budget.reserve(800).unwrap();
+        assert_eq!(budget.chunks(120, 4, 256).unwrap(), vec![0..50, 50..100, 100..120]);
+
+        assert!(budget.chunks(0, 4, 256).unwrap().is_empty());
+        assert!(matches!(budget.chunks(10, 256, 256), Err(Error::VramExceeded { .. })));
+    }
+}
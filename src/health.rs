@@ -0,0 +1,299 @@
+//! Startup self-test
+//!
+//! [`self_test`] (also [`Database::self_test`](crate::Database::self_test))
+//! runs a few tiny end-to-end checks and returns a [`HealthReport`], so an
+//! embedder can detect a broken GPU driver or a misconfigured environment
+//! before serving queries:
+//!
+//! - **simd**: Arrow compute kernels (SUM/MIN/MAX with nulls and a ragged
+//!   tail) against a scalar loop
+//! - **query**: a filtered aggregate through the SQL engine
+//! - **storage**: a Parquet write/read roundtrip in the temp directory
+//!   (`parquet-io` feature)
+//! - **gpu**: a GPU SUM reduction against the scalar result (`gpu` feature);
+//!   skipped when no adapter is present, failed when one is present but
+//!   cannot run the kernel
+//!
+//! Every check works on a few thousand values, so the whole suite runs in
+//! milliseconds (plus GPU device creation).
+//!
+//! Toyota Way: Jidoka (stop at startup instead of serving wrong answers)
+
+use crate::backend::HardwareProfile;
+use crate::query::{QueryEngine, QueryExecutor};
+use crate::storage::StorageEngine;
+use crate::Result;
+use arrow::array::{Array, Float64Array, Int32Array, Int64Array, RecordBatch};
+use arrow::compute;
+use arrow::datatypes::{DataType, Field, Schema};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Values per check: not a multiple of any SIMD width, so tails are covered
+const CHECK_LEN: i32 = 2_051;
+
+/// Upper bound for the GPU reduction (device creation excluded)
+#[cfg(feature = "gpu")]
+const GPU_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Outcome of one check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckStatus {
+    /// The check ran and produced the expected result
+    Passed,
+    /// The check ran and failed (message explains why)
+    Failed(String),
+    /// The check did not apply to this build or machine (message explains why)
+    Skipped(String),
+}
+
+/// One named check and how long it took
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthCheck {
+    /// Check name (`simd`, `query`, `storage`, `gpu`)
+    pub name: &'static str,
+    /// Outcome
+    pub status: CheckStatus,
+    /// Wall time spent
+    pub elapsed: Duration,
+}
+
+/// Results of [`self_test`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthReport {
+    /// Hardware detected on this machine
+    pub profile: HardwareProfile,
+    /// Checks in the order they ran
+    pub checks: Vec<HealthCheck>,
+}
+
+impl HealthReport {
+    /// No check failed (skipped checks are fine)
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// Checks that failed
+    pub fn failures(&self) -> impl Iterator<Item = &HealthCheck> {
+        self.checks.iter().filter(|check| matches!(check.status, CheckStatus::Failed(_)))
+    }
+
+    /// Look up a check by name
+    #[must_use]
+    pub fn check(&self, name: &str) -> Option<&HealthCheck> {
+        self.checks.iter().find(|check| check.name == name)
+    }
+}
+
+impl fmt::Display for HealthReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            match &check.status {
+                CheckStatus::Passed => writeln!(f, "{:<8} ok ({:?})", check.name, check.elapsed)?,
+                CheckStatus::Failed(reason) => writeln!(f, "{:<8} FAILED: {reason}", check.name)?,
+                CheckStatus::Skipped(reason) => writeln!(f, "{:<8} skipped: {reason}", check.name)?,
+            }
+        }
+        write!(f, "{}", if self.is_healthy() { "healthy" } else { "UNHEALTHY" })
+    }
+}
+
+/// Run every check and report (see the [module docs](self))
+#[must_use]
+pub fn self_test() -> HealthReport {
+    let profile = HardwareProfile::detect();
+    let checks = vec![
+        run("simd", check_simd),
+        run("query", check_query),
+        run("storage", check_storage),
+        run("gpu", || check_gpu(&profile)),
+    ];
+    HealthReport { profile, checks }
+}
+
+fn run(name: &'static str, check: impl FnOnce() -> CheckStatus) -> HealthCheck {
+    let started = Instant::now();
+    let status = check();
+    HealthCheck { name, status, elapsed: started.elapsed() }
+}
+
+/// Turn a check's own error into a failure
+fn status(result: Result<CheckStatus>) -> CheckStatus {
+    result.unwrap_or_else(|e| CheckStatus::Failed(e.to_string()))
+}
+
+/// Check values with a null every 7th slot
+fn sample_ints() -> Int32Array {
+    (0..CHECK_LEN).map(|i| (i % 7 != 0).then_some(i * 37 % 1_009 - 500)).collect()
+}
+
+fn check_simd() -> CheckStatus {
+    let ints = sample_ints();
+    let scalar: Vec<i32> = ints.iter().flatten().collect();
+    let expected_sum: i64 = scalar.iter().map(|&v| i64::from(v)).sum();
+
+    let wide = compute::cast(&ints, &DataType::Int64)
+        .map(|array| array.as_any().downcast_ref::<Int64Array>().and_then(compute::sum));
+    match wide {
+        Ok(Some(sum)) if sum == expected_sum => {}
+        Ok(sum) => return failed("SUM", sum, expected_sum),
+        Err(e) => return CheckStatus::Failed(e.to_string()),
+    }
+    if compute::min(&ints) != scalar.iter().min().copied() {
+        return failed("MIN", compute::min(&ints), scalar.iter().min());
+    }
+    if compute::max(&ints) != scalar.iter().max().copied() {
+        return failed("MAX", compute::max(&ints), scalar.iter().max());
+    }
+
+    let floats = Float64Array::from_iter_values((0..CHECK_LEN).map(|i| f64::from(i) * 0.25));
+    let expected: f64 = floats.values().iter().sum();
+    match compute::sum(&floats) {
+        Some(sum) if (sum - expected).abs() <= expected.abs() * 1e-12 => CheckStatus::Passed,
+        sum => failed("f64 SUM", sum, expected),
+    }
+}
+
+fn check_query() -> CheckStatus {
+    status(query_roundtrip())
+}
+
+fn query_roundtrip() -> Result<CheckStatus> {
+    let ints = sample_ints();
+    let schema = Arc::new(Schema::new(vec![Field::new("v", DataType::Int32, true)]));
+    let batch = RecordBatch::try_new(schema, vec![Arc::new(ints.clone())])?;
+    let storage = StorageEngine::new(vec![batch]);
+
+    let plan = QueryEngine::new().parse("SELECT COUNT(v), MAX(v) FROM t WHERE v > 0")?;
+    let result = QueryExecutor::new().execute(&plan, &storage)?;
+
+    let positive: Vec<i32> = ints.iter().flatten().filter(|&v| v > 0).collect();
+    let count = result.column(0).as_any().downcast_ref::<Int64Array>().map(|a| a.value(0));
+    let max = result.column(1).as_any().downcast_ref::<Int32Array>().map(|a| a.value(0));
+    let expected = (i64::try_from(positive.len()).ok(), positive.iter().max().copied());
+    Ok(if (count, max) == expected {
+        CheckStatus::Passed
+    } else {
+        failed("COUNT/MAX", (count, max), expected)
+    })
+}
+
+#[cfg(feature = "parquet-io")]
+fn check_storage() -> CheckStatus {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static RUN: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "trueno_db_self_test_{}_{}.parquet",
+        std::process::id(),
+        RUN.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = status(parquet_roundtrip(&path));
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+/// Write a sample batch to `path` and check it reads back unchanged
+#[cfg(feature = "parquet-io")]
+fn parquet_roundtrip(path: &std::path::Path) -> Result<CheckStatus> {
+    use parquet::arrow::ArrowWriter;
+
+    let schema = Arc::new(Schema::new(vec![Field::new("v", DataType::Int32, true)]));
+    let batch = RecordBatch::try_new(schema, vec![Arc::new(sample_ints())])?;
+    let file = std::fs::File::create(path)?;
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), None)
+        .map_err(|e| crate::Error::StorageError(e.to_string()))?;
+    writer.write(&batch).map_err(|e| crate::Error::StorageError(e.to_string()))?;
+    writer.close().map_err(|e| crate::Error::StorageError(e.to_string()))?;
+
+    // The reader returns the rows in batches of its own size
+    let loaded = StorageEngine::load_parquet(path)?;
+    let loaded = compute::concat_batches(&batch.schema(), loaded.batches())?;
+    let same = loaded.columns() == batch.columns();
+    Ok(if same {
+        CheckStatus::Passed
+    } else {
+        CheckStatus::Failed(format!("Parquet roundtrip changed data at {}", path.display()))
+    })
+}
+
+#[cfg(not(feature = "parquet-io"))]
+fn check_storage() -> CheckStatus {
+    CheckStatus::Skipped("built without the parquet-io feature".to_string())
+}
+
+#[cfg(feature = "gpu")]
+fn check_gpu(profile: &HardwareProfile) -> CheckStatus {
+    use crate::gpu::{block_on, GpuEngine};
+
+    if !profile.has_gpu() {
+        return CheckStatus::Skipped("no GPU adapter detected".to_string());
+    }
+    let data = Int32Array::from_iter_values((0..CHECK_LEN).map(|i| i * 37 % 1_009 - 500));
+    let expected: i32 = data.values().iter().sum();
+
+    // A dedicated thread keeps the blocking wait off any async runtime the
+    // caller is on, and contains a panicking driver
+    let outcome = std::thread::scope(|scope| {
+        scope
+            .spawn(|| {
+                block_on(async {
+                    let engine = GpuEngine::new().await?.with_submission_timeout(GPU_CHECK_TIMEOUT);
+                    engine.sum_i32(&data).await
+                })
+            })
+            .join()
+    });
+    match outcome {
+        Ok(Ok(sum)) if sum == expected => CheckStatus::Passed,
+        Ok(Ok(sum)) => failed("GPU SUM", sum, expected),
+        Ok(Err(e)) => CheckStatus::Failed(e.to_string()),
+        Err(_) => CheckStatus::Failed("GPU check panicked".to_string()),
+    }
+}
+
+#[cfg(not(feature = "gpu"))]
+fn check_gpu(_profile: &HardwareProfile) -> CheckStatus {
+    CheckStatus::Skipped("built without the gpu feature".to_string())
+}
+
+fn failed(what: &str, got: impl fmt::Debug, expected: impl fmt::Debug) -> CheckStatus {
+    CheckStatus::Failed(format!("{what} returned {got:?}, scalar reference {expected:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_self_test_is_healthy() {
+        let report = self_test();
+        assert!(report.is_healthy(), "{report}");
+        assert_eq!(report.check("simd").unwrap().status, CheckStatus::Passed);
+        assert_eq!(report.check("query").unwrap().status, CheckStatus::Passed);
+        #[cfg(feature = "parquet-io")]
+        assert_eq!(report.check("storage").unwrap().status, CheckStatus::Passed);
+        assert!(report.check("gpu").is_some());
+    }
+
+    #[test]
+    fn test_report_failures() {
+        let report = HealthReport {
+            profile: HardwareProfile::detect(),
+            checks: vec![
+                HealthCheck { name: "simd", status: CheckStatus::Passed, elapsed: Duration::ZERO },
+                HealthCheck {
+                    name: "gpu",
+                    status: CheckStatus::Failed("device lost".to_string()),
+                    elapsed: Duration::ZERO,
+                },
+            ],
+        };
+        assert!(!report.is_healthy());
+        assert_eq!(report.failures().map(|check| check.name).collect::<Vec<_>>(), ["gpu"]);
+        assert!(report.to_string().contains("gpu      FAILED: device lost"));
+    }
+}
@@ -7,6 +7,8 @@
 
 use crate::kv::KvStore;
 use crate::Result;
+use std::ops::Bound;
+use std::time::Duration;
 
 pub use batuta_common::compression::Compression;
 
@@ -71,6 +73,11 @@ impl<S: KvStore> KvStore for CompressedKvStore<S> {
         self.inner.set(key, compressed).await
     }
 
+    async fn set_with_ttl(&self, key: &str, value: Vec<u8>, ttl: Duration) -> Result<()> {
+        let compressed = self.compression.compress(&value)?;
+        self.inner.set_with_ttl(key, compressed, ttl).await
+    }
+
     async fn delete(&self, key: &str) -> Result<()> {
         self.inner.delete(key).await
     }
@@ -78,6 +85,19 @@ impl<S: KvStore> KvStore for CompressedKvStore<S> {
     async fn exists(&self, key: &str) -> Result<bool> {
         self.inner.exists(key).await
     }
+
+    async fn scan_range(
+        &self,
+        start: Bound<&str>,
+        end: Bound<&str>,
+        limit: usize,
+    ) -> Result<Vec<(String, Vec<u8>)>> {
+        let pairs = self.inner.scan_range(start, end, limit).await?;
+        pairs
+            .into_iter()
+            .map(|(key, compressed)| Ok((key, self.compression.decompress(&compressed)?)))
+            .collect()
+    }
 }
 
 #[cfg(test)]
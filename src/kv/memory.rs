@@ -1,16 +1,47 @@
 //! In-memory KV store implementation using `DashMap`.
 //!
 //! This is the default backend - data is lost on process restart.
-//! For persistence, use `ParquetKvStore` (future).
+//! For persistence, use `PersistentKvStore`.
+//!
+//! Entries written with [`KvStore::set_with_ttl`] expire lazily: a read
+//! after the deadline removes the entry and reports it missing. Expired
+//! entries nobody reads are dropped by [`MemoryKvStore::purge_expired`], or
+//! periodically by [`MemoryKvStore::spawn_expiry`].
+//!
+//! Range and prefix scans walk a sorted key set kept beside the map. Writers
+//! update it while holding the key's map shard, and scans never hold both
+//! locks at once, so the two cannot deadlock.
 
-use super::KvStore;
+use super::{scan, KvStore};
+use crate::metrics::{self, Counter};
 use crate::Result;
+use dashmap::mapref::entry::Entry as Slot;
 use dashmap::DashMap;
+use std::collections::BTreeSet;
+use std::ops::Bound;
+#[cfg(feature = "tokio")]
+use std::sync::Arc;
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, Instant};
+
+/// A stored value and its expiry deadline
+struct Entry {
+    value: Vec<u8>,
+    /// `None` for entries that never expire
+    expires_at: Option<Instant>,
+}
+
+impl Entry {
+    fn is_live(&self, now: Instant) -> bool {
+        self.expires_at.map_or(true, |deadline| now < deadline)
+    }
+}
 
 /// In-memory key-value store using lock-free concurrent hashmap.
 ///
 /// Thread-safe and optimized for high-concurrency read/write workloads.
-/// Uses `DashMap` internally for O(1) average-case operations.
+/// Uses `DashMap` internally for O(1) average-case operations, plus an
+/// ordered key index for [`KvStore::range`] and [`KvStore::scan_prefix`].
 ///
 /// # Example
 ///
@@ -25,23 +56,27 @@ use dashmap::DashMap;
 /// # }
 /// ```
 pub struct MemoryKvStore {
-    store: DashMap<String, Vec<u8>>,
+    store: DashMap<String, Entry>,
+    /// Keys of `store` in order, for scans
+    ordered: RwLock<BTreeSet<String>>,
 }
 
 impl MemoryKvStore {
     /// Create a new in-memory KV store.
     #[must_use]
     pub fn new() -> Self {
-        Self { store: DashMap::new() }
+        Self { store: DashMap::new(), ordered: RwLock::default() }
     }
 
     /// Create with pre-allocated capacity.
     #[must_use]
     pub fn with_capacity(capacity: usize) -> Self {
-        Self { store: DashMap::with_capacity(capacity) }
+        Self { store: DashMap::with_capacity(capacity), ordered: RwLock::default() }
     }
 
     /// Get the number of entries in the store.
+    ///
+    /// Includes expired entries that have not been read or purged yet.
     #[must_use]
     pub fn len(&self) -> usize {
         self.store.len()
@@ -55,7 +90,103 @@ impl MemoryKvStore {
 
     /// Clear all entries.
     pub fn clear(&self) {
-        self.store.clear();
+        self.store.retain(|key, _| {
+            self.keys_mut().remove(key);
+            false
+        });
+    }
+
+    /// Remove every expired entry, returning how many were removed.
+    pub fn purge_expired(&self) -> usize {
+        let now = Instant::now();
+        let mut removed = 0;
+        self.store.retain(|key, entry| {
+            let live = entry.is_live(now);
+            if !live {
+                self.keys_mut().remove(key);
+                removed += 1;
+            }
+            live
+        });
+        removed
+    }
+
+    /// Purge expired entries every `period` on the Tokio runtime.
+    ///
+    /// The task holds only a weak reference and ends once the store is
+    /// dropped.
+    ///
+    /// # Panics
+    /// Panics if `period` is zero.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    /// use trueno_db::kv::{KvStore, MemoryKvStore};
+    ///
+    /// # async fn example() -> trueno_db::Result<()> {
+    /// let sessions = Arc::new(MemoryKvStore::new());
+    /// sessions.spawn_expiry(Duration::from_secs(30));
+    /// sessions.set_with_ttl("session:42", b"alice".to_vec(), Duration::from_secs(900)).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn spawn_expiry(self: &Arc<Self>, period: Duration) -> tokio::task::JoinHandle<()> {
+        let store = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(period);
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticks.tick().await;
+                let Some(store) = store.upgrade() else { break };
+                store.purge_expired();
+            }
+        })
+    }
+
+    /// Apply `read` to the entry for `key` if it is live
+    ///
+    /// An expired entry is removed and reads as missing.
+    fn read_live<T>(&self, key: &str, read: impl FnOnce(&Entry) -> T) -> Option<T> {
+        let now = Instant::now();
+        {
+            let entry = self.store.get(key)?;
+            if entry.is_live(now) {
+                return Some(read(&entry));
+            }
+        }
+        if let Slot::Occupied(slot) = self.store.entry(key.to_string()) {
+            if !slot.get().is_live(now) {
+                self.keys_mut().remove(key);
+                slot.remove();
+            }
+        }
+        None
+    }
+
+    /// Insert or replace the entry for `key`
+    fn insert(&self, key: &str, entry: Entry) {
+        match self.store.entry(key.to_string()) {
+            Slot::Occupied(mut slot) => {
+                slot.insert(entry);
+            }
+            Slot::Vacant(slot) => {
+                self.keys_mut().insert(key.to_string());
+                slot.insert(entry);
+            }
+        }
+    }
+
+    fn keys(&self) -> RwLockReadGuard<'_, BTreeSet<String>> {
+        self.ordered.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Lock the key index for writing (only while holding the key's shard)
+    fn keys_mut(&self) -> RwLockWriteGuard<'_, BTreeSet<String>> {
+        self.ordered.write().unwrap_or_else(PoisonError::into_inner)
     }
 }
 
@@ -67,20 +198,67 @@ impl Default for MemoryKvStore {
 
 impl KvStore for MemoryKvStore {
     async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
-        Ok(self.store.get(key).map(|v| v.value().clone()))
+        metrics::count(Counter::KvReads, 1);
+        Ok(self.read_live(key, |entry| entry.value.clone()))
     }
 
     async fn set(&self, key: &str, value: Vec<u8>) -> Result<()> {
-        self.store.insert(key.to_string(), value);
+        metrics::count(Counter::KvWrites, 1);
+        self.insert(key, Entry { value, expires_at: None });
+        Ok(())
+    }
+
+    /// A `ttl` too large to represent never expires.
+    async fn set_with_ttl(&self, key: &str, value: Vec<u8>, ttl: Duration) -> Result<()> {
+        metrics::count(Counter::KvWrites, 1);
+        let expires_at = Instant::now().checked_add(ttl);
+        self.insert(key, Entry { value, expires_at });
         Ok(())
     }
 
     async fn delete(&self, key: &str) -> Result<()> {
-        self.store.remove(key);
+        metrics::count(Counter::KvWrites, 1);
+        if let Slot::Occupied(slot) = self.store.entry(key.to_string()) {
+            self.keys_mut().remove(key);
+            slot.remove();
+        }
         Ok(())
     }
 
     async fn exists(&self, key: &str) -> Result<bool> {
-        Ok(self.store.contains_key(key))
+        metrics::count(Counter::KvReads, 1);
+        Ok(self.read_live(key, |_| ()).is_some())
+    }
+
+    async fn scan_range(
+        &self,
+        start: Bound<&str>,
+        end: Bound<&str>,
+        limit: usize,
+    ) -> Result<Vec<(String, Vec<u8>)>> {
+        let mut pairs = Vec::new();
+        let mut from = scan::owned(start.as_ref());
+        // Expired keys are skipped, so a page may take several index reads
+        while pairs.len() < limit && !scan::is_empty_range(scan::borrowed(&from), end) {
+            let wanted = limit - pairs.len();
+            let keys: Vec<String> = self
+                .keys()
+                .range::<str, _>((scan::borrowed(&from), end))
+                .take(wanted)
+                .cloned()
+                .collect();
+            let Some(last) = keys.last() else { break };
+            from = Bound::Excluded(last.clone());
+            let exhausted = keys.len() < wanted;
+            metrics::count(Counter::KvReads, keys.len());
+            pairs.extend(keys.into_iter().filter_map(|key| {
+                let value = self.read_live(&key, |entry| entry.value.clone())?;
+                Some((key, value))
+            }));
+            if exhausted {
+                break;
+            }
+        }
+        Ok(pairs)
     }
 }
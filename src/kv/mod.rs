@@ -2,8 +2,11 @@
 //!
 //! Provides a simple, high-performance key-value store with:
 //! - SIMD-optimized key hashing via `trueno::hash`
-//! - In-memory and persistent backends
+//! - In-memory (`MemoryKvStore`) and on-disk (`PersistentKvStore`) backends
 //! - Async-first API compatible with pforge `StateManager`
+//! - Per-entry TTLs (`set_with_ttl`) for session caches
+//! - Ordered range and prefix scans (`range`, `scan_prefix`) for namespaced
+//!   keys such as `run-001/metrics/*`
 //!
 //! # Example
 //!
@@ -26,10 +29,19 @@
 
 mod memory;
 
+#[cfg(feature = "tokio")]
+mod persistent;
+
 #[cfg(feature = "compression")]
 mod compressed;
 
+mod scan;
+
 pub use memory::MemoryKvStore;
+pub use scan::{KvScan, SCAN_PAGE};
+
+#[cfg(feature = "tokio")]
+pub use persistent::{LogStats, PersistentKvOptions, PersistentKvStore};
 
 #[cfg(feature = "compression")]
 pub use compressed::{CompressedKvStore, Compression};
@@ -39,6 +51,8 @@ pub use trueno::{hash_bytes, hash_key, hash_keys_batch};
 
 use crate::Result;
 use std::future::Future;
+use std::ops::{Bound, RangeBounds};
+use std::time::Duration;
 
 /// Key-value store trait for pforge state management integration.
 ///
@@ -55,6 +69,18 @@ pub trait KvStore: Send + Sync {
     /// Overwrites any existing value.
     fn set(&self, key: &str, value: Vec<u8>) -> impl Future<Output = Result<()>> + Send;
 
+    /// Set a value that expires `ttl` from now.
+    ///
+    /// Once expired the key reads as missing: `get` returns `None` and
+    /// `exists` returns `false`. A later `set` replaces the value and
+    /// clears the expiry.
+    fn set_with_ttl(
+        &self,
+        key: &str,
+        value: Vec<u8>,
+        ttl: Duration,
+    ) -> impl Future<Output = Result<()>> + Send;
+
     /// Delete a key.
     ///
     /// No-op if the key doesn't exist.
@@ -88,6 +114,31 @@ pub trait KvStore: Send + Sync {
             Ok(())
         }
     }
+
+    /// Get up to `limit` live pairs with keys between `start` and `end`,
+    /// in ascending key order.
+    ///
+    /// This is one page of a scan; most callers want [`KvStore::range`] or
+    /// [`KvStore::scan_prefix`] instead.
+    fn scan_range(
+        &self,
+        start: Bound<&str>,
+        end: Bound<&str>,
+        limit: usize,
+    ) -> impl Future<Output = Result<Vec<(String, Vec<u8>)>>> + Send;
+
+    /// Iterate the pairs whose keys fall in `range`, in key order.
+    ///
+    /// Keys compare as byte strings, so `"a".."b"` holds every key starting
+    /// with `"a"`.
+    fn range<'k>(&self, range: impl RangeBounds<&'k str>) -> KvScan<'_, Self> {
+        KvScan::new(self, scan::owned(range.start_bound()), scan::owned(range.end_bound()))
+    }
+
+    /// Iterate the pairs whose keys start with `prefix`, in key order.
+    fn scan_prefix(&self, prefix: &str) -> KvScan<'_, Self> {
+        KvScan::prefix(self, prefix)
+    }
 }
 
 #[cfg(test)]
@@ -281,6 +332,101 @@ mod tests {
         assert_eq!(store.get("key1").await.unwrap(), None);
     }
 
+    #[tokio::test]
+    async fn test_memory_kv_ttl_expires_lazily() {
+        let store = MemoryKvStore::new();
+
+        store.set_with_ttl("session", b"alice".to_vec(), Duration::from_secs(60)).await.unwrap();
+        store.set_with_ttl("stale", b"bob".to_vec(), Duration::ZERO).await.unwrap();
+        assert_eq!(store.get("session").await.unwrap(), Some(b"alice".to_vec()));
+        assert_eq!(store.len(), 2);
+
+        // Reading an expired entry removes it
+        assert_eq!(store.get("stale").await.unwrap(), None);
+        assert!(!store.exists("stale").await.unwrap());
+        assert_eq!(store.len(), 1);
+
+        // A plain set clears the expiry
+        store.set_with_ttl("session", b"alice".to_vec(), Duration::ZERO).await.unwrap();
+        store.set("session", b"carol".to_vec()).await.unwrap();
+        assert_eq!(store.get("session").await.unwrap(), Some(b"carol".to_vec()));
+    }
+
+    #[tokio::test]
+    async fn test_memory_kv_purge_expired() {
+        let store = MemoryKvStore::new();
+        store.set("keep", b"1".to_vec()).await.unwrap();
+        store.set_with_ttl("a", b"2".to_vec(), Duration::ZERO).await.unwrap();
+        store.set_with_ttl("b", b"3".to_vec(), Duration::ZERO).await.unwrap();
+
+        assert_eq!(store.purge_expired(), 2);
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.get("keep").await.unwrap(), Some(b"1".to_vec()));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_memory_kv_background_expiry() {
+        let store = std::sync::Arc::new(MemoryKvStore::new());
+        store.set("keep", b"1".to_vec()).await.unwrap();
+        store.set_with_ttl("c", b"4".to_vec(), Duration::ZERO).await.unwrap();
+        let task = store.spawn_expiry(Duration::from_millis(5));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(store.len(), 1);
+
+        // The task ends with the store
+        drop(store);
+        tokio::time::timeout(Duration::from_secs(1), task).await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_memory_kv_scan_prefix() {
+        let store = MemoryKvStore::new();
+        for key in ["run-002/metrics/loss", "run-001/params/lr", "run-001/metrics/loss"] {
+            store.set(key, key.as_bytes().to_vec()).await.unwrap();
+        }
+        store.set("run-001/metrics/acc", b"0.9".to_vec()).await.unwrap();
+        store.set_with_ttl("run-001/metrics/old", b"x".to_vec(), Duration::ZERO).await.unwrap();
+        store.set("run-001/metrics/gone", b"x".to_vec()).await.unwrap();
+        store.delete("run-001/metrics/gone").await.unwrap();
+
+        let pairs = store.scan_prefix("run-001/metrics/").try_collect().await.unwrap();
+        let keys: Vec<&str> = pairs.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, vec!["run-001/metrics/acc", "run-001/metrics/loss"]);
+        assert_eq!(pairs[0].1, b"0.9".to_vec());
+
+        assert_eq!(store.scan_prefix("").try_collect().await.unwrap().len(), 4);
+        assert!(store.scan_prefix("run-003/").try_next().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_memory_kv_range_spans_pages() {
+        let store = MemoryKvStore::new();
+        let total = SCAN_PAGE * 2 + 10;
+        for i in 0..total {
+            store.set(&format!("k{i:05}"), vec![]).await.unwrap();
+        }
+
+        let all = store.range(..).try_collect().await.unwrap();
+        assert_eq!(all.len(), total);
+        assert!(all.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+        let keys: Vec<String> = store
+            .range("k00010".."k00013")
+            .try_collect()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(keys, vec!["k00010", "k00011", "k00012"]);
+        assert_eq!(store.range("k00013"..="k00013").try_collect().await.unwrap().len(), 1);
+        assert!(store.range("k9".."k0").try_collect().await.unwrap().is_empty());
+
+        store.clear();
+        assert!(store.range(..).try_next().await.unwrap().is_none());
+    }
+
     #[test]
     fn test_memory_kv_default() {
         let store: MemoryKvStore = MemoryKvStore::default();
@@ -383,12 +529,33 @@ mod tests {
             assert_eq!(results[2], None);
         }
 
+        #[tokio::test]
+        async fn test_compressed_kv_ttl() {
+            let inner = MemoryKvStore::new();
+            let store = CompressedKvStore::new(inner, Compression::Lz4);
+
+            store.set_with_ttl("live", b"value".to_vec(), Duration::from_secs(60)).await.unwrap();
+            store.set_with_ttl("gone", b"value".to_vec(), Duration::ZERO).await.unwrap();
+            assert_eq!(store.get("live").await.unwrap(), Some(b"value".to_vec()));
+            assert_eq!(store.get("gone").await.unwrap(), None);
+        }
+
         #[tokio::test]
         async fn test_compression_enum_variants() {
             assert_eq!(Compression::Lz4.as_str(), "lz4");
             assert_eq!(Compression::Zstd.as_str(), "zstd");
         }
 
+        #[tokio::test]
+        async fn test_compressed_kv_scan_prefix() {
+            let store = CompressedKvStore::new(MemoryKvStore::new(), Compression::Lz4);
+            store.set("a/1", b"one one one".to_vec()).await.unwrap();
+            store.set("b/1", b"two".to_vec()).await.unwrap();
+
+            let pairs = store.scan_prefix("a/").try_collect().await.unwrap();
+            assert_eq!(pairs, vec![("a/1".to_string(), b"one one one".to_vec())]);
+        }
+
         #[tokio::test]
         async fn test_compressed_kv_large_value() {
             let inner = MemoryKvStore::new();
@@ -0,0 +1,876 @@
+//! Persistent KV store backed by an append-only log file.
+//!
+//! Every `set` and `delete` appends one checksummed record to `kv.log` inside
+//! the store directory; an in-memory index keyed by `trueno::hash_key` maps each
+//! live key to the offset of its latest value. Opening the store replays the log
+//! to rebuild the index, truncating a torn or corrupt tail left by a crash.
+//! [`PersistentKvStore::compact`] rewrites only the live records into a fresh
+//! file and atomically renames it over the old log.
+//!
+//! Record layout (little-endian):
+//!
+//! ```text
+//! | checksum u64 | kind u8 | key_len u32 | value_len u32 | key | value |
+//! ```
+//!
+//! The checksum is `trueno::hash_bytes` over everything after it. Values
+//! written with [`KvStore::set_with_ttl`] use a third record kind whose value
+//! starts with the expiry deadline (`u64` Unix milliseconds), so TTLs survive
+//! a restart. Expired values read as missing, leave the index on access or
+//! via [`PersistentKvStore::purge_expired`], and are dropped by compaction.
+//!
+//! Toyota Way: Jidoka - a record that fails its checksum stops replay rather
+//! than surfacing half-written state.
+
+use super::{scan, KvStore};
+use crate::metrics::{self, Counter};
+use crate::{Error, Result};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Log file name inside the store directory
+const LOG_FILE: &str = "kv.log";
+
+/// Temporary file compaction writes before renaming it over [`LOG_FILE`]
+const COMPACT_FILE: &str = "kv.log.compact";
+
+/// Bytes before the key: checksum, kind, key length, value length
+const HEADER_LEN: u64 = 8 + 1 + 4 + 4;
+
+const KIND_SET: u8 = 1;
+const KIND_DELETE: u8 = 2;
+const KIND_SET_EXPIRING: u8 = 3;
+
+/// Bytes of expiry deadline before the value of a [`KIND_SET_EXPIRING`] record
+const EXPIRY_LEN: u64 = 8;
+
+/// Options for [`PersistentKvStore::open_with_options`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PersistentKvOptions {
+    /// `fsync` the log after every write (default `true`).
+    ///
+    /// Disabling this trades durability of the most recent writes for
+    /// throughput; the log stays consistent either way because replay drops
+    /// any torn tail.
+    pub sync_writes: bool,
+    /// Compact automatically once dead bytes exceed this fraction of the log
+    /// (default `None`, compaction only runs via [`PersistentKvStore::compact`]).
+    pub auto_compact_ratio: Option<f64>,
+}
+
+impl Default for PersistentKvOptions {
+    fn default() -> Self {
+        Self { sync_writes: true, auto_compact_ratio: None }
+    }
+}
+
+/// Log statistics reported by [`PersistentKvStore::stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogStats {
+    /// Number of live keys
+    pub keys: usize,
+    /// Total size of the log file in bytes
+    pub log_bytes: u64,
+    /// Bytes held by overwritten values and tombstones
+    pub dead_bytes: u64,
+}
+
+/// Location of a live value inside the log
+#[derive(Debug, Clone, Copy)]
+struct ValueRef {
+    /// Offset of the whole record (used to size it when it goes dead)
+    record_offset: u64,
+    /// Offset of the value bytes
+    value_offset: u64,
+    value_len: u32,
+    /// Expiry deadline in Unix milliseconds (`None` never expires)
+    expires_at: Option<u64>,
+}
+
+impl ValueRef {
+    fn record_len(&self) -> u64 {
+        self.value_offset + u64::from(self.value_len) - self.record_offset
+    }
+
+    fn is_live(&self, now: u64) -> bool {
+        self.expires_at.map_or(true, |deadline| now < deadline)
+    }
+}
+
+/// One decoded log record
+struct Record {
+    kind: u8,
+    key: String,
+    /// Length of the value, excluding any expiry deadline
+    value_len: u32,
+    expires_at: Option<u64>,
+}
+
+impl Record {
+    /// Bytes the record occupies in the log
+    fn len(&self) -> u64 {
+        let expiry = if self.kind == KIND_SET_EXPIRING { EXPIRY_LEN } else { 0 };
+        HEADER_LEN + self.key.len() as u64 + expiry + u64::from(self.value_len)
+    }
+}
+
+/// Mutable store state, guarded by a single mutex
+struct LogState {
+    dir: PathBuf,
+    file: File,
+    /// Index keyed by `hash_key`; each bucket resolves hash collisions
+    index: HashMap<u64, Vec<(String, ValueRef)>>,
+    keys: usize,
+    log_bytes: u64,
+    dead_bytes: u64,
+    options: PersistentKvOptions,
+}
+
+/// Disk-backed key-value store with crash-safe appends and compaction.
+///
+/// Values live on disk and are read back on `get`; only keys and offsets are
+/// held in memory. File I/O runs on Tokio's blocking pool so it never stalls
+/// the async reactor.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use trueno_db::kv::{KvStore, PersistentKvStore};
+///
+/// # async fn example() -> trueno_db::Result<()> {
+/// let store = PersistentKvStore::open("/var/lib/pforge/state").await?;
+/// store.set("session", b"abc123".to_vec()).await?;
+/// store.close().await?;
+///
+/// let store = PersistentKvStore::open("/var/lib/pforge/state").await?;
+/// assert_eq!(store.get("session").await?, Some(b"abc123".to_vec()));
+/// # Ok(())
+/// # }
+/// ```
+pub struct PersistentKvStore {
+    state: Arc<Mutex<LogState>>,
+}
+
+impl PersistentKvStore {
+    /// Open (or create) a store in `dir` with default options.
+    pub async fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        Self::open_with_options(dir, PersistentKvOptions::default()).await
+    }
+
+    /// Open (or create) a store in `dir`, replaying its log.
+    ///
+    /// A torn or corrupt tail is truncated away; everything before it is kept.
+    pub async fn open_with_options(
+        dir: impl AsRef<Path>,
+        options: PersistentKvOptions,
+    ) -> Result<Self> {
+        if let Some(ratio) = options.auto_compact_ratio {
+            if !(0.0..=1.0).contains(&ratio) {
+                return Err(Error::InvalidInput(format!(
+                    "auto_compact_ratio must be within 0.0..=1.0, got {ratio}"
+                )));
+            }
+        }
+        let dir = dir.as_ref().to_path_buf();
+        let state = blocking(move || LogState::open(dir, options)).await?;
+        Ok(Self { state: Arc::new(Mutex::new(state)) })
+    }
+
+    /// Flush the log to disk and close the store.
+    pub async fn close(self) -> Result<()> {
+        self.with_state(|state| Ok(state.file.sync_all()?)).await
+    }
+
+    /// Rewrite the log keeping only live values.
+    ///
+    /// The compacted log is written to a temporary file, synced, and renamed
+    /// over the old one, so a crash mid-compaction leaves the original intact.
+    pub async fn compact(&self) -> Result<()> {
+        self.with_state(LogState::compact).await
+    }
+
+    /// Current key count and log size.
+    pub fn stats(&self) -> LogStats {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        LogStats { keys: state.keys, log_bytes: state.log_bytes, dead_bytes: state.dead_bytes }
+    }
+
+    /// Remove expired keys from the index, returning how many were removed.
+    ///
+    /// Their records stay in the log (as dead bytes) until compaction.
+    pub async fn purge_expired(&self) -> Result<usize> {
+        self.with_state(|state| Ok(state.purge_expired())).await
+    }
+
+    /// Get the number of live keys.
+    ///
+    /// Includes expired keys that have not been read or purged yet.
+    pub fn len(&self) -> usize {
+        self.stats().keys
+    }
+
+    /// Check if the store holds no live keys.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Run `op` against the locked state on the blocking pool
+    async fn with_state<T, F>(&self, op: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut LogState) -> Result<T> + Send + 'static,
+    {
+        let state = Arc::clone(&self.state);
+        blocking(move || op(&mut state.lock().unwrap_or_else(PoisonError::into_inner))).await
+    }
+}
+
+impl KvStore for PersistentKvStore {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        metrics::count(Counter::KvReads, 1);
+        let key = key.to_string();
+        self.with_state(move |state| state.read(&key)).await
+    }
+
+    async fn set(&self, key: &str, value: Vec<u8>) -> Result<()> {
+        metrics::count(Counter::KvWrites, 1);
+        let key = key.to_string();
+        self.with_state(move |state| state.append(&[(key, Some(value))], None)).await
+    }
+
+    /// The deadline is wall-clock time, so it holds across restarts.
+    async fn set_with_ttl(&self, key: &str, value: Vec<u8>, ttl: Duration) -> Result<()> {
+        metrics::count(Counter::KvWrites, 1);
+        let key = key.to_string();
+        let ttl = u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX);
+        let expires_at = Some(now_millis().saturating_add(ttl));
+        self.with_state(move |state| state.append(&[(key, Some(value))], expires_at)).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        metrics::count(Counter::KvWrites, 1);
+        let key = key.to_string();
+        self.with_state(move |state| state.append(&[(key, None)], None)).await
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool> {
+        metrics::count(Counter::KvReads, 1);
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        Ok(state.lookup(key).is_some_and(|location| location.is_live(now_millis())))
+    }
+
+    async fn batch_get(&self, keys: &[&str]) -> Result<Vec<Option<Vec<u8>>>> {
+        metrics::count(Counter::KvReads, keys.len());
+        let keys: Vec<String> = keys.iter().map(ToString::to_string).collect();
+        self.with_state(move |state| keys.iter().map(|key| state.read(key)).collect()).await
+    }
+
+    /// Appends every pair with a single write and a single `fsync`.
+    async fn batch_set(&self, pairs: Vec<(&str, Vec<u8>)>) -> Result<()> {
+        metrics::count(Counter::KvWrites, pairs.len());
+        let writes: Vec<(String, Option<Vec<u8>>)> =
+            pairs.into_iter().map(|(key, value)| (key.to_string(), Some(value))).collect();
+        self.with_state(move |state| state.append(&writes, None)).await
+    }
+
+    /// Keys are not stored in order, so each page sorts the matching keys.
+    async fn scan_range(
+        &self,
+        start: Bound<&str>,
+        end: Bound<&str>,
+        limit: usize,
+    ) -> Result<Vec<(String, Vec<u8>)>> {
+        let (start, end) = (scan::owned(start.as_ref()), scan::owned(end.as_ref()));
+        let pairs = self
+            .with_state(move |state| {
+                state.scan(scan::borrowed(&start), scan::borrowed(&end), limit)
+            })
+            .await?;
+        metrics::count(Counter::KvReads, pairs.len());
+        Ok(pairs)
+    }
+}
+
+impl LogState {
+    fn open(dir: PathBuf, options: PersistentKvOptions) -> Result<Self> {
+        fs::create_dir_all(&dir)?;
+        // A leftover compaction file means we crashed before the rename; the
+        // original log is still authoritative.
+        if let Err(e) = fs::remove_file(dir.join(COMPACT_FILE)) {
+            if e.kind() != ErrorKind::NotFound {
+                return Err(e.into());
+            }
+        }
+
+        let file =
+            OpenOptions::new().read(true).append(true).create(true).open(dir.join(LOG_FILE))?;
+        let mut state = Self {
+            dir,
+            file,
+            index: HashMap::new(),
+            keys: 0,
+            log_bytes: 0,
+            dead_bytes: 0,
+            options,
+        };
+        state.replay()?;
+        Ok(state)
+    }
+
+    /// Rebuild the index from the log, truncating anything after the last
+    /// intact record.
+    fn replay(&mut self) -> Result<()> {
+        let file_len = self.file.metadata()?.len();
+        self.file.seek(SeekFrom::Start(0))?;
+        let mut offset = 0;
+        let mut records = Vec::new();
+        {
+            let mut reader = BufReader::new(&self.file);
+            while let Some(record) = read_record(&mut reader, file_len - offset)? {
+                let record_len = record.len();
+                records.push((offset, record));
+                offset += record_len;
+            }
+        }
+
+        if offset < file_len {
+            tracing::warn!(
+                "Truncating {} bytes of torn or corrupt KV log tail at offset {offset}",
+                file_len - offset
+            );
+            self.file.set_len(offset)?;
+            self.file.sync_all()?;
+        }
+        self.log_bytes = offset;
+        for (record_offset, record) in records {
+            self.apply(record_offset, record.kind, record.key, record.value_len, record.expires_at);
+        }
+        self.purge_expired();
+        Ok(())
+    }
+
+    /// Index entry for `key`, live or expired
+    fn lookup(&self, key: &str) -> Option<ValueRef> {
+        let bucket = self.index.get(&super::hash_key(key))?;
+        bucket.iter().find(|(k, _)| k == key).map(|(_, location)| *location)
+    }
+
+    /// Update the index for a record that starts at `record_offset`
+    fn apply(
+        &mut self,
+        record_offset: u64,
+        kind: u8,
+        key: String,
+        value_len: u32,
+        expires_at: Option<u64>,
+    ) {
+        let expiry = if kind == KIND_SET_EXPIRING { EXPIRY_LEN } else { 0 };
+        let value_offset = record_offset + HEADER_LEN + key.len() as u64 + expiry;
+        let hash = super::hash_key(&key);
+        let bucket = self.index.entry(hash).or_default();
+        let previous = bucket.iter().position(|(k, _)| *k == key);
+
+        if let Some(i) = previous {
+            self.dead_bytes += bucket[i].1.record_len();
+        }
+        if kind == KIND_DELETE {
+            // Tombstones are never needed once replayed
+            self.dead_bytes += value_offset - record_offset;
+            if previous.is_some() {
+                self.forget(hash, &key);
+            }
+        } else {
+            let location = ValueRef { record_offset, value_offset, value_len, expires_at };
+            if let Some(i) = previous {
+                bucket[i].1 = location;
+            } else {
+                bucket.push((key, location));
+                self.keys += 1;
+            }
+        }
+    }
+
+    /// Drop `key` from its index bucket (its record must already be counted dead)
+    fn forget(&mut self, hash: u64, key: &str) {
+        let Some(bucket) = self.index.get_mut(&hash) else {
+            return;
+        };
+        if let Some(i) = bucket.iter().position(|(k, _)| k == key) {
+            bucket.swap_remove(i);
+            self.keys -= 1;
+            if bucket.is_empty() {
+                self.index.remove(&hash);
+            }
+        }
+    }
+
+    /// Remove expired keys from the index, counting their records dead
+    fn purge_expired(&mut self) -> usize {
+        let now = now_millis();
+        let expired: Vec<(u64, String, u64)> = self
+            .index
+            .iter()
+            .flat_map(|(&hash, bucket)| bucket.iter().map(move |entry| (hash, entry)))
+            .filter(|(_, (_, location))| !location.is_live(now))
+            .map(|(hash, (key, location))| (hash, key.clone(), location.record_len()))
+            .collect();
+        for (hash, key, record_len) in &expired {
+            self.dead_bytes += record_len;
+            self.forget(*hash, key);
+        }
+        expired.len()
+    }
+
+    fn read(&mut self, key: &str) -> Result<Option<Vec<u8>>> {
+        let Some(location) = self.lookup(key) else {
+            return Ok(None);
+        };
+        if !location.is_live(now_millis()) {
+            self.dead_bytes += location.record_len();
+            self.forget(super::hash_key(key), key);
+            return Ok(None);
+        }
+        self.read_value(location).map(Some)
+    }
+
+    fn read_value(&mut self, location: ValueRef) -> Result<Vec<u8>> {
+        let mut value = vec![0; location.value_len as usize];
+        self.file.seek(SeekFrom::Start(location.value_offset))?;
+        self.file.read_exact(&mut value)?;
+        Ok(value)
+    }
+
+    /// The first `limit` live pairs in key order between `start` and `end`
+    fn scan(
+        &mut self,
+        start: Bound<&str>,
+        end: Bound<&str>,
+        limit: usize,
+    ) -> Result<Vec<(String, Vec<u8>)>> {
+        let now = now_millis();
+        let mut matches: Vec<(String, ValueRef)> = self
+            .index
+            .values()
+            .flatten()
+            .filter(|(key, location)| location.is_live(now) && scan::in_range(key, start, end))
+            .map(|(key, location)| (key.clone(), *location))
+            .collect();
+        if matches.len() > limit {
+            matches.select_nth_unstable_by(limit, |(a, _), (b, _)| a.cmp(b));
+            matches.truncate(limit);
+        }
+        matches.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        matches.into_iter().map(|(key, location)| Ok((key, self.read_value(location)?))).collect()
+    }
+
+    /// Append one record per write (`None` deletes), then update the index
+    ///
+    /// Values expire at `expires_at` (Unix milliseconds) if given.
+    fn append(
+        &mut self,
+        writes: &[(String, Option<Vec<u8>>)],
+        expires_at: Option<u64>,
+    ) -> Result<()> {
+        let mut buffer = Vec::new();
+        let mut applied = Vec::with_capacity(writes.len());
+        for (key, value) in writes {
+            // Deleting a missing key is a no-op and needs no tombstone
+            if value.is_none() && self.lookup(key).is_none() {
+                continue;
+            }
+            let offset = self.log_bytes + buffer.len() as u64;
+            let (kind, value_len) = encode_record(&mut buffer, key, value.as_deref(), expires_at)?;
+            applied.push((offset, kind, key.clone(), value_len));
+        }
+        if buffer.is_empty() {
+            return Ok(());
+        }
+
+        if let Err(e) = self.write_durably(&buffer) {
+            // Drop any partial write so the next append starts on a record
+            // boundary; replay would discard it anyway.
+            let _ = self.file.set_len(self.log_bytes);
+            return Err(e);
+        }
+        self.log_bytes += buffer.len() as u64;
+        for (offset, kind, key, value_len) in applied {
+            self.apply(offset, kind, key, value_len, expires_at);
+        }
+
+        if let Some(ratio) = self.options.auto_compact_ratio {
+            #[allow(clippy::cast_precision_loss)]
+            let garbage = self.dead_bytes as f64 / self.log_bytes as f64;
+            if garbage > ratio {
+                self.compact()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_durably(&mut self, buffer: &[u8]) -> Result<()> {
+        self.file.write_all(buffer)?;
+        if self.options.sync_writes {
+            self.file.sync_data()?;
+        }
+        Ok(())
+    }
+
+    fn compact(&mut self) -> Result<()> {
+        let compact_path = self.dir.join(COMPACT_FILE);
+        let now = now_millis();
+        let mut live: Vec<(String, ValueRef)> = self
+            .index
+            .values()
+            .flatten()
+            .filter(|(_, location)| location.is_live(now))
+            .map(|(k, location)| (k.clone(), *location))
+            .collect();
+        // Preserve log order so compaction reads the old file sequentially
+        live.sort_unstable_by_key(|(_, location)| location.record_offset);
+
+        {
+            let mut writer = BufWriter::new(File::create(&compact_path)?);
+            let mut buffer = Vec::new();
+            for (key, location) in &live {
+                let mut value = vec![0; location.value_len as usize];
+                self.file.seek(SeekFrom::Start(location.value_offset))?;
+                self.file.read_exact(&mut value)?;
+                buffer.clear();
+                encode_record(&mut buffer, key, Some(&value), location.expires_at)?;
+                writer.write_all(&buffer)?;
+            }
+            writer.into_inner().map_err(|e| Error::Io(e.into_error()))?.sync_all()?;
+        }
+
+        let log_path = self.dir.join(LOG_FILE);
+        fs::rename(&compact_path, &log_path)?;
+        sync_dir(&self.dir)?;
+
+        self.file = OpenOptions::new().read(true).append(true).open(&log_path)?;
+        self.index.clear();
+        self.keys = 0;
+        self.log_bytes = 0;
+        self.dead_bytes = 0;
+        for (key, location) in live {
+            let offset = self.log_bytes;
+            self.log_bytes += location.record_len();
+            let kind = if location.expires_at.is_some() { KIND_SET_EXPIRING } else { KIND_SET };
+            self.apply(offset, kind, key, location.value_len, location.expires_at);
+        }
+        Ok(())
+    }
+}
+
+/// Serialize a record onto `buffer`, returning its kind and value length
+///
+/// A value with an `expires_at` deadline is written as [`KIND_SET_EXPIRING`].
+#[allow(clippy::cast_possible_truncation)]
+fn encode_record(
+    buffer: &mut Vec<u8>,
+    key: &str,
+    value: Option<&[u8]>,
+    expires_at: Option<u64>,
+) -> Result<(u8, u32)> {
+    let (kind, expiry) = match (value, expires_at) {
+        (None, _) => (KIND_DELETE, None),
+        (Some(_), None) => (KIND_SET, None),
+        (Some(_), Some(deadline)) => (KIND_SET_EXPIRING, Some(deadline)),
+    };
+    let value = value.unwrap_or_default();
+    let key_len = u32::try_from(key.len())
+        .map_err(|_| Error::InvalidInput(format!("KV key too long: {} bytes", key.len())))?;
+    let too_long = || Error::InvalidInput(format!("KV value too long: {} bytes", value.len()));
+    let value_len = u32::try_from(value.len()).map_err(|_| too_long())?;
+    let stored_len = if expiry.is_some() {
+        value_len.checked_add(EXPIRY_LEN as u32).ok_or_else(too_long)?
+    } else {
+        value_len
+    };
+
+    let start = buffer.len();
+    buffer.extend_from_slice(&[0; 8]);
+    buffer.push(kind);
+    buffer.extend_from_slice(&key_len.to_le_bytes());
+    buffer.extend_from_slice(&stored_len.to_le_bytes());
+    buffer.extend_from_slice(key.as_bytes());
+    if let Some(deadline) = expiry {
+        buffer.extend_from_slice(&deadline.to_le_bytes());
+    }
+    buffer.extend_from_slice(value);
+    let checksum = super::hash_bytes(&buffer[start + 8..]);
+    buffer[start..start + 8].copy_from_slice(&checksum.to_le_bytes());
+    Ok((kind, value_len))
+}
+
+/// Read the next intact record, or `None` at end of log or on a torn/corrupt
+/// record. `remaining` bounds lengths so garbage headers can't trigger huge
+/// allocations.
+#[allow(clippy::cast_possible_truncation)]
+fn read_record(reader: &mut impl Read, remaining: u64) -> Result<Option<Record>> {
+    if remaining < HEADER_LEN {
+        return Ok(None);
+    }
+    let mut header = [0; HEADER_LEN as usize];
+    reader.read_exact(&mut header)?;
+    let checksum = u64::from_le_bytes(header[0..8].try_into().expect("8-byte checksum"));
+    let kind = header[8];
+    let key_len = u32::from_le_bytes(header[9..13].try_into().expect("4-byte key length"));
+    let value_len = u32::from_le_bytes(header[13..17].try_into().expect("4-byte value length"));
+
+    let body_len = u64::from(key_len) + u64::from(value_len);
+    let known = matches!(kind, KIND_SET | KIND_DELETE)
+        || (kind == KIND_SET_EXPIRING && u64::from(value_len) >= EXPIRY_LEN);
+    if !known || body_len > remaining - HEADER_LEN {
+        return Ok(None);
+    }
+    let mut body = vec![0; usize::try_from(body_len).unwrap_or(usize::MAX)];
+    reader.read_exact(&mut body)?;
+
+    let mut hashed = Vec::with_capacity(header.len() - 8 + body.len());
+    hashed.extend_from_slice(&header[8..]);
+    hashed.extend_from_slice(&body);
+    if super::hash_bytes(&hashed) != checksum {
+        return Ok(None);
+    }
+    let (value_len, expires_at) = if kind == KIND_SET_EXPIRING {
+        let deadline = &body[key_len as usize..key_len as usize + EXPIRY_LEN as usize];
+        let deadline = u64::from_le_bytes(deadline.try_into().expect("8-byte deadline"));
+        (value_len - EXPIRY_LEN as u32, Some(deadline))
+    } else {
+        (value_len, None)
+    };
+    body.truncate(key_len as usize);
+    let Ok(key) = String::from_utf8(body) else {
+        return Ok(None);
+    };
+    Ok(Some(Record { kind, key, value_len, expires_at }))
+}
+
+/// Wall-clock time in Unix milliseconds (expiry deadlines outlive the process)
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX))
+}
+
+/// Make a rename inside `dir` durable
+fn sync_dir(dir: &Path) -> Result<()> {
+    #[cfg(unix)]
+    File::open(dir)?.sync_all()?;
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
+
+/// Run blocking file I/O off the async reactor
+async fn blocking<T, F>(op: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(op)
+        .await
+        .map_err(|e| Error::StorageError(format!("KV store task failed: {e}")))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("trueno_test_kv_{name}"));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_persistent_kv_survives_reopen() {
+        let dir = test_dir("reopen");
+        let store = PersistentKvStore::open(&dir).await.unwrap();
+        store.set("a", b"1".to_vec()).await.unwrap();
+        store.set("b", b"2".to_vec()).await.unwrap();
+        store.set("a", b"3".to_vec()).await.unwrap();
+        store.delete("b").await.unwrap();
+        store.set("empty", vec![]).await.unwrap();
+        store.close().await.unwrap();
+
+        let store = PersistentKvStore::open(&dir).await.unwrap();
+        assert_eq!(store.get("a").await.unwrap(), Some(b"3".to_vec()));
+        assert_eq!(store.get("b").await.unwrap(), None);
+        assert_eq!(store.get("empty").await.unwrap(), Some(vec![]));
+        assert!(!store.exists("b").await.unwrap());
+        assert_eq!(store.len(), 2);
+        store.close().await.unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_persistent_kv_truncates_torn_tail() {
+        let dir = test_dir("torn");
+        let store = PersistentKvStore::open(&dir).await.unwrap();
+        store.set("kept", b"value".to_vec()).await.unwrap();
+        store.set("torn", b"partial".to_vec()).await.unwrap();
+        let intact = store.stats().log_bytes;
+        store.close().await.unwrap();
+
+        // Simulate a crash halfway through the second record
+        let log = dir.join(LOG_FILE);
+        let first_record = HEADER_LEN + 4 + 5;
+        OpenOptions::new().write(true).open(&log).unwrap().set_len(first_record + 10).unwrap();
+        assert!(fs::metadata(&log).unwrap().len() < intact);
+
+        let store = PersistentKvStore::open(&dir).await.unwrap();
+        assert_eq!(store.get("kept").await.unwrap(), Some(b"value".to_vec()));
+        assert_eq!(store.get("torn").await.unwrap(), None);
+        assert_eq!(fs::metadata(&log).unwrap().len(), first_record);
+
+        // Appends after recovery land on a clean record boundary
+        store.set("after", b"ok".to_vec()).await.unwrap();
+        store.close().await.unwrap();
+        let store = PersistentKvStore::open(&dir).await.unwrap();
+        assert_eq!(store.get("after").await.unwrap(), Some(b"ok".to_vec()));
+        store.close().await.unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_persistent_kv_rejects_corrupt_record() {
+        let dir = test_dir("corrupt");
+        let store = PersistentKvStore::open(&dir).await.unwrap();
+        store.set("a", b"1".to_vec()).await.unwrap();
+        store.set("b", b"2".to_vec()).await.unwrap();
+        store.close().await.unwrap();
+
+        // Flip the last value byte so the second record fails its checksum
+        let log = dir.join(LOG_FILE);
+        let mut bytes = fs::read(&log).unwrap();
+        *bytes.last_mut().unwrap() ^= 0xFF;
+        fs::write(&log, bytes).unwrap();
+
+        let store = PersistentKvStore::open(&dir).await.unwrap();
+        assert_eq!(store.get("a").await.unwrap(), Some(b"1".to_vec()));
+        assert_eq!(store.get("b").await.unwrap(), None);
+        store.close().await.unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_persistent_kv_compaction() {
+        let dir = test_dir("compact");
+        let store = PersistentKvStore::open(&dir).await.unwrap();
+        for i in 0..50 {
+            store.set("hot", format!("v{i}").into_bytes()).await.unwrap();
+        }
+        store.set("gone", b"x".to_vec()).await.unwrap();
+        store.delete("gone").await.unwrap();
+        store.set("cold", b"c".to_vec()).await.unwrap();
+
+        let before = store.stats();
+        assert!(before.dead_bytes > 0);
+        store.compact().await.unwrap();
+        let after = store.stats();
+        assert_eq!(after.dead_bytes, 0);
+        assert_eq!(after.keys, 2);
+        assert!(after.log_bytes < before.log_bytes);
+        assert_eq!(after.log_bytes, fs::metadata(dir.join(LOG_FILE)).unwrap().len());
+        assert_eq!(store.get("hot").await.unwrap(), Some(b"v49".to_vec()));
+
+        store.set("new", b"n".to_vec()).await.unwrap();
+        store.close().await.unwrap();
+        let store = PersistentKvStore::open(&dir).await.unwrap();
+        assert_eq!(store.get("hot").await.unwrap(), Some(b"v49".to_vec()));
+        assert_eq!(store.get("cold").await.unwrap(), Some(b"c".to_vec()));
+        assert_eq!(store.get("new").await.unwrap(), Some(b"n".to_vec()));
+        assert_eq!(store.get("gone").await.unwrap(), None);
+        store.close().await.unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_persistent_kv_auto_compaction_and_batches() {
+        let dir = test_dir("auto");
+        let options = PersistentKvOptions { sync_writes: false, auto_compact_ratio: Some(0.5) };
+        let store = PersistentKvStore::open_with_options(&dir, options).await.unwrap();
+        for i in 0..20 {
+            store
+                .batch_set(vec![("a", format!("{i}").into_bytes()), ("b", b"b".to_vec())])
+                .await
+                .unwrap();
+            assert!(store.stats().dead_bytes * 2 <= store.stats().log_bytes);
+        }
+        let values = store.batch_get(&["a", "b", "c"]).await.unwrap();
+        assert_eq!(values, vec![Some(b"19".to_vec()), Some(b"b".to_vec()), None]);
+
+        // A stale compaction file from a crash is discarded on open
+        store.close().await.unwrap();
+        fs::write(dir.join(COMPACT_FILE), b"garbage").unwrap();
+        let store = PersistentKvStore::open(&dir).await.unwrap();
+        assert!(!dir.join(COMPACT_FILE).exists());
+        assert_eq!(store.get("a").await.unwrap(), Some(b"19".to_vec()));
+        store.close().await.unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_persistent_kv_ttl() {
+        let dir = test_dir("ttl");
+        let store = PersistentKvStore::open(&dir).await.unwrap();
+        store.set_with_ttl("long", b"l".to_vec(), Duration::from_secs(3600)).await.unwrap();
+        store.set_with_ttl("short", b"s".to_vec(), Duration::from_millis(200)).await.unwrap();
+        store.set("forever", b"f".to_vec()).await.unwrap();
+        assert_eq!(store.get("short").await.unwrap(), Some(b"s".to_vec()));
+
+        // Deadlines survive a restart
+        store.close().await.unwrap();
+        let store = PersistentKvStore::open(&dir).await.unwrap();
+        assert_eq!(store.get("long").await.unwrap(), Some(b"l".to_vec()));
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert!(!store.exists("short").await.unwrap());
+        assert_eq!(store.purge_expired().await.unwrap(), 1);
+        assert_eq!(store.len(), 2);
+
+        // Compaction keeps the remaining deadline
+        store.compact().await.unwrap();
+        store.close().await.unwrap();
+        let store = PersistentKvStore::open(&dir).await.unwrap();
+        assert_eq!(store.get("long").await.unwrap(), Some(b"l".to_vec()));
+        assert_eq!(store.get("short").await.unwrap(), None);
+        assert_eq!(store.get("forever").await.unwrap(), Some(b"f".to_vec()));
+        store.close().await.unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_persistent_kv_scan_prefix() {
+        let dir = test_dir("scan");
+        let store = PersistentKvStore::open(&dir).await.unwrap();
+        let keys: Vec<String> = (0..300).rev().map(|i| format!("run-001/step/{i:03}")).collect();
+        store.batch_set(keys.iter().map(|key| (key.as_str(), vec![1])).collect()).await.unwrap();
+        store.set("run-002/step/000", vec![2]).await.unwrap();
+        store.set_with_ttl("run-001/step/999", vec![3], Duration::ZERO).await.unwrap();
+        store.delete("run-001/step/000").await.unwrap();
+
+        let pairs = store.scan_prefix("run-001/").try_collect().await.unwrap();
+        assert_eq!(pairs.len(), 299);
+        assert_eq!(pairs[0].0, "run-001/step/001");
+        assert!(pairs.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+        let tail = store.range("run-001/step/298"..).try_collect().await.unwrap();
+        let keys: Vec<&str> = tail.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, vec!["run-001/step/298", "run-001/step/299", "run-002/step/000"]);
+        store.close().await.unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_persistent_kv_invalid_ratio() {
+        let options = PersistentKvOptions { sync_writes: true, auto_compact_ratio: Some(1.5) };
+        let result = PersistentKvStore::open_with_options(test_dir("ratio"), options).await;
+        assert!(matches!(result, Err(Error::InvalidInput(_))));
+    }
+}
@@ -0,0 +1,186 @@
+//! Ordered range and prefix scans over a [`KvStore`].
+//!
+//! A [`KvScan`] is an async iterator: it pulls key/value pairs from the store
+//! in pages of [`SCAN_PAGE`] via [`KvStore::scan_range`], resuming after the
+//! last key it returned. It never holds a lock between pages, so writes made
+//! during a scan may or may not be observed, but every key is yielded at most
+//! once and in ascending order.
+
+use super::KvStore;
+use crate::Result;
+use std::collections::VecDeque;
+use std::ops::Bound;
+
+/// Pairs fetched from the store per page of a scan
+pub const SCAN_PAGE: usize = 256;
+
+/// Async iterator over the live key/value pairs of a key range, in key order.
+///
+/// Created by [`KvStore::range`] and [`KvStore::scan_prefix`].
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use trueno_db::kv::{KvStore, MemoryKvStore};
+///
+/// # async fn example() -> trueno_db::Result<()> {
+/// let store = MemoryKvStore::new();
+/// store.set("run-001/metrics/loss", b"0.25".to_vec()).await?;
+/// store.set("run-001/params/lr", b"0.01".to_vec()).await?;
+///
+/// let mut metrics = store.scan_prefix("run-001/metrics/");
+/// while let Some((key, value)) = metrics.try_next().await? {
+///     println!("{key} = {value:?}");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct KvScan<'a, S: KvStore + ?Sized> {
+    store: &'a S,
+    /// Lower bound of the next page (excludes the last key yielded)
+    start: Bound<String>,
+    end: Bound<String>,
+    page: VecDeque<(String, Vec<u8>)>,
+    exhausted: bool,
+}
+
+impl<'a, S: KvStore + ?Sized> KvScan<'a, S> {
+    pub(crate) fn new(store: &'a S, start: Bound<String>, end: Bound<String>) -> Self {
+        let exhausted = is_empty_range(borrowed(&start), borrowed(&end));
+        Self { store, start, end, page: VecDeque::new(), exhausted }
+    }
+
+    /// Scan the keys starting with `prefix`
+    pub(crate) fn prefix(store: &'a S, prefix: &str) -> Self {
+        let end = prefix_end(prefix).map_or(Bound::Unbounded, Bound::Excluded);
+        Self::new(store, Bound::Included(prefix.to_string()), end)
+    }
+
+    /// Get the next pair, or `None` once the range is exhausted.
+    ///
+    /// # Errors
+    /// Returns the store's error if fetching a page fails; the scan ends there.
+    pub async fn try_next(&mut self) -> Result<Option<(String, Vec<u8>)>> {
+        if self.page.is_empty() && !self.exhausted {
+            let page = self.store.scan_range(borrowed(&self.start), borrowed(&self.end), SCAN_PAGE);
+            let page = match page.await {
+                Ok(page) => page,
+                Err(e) => {
+                    self.exhausted = true;
+                    return Err(e);
+                }
+            };
+            self.exhausted = page.len() < SCAN_PAGE;
+            if let Some((last, _)) = page.last() {
+                self.start = Bound::Excluded(last.clone());
+            }
+            self.page = page.into();
+        }
+        Ok(self.page.pop_front())
+    }
+
+    /// Collect the remaining pairs.
+    ///
+    /// # Errors
+    /// Returns the first error from the store.
+    pub async fn try_collect(mut self) -> Result<Vec<(String, Vec<u8>)>> {
+        let mut pairs = Vec::new();
+        while let Some(pair) = self.try_next().await? {
+            pairs.push(pair);
+        }
+        Ok(pairs)
+    }
+}
+
+/// Own the key of a range bound
+pub(super) fn owned(bound: Bound<&&str>) -> Bound<String> {
+    match bound {
+        Bound::Included(key) => Bound::Included((*key).to_string()),
+        Bound::Excluded(key) => Bound::Excluded((*key).to_string()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+/// Borrow the key of a range bound
+pub(super) fn borrowed(bound: &Bound<String>) -> Bound<&str> {
+    match bound {
+        Bound::Included(key) => Bound::Included(key),
+        Bound::Excluded(key) => Bound::Excluded(key),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+/// Whether `key` lies between `start` and `end`
+#[cfg_attr(not(feature = "tokio"), allow(dead_code))]
+pub(super) fn in_range(key: &str, start: Bound<&str>, end: Bound<&str>) -> bool {
+    let above = match start {
+        Bound::Included(start) => key >= start,
+        Bound::Excluded(start) => key > start,
+        Bound::Unbounded => true,
+    };
+    let below = match end {
+        Bound::Included(end) => key <= end,
+        Bound::Excluded(end) => key < end,
+        Bound::Unbounded => true,
+    };
+    above && below
+}
+
+/// Whether no key can lie between `start` and `end`
+///
+/// Ordered maps panic on such ranges, so callers check first.
+pub(super) fn is_empty_range(start: Bound<&str>, end: Bound<&str>) -> bool {
+    match (start, end) {
+        (Bound::Included(start), Bound::Included(end)) => start > end,
+        (Bound::Included(start) | Bound::Excluded(start), Bound::Excluded(end))
+        | (Bound::Excluded(start), Bound::Included(end)) => start >= end,
+        _ => false,
+    }
+}
+
+/// The smallest string above every string starting with `prefix`, or `None`
+/// if there is none (empty prefix, or one made only of `char::MAX`)
+fn prefix_end(prefix: &str) -> Option<String> {
+    let mut end: Vec<char> = prefix.chars().collect();
+    while let Some(last) = end.pop() {
+        // Skip the surrogate gap, which holds no chars
+        let next = match u32::from(last) + 1 {
+            0xD800 => Some('\u{E000}'),
+            code => char::from_u32(code),
+        };
+        if let Some(next) = next {
+            end.push(next);
+            return Some(end.into_iter().collect());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_end() {
+        assert_eq!(prefix_end("run-001/").as_deref(), Some("run-0010"));
+        assert_eq!(prefix_end("a\u{D7FF}").as_deref(), Some("a\u{E000}"));
+        assert_eq!(prefix_end("a\u{10FFFF}").as_deref(), Some("b"));
+        assert_eq!(prefix_end(""), None);
+        assert_eq!(prefix_end("\u{10FFFF}"), None);
+    }
+
+    #[test]
+    fn test_range_bounds() {
+        let (a, b) = (Bound::Included("a"), Bound::Excluded("b"));
+        assert!(in_range("a", a, b));
+        assert!(in_range("az", a, b));
+        assert!(!in_range("b", a, b));
+        assert!(in_range("zzz", Bound::Excluded("b"), Bound::Unbounded));
+
+        assert!(!is_empty_range(a, b));
+        assert!(!is_empty_range(Bound::Included("a"), Bound::Included("a")));
+        assert!(is_empty_range(Bound::Included("a"), Bound::Excluded("a")));
+        assert!(is_empty_range(Bound::Included("b"), Bound::Included("a")));
+        assert!(!is_empty_range(Bound::Unbounded, Bound::Excluded("a")));
+    }
+}
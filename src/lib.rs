@@ -18,6 +18,8 @@
 //! ```rust,no_run
 //! use trueno_db::storage::StorageEngine;
 //!
+//! # #[cfg(feature = "parquet-io")]
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
 //! // Load Parquet file
 //! let storage = StorageEngine::load_parquet("data/events.parquet")?;
 //!
@@ -25,7 +27,10 @@
 //! for morsel in storage.morsels() {
 //!     println!("Morsel: {} rows", morsel.num_rows());
 //! }
-//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "parquet-io"))]
+//! # fn main() {}
 //! ```
 
 #![warn(missing_docs)]
@@ -39,27 +44,87 @@
 #![allow(clippy::missing_errors_doc)]
 #![allow(clippy::missing_panics_doc)]
 
+pub mod admission;
 pub mod backend;
+pub mod cancel;
+pub mod catalog;
 pub mod error;
 pub mod experiment;
+#[cfg(feature = "ipc-io")]
+pub mod external_sort;
+#[cfg(all(feature = "flight", not(target_arch = "wasm32")))]
+pub mod flight;
 #[cfg(feature = "gpu")]
 pub mod gpu;
+pub mod health;
 pub mod kv;
+pub mod mcp;
+pub mod metrics;
+pub mod output;
+#[cfg(all(feature = "pgwire", not(target_arch = "wasm32")))]
+pub mod pgwire;
 pub mod query;
+pub mod reduce;
+#[cfg(feature = "parquet-io")]
+pub mod replica;
+pub mod shared;
+pub mod sketch;
 pub mod storage;
 pub mod topk;
+pub mod variance;
+#[cfg(feature = "verify")]
+pub mod verify;
 #[cfg(all(target_arch = "wasm32", feature = "wasm"))]
 pub mod wasm;
+pub mod workload;
 
+pub use cancel::CancellationToken;
+pub use catalog::Catalog;
 pub use error::{Error, Result};
+pub use shared::SharedDatabase;
+
+use std::path::Path;
 
 /// Database instance
+///
+/// Owns the table catalog, the SQL parser and executor configured by
+/// [`DatabaseBuilder`], and (with the `gpu` feature) the GPU engine.
+///
+/// # Example
+/// ```
+/// use arrow::array::{Int32Array, RecordBatch};
+/// use arrow::datatypes::{DataType, Field, Schema};
+/// use std::sync::Arc;
+/// use trueno_db::{Backend, Database};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let schema = Arc::new(Schema::new(vec![Field::new("score", DataType::Int32, false)]));
+/// let batch = RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![3, 1, 2]))])?;
+///
+/// let mut db = Database::builder().backend(Backend::Simd).morsel_size_mb(64).build()?;
+/// db.register_batch("scores", batch)?;
+/// let batches = db.query("SELECT score FROM scores WHERE score > 1")?;
+/// assert_eq!(batches.iter().map(RecordBatch::num_rows).sum::<usize>(), 2);
+/// # Ok(())
+/// # }
+/// ```
 pub struct Database {
-    _private: (),
+    catalog: Catalog,
+    engine: query::QueryEngine,
+    executor: query::QueryExecutor,
+    backend: Backend,
+    /// Size of the batches [`query`](Self::query) returns
+    morsel_size_bytes: usize,
+    #[cfg(feature = "gpu")]
+    gpu: Option<gpu::GpuEngine>,
+    admission: admission::AdmissionController,
+    /// Queries recorded since `start_capture`
+    capture: Option<workload::Workload>,
 }
 
 /// Backend selection strategy
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Backend {
     /// Cost-based dispatch (arithmetic intensity)
     CostBased,
@@ -69,30 +134,737 @@ pub enum Backend {
     Simd,
 }
 
+impl Backend {
+    /// Stable lowercase name (`cost_based`, `gpu`, `simd`)
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::CostBased => "cost_based",
+            Self::Gpu => "gpu",
+            Self::Simd => "simd",
+        }
+    }
+
+    /// Parse a name produced by [`name`](Self::name)
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "cost_based" => Some(Self::CostBased),
+            "gpu" => Some(Self::Gpu),
+            "simd" => Some(Self::Simd),
+            _ => None,
+        }
+    }
+}
+
 impl Database {
     /// Create a new database builder
     #[must_use]
     pub fn builder() -> DatabaseBuilder {
         DatabaseBuilder::default()
     }
+
+    /// Attach a data directory, registering each file or subdirectory as a table
+    ///
+    /// See [`Catalog::attach_dir`] for naming rules and supported formats.
+    ///
+    /// # Errors
+    /// Returns error if the directory cannot be scanned or a file fails to load
+    pub fn attach_dir<P: AsRef<Path>>(&mut self, path: P) -> Result<Vec<String>> {
+        self.catalog.attach_dir(path)
+    }
+
+    /// Re-scan all attached directories
+    ///
+    /// # Errors
+    /// Returns error if any attached directory fails to re-scan
+    pub fn refresh(&mut self) -> Result<Vec<String>> {
+        self.catalog.refresh()
+    }
+
+    /// Expose an in-memory batch as a temporary table for this session
+    ///
+    /// The table is queryable like any loaded table (and can be combined
+    /// with them), replaces an earlier temporary table of the same name, and
+    /// is never persisted. It lives until dropped or the database is dropped.
+    ///
+    /// # Example
+    /// ```
+    /// use arrow::array::{Int32Array, RecordBatch};
+    /// use arrow::datatypes::{DataType, Field, Schema};
+    /// use std::sync::Arc;
+    /// use trueno_db::Database;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+    /// let batch = RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![1, 2]))])?;
+    ///
+    /// let mut db = Database::builder().build()?;
+    /// db.register_batch("vip_users", batch)?;
+    /// assert!(db.catalog().is_temporary("vip_users"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns error if a persistent table already uses `name`
+    pub fn register_batch(
+        &mut self,
+        name: impl Into<String>,
+        batch: arrow::record_batch::RecordBatch,
+    ) -> Result<()> {
+        self.catalog.register_temporary(name, storage::StorageEngine::new(vec![batch]))?;
+        #[cfg(feature = "ipc-io")]
+        self.catalog.evict_cold()?;
+        Ok(())
+    }
+
+    /// Register a persistent table under `name`, returning the table it replaces
+    ///
+    /// Queries run with [`sql`](Self::sql) or [`sql_batch`](Self::sql_batch)
+    /// find it by the name in their FROM and JOIN clauses.
+    ///
+    /// # Example
+    /// ```
+    /// use arrow::array::{Int32Array, RecordBatch};
+    /// use arrow::datatypes::{DataType, Field, Schema};
+    /// use std::sync::Arc;
+    /// use trueno_db::storage::StorageEngine;
+    /// use trueno_db::Database;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+    /// let batch = RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![1, 2]))])?;
+    ///
+    /// let mut db = Database::builder().build()?;
+    /// db.register_table("events", StorageEngine::new(vec![batch]))?;
+    /// assert_eq!(db.sql("SELECT COUNT(*) FROM events")?.num_rows(), 1);
+    /// assert!(db.sql("SELECT COUNT(*) FROM missing").is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns error if colder tables cannot be evicted to stay under the
+    /// memory limit (`ipc-io` feature)
+    pub fn register_table(
+        &mut self,
+        name: impl Into<String>,
+        storage: storage::StorageEngine,
+    ) -> Result<Option<storage::StorageEngine>> {
+        let replaced = self.catalog.register(name, storage);
+        #[cfg(feature = "ipc-io")]
+        self.catalog.evict_cold()?;
+        Ok(replaced)
+    }
+
+    /// Append `batch` to the table registered as `name`
+    ///
+    /// Queries started afterwards see the new rows. To append while other
+    /// threads query, share the database with
+    /// [`into_shared`](Self::into_shared).
+    ///
+    /// # Errors
+    /// Returns error if no table is registered as `name`, the batch schema
+    /// does not match the table's, or an evicted table cannot be reloaded
+    pub fn append_batch(
+        &mut self,
+        name: &str,
+        batch: arrow::record_batch::RecordBatch,
+    ) -> Result<()> {
+        self.catalog
+            .table_mut(name)?
+            .ok_or_else(|| Error::InvalidInput(format!("Table not found: {name}")))?
+            .append_batch(batch)
+    }
+
+    /// Append several batches to the table registered as `name` as one
+    /// version: all of them or, if one is rejected, none
+    ///
+    /// # Errors
+    /// As [`append_batch`](Self::append_batch)
+    pub fn append_batches(
+        &mut self,
+        name: &str,
+        batches: Vec<arrow::record_batch::RecordBatch>,
+    ) -> Result<()> {
+        self.catalog
+            .table_mut(name)?
+            .ok_or_else(|| Error::InvalidInput(format!("Table not found: {name}")))?
+            .append_batches(batches)
+    }
+
+    /// Handle for sharing this database between threads (ingest and queries)
+    ///
+    /// See [`SharedDatabase`] for the snapshot semantics.
+    #[must_use]
+    pub fn into_shared(self) -> SharedDatabase {
+        SharedDatabase::new(self)
+    }
+
+    /// Register an experiment store's tables for SQL queries
+    ///
+    /// Registers (or replaces) the `experiments`, `runs`, `metrics`, and
+    /// `artifacts` tables described in [`experiment::tables`].
+    ///
+    /// # Example
+    /// ```
+    /// use trueno_db::experiment::{ExperimentStore, MetricRecord};
+    /// use trueno_db::Database;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut store = ExperimentStore::new();
+    /// store.add_metric(MetricRecord::new("run-001", "loss", 0, 0.9));
+    /// store.add_metric(MetricRecord::new("run-002", "loss", 0, 0.7));
+    ///
+    /// let mut db = Database::builder().build()?;
+    /// db.register_experiments(&store)?;
+    /// let sql = "SELECT run_id, MIN(value) FROM metrics WHERE key = 'loss' GROUP BY run_id";
+    /// let best = db.sql(sql)?;
+    /// assert_eq!(best.num_rows(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns error if a table cannot be assembled, or colder tables cannot
+    /// be evicted to stay under the memory limit (`ipc-io` feature)
+    pub fn register_experiments(&mut self, store: &experiment::ExperimentStore) -> Result<()> {
+        for (name, batch) in store.as_record_batches()? {
+            self.register_table(name, storage::StorageEngine::new(vec![batch]))?;
+        }
+        Ok(())
+    }
+
+    /// Run a query and return its result as morsel-sized record batches
+    ///
+    /// This is [`sql`](Self::sql) with the result split into batches of
+    /// about the configured
+    /// [`morsel_size_mb`](DatabaseBuilder::morsel_size_mb), ready to hand to
+    /// out-of-core or GPU consumers batch by batch. An empty result is one
+    /// empty batch, so the schema is always available.
+    ///
+    /// # Errors
+    /// Same as [`sql`](Self::sql)
+    pub fn query(&mut self, sql: &str) -> Result<Vec<arrow::record_batch::RecordBatch>> {
+        let result = self.sql(sql)?;
+        if result.num_rows() == 0 {
+            return Ok(vec![result]);
+        }
+        let result = storage::StorageEngine::new(vec![result]);
+        Ok(result.morsels_with_size(self.morsel_size_bytes).collect())
+    }
+
+    /// Run a query, resolving its FROM and JOIN tables by name in the catalog
+    ///
+    /// Evicted tables are reloaded first. The query holds an admission slot
+    /// while it runs.
+    ///
+    /// # Errors
+    /// Returns error if admission is refused, the query fails to parse,
+    /// reads an unknown table, or fails to execute
+    pub fn sql(&mut self, query: &str) -> Result<arrow::record_batch::RecordBatch> {
+        let _permit = self.admit()?;
+        let started = std::time::Instant::now();
+        let mut tables = Catalog::new();
+        let result = self.run_sql(query, &mut tables);
+        if let Some(workload) = &mut self.capture {
+            workload.record(query, tables.iter(), &result, started.elapsed());
+        }
+        result
+    }
+
+    /// Parse and execute `query`, leaving the tables it read in `tables`
+    fn run_sql(
+        &mut self,
+        query: &str,
+        tables: &mut Catalog,
+    ) -> Result<arrow::record_batch::RecordBatch> {
+        let plan = self.engine.parse(query)?;
+        *tables = self.resident_tables(&plan.base_tables())?;
+        self.executor.execute_catalog(&plan, tables)
+    }
+
+    /// The named tables, reloaded if evicted, in a catalog of their own
+    ///
+    /// Batches are shared, not copied, and the snapshot stays complete even
+    /// if reloading one table evicts another.
+    fn resident_tables(&mut self, names: &[&str]) -> Result<Catalog> {
+        let mut tables = Catalog::new();
+        for &name in names {
+            let storage = self
+                .catalog
+                .table(name)?
+                .ok_or_else(|| Error::InvalidInput(format!("Table not found: {name}")))?;
+            tables.register(name, storage.snapshot().into_storage());
+        }
+        Ok(tables)
+    }
+
+    /// Run a query and write its result to a Parquet file
+    ///
+    /// Single-table scans stream batch by batch into the file; other queries
+    /// (aggregations, ORDER BY, JOINs) are executed first and their result
+    /// written (see
+    /// [`QueryExecutor::execute_to_parquet`](query::QueryExecutor::execute_to_parquet)).
+    /// The export holds an admission slot while it runs and is not recorded
+    /// by workload capture.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use trueno_db::storage::{ColumnCodec, ParquetWriteOptions};
+    /// use trueno_db::Database;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut db = Database::builder().build()?;
+    /// db.attach_dir("data")?;
+    /// let options = ParquetWriteOptions::new()
+    ///     .with_default_codec(ColumnCodec::Zstd(3))
+    ///     .with_max_row_group_size(128 * 1024);
+    /// let summary = db.export_parquet(
+    ///     "SELECT user_id, amount FROM events WHERE amount > 100",
+    ///     "out/large_events.parquet",
+    ///     &options,
+    /// )?;
+    /// println!("wrote {} rows ({} bytes)", summary.rows, summary.bytes);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns error if admission is refused, the query fails to parse,
+    /// reads an unknown table, fails to execute, or the file cannot be written
+    #[cfg(feature = "parquet-io")]
+    pub fn export_parquet<P: AsRef<Path>>(
+        &mut self,
+        query: &str,
+        path: P,
+        options: &storage::ParquetWriteOptions,
+    ) -> Result<storage::ParquetWriteSummary> {
+        let _permit = self.admit()?;
+        let plan = self.engine.parse(query)?;
+        let tables = self.resident_tables(&plan.base_tables())?;
+        let executor = &self.executor;
+        if plan.base_tables().len() > 1 {
+            let result = executor.execute_catalog(&plan, &tables)?;
+            return storage::StorageEngine::new(vec![result]).write_parquet(path, options);
+        }
+        let table = plan.source_table();
+        let storage = tables
+            .get(table)
+            .ok_or_else(|| Error::InvalidInput(format!("Table not found: {table}")))?;
+        executor.execute_to_parquet(&plan, storage, path, options)
+    }
+
+    /// Look up a table, reloading it from disk if it was evicted
+    ///
+    /// Tables are only evicted with `DatabaseBuilder::memory_limit` (`ipc-io`
+    /// feature); see [`Catalog::table`].
+    ///
+    /// # Errors
+    /// Returns error if the table cannot be reloaded or others cannot be evicted
+    pub fn table(&mut self, name: &str) -> Result<Option<&storage::StorageEngine>> {
+        self.catalog.table(name)
+    }
+
+    /// Run several related queries together, sharing scans of the same table
+    ///
+    /// All queries are parsed first, so a syntax error anywhere runs nothing.
+    /// Queries are then grouped by the table they read: each table is looked
+    /// up (reloaded if evicted) and scanned once, and queries with the same
+    /// WHERE clause share its filtered rows (see
+    /// [`QueryExecutor::execute_batch`](query::QueryExecutor::execute_batch)).
+    /// Queries that read several tables (JOINs) run individually, as with
+    /// [`sql`](Self::sql). The whole batch holds one admission slot. Results are returned in
+    /// query order.
+    ///
+    /// # Example
+    /// ```
+    /// use arrow::array::{Float64Array, RecordBatch, StringArray};
+    /// use arrow::datatypes::{DataType, Field, Schema};
+    /// use std::sync::Arc;
+    /// use trueno_db::Database;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let schema = Arc::new(Schema::new(vec![
+    ///     Field::new("region", DataType::Utf8, false),
+    ///     Field::new("amount", DataType::Float64, false),
+    /// ]));
+    /// let batch = RecordBatch::try_new(
+    ///     schema,
+    ///     vec![
+    ///         Arc::new(StringArray::from(vec!["eu", "us", "eu"])),
+    ///         Arc::new(Float64Array::from(vec![1.0, 2.0, 4.0])),
+    ///     ],
+    /// )?;
+    ///
+    /// let mut db = Database::builder().build()?;
+    /// db.register_batch("sales", batch)?;
+    /// let results = db.sql_batch(&[
+    ///     "SELECT SUM(amount) FROM sales",
+    ///     "SELECT region, SUM(amount) FROM sales GROUP BY region",
+    /// ])?;
+    /// assert_eq!(results[1].num_rows(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns error if admission is refused, any query fails to parse,
+    /// reads an unknown table, or fails to execute
+    pub fn sql_batch(&mut self, queries: &[&str]) -> Result<Vec<arrow::record_batch::RecordBatch>> {
+        let _permit = self.admit()?;
+        let started = std::time::Instant::now();
+        let plans = queries.iter().map(|sql| self.engine.parse(sql)).collect::<Result<Vec<_>>>()?;
+
+        let mut names: Vec<&str> = Vec::new();
+        for plan in &plans {
+            for name in plan.base_tables() {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+        let tables = self.resident_tables(&names)?;
+
+        // Joins read several tables and run on their own; group the other
+        // query indices by source table, keeping first-seen table order
+        let executor = &self.executor;
+        let mut results = vec![None; plans.len()];
+        let mut by_table: Vec<(&str, Vec<usize>)> = Vec::new();
+        for (index, plan) in plans.iter().enumerate() {
+            if plan.base_tables().len() > 1 {
+                results[index] = Some(executor.execute_catalog(plan, &tables)?);
+                continue;
+            }
+            let table = plan.source_table();
+            match by_table.iter_mut().find(|(name, _)| *name == table) {
+                Some((_, indices)) => indices.push(index),
+                None => by_table.push((table, vec![index])),
+            }
+        }
+
+        for (table, indices) in by_table {
+            let storage = tables
+                .get(table)
+                .ok_or_else(|| Error::InvalidInput(format!("Table not found: {table}")))?;
+            let group: Vec<query::QueryPlan> =
+                indices.iter().map(|&index| plans[index].clone()).collect();
+            for (index, result) in indices.into_iter().zip(executor.execute_batch(&group, storage)?)
+            {
+                results[index] = Some(result);
+            }
+        }
+
+        let results: Vec<_> = results.into_iter().flatten().collect();
+        if let Some(workload) = &mut self.capture {
+            // The batch shares scans, so per-query time is only an average
+            let share = started.elapsed() / u32::try_from(queries.len().max(1)).unwrap_or(u32::MAX);
+            for ((sql, plan), result) in queries.iter().zip(&plans).zip(&results) {
+                let read = plan.base_tables();
+                let read_tables = tables.iter().filter(|(name, _)| read.contains(name));
+                workload.record(sql, read_tables, &Ok(result.clone()), share);
+            }
+        }
+        Ok(results)
+    }
+
+    /// Start recording executed queries for later replay
+    ///
+    /// Until [`finish_capture`](Self::finish_capture), every query run with
+    /// [`sql`](Self::sql) or [`sql_batch`](Self::sql_batch) is recorded with
+    /// fingerprints of the tables it read and of its result (see
+    /// [`workload`]). Failed `sql` queries are recorded with their error;
+    /// a failed `sql_batch` records nothing. Fingerprinting hashes every
+    /// table a query reads, so capture is meant for validation runs rather
+    /// than left on. Restarting capture discards the queries recorded so far.
+    pub fn start_capture(&mut self) {
+        self.capture = Some(workload::Workload::new());
+    }
+
+    /// Stop capture and return the recorded workload (`None` if not capturing)
+    pub fn finish_capture(&mut self) -> Option<workload::Workload> {
+        self.capture.take()
+    }
+
+    /// Attach [`QueryStats`](query::QueryStats) to query results from now on
+    ///
+    /// Same as [`DatabaseBuilder::query_stats`], for a database that is
+    /// already built (e.g. one handed to a server).
+    pub fn set_query_stats(&mut self, enabled: bool) {
+        self.executor = std::mem::take(&mut self.executor).with_query_stats(enabled);
+    }
+
+    /// Drop a temporary table registered with [`register_batch`](Self::register_batch)
+    ///
+    /// Returns `false` (and leaves the catalog unchanged) if `name` is not a
+    /// temporary table.
+    pub fn drop_temporary(&mut self, name: &str) -> bool {
+        if !self.catalog.is_temporary(name) {
+            return false;
+        }
+        self.catalog.deregister(name).is_some()
+    }
+
+    /// Run the startup self-test suite and report the results
+    ///
+    /// Checks SIMD kernels, the SQL engine, a Parquet roundtrip and (with the
+    /// `gpu` feature) a GPU reduction against scalar references; see
+    /// [`health`]. Call it before serving queries to catch broken drivers or
+    /// a misconfigured environment early.
+    ///
+    /// # Example
+    /// ```
+    /// use trueno_db::Database;
+    ///
+    /// let db = Database::builder().build().unwrap();
+    /// let report = db.self_test();
+    /// assert!(report.is_healthy(), "{report}");
+    /// ```
+    #[must_use]
+    pub fn self_test(&self) -> health::HealthReport {
+        health::self_test()
+    }
+
+    /// Reserve a query slot, queueing if all slots are busy
+    ///
+    /// Hold the permit while the query runs; dropping it admits the next
+    /// queued query. Limits are set on the builder
+    /// ([`max_concurrent_queries`](DatabaseBuilder::max_concurrent_queries) etc.).
+    ///
+    /// # Errors
+    /// Returns [`Error::Overloaded`] if the queue is full or the wait times out
+    pub fn admit(&self) -> Result<admission::AdmissionPermit> {
+        self.admission.acquire()
+    }
+
+    /// Admission controller shared by all queries (clone it to hand to worker threads)
+    #[must_use]
+    pub const fn admission(&self) -> &admission::AdmissionController {
+        &self.admission
+    }
+
+    /// Current admission counters (in flight, queued, shed)
+    #[must_use]
+    pub fn admission_metrics(&self) -> admission::AdmissionMetrics {
+        self.admission.metrics()
+    }
+
+    /// Snapshot of the process-wide counters (queries, rows, GPU dispatches,
+    /// `PCIe` bytes, fallbacks, KV operations); see [`metrics`]
+    #[must_use]
+    pub fn metrics(&self) -> metrics::MetricsSnapshot {
+        metrics::Metrics::global().snapshot()
+    }
+
+    /// Backend selection strategy the executor was configured with
+    ///
+    /// Operators currently execute on the SIMD path whatever this is; it
+    /// drives backend estimates such as
+    /// [`QueryExecutor::join_backend`](query::QueryExecutor::join_backend).
+    #[must_use]
+    pub const fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    /// Bytes per batch returned by [`query`](Self::query)
+    #[must_use]
+    pub const fn morsel_size_bytes(&self) -> usize {
+        self.morsel_size_bytes
+    }
+
+    /// Worker threads each query runs on (see [`DatabaseBuilder::parallelism`])
+    #[must_use]
+    pub const fn parallelism(&self) -> usize {
+        self.executor.parallelism()
+    }
+
+    /// Plan cache counters, if [`DatabaseBuilder::plan_cache`] enabled one
+    #[must_use]
+    pub fn plan_cache_stats(&self) -> Option<query::PlanCacheStats> {
+        self.engine.plan_cache_stats()
+    }
+
+    /// Query executor shared by all queries (backend and parallelism set by the builder)
+    #[must_use]
+    pub const fn executor(&self) -> &query::QueryExecutor {
+        &self.executor
+    }
+
+    /// GPU engine, if the backend uses one and a GPU was found (`gpu` feature)
+    ///
+    /// [`Backend::Gpu`] requires a GPU at build time; [`Backend::CostBased`]
+    /// connects to one if available; [`Backend::Simd`] never does.
+    #[cfg(feature = "gpu")]
+    #[must_use]
+    pub const fn gpu(&self) -> Option<&gpu::GpuEngine> {
+        self.gpu.as_ref()
+    }
+
+    /// Table catalog
+    #[must_use]
+    pub const fn catalog(&self) -> &Catalog {
+        &self.catalog
+    }
+
+    /// Mutable table catalog (register or drop tables manually)
+    pub fn catalog_mut(&mut self) -> &mut Catalog {
+        &mut self.catalog
+    }
 }
 
 /// Database builder
 #[derive(Default)]
 pub struct DatabaseBuilder {
-    _private: (),
+    backend: Option<Backend>,
+    dispatch: backend::DispatchPolicy,
+    morsel_size_mb: Option<usize>,
+    parallelism: Option<usize>,
+    limits: query::QueryLimits,
+    query_stats: bool,
+    admission: admission::AdmissionConfig,
+    plan_cache: Option<usize>,
+    #[cfg(feature = "ipc-io")]
+    eviction: Option<storage::EvictionConfig>,
 }
 
 impl DatabaseBuilder {
-    /// Set backend selection strategy
+    /// Set backend selection strategy (default [`Backend::CostBased`])
+    ///
+    /// [`Backend::Gpu`] makes [`build`](Self::build) fail if no GPU engine
+    /// can be created (always, without the `gpu` feature).
+    #[must_use]
+    pub const fn backend(mut self, backend: Backend) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Tune cost-based dispatch for the hardware (thresholds, bandwidth,
+    /// throughput), overriding the built-in or calibrated values
+    ///
+    /// The policy's `force_backend` applies when [`backend`](Self::backend)
+    /// is not set.
+    ///
+    /// # Example
+    /// ```
+    /// use trueno_db::backend::DispatchPolicy;
+    /// use trueno_db::{Backend, Database};
+    ///
+    /// let policy = DispatchPolicy { min_gpu_bytes: Some(1_000_000), ..DispatchPolicy::new() };
+    /// let db = Database::builder().backend(Backend::Simd).dispatch_policy(policy).build().unwrap();
+    /// assert_eq!(db.executor().dispatch_policy().min_gpu_bytes, Some(1_000_000));
+    /// ```
+    #[must_use]
+    pub const fn dispatch_policy(mut self, policy: backend::DispatchPolicy) -> Self {
+        self.dispatch = policy;
+        self
+    }
+
+    /// Set morsel size for out-of-core execution (Poka-Yoke, default 128 MB)
+    #[must_use]
+    pub const fn morsel_size_mb(mut self, size: usize) -> Self {
+        self.morsel_size_mb = Some(size);
+        self
+    }
+
+    /// Worker threads per query for morsel-driven execution (default: one
+    /// per core, `1` runs queries serially)
+    ///
+    /// See [`QueryExecutor::with_parallelism`](query::QueryExecutor::with_parallelism).
     #[must_use]
-    pub const fn backend(self, _backend: Backend) -> Self {
+    pub const fn parallelism(mut self, threads: usize) -> Self {
+        self.parallelism = Some(threads);
         self
     }
 
-    /// Set morsel size for out-of-core execution (Poka-Yoke)
+    /// Bound every query's execution time, result rows and intermediate
+    /// memory (default unlimited)
+    ///
+    /// See [`QueryExecutor::with_limits`](query::QueryExecutor::with_limits).
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// use trueno_db::query::QueryLimits;
+    /// use trueno_db::{Backend, Database};
+    ///
+    /// let limits = QueryLimits::new().with_max_execution_time(Duration::from_secs(30));
+    /// let db = Database::builder().backend(Backend::Simd).query_limits(limits).build().unwrap();
+    /// assert_eq!(db.executor().limits().max_execution_time, Some(Duration::from_secs(30)));
+    /// ```
     #[must_use]
-    pub const fn morsel_size_mb(self, _size: usize) -> Self {
+    pub const fn query_limits(mut self, limits: query::QueryLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Attach [`QueryStats`](query::QueryStats) to every query result as
+    /// schema metadata (default off), e.g. for a Flight server whose clients
+    /// display them
+    ///
+    /// See [`QueryExecutor::with_query_stats`](query::QueryExecutor::with_query_stats).
+    #[must_use]
+    pub const fn query_stats(mut self, enabled: bool) -> Self {
+        self.query_stats = enabled;
+        self
+    }
+
+    /// Maximum queries executing at once (default 8, minimum 1)
+    #[must_use]
+    pub const fn max_concurrent_queries(mut self, max: usize) -> Self {
+        self.admission.max_concurrent = max;
+        self
+    }
+
+    /// Maximum queries waiting for a slot before new ones are rejected (default 64)
+    #[must_use]
+    pub const fn max_queued_queries(mut self, max: usize) -> Self {
+        self.admission.max_queued = max;
+        self
+    }
+
+    /// Maximum time a queued query waits for a slot (default 30s)
+    #[must_use]
+    pub const fn queue_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.admission.queue_timeout = timeout;
+        self
+    }
+
+    /// Cache the parsed plans of the last `capacity` distinct statements
+    /// (default off), so dashboards re-running the same queries skip
+    /// parsing; see [`query::plan_cache`]
+    ///
+    /// # Example
+    /// ```
+    /// use trueno_db::{Backend, Database};
+    ///
+    /// let db = Database::builder().backend(Backend::Simd).plan_cache(128).build().unwrap();
+    /// assert_eq!(db.plan_cache_stats().unwrap().capacity, 128);
+    /// ```
+    #[must_use]
+    pub const fn plan_cache(mut self, capacity: usize) -> Self {
+        self.plan_cache = Some(capacity);
+        self
+    }
+
+    /// Evict least recently used tables to disk when resident tables exceed
+    /// the configured limit; evicted tables reload on [`Database::table`]
+    ///
+    /// # Example
+    /// ```
+    /// use trueno_db::storage::EvictionConfig;
+    /// use trueno_db::Database;
+    ///
+    /// let db = Database::builder()
+    ///     .memory_limit(EvictionConfig::new(512 * 1024 * 1024))
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(db.catalog().eviction_metrics().is_some());
+    /// ```
+    #[cfg(feature = "ipc-io")]
+    #[must_use]
+    pub fn memory_limit(mut self, config: storage::EvictionConfig) -> Self {
+        self.eviction = Some(config);
         self
     }
 
@@ -100,8 +872,71 @@ impl DatabaseBuilder {
     ///
     /// # Errors
     ///
-    /// Returns error if GPU initialization fails
-    pub const fn build(self) -> Result<Database> {
-        Ok(Database { _private: () })
+    /// Returns error if the morsel size or parallelism is zero, or the
+    /// backend is [`Backend::Gpu`] and GPU initialization fails
+    pub fn build(self) -> Result<Database> {
+        let backend = self.backend.or(self.dispatch.force_backend).unwrap_or(Backend::CostBased);
+        let morsel_size_bytes = match self.morsel_size_mb {
+            Some(0) => {
+                return Err(Error::InvalidInput("Morsel size must be at least 1 MB".to_string()))
+            }
+            Some(mb) => mb.saturating_mul(1024 * 1024),
+            None => storage::MORSEL_SIZE_BYTES,
+        };
+        let parallelism = match self.parallelism {
+            Some(0) => {
+                return Err(Error::InvalidInput("Parallelism must be at least 1".to_string()))
+            }
+            Some(threads) => threads,
+            None => query::parallel::default_parallelism(),
+        };
+        #[cfg(feature = "gpu")]
+        let gpu = connect_gpu(backend)?;
+        #[cfg(not(feature = "gpu"))]
+        if backend == Backend::Gpu {
+            return Err(Error::GpuInitFailed("built without the gpu feature".to_string()));
+        }
+
+        let catalog = Catalog::new();
+        #[cfg(feature = "ipc-io")]
+        let catalog = match self.eviction {
+            Some(config) => catalog.with_memory_limit(config),
+            None => catalog,
+        };
+
+        Ok(Database {
+            catalog,
+            engine: self.plan_cache.map_or_else(query::QueryEngine::new, |capacity| {
+                query::QueryEngine::new().with_plan_cache(capacity)
+            }),
+            executor: query::QueryExecutor::with_backend(backend)
+                .with_dispatch_policy(self.dispatch)
+                .with_parallelism(parallelism)
+                .with_limits(self.limits)
+                .with_query_stats(self.query_stats),
+            backend,
+            morsel_size_bytes,
+            #[cfg(feature = "gpu")]
+            gpu,
+            admission: admission::AdmissionController::new(self.admission),
+            capture: None,
+        })
+    }
+}
+
+/// GPU engine for `backend`: required for [`Backend::Gpu`], best effort for
+/// [`Backend::CostBased`], never for [`Backend::Simd`]
+#[cfg(feature = "gpu")]
+fn connect_gpu(backend: Backend) -> Result<Option<gpu::GpuEngine>> {
+    match backend {
+        Backend::Simd => Ok(None),
+        Backend::Gpu => gpu::block_on(gpu::GpuEngine::new()).map(Some),
+        Backend::CostBased => match gpu::block_on(gpu::GpuEngine::new()) {
+            Ok(engine) => Ok(Some(engine)),
+            Err(e) => {
+                tracing::debug!("No GPU for cost-based dispatch, using SIMD only: {e}");
+                Ok(None)
+            }
+        },
     }
 }
@@ -1,32 +1,106 @@
 //! trueno-db server binary.
 //!
-//! Analytics database server with HTTP API for SQL queries.
+//! Analytics database server with HTTP API for SQL queries. With `-c` it
+//! instead runs one query over a table read from stdin (CSV or an Arrow IPC
+//! stream) and writes the result to stdout, so it composes in pipelines. With
+//! `--replay` it re-runs a captured workload file against a data directory and
+//! exits non-zero if any query regressed. With `--mcp` it serves the data
+//! directory to an agent as Model Context Protocol tools over stdio.
 //!
 //! Usage:
 //!   trueno-db --config /path/to/config.yaml
+//!   cat data.csv | trueno-db -c "SELECT region, SUM(amount) FROM stdin GROUP BY region"
+//!   trueno-db --replay workload.json --data /path/to/tables
+//!   trueno-db --mcp --data /path/to/tables
 //!   trueno-db --version
 
 use axum::extract::State;
-use axum::http::StatusCode;
+use axum::http::{header, StatusCode};
 use axum::routing::{get, post};
 use axum::Router;
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use serde::{Deserialize, Serialize};
+use std::io::Read;
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 use tokio::net::TcpListener;
 use tracing::{error, info};
-use trueno_db::query::{QueryEngine, QueryExecutor};
+use trueno_db::admission::{AdmissionConfig, AdmissionController};
+use trueno_db::output::{json_value, OutputOptions};
+use trueno_db::query::{QueryEngine, QueryExecutor, QueryStats};
 use trueno_db::storage::StorageEngine;
+use trueno_db::workload::{ReplayOptions, Workload};
+use trueno_db::Database;
 
 /// trueno-db: GPU-first embedded analytics database server.
 #[derive(Parser)]
 #[command(name = "trueno-db", version, about)]
 struct Cli {
     /// Path to YAML configuration file.
-    #[arg(long)]
-    config: PathBuf,
+    #[arg(long, required_unless_present_any = ["command", "replay", "mcp"])]
+    config: Option<PathBuf>,
+
+    /// Run one SQL query over stdin, print the result, and exit.
+    #[arg(short = 'c', long, conflicts_with = "config")]
+    command: Option<String>,
+
+    /// Table name stdin is registered under.
+    #[arg(long, default_value = "stdin", requires = "command")]
+    table: String,
+
+    /// Format of stdin.
+    #[arg(long, value_enum, default_value_t = InputFormat::Auto, requires = "command")]
+    input_format: InputFormat,
+
+    /// Format of the result written to stdout.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Csv, requires = "command")]
+    output_format: OutputFormat,
+
+    /// Replay a captured workload file, report regressions, and exit.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["config", "command"])]
+    replay: Option<PathBuf>,
+
+    /// Data directory attached (one table per file) before replaying or
+    /// serving MCP.
+    #[arg(long, value_name = "DIR")]
+    data: Option<PathBuf>,
+
+    /// Serve MCP tools to an agent over stdin/stdout.
+    #[arg(long, conflicts_with_all = ["config", "command", "replay"])]
+    mcp: bool,
+}
+
+/// Format of a table piped in on stdin.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum InputFormat {
+    /// Arrow IPC stream if it starts with the stream marker, else CSV.
+    Auto,
+    /// CSV with a header row (schema is inferred).
+    Csv,
+    /// Arrow IPC stream.
+    Arrow,
+}
+
+impl InputFormat {
+    /// Resolve `Auto` by sniffing the first bytes of `input`.
+    fn detect(self, input: &[u8]) -> Self {
+        // IPC stream messages open with the 0xFFFFFFFF continuation marker
+        match self {
+            Self::Auto if input.starts_with(&[0xFF; 4]) => Self::Arrow,
+            Self::Auto => Self::Csv,
+            format => format,
+        }
+    }
+}
+
+/// Format of a query result written to stdout.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// CSV with a header row.
+    Csv,
+    /// Arrow IPC stream (pipe into another `trueno-db -c`).
+    Arrow,
 }
 
 /// Server configuration loaded from YAML.
@@ -48,6 +122,18 @@ struct ServerConfig {
     #[serde(default = "default_max_connections")]
     max_connections: u32,
 
+    /// Maximum queries executing at once
+    #[serde(default = "default_max_concurrent_queries")]
+    max_concurrent_queries: usize,
+
+    /// Maximum queries waiting for a slot before new ones get 503
+    #[serde(default = "default_max_queued_queries")]
+    max_queued_queries: usize,
+
+    /// Maximum time a query waits for a slot, in milliseconds
+    #[serde(default = "default_queue_timeout_ms")]
+    queue_timeout_ms: u64,
+
     /// Enable write-ahead logging
     #[serde(default = "default_true")]
     wal_enabled: bool,
@@ -59,6 +145,11 @@ struct ServerConfig {
     /// Compaction interval in seconds (0 = disabled)
     #[serde(default)]
     compaction_interval_secs: u64,
+
+    /// PostgreSQL wire protocol listen address (`pgwire` feature), serving
+    /// the files in `data_dir` as tables
+    #[serde(default)]
+    pgwire_listen: Option<String>,
 }
 
 fn default_data_dir() -> String {
@@ -70,6 +161,15 @@ fn default_max_memory() -> u64 {
 fn default_max_connections() -> u32 {
     128
 }
+fn default_max_concurrent_queries() -> usize {
+    trueno_db::admission::DEFAULT_MAX_CONCURRENT_QUERIES
+}
+fn default_max_queued_queries() -> usize {
+    trueno_db::admission::DEFAULT_MAX_QUEUED_QUERIES
+}
+fn default_queue_timeout_ms() -> u64 {
+    5_000
+}
 fn default_true() -> bool {
     true
 }
@@ -82,6 +182,7 @@ struct AppState {
     storage: RwLock<StorageEngine>,
     query_engine: QueryEngine,
     executor: QueryExecutor,
+    admission: AdmissionController,
     config: ServerConfig,
 }
 
@@ -97,6 +198,7 @@ struct QueryResponse {
     columns: Vec<String>,
     rows: Vec<Vec<serde_json::Value>>,
     row_count: usize,
+    stats: Option<QueryStats>,
 }
 
 /// Error response.
@@ -107,16 +209,35 @@ struct ErrorResponse {
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
+    // Logs go to stderr so stdout stays clean for pipelines
     tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()),
-        )
+        .with_env_filter(tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(
+            |_| if cli.command.is_some() || cli.mcp { "warn" } else { "info" }.into(),
+        ))
+        .with_writer(std::io::stderr)
         .init();
 
-    let cli = Cli::parse();
+    if let Some(sql) = &cli.command {
+        return run_pipeline(&cli, sql);
+    }
+    if let Some(workload) = &cli.replay {
+        return run_replay(workload, cli.data.as_deref());
+    }
+    if cli.mcp {
+        let mut db = Database::builder().build()?;
+        if let Some(dir) = &cli.data {
+            db.attach_dir(dir)?;
+        }
+        return Ok(trueno_db::mcp::McpServer::new(db).serve_stdio().await?);
+    }
+    let Some(config_path) = &cli.config else {
+        anyhow::bail!("one of --config, -c, --replay or --mcp is required");
+    };
 
-    let config_str = std::fs::read_to_string(&cli.config)
-        .map_err(|e| anyhow::anyhow!("cannot read config {}: {}", cli.config.display(), e))?;
+    let config_str = std::fs::read_to_string(config_path)
+        .map_err(|e| anyhow::anyhow!("cannot read config {}: {}", config_path.display(), e))?;
     let config: ServerConfig = serde_yaml_ng::from_str(&config_str)
         .map_err(|e| anyhow::anyhow!("invalid config: {}", e))?;
 
@@ -127,23 +248,49 @@ async fn main() -> anyhow::Result<()> {
         "starting trueno-db server"
     );
 
+    let report = trueno_db::health::self_test();
+    for check in report.failures() {
+        error!(check = check.name, status = ?check.status, "self-test failed");
+    }
+
     // Create data directory if it doesn't exist
     std::fs::create_dir_all(&config.data_dir)?;
 
     // Load any existing Parquet files from data_dir
     let storage = load_data_dir(&config.data_dir)?;
 
+    let admission = AdmissionController::new(AdmissionConfig {
+        max_concurrent: config.max_concurrent_queries,
+        max_queued: config.max_queued_queries,
+        queue_timeout: std::time::Duration::from_millis(config.queue_timeout_ms),
+    });
+
     let state = Arc::new(AppState {
         storage: RwLock::new(storage),
         query_engine: QueryEngine::new(),
-        executor: QueryExecutor::new(),
+        executor: QueryExecutor::new().with_query_stats(true),
+        admission,
         config,
     });
 
+    #[cfg(feature = "pgwire")]
+    if let Some(listen) = &state.config.pgwire_listen {
+        let mut db = Database::builder().build()?;
+        db.attach_dir(&state.config.data_dir)?;
+        let listener = TcpListener::bind(listen).await?;
+        info!(addr = %listen, "pgwire listening");
+        tokio::spawn(async move {
+            if let Err(e) = trueno_db::pgwire::PgServer::new(db).serve(listener).await {
+                error!(error = %e, "pgwire server stopped");
+            }
+        });
+    }
+
     let app = Router::new()
         .route("/health", get(health))
         .route("/query", post(query))
         .route("/status", get(status))
+        .route("/metrics", get(metrics))
         .with_state(state.clone());
 
     let addr: SocketAddr =
@@ -160,6 +307,55 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Run `sql` over the table on stdin and write the result to stdout.
+fn run_pipeline(cli: &Cli, sql: &str) -> anyhow::Result<()> {
+    let mut input = Vec::new();
+    std::io::stdin().lock().read_to_end(&mut input)?;
+
+    let storage = match cli.input_format.detect(&input) {
+        InputFormat::Arrow => StorageEngine::read_ipc_stream(input.as_slice()),
+        InputFormat::Csv | InputFormat::Auto => StorageEngine::read_csv(input.as_slice()),
+    }
+    .map_err(|e| anyhow::anyhow!("cannot read stdin: {e}"))?;
+
+    let mut db = Database::builder().build()?;
+    db.catalog_mut().register_temporary(cli.table.as_str(), storage)?;
+    let result = db.sql(sql)?;
+
+    let stdout = std::io::stdout().lock();
+    match cli.output_format {
+        OutputFormat::Csv => arrow::csv::Writer::new(stdout).write(&result)?,
+        OutputFormat::Arrow => {
+            let mut writer = arrow::ipc::writer::StreamWriter::try_new(stdout, &result.schema())?;
+            writer.write(&result)?;
+            writer.finish()?;
+        }
+    }
+    Ok(())
+}
+
+/// Replay a captured workload against `data` and print the report.
+fn run_replay(path: &std::path::Path, data: Option<&std::path::Path>) -> anyhow::Result<()> {
+    let workload = Workload::load(path)?;
+    let mut db = Database::builder().build()?;
+    if let Some(dir) = data {
+        db.attach_dir(dir)?;
+    }
+    info!(
+        queries = workload.len(),
+        captured_by = %workload.crate_version,
+        "replaying workload"
+    );
+
+    let report = workload.replay(&mut db, &ReplayOptions::default());
+    println!("{report}");
+    let regressions = report.regressions().count();
+    if regressions > 0 {
+        anyhow::bail!("{regressions} of {} replayed queries regressed", report.results.len());
+    }
+    Ok(())
+}
+
 /// Load all Parquet files from a directory into a single StorageEngine.
 fn load_data_dir(dir: &str) -> anyhow::Result<StorageEngine> {
     let path = std::path::Path::new(dir);
@@ -203,9 +399,18 @@ async fn status(State(state): State<Arc<AppState>>) -> axum::Json<serde_json::Va
         "data_dir": state.config.data_dir,
         "max_memory_mb": state.config.max_memory_mb,
         "row_count": row_count,
+        "admission": state.admission.metrics(),
     }))
 }
 
+/// GET /metrics — process counters in the Prometheus text format.
+async fn metrics() -> impl axum::response::IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        trueno_db::metrics::Metrics::global().snapshot().to_prometheus(),
+    )
+}
+
 /// POST /query — execute SQL query.
 async fn query(
     State(state): State<Arc<AppState>>,
@@ -215,6 +420,20 @@ async fn query(
         (StatusCode::BAD_REQUEST, axum::Json(ErrorResponse { error: format!("parse error: {e}") }))
     })?;
 
+    // Wait for a query slot off the async runtime; shed with 503 when saturated
+    let admission = state.admission.clone();
+    let _permit = tokio::task::spawn_blocking(move || admission.acquire())
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                axum::Json(ErrorResponse { error: format!("admission task: {e}") }),
+            )
+        })?
+        .map_err(|e| {
+            (StatusCode::SERVICE_UNAVAILABLE, axum::Json(ErrorResponse { error: e.to_string() }))
+        })?;
+
     let storage = state.storage.read().map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -232,58 +451,26 @@ async fn query(
     // Convert RecordBatch to JSON rows
     let columns: Vec<String> = result.schema().fields().iter().map(|f| f.name().clone()).collect();
 
-    let mut rows = Vec::with_capacity(result.num_rows());
-    for row_idx in 0..result.num_rows() {
-        let mut row = Vec::with_capacity(columns.len());
-        for col_idx in 0..result.num_columns() {
-            let col = result.column(col_idx);
-            let value = arrow_value_to_json(col, row_idx);
-            row.push(value);
-        }
-        rows.push(row);
-    }
+    let options = OutputOptions::default();
+    let rows = (0..result.num_rows())
+        .map(|row| {
+            result
+                .columns()
+                .iter()
+                .map(|column| json_value(column.as_ref(), row, &options))
+                .collect()
+        })
+        .collect::<trueno_db::Result<Vec<Vec<_>>>>()
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                axum::Json(ErrorResponse { error: format!("result encoding: {e}") }),
+            )
+        })?;
 
     let row_count = rows.len();
-    Ok(axum::Json(QueryResponse { columns, rows, row_count }))
-}
-
-/// Convert an Arrow array value at a given index to a JSON value.
-fn arrow_value_to_json(array: &dyn arrow::array::Array, index: usize) -> serde_json::Value {
-    #[allow(clippy::wildcard_imports)]
-    use arrow::array::*;
-    use arrow::datatypes::DataType;
-
-    if array.is_null(index) {
-        return serde_json::Value::Null;
-    }
-
-    match array.data_type() {
-        DataType::Int32 => {
-            let a = array.as_any().downcast_ref::<Int32Array>().unwrap();
-            serde_json::Value::Number(a.value(index).into())
-        }
-        DataType::Int64 => {
-            let a = array.as_any().downcast_ref::<Int64Array>().unwrap();
-            serde_json::Value::Number(a.value(index).into())
-        }
-        DataType::Float32 => {
-            let a = array.as_any().downcast_ref::<Float32Array>().unwrap();
-            serde_json::json!(a.value(index))
-        }
-        DataType::Float64 => {
-            let a = array.as_any().downcast_ref::<Float64Array>().unwrap();
-            serde_json::json!(a.value(index))
-        }
-        DataType::Utf8 => {
-            let a = array.as_any().downcast_ref::<StringArray>().unwrap();
-            serde_json::Value::String(a.value(index).to_string())
-        }
-        DataType::Boolean => {
-            let a = array.as_any().downcast_ref::<BooleanArray>().unwrap();
-            serde_json::Value::Bool(a.value(index))
-        }
-        _ => serde_json::Value::String(format!("<unsupported: {:?}>", array.data_type())),
-    }
+    let query_stats = QueryStats::from_batch(&result);
+    Ok(axum::Json(QueryResponse { columns, rows, row_count, stats: query_stats }))
 }
 
 /// Wait for SIGTERM or Ctrl+C for graceful shutdown.
@@ -0,0 +1,461 @@
+//! Model Context Protocol (MCP) tools for agents
+//!
+//! Agents (pmat, pforge, any MCP client) use the database through tools with
+//! JSON Schema inputs instead of bespoke glue:
+//!
+//! | Tool | Does |
+//! |------|------|
+//! | `list_tables` | names of registered tables |
+//! | `register_table` | load a Parquet/CSV/Arrow file as a table |
+//! | `run_query` | run SQL, rows back as JSON |
+//! | `top_k` | the `k` rows with the largest (or smallest) values of a column |
+//! | `kv_get` / `kv_set` | read and write the key-value store |
+//! | `log_metric` | record an experiment metric, queryable as the `metrics` table |
+//!
+//! [`McpServer::handle`] answers one JSON-RPC 2.0 message (`initialize`,
+//! `ping`, `tools/list`, `tools/call`); [`McpServer::serve_stdio`] runs the
+//! stdio transport, one message per line. A failing tool returns a result
+//! with `isError: true` so the agent sees the message, as MCP specifies.
+//!
+//! # Example
+//! ```
+//! use trueno_db::mcp::McpServer;
+//! use trueno_db::Database;
+//!
+//! # #[cfg(feature = "tokio")]
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let server = McpServer::new(Database::builder().build()?);
+//! let request = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call",
+//!     "params":{"name":"kv_set","arguments":{"key":"run","value":"42"}}}"#;
+//! let response = server.handle(request).await.unwrap();
+//! assert!(response.contains(r#""isError":false"#));
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "tokio"))]
+//! # fn main() {}
+//! ```
+//!
+//! References:
+//! - Anthropic (2024): Model Context Protocol specification, revision
+//!   2024-11-05
+//!
+//! Toyota Way: Jidoka (agents get structured errors, not broken glue)
+
+use crate::experiment::{ExperimentStore, MetricRecord};
+use crate::kv::{KvStore, MemoryKvStore};
+use crate::output::{self, OutputOptions};
+use crate::{catalog, Error, Result, SharedDatabase};
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+/// MCP protocol revision implemented
+pub const PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// Rows returned by `run_query` and `top_k` unless `max_rows` says otherwise
+pub const DEFAULT_MAX_ROWS: usize = 1000;
+
+/// MCP server exposing a database, a key-value store and an experiment log
+pub struct McpServer<K = MemoryKvStore> {
+    db: SharedDatabase,
+    kv: Arc<K>,
+    experiments: Mutex<ExperimentStore>,
+}
+
+impl McpServer {
+    /// Serve `db` with an in-memory key-value store
+    #[must_use]
+    pub fn new(db: impl Into<SharedDatabase>) -> Self {
+        Self {
+            db: db.into(),
+            kv: Arc::new(MemoryKvStore::new()),
+            experiments: Mutex::new(ExperimentStore::new()),
+        }
+    }
+}
+
+impl<K: KvStore> McpServer<K> {
+    /// Use `kv` (e.g. a persistent store) for `kv_get` and `kv_set`
+    #[must_use]
+    pub fn with_kv<T: KvStore>(self, kv: Arc<T>) -> McpServer<T> {
+        McpServer { db: self.db, kv, experiments: self.experiments }
+    }
+
+    /// Database the tools run against
+    #[must_use]
+    pub const fn database(&self) -> &SharedDatabase {
+        &self.db
+    }
+
+    /// Answer one JSON-RPC message; `None` for notifications
+    pub async fn handle(&self, message: &str) -> Option<String> {
+        let request: Value = match serde_json::from_str(message) {
+            Ok(request) => request,
+            Err(e) => return Some(rpc_error(&Value::Null, -32700, &format!("Parse error: {e}"))),
+        };
+        let id = request.get("id")?.clone();
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+        let result = match request.get("method").and_then(Value::as_str) {
+            Some("initialize") => json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": {"tools": {}},
+                "serverInfo": {"name": "trueno-db", "version": env!("CARGO_PKG_VERSION")}
+            }),
+            Some("ping") => json!({}),
+            Some("tools/list") => json!({ "tools": tools() }),
+            Some("tools/call") => {
+                let Some(name) = params.get("name").and_then(Value::as_str) else {
+                    return Some(rpc_error(&id, -32602, "tools/call needs a tool name"));
+                };
+                let arguments = params.get("arguments").cloned().unwrap_or_else(|| json!({}));
+                let (text, is_error) = match self.call_tool(name, &arguments).await {
+                    Ok(value) => (value.to_string(), false),
+                    Err(e) => (e.to_string(), true),
+                };
+                json!({"content": [{"type": "text", "text": text}], "isError": is_error})
+            }
+            Some(method) => {
+                return Some(rpc_error(&id, -32601, &format!("Unknown method: {method}")))
+            }
+            None => return Some(rpc_error(&id, -32600, "Request has no method")),
+        };
+        Some(json!({"jsonrpc": "2.0", "id": id, "result": result}).to_string())
+    }
+
+    /// Run tool `name`, returning its JSON payload
+    ///
+    /// # Errors
+    /// Returns error if the tool is unknown, an argument is missing or has
+    /// the wrong type, or the operation fails
+    pub async fn call_tool(&self, name: &str, arguments: &Value) -> Result<Value> {
+        match name {
+            "list_tables" => {
+                let db = self.db.read();
+                Ok(json!(db.catalog().table_names()))
+            }
+            "register_table" => {
+                let (name, path) = (string(arguments, "name")?, string(arguments, "path")?);
+                let storage = catalog::load_file(Path::new(path))?.ok_or_else(|| {
+                    Error::InvalidInput(format!("Unsupported file format: {path}"))
+                })?;
+                let rows = storage
+                    .batches()
+                    .iter()
+                    .map(arrow::array::RecordBatch::num_rows)
+                    .sum::<usize>();
+                self.db.register_table(name, storage)?;
+                Ok(json!({"table": name, "rows": rows}))
+            }
+            "run_query" => {
+                let max_rows = match arguments.get("max_rows") {
+                    Some(_) => {
+                        usize::try_from(integer(arguments, "max_rows")?).unwrap_or(usize::MAX)
+                    }
+                    None => DEFAULT_MAX_ROWS,
+                };
+                self.query(string(arguments, "sql")?, max_rows)
+            }
+            "top_k" => {
+                let (table, column) = (string(arguments, "table")?, string(arguments, "column")?);
+                let k = integer(arguments, "k")?;
+                let order = match arguments.get("order").and_then(Value::as_str) {
+                    None | Some("desc") => "DESC",
+                    Some("asc") => "ASC",
+                    Some(other) => {
+                        return Err(Error::InvalidInput(format!(
+                            "order must be desc or asc, got {other}"
+                        )))
+                    }
+                };
+                let sql = format!(
+                    "SELECT * FROM {} ORDER BY {} {order} LIMIT {k}",
+                    identifier(table)?,
+                    identifier(column)?
+                );
+                self.query(&sql, usize::try_from(k).unwrap_or(usize::MAX))
+            }
+            "kv_get" => {
+                let value = self.kv.get(string(arguments, "key")?).await?;
+                Ok(value.map_or(Value::Null, |bytes| {
+                    Value::String(String::from_utf8_lossy(&bytes).into_owned())
+                }))
+            }
+            "kv_set" => {
+                let (key, value) = (string(arguments, "key")?, string(arguments, "value")?);
+                let value = value.as_bytes().to_vec();
+                match arguments.get("ttl_secs") {
+                    Some(_) => {
+                        let ttl = Duration::from_secs(integer(arguments, "ttl_secs")?);
+                        self.kv.set_with_ttl(key, value, ttl).await?;
+                    }
+                    None => self.kv.set(key, value).await?,
+                }
+                Ok(json!({"key": key}))
+            }
+            "log_metric" => {
+                let metric = MetricRecord::new(
+                    string(arguments, "run_id")?,
+                    string(arguments, "key")?,
+                    integer(arguments, "step")?,
+                    arguments
+                        .get("value")
+                        .and_then(Value::as_f64)
+                        .ok_or_else(|| Error::InvalidInput("value must be a number".to_string()))?,
+                );
+                let mut experiments =
+                    self.experiments.lock().unwrap_or_else(PoisonError::into_inner);
+                experiments.add_metric(metric);
+                self.db.write().register_experiments(&experiments)?;
+                Ok(json!({"metrics": experiments.metric_count()}))
+            }
+            _ => Err(Error::InvalidInput(format!("Unknown tool: {name}"))),
+        }
+    }
+
+    /// Run on stdin/stdout, one JSON-RPC message per line, until stdin ends
+    ///
+    /// # Errors
+    /// Returns error if reading stdin or writing stdout fails
+    #[cfg(feature = "tokio")]
+    pub async fn serve_stdio(&self) -> Result<()> {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        let mut stdout = tokio::io::stdout();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.handle(&line).await {
+                stdout.write_all(response.as_bytes()).await?;
+                stdout.write_all(b"\n").await?;
+                stdout.flush().await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Run `sql`, returning up to `max_rows` rows as JSON objects
+    fn query(&self, sql: &str, max_rows: usize) -> Result<Value> {
+        let result = self.db.sql(sql)?;
+        let rows = result.num_rows();
+        let shown = result.slice(0, rows.min(max_rows));
+        let json = output::to_json_rows(&shown, &OutputOptions::default())?;
+        let rows_json: Value = serde_json::from_str(&json)
+            .map_err(|e| Error::Other(format!("Failed to encode rows: {e}")))?;
+        Ok(json!({"rows": rows_json, "row_count": rows, "truncated": rows > max_rows}))
+    }
+}
+
+/// Tool definitions returned by `tools/list`
+#[must_use]
+pub fn tools() -> Value {
+    json!([
+        {
+            "name": "list_tables",
+            "description": "List the tables that can be queried",
+            "inputSchema": {"type": "object", "properties": {}}
+        },
+        {
+            "name": "register_table",
+            "description": "Load a Parquet, CSV or Arrow IPC file as a table",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": {"type": "string", "description": "Table name"},
+                    "path": {"type": "string", "description": "File path (.parquet, .csv, .arrow)"}
+                },
+                "required": ["name", "path"]
+            }
+        },
+        {
+            "name": "run_query",
+            "description": "Run a SQL query and return the rows as JSON objects",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "sql": {"type": "string", "description": "SQL SELECT statement"},
+                    "max_rows": {"type": "integer", "minimum": 0, "default": DEFAULT_MAX_ROWS}
+                },
+                "required": ["sql"]
+            }
+        },
+        {
+            "name": "top_k",
+            "description": "Return the k rows of a table with the largest (or smallest) values of a column",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "table": {"type": "string"},
+                    "column": {"type": "string"},
+                    "k": {"type": "integer", "minimum": 1},
+                    "order": {"type": "string", "enum": ["desc", "asc"], "default": "desc"}
+                },
+                "required": ["table", "column", "k"]
+            }
+        },
+        {
+            "name": "kv_get",
+            "description": "Read a value from the key-value store (null if absent)",
+            "inputSchema": {
+                "type": "object",
+                "properties": {"key": {"type": "string"}},
+                "required": ["key"]
+            }
+        },
+        {
+            "name": "kv_set",
+            "description": "Write a value to the key-value store",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "key": {"type": "string"},
+                    "value": {"type": "string"},
+                    "ttl_secs": {"type": "integer", "minimum": 1, "description": "Expire after this many seconds"}
+                },
+                "required": ["key", "value"]
+            }
+        },
+        {
+            "name": "log_metric",
+            "description": "Record a metric for an experiment run (queryable as the metrics table)",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "run_id": {"type": "string"},
+                    "key": {"type": "string", "description": "Metric name, e.g. loss"},
+                    "step": {"type": "integer", "minimum": 0},
+                    "value": {"type": "number"}
+                },
+                "required": ["run_id", "key", "step", "value"]
+            }
+        }
+    ])
+}
+
+fn rpc_error(id: &Value, code: i32, message: &str) -> String {
+    json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}}).to_string()
+}
+
+fn string<'a>(arguments: &'a Value, name: &str) -> Result<&'a str> {
+    arguments
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| Error::InvalidInput(format!("{name} must be a string")))
+}
+
+fn integer(arguments: &Value, name: &str) -> Result<u64> {
+    arguments
+        .get(name)
+        .and_then(Value::as_u64)
+        .ok_or_else(|| Error::InvalidInput(format!("{name} must be a non-negative integer")))
+}
+
+/// `name` if it is a plain SQL identifier (no quoting or injection)
+fn identifier(name: &str) -> Result<&str> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(name)
+    } else {
+        Err(Error::InvalidInput(format!("Not a plain identifier: {name}")))
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use crate::Database;
+    use arrow::array::{Int32Array, RecordBatch};
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    fn server() -> McpServer {
+        let schema = Arc::new(Schema::new(vec![Field::new("score", DataType::Int32, false)]));
+        let batch =
+            RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![3, 1, 2]))]).unwrap();
+        let mut db = Database::builder().backend(crate::Backend::Simd).build().unwrap();
+        db.register_batch("scores", batch).unwrap();
+        McpServer::new(db)
+    }
+
+    async fn call(server: &McpServer, name: &str, arguments: Value) -> Value {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 7,
+            "method": "tools/call",
+            "params": {"name": name, "arguments": arguments}
+        });
+        let response: Value =
+            serde_json::from_str(&server.handle(&request.to_string()).await.unwrap()).unwrap();
+        assert_eq!(response["id"], 7);
+        response["result"].clone()
+    }
+
+    fn payload(result: &Value) -> Value {
+        serde_json::from_str(result["content"][0]["text"].as_str().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_initialize_and_list_tools() {
+        let server = server();
+        let init = server.handle(r#"{"jsonrpc":"2.0","id":1,"method":"initialize"}"#).await;
+        assert!(init.unwrap().contains(PROTOCOL_VERSION));
+        assert!(server
+            .handle(r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#)
+            .await
+            .is_none());
+
+        let list: Value = serde_json::from_str(
+            &server.handle(r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#).await.unwrap(),
+        )
+        .unwrap();
+        let tools = list["result"]["tools"].as_array().unwrap();
+        assert_eq!(tools.len(), 7);
+        assert!(tools.iter().all(|tool| tool["inputSchema"]["type"] == "object"));
+
+        let unknown = server.handle(r#"{"jsonrpc":"2.0","id":3,"method":"nope"}"#).await.unwrap();
+        assert!(unknown.contains("-32601"));
+        assert!(server.handle("{not json").await.unwrap().contains("-32700"));
+    }
+
+    #[tokio::test]
+    async fn test_query_top_k_kv_and_metrics() {
+        let server = server();
+        let result =
+            call(&server, "run_query", json!({"sql": "SELECT score FROM scores", "max_rows": 2}))
+                .await;
+        assert_eq!(result["isError"], false);
+        let query = payload(&result);
+        assert_eq!(query["row_count"], 3);
+        assert_eq!(query["truncated"], true);
+        assert_eq!(query["rows"].as_array().unwrap().len(), 2);
+
+        let top = payload(
+            &call(&server, "top_k", json!({"table": "scores", "column": "score", "k": 2})).await,
+        );
+        assert_eq!(top["rows"], json!([{"score": 3}, {"score": 2}]));
+        let injected =
+            call(&server, "top_k", json!({"table": "scores; DROP", "column": "score", "k": 1}))
+                .await;
+        assert_eq!(injected["isError"], true);
+
+        call(&server, "kv_set", json!({"key": "best", "value": "run-002"})).await;
+        assert_eq!(payload(&call(&server, "kv_get", json!({"key": "best"})).await), "run-002");
+        assert_eq!(payload(&call(&server, "kv_get", json!({"key": "missing"})).await), Value::Null);
+
+        for (step, loss) in [(0, 0.9), (1, 0.4)] {
+            let metric = json!({"run_id": "run-001", "key": "loss", "step": step, "value": loss});
+            assert_eq!(call(&server, "log_metric", metric).await["isError"], false);
+        }
+        let best = payload(
+            &call(&server, "run_query", json!({"sql": "SELECT MIN(value) FROM metrics"})).await,
+        );
+        assert_eq!(best["row_count"], 1);
+
+        let missing = call(&server, "run_query", json!({})).await;
+        assert_eq!(missing["isError"], true);
+    }
+}
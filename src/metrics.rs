@@ -0,0 +1,236 @@
+//! Process-wide operational counters
+//!
+//! The storage, query, GPU and KV layers count what they do into one
+//! [`Metrics`] registry, so an embedder can chart throughput and spot
+//! degradation (GPU fallbacks, `PCIe` traffic) without wrapping every call:
+//!
+//! | Counter              | Incremented when                                    |
+//! |----------------------|-----------------------------------------------------|
+//! | `queries_executed`   | a materialized query finishes, successfully or not  |
+//! | `query_errors`       | a query fails (parse errors excluded)               |
+//! | `rows_scanned`       | rows are read from storage by a query               |
+//! | `rows_returned`      | rows are returned in a query result                 |
+//! | `rows_appended`      | rows are appended to a [`StorageEngine`]            |
+//! | `gpu_dispatches`     | a GPU submission is read back                       |
+//! | `pcie_bytes`         | bytes are uploaded to or read back from the GPU     |
+//! | `fallback_events`    | a backend fails and the next tier takes over        |
+//! | `kv_reads`           | a key is read (`get`, `exists`, per key of a batch) |
+//! | `kv_writes`          | a key is written or deleted                         |
+//!
+//! Like Prometheus' default registry, counters are shared by every
+//! [`Database`](crate::Database), KV store and GPU engine in the process and
+//! only ever increase; rates come from the difference between two
+//! [`snapshot`](Metrics::snapshot)s. [`MetricsSnapshot::to_prometheus`]
+//! renders the text exposition format for a `/metrics` endpoint.
+//!
+//! Alongside the counters, queries, table loads and GPU readbacks emit
+//! `tracing` spans and events at `debug` level.
+//!
+//! ```
+//! use arrow::array::{Int32Array, RecordBatch};
+//! use arrow::datatypes::{DataType, Field, Schema};
+//! use std::sync::Arc;
+//! use trueno_db::metrics::Metrics;
+//! use trueno_db::Database;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let schema = Arc::new(Schema::new(vec![Field::new("score", DataType::Int32, false)]));
+//! let batch = RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![3, 1, 2]))])?;
+//! let mut db = Database::builder().build()?;
+//! db.register_batch("scores", batch)?;
+//!
+//! let before = db.metrics();
+//! db.sql("SELECT score FROM scores WHERE score > 1")?;
+//! let after = db.metrics();
+//! assert!(after.queries_executed > before.queries_executed);
+//! assert!(after.rows_scanned >= before.rows_scanned + 3);
+//!
+//! let text = Metrics::global().snapshot().to_prometheus();
+//! assert!(text.contains("# TYPE trueno_db_rows_scanned_total counter"));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! References:
+//! - Prometheus text exposition format 0.0.4
+//!   <https://prometheus.io/docs/instrumenting/exposition_formats/>
+//!
+//! Toyota Way: Genchi Genbutsu (see what production is actually doing)
+//!
+//! [`StorageEngine`]: crate::storage::StorageEngine
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// One counter of the registry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Counter {
+    QueriesExecuted,
+    QueryErrors,
+    RowsScanned,
+    RowsReturned,
+    RowsAppended,
+    GpuDispatches,
+    PcieBytes,
+    FallbackEvents,
+    KvReads,
+    KvWrites,
+}
+
+/// Counters in snapshot order, with their Prometheus name and help text
+const COUNTERS: [(Counter, &str, &str); 10] = [
+    (Counter::QueriesExecuted, "queries_executed", "Queries executed"),
+    (Counter::QueryErrors, "query_errors", "Queries that failed"),
+    (Counter::RowsScanned, "rows_scanned", "Rows read from storage by queries"),
+    (Counter::RowsReturned, "rows_returned", "Rows returned in query results"),
+    (Counter::RowsAppended, "rows_appended", "Rows appended to storage"),
+    (Counter::GpuDispatches, "gpu_dispatches", "GPU submissions read back"),
+    (Counter::PcieBytes, "pcie_bytes", "Bytes transferred to and from the GPU"),
+    (Counter::FallbackEvents, "fallback_events", "Backend failures handed to the next tier"),
+    (Counter::KvReads, "kv_reads", "Keys read from KV stores"),
+    (Counter::KvWrites, "kv_writes", "Keys written to or deleted from KV stores"),
+];
+
+/// Registry of monotonically increasing counters
+#[derive(Debug)]
+pub struct Metrics {
+    counters: [AtomicU64; COUNTERS.len()],
+}
+
+static GLOBAL: Metrics = Metrics::new();
+
+impl Metrics {
+    const fn new() -> Self {
+        #[allow(clippy::declare_interior_mutable_const)]
+        const ZERO: AtomicU64 = AtomicU64::new(0);
+        Self { counters: [ZERO; COUNTERS.len()] }
+    }
+
+    /// The process-wide registry every module counts into
+    #[must_use]
+    pub fn global() -> &'static Self {
+        &GLOBAL
+    }
+
+    /// Current value of every counter
+    #[must_use]
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let value = |counter: Counter| self.counters[counter as usize].load(Ordering::Relaxed);
+        MetricsSnapshot {
+            queries_executed: value(Counter::QueriesExecuted),
+            query_errors: value(Counter::QueryErrors),
+            rows_scanned: value(Counter::RowsScanned),
+            rows_returned: value(Counter::RowsReturned),
+            rows_appended: value(Counter::RowsAppended),
+            gpu_dispatches: value(Counter::GpuDispatches),
+            pcie_bytes: value(Counter::PcieBytes),
+            fallback_events: value(Counter::FallbackEvents),
+            kv_reads: value(Counter::KvReads),
+            kv_writes: value(Counter::KvWrites),
+        }
+    }
+
+    /// Add `n` to `counter`
+    pub(crate) fn add(&self, counter: Counter, n: u64) {
+        self.counters[counter as usize].fetch_add(n, Ordering::Relaxed);
+    }
+}
+
+/// Add `n` to `counter` of the global registry
+pub(crate) fn count(counter: Counter, n: usize) {
+    Metrics::global().add(counter, n as u64);
+}
+
+/// Count a finished query and the rows it read and returned
+pub(crate) fn record_query(rows_scanned: usize, rows_returned: usize) {
+    count(Counter::QueriesExecuted, 1);
+    count(Counter::RowsScanned, rows_scanned);
+    count(Counter::RowsReturned, rows_returned);
+}
+
+/// Count a failed query
+pub(crate) fn record_query_error() {
+    count(Counter::QueriesExecuted, 1);
+    count(Counter::QueryErrors, 1);
+}
+
+/// Counter values at one point in time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    /// Queries executed, successfully or not
+    pub queries_executed: u64,
+    /// Queries that failed during execution
+    pub query_errors: u64,
+    /// Rows read from storage by queries
+    pub rows_scanned: u64,
+    /// Rows returned in query results
+    pub rows_returned: u64,
+    /// Rows appended to storage engines
+    pub rows_appended: u64,
+    /// GPU submissions read back
+    pub gpu_dispatches: u64,
+    /// Bytes uploaded to and read back from the GPU
+    pub pcie_bytes: u64,
+    /// Backend failures that fell back to the next tier
+    pub fallback_events: u64,
+    /// Keys read from KV stores
+    pub kv_reads: u64,
+    /// Keys written to or deleted from KV stores
+    pub kv_writes: u64,
+}
+
+impl MetricsSnapshot {
+    /// Value of `counter`
+    const fn get(&self, counter: Counter) -> u64 {
+        match counter {
+            Counter::QueriesExecuted => self.queries_executed,
+            Counter::QueryErrors => self.query_errors,
+            Counter::RowsScanned => self.rows_scanned,
+            Counter::RowsReturned => self.rows_returned,
+            Counter::RowsAppended => self.rows_appended,
+            Counter::GpuDispatches => self.gpu_dispatches,
+            Counter::PcieBytes => self.pcie_bytes,
+            Counter::FallbackEvents => self.fallback_events,
+            Counter::KvReads => self.kv_reads,
+            Counter::KvWrites => self.kv_writes,
+        }
+    }
+
+    /// Counters in the Prometheus text exposition format, each named
+    /// `trueno_db_<counter>_total`
+    #[must_use]
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        for (counter, name, help) in COUNTERS {
+            let _ = writeln!(out, "# HELP trueno_db_{name}_total {help}");
+            let _ = writeln!(out, "# TYPE trueno_db_{name}_total counter");
+            let _ = writeln!(out, "trueno_db_{name}_total {}", self.get(counter));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_counts_and_renders() {
+        let metrics = Metrics::new();
+        metrics.add(Counter::KvWrites, 3);
+        metrics.add(Counter::PcieBytes, 4096);
+        let snapshot = metrics.snapshot();
+        assert_eq!(
+            snapshot,
+            MetricsSnapshot { kv_writes: 3, pcie_bytes: 4096, ..MetricsSnapshot::default() }
+        );
+
+        let text = snapshot.to_prometheus();
+        assert_eq!(text.lines().count(), COUNTERS.len() * 3);
+        assert!(text.contains("# TYPE trueno_db_kv_writes_total counter\n"));
+        assert!(text.contains("\ntrueno_db_kv_writes_total 3\n"));
+        assert!(text.contains("\ntrueno_db_pcie_bytes_total 4096\n"));
+        assert!(text.contains("\ntrueno_db_queries_executed_total 0\n"));
+    }
+}
@@ -0,0 +1,268 @@
+//! Query results as text: JSON rows, JSON lines and CSV
+//!
+//! Embedders serving results over HTTP, writing them to files or printing
+//! them all need the same Arrow→text conversion. These helpers do it once,
+//! for any column type, with the same rules everywhere:
+//!
+//! - **NULL**: `null` in JSON; [`OutputOptions::null`] in CSV (empty by default)
+//! - **Integers and booleans**: JSON numbers and booleans
+//! - **Floats**: JSON numbers, rounded to [`OutputOptions::float_precision`]
+//!   digits when set; NaN and infinities (not representable in JSON) become
+//!   `null`
+//! - **Everything else** (strings, dates, timestamps, decimals, ...): the
+//!   Arrow display form, as a JSON string
+//!
+//! JSON objects keep the result's column order. CSV follows RFC 4180:
+//! fields holding a comma, quote or line break are quoted.
+//!
+//! ```
+//! use arrow::array::{Float64Array, RecordBatch, StringArray};
+//! use std::sync::Arc;
+//! use trueno_db::output::{to_csv, to_json_rows, OutputOptions};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let batch = RecordBatch::try_from_iter(vec![
+//!     ("region", Arc::new(StringArray::from(vec![Some("eu"), None])) as _),
+//!     ("avg", Arc::new(Float64Array::from(vec![1.0 / 3.0, 2.5])) as _),
+//! ])?;
+//! let options = OutputOptions { float_precision: Some(2), ..OutputOptions::default() };
+//!
+//! assert_eq!(to_csv(&batch, &options)?, "region,avg\neu,0.33\n,2.50\n");
+//! assert_eq!(
+//!     to_json_rows(&batch, &options)?,
+//!     r#"[{"region":"eu","avg":0.33},{"region":null,"avg":2.5}]"#
+//! );
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! References:
+//! - RFC 4180 (2005): Common format and MIME type for CSV files
+//! - RFC 8259 (2017): The JavaScript Object Notation (JSON) data interchange
+//!   format
+//!
+//! Toyota Way: Standardized work (one conversion, every caller)
+
+use crate::Result;
+use arrow::array::{Array, AsArray};
+use arrow::datatypes::{
+    DataType, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, UInt16Type,
+    UInt32Type, UInt64Type, UInt8Type,
+};
+use arrow::record_batch::RecordBatch;
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use serde_json::Value;
+use std::fmt::Write;
+
+/// How values are rendered as text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputOptions {
+    /// Digits after the decimal point for floats (`None`: shortest form that
+    /// round-trips)
+    pub float_precision: Option<usize>,
+    /// CSV text for NULL (JSON always writes `null`)
+    pub null: String,
+    /// Write a header row of column names (CSV)
+    pub header: bool,
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        Self { float_precision: None, null: String::new(), header: true }
+    }
+}
+
+/// Value at `index` of `array` as JSON
+///
+/// # Errors
+/// Returns error if the column type cannot be displayed
+pub fn json_value(array: &dyn Array, index: usize, options: &OutputOptions) -> Result<Value> {
+    if array.is_null(index) {
+        return Ok(Value::Null);
+    }
+    let precision = options.float_precision;
+    Ok(match array.data_type() {
+        DataType::Boolean => Value::Bool(array.as_boolean().value(index)),
+        DataType::Int8 => array.as_primitive::<Int8Type>().value(index).into(),
+        DataType::Int16 => array.as_primitive::<Int16Type>().value(index).into(),
+        DataType::Int32 => array.as_primitive::<Int32Type>().value(index).into(),
+        DataType::Int64 => array.as_primitive::<Int64Type>().value(index).into(),
+        DataType::UInt8 => array.as_primitive::<UInt8Type>().value(index).into(),
+        DataType::UInt16 => array.as_primitive::<UInt16Type>().value(index).into(),
+        DataType::UInt32 => array.as_primitive::<UInt32Type>().value(index).into(),
+        DataType::UInt64 => array.as_primitive::<UInt64Type>().value(index).into(),
+        DataType::Float32 => {
+            float_json(&float_text(array.as_primitive::<Float32Type>().value(index), precision))
+        }
+        DataType::Float64 => {
+            float_json(&float_text(array.as_primitive::<Float64Type>().value(index), precision))
+        }
+        DataType::Utf8 => Value::String(array.as_string::<i32>().value(index).to_string()),
+        DataType::LargeUtf8 => Value::String(array.as_string::<i64>().value(index).to_string()),
+        _ => {
+            let formatter = ArrayFormatter::try_new(array, &FormatOptions::default())?;
+            Value::String(formatter.value(index).to_string())
+        }
+    })
+}
+
+/// `batch` as a JSON array of objects, one per row
+///
+/// # Errors
+/// Returns error if a column type cannot be displayed
+pub fn to_json_rows(batch: &RecordBatch, options: &OutputOptions) -> Result<String> {
+    let mut out = String::from("[");
+    for row in 0..batch.num_rows() {
+        if row > 0 {
+            out.push(',');
+        }
+        json_object(&mut out, batch, row, options)?;
+    }
+    out.push(']');
+    Ok(out)
+}
+
+/// `batch` as JSON lines: one object per row, each ending in `\n`
+///
+/// # Errors
+/// Returns error if a column type cannot be displayed
+pub fn to_json_lines(batch: &RecordBatch, options: &OutputOptions) -> Result<String> {
+    let mut out = String::new();
+    for row in 0..batch.num_rows() {
+        json_object(&mut out, batch, row, options)?;
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// `batch` as CSV, with a header row if [`OutputOptions::header`] is set
+///
+/// # Errors
+/// Returns error if a column type cannot be displayed
+pub fn to_csv(batch: &RecordBatch, options: &OutputOptions) -> Result<String> {
+    let mut out = String::new();
+    if options.header {
+        let schema = batch.schema();
+        csv_record(&mut out, schema.fields().iter().map(|f| f.name().as_str()));
+    }
+
+    let format = FormatOptions::default().with_null(&options.null);
+    let formatters = batch
+        .columns()
+        .iter()
+        .map(|column| ArrayFormatter::try_new(column.as_ref(), &format))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    for row in 0..batch.num_rows() {
+        let fields = batch.columns().iter().zip(&formatters).map(|(column, formatter)| {
+            match (column.data_type(), options.float_precision) {
+                (DataType::Float32, Some(_)) if column.is_valid(row) => float_text(
+                    column.as_primitive::<Float32Type>().value(row),
+                    options.float_precision,
+                ),
+                (DataType::Float64, Some(_)) if column.is_valid(row) => float_text(
+                    column.as_primitive::<Float64Type>().value(row),
+                    options.float_precision,
+                ),
+                _ => formatter.value(row).to_string(),
+            }
+        });
+        csv_record(&mut out, fields);
+    }
+    Ok(out)
+}
+
+/// Append row `row` of `batch` to `out` as a JSON object
+fn json_object(
+    out: &mut String,
+    batch: &RecordBatch,
+    row: usize,
+    options: &OutputOptions,
+) -> Result<()> {
+    let schema = batch.schema();
+    out.push('{');
+    for (i, (field, column)) in schema.fields().iter().zip(batch.columns()).enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(&Value::String(field.name().clone()).to_string());
+        out.push(':');
+        out.push_str(&json_value(column.as_ref(), row, options)?.to_string());
+    }
+    out.push('}');
+    Ok(())
+}
+
+/// Append one CSV record (quoting fields as needed) and a line break
+fn csv_record<S: AsRef<str>>(out: &mut String, fields: impl Iterator<Item = S>) {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let field = field.as_ref();
+        if field.contains([',', '"', '\n', '\r']) {
+            let _ = write!(out, "\"{}\"", field.replace('"', "\"\""));
+        } else {
+            out.push_str(field);
+        }
+    }
+    out.push('\n');
+}
+
+/// `value` with `precision` decimals, or its shortest round-trip form
+fn float_text<F: std::fmt::Display>(value: F, precision: Option<usize>) -> String {
+    precision.map_or_else(|| value.to_string(), |digits| format!("{value:.digits$}"))
+}
+
+/// JSON number for formatted float `text` (`null` for NaN and infinities)
+fn float_json(text: &str) -> Value {
+    text.parse::<f64>()
+        .ok()
+        .and_then(serde_json::Number::from_f64)
+        .map_or(Value::Null, Value::Number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{
+        ArrayRef, BooleanArray, Date32Array, Float32Array, Int64Array, StringArray,
+    };
+    use std::sync::Arc;
+
+    fn batch() -> RecordBatch {
+        RecordBatch::try_from_iter(vec![
+            ("id", Arc::new(Int64Array::from(vec![Some(1), None])) as ArrayRef),
+            ("name", Arc::new(StringArray::from(vec![Some("a,\"b\""), Some("c")])) as ArrayRef),
+            ("score", Arc::new(Float32Array::from(vec![0.1, f32::NAN])) as ArrayRef),
+            ("ok", Arc::new(BooleanArray::from(vec![Some(true), None])) as ArrayRef),
+            ("day", Arc::new(Date32Array::from(vec![Some(0), None])) as ArrayRef),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_json_rows_and_lines() {
+        let options = OutputOptions::default();
+        let first = r#"{"id":1,"name":"a,\"b\"","score":0.1,"ok":true,"day":"1970-01-01"}"#;
+        let second = r#"{"id":null,"name":"c","score":null,"ok":null,"day":null}"#;
+        assert_eq!(to_json_rows(&batch(), &options).unwrap(), format!("[{first},{second}]"));
+        assert_eq!(to_json_lines(&batch(), &options).unwrap(), format!("{first}\n{second}\n"));
+
+        let empty = batch().slice(0, 0);
+        assert_eq!(to_json_rows(&empty, &options).unwrap(), "[]");
+        assert_eq!(to_json_lines(&empty, &options).unwrap(), "");
+    }
+
+    #[test]
+    fn test_csv_quoting_nulls_and_precision() {
+        let options =
+            OutputOptions { float_precision: Some(3), null: "NULL".to_string(), header: true };
+        assert_eq!(
+            to_csv(&batch(), &options).unwrap(),
+            "id,name,score,ok,day\n1,\"a,\"\"b\"\"\",0.100,true,1970-01-01\nNULL,c,NaN,NULL,NULL\n"
+        );
+
+        let options = OutputOptions { header: false, ..OutputOptions::default() };
+        assert_eq!(to_csv(&batch().slice(1, 1), &options).unwrap(), ",c,NaN,,\n");
+    }
+}
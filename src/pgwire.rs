@@ -0,0 +1,505 @@
+//! `PostgreSQL` wire protocol front-end over a [`SharedDatabase`]
+//!
+//! BI tools and drivers that speak `PostgreSQL` (psql, Grafana, `DBeaver`,
+//! psycopg) connect to trueno-db directly:
+//!
+//! - **Startup**: SSL/GSS encryption requests are declined, then the
+//!   session starts without authentication
+//! - **Simple query protocol** (`Q`): each `;`-separated statement runs on
+//!   the blocking thread pool and its rows stream back in text format,
+//!   flushed every [`FLUSH_BYTES`]
+//! - **Session statements** (`SET`, `RESET`, `BEGIN`, `COMMIT`, `ROLLBACK`,
+//!   `DISCARD`) are acknowledged and ignored: tables are append-only and
+//!   there is no session state
+//! - **Extended query protocol** (`Parse`/`Bind`/`Execute`) is answered
+//!   with an error; configure JDBC clients with `preferQueryMode=simple`
+//!
+//! There is no authentication or TLS: listen on localhost or behind a proxy
+//! that provides them.
+//!
+//! # Example
+//! ```rust,no_run
+//! use trueno_db::pgwire::PgServer;
+//! use trueno_db::Database;
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut db = Database::builder().build()?;
+//! db.attach_dir("data/")?;
+//! let listener = tokio::net::TcpListener::bind("127.0.0.1:5432").await?;
+//! PgServer::new(db).serve(listener).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Then `psql -h 127.0.0.1 -c "SELECT COUNT(*) FROM events"`.
+//!
+//! References:
+//! - `PostgreSQL` Global Development Group: Frontend/Backend Protocol
+//!   (protocol version 3.0), `PostgreSQL` documentation chapter 55
+//!
+//! Toyota Way: Genchi Genbutsu (analysts query the data from the tools they
+//! already use)
+
+use crate::{Error, Result, SharedDatabase};
+use arrow::array::{Array, AsArray};
+use arrow::datatypes::DataType;
+use arrow::record_batch::RecordBatch;
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use std::fmt::Write as _;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Default `PostgreSQL` port
+pub const DEFAULT_PORT: u16 = 5432;
+
+/// Output buffered before it is written to the client while rows stream
+pub const FLUSH_BYTES: usize = 64 * 1024;
+
+/// Largest message accepted from a client
+const MAX_MESSAGE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Protocol version 3.0 in a startup message
+const PROTOCOL_V3: i32 = 196_608;
+const SSL_REQUEST: i32 = 80_877_103;
+const GSSENC_REQUEST: i32 = 80_877_104;
+const CANCEL_REQUEST: i32 = 80_877_102;
+
+/// `server_version` reported to clients (drivers gate features on it)
+const SERVER_VERSION: &str = "14.0 (trueno-db)";
+
+/// `PostgreSQL` server speaking the simple query protocol
+#[derive(Clone)]
+pub struct PgServer {
+    db: SharedDatabase,
+}
+
+impl PgServer {
+    /// Serve `db` (a [`Database`](crate::Database) or a handle the
+    /// application keeps using)
+    #[must_use]
+    pub fn new(db: impl Into<SharedDatabase>) -> Self {
+        Self { db: db.into() }
+    }
+
+    /// Accept connections on `listener` until it fails, one task per client
+    ///
+    /// # Errors
+    /// Returns error if accepting a connection fails
+    pub async fn serve(self, listener: TcpListener) -> Result<()> {
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            let server = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = server.handle(stream).await {
+                    tracing::debug!(%peer, error = %e, "pgwire connection closed");
+                }
+            });
+        }
+    }
+
+    /// Run one client session on `stream` until it terminates
+    ///
+    /// # Errors
+    /// Returns error if the stream fails or the client breaks the protocol
+    pub async fn handle<S>(&self, mut stream: S) -> Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        if !startup(&mut stream).await? {
+            return Ok(());
+        }
+        let mut out = Vec::new();
+        message(&mut out, b'R', |body| body.extend_from_slice(&0_i32.to_be_bytes()));
+        for (name, value) in [
+            ("server_version", SERVER_VERSION),
+            ("server_encoding", "UTF8"),
+            ("client_encoding", "UTF8"),
+            ("DateStyle", "ISO, MDY"),
+            ("integer_datetimes", "on"),
+            ("standard_conforming_strings", "on"),
+        ] {
+            message(&mut out, b'S', |body| {
+                cstring(body, name);
+                cstring(body, value);
+            });
+        }
+        ready(&mut out);
+        stream.write_all(&out).await?;
+        out.clear();
+
+        // After an extended-protocol error, skip messages until Sync
+        let mut failed = false;
+        loop {
+            let tag = match stream.read_u8().await {
+                Ok(tag) => tag,
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+                Err(e) => return Err(e.into()),
+            };
+            let body = read_body(&mut stream).await?;
+            match tag {
+                b'Q' => {
+                    let query = std::str::from_utf8(body.strip_suffix(&[0]).unwrap_or(&body))
+                        .map_err(|e| Error::InvalidInput(format!("query is not UTF-8: {e}")))?;
+                    self.simple_query(&mut stream, &mut out, query).await?;
+                    ready(&mut out);
+                }
+                b'X' => return Ok(()),
+                b'S' => {
+                    failed = false;
+                    ready(&mut out);
+                }
+                b'H' => {}
+                _ if failed => {}
+                _ => {
+                    failed = true;
+                    error_response(
+                        &mut out,
+                        "0A000",
+                        "extended query protocol is not supported (use the simple query protocol)",
+                    );
+                }
+            }
+            stream.write_all(&out).await?;
+            stream.flush().await?;
+            out.clear();
+        }
+    }
+
+    /// Run each statement of `query`, stopping at the first error
+    async fn simple_query<S>(&self, stream: &mut S, out: &mut Vec<u8>, query: &str) -> Result<()>
+    where
+        S: AsyncWrite + Unpin,
+    {
+        let statements = split_statements(query);
+        if statements.is_empty() {
+            message(out, b'I', |_| {});
+            return Ok(());
+        }
+        for statement in statements {
+            if let Some(tag) = session_command(statement) {
+                message(out, b'C', |body| cstring(body, tag));
+                continue;
+            }
+            let db = self.db.clone();
+            let sql = statement.to_string();
+            let result = tokio::task::spawn_blocking(move || db.sql(&sql))
+                .await
+                .map_err(|e| Error::Other(format!("query task: {e}")))?;
+            match result {
+                Ok(batch) => write_result(stream, out, &batch).await?,
+                Err(e) => {
+                    error_response(out, sqlstate(&e), &e.to_string());
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Negotiate the startup; `false` if the client only sent a cancel request
+async fn startup<S>(stream: &mut S) -> Result<bool>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    loop {
+        let body = read_body(stream).await?;
+        let code =
+            body.get(..4).and_then(|code| code.try_into().ok()).map_or(0, i32::from_be_bytes);
+        match code {
+            SSL_REQUEST | GSSENC_REQUEST => stream.write_all(b"N").await?,
+            CANCEL_REQUEST => return Ok(false),
+            PROTOCOL_V3 => return Ok(true),
+            _ => {
+                let mut out = Vec::new();
+                error_response(&mut out, "08P01", &format!("unsupported protocol version {code}"));
+                stream.write_all(&out).await?;
+                return Err(Error::InvalidInput(format!("unsupported protocol version {code}")));
+            }
+        }
+    }
+}
+
+/// Read a length-prefixed message body (the length counts itself)
+async fn read_body<S: AsyncRead + Unpin>(stream: &mut S) -> Result<Vec<u8>> {
+    let len = stream.read_i32().await?;
+    let len = usize::try_from(len)
+        .ok()
+        .and_then(|len| len.checked_sub(4))
+        .filter(|&len| len <= MAX_MESSAGE_BYTES)
+        .ok_or_else(|| Error::InvalidInput(format!("invalid message length {len}")))?;
+    let mut body = vec![0; len];
+    stream.read_exact(&mut body).await?;
+    Ok(body)
+}
+
+/// Append a message: tag, length (including itself), body
+fn message(out: &mut Vec<u8>, tag: u8, body: impl FnOnce(&mut Vec<u8>)) {
+    out.push(tag);
+    let start = out.len();
+    out.extend_from_slice(&[0; 4]);
+    body(out);
+    let len = i32::try_from(out.len() - start).unwrap_or(i32::MAX);
+    out[start..start + 4].copy_from_slice(&len.to_be_bytes());
+}
+
+fn cstring(out: &mut Vec<u8>, value: &str) {
+    out.extend_from_slice(value.as_bytes());
+    out.push(0);
+}
+
+fn ready(out: &mut Vec<u8>) {
+    message(out, b'Z', |body| body.push(b'I'));
+}
+
+fn error_response(out: &mut Vec<u8>, code: &str, text: &str) {
+    message(out, b'E', |body| {
+        for (field, value) in [(b'S', "ERROR"), (b'V', "ERROR"), (b'C', code), (b'M', text)] {
+            body.push(field);
+            cstring(body, value);
+        }
+        body.push(0);
+    });
+}
+
+/// SQLSTATE for `e`
+const fn sqlstate(e: &Error) -> &'static str {
+    match e {
+        Error::ParseError(_) => "42601",
+        Error::InvalidInput(_) => "42000",
+        Error::AccessDenied(_) => "42501",
+        Error::Overloaded(_) => "53000",
+        Error::Timeout(_) | Error::Cancelled(_) => "57014",
+        _ => "XX000",
+    }
+}
+
+/// Command tag for statements acknowledged without running
+fn session_command(statement: &str) -> Option<&'static str> {
+    let keyword = statement.split_whitespace().next()?.to_ascii_uppercase();
+    Some(match keyword.as_str() {
+        "SET" => "SET",
+        "RESET" => "RESET",
+        "BEGIN" | "START" => "BEGIN",
+        "COMMIT" | "END" => "COMMIT",
+        "ROLLBACK" | "ABORT" => "ROLLBACK",
+        "DISCARD" => "DISCARD ALL",
+        _ => return None,
+    })
+}
+
+/// Non-empty statements of `query`, split at `;` outside quotes
+fn split_statements(query: &str) -> Vec<&str> {
+    let mut statements = Vec::new();
+    let (mut start, mut quote) = (0, None);
+    for (i, c) in query.char_indices() {
+        match (c, quote) {
+            ('\'' | '"', None) => quote = Some(c),
+            (c, Some(open)) if c == open => quote = None,
+            (';', None) => {
+                statements.push(&query[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    statements.push(&query[start..]);
+    statements.into_iter().map(str::trim).filter(|s| !s.is_empty()).collect()
+}
+
+/// Stream `batch` as `RowDescription`, `DataRows` and `CommandComplete`
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+async fn write_result<S>(stream: &mut S, out: &mut Vec<u8>, batch: &RecordBatch) -> Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    let schema = batch.schema();
+    message(out, b'T', |body| {
+        body.extend_from_slice(&(schema.fields().len() as i16).to_be_bytes());
+        for field in schema.fields() {
+            let (oid, size) = pg_type(field.data_type());
+            cstring(body, field.name());
+            body.extend_from_slice(&0_i32.to_be_bytes()); // table oid
+            body.extend_from_slice(&0_i16.to_be_bytes()); // column number
+            body.extend_from_slice(&oid.to_be_bytes());
+            body.extend_from_slice(&size.to_be_bytes());
+            body.extend_from_slice(&(-1_i32).to_be_bytes()); // type modifier
+            body.extend_from_slice(&0_i16.to_be_bytes()); // text format
+        }
+    });
+
+    let mut row = 0;
+    while row < batch.num_rows() {
+        row = write_rows(out, batch, row)?;
+        if out.len() >= FLUSH_BYTES {
+            stream.write_all(out).await?;
+            out.clear();
+        }
+    }
+    message(out, b'C', |body| cstring(body, &format!("SELECT {}", batch.num_rows())));
+    Ok(())
+}
+
+/// Encode `DataRows` of `batch` from row `start` until `out` reaches
+/// [`FLUSH_BYTES`], returning the next row to encode (formatters are not
+/// `Send`, so they must not live across a flush)
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+fn write_rows(out: &mut Vec<u8>, batch: &RecordBatch, start: usize) -> Result<usize> {
+    let options = FormatOptions::default()
+        .with_timestamp_format(Some("%Y-%m-%d %H:%M:%S%.f"))
+        .with_timestamp_tz_format(Some("%Y-%m-%d %H:%M:%S%.f%:z"));
+    let formatters = batch
+        .columns()
+        .iter()
+        .map(|column| ArrayFormatter::try_new(column.as_ref(), &options))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let mut text = String::new();
+    for row in start..batch.num_rows() {
+        message(out, b'D', |body| {
+            body.extend_from_slice(&(formatters.len() as i16).to_be_bytes());
+            for (column, formatter) in batch.columns().iter().zip(&formatters) {
+                if column.is_null(row) {
+                    body.extend_from_slice(&(-1_i32).to_be_bytes());
+                    continue;
+                }
+                text.clear();
+                if column.data_type() == &DataType::Boolean {
+                    text.push(if column.as_boolean().value(row) { 't' } else { 'f' });
+                } else {
+                    let _ = write!(text, "{}", formatter.value(row));
+                }
+                body.extend_from_slice(&(text.len() as i32).to_be_bytes());
+                body.extend_from_slice(text.as_bytes());
+            }
+        });
+        if out.len() >= FLUSH_BYTES {
+            return Ok(row + 1);
+        }
+    }
+    Ok(batch.num_rows())
+}
+
+/// `PostgreSQL` type oid and size for an Arrow type (text for anything else)
+const fn pg_type(data_type: &DataType) -> (i32, i16) {
+    match data_type {
+        DataType::Boolean => (16, 1),
+        DataType::Int8 | DataType::Int16 | DataType::UInt8 => (21, 2),
+        DataType::Int32 | DataType::UInt16 => (23, 4),
+        DataType::Int64 | DataType::UInt32 => (20, 8),
+        DataType::Float32 => (700, 4),
+        DataType::Float64 => (701, 8),
+        DataType::Decimal128(..) | DataType::Decimal256(..) | DataType::UInt64 => (1700, -1),
+        DataType::Date32 | DataType::Date64 => (1082, 4),
+        DataType::Timestamp(_, None) => (1114, 8),
+        DataType::Timestamp(_, Some(_)) => (1184, 8),
+        DataType::Time32(_) | DataType::Time64(_) => (1083, 8),
+        _ => (25, -1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Database;
+    use arrow::array::{Int32Array, StringArray};
+    use arrow::datatypes::{Field, Schema};
+    use std::sync::Arc;
+    use tokio::io::DuplexStream;
+
+    fn server() -> PgServer {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])),
+                Arc::new(StringArray::from(vec![Some("a"), None])),
+            ],
+        )
+        .unwrap();
+        let mut db = Database::builder().backend(crate::Backend::Simd).build().unwrap();
+        db.register_batch("users", batch).unwrap();
+        PgServer::new(db)
+    }
+
+    async fn send(client: &mut DuplexStream, tag: u8, body: &[u8]) {
+        let mut out = Vec::new();
+        message(&mut out, tag, |b| b.extend_from_slice(body));
+        client.write_all(&out).await.unwrap();
+    }
+
+    /// Read messages up to and including `ReadyForQuery`
+    async fn until_ready(client: &mut DuplexStream) -> Vec<(u8, Vec<u8>)> {
+        let mut messages = Vec::new();
+        loop {
+            let tag = client.read_u8().await.unwrap();
+            let body = read_body(client).await.unwrap();
+            messages.push((tag, body));
+            if tag == b'Z' {
+                return messages;
+            }
+        }
+    }
+
+    fn tags(messages: &[(u8, Vec<u8>)]) -> String {
+        messages.iter().map(|(tag, _)| char::from(*tag)).collect()
+    }
+
+    #[tokio::test]
+    async fn test_startup_and_simple_query() {
+        let (mut client, backend) = tokio::io::duplex(4096);
+        let session = tokio::spawn(async move { server().handle(backend).await });
+
+        let mut ssl = Vec::new();
+        ssl.extend_from_slice(&8_i32.to_be_bytes());
+        ssl.extend_from_slice(&SSL_REQUEST.to_be_bytes());
+        client.write_all(&ssl).await.unwrap();
+        assert_eq!(client.read_u8().await.unwrap(), b'N');
+
+        let mut startup = PROTOCOL_V3.to_be_bytes().to_vec();
+        startup.extend_from_slice(b"user\0analyst\0\0");
+        let mut framed = (i32::try_from(startup.len()).unwrap() + 4).to_be_bytes().to_vec();
+        framed.extend_from_slice(&startup);
+        client.write_all(&framed).await.unwrap();
+        assert_eq!(tags(&until_ready(&mut client).await), "RSSSSSSZ");
+
+        send(
+            &mut client,
+            b'Q',
+            b"SET extra_float_digits = 3; SELECT id, name FROM users ORDER BY id\0",
+        )
+        .await;
+        let messages = until_ready(&mut client).await;
+        assert_eq!(tags(&messages), "CTDDCZ");
+        // Second row: id "2", name NULL
+        let row = &messages[3].1;
+        assert_eq!(&row[..2], &2_i16.to_be_bytes());
+        assert_eq!(&row[2..7], &[0, 0, 0, 1, b'2']);
+        assert_eq!(&row[7..11], &(-1_i32).to_be_bytes());
+        assert_eq!(messages[4].1, b"SELECT 2\0");
+
+        send(&mut client, b'Q', b"SELECT * FROM missing\0").await;
+        let messages = until_ready(&mut client).await;
+        assert_eq!(tags(&messages), "EZ");
+
+        send(&mut client, b'P', b"\0SELECT 1\0\0\0").await;
+        send(&mut client, b'S', b"").await;
+        assert_eq!(tags(&until_ready(&mut client).await), "EZ");
+
+        send(&mut client, b'X', b"").await;
+        session.await.unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_split_statements_respects_quotes() {
+        assert_eq!(
+            split_statements("SELECT ';' FROM t; ;SELECT 2;"),
+            vec!["SELECT ';' FROM t", "SELECT 2"]
+        );
+        assert!(split_statements(" ; ").is_empty());
+        assert_eq!(session_command("begin"), Some("BEGIN"));
+        assert_eq!(session_command("SELECT 1"), None);
+    }
+}
@@ -0,0 +1,181 @@
+//! Table/column access control hooks
+//!
+//! Embedders exposing SQL to semi-trusted callers (agents, tenant
+//! dashboards) register an [`AccessPolicy`] on the
+//! [`QueryEngine`](super::QueryEngine). While a query is bound, every table
+//! it reads and every column it references (in SELECT, WHERE, GROUP BY, and
+//! ORDER BY, including inside CTEs) is checked for the session principal
+//! passed to [`parse_as`](super::QueryEngine::parse_as). The first denial
+//! fails the parse with [`Error::AccessDenied`](crate::Error::AccessDenied),
+//! so nothing is executed.
+//!
+//! `SELECT *` cannot be expanded at bind time (the schema is not known
+//! yet), so it is checked as the column [`WILDCARD`]; a policy that hides
+//! any column of a table should deny the wildcard for it, as
+//! [`DenyList`] does. References to CTEs are not re-checked: the CTE body
+//! was checked where it reads the underlying table. With JOINs, column
+//! references are not attributed to tables at bind time, so every joined
+//! table is checked for every referenced column (including JOIN keys).
+//!
+//! Toyota Way: Poka-Yoke (denied data never reaches the executor)
+
+use std::collections::{HashMap, HashSet};
+
+/// Column name under which `SELECT *` (and unanalyzable expressions) are checked
+pub const WILDCARD: &str = "*";
+
+/// Principal used by [`QueryEngine::parse`](super::QueryEngine::parse)
+pub const ANONYMOUS_PRINCIPAL: &str = "anonymous";
+
+/// Decides whether a principal may read a table or column
+///
+/// Implementations must be cheap: they are called once per referenced
+/// table and column on every parse.
+pub trait AccessPolicy: Send + Sync {
+    /// Whether `principal` may read anything from `table`
+    fn can_read_table(&self, principal: &str, table: &str) -> bool;
+
+    /// Whether `principal` may read `column` of `table` ([`WILDCARD`] for `*`)
+    ///
+    /// Only consulted after [`can_read_table`](Self::can_read_table) allowed
+    /// the table. Defaults to allowing every column.
+    fn can_read_column(&self, principal: &str, table: &str, column: &str) -> bool {
+        let _ = (principal, table, column);
+        true
+    }
+}
+
+/// Simple deny-list policy: hidden tables and columns per principal
+///
+/// # Example
+/// ```
+/// use std::sync::Arc;
+/// use trueno_db::query::access::DenyList;
+/// use trueno_db::query::QueryEngine;
+///
+/// let policy = DenyList::new().deny_column("agent", "users", "email");
+/// let engine = QueryEngine::new().with_access_policy(Arc::new(policy));
+///
+/// assert!(engine.parse_as("SELECT id FROM users", "agent").is_ok());
+/// assert!(engine.parse_as("SELECT email FROM users", "agent").is_err());
+/// assert!(engine.parse_as("SELECT * FROM users", "agent").is_err());
+/// assert!(engine.parse_as("SELECT email FROM users", "admin").is_ok());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DenyList {
+    tables: HashSet<(String, String)>,
+    columns: HashMap<(String, String), HashSet<String>>,
+}
+
+impl DenyList {
+    /// Empty policy (allows everything)
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hide `table` entirely from `principal`
+    #[must_use]
+    pub fn deny_table(mut self, principal: impl Into<String>, table: impl Into<String>) -> Self {
+        self.tables.insert((principal.into(), table.into()));
+        self
+    }
+
+    /// Hide one column of `table` from `principal` (also denies `SELECT *`)
+    #[must_use]
+    pub fn deny_column(
+        mut self,
+        principal: impl Into<String>,
+        table: impl Into<String>,
+        column: impl Into<String>,
+    ) -> Self {
+        self.columns.entry((principal.into(), table.into())).or_default().insert(column.into());
+        self
+    }
+}
+
+impl AccessPolicy for DenyList {
+    fn can_read_table(&self, principal: &str, table: &str) -> bool {
+        !self.tables.contains(&(principal.to_string(), table.to_string()))
+    }
+
+    fn can_read_column(&self, principal: &str, table: &str, column: &str) -> bool {
+        self.columns
+            .get(&(principal.to_string(), table.to_string()))
+            .map_or(true, |denied| column != WILDCARD && !denied.contains(column))
+    }
+}
+
+/// Policy and principal threaded through binding of one query
+#[derive(Clone)]
+pub(crate) struct AccessContext<'a> {
+    policy: Option<&'a dyn AccessPolicy>,
+    principal: &'a str,
+    /// CTE names in scope (checked where they are defined, not where used)
+    ctes: Vec<String>,
+}
+
+impl<'a> AccessContext<'a> {
+    pub(crate) const fn new(policy: Option<&'a dyn AccessPolicy>, principal: &'a str) -> Self {
+        Self { policy, principal, ctes: Vec::new() }
+    }
+
+    /// Bring a CTE name into scope for the rest of the query
+    pub(crate) fn push_cte(&mut self, name: &str) {
+        self.ctes.push(name.to_string());
+    }
+
+    /// Check a bound table and the columns a query reads from it
+    pub(crate) fn authorize(&self, table: &str, columns: &[String]) -> crate::Result<()> {
+        let Some(policy) = self.policy else {
+            return Ok(());
+        };
+        if table.is_empty() || self.ctes.iter().any(|cte| cte == table) {
+            return Ok(());
+        }
+
+        if !policy.can_read_table(self.principal, table) {
+            return Err(crate::Error::AccessDenied(format!(
+                "principal '{}' may not read table '{table}'",
+                self.principal
+            )));
+        }
+        if let Some(column) =
+            columns.iter().find(|column| !policy.can_read_column(self.principal, table, column))
+        {
+            let column = if column == WILDCARD { "* (all columns)" } else { column };
+            return Err(crate::Error::AccessDenied(format!(
+                "principal '{}' may not read column '{column}' of table '{table}'",
+                self.principal
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deny_list_columns_and_wildcard() {
+        let policy = DenyList::new().deny_column("agent", "users", "email");
+        assert!(policy.can_read_column("agent", "users", "id"));
+        assert!(!policy.can_read_column("agent", "users", "email"));
+        assert!(!policy.can_read_column("agent", "users", WILDCARD));
+        assert!(policy.can_read_column("agent", "orders", WILDCARD));
+        assert!(policy.can_read_column("admin", "users", "email"));
+    }
+
+    #[test]
+    fn test_context_skips_ctes_and_reports_denial() {
+        let policy = DenyList::new().deny_table("agent", "secrets");
+        let mut context = AccessContext::new(Some(&policy), "agent");
+
+        let err = context.authorize("secrets", &[]).unwrap_err();
+        assert!(err.to_string().contains("table 'secrets'"));
+
+        context.push_cte("secrets");
+        assert!(context.authorize("secrets", &[WILDCARD.to_string()]).is_ok());
+    }
+}
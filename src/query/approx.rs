@@ -0,0 +1,80 @@
+//! Approximate aggregates over mergeable sketches
+//!
+//! `APPROX_COUNT_DISTINCT` and `APPROX_QUANTILE` fold a column into a
+//! [`HyperLogLog`] or [`TDigest`] instead of running a typed kernel. Serial
+//! execution sketches each group's values and reads the estimate off;
+//! morsel-parallel execution keeps one sketch per morsel group as its
+//! partial state and merges them, so both agree up to the sketch's error.
+//!
+//! Toyota Way: Heijunka (the same bounded state per group, however many rows)
+
+use super::AggregateFunction;
+use crate::sketch::{HyperLogLog, TDigest};
+use crate::Result;
+use arrow::array::{ArrayRef, Float64Array, Int64Array};
+use arrow::datatypes::DataType;
+use std::sync::Arc;
+
+/// Partial state of an approximate aggregate
+#[derive(Debug, Clone)]
+pub(super) enum Sketch {
+    /// `APPROX_COUNT_DISTINCT`
+    Distinct(HyperLogLog),
+    /// `APPROX_QUANTILE` at this fraction
+    Quantile(TDigest, f64),
+}
+
+impl Sketch {
+    /// Empty sketch for `func` (`None` for exact aggregates)
+    pub(super) fn new(func: AggregateFunction) -> Option<Self> {
+        match func {
+            AggregateFunction::ApproxCountDistinct => Some(Self::Distinct(HyperLogLog::default())),
+            AggregateFunction::ApproxQuantile(q) => {
+                Some(Self::Quantile(TDigest::default(), q.fraction()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Sketch of the non-null values of `column` for `func` (`None` for
+    /// exact aggregates)
+    ///
+    /// # Errors
+    /// Returns error if `func` does not support the column's type
+    pub(super) fn of(func: AggregateFunction, column: &ArrayRef) -> Result<Option<Self>> {
+        let Some(mut sketch) = Self::new(func) else {
+            return Ok(None);
+        };
+        match &mut sketch {
+            Self::Distinct(hll) => hll.insert_array(column)?,
+            Self::Quantile(digest, _) => digest.push_array(column)?,
+        }
+        Ok(Some(sketch))
+    }
+
+    /// Fold `other` (a sketch for the same aggregate) in
+    pub(super) fn merge(&mut self, other: &Self) -> Result<()> {
+        match (self, other) {
+            (Self::Distinct(hll), Self::Distinct(theirs)) => hll.merge(theirs),
+            (Self::Quantile(digest, _), Self::Quantile(theirs, _)) => {
+                digest.merge(theirs);
+                Ok(())
+            }
+            _ => unreachable!("partial kinds depend only on the aggregate"),
+        }
+    }
+
+    /// Estimate as a one-row result: `Int64` distinct count, or `Float64`
+    /// quantile (NULL without values)
+    #[allow(clippy::cast_possible_wrap)]
+    pub(super) fn finish(&self) -> (ArrayRef, DataType) {
+        match self {
+            Self::Distinct(hll) => {
+                (Arc::new(Int64Array::from(vec![hll.estimate() as i64])), DataType::Int64)
+            }
+            Self::Quantile(digest, q) => {
+                (Arc::new(Float64Array::from(vec![digest.quantile(*q)])), DataType::Float64)
+            }
+        }
+    }
+}
@@ -0,0 +1,453 @@
+//! Identifier binding: AST identifiers → names used by the executor
+//!
+//! sqlparser keeps quote style on identifiers, so `"Order"` and `Order`
+//! stringify differently even though both name the same column. Binding
+//! strips quotes (and unescapes doubled quotes) so table, column, and CTE
+//! names resolve the same way whether or not they were quoted. Quoting is
+//! what makes reserved words (`"order"`, `"select"`) and names containing
+//! spaces usable; case is preserved exactly as written in both forms.
+//!
+//! Filters are passed to the executor as text, so [`filter`] renders them
+//! in a canonical form: simple identifiers unquoted, anything else
+//! double-quoted (see [`parse_filter`] for the executor side).
+
+use super::access::WILDCARD;
+use sqlparser::ast::{
+    BinaryOperator, Expr, FunctionArg, FunctionArgExpr, FunctionArguments, GroupByExpr, Ident,
+    JoinConstraint, JoinOperator, ObjectName, OrderBy, Select, SelectItem, TableFactor,
+};
+use sqlparser::dialect::GenericDialect;
+use sqlparser::parser::Parser;
+use sqlparser::tokenizer::Token;
+use std::convert::Infallible;
+
+/// Bound name of a column expression (quotes stripped for identifiers)
+#[allow(clippy::redundant_pub_crate)] // also used by the GPU JIT
+pub(crate) fn column_name(expr: &Expr) -> String {
+    match expr {
+        Expr::Identifier(ident) => ident.value.clone(),
+        Expr::CompoundIdentifier(idents) => join_idents(idents),
+        _ => expr.to_string(),
+    }
+}
+
+/// Bound name of a column, or the canonical text of any other expression
+/// (`date_trunc('day', ts)`), as rendered by [`filter`]
+pub(super) fn expression_name(expr: &Expr) -> String {
+    if is_column(expr) {
+        column_name(expr)
+    } else {
+        filter(expr)
+    }
+}
+
+/// Bound name of a function argument (`*` for wildcards); expressions
+/// (`price * quantity`) are rendered canonically, as by [`filter`]
+pub(super) fn argument_name(arg: &FunctionArg) -> String {
+    match arg {
+        FunctionArg::Unnamed(FunctionArgExpr::Expr(expr))
+        | FunctionArg::Named { arg: FunctionArgExpr::Expr(expr), .. } => expression_name(expr),
+        _ => "*".to_string(),
+    }
+}
+
+/// Bound name of a FROM relation (alias ignored)
+pub(super) fn table_name(relation: &TableFactor) -> String {
+    match relation {
+        TableFactor::Table { name, .. } => object_name(name),
+        _ => relation.to_string(),
+    }
+}
+
+/// Alias given to a FROM relation (`orders o`, `orders AS o`)
+pub(super) fn table_alias(relation: &TableFactor) -> Option<String> {
+    match relation {
+        TableFactor::Table { alias: Some(alias), .. } => Some(alias.name.value.clone()),
+        _ => None,
+    }
+}
+
+/// Column pairs of a join condition: `a = b [AND c = d ...]`, in written order
+pub(super) fn join_keys(condition: &Expr, out: &mut Vec<(String, String)>) -> crate::Result<()> {
+    match condition {
+        Expr::BinaryOp { left, op: BinaryOperator::And, right } => {
+            join_keys(left, out)?;
+            join_keys(right, out)
+        }
+        Expr::BinaryOp { left, op: BinaryOperator::Eq, right }
+            if is_column(left) && is_column(right) =>
+        {
+            out.push((column_name(left), column_name(right)));
+            Ok(())
+        }
+        Expr::Nested(inner) => join_keys(inner, out),
+        _ => Err(crate::Error::ParseError(format!(
+            "JOIN conditions must be column equalities joined by AND: {condition}"
+        ))),
+    }
+}
+
+/// Whether `expr` is a (possibly qualified) column reference
+pub(super) const fn is_column(expr: &Expr) -> bool {
+    matches!(expr, Expr::Identifier(_) | Expr::CompoundIdentifier(_))
+}
+
+/// Bound (dotted) name of an object such as a table
+pub(super) fn object_name(name: &ObjectName) -> String {
+    join_idents(&name.0)
+}
+
+/// Canonical text of a WHERE expression (or a computed SELECT item) for
+/// the executor
+pub(super) fn filter(expr: &Expr) -> String {
+    let mut expr = expr.clone();
+    canonicalize(&mut expr);
+    expr.to_string()
+}
+
+/// Parse a canonical filter back into an expression
+#[allow(clippy::redundant_pub_crate)] // also used by the GPU JIT
+pub(crate) fn parse_filter(filter: &str) -> crate::Result<Expr> {
+    let dialect = GenericDialect {};
+    Parser::new(&dialect)
+        .try_with_sql(filter)
+        .and_then(|mut parser| {
+            let expr = parser.parse_expr()?;
+            parser.expect_token(&Token::EOF)?;
+            Ok(expr)
+        })
+        .map_err(|e| crate::Error::ParseError(format!("Invalid filter expression {filter}: {e}")))
+}
+
+/// Canonical filter with every column reference renamed by `bind`
+pub(super) fn rebind_filter(
+    filter: &str,
+    mut bind: impl FnMut(&str) -> crate::Result<String>,
+) -> crate::Result<String> {
+    let mut expr = parse_filter(filter)?;
+    visit_columns(&mut expr, &mut |column| {
+        let mut ident = Ident::new(bind(&column_name(column))?);
+        canonicalize_ident(&mut ident);
+        *column = Expr::Identifier(ident);
+        Ok::<(), crate::Error>(())
+    })?;
+    Ok(expr.to_string())
+}
+
+/// Split a canonical filter into tokens on whitespace, honouring
+/// double-quoted identifiers (`"my col" > 5` → `my col`, `>`, `5`)
+pub(super) fn split_filter(filter: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = filter.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut token = String::new();
+            while let Some(c) = chars.next() {
+                if c == '"' {
+                    if chars.peek() == Some(&'"') {
+                        chars.next();
+                        token.push('"');
+                    } else {
+                        break;
+                    }
+                } else {
+                    token.push(c);
+                }
+            }
+            tokens.push(token);
+        } else {
+            let mut token = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                token.push(c);
+                chars.next();
+            }
+            tokens.push(token);
+        }
+    }
+
+    tokens
+}
+
+/// Columns a SELECT reads, for access checks and column-pruned scans
+///
+/// Covers the projection (including aggregate arguments), JOIN conditions,
+/// WHERE, GROUP BY, and ORDER BY; GROUP BY and ORDER BY names that refer to
+/// projection aliases are skipped. Wildcards and expressions this walker cannot analyze are
+/// reported as [`WILDCARD`] so policies fail closed. `COUNT(*)` reads no
+/// column values and contributes nothing.
+pub(super) fn referenced_columns(select: &Select, order_by: Option<&OrderBy>) -> Vec<String> {
+    let mut columns = Vec::new();
+    let mut aliases = Vec::new();
+
+    for item in &select.projection {
+        match item {
+            SelectItem::UnnamedExpr(expr) => collect_columns(expr, &mut columns),
+            SelectItem::ExprWithAlias { expr, alias } => {
+                collect_columns(expr, &mut columns);
+                aliases.push(alias.value.as_str());
+            }
+            SelectItem::Wildcard(_) | SelectItem::QualifiedWildcard(..) => {
+                columns.push(WILDCARD.to_string());
+            }
+        }
+    }
+    for join in select.from.iter().flat_map(|table| &table.joins) {
+        if let JoinOperator::Inner(JoinConstraint::On(condition)) = &join.join_operator {
+            collect_columns(condition, &mut columns);
+        }
+    }
+    if let Some(selection) = &select.selection {
+        collect_columns(selection, &mut columns);
+    }
+    let keys = match &select.group_by {
+        GroupByExpr::Expressions(exprs, _) => exprs.as_slice(),
+        GroupByExpr::All(_) => &[],
+    };
+    let orders = order_by.map(|ob| ob.exprs.as_slice()).unwrap_or_default();
+    for expr in keys.iter().chain(orders.iter().map(|order| &order.expr)) {
+        match expr {
+            Expr::Identifier(ident) if aliases.contains(&ident.value.as_str()) => {}
+            expr => collect_columns(expr, &mut columns),
+        }
+    }
+
+    columns.sort();
+    columns.dedup();
+    columns
+}
+
+/// Columns a canonical expression reads ([`WILDCARD`] if it cannot be
+/// analyzed)
+pub(super) fn expression_columns(expr: &str) -> Vec<String> {
+    parse_filter(expr).map_or_else(|_| vec![WILDCARD.to_string()], |parsed| expr_columns(&parsed))
+}
+
+/// Columns a parsed expression reads ([`WILDCARD`] for parts that cannot be
+/// analyzed)
+pub(super) fn expr_columns(expr: &Expr) -> Vec<String> {
+    let mut columns = Vec::new();
+    collect_columns(expr, &mut columns);
+    columns
+}
+
+fn collect_columns(expr: &Expr, out: &mut Vec<String>) {
+    match expr {
+        Expr::Identifier(ident) => out.push(ident.value.clone()),
+        Expr::CompoundIdentifier(idents) => out.extend(idents.last().map(|i| i.value.clone())),
+        // Uncorrelated: a subquery's own tables are checked when it is planned
+        Expr::Value(_) | Expr::Subquery(_) => {}
+        Expr::BinaryOp { left, right, .. } => {
+            collect_columns(left, out);
+            collect_columns(right, out);
+        }
+        Expr::UnaryOp { expr, .. }
+        | Expr::InSubquery { expr, .. }
+        | Expr::Nested(expr)
+        | Expr::IsNull(expr)
+        | Expr::IsNotNull(expr)
+        | Expr::IsTrue(expr)
+        | Expr::IsFalse(expr)
+        | Expr::Cast { expr, .. }
+        | Expr::Extract { expr, .. } => collect_columns(expr, out),
+        Expr::Between { expr, low, high, .. } => {
+            collect_columns(expr, out);
+            collect_columns(low, out);
+            collect_columns(high, out);
+        }
+        Expr::InList { expr, list, .. } => {
+            collect_columns(expr, out);
+            for item in list {
+                collect_columns(item, out);
+            }
+        }
+        Expr::Like { expr, pattern, .. } | Expr::ILike { expr, pattern, .. } => {
+            collect_columns(expr, out);
+            collect_columns(pattern, out);
+        }
+        Expr::Case { operand, conditions, results, else_result } => {
+            operand.iter().chain(else_result).for_each(|e| collect_columns(e, out));
+            conditions.iter().chain(results).for_each(|e| collect_columns(e, out));
+        }
+        Expr::Function(func) => match &func.args {
+            FunctionArguments::None => {}
+            FunctionArguments::List(list) => {
+                for arg in &list.args {
+                    match arg {
+                        FunctionArg::Unnamed(FunctionArgExpr::Expr(expr))
+                        | FunctionArg::Named { arg: FunctionArgExpr::Expr(expr), .. } => {
+                            collect_columns(expr, out);
+                        }
+                        FunctionArg::Unnamed(FunctionArgExpr::Wildcard) => {}
+                        _ => out.push(WILDCARD.to_string()),
+                    }
+                }
+            }
+            FunctionArguments::Subquery(_) => out.push(WILDCARD.to_string()),
+        },
+        _ => out.push(WILDCARD.to_string()),
+    }
+}
+
+fn join_idents(idents: &[Ident]) -> String {
+    idents.iter().map(|ident| ident.value.as_str()).collect::<Vec<_>>().join(".")
+}
+
+/// Re-quote identifiers canonically throughout a filter expression
+fn canonicalize(expr: &mut Expr) {
+    visit_columns(expr, &mut |column| {
+        match column {
+            Expr::Identifier(ident) => canonicalize_ident(ident),
+            Expr::CompoundIdentifier(idents) => idents.iter_mut().for_each(canonicalize_ident),
+            _ => {}
+        }
+        Ok(())
+    })
+    .unwrap_or_else(|never: Infallible| match never {});
+}
+
+/// Call `f` on every column reference in a filter expression (subqueries
+/// excepted: they are planned separately)
+fn visit_columns<E>(
+    expr: &mut Expr,
+    f: &mut impl FnMut(&mut Expr) -> Result<(), E>,
+) -> Result<(), E> {
+    match expr {
+        Expr::Identifier(_) | Expr::CompoundIdentifier(_) => f(expr),
+        Expr::BinaryOp { left, right, .. } => {
+            visit_columns(left, f)?;
+            visit_columns(right, f)
+        }
+        Expr::UnaryOp { expr, .. }
+        | Expr::Nested(expr)
+        | Expr::IsNull(expr)
+        | Expr::IsNotNull(expr)
+        | Expr::IsTrue(expr)
+        | Expr::IsFalse(expr)
+        | Expr::Cast { expr, .. }
+        | Expr::Extract { expr, .. }
+        | Expr::InSubquery { expr, .. } => visit_columns(expr, f),
+        Expr::Between { expr, low, high, .. } => {
+            visit_columns(expr, f)?;
+            visit_columns(low, f)?;
+            visit_columns(high, f)
+        }
+        Expr::InList { expr, list, .. } => {
+            visit_columns(expr, f)?;
+            list.iter_mut().try_for_each(|item| visit_columns(item, f))
+        }
+        Expr::Like { expr, pattern, .. } | Expr::ILike { expr, pattern, .. } => {
+            visit_columns(expr, f)?;
+            visit_columns(pattern, f)
+        }
+        Expr::Function(func) => match &mut func.args {
+            FunctionArguments::List(list) => list.args.iter_mut().try_for_each(|arg| match arg {
+                FunctionArg::Unnamed(FunctionArgExpr::Expr(expr))
+                | FunctionArg::Named { arg: FunctionArgExpr::Expr(expr), .. } => {
+                    visit_columns(expr, f)
+                }
+                _ => Ok(()),
+            }),
+            _ => Ok(()),
+        },
+        _ => Ok(()),
+    }
+}
+
+fn canonicalize_ident(ident: &mut Ident) {
+    let simple = ident.value.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && ident.value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    ident.quote_style = if simple { None } else { Some('"') };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn select(sql: &str) -> (Select, Option<OrderBy>) {
+        use sqlparser::ast::{SetExpr, Statement};
+        use sqlparser::dialect::GenericDialect;
+        use sqlparser::parser::Parser;
+
+        let Statement::Query(query) = Parser::parse_sql(&GenericDialect {}, sql).unwrap().remove(0)
+        else {
+            unreachable!()
+        };
+        let SetExpr::Select(select) = *query.body else { unreachable!() };
+        (*select, query.order_by)
+    }
+
+    #[test]
+    fn test_referenced_columns_sees_through_aliases() {
+        let (select, order_by) = select(
+            "SELECT salary AS s, COUNT(*), SUM(CASE WHEN vip THEN 1 ELSE 0 END) AS n \
+             FROM t WHERE dept = 'x' AND age BETWEEN 1 AND 2 GROUP BY dept ORDER BY s, region",
+        );
+        assert_eq!(
+            referenced_columns(&select, order_by.as_ref()),
+            vec!["age", "dept", "region", "salary", "vip"]
+        );
+    }
+
+    #[test]
+    fn test_referenced_columns_skips_group_by_aliases() {
+        let (select, order_by) =
+            select("SELECT date_trunc('hour', ts) AS hour, COUNT(*) FROM t GROUP BY hour");
+        assert_eq!(referenced_columns(&select, order_by.as_ref()), vec!["ts"]);
+    }
+
+    #[test]
+    fn test_referenced_columns_wildcard() {
+        let (select, order_by) = select("SELECT * FROM t");
+        assert_eq!(referenced_columns(&select, order_by.as_ref()), vec![WILDCARD]);
+    }
+
+    #[test]
+    fn test_split_filter_plain() {
+        assert_eq!(split_filter("value > 10"), vec!["value", ">", "10"]);
+    }
+
+    #[test]
+    fn test_split_filter_quoted_identifier() {
+        assert_eq!(split_filter(r#""my ""col""" >= 1.5"#), vec![r#"my "col""#, ">=", "1.5"]);
+    }
+
+    #[test]
+    fn test_filter_round_trips_through_parse() {
+        let (select, _) = select(
+            "SELECT * FROM t WHERE (`unit price` BETWEEN 1 AND 2 OR \"order\" IN (3, 4)) \
+             AND NOT flag",
+        );
+        let canonical = filter(select.selection.as_ref().unwrap());
+        assert_eq!(canonical, r#"("unit price" BETWEEN 1 AND 2 OR order IN (3, 4)) AND NOT flag"#);
+        assert_eq!(parse_filter(&canonical).unwrap().to_string(), canonical);
+        assert!(parse_filter("a > 1 b").is_err());
+    }
+
+    #[test]
+    fn test_rebind_filter_renames_every_column() {
+        let rebound =
+            rebind_filter("a > b AND c IN (1, 2)", |name| Ok(format!("t.{name}"))).unwrap();
+        assert_eq!(rebound, r#""t.a" > "t.b" AND "t.c" IN (1, 2)"#);
+
+        let rebound = rebind_filter("ROUND(a * 2, 1)", |name| Ok(format!("t.{name}"))).unwrap();
+        assert_eq!(rebound, r#"ROUND("t.a" * 2, 1)"#);
+    }
+
+    #[test]
+    fn test_canonicalize_ident_quotes_only_when_needed() {
+        let mut plain = Ident::with_quote('"', "order");
+        canonicalize_ident(&mut plain);
+        assert_eq!(plain.to_string(), "order");
+
+        let mut spaced = Ident::with_quote('`', "unit price");
+        canonicalize_ident(&mut spaced);
+        assert_eq!(spaced.to_string(), r#""unit price""#);
+    }
+}
@@ -7,21 +7,68 @@
 //! - Kaizen: Top-K optimization (O(N log K) vs O(N log N))
 //! - Genchi Genbutsu: Cost-based backend selection
 
+use super::approx::Sketch;
+use super::explain::{self, PlanStep};
+use super::group_by::Groups;
+use super::join::{self, JoinedColumns};
+use super::limits::{LimitGuard, QueryLimits};
+use super::lint::{self, LintWarning};
+use super::materialize;
+use super::optimizer::distinct_aggregates;
+use super::parallel;
+use super::pruning;
+use super::scalar::{self, Subexpressions};
+use super::selection::SelectionVector;
+use super::stats::{ExecutionReport, OperatorReport, QueryStats, Stopwatch};
+use super::stream::ResultStream;
+use super::subquery;
 use super::{AggregateFunction, OrderDirection, QueryPlan};
-use crate::storage::StorageEngine;
-use crate::topk::{SortOrder, TopKSelection};
+use crate::backend::{BackendDispatcher, DispatchPolicy};
+use crate::catalog::Catalog;
+use crate::metrics;
+use crate::reduce;
+use crate::storage::dictionary::{decode_column, logical_schema};
+use crate::storage::{decode_dictionaries, StorageEngine};
+#[cfg(feature = "parquet-io")]
+use crate::storage::{ParquetSink, ParquetWriteOptions, ParquetWriteSummary};
+use crate::topk::{self, SortOrder, TopKSelection, TopKStrategy};
+use crate::variance::{welford_simd, VarianceKind};
+#[cfg(feature = "tokio")]
+use crate::CancellationToken;
 use crate::{Backend, Error, Result};
 use arrow::array::{
-    Array, ArrayRef, Float32Array, Float64Array, Int32Array, Int64Array, RecordBatch,
+    Array, ArrayRef, AsArray, BooleanArray, Float32Array, Float64Array, Int32Array, Int64Array,
+    PrimitiveArray, RecordBatch, UInt64Array,
 };
+use arrow::buffer::BooleanBuffer;
 use arrow::compute;
-use arrow::datatypes::{DataType, Field, Schema};
+use arrow::datatypes::{
+    ArrowPrimitiveType, DataType, Field, Int16Type, Int8Type, Schema, SchemaRef, UInt16Type,
+    UInt32Type, UInt64Type, UInt8Type,
+};
+use std::borrow::Cow;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt::Write;
+#[cfg(feature = "tokio")]
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 /// Query executor for parsed SQL queries
+#[derive(Debug, Clone)]
 pub struct QueryExecutor {
-    #[allow(dead_code)]
     backend: Backend,
+    /// Overrides for cost-based backend selection
+    dispatch: DispatchPolicy,
+    /// Worker threads for morsel-driven execution (1 = serial)
+    parallelism: usize,
+    /// Per-query time, output row and memory bounds
+    limits: QueryLimits,
+    /// Attach [`QueryStats`] to results as schema metadata
+    query_stats: bool,
+    /// Bytes of working memory for ORDER BY without LIMIT before spilling
+    #[cfg(feature = "ipc-io")]
+    sort_memory_budget: Option<usize>,
 }
 
 impl Default for QueryExecutor {
@@ -34,13 +81,163 @@ impl QueryExecutor {
     /// Create a new query executor with cost-based backend selection
     #[must_use]
     pub const fn new() -> Self {
-        Self { backend: Backend::CostBased }
+        Self::with_backend(Backend::CostBased)
     }
 
     /// Create executor with forced backend
     #[must_use]
     pub const fn with_backend(backend: Backend) -> Self {
-        Self { backend }
+        Self {
+            backend,
+            dispatch: DispatchPolicy::new(),
+            parallelism: 1,
+            limits: QueryLimits::new(),
+            query_stats: false,
+            #[cfg(feature = "ipc-io")]
+            sort_memory_budget: None,
+        }
+    }
+
+    /// Run single-table plans on up to `threads` worker threads
+    ///
+    /// The table is split into morsels of [`parallel::MORSEL_ROWS`] rows
+    /// that workers filter and pre-aggregate independently; partial
+    /// aggregates are then merged (see [`parallel`]). Tables of one morsel,
+    /// and plans with JOINs, run serially. `0` is treated as `1`.
+    ///
+    /// # Example
+    /// ```
+    /// use arrow::array::{Int64Array, RecordBatch};
+    /// use arrow::datatypes::{DataType, Field, Schema};
+    /// use std::sync::Arc;
+    /// use trueno_db::query::{QueryEngine, QueryExecutor};
+    /// use trueno_db::storage::StorageEngine;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let schema = Arc::new(Schema::new(vec![Field::new("v", DataType::Int64, false)]));
+    /// let values = Int64Array::from_iter_values(0..200_000);
+    /// let batch = RecordBatch::try_new(schema, vec![Arc::new(values)])?;
+    /// let storage = StorageEngine::new(vec![batch]);
+    ///
+    /// let plan = QueryEngine::new().parse("SELECT SUM(v), MAX(v) FROM t WHERE v > 1000")?;
+    /// let parallel = QueryExecutor::new().with_parallelism(4).execute(&plan, &storage)?;
+    /// let serial = QueryExecutor::new().execute(&plan, &storage)?;
+    /// assert_eq!(parallel.columns(), serial.columns());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub const fn with_parallelism(mut self, threads: usize) -> Self {
+        self.parallelism = if threads == 0 { 1 } else { threads };
+        self
+    }
+
+    /// Override cost-based selection thresholds, bandwidth or throughput
+    ///
+    /// Applies to the `*_backend` estimates (and [`explain`](Self::explain))
+    /// when the backend is [`Backend::CostBased`]; a forced backend ignores
+    /// the policy.
+    #[must_use]
+    pub const fn with_dispatch_policy(mut self, policy: DispatchPolicy) -> Self {
+        self.dispatch = policy;
+        self
+    }
+
+    /// Bound each query's execution time, result rows and intermediate
+    /// memory (see [`limits`](super::limits))
+    ///
+    /// Limits are checked before each operator and between morsels; a query
+    /// exceeding one fails with [`Error::ResourceLimitExceeded`]. Plans that
+    /// would stream ([`execute_stream`](Self::execute_stream), Parquet
+    /// export) or share a scan ([`execute_batch`](Self::execute_batch)) are
+    /// executed whole instead, so the limits apply to them too.
+    #[must_use]
+    pub const fn with_limits(mut self, limits: QueryLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Attach [`QueryStats`] (rows scanned and returned, elapsed time,
+    /// backend) to every result as schema metadata
+    ///
+    /// Off by default: the elapsed time differs on every run, so results
+    /// carrying it no longer compare equal or share a schema. Turn it on
+    /// where the result is displayed or shipped (CLI, WASM, Flight) and read
+    /// the stats back with [`QueryStats::from_batch`].
+    ///
+    /// # Example
+    /// ```
+    /// use arrow::array::{Int32Array, RecordBatch};
+    /// use arrow::datatypes::{DataType, Field, Schema};
+    /// use std::sync::Arc;
+    /// use trueno_db::query::{QueryEngine, QueryExecutor, QueryStats};
+    /// use trueno_db::storage::StorageEngine;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let schema = Arc::new(Schema::new(vec![Field::new("score", DataType::Int32, false)]));
+    /// let batch = RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![3, 1, 2]))])?;
+    /// let storage = StorageEngine::new(vec![batch]);
+    ///
+    /// let plan = QueryEngine::new().parse("SELECT score FROM t WHERE score > 1")?;
+    /// let result = QueryExecutor::new().with_query_stats(true).execute(&plan, &storage)?;
+    /// assert_eq!(QueryStats::from_batch(&result).map(|stats| stats.rows_scanned), Some(3));
+    ///
+    /// let plain = QueryExecutor::new().execute(&plan, &storage)?;
+    /// assert_eq!(QueryStats::from_batch(&plain), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub const fn with_query_stats(mut self, enabled: bool) -> Self {
+        self.query_stats = enabled;
+        self
+    }
+
+    /// Whether results carry [`QueryStats`] (see
+    /// [`with_query_stats`](Self::with_query_stats))
+    #[must_use]
+    pub const fn query_stats(&self) -> bool {
+        self.query_stats
+    }
+
+    /// Backend selection strategy
+    #[must_use]
+    pub const fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    /// Overrides applied to cost-based backend selection
+    #[must_use]
+    pub const fn dispatch_policy(&self) -> &DispatchPolicy {
+        &self.dispatch
+    }
+
+    /// Worker threads used for single-table plans (1 = serial)
+    #[must_use]
+    pub const fn parallelism(&self) -> usize {
+        self.parallelism
+    }
+
+    /// Per-query limits (unlimited by default)
+    #[must_use]
+    pub const fn limits(&self) -> &QueryLimits {
+        &self.limits
+    }
+
+    /// Bound the memory used by full sorts (ORDER BY without LIMIT)
+    ///
+    /// Plain scans (projection and WHERE without aggregation, GROUP BY,
+    /// JOINs or CTEs) feed each filtered and projected zone into an
+    /// [`ExternalSorter`](crate::external_sort::ExternalSorter), which buffers
+    /// at most `bytes` before spilling a sorted run to an Arrow IPC file in
+    /// the system temp dir; the runs are k-way merged.
+    /// [`execute_stream`](Self::execute_stream) yields the merge batch by
+    /// batch. Other plans sort their (already reduced) result in memory.
+    #[cfg(feature = "ipc-io")]
+    #[must_use]
+    pub const fn with_sort_memory_budget(mut self, bytes: usize) -> Self {
+        self.sort_memory_budget = Some(bytes);
+        self
     }
 
     /// Execute a query plan against storage
@@ -50,7 +247,11 @@ impl QueryExecutor {
     /// * `storage` - Storage engine containing the data
     ///
     /// # Returns
-    /// Result record batch with query results
+    /// Result record batch with query results. With
+    /// [`with_query_stats`](Self::with_query_stats), execution statistics are
+    /// attached as schema metadata; read them with [`QueryStats::from_batch`].
+    /// An `EXPLAIN` plan is not run: the result lists its operators with
+    /// their estimates (see [`explain`](super::explain)).
     ///
     /// # Errors
     /// Returns error if:
@@ -64,6 +265,7 @@ impl QueryExecutor {
     /// use trueno_db::query::{QueryEngine, QueryExecutor};
     /// use trueno_db::storage::StorageEngine;
     ///
+    /// # #[cfg(feature = "parquet-io")]
     /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// let storage = StorageEngine::load_parquet("data/events.parquet")?;
     /// let engine = QueryEngine::new();
@@ -72,11 +274,1313 @@ impl QueryExecutor {
     /// let plan = engine.parse("SELECT category, SUM(value) FROM events GROUP BY category")?;
     /// let result = executor.execute(&plan, &storage)?;
     ///
-    /// println!("Results: {} rows", result.num_rows());
-    /// # Ok(())
-    /// # }
-    /// ```
-    pub fn execute(&self, plan: &QueryPlan, storage: &StorageEngine) -> Result<RecordBatch> {
+    /// println!("Results: {} rows", result.num_rows());
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "parquet-io"))]
+    /// # fn main() {}
+    /// ```
+    pub fn execute(&self, plan: &QueryPlan, storage: &StorageEngine) -> Result<RecordBatch> {
+        self.execute_with_tables(plan, storage, &HashMap::new())
+    }
+
+    /// Execute a query plan whose JOINs read other tables
+    ///
+    /// `storage` holds the FROM table; `tables` maps each joined table name
+    /// to its storage (CTE names resolve to the CTE first). Joins run as
+    /// inner hash joins with the joined table as the build side.
+    ///
+    /// # Errors
+    /// Returns error if a joined table is not in `tables`, a join column is
+    /// missing or ambiguous, or execution fails (see [`execute`](Self::execute))
+    ///
+    /// # Example
+    /// ```
+    /// use arrow::array::{Int32Array, RecordBatch, StringArray};
+    /// use arrow::datatypes::{DataType, Field, Schema};
+    /// use std::collections::HashMap;
+    /// use std::sync::Arc;
+    /// use trueno_db::query::{QueryEngine, QueryExecutor};
+    /// use trueno_db::storage::StorageEngine;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let orders = RecordBatch::try_new(
+    ///     Arc::new(Schema::new(vec![Field::new("customer_id", DataType::Int32, false)])),
+    ///     vec![Arc::new(Int32Array::from(vec![1, 2, 1]))],
+    /// )?;
+    /// let customers = RecordBatch::try_new(
+    ///     Arc::new(Schema::new(vec![
+    ///         Field::new("id", DataType::Int32, false),
+    ///         Field::new("name", DataType::Utf8, false),
+    ///     ])),
+    ///     vec![
+    ///         Arc::new(Int32Array::from(vec![1, 2])),
+    ///         Arc::new(StringArray::from(vec!["ada", "bob"])),
+    ///     ],
+    /// )?;
+    /// let orders = StorageEngine::new(vec![orders]);
+    /// let customers = StorageEngine::new(vec![customers]);
+    ///
+    /// let plan = QueryEngine::new().parse(
+    ///     "SELECT name, COUNT(*) FROM orders o JOIN customers c ON o.customer_id = c.id \
+    ///      GROUP BY name",
+    /// )?;
+    /// let tables = HashMap::from([("customers", &customers)]);
+    /// let result = QueryExecutor::new().execute_with_tables(&plan, &orders, &tables)?;
+    /// assert_eq!(result.num_rows(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn execute_with_tables(
+        &self,
+        plan: &QueryPlan,
+        storage: &StorageEngine,
+        tables: &HashMap<&str, &StorageEngine>,
+    ) -> Result<RecordBatch> {
+        let guard = LimitGuard::start(self.limits);
+        self.execute_reported(plan, storage, tables, &guard).map(|(result, _)| result)
+    }
+
+    /// Execute a query plan, returning a per-operator [`ExecutionReport`]
+    /// alongside the result
+    ///
+    /// The report lists each operator with its rows in and out, wall-clock
+    /// time, the backend the cost model picked and the backend that ran it,
+    /// and the `PCIe` bytes
+    /// [`BackendDispatcher::pcie_transfer_bytes`] estimates for the pick.
+    /// [`ExecutionReport::stats`] holds the query's [`QueryStats`], which
+    /// the result also carries with
+    /// [`with_query_stats`](Self::with_query_stats).
+    ///
+    /// # Errors
+    /// Returns error if execution fails (see [`execute`](Self::execute))
+    ///
+    /// # Example
+    /// ```
+    /// use arrow::array::{Int32Array, RecordBatch};
+    /// use arrow::datatypes::{DataType, Field, Schema};
+    /// use std::sync::Arc;
+    /// use trueno_db::query::{QueryEngine, QueryExecutor};
+    /// use trueno_db::storage::StorageEngine;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let schema = Arc::new(Schema::new(vec![Field::new("score", DataType::Int32, false)]));
+    /// let batch = RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![3, 1, 2]))])?;
+    /// let storage = StorageEngine::new(vec![batch]);
+    ///
+    /// let plan = QueryEngine::new().parse("SELECT SUM(score) FROM t WHERE score > 1")?;
+    /// let (result, report) = QueryExecutor::new().execute_with_report(&plan, &storage)?;
+    /// assert_eq!(result.num_rows(), 1);
+    /// assert_eq!(report.operator("Filter").map(|op| op.rows_out), Some(2));
+    /// println!("{report}");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn execute_with_report(
+        &self,
+        plan: &QueryPlan,
+        storage: &StorageEngine,
+    ) -> Result<(RecordBatch, ExecutionReport)> {
+        self.execute_reported(plan, storage, &HashMap::new(), &LimitGuard::start(self.limits))
+    }
+
+    /// Execute with joined `tables` under `guard`, recording every operator
+    fn execute_reported(
+        &self,
+        plan: &QueryPlan,
+        storage: &StorageEngine,
+        tables: &HashMap<&str, &StorageEngine>,
+        guard: &LimitGuard,
+    ) -> Result<(RecordBatch, ExecutionReport)> {
+        let _span = tracing::debug_span!("query", table = %plan.table).entered();
+        match self.run_reported(plan, storage, tables, guard) {
+            Ok((result, report)) => {
+                tracing::debug!(
+                    rows_scanned = report.rows_scanned,
+                    rows_returned = report.rows_returned,
+                    elapsed_ms = report.elapsed_ms,
+                    "query finished"
+                );
+                metrics::record_query(report.rows_scanned, report.rows_returned);
+                Ok((result, report))
+            }
+            Err(e) => {
+                tracing::debug!(error = %e, "query failed");
+                metrics::record_query_error();
+                Err(e)
+            }
+        }
+    }
+
+    /// Run the plan (or describe it, for EXPLAIN) and fill in the report
+    fn run_reported(
+        &self,
+        plan: &QueryPlan,
+        storage: &StorageEngine,
+        tables: &HashMap<&str, &StorageEngine>,
+        guard: &LimitGuard,
+    ) -> Result<(RecordBatch, ExecutionReport)> {
+        let stopwatch = Stopwatch::start();
+        let mut report = ExecutionReport::new();
+        let result = if plan.explain {
+            explain::to_batch(&self.explain_steps(plan, storage, tables))?
+        } else {
+            let result = self.execute_with_ctes(plan, storage, tables, guard, &mut report)?;
+            guard.check_output(result.num_rows())?;
+            result
+        };
+
+        // Operators currently run on the CPU (SIMD) path whatever the
+        // requested backend; report what actually executed.
+        report.rows_returned = result.num_rows();
+        report.elapsed_ms = stopwatch.elapsed_ms();
+        report.backend = Backend::Simd;
+        let result = self.attach_stats(report.stats(), result)?;
+        Ok((result, report))
+    }
+
+    /// `result` with `stats` in its schema metadata, if enabled
+    fn attach_stats(&self, stats: QueryStats, result: RecordBatch) -> Result<RecordBatch> {
+        if self.query_stats {
+            stats.attach(result)
+        } else {
+            Ok(result)
+        }
+    }
+
+    /// Execute a query plan on the Tokio runtime, abortable through `token`
+    ///
+    /// The table is scanned morsel by morsel (see
+    /// [`StorageEngine::morsels`]) on Tokio's blocking pool, with the
+    /// executor's workers filtering morsels in parallel. `token` is checked
+    /// as each morsel is picked up, so cancelling a long scan stops it within
+    /// one morsel per worker and returns [`Error::Cancelled`] reporting how
+    /// many rows had been scanned. Aggregation, ORDER BY and LIMIT then run
+    /// on the blocking pool over the filtered rows. Plans with JOINs, CTEs or
+    /// subqueries run whole (as with [`execute`](Self::execute)), checking
+    /// `token` before each operator; joined tables come from
+    /// [`execute_async_with_tables`](Self::execute_async_with_tables).
+    /// Dropping the returned future also abandons a scan after the current
+    /// morsels.
+    ///
+    /// Results match [`execute`](Self::execute), including any attached
+    /// [`QueryStats`].
+    ///
+    /// # Errors
+    /// Returns [`Error::Cancelled`] if `token` fires before the result is
+    /// complete, or any error [`execute`](Self::execute) returns
+    ///
+    /// # Example
+    /// ```
+    /// use arrow::array::{Int32Array, RecordBatch};
+    /// use arrow::datatypes::{DataType, Field, Schema};
+    /// use std::sync::Arc;
+    /// use trueno_db::query::{QueryEngine, QueryExecutor};
+    /// use trueno_db::storage::StorageEngine;
+    /// use trueno_db::CancellationToken;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let schema = Arc::new(Schema::new(vec![Field::new("score", DataType::Int32, false)]));
+    /// let batch = RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![3, 1, 2]))])?;
+    /// let storage = StorageEngine::new(vec![batch]);
+    /// let plan = QueryEngine::new().parse("SELECT SUM(score) FROM t WHERE score > 1")?;
+    ///
+    /// let token = CancellationToken::new();
+    /// let result = QueryExecutor::new().execute_async(&plan, &storage, &token).await?;
+    /// assert_eq!(result.num_rows(), 1);
+    ///
+    /// token.cancel();
+    /// assert!(QueryExecutor::new().execute_async(&plan, &storage, &token).await.is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub async fn execute_async(
+        &self,
+        plan: &QueryPlan,
+        storage: &StorageEngine,
+        token: &CancellationToken,
+    ) -> Result<RecordBatch> {
+        self.execute_async_with_tables(plan, storage, &HashMap::new(), token).await
+    }
+
+    /// [`execute_async`](Self::execute_async) for a plan whose JOINs read
+    /// other tables (see [`execute_with_tables`](Self::execute_with_tables))
+    ///
+    /// Whole-plan queries run on the calling worker, through
+    /// `block_in_place` on a multi-threaded runtime so other tasks keep
+    /// running, and read `storage` and `tables` as given.
+    ///
+    /// # Errors
+    /// Returns [`Error::Cancelled`] if `token` fires before the result is
+    /// complete, or any error
+    /// [`execute_with_tables`](Self::execute_with_tables) returns
+    #[cfg(feature = "tokio")]
+    pub async fn execute_async_with_tables(
+        &self,
+        plan: &QueryPlan,
+        storage: &StorageEngine,
+        tables: &HashMap<&str, &StorageEngine>,
+        token: &CancellationToken,
+    ) -> Result<RecordBatch> {
+        let stopwatch = Stopwatch::start();
+        let total_rows: usize = storage.batches().iter().map(RecordBatch::num_rows).sum();
+        let cancelled = move |scanned: usize| {
+            Error::Cancelled(format!(
+                "query cancelled after scanning {scanned} of {total_rows} rows"
+            ))
+        };
+        if token.is_cancelled() {
+            return Err(cancelled(0));
+        }
+
+        if plan.explain
+            || !plan.joins.is_empty()
+            || !plan.ctes.is_empty()
+            || !plan.subqueries.is_empty()
+        {
+            let guard = LimitGuard::start(self.limits).with_cancellation(token.clone());
+            let execute =
+                || self.execute_reported(plan, storage, tables, &guard).map(|(result, _)| result);
+            return match tokio::runtime::Handle::current().runtime_flavor() {
+                tokio::runtime::RuntimeFlavor::MultiThread => tokio::task::block_in_place(execute),
+                _ => execute(),
+            };
+        }
+
+        let (result, rows_scanned) = match self.scan_async(plan, storage, token, cancelled).await {
+            Ok(scanned) => scanned,
+            Err(e) => {
+                metrics::record_query_error();
+                return Err(e);
+            }
+        };
+        metrics::record_query(rows_scanned, result.num_rows());
+
+        let stats = QueryStats {
+            rows_scanned,
+            rows_returned: result.num_rows(),
+            elapsed_ms: stopwatch.elapsed_ms(),
+            backend: Backend::Simd,
+        };
+        self.attach_stats(stats, result)
+    }
+
+    /// Filter `storage`'s morsels on the executor's workers, then
+    /// aggregate/sort, all in one blocking task; returns the result and the
+    /// rows scanned
+    #[cfg(feature = "tokio")]
+    async fn scan_async(
+        &self,
+        plan: &QueryPlan,
+        storage: &StorageEngine,
+        token: &CancellationToken,
+        cancelled: impl Fn(usize) -> Error + Send + Sync + 'static,
+    ) -> Result<(RecordBatch, usize)> {
+        /// Cancels the scan if the query's future is dropped
+        struct Abandon(CancellationToken);
+        impl Drop for Abandon {
+            fn drop(&mut self) {
+                self.0.cancel();
+            }
+        }
+
+        let abandon = Abandon(CancellationToken::new());
+        let morsels: Vec<RecordBatch> = storage.morsels().collect();
+        let (token, abandoned) = (token.clone(), abandon.0.clone());
+        let (executor, plan) = (self.clone(), plan.clone());
+        tokio::task::spawn_blocking(move || {
+            if morsels.is_empty() {
+                return Err(Error::InvalidInput("No data in storage".to_string()));
+            }
+            let stopped = || token.is_cancelled() || abandoned.is_cancelled();
+            let guard = LimitGuard::start(executor.limits);
+            let scanned = AtomicUsize::new(0);
+            let filtered = parallel::run_morsels(&morsels, executor.parallelism, |morsel| {
+                if stopped() {
+                    return Err(cancelled(scanned.load(Ordering::Relaxed)));
+                }
+                guard.check()?;
+                scanned.fetch_add(morsel.num_rows(), Ordering::Relaxed);
+                let filtered = materialize::filter(morsel, &plan)?;
+                guard.reserve(&filtered)?;
+                Ok(filtered)
+            })?;
+            let rows_scanned = scanned.into_inner();
+            if stopped() {
+                return Err(cancelled(rows_scanned));
+            }
+            guard.check()?;
+
+            let filtered = Self::combine_batches(&filtered)?;
+            let result = executor.finish_plan(&filtered, &plan, &mut ExecutionReport::new())?;
+            guard.check_output(result.num_rows())?;
+            Ok((result, rows_scanned))
+        })
+        .await
+        .map_err(Self::task_failed)?
+    }
+
+    /// A blocking query task panicked or was aborted
+    #[cfg(feature = "tokio")]
+    #[allow(clippy::needless_pass_by_value)]
+    fn task_failed(e: tokio::task::JoinError) -> Error {
+        Error::Other(format!("Query task failed: {e}"))
+    }
+
+    /// Execute a query plan, looking up every table it reads in `catalog`
+    ///
+    /// The FROM table and each joined table (including those read inside
+    /// CTEs) are resolved by name. Only resident tables are visible: reload
+    /// evicted ones first with [`Catalog::table`], or run the query through
+    /// [`Database::sql`](crate::Database::sql), which does.
+    ///
+    /// # Errors
+    /// Returns error if a table is not registered (or is evicted), or
+    /// execution fails (see [`execute_with_tables`](Self::execute_with_tables))
+    ///
+    /// # Example
+    /// ```
+    /// use arrow::array::{Int32Array, RecordBatch};
+    /// use arrow::datatypes::{DataType, Field, Schema};
+    /// use std::sync::Arc;
+    /// use trueno_db::query::{QueryEngine, QueryExecutor};
+    /// use trueno_db::storage::StorageEngine;
+    /// use trueno_db::Catalog;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let table = |ids: Vec<i32>| -> Result<StorageEngine, Box<dyn std::error::Error>> {
+    ///     let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+    ///     let batch = RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(ids))])?;
+    ///     Ok(StorageEngine::new(vec![batch]))
+    /// };
+    /// let mut catalog = Catalog::new();
+    /// catalog.register("events", table(vec![1, 2, 3])?);
+    /// catalog.register("users", table(vec![7])?);
+    ///
+    /// let engine = QueryEngine::new();
+    /// let executor = QueryExecutor::new();
+    /// let plan = engine.parse("SELECT COUNT(*) FROM users")?;
+    /// assert_eq!(executor.execute_catalog(&plan, &catalog)?.num_rows(), 1);
+    ///
+    /// let plan = engine.parse("SELECT * FROM missing")?;
+    /// assert!(executor.execute_catalog(&plan, &catalog).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn execute_catalog(&self, plan: &QueryPlan, catalog: &Catalog) -> Result<RecordBatch> {
+        let names = plan.base_tables();
+        let mut tables = HashMap::new();
+        for &name in &names {
+            let storage = catalog.get(name).ok_or_else(|| {
+                if catalog.contains(name) {
+                    Error::InvalidInput(format!("Table is evicted (reload it first): {name}"))
+                } else {
+                    Error::InvalidInput(format!("Table not found: {name}"))
+                }
+            })?;
+            tables.insert(name, storage);
+        }
+
+        let storage = tables[plan.source_table()];
+        self.execute_with_tables(plan, storage, &tables)
+    }
+
+    /// Execute several plans against the same storage, sharing work
+    ///
+    /// The table is scanned (its batches combined) once for the whole batch,
+    /// and each distinct WHERE clause is evaluated once; queries with the
+    /// same filter reuse its output. Plans with CTEs or JOINs run on their
+    /// own, as with [`execute`](Self::execute). Results are returned in plan order,
+    /// each carrying its own [`QueryStats`] if enabled.
+    ///
+    /// # Errors
+    /// Returns the first error any plan produces (see [`execute`](Self::execute))
+    ///
+    /// # Example
+    /// ```
+    /// use arrow::array::{Int32Array, RecordBatch};
+    /// use arrow::datatypes::{DataType, Field, Schema};
+    /// use std::sync::Arc;
+    /// use trueno_db::query::{QueryEngine, QueryExecutor};
+    /// use trueno_db::storage::StorageEngine;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let schema = Arc::new(Schema::new(vec![Field::new("score", DataType::Int32, false)]));
+    /// let batch = RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![3, 1, 2]))])?;
+    /// let storage = StorageEngine::new(vec![batch]);
+    ///
+    /// let engine = QueryEngine::new();
+    /// let plans = vec![
+    ///     engine.parse("SELECT COUNT(*) FROM t WHERE score > 1")?,
+    ///     engine.parse("SELECT MAX(score) FROM t WHERE score > 1")?,
+    /// ];
+    /// let results = QueryExecutor::new().execute_batch(&plans, &storage)?;
+    /// assert_eq!(results.len(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn execute_batch(
+        &self,
+        plans: &[QueryPlan],
+        storage: &StorageEngine,
+    ) -> Result<Vec<RecordBatch>> {
+        let mut combined: Option<RecordBatch> = None;
+        let mut filtered: HashMap<Option<&str>, RecordBatch> = HashMap::new();
+        let mut results = Vec::with_capacity(plans.len());
+
+        for plan in plans {
+            let shared = !plan.explain
+                && plan.ctes.is_empty()
+                && plan.subqueries.is_empty()
+                && plan.joins.is_empty();
+            if !shared || !self.limits.is_unlimited() {
+                results.push(self.execute(plan, storage)?);
+                continue;
+            }
+
+            let stopwatch = Stopwatch::start();
+            let table = if let Some(table) = &combined {
+                table.clone()
+            } else {
+                let table = Self::scan(storage)?;
+                combined = Some(table.clone());
+                table
+            };
+            let rows_scanned = table.num_rows();
+
+            let filter = plan.filter.as_deref();
+            let input = if let Some(input) = filtered.get(&filter) {
+                input.clone()
+            } else {
+                let input = match filter {
+                    Some(filter_expr) => Self::apply_filter(&table, filter_expr)?,
+                    None => table.clone(),
+                };
+                filtered.insert(filter, input.clone());
+                input
+            };
+
+            let result = self.finish_plan(&input, plan, &mut ExecutionReport::new())?;
+            metrics::record_query(rows_scanned, result.num_rows());
+            let stats = QueryStats {
+                rows_scanned,
+                rows_returned: result.num_rows(),
+                elapsed_ms: stopwatch.elapsed_ms(),
+                backend: Backend::Simd,
+            };
+            results.push(self.attach_stats(stats, result)?);
+        }
+
+        Ok(results)
+    }
+
+    /// Execute a query plan, writing its result to a Parquet file
+    ///
+    /// Plain scans (projection, WHERE, LIMIT and OFFSET without ORDER BY) stream:
+    /// each storage batch is filtered, projected and written as it is read,
+    /// so the result is never combined in memory. Plans with aggregations,
+    /// GROUP BY, ORDER BY, JOINs or CTEs are executed as with
+    /// [`execute`](Self::execute) and the result then written. Codec and row
+    /// group size come from `options`; the file appears at `path` only once
+    /// complete (see [`ParquetSink`]).
+    ///
+    /// # Errors
+    /// Returns error if execution fails (see [`execute`](Self::execute)) or
+    /// the file cannot be written
+    ///
+    /// # Example
+    /// ```
+    /// use arrow::array::{Int32Array, RecordBatch};
+    /// use arrow::datatypes::{DataType, Field, Schema};
+    /// use std::sync::Arc;
+    /// use trueno_db::query::{QueryEngine, QueryExecutor};
+    /// use trueno_db::storage::{ParquetWriteOptions, StorageEngine};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let schema = Arc::new(Schema::new(vec![Field::new("score", DataType::Int32, false)]));
+    /// let batch = RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![3, 1, 2]))])?;
+    /// let storage = StorageEngine::new(vec![batch]);
+    /// let path = std::env::temp_dir().join("trueno_doc_export.parquet");
+    ///
+    /// let plan = QueryEngine::new().parse("SELECT score FROM t WHERE score > 1")?;
+    /// let options = ParquetWriteOptions::new().with_max_row_group_size(1024);
+    /// let summary = QueryExecutor::new().execute_to_parquet(&plan, &storage, &path, &options)?;
+    /// assert_eq!(summary.rows, 2);
+    /// # std::fs::remove_file(&path)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "parquet-io")]
+    pub fn execute_to_parquet<P: AsRef<std::path::Path>>(
+        &self,
+        plan: &QueryPlan,
+        storage: &StorageEngine,
+        path: P,
+        options: &ParquetWriteOptions,
+    ) -> Result<ParquetWriteSummary> {
+        let streamable = plan.aggregations.is_empty()
+            && plan.group_by.is_empty()
+            && plan.order_by.is_empty()
+            && plan.joins.is_empty()
+            && plan.ctes.is_empty()
+            && plan.subqueries.is_empty()
+            && !plan.explain
+            && self.limits.is_unlimited();
+        if !streamable {
+            let result = self.execute(plan, storage)?;
+            return StorageEngine::new(vec![result]).write_parquet(path, options);
+        }
+        if storage.batches().is_empty() {
+            return Err(Error::InvalidInput("No data in storage".to_string()));
+        }
+
+        let mut sink = ParquetSink::create(path, options.clone())?;
+        let mut remaining = plan.limit.unwrap_or(usize::MAX);
+        let mut skip = plan.offset;
+        for batch in storage.batches() {
+            let filtered = match &plan.filter {
+                Some(filter_expr) => Self::apply_filter(batch, filter_expr)?,
+                None => batch.clone(),
+            };
+            let projected = Self::project_columns(&filtered, plan)?;
+            let skipped = projected.num_rows().min(skip);
+            skip -= skipped;
+            let rows = (projected.num_rows() - skipped).min(remaining);
+            sink.write(&projected.slice(skipped, rows))?;
+            remaining -= rows;
+            if remaining == 0 {
+                break;
+            }
+        }
+        sink.finish()
+    }
+
+    /// Execute a query plan directly against a Parquet file
+    ///
+    /// Only the columns the plan needs ([`QueryPlan::required_columns`])
+    /// are read and decoded; plans that need every column (`SELECT *`,
+    /// JOINs) load the whole file. The result is the one
+    /// [`execute`](Self::execute) returns for the fully loaded table.
+    ///
+    /// # Errors
+    /// Returns error if the file cannot be read, a required column is not
+    /// in the file, or execution fails (see [`execute`](Self::execute))
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use trueno_db::query::{QueryEngine, QueryExecutor};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let plan = QueryEngine::new().parse("SELECT SUM(value) FROM events WHERE value > 10")?;
+    /// let result = QueryExecutor::new().execute_parquet(&plan, "data/events.parquet")?;
+    /// println!("{} rows", result.num_rows());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "parquet-io")]
+    pub fn execute_parquet<P: AsRef<std::path::Path>>(
+        &self,
+        plan: &QueryPlan,
+        path: P,
+    ) -> Result<RecordBatch> {
+        let storage = match plan.required_columns() {
+            Some(columns) => StorageEngine::load_parquet_with_projection(path, &columns)?,
+            None => StorageEngine::load_parquet(path)?,
+        };
+        self.execute(plan, &storage)
+    }
+
+    /// Execute a query plan, yielding its result batch by batch
+    ///
+    /// Plain scans (projection, WHERE, LIMIT and OFFSET without aggregation,
+    /// GROUP BY, ORDER BY, JOINs or CTEs) are filtered and projected one storage
+    /// zone at a time as the stream is consumed, so a large `SELECT *` never
+    /// holds its whole result in memory; zones the WHERE clause rules out
+    /// are skipped, and LIMIT stops the scan early. Plain scans with ORDER BY
+    /// but no LIMIT stream their sorted output when a
+    /// [sort memory budget](Self::with_sort_memory_budget) is set. Other plans
+    /// run as with [`execute`](Self::execute) and their result is yielded as
+    /// one batch.
+    /// Concatenating the batches gives the rows [`execute`](Self::execute)
+    /// returns, in the same order.
+    ///
+    /// # Errors
+    /// Returns error if storage is empty, the plan names an unknown column,
+    /// or a materialized plan fails (see [`execute`](Self::execute)); errors
+    /// filtering a later batch are yielded by the stream
+    ///
+    /// # Example
+    /// ```
+    /// use arrow::array::{Int32Array, RecordBatch};
+    /// use arrow::datatypes::{DataType, Field, Schema};
+    /// use std::sync::Arc;
+    /// use trueno_db::query::{QueryEngine, QueryExecutor};
+    /// use trueno_db::storage::StorageEngine;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let schema = Arc::new(Schema::new(vec![Field::new("score", DataType::Int32, false)]));
+    /// let batch = |values: Vec<i32>| RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(values))]);
+    /// let storage = StorageEngine::new(vec![batch(vec![3, 1, 2])?, batch(vec![5, 4])?]);
+    ///
+    /// let plan = QueryEngine::new().parse("SELECT score FROM t WHERE score > 1")?;
+    /// let mut rows = 0;
+    /// for batch in QueryExecutor::new().execute_stream(&plan, &storage)? {
+    ///     rows += batch?.num_rows();
+    /// }
+    /// assert_eq!(rows, 4);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn execute_stream(
+        &self,
+        plan: &QueryPlan,
+        storage: &StorageEngine,
+    ) -> Result<ResultStream> {
+        #[cfg(feature = "ipc-io")]
+        if let Some(budget) = self.spill_budget(plan).filter(|_| self.limits.is_unlimited()) {
+            let (morsels, _) = pruning::morsels(storage, plan.filter.as_deref())?;
+            let (schema, sorter) = Self::sort_morsels(plan, storage, morsels, budget)?;
+            return Ok(ResultStream::sorted(schema, sorter.finish()?, plan.offset));
+        }
+
+        let streamable =
+            Self::is_plain_scan(plan) && plan.order_by.is_empty() && self.limits.is_unlimited();
+        if !streamable {
+            return Ok(ResultStream::materialized(self.execute(plan, storage)?));
+        }
+        let Some(first) = storage.batches().first() else {
+            return Err(Error::InvalidInput("No data in storage".to_string()));
+        };
+        let (morsels, _) = pruning::morsels(storage, plan.filter.as_deref())?;
+        ResultStream::scan(plan, morsels, first)
+    }
+
+    /// Whether `plan` only filters and projects one table's rows (before
+    /// ORDER BY, LIMIT and OFFSET)
+    fn is_plain_scan(plan: &QueryPlan) -> bool {
+        plan.aggregations.is_empty()
+            && plan.group_by.is_empty()
+            && plan.joins.is_empty()
+            && plan.ctes.is_empty()
+            && plan.subqueries.is_empty()
+            && !plan.explain
+    }
+
+    /// Sort memory budget for plans that sort externally: plain scans
+    /// with ORDER BY and no LIMIT
+    #[cfg(feature = "ipc-io")]
+    fn spill_budget(&self, plan: &QueryPlan) -> Option<usize> {
+        let full_sort = !plan.order_by.is_empty() && plan.limit.is_none();
+        self.sort_memory_budget.filter(|_| full_sort && Self::is_plain_scan(plan))
+    }
+
+    /// Filter and project each morsel into an [`ExternalSorter`] on the
+    /// plan's ORDER BY keys; returns the projected schema and the sorter
+    ///
+    /// [`ExternalSorter`]: crate::external_sort::ExternalSorter
+    #[cfg(feature = "ipc-io")]
+    fn sort_morsels(
+        plan: &QueryPlan,
+        storage: &StorageEngine,
+        morsels: Vec<RecordBatch>,
+        budget: usize,
+    ) -> Result<(SchemaRef, crate::external_sort::ExternalSorter)> {
+        use crate::external_sort::ExternalSorter;
+        use crate::topk::TieBreak;
+
+        let Some(first) = storage.batches().first() else {
+            return Err(Error::InvalidInput("No data in storage".to_string()));
+        };
+        let schema = Self::project_columns(&first.slice(0, 0), plan)?.schema();
+        let keys = Self::sort_keys(&schema, plan)?;
+        let (col_index, sort_order) = keys[0];
+        let mut sorter =
+            ExternalSorter::new(SchemaRef::clone(&schema), col_index, sort_order, budget)?
+                .with_tie_break(TieBreak::Columns(keys[1..].to_vec()))?;
+        for morsel in morsels {
+            sorter.push(ResultStream::process(plan, &morsel)?)?;
+        }
+        Ok((schema, sorter))
+    }
+
+    /// Sort a plain scan's rows within `budget` bytes (see
+    /// [`Self::with_sort_memory_budget`]), then apply OFFSET
+    #[cfg(feature = "ipc-io")]
+    fn execute_external_sort(
+        plan: &QueryPlan,
+        storage: &StorageEngine,
+        morsels: Vec<RecordBatch>,
+        budget: usize,
+        guard: &LimitGuard,
+        report: &mut ExecutionReport,
+    ) -> Result<RecordBatch> {
+        let stopwatch = Stopwatch::start();
+        let rows = morsels.iter().map(RecordBatch::num_rows).sum::<usize>();
+        report.rows_scanned += rows;
+
+        let (schema, sorter) = Self::sort_morsels(plan, storage, morsels, budget)?;
+        guard.check()?;
+        let detail = format!(
+            "{} ({} runs spilled, peak {} bytes){}",
+            Self::order_keys(plan),
+            sorter.spilled_runs(),
+            sorter.peak_memory(),
+            Self::offset_detail(plan)
+        );
+        let batches =
+            ResultStream::sorted(SchemaRef::clone(&schema), sorter.finish()?, plan.offset)
+                .collect::<Result<Vec<_>>>()?;
+        let result = if batches.is_empty() {
+            RecordBatch::new_empty(schema)
+        } else {
+            Self::combine_batches(&batches)?
+        };
+        guard.reserve(&result)?;
+        report.push(OperatorReport::simd(
+            "ExternalSort",
+            detail,
+            rows,
+            result.num_rows(),
+            stopwatch.elapsed_ms(),
+        ));
+        Ok(result)
+    }
+
+    /// Describe how a plan would execute against `storage` (EXPLAIN)
+    ///
+    /// One operator per line, in execution order. Row counts are estimates
+    /// taken before filtering, so the Top-K strategy shown is the one chosen
+    /// for the unfiltered input; the executor re-decides on the actual rows.
+    /// Optimizer rewrites (see [`optimizer`](super::optimizer)) precede each
+    /// SELECT's operators as `Rewrite:` lines, and lint warnings (see
+    /// [`lint`](Self::lint)) follow as `Warning:` lines.
+    /// Executing the plan parsed from `EXPLAIN SELECT ...` returns the same
+    /// operators as a `RecordBatch`, with estimated FLOPs, backend and morsels.
+    ///
+    /// # Example
+    /// ```
+    /// use arrow::array::{Int32Array, RecordBatch};
+    /// use arrow::datatypes::{DataType, Field, Schema};
+    /// use std::sync::Arc;
+    /// use trueno_db::query::{QueryEngine, QueryExecutor};
+    /// use trueno_db::storage::StorageEngine;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let schema = Arc::new(Schema::new(vec![Field::new("score", DataType::Int32, false)]));
+    /// let batch = RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![3, 1, 2]))])?;
+    /// let storage = StorageEngine::new(vec![batch]);
+    ///
+    /// let plan = QueryEngine::new().parse("SELECT score FROM t ORDER BY score DESC LIMIT 2")?;
+    /// let explain = QueryExecutor::new().explain(&plan, &storage);
+    /// assert!(explain.contains("TopK: score DESC k=2"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn explain(&self, plan: &QueryPlan, storage: &StorageEngine) -> String {
+        self.explain_with_tables(plan, storage, &HashMap::new())
+    }
+
+    /// EXPLAIN for a plan whose JOINs read `tables` (see
+    /// [`execute_with_tables`](Self::execute_with_tables))
+    ///
+    /// Each join shows the backend the cost model picks for it from the
+    /// table sizes (see [`join_backend`](Self::join_backend)).
+    #[must_use]
+    pub fn explain_with_tables(
+        &self,
+        plan: &QueryPlan,
+        storage: &StorageEngine,
+        tables: &HashMap<&str, &StorageEngine>,
+    ) -> String {
+        explain::render(&self.explain_steps(plan, storage, tables))
+    }
+
+    /// Operators of `plan` with their estimates, CTEs first, then lint warnings
+    fn explain_steps(
+        &self,
+        plan: &QueryPlan,
+        storage: &StorageEngine,
+        tables: &HashMap<&str, &StorageEngine>,
+    ) -> Vec<PlanStep> {
+        let mut steps = Vec::new();
+        let mut cte_rows: HashMap<&str, usize> = HashMap::new();
+        let storage_rows = storage.batches().iter().map(RecordBatch::num_rows).sum();
+
+        for (name, cte_plan) in &plan.ctes {
+            let rows = cte_rows.get(cte_plan.table.as_str()).copied().unwrap_or(storage_rows);
+            let output_rows = self.explain_plan(cte_plan, rows, tables, Some(name), &mut steps);
+            cte_rows.insert(name.as_str(), output_rows);
+        }
+
+        let rows = cte_rows.get(plan.table.as_str()).copied().unwrap_or(storage_rows);
+        self.explain_plan(plan, rows, tables, None, &mut steps);
+        let warnings = Self::lint_with_tables(plan, storage, tables);
+        steps.extend(warnings.iter().map(|warning| PlanStep {
+            cte: None,
+            operator: "Warning",
+            detail: warning.to_string(),
+            estimated_rows: 0,
+            estimated_flops: 0.0,
+            backend: None,
+            morsels: 0,
+        }));
+        steps
+    }
+
+    /// Static analysis warnings for a plan (see [`lint`](super::lint))
+    ///
+    /// Warnings never stop a query; [`explain`](Self::explain) lists them
+    /// after the operators. CTEs are linted too, but only checks that need
+    /// no data apply to queries reading a CTE.
+    ///
+    /// # Example
+    /// ```
+    /// use arrow::array::{Int32Array, RecordBatch};
+    /// use arrow::datatypes::{DataType, Field, Schema};
+    /// use std::sync::Arc;
+    /// use trueno_db::query::{Lint, QueryEngine, QueryExecutor};
+    /// use trueno_db::storage::StorageEngine;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let schema = Arc::new(Schema::new(vec![Field::new("score", DataType::Int32, false)]));
+    /// let batch = RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![3, 1, 2]))])?;
+    /// let storage = StorageEngine::new(vec![batch]);
+    ///
+    /// let plan = QueryEngine::new().parse("SELECT score FROM t ORDER BY score")?;
+    /// let warnings = QueryExecutor::new().lint(&plan, &storage);
+    /// assert_eq!(warnings[0].lint, Lint::SortWithoutLimit);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    #[allow(clippy::unused_self)]
+    pub fn lint(&self, plan: &QueryPlan, storage: &StorageEngine) -> Vec<LintWarning> {
+        Self::lint_with_tables(plan, storage, &HashMap::new())
+    }
+
+    /// Lint with table lookup as in [`execute_with_tables`](Self::execute_with_tables)
+    fn lint_with_tables(
+        plan: &QueryPlan,
+        storage: &StorageEngine,
+        tables: &HashMap<&str, &StorageEngine>,
+    ) -> Vec<LintWarning> {
+        let reads = |table: &str| {
+            let is_cte = plan.ctes.iter().any(|(name, _)| name == table);
+            (!is_cte).then(|| tables.get(table).copied().unwrap_or(storage))
+        };
+        let mut warnings = Vec::new();
+        for (_, cte_plan) in &plan.ctes {
+            warnings.extend(lint::check(cte_plan, reads(&cte_plan.table)));
+        }
+        warnings.extend(lint::check(plan, reads(&plan.table)));
+        warnings
+    }
+
+    /// Append operator steps for one SELECT; returns its estimated output rows
+    fn explain_plan(
+        &self,
+        plan: &QueryPlan,
+        rows: usize,
+        tables: &HashMap<&str, &StorageEngine>,
+        cte: Option<&str>,
+        steps: &mut Vec<PlanStep>,
+    ) -> usize {
+        steps.extend(plan.rewrites.iter().map(|rewrite| PlanStep {
+            cte: cte.map(str::to_string),
+            operator: "Rewrite",
+            detail: rewrite.to_string(),
+            estimated_rows: 0,
+            estimated_flops: 0.0,
+            backend: None,
+            morsels: 0,
+        }));
+        let mut push = |operator, detail, rows_in: usize, rows_out, flops, backend| {
+            steps.push(PlanStep {
+                cte: cte.map(str::to_string),
+                operator,
+                detail,
+                estimated_rows: rows_out,
+                estimated_flops: flops,
+                backend: Some(backend),
+                morsels: rows_in.div_ceil(parallel::MORSEL_ROWS),
+            });
+        };
+
+        push("Scan", format!("{} (~{rows} rows)", plan.table), rows, rows, 0.0, Backend::Simd);
+        // Joins are estimated as key/foreign-key joins: one match per probe row
+        for join in &plan.joins {
+            let on: Vec<String> =
+                join.on.iter().map(|(left, right)| format!("{left} = {right}")).collect();
+            let mut detail = format!("{} ON {}", join.table, on.join(" AND "));
+            let (build_rows, backend) =
+                tables.get(join.table.as_str()).map_or((0, Backend::Simd), |build| {
+                    let build_rows = build.batches().iter().map(RecordBatch::num_rows).sum();
+                    let backend = self.join_backend(rows, build_rows, build.memory_size());
+                    let _ =
+                        write!(detail, " (~{build_rows} build rows) backend={}", backend.name());
+                    (build_rows, backend)
+                });
+            let flops = BackendDispatcher::estimate_join_flops(build_rows, rows);
+            push("HashJoin", detail, rows + build_rows, rows, flops, backend);
+        }
+        if let Some(filter) = &plan.filter {
+            let flops = BackendDispatcher::estimate_filter_flops(rows);
+            push("Filter", filter.clone(), rows, rows, flops, Backend::Simd);
+        }
+
+        let rows = if plan.aggregations.is_empty() {
+            push("Project", plan.columns.join(", "), rows, rows, 0.0, Backend::Simd);
+            rows
+        } else {
+            let aggregates = Self::aggregate_list(plan);
+            // Only key and aggregate input columns are shipped to the GPU
+            let columns = plan.group_by.len() + plan.aggregations.len();
+            if plan.group_by.is_empty() {
+                let flops = BackendDispatcher::estimate_simple_aggregation_flops(
+                    rows * plan.aggregations.len(),
+                );
+                let backend = self.aggregate_backend(rows, rows * columns * 8);
+                push("Aggregate", aggregates, rows, 1, flops, backend);
+                1
+            } else {
+                let flops = BackendDispatcher::estimate_group_by_flops(rows);
+                let backend = self.group_by_backend(rows, rows * columns * 8);
+                let detail = format!(
+                    "{aggregates} GROUP BY {} backend={}",
+                    plan.group_by.join(", "),
+                    backend.name()
+                );
+                push("HashAggregate", detail, rows, rows, flops, backend);
+                rows
+            }
+        };
+
+        if !plan.order_by.is_empty() {
+            let k = plan.fetch().unwrap_or(rows);
+            let strategy = TopKStrategy::choose(k, rows);
+            let keys = Self::order_keys(plan);
+            let detail =
+                format!("{keys} k={k}{} strategy={}", Self::offset_detail(plan), strategy.name());
+            // One comparison per row against the current K-th value
+            let flops = BackendDispatcher::estimate_simple_aggregation_flops(rows);
+            let output = k.min(rows).saturating_sub(plan.offset);
+            push("TopK", detail, rows, output, flops, Backend::Simd);
+            output
+        } else if plan.limit.is_some() || plan.offset > 0 {
+            let output = Self::page(rows, plan).1;
+            push("Limit", Self::limit_detail(plan), rows, output, 0.0, Backend::Simd);
+            output
+        } else {
+            rows
+        }
+    }
+
+    /// Execute a plan, materializing its CTEs first
+    fn execute_with_ctes(
+        &self,
+        plan: &QueryPlan,
+        storage: &StorageEngine,
+        tables: &HashMap<&str, &StorageEngine>,
+        guard: &LimitGuard,
+        report: &mut ExecutionReport,
+    ) -> Result<RecordBatch> {
+        if plan.ctes.is_empty() {
+            let plan = self.bind_subqueries(plan, storage, tables, guard, report)?;
+            return self.execute_plan(&plan, storage, tables, guard, report);
+        }
+
+        // Materialize CTEs as query-scoped temporary tables (dropped on return).
+        // Later CTEs may reference earlier ones; other names resolve through
+        // `tables`, and a FROM table found in neither reads `storage`.
+        let mut temp_tables: HashMap<&str, StorageEngine> = HashMap::new();
+        for (name, cte_plan) in &plan.ctes {
+            let materialized = {
+                let scope = Self::scope(tables, &temp_tables);
+                let source = scope.get(cte_plan.table.as_str()).copied().unwrap_or(storage);
+                self.execute_with_ctes(cte_plan, source, &scope, guard, report)?
+            };
+            guard.reserve(&materialized)?;
+            temp_tables.insert(name.as_str(), StorageEngine::new(vec![materialized]));
+        }
+
+        let scope = Self::scope(tables, &temp_tables);
+        let source = scope.get(plan.table.as_str()).copied().unwrap_or(storage);
+        let plan = self.bind_subqueries(plan, storage, &scope, guard, report)?;
+        self.execute_plan(&plan, source, &scope, guard, report)
+    }
+
+    /// `plan` with its WHERE subqueries run and their results bound into
+    /// the filter as literals (see [`QueryPlan::subqueries`])
+    ///
+    /// Subquery FROM tables resolve like a CTE's: `tables` first, else
+    /// `storage`.
+    fn bind_subqueries<'p>(
+        &self,
+        plan: &'p QueryPlan,
+        storage: &StorageEngine,
+        tables: &HashMap<&str, &StorageEngine>,
+        guard: &LimitGuard,
+        report: &mut ExecutionReport,
+    ) -> Result<Cow<'p, QueryPlan>> {
+        if plan.subqueries.is_empty() {
+            return Ok(Cow::Borrowed(plan));
+        }
+        let mut results = Vec::with_capacity(plan.subqueries.len());
+        for (sql, subquery_plan) in &plan.subqueries {
+            let source = tables.get(subquery_plan.table.as_str()).copied().unwrap_or(storage);
+            let result = self.execute_with_ctes(subquery_plan, source, tables, guard, report)?;
+            guard.reserve(&result)?;
+            results.push((sql.as_str(), result));
+        }
+        let filter =
+            plan.filter.as_deref().map(|filter| subquery::bind(filter, &results)).transpose()?;
+        Ok(Cow::Owned(QueryPlan { filter, subqueries: Vec::new(), ..plan.clone() }))
+    }
+
+    /// Joinable tables: `tables`, shadowed by materialized CTEs
+    fn scope<'a>(
+        tables: &HashMap<&'a str, &'a StorageEngine>,
+        temp_tables: &'a HashMap<&'a str, StorageEngine>,
+    ) -> HashMap<&'a str, &'a StorageEngine> {
+        let mut scope = tables.clone();
+        scope.extend(temp_tables.iter().map(|(name, storage)| (*name, storage)));
+        scope
+    }
+
+    /// Execute a single SELECT (no CTEs) against one storage engine
+    fn execute_plan(
+        &self,
+        plan: &QueryPlan,
+        storage: &StorageEngine,
+        tables: &HashMap<&str, &StorageEngine>,
+        guard: &LimitGuard,
+        report: &mut ExecutionReport,
+    ) -> Result<RecordBatch> {
+        guard.check()?;
+        let stopwatch = Stopwatch::start();
+        // Zones whose min/max rule out the WHERE clause are never read
+        let (morsels, skipped) = if plan.joins.is_empty() {
+            pruning::morsels(storage, plan.filter.as_deref())?
+        } else {
+            (Vec::new(), 0)
+        };
+
+        #[cfg(feature = "ipc-io")]
+        if let Some(budget) = self.spill_budget(plan) {
+            return Self::execute_external_sort(plan, storage, morsels, budget, guard, report);
+        }
+
+        if self.parallelism > 1 && morsels.len() > 1 {
+            let rows = morsels.iter().map(RecordBatch::num_rows).sum::<usize>();
+            report.rows_scanned += rows;
+            let result = parallel::execute(self, plan, &morsels, guard)?;
+            let pruned =
+                if skipped > 0 { format!(", {skipped} zones skipped") } else { String::new() };
+            report.push(OperatorReport::simd(
+                "MorselPipeline",
+                format!(
+                    "{} ({} morsels, {} workers{pruned})",
+                    plan.table,
+                    morsels.len(),
+                    self.parallelism
+                ),
+                rows,
+                result.num_rows(),
+                stopwatch.elapsed_ms(),
+            ));
+            return Self::order_and_limit(result, plan, report);
+        }
+
+        let combined = match (skipped, morsels.is_empty()) {
+            (0, _) => Self::scan(storage)?,
+            (_, false) => Self::combine_batches(&morsels)?,
+            (_, true) => RecordBatch::new_empty(storage.batches()[0].schema()),
+        };
+        let rows = combined.num_rows();
+        report.rows_scanned += rows;
+        report.push(OperatorReport::simd(
+            "Scan",
+            match skipped {
+                0 => plan.table.clone(),
+                skipped => format!("{} ({skipped} zones skipped)", plan.table),
+            },
+            rows,
+            rows,
+            stopwatch.elapsed_ms(),
+        ));
+
+        let bound;
+        let (combined, plan) = if plan.joins.is_empty() {
+            (combined, plan)
+        } else {
+            let (joined, joined_plan) = self.apply_joins(combined, plan, tables, report)?;
+            guard.reserve(&joined)?;
+            bound = joined_plan;
+            (joined, &bound)
+        };
+
+        // Apply WHERE filter: a selection vector, consumed by top-k and gather
+        guard.check()?;
+        let selected = if let Some(ref filter_expr) = plan.filter {
+            let stopwatch = Stopwatch::start();
+            let selected = SelectionVector::filter(&combined, filter_expr)?;
+            report.push(OperatorReport::simd(
+                "Filter",
+                filter_expr.clone(),
+                combined.num_rows(),
+                selected.len(),
+                stopwatch.elapsed_ms(),
+            ));
+            selected
+        } else {
+            SelectionVector::All(combined.num_rows())
+        };
+
+        // Rank the sort keys alone, then gather the other columns for the
+        // top rows only
+        if let Some(top) = Self::late_top_k(&combined, plan, &selected, report)? {
+            let gathered = materialize::gather(&combined, plan, &top)?;
+            guard.reserve(&gathered)?;
+            guard.check()?;
+            let ranked = QueryPlan { order_by: Vec::new(), limit: None, ..plan.clone() };
+            return self.finish_plan(&gathered, &ranked, report);
+        }
+
+        let filtered = materialize::gather(&combined, plan, &selected)?;
+        guard.reserve(&filtered)?;
+        guard.check()?;
+        self.finish_plan(&filtered, plan, report)
+    }
+
+    /// Rows of a projection's ORDER BY ... LIMIT result, ranked before
+    /// projecting (see [`materialize::top_k`])
+    ///
+    /// `None` leaves ORDER BY to [`Self::order_and_limit`]: for plans
+    /// `materialize::top_k` does not handle and `LIMIT 0`.
+    fn late_top_k(
+        batch: &RecordBatch,
+        plan: &QueryPlan,
+        selection: &SelectionVector,
+        report: &mut ExecutionReport,
+    ) -> Result<Option<SelectionVector>> {
+        if plan.limit == Some(0) {
+            return Ok(None);
+        }
+        let stopwatch = Stopwatch::start();
+        let Some(top) = materialize::top_k(batch, plan, selection)? else {
+            return Ok(None);
+        };
+        report.push(OperatorReport::simd(
+            "TopK",
+            format!("{} k={}", Self::order_keys(plan), plan.fetch().unwrap_or(selection.len())),
+            selection.len(),
+            top.len(),
+            stopwatch.elapsed_ms(),
+        ));
+        Ok(Some(top))
+    }
+
+    /// Hash-join `probe` (the FROM table's rows) with each joined table in
+    /// turn; returns the joined rows and `plan` bound to their column names
+    fn apply_joins(
+        &self,
+        mut probe: RecordBatch,
+        plan: &QueryPlan,
+        tables: &HashMap<&str, &StorageEngine>,
+        report: &mut ExecutionReport,
+    ) -> Result<(RecordBatch, QueryPlan)> {
+        let qualifier = plan.table_alias.as_deref().unwrap_or(&plan.table);
+        let mut columns = JoinedColumns::new(qualifier, &probe.schema());
+
+        for join in &plan.joins {
+            let storage = tables
+                .get(join.table.as_str())
+                .ok_or_else(|| Error::InvalidInput(format!("Table not found: {}", join.table)))?;
+            let stopwatch = Stopwatch::start();
+            let build = decode_dictionaries(&Self::scan(storage)?)?;
+            report.rows_scanned += build.num_rows();
+
+            let qualifier = join.alias.as_deref().unwrap_or(&join.table);
+            let build_columns = JoinedColumns::new(qualifier, &build.schema());
+            let mut probe_keys = Vec::with_capacity(join.on.len());
+            let mut build_keys = Vec::with_capacity(join.on.len());
+            for (left, right) in &join.on {
+                probe_keys.push(Arc::clone(probe.column(columns.require(left)?)));
+                build_keys.push(Arc::clone(build.column(build_columns.require(right)?)));
+            }
+
+            let (probe_indices, build_indices) = join::hash_join(&probe_keys, &build_keys)?;
+            let probe_rows = probe.num_rows();
+            probe = join::gather(&probe, &build, &probe_indices, &build_indices)?;
+            columns.extend(qualifier, &build.schema());
+
+            let on: Vec<String> =
+                join.on.iter().map(|(left, right)| format!("{left} = {right}")).collect();
+            let total_bytes = storage.memory_size();
+            let planned = self.join_backend(probe_rows, build.num_rows(), total_bytes);
+            report.push(
+                OperatorReport::simd(
+                    "HashJoin",
+                    format!("{} ON {}", join.table, on.join(" AND ")),
+                    probe_rows + build.num_rows(),
+                    probe.num_rows(),
+                    stopwatch.elapsed_ms(),
+                )
+                .planned(planned, total_bytes),
+            );
+        }
+
+        Ok((columns.rename(&probe)?, columns.bind(plan)?))
+    }
+
+    /// Backend the cost model picks for a hash join
+    ///
+    /// With cost-based selection this applies
+    /// [`BackendDispatcher::estimate_join_flops`] to the input sizes; a
+    /// forced backend is returned as is. There is no GPU join kernel yet, so
+    /// joins execute on the CPU whichever backend is picked.
+    #[must_use]
+    pub fn join_backend(
+        &self,
+        probe_rows: usize,
+        build_rows: usize,
+        total_bytes: usize,
+    ) -> Backend {
+        match self.backend {
+            Backend::CostBased => BackendDispatcher::select_with(
+                &self.dispatch,
+                total_bytes,
+                BackendDispatcher::estimate_join_flops(build_rows, probe_rows),
+            ),
+            forced => forced,
+        }
+    }
+
+    /// Backend the cost model picks for a hash GROUP BY over `rows` rows
+    ///
+    /// With cost-based selection this applies
+    /// [`BackendDispatcher::estimate_group_by_flops`]; a forced backend is
+    /// returned as is. The executor aggregates on the CPU; callers holding a
+    /// GPU engine run `GpuEngine::group_by_i32` (`gpu` feature) when this
+    /// picks [`Backend::Gpu`], or `GpuEngine::group_by_top_k_i32` for
+    /// `ORDER BY <aggregate> LIMIT k`, which reads back only the K groups.
+    #[must_use]
+    pub fn group_by_backend(&self, rows: usize, total_bytes: usize) -> Backend {
+        match self.backend {
+            Backend::CostBased => BackendDispatcher::select_with(
+                &self.dispatch,
+                total_bytes,
+                BackendDispatcher::estimate_group_by_flops(rows),
+            ),
+            forced => forced,
+        }
+    }
+
+    /// All rows of `storage` as one batch
+    fn scan(storage: &StorageEngine) -> Result<RecordBatch> {
         // Get all batches from storage
         let batches = storage.batches();
         if batches.is_empty() {
@@ -84,245 +1588,195 @@ impl QueryExecutor {
         }
 
         // Combine batches (Phase 1: single table only)
-        let combined = Self::combine_batches(batches)?;
+        Self::combine_batches(batches)
+    }
 
-        // Apply WHERE filter
-        let filtered = if let Some(ref filter_expr) = plan.filter {
-            Self::apply_filter(&combined, filter_expr)?
-        } else {
-            combined
-        };
+    /// Backend the cost model picks for an ungrouped aggregate over `rows` rows
+    ///
+    /// With cost-based selection this applies
+    /// [`BackendDispatcher::estimate_simple_aggregation_flops`]; a forced
+    /// backend is returned as is. When this picks [`Backend::Gpu`], the
+    /// fallback executor's GPU tier computes all aggregates of a column with
+    /// one `GpuEngine::multi_aggregate` pass (`gpu` feature).
+    #[must_use]
+    pub fn aggregate_backend(&self, rows: usize, total_bytes: usize) -> Backend {
+        match self.backend {
+            Backend::CostBased => BackendDispatcher::select_with(
+                &self.dispatch,
+                total_bytes,
+                BackendDispatcher::estimate_simple_aggregation_flops(rows),
+            ),
+            forced => forced,
+        }
+    }
 
-        // Execute aggregations if present
-        let result = if plan.aggregations.is_empty() {
+    /// Aggregate/project, then ORDER BY + LIMIT, over already filtered rows
+    fn finish_plan(
+        &self,
+        filtered: &RecordBatch,
+        plan: &QueryPlan,
+        report: &mut ExecutionReport,
+    ) -> Result<RecordBatch> {
+        let stopwatch = Stopwatch::start();
+        let rows = filtered.num_rows();
+        // Execute aggregations if present (GROUP BY alone yields distinct keys)
+        let result = if plan.aggregations.is_empty() && plan.group_by.is_empty() {
             // Project columns
-            Self::project_columns(&filtered, &plan.columns)?
+            let result = Self::project_columns(filtered, plan)?;
+            report.push(OperatorReport::simd(
+                "Project",
+                plan.columns.join(", "),
+                rows,
+                result.num_rows(),
+                stopwatch.elapsed_ms(),
+            ));
+            result
         } else {
-            Self::execute_aggregations(&filtered, plan)?
+            let result = Self::execute_aggregations(filtered, plan)?;
+            let aggregates = Self::aggregate_list(plan);
+            // As in EXPLAIN: only key and aggregate input columns are shipped
+            let total_bytes = rows * (plan.group_by.len() + plan.aggregations.len()) * 8;
+            let operator = if plan.group_by.is_empty() {
+                OperatorReport::simd(
+                    "Aggregate",
+                    aggregates,
+                    rows,
+                    result.num_rows(),
+                    stopwatch.elapsed_ms(),
+                )
+                .planned(self.aggregate_backend(rows, total_bytes), total_bytes)
+            } else {
+                OperatorReport::simd(
+                    "HashAggregate",
+                    format!("{aggregates} GROUP BY {}", plan.group_by.join(", ")),
+                    rows,
+                    result.num_rows(),
+                    stopwatch.elapsed_ms(),
+                )
+                .planned(self.group_by_backend(rows, total_bytes), total_bytes)
+            };
+            report.push(operator);
+            result
         };
 
-        // Apply ORDER BY + LIMIT (Top-K optimization)
-        let result = if !plan.order_by.is_empty() {
-            Self::apply_order_by_limit(&result, plan)?
-        } else if let Some(limit) = plan.limit {
-            // LIMIT without ORDER BY: just slice
-            result.slice(0, limit.min(result.num_rows()))
+        Self::order_and_limit(result, plan, report)
+    }
+
+    /// Apply ORDER BY + LIMIT (Top-K optimization) and OFFSET to aggregated/projected rows
+    pub(super) fn order_and_limit(
+        result: RecordBatch,
+        plan: &QueryPlan,
+        report: &mut ExecutionReport,
+    ) -> Result<RecordBatch> {
+        let stopwatch = Stopwatch::start();
+        let rows = result.num_rows();
+        let (operator, detail, output) = if !plan.order_by.is_empty() {
+            let k = plan.fetch().unwrap_or(rows);
+            let detail = format!("{} k={k}{}", Self::order_keys(plan), Self::offset_detail(plan));
+            ("TopK", detail, Self::apply_order_by_limit(&result, plan)?)
+        } else if plan.limit.is_some() || plan.offset > 0 {
+            // LIMIT/OFFSET without ORDER BY: just slice
+            let (offset, len) = Self::page(rows, plan);
+            ("Limit", Self::limit_detail(plan), result.slice(offset, len))
         } else {
-            result
+            return Ok(result);
         };
-
-        Ok(result)
+        report.push(OperatorReport::simd(
+            operator,
+            detail,
+            rows,
+            output.num_rows(),
+            stopwatch.elapsed_ms(),
+        ));
+        Ok(output)
     }
 
-    /// Combine multiple batches into single batch
-    fn combine_batches(batches: &[RecordBatch]) -> Result<RecordBatch> {
-        if batches.len() == 1 {
-            return Ok(batches[0].clone());
-        }
+    /// Start and length of the rows LIMIT/OFFSET keep out of `rows`
+    const fn page(rows: usize, plan: &QueryPlan) -> (usize, usize) {
+        let offset = if plan.offset < rows { plan.offset } else { rows };
+        let len = match plan.limit {
+            Some(limit) if limit < rows - offset => limit,
+            _ => rows - offset,
+        };
+        (offset, len)
+    }
 
-        // Use Arrow concat
-        compute::concat_batches(&batches[0].schema(), batches)
-            .map_err(|e| Error::StorageError(format!("Failed to combine batches: {e}")))
+    /// LIMIT/OFFSET as shown by EXPLAIN and the execution report (`20 OFFSET 40`)
+    fn limit_detail(plan: &QueryPlan) -> String {
+        let offset = Self::offset_detail(plan);
+        plan.limit.map_or_else(|| format!("ALL{offset}"), |limit| format!("{limit}{offset}"))
     }
 
-    /// Apply WHERE filter
-    fn apply_filter(batch: &RecordBatch, filter_expr: &str) -> Result<RecordBatch> {
-        // Phase 1: Simple predicates only (column > value, column < value, etc.)
-        // Parse filter expression: "column op value"
-        let parts: Vec<&str> = filter_expr.split_whitespace().collect();
-        if parts.len() < 3 {
-            return Err(Error::ParseError(format!("Invalid filter expression: {filter_expr}")));
+    /// ` OFFSET n` suffix of Top-K and Limit details (empty without OFFSET)
+    fn offset_detail(plan: &QueryPlan) -> String {
+        if plan.offset == 0 {
+            String::new()
+        } else {
+            format!(" OFFSET {}", plan.offset)
         }
-
-        let column_name = parts[0];
-        let op = parts[1];
-        let value_str = parts.get(2..).unwrap_or(&[]).join(" ");
-
-        // Find column index
-        let schema = batch.schema();
-        let column_index = schema
-            .fields()
-            .iter()
-            .position(|f| f.name() == column_name)
-            .ok_or_else(|| Error::InvalidInput(format!("Column not found: {column_name}")))?;
-
-        let column = batch.column(column_index);
-
-        // Build boolean mask based on data type
-        let mask = match column.data_type() {
-            DataType::Int32 => {
-                let array = column
-                    .as_any()
-                    .downcast_ref::<Int32Array>()
-                    .ok_or_else(|| Error::Other("Failed to downcast to Int32Array".to_string()))?;
-                let value: i32 = value_str
-                    .parse()
-                    .map_err(|_| Error::ParseError(format!("Invalid Int32 value: {value_str}")))?;
-                Self::build_comparison_mask_i32(array, op, value)?
-            }
-            DataType::Int64 => {
-                let array = column
-                    .as_any()
-                    .downcast_ref::<Int64Array>()
-                    .ok_or_else(|| Error::Other("Failed to downcast to Int64Array".to_string()))?;
-                let value: i64 = value_str
-                    .parse()
-                    .map_err(|_| Error::ParseError(format!("Invalid Int64 value: {value_str}")))?;
-                Self::build_comparison_mask_i64(array, op, value)?
-            }
-            DataType::Float32 => {
-                let array = column.as_any().downcast_ref::<Float32Array>().ok_or_else(|| {
-                    Error::Other("Failed to downcast to Float32Array".to_string())
-                })?;
-                let value: f32 = value_str.parse().map_err(|_| {
-                    Error::ParseError(format!("Invalid Float32 value: {value_str}"))
-                })?;
-                Self::build_comparison_mask_f32(array, op, value)?
-            }
-            DataType::Float64 => {
-                let array = column.as_any().downcast_ref::<Float64Array>().ok_or_else(|| {
-                    Error::Other("Failed to downcast to Float64Array".to_string())
-                })?;
-                let value: f64 = value_str.parse().map_err(|_| {
-                    Error::ParseError(format!("Invalid Float64 value: {value_str}"))
-                })?;
-                Self::build_comparison_mask_f64(array, op, value)?
-            }
-            dt => {
-                return Err(Error::InvalidInput(format!(
-                    "Filter not supported for data type: {dt:?}"
-                )))
-            }
-        };
-
-        // Apply filter using Arrow compute
-        compute::filter_record_batch(batch, &mask)
-            .map_err(|e| Error::StorageError(format!("Failed to apply filter: {e}")))
     }
 
-    #[allow(clippy::unnecessary_wraps)]
-    fn build_comparison_mask_i32(
-        array: &Int32Array,
-        op: &str,
-        value: i32,
-    ) -> Result<arrow::array::BooleanArray> {
-        use arrow::array::BooleanArray;
-        let values: Vec<bool> = (0..array.len())
-            .map(|i| {
-                if array.is_null(i) {
-                    false
-                } else {
-                    let v = array.value(i);
-                    match op {
-                        ">" => v > value,
-                        ">=" => v >= value,
-                        "<" => v < value,
-                        "<=" => v <= value,
-                        "=" => v == value,
-                        "!=" | "<>" => v != value,
-                        _ => false,
-                    }
-                }
-            })
+    /// Aggregates as shown by EXPLAIN and the execution report (`SUM(x), COUNT(*)`)
+    fn aggregate_list(plan: &QueryPlan) -> String {
+        let aggregates: Vec<String> = plan
+            .aggregations
+            .iter()
+            .map(|(func, col, _)| format!("{}({col})", func.sql_name()))
             .collect();
-        Ok(BooleanArray::from(values))
+        aggregates.join(", ")
     }
 
-    #[allow(clippy::unnecessary_wraps)]
-    fn build_comparison_mask_i64(
-        array: &Int64Array,
-        op: &str,
-        value: i64,
-    ) -> Result<arrow::array::BooleanArray> {
-        use arrow::array::BooleanArray;
-        let values: Vec<bool> = (0..array.len())
-            .map(|i| {
-                if array.is_null(i) {
-                    false
-                } else {
-                    let v = array.value(i);
-                    match op {
-                        ">" => v > value,
-                        ">=" => v >= value,
-                        "<" => v < value,
-                        "<=" => v <= value,
-                        "=" => v == value,
-                        "!=" | "<>" => v != value,
-                        _ => false,
-                    }
-                }
+    /// ORDER BY keys as shown by EXPLAIN and the execution report (`x DESC, y ASC`)
+    fn order_keys(plan: &QueryPlan) -> String {
+        let keys: Vec<String> = plan
+            .order_by
+            .iter()
+            .map(|(col, direction)| match direction {
+                OrderDirection::Asc => format!("{col} ASC"),
+                OrderDirection::Desc => format!("{col} DESC"),
             })
             .collect();
-        Ok(BooleanArray::from(values))
+        keys.join(", ")
     }
 
-    #[allow(clippy::unnecessary_wraps)]
-    fn build_comparison_mask_f32(
-        array: &Float32Array,
-        op: &str,
-        value: f32,
-    ) -> Result<arrow::array::BooleanArray> {
-        use arrow::array::BooleanArray;
-        let values: Vec<bool> = (0..array.len())
-            .map(|i| {
-                if array.is_null(i) {
-                    false
-                } else {
-                    let v = array.value(i);
-                    match op {
-                        ">" => v > value,
-                        ">=" => v >= value,
-                        "<" => v < value,
-                        "<=" => v <= value,
-                        "=" => (v - value).abs() < f32::EPSILON,
-                        "!=" | "<>" => (v - value).abs() >= f32::EPSILON,
-                        _ => false,
-                    }
-                }
-            })
-            .collect();
-        Ok(BooleanArray::from(values))
+    /// Combine multiple batches into single batch
+    pub(super) fn combine_batches(batches: &[RecordBatch]) -> Result<RecordBatch> {
+        if batches.len() == 1 {
+            return Ok(batches[0].clone());
+        }
+
+        // Use Arrow concat
+        compute::concat_batches(&batches[0].schema(), batches)
+            .map_err(|e| Error::StorageError(format!("Failed to combine batches: {e}")))
     }
 
-    #[allow(clippy::unnecessary_wraps)]
-    fn build_comparison_mask_f64(
-        array: &Float64Array,
-        op: &str,
-        value: f64,
-    ) -> Result<arrow::array::BooleanArray> {
-        use arrow::array::BooleanArray;
-        let values: Vec<bool> = (0..array.len())
-            .map(|i| {
-                if array.is_null(i) {
-                    false
-                } else {
-                    let v = array.value(i);
-                    match op {
-                        ">" => v > value,
-                        ">=" => v >= value,
-                        "<" => v < value,
-                        "<=" => v <= value,
-                        "=" => (v - value).abs() < f64::EPSILON,
-                        "!=" | "<>" => (v - value).abs() >= f64::EPSILON,
-                        _ => false,
-                    }
-                }
-            })
-            .collect();
-        Ok(BooleanArray::from(values))
+    /// Apply WHERE filter (see [`predicate`](super::predicate) for the supported
+    /// expressions)
+    pub(super) fn apply_filter(batch: &RecordBatch, filter_expr: &str) -> Result<RecordBatch> {
+        SelectionVector::filter(batch, filter_expr)?.gather(batch)
     }
 
-    /// Project columns from batch
-    fn project_columns(batch: &RecordBatch, columns: &[String]) -> Result<RecordBatch> {
+    /// Project the plan's columns from batch, evaluating computed ones
+    /// (dictionary-encoded strings are decoded)
+    pub(super) fn project_columns(batch: &RecordBatch, plan: &QueryPlan) -> Result<RecordBatch> {
+        let columns = &plan.columns;
         if columns.len() == 1 && columns[0] == "*" {
-            return Ok(batch.clone());
+            return decode_dictionaries(batch);
         }
 
         let schema = batch.schema();
         let mut new_columns = Vec::new();
         let mut new_fields = Vec::new();
+        // Computed items share repeated subexpressions
+        let mut subexpressions = Subexpressions::default();
 
         for col_name in columns {
+            if let Some((_, expr)) = plan.computed.iter().find(|(name, _)| name == col_name) {
+                let column = subexpressions.evaluate(batch, expr)?;
+                new_fields.push(Field::new(col_name, column.data_type().clone(), true));
+                new_columns.push(column);
+                continue;
+            }
             let index = schema
                 .fields()
                 .iter()
@@ -334,41 +1788,233 @@ impl QueryExecutor {
         }
 
         let new_schema = Arc::new(Schema::new(new_fields));
-        RecordBatch::try_new(new_schema, new_columns)
-            .map_err(|e| Error::StorageError(format!("Failed to project columns: {e}")))
+        let projected = RecordBatch::try_new(new_schema, new_columns)
+            .map_err(|e| Error::StorageError(format!("Failed to project columns: {e}")))?;
+        decode_dictionaries(&projected)
     }
 
     /// Execute aggregations
     fn execute_aggregations(batch: &RecordBatch, plan: &QueryPlan) -> Result<RecordBatch> {
-        // Phase 1: Simple aggregations without GROUP BY
+        let batch = &scalar::with_computed_inputs(batch, plan)?;
         if !plan.group_by.is_empty() {
-            return Err(Error::InvalidInput(
-                "GROUP BY aggregations not yet implemented in Phase 1".to_string(),
-            ));
+            return Self::execute_group_by(batch, plan);
         }
 
         let mut result_columns: Vec<ArrayRef> = Vec::new();
         let mut result_fields: Vec<Field> = Vec::new();
+        // Repeated aggregates share one slot and run once (the optimizer's
+        // `ShareAggregate` rewrite)
+        let (distinct, slots) = distinct_aggregates(plan);
+        let values = distinct
+            .iter()
+            .map(|&(func, col_name)| {
+                Self::execute_single_aggregation(func, &Self::aggregate_column(batch, col_name)?)
+            })
+            .collect::<Result<Vec<_>>>()?;
 
-        for (agg_func, col_name, alias) in &plan.aggregations {
+        for ((_, col_name, alias), &slot) in plan.aggregations.iter().zip(&slots) {
             let result_name = alias.as_deref().unwrap_or(col_name);
+            let (result_value, result_type) = values[slot].clone();
 
-            // Find column
-            let schema = batch.schema();
-            let col_index = schema
-                .fields()
-                .iter()
-                .position(|f| f.name() == col_name || col_name == "*")
-                .ok_or_else(|| Error::InvalidInput(format!("Column not found: {col_name}")))?;
+            let nullable = result_value.null_count() > 0;
+            result_columns.push(result_value);
+            result_fields.push(Field::new(result_name, result_type, nullable));
+        }
+
+        let result_schema = Arc::new(Schema::new(result_fields));
+        RecordBatch::try_new(result_schema, result_columns)
+            .map_err(|e| Error::StorageError(format!("Failed to create result batch: {e}")))
+    }
+
+    /// Whether the GPU tier runs `plan`'s aggregates over `batches` with
+    /// [`GpuEngine::multi_aggregate`](crate::gpu::GpuEngine::multi_aggregate)
+    ///
+    /// It takes ungrouped plans without JOINs or CTEs whose aggregates are
+    /// all SUM, AVG, COUNT, MIN or MAX over NULL-free `Int32`, `Int64`,
+    /// `UInt32` or `UInt64` columns.
+    #[cfg(feature = "gpu")]
+    pub(super) fn gpu_aggregates_apply(plan: &QueryPlan, batches: &[RecordBatch]) -> bool {
+        let Some(first) = batches.first() else {
+            return false;
+        };
+        if plan.explain
+            || plan.aggregations.is_empty()
+            || !plan.group_by.is_empty()
+            || !plan.joins.is_empty()
+            || !plan.ctes.is_empty()
+            || !plan.subqueries.is_empty()
+        {
+            return false;
+        }
+        plan.aggregations.iter().all(|(func, col_name, _)| {
+            crate::gpu::multi_aggregate_supports(*func)
+                && Self::aggregate_input(first, col_name).is_ok_and(|index| {
+                    crate::gpu::multi_aggregate_reads(first.column(index).data_type())
+                        && batches.iter().all(|batch| batch.column(index).null_count() == 0)
+                })
+        })
+    }
+
+    /// Execute ungrouped aggregations on the GPU
+    ///
+    /// Filters on the host, then computes every aggregate reading the same
+    /// column in one `multi_aggregate` pass (one dispatch and one readback
+    /// instead of one per aggregate). The result matches
+    /// [`Self::execute_aggregations`]; check [`Self::gpu_aggregates_apply`]
+    /// first.
+    #[cfg(feature = "gpu")]
+    pub(super) fn execute_gpu_aggregations(
+        engine: &crate::gpu::GpuEngine,
+        batch: &RecordBatch,
+        plan: &QueryPlan,
+    ) -> Result<RecordBatch> {
+        crate::gpu::block_on(Self::gpu_aggregations(engine, batch, plan))
+    }
+
+    /// Run `plan` on `engine` without blocking, if a GPU kernel applies
+    ///
+    /// Takes the plans [`FallbackExecutor`](super::FallbackExecutor) sends
+    /// to `multi_aggregate` (ungrouped SUM/AVG/COUNT/MIN/MAX over NULL-free
+    /// integer columns) and returns `None` for the rest, which the caller
+    /// runs with [`execute`](Self::execute). This is the GPU path on
+    /// `wasm32`, where the blocking tiers cannot wait on WebGPU.
+    ///
+    /// # Errors
+    /// Returns error if the filter, a kernel, or ORDER BY/LIMIT fails
+    #[cfg(feature = "gpu")]
+    pub async fn execute_on_gpu(
+        &self,
+        engine: &crate::gpu::GpuEngine,
+        plan: &QueryPlan,
+        storage: &StorageEngine,
+    ) -> Result<Option<RecordBatch>> {
+        if !Self::gpu_aggregates_apply(plan, storage.batches()) {
+            return Ok(None);
+        }
+        let batch = Self::combine_batches(storage.batches())?;
+        let aggregated = Self::gpu_aggregations(engine, &batch, plan).await?;
+        Self::order_and_limit(aggregated, plan, &mut ExecutionReport::new()).map(Some)
+    }
+
+    #[cfg(feature = "gpu")]
+    async fn gpu_aggregations(
+        engine: &crate::gpu::GpuEngine,
+        batch: &RecordBatch,
+        plan: &QueryPlan,
+    ) -> Result<RecordBatch> {
+        let filtered = match &plan.filter {
+            Some(filter_expr) => Self::apply_filter(batch, filter_expr)?,
+            None => batch.clone(),
+        };
 
-            let column = batch.column(col_index);
+        // Distinct aggregates (see `distinct_aggregates`) per input column,
+        // columns in order of first use
+        let (distinct, slots) = distinct_aggregates(plan);
+        let mut inputs: Vec<(usize, Vec<AggregateFunction>)> = Vec::new();
+        let mut positions = Vec::with_capacity(distinct.len());
+        for (agg_func, col_name) in distinct {
+            let col_index = Self::aggregate_input(&filtered, col_name)?;
+            let input =
+                inputs.iter().position(|(index, _)| *index == col_index).unwrap_or_else(|| {
+                    inputs.push((col_index, Vec::new()));
+                    inputs.len() - 1
+                });
+            inputs[input].1.push(agg_func);
+            positions.push((input, inputs[input].1.len() - 1));
+        }
+
+        let mut computed = Vec::with_capacity(inputs.len());
+        for (col_index, functions) in &inputs {
+            let column = filtered.column(*col_index).as_ref();
+            computed.push(engine.multi_aggregate(column, functions).await?);
+        }
+
+        let mut result_columns: Vec<ArrayRef> = Vec::new();
+        let mut result_fields: Vec<Field> = Vec::new();
+        for ((_, col_name, alias), &slot) in plan.aggregations.iter().zip(&slots) {
+            let (input, function) = positions[slot];
+            let column = Arc::clone(&computed[input][function]);
+            let result_name = alias.as_deref().unwrap_or(col_name);
+            result_fields.push(Field::new(result_name, column.data_type().clone(), false));
+            result_columns.push(column);
+        }
+
+        let result_schema = Arc::new(Schema::new(result_fields));
+        RecordBatch::try_new(result_schema, result_columns)
+            .map_err(|e| Error::StorageError(format!("Failed to create result batch: {e}")))
+    }
+
+    /// Index of the column an aggregate reads (`*`, as in `COUNT(*)`, reads the first column)
+    pub(super) fn aggregate_input(batch: &RecordBatch, col_name: &str) -> Result<usize> {
+        batch
+            .schema()
+            .fields()
+            .iter()
+            .position(|f| f.name() == col_name || col_name == "*")
+            .ok_or_else(|| Error::InvalidInput(format!("Column not found: {col_name}")))
+    }
+
+    /// Column an aggregate reads
+    ///
+    /// `*` (as in `COUNT(*)`) reads a NULL-free Boolean stand-in, so COUNT,
+    /// which skips NULLs, counts every row.
+    pub(super) fn aggregate_column(batch: &RecordBatch, col_name: &str) -> Result<ArrayRef> {
+        if col_name == "*" {
+            let rows = BooleanBuffer::new_set(batch.num_rows());
+            return Ok(Arc::new(BooleanArray::new(rows, None)));
+        }
+        Ok(Arc::clone(batch.column(Self::aggregate_input(batch, col_name)?)))
+    }
+
+    /// Execute a grouped aggregation (hash GROUP BY)
+    ///
+    /// Output: the GROUP BY key columns (in GROUP BY order, dictionary keys
+    /// decoded) followed by one column per aggregate, one row per group in
+    /// order of first appearance. Each aggregate runs the same kernel as
+    /// the ungrouped path on every group, so result types match. Each input
+    /// column is partitioned once however many aggregates read it, and
+    /// repeated aggregates are computed once.
+    fn execute_group_by(batch: &RecordBatch, plan: &QueryPlan) -> Result<RecordBatch> {
+        // Dictionary keys are grouped encoded; only one value per group is decoded
+        let schema = logical_schema(&batch.schema());
+
+        let mut key_columns = Vec::with_capacity(plan.group_by.len());
+        let mut result_fields: Vec<Field> = Vec::new();
+        for key in &plan.group_by {
+            let index = schema
+                .index_of(key)
+                .map_err(|_| Error::InvalidInput(format!("GROUP BY column not found: {key}")))?;
+            key_columns.push(Arc::clone(batch.column(index)));
+            result_fields.push(schema.field(index).clone());
+        }
+
+        let groups = Groups::build(&key_columns)?;
+        let mut result_columns = key_columns
+            .iter()
+            .map(|key| decode_column(&groups.keys(key)?))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Each input column is partitioned once, and repeated aggregates
+        // share one slot (the optimizer's `ShareAggregate` rewrite)
+        let mut partitions: HashMap<&str, Vec<ArrayRef>> = HashMap::new();
+        let (distinct, slots) = distinct_aggregates(plan);
+        let mut values = Vec::with_capacity(distinct.len());
+        for (func, col_name) in distinct {
+            let column = Self::aggregate_column(batch, col_name)?;
+            let parts = match partitions.entry(col_name) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(groups.partition(&column)?),
+            };
+            values.push(Self::aggregate_groups(func, &column, parts)?);
+        }
 
-            // Execute aggregation
-            let (result_value, result_type) =
-                Self::execute_single_aggregation(*agg_func, column, batch.num_rows())?;
+        for ((_, col_name, alias), &slot) in plan.aggregations.iter().zip(&slots) {
+            let result_name = alias.as_deref().unwrap_or(col_name);
+            let result_value = Arc::clone(&values[slot]);
 
+            let nullable = result_value.null_count() > 0;
+            result_fields.push(Field::new(result_name, result_value.data_type().clone(), nullable));
             result_columns.push(result_value);
-            result_fields.push(Field::new(result_name, result_type, false));
         }
 
         let result_schema = Arc::new(Schema::new(result_fields));
@@ -376,39 +2022,95 @@ impl QueryExecutor {
             .map_err(|e| Error::StorageError(format!("Failed to create result batch: {e}")))
     }
 
+    /// Run one aggregate over each group's slice of `column` (one row per group)
+    fn aggregate_groups(
+        func: AggregateFunction,
+        column: &ArrayRef,
+        parts: &[ArrayRef],
+    ) -> Result<ArrayRef> {
+        if parts.is_empty() {
+            // No groups: run once on no rows just to get an empty column of the right type
+            let (value, _) = Self::execute_single_aggregation(func, &column.slice(0, 0))?;
+            return Ok(value.slice(0, 0));
+        }
+
+        let per_group = parts
+            .iter()
+            .map(|part| Self::execute_single_aggregation(func, part))
+            .map(|result| result.map(|(value, _)| value))
+            .collect::<Result<Vec<_>>>()?;
+        let per_group: Vec<&dyn Array> = per_group.iter().map(AsRef::as_ref).collect();
+        compute::concat(&per_group)
+            .map_err(|e| Error::Other(format!("Failed to combine groups: {e}")))
+    }
+
     /// Execute single aggregation function
-    fn execute_single_aggregation(
+    ///
+    /// NULLs are skipped by every aggregate: COUNT counts non-NULL values
+    /// (of any type), and SUM, AVG, MIN, MAX and the variance family run
+    /// over the non-NULL values only. Without any, SUM is 0 and AVG, MIN,
+    /// MAX and the variance family are NULL. Approximate aggregates read the
+    /// estimate off a sketch of the column; exact percentiles are NULL
+    /// without values.
+    pub(crate) fn execute_single_aggregation(
         func: AggregateFunction,
         column: &ArrayRef,
-        num_rows: usize,
     ) -> Result<(ArrayRef, DataType)> {
+        if func == AggregateFunction::CountIf && column.data_type() != &DataType::Boolean {
+            return Err(Self::count_if_unsupported(column.data_type()));
+        }
+        if let Some(sketch) = Sketch::of(func, column)? {
+            return Ok(sketch.finish());
+        }
+        if let AggregateFunction::PercentileCont(p) = func {
+            let value = topk::percentile_cont(column, p.fraction())?;
+            return Ok((Arc::new(Float64Array::from(vec![value])), DataType::Float64));
+        }
+        let non_null = column.len() - column.logical_null_count();
+
         match column.data_type() {
+            DataType::Boolean => {
+                let array = column.as_boolean_opt().ok_or_else(|| {
+                    Error::Other("Failed to downcast to BooleanArray".to_string())
+                })?;
+                Ok(Self::aggregate_bool(func, array, non_null))
+            }
             DataType::Int32 => {
                 let array = column
                     .as_any()
                     .downcast_ref::<Int32Array>()
                     .ok_or_else(|| Error::Other("Failed to downcast to Int32Array".to_string()))?;
-                Self::aggregate_i32(func, array, num_rows)
+                Self::aggregate_i32(func, array, non_null)
             }
             DataType::Int64 => {
                 let array = column
                     .as_any()
                     .downcast_ref::<Int64Array>()
                     .ok_or_else(|| Error::Other("Failed to downcast to Int64Array".to_string()))?;
-                Self::aggregate_i64(func, array, num_rows)
+                Self::aggregate_i64(func, array, non_null)
             }
             DataType::Float32 => {
                 let array = column.as_any().downcast_ref::<Float32Array>().ok_or_else(|| {
                     Error::Other("Failed to downcast to Float32Array".to_string())
                 })?;
-                Self::aggregate_f32(func, array, num_rows)
+                Self::aggregate_f32(func, array, non_null)
             }
             DataType::Float64 => {
                 let array = column.as_any().downcast_ref::<Float64Array>().ok_or_else(|| {
                     Error::Other("Failed to downcast to Float64Array".to_string())
                 })?;
-                Self::aggregate_f64(func, array, num_rows)
+                Self::aggregate_f64(func, array, non_null)
             }
+            DataType::Int8 => Self::aggregate_int::<Int8Type>(func, column, non_null),
+            DataType::Int16 => Self::aggregate_int::<Int16Type>(func, column, non_null),
+            DataType::UInt8 => Self::aggregate_int::<UInt8Type>(func, column, non_null),
+            DataType::UInt16 => Self::aggregate_int::<UInt16Type>(func, column, non_null),
+            DataType::UInt32 => Self::aggregate_int::<UInt32Type>(func, column, non_null),
+            DataType::UInt64 => Self::aggregate_int::<UInt64Type>(func, column, non_null),
+            DataType::Date32 | DataType::Date64 | DataType::Timestamp(..) => {
+                Self::aggregate_temporal(func, column, non_null)
+            }
+            _ if func == AggregateFunction::Count => Ok(Self::count(non_null)),
             dt => {
                 Err(Error::InvalidInput(format!("Aggregation not supported for data type: {dt:?}")))
             }
@@ -419,7 +2121,7 @@ impl QueryExecutor {
     fn aggregate_i32(
         func: AggregateFunction,
         array: &Int32Array,
-        num_rows: usize,
+        non_null: usize,
     ) -> Result<(ArrayRef, DataType)> {
         match func {
             AggregateFunction::Sum => {
@@ -435,36 +2137,203 @@ impl QueryExecutor {
                     .map(|i| f64::from(array.value(i)))
                     .sum();
                 let count = (0..array.len()).filter(|&i| !array.is_null(i)).count();
-                let avg = if count > 0 { sum / count as f64 } else { 0.0 };
+                let avg = (count > 0).then(|| sum / count as f64);
                 Ok((Arc::new(Float64Array::from(vec![avg])), DataType::Float64))
             }
-            AggregateFunction::Count => {
-                Ok((Arc::new(Int64Array::from(vec![num_rows as i64])), DataType::Int64))
+            AggregateFunction::CountIf => Err(Self::count_if_unsupported(&DataType::Int32)),
+            AggregateFunction::ApproxCountDistinct
+            | AggregateFunction::ApproxQuantile(_)
+            | AggregateFunction::PercentileCont(_) => {
+                unreachable!("sketches and percentiles are computed before type dispatch")
             }
+            AggregateFunction::VarSamp
+            | AggregateFunction::VarPop
+            | AggregateFunction::StddevSamp
+            | AggregateFunction::StddevPop => Ok(Self::aggregate_variance(
+                func,
+                &array.iter().flatten().map(f64::from).collect::<Vec<_>>(),
+            )),
+            AggregateFunction::Count => Ok(Self::count(non_null)),
             AggregateFunction::Min => {
-                let min = (0..array.len())
-                    .filter(|&i| !array.is_null(i))
-                    .map(|i| array.value(i))
-                    .min()
-                    .unwrap_or(0);
+                let min =
+                    (0..array.len()).filter(|&i| !array.is_null(i)).map(|i| array.value(i)).min();
                 Ok((Arc::new(Int32Array::from(vec![min])), DataType::Int32))
             }
             AggregateFunction::Max => {
-                let max = (0..array.len())
-                    .filter(|&i| !array.is_null(i))
-                    .map(|i| array.value(i))
-                    .max()
-                    .unwrap_or(0);
+                let max =
+                    (0..array.len()).filter(|&i| !array.is_null(i)).map(|i| array.value(i)).max();
                 Ok((Arc::new(Int32Array::from(vec![max])), DataType::Int32))
             }
         }
     }
 
+    /// Aggregate Boolean columns
+    ///
+    /// SUM and `COUNT_IF` count true values, AVG is the fraction of true
+    /// values, MIN/MAX behave as logical AND/OR (NULL without values).
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_wrap)]
+    fn aggregate_bool(
+        func: AggregateFunction,
+        array: &BooleanArray,
+        non_null: usize,
+    ) -> (ArrayRef, DataType) {
+        let true_count = array.true_count() as i64;
+        match func {
+            AggregateFunction::Sum | AggregateFunction::CountIf => {
+                (Arc::new(Int64Array::from(vec![true_count])), DataType::Int64)
+            }
+            AggregateFunction::Avg => {
+                let count = array.len() - array.null_count();
+                let avg = (count > 0).then(|| true_count as f64 / count as f64);
+                (Arc::new(Float64Array::from(vec![avg])), DataType::Float64)
+            }
+            AggregateFunction::Count => Self::count(non_null),
+            AggregateFunction::ApproxCountDistinct
+            | AggregateFunction::ApproxQuantile(_)
+            | AggregateFunction::PercentileCont(_) => {
+                unreachable!("sketches and percentiles are computed before type dispatch")
+            }
+            AggregateFunction::VarSamp
+            | AggregateFunction::VarPop
+            | AggregateFunction::StddevSamp
+            | AggregateFunction::StddevPop => {
+                let values: Vec<f64> =
+                    array.iter().flatten().map(|b| f64::from(u8::from(b))).collect();
+                Self::aggregate_variance(func, &values)
+            }
+            AggregateFunction::Min => {
+                let min = array.iter().flatten().min();
+                (Arc::new(BooleanArray::from(vec![min])), DataType::Boolean)
+            }
+            AggregateFunction::Max => {
+                let max = array.iter().flatten().max();
+                (Arc::new(BooleanArray::from(vec![max])), DataType::Boolean)
+            }
+        }
+    }
+
+    /// Aggregate date and timestamp columns
+    ///
+    /// MIN/MAX run over the stored integers and keep the input type (time
+    /// zone included); COUNT counts rows. Instants cannot be added, so SUM,
+    /// AVG and the variance family are rejected.
+    #[allow(clippy::cast_possible_wrap)]
+    fn aggregate_temporal(
+        func: AggregateFunction,
+        column: &ArrayRef,
+        non_null: usize,
+    ) -> Result<(ArrayRef, DataType)> {
+        let data_type = column.data_type();
+        match func {
+            AggregateFunction::Min | AggregateFunction::Max => {
+                let stored =
+                    if data_type == &DataType::Date32 { DataType::Int32 } else { DataType::Int64 };
+                let (value, _) =
+                    Self::execute_single_aggregation(func, &compute::cast(column, &stored)?)?;
+                Ok((compute::cast(&value, data_type)?, data_type.clone()))
+            }
+            AggregateFunction::Count => Ok(Self::count(non_null)),
+            _ => Err(Error::InvalidInput(format!(
+                "{} not supported for data type: {data_type:?}",
+                func.sql_name()
+            ))),
+        }
+    }
+
+    /// COUNT result over `non_null` values
+    #[allow(clippy::cast_possible_wrap)]
+    fn count(non_null: usize) -> (ArrayRef, DataType) {
+        (Arc::new(Int64Array::from(vec![non_null as i64])), DataType::Int64)
+    }
+
+    /// Variance/stddev of non-null values via SIMD Welford (NULL when undefined:
+    /// no values, or a single value for the sample estimators)
+    fn aggregate_variance(func: AggregateFunction, values: &[f64]) -> (ArrayRef, DataType) {
+        let kind = func.variance_kind().unwrap_or(VarianceKind::Sample);
+        let state = welford_simd(values);
+        let value = match func {
+            AggregateFunction::StddevSamp | AggregateFunction::StddevPop => state.stddev(kind),
+            _ => state.variance(kind),
+        };
+        (Arc::new(Float64Array::from(vec![value])), DataType::Float64)
+    }
+
+    fn count_if_unsupported(data_type: &DataType) -> Error {
+        Error::InvalidInput(format!("COUNT_IF requires a Boolean column, got {data_type:?}"))
+    }
+
+    fn downcast_primitive<T: ArrowPrimitiveType>(column: &ArrayRef) -> Result<&PrimitiveArray<T>> {
+        column
+            .as_primitive_opt::<T>()
+            .ok_or_else(|| Error::Other(format!("Failed to downcast to {:?} array", T::DATA_TYPE)))
+    }
+
+    /// Aggregate narrow/unsigned integer columns with widening accumulation
+    ///
+    /// SUM accumulates in `i128` and returns `Int64` (signed input) or
+    /// `UInt64` (unsigned input); MIN/MAX keep the input type. MIN, MAX and
+    /// AVG are NULL without values.
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_wrap)]
+    fn aggregate_int<T>(
+        func: AggregateFunction,
+        column: &ArrayRef,
+        non_null: usize,
+    ) -> Result<(ArrayRef, DataType)>
+    where
+        T: ArrowPrimitiveType,
+        T::Native: Into<i128> + Ord,
+    {
+        let array = Self::downcast_primitive::<T>(column)?;
+        let values = || array.iter().flatten();
+
+        match func {
+            AggregateFunction::Sum => {
+                let sum: i128 = values().map(Into::<i128>::into).sum();
+                let overflow = || Error::InvalidInput(format!("SUM overflow: {sum}"));
+                if T::DATA_TYPE.is_signed_integer() {
+                    let sum = i64::try_from(sum).map_err(|_| overflow())?;
+                    Ok((Arc::new(Int64Array::from(vec![sum])), DataType::Int64))
+                } else {
+                    let sum = u64::try_from(sum).map_err(|_| overflow())?;
+                    Ok((Arc::new(UInt64Array::from(vec![sum])), DataType::UInt64))
+                }
+            }
+            AggregateFunction::Avg => {
+                let sum: i128 = values().map(Into::<i128>::into).sum();
+                let count = values().count();
+                let avg = (count > 0).then(|| sum as f64 / count as f64);
+                Ok((Arc::new(Float64Array::from(vec![avg])), DataType::Float64))
+            }
+            AggregateFunction::CountIf => Err(Self::count_if_unsupported(&T::DATA_TYPE)),
+            AggregateFunction::ApproxCountDistinct
+            | AggregateFunction::ApproxQuantile(_)
+            | AggregateFunction::PercentileCont(_) => {
+                unreachable!("sketches and percentiles are computed before type dispatch")
+            }
+            AggregateFunction::VarSamp
+            | AggregateFunction::VarPop
+            | AggregateFunction::StddevSamp
+            | AggregateFunction::StddevPop => Ok(Self::aggregate_variance(
+                func,
+                &values().map(|v| Into::<i128>::into(v) as f64).collect::<Vec<_>>(),
+            )),
+            AggregateFunction::Count => Ok(Self::count(non_null)),
+            AggregateFunction::Min => {
+                let min = compute::min(array);
+                Ok((Arc::new(PrimitiveArray::<T>::from_iter([min])), T::DATA_TYPE))
+            }
+            AggregateFunction::Max => {
+                let max = compute::max(array);
+                Ok((Arc::new(PrimitiveArray::<T>::from_iter([max])), T::DATA_TYPE))
+            }
+        }
+    }
+
     #[allow(clippy::cast_precision_loss, clippy::cast_possible_wrap, clippy::unnecessary_wraps)]
     fn aggregate_i64(
         func: AggregateFunction,
         array: &Int64Array,
-        num_rows: usize,
+        non_null: usize,
     ) -> Result<(ArrayRef, DataType)> {
         match func {
             AggregateFunction::Sum => {
@@ -478,26 +2347,31 @@ impl QueryExecutor {
                     .map(|i| array.value(i) as f64)
                     .sum();
                 let count = (0..array.len()).filter(|&i| !array.is_null(i)).count();
-                let avg = if count > 0 { sum / count as f64 } else { 0.0 };
+                let avg = (count > 0).then(|| sum / count as f64);
                 Ok((Arc::new(Float64Array::from(vec![avg])), DataType::Float64))
             }
-            AggregateFunction::Count => {
-                Ok((Arc::new(Int64Array::from(vec![num_rows as i64])), DataType::Int64))
+            AggregateFunction::CountIf => Err(Self::count_if_unsupported(&DataType::Int64)),
+            AggregateFunction::ApproxCountDistinct
+            | AggregateFunction::ApproxQuantile(_)
+            | AggregateFunction::PercentileCont(_) => {
+                unreachable!("sketches and percentiles are computed before type dispatch")
             }
+            AggregateFunction::VarSamp
+            | AggregateFunction::VarPop
+            | AggregateFunction::StddevSamp
+            | AggregateFunction::StddevPop => Ok(Self::aggregate_variance(
+                func,
+                &array.iter().flatten().map(|v| v as f64).collect::<Vec<_>>(),
+            )),
+            AggregateFunction::Count => Ok(Self::count(non_null)),
             AggregateFunction::Min => {
-                let min = (0..array.len())
-                    .filter(|&i| !array.is_null(i))
-                    .map(|i| array.value(i))
-                    .min()
-                    .unwrap_or(0);
+                let min =
+                    (0..array.len()).filter(|&i| !array.is_null(i)).map(|i| array.value(i)).min();
                 Ok((Arc::new(Int64Array::from(vec![min])), DataType::Int64))
             }
             AggregateFunction::Max => {
-                let max = (0..array.len())
-                    .filter(|&i| !array.is_null(i))
-                    .map(|i| array.value(i))
-                    .max()
-                    .unwrap_or(0);
+                let max =
+                    (0..array.len()).filter(|&i| !array.is_null(i)).map(|i| array.value(i)).max();
                 Ok((Arc::new(Int64Array::from(vec![max])), DataType::Int64))
             }
         }
@@ -507,40 +2381,44 @@ impl QueryExecutor {
     fn aggregate_f32(
         func: AggregateFunction,
         array: &Float32Array,
-        num_rows: usize,
+        non_null: usize,
     ) -> Result<(ArrayRef, DataType)> {
+        let collected: Vec<f32>;
+        let values: &[f32] = if array.null_count() == 0 {
+            array.values()
+        } else {
+            collected = array.iter().flatten().collect();
+            &collected
+        };
         match func {
             AggregateFunction::Sum => {
-                let sum: f32 =
-                    (0..array.len()).filter(|&i| !array.is_null(i)).map(|i| array.value(i)).sum();
-                Ok((Arc::new(Float32Array::from(vec![sum])), DataType::Float32))
+                Ok((Arc::new(Float32Array::from(vec![reduce::sum_f32(values)])), DataType::Float32))
             }
             AggregateFunction::Avg => {
-                let sum: f64 = (0..array.len())
-                    .filter(|&i| !array.is_null(i))
-                    .map(|i| f64::from(array.value(i)))
-                    .sum();
-                let count = (0..array.len()).filter(|&i| !array.is_null(i)).count();
-                let avg = if count > 0 { sum / count as f64 } else { 0.0 };
+                let avg =
+                    (!values.is_empty()).then(|| reduce::sum_f64(values) / values.len() as f64);
                 Ok((Arc::new(Float64Array::from(vec![avg])), DataType::Float64))
             }
-            AggregateFunction::Count => {
-                Ok((Arc::new(Int64Array::from(vec![num_rows as i64])), DataType::Int64))
+            AggregateFunction::CountIf => Err(Self::count_if_unsupported(&DataType::Float32)),
+            AggregateFunction::ApproxCountDistinct
+            | AggregateFunction::ApproxQuantile(_)
+            | AggregateFunction::PercentileCont(_) => {
+                unreachable!("sketches and percentiles are computed before type dispatch")
             }
+            AggregateFunction::VarSamp
+            | AggregateFunction::VarPop
+            | AggregateFunction::StddevSamp
+            | AggregateFunction::StddevPop => Ok(Self::aggregate_variance(
+                func,
+                &values.iter().copied().map(f64::from).collect::<Vec<_>>(),
+            )),
+            AggregateFunction::Count => Ok(Self::count(non_null)),
             AggregateFunction::Min => {
-                let min = (0..array.len())
-                    .filter(|&i| !array.is_null(i))
-                    .map(|i| array.value(i))
-                    .min_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
-                    .unwrap_or(0.0);
+                let min = reduce::min_f32(values);
                 Ok((Arc::new(Float32Array::from(vec![min])), DataType::Float32))
             }
             AggregateFunction::Max => {
-                let max = (0..array.len())
-                    .filter(|&i| !array.is_null(i))
-                    .map(|i| array.value(i))
-                    .max_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
-                    .unwrap_or(0.0);
+                let max = reduce::max_f32(values);
                 Ok((Arc::new(Float32Array::from(vec![max])), DataType::Float32))
             }
         }
@@ -550,38 +2428,41 @@ impl QueryExecutor {
     fn aggregate_f64(
         func: AggregateFunction,
         array: &Float64Array,
-        num_rows: usize,
+        non_null: usize,
     ) -> Result<(ArrayRef, DataType)> {
+        let collected: Vec<f64>;
+        let values: &[f64] = if array.null_count() == 0 {
+            array.values()
+        } else {
+            collected = array.iter().flatten().collect();
+            &collected
+        };
         match func {
             AggregateFunction::Sum => {
-                let sum: f64 =
-                    (0..array.len()).filter(|&i| !array.is_null(i)).map(|i| array.value(i)).sum();
-                Ok((Arc::new(Float64Array::from(vec![sum])), DataType::Float64))
+                Ok((Arc::new(Float64Array::from(vec![reduce::sum_f64(values)])), DataType::Float64))
             }
             AggregateFunction::Avg => {
-                let sum: f64 =
-                    (0..array.len()).filter(|&i| !array.is_null(i)).map(|i| array.value(i)).sum();
-                let count = (0..array.len()).filter(|&i| !array.is_null(i)).count();
-                let avg = if count > 0 { sum / count as f64 } else { 0.0 };
+                let avg =
+                    (!values.is_empty()).then(|| reduce::sum_f64(values) / values.len() as f64);
                 Ok((Arc::new(Float64Array::from(vec![avg])), DataType::Float64))
             }
-            AggregateFunction::Count => {
-                Ok((Arc::new(Int64Array::from(vec![num_rows as i64])), DataType::Int64))
+            AggregateFunction::CountIf => Err(Self::count_if_unsupported(&DataType::Float64)),
+            AggregateFunction::ApproxCountDistinct
+            | AggregateFunction::ApproxQuantile(_)
+            | AggregateFunction::PercentileCont(_) => {
+                unreachable!("sketches and percentiles are computed before type dispatch")
             }
+            AggregateFunction::VarSamp
+            | AggregateFunction::VarPop
+            | AggregateFunction::StddevSamp
+            | AggregateFunction::StddevPop => Ok(Self::aggregate_variance(func, values)),
+            AggregateFunction::Count => Ok(Self::count(non_null)),
             AggregateFunction::Min => {
-                let min = (0..array.len())
-                    .filter(|&i| !array.is_null(i))
-                    .map(|i| array.value(i))
-                    .min_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
-                    .unwrap_or(0.0);
+                let min = reduce::min_f64(values);
                 Ok((Arc::new(Float64Array::from(vec![min])), DataType::Float64))
             }
             AggregateFunction::Max => {
-                let max = (0..array.len())
-                    .filter(|&i| !array.is_null(i))
-                    .map(|i| array.value(i))
-                    .max_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
-                    .unwrap_or(0.0);
+                let max = reduce::max_f64(values);
                 Ok((Arc::new(Float64Array::from(vec![max])), DataType::Float64))
             }
         }
@@ -593,25 +2474,31 @@ impl QueryExecutor {
             return Ok(batch.clone());
         }
 
-        // Phase 1: Single ORDER BY column only
-        let (col_name, direction) = &plan.order_by[0];
+        // Use Top-K over LIMIT + OFFSET rows if LIMIT is present, otherwise
+        // sort all; the first key drives selection and the rest break its
+        // ties, then row order. OFFSET then skips the leading rows
+        let keys = Self::sort_keys(&batch.schema(), plan)?;
+        let k = plan.fetch().unwrap_or_else(|| batch.num_rows());
+        let sorted = batch.top_k_multi(&keys, k)?;
+        let (offset, len) = Self::page(sorted.num_rows(), plan);
+        Ok(sorted.slice(offset, len))
+    }
 
-        // Find column index
-        let schema = batch.schema();
-        let col_index = schema
-            .fields()
+    /// Resolve each ORDER BY key to (column index, `SortOrder`) in `schema`
+    fn sort_keys(schema: &Schema, plan: &QueryPlan) -> Result<Vec<(usize, SortOrder)>> {
+        plan.order_by
             .iter()
-            .position(|f| f.name() == col_name)
-            .ok_or_else(|| Error::InvalidInput(format!("Column not found: {col_name}")))?;
-
-        // Convert OrderDirection to SortOrder
-        let sort_order = match direction {
-            OrderDirection::Asc => SortOrder::Ascending,
-            OrderDirection::Desc => SortOrder::Descending,
-        };
-
-        // Use Top-K if LIMIT is present, otherwise sort all
-        let k = plan.limit.unwrap_or_else(|| batch.num_rows());
-        batch.top_k(col_index, k, sort_order)
+            .map(|(col_name, direction)| {
+                let col_index =
+                    schema.fields().iter().position(|f| f.name() == col_name).ok_or_else(|| {
+                        Error::InvalidInput(format!("Column not found: {col_name}"))
+                    })?;
+                let sort_order = match direction {
+                    OrderDirection::Asc => SortOrder::Ascending,
+                    OrderDirection::Desc => SortOrder::Descending,
+                };
+                Ok((col_index, sort_order))
+            })
+            .collect()
     }
 }
@@ -0,0 +1,134 @@
+//! EXPLAIN output
+//!
+//! The executor describes a plan as one step per operator, without running
+//! it. [`QueryExecutor::explain`](super::QueryExecutor::explain) renders the
+//! steps as text; executing an `EXPLAIN SELECT ...` plan returns them as a
+//! `RecordBatch` with one row per operator:
+//!
+//! | Column | Type | Meaning |
+//! |--------|------|---------|
+//! | `cte` | Utf8 (nullable) | CTE the operator belongs to (null for the main query) |
+//! | `operator` | Utf8 | Operator name, as in the text output |
+//! | `detail` | Utf8 | Operator arguments, as in the text output |
+//! | `estimated_rows` | `UInt64` | Rows the operator is expected to produce |
+//! | `estimated_flops` | Float64 | FLOPs per the `BackendDispatcher` estimators |
+//! | `backend` | Utf8 (nullable) | Backend the cost model predicts (null for rewrites and warnings) |
+//! | `morsels` | `UInt64` | Input morsels the operator streams |
+//!
+//! Each SELECT's operators are preceded by a `Rewrite` row per optimizer
+//! rewrite applied to it (see [`optimizer`](super::optimizer)). Operators
+//! are `Scan`, `HashJoin`, `Filter`, `Project`, `Aggregate`,
+//! `HashAggregate`, `TopK` and `Limit`, followed by a `Warning` row per lint
+//! (see [`lint`](super::lint)). FLOPs come from the
+//! [`BackendDispatcher`](crate::backend::BackendDispatcher) estimators and
+//! morsels are [`MORSEL_ROWS`](super::parallel::MORSEL_ROWS) rows each.
+//!
+//! Toyota Way: Genchi Genbutsu (see what the engine will do before it runs)
+
+use crate::{Backend, Error, Result};
+use arrow::array::{ArrayRef, Float64Array, RecordBatch, StringArray, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use std::sync::Arc;
+
+/// One operator of an explained plan
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct PlanStep {
+    /// CTE the operator belongs to (`None` for the main query)
+    pub cte: Option<String>,
+    /// Operator name (`Scan`, `Filter`, ..., `Rewrite` for an optimizer
+    /// rewrite, or `Warning` for a lint)
+    pub operator: &'static str,
+    /// Operator arguments
+    pub detail: String,
+    /// Estimated output rows
+    pub estimated_rows: usize,
+    /// Estimated FLOPs
+    pub estimated_flops: f64,
+    /// Predicted backend (`None` for rewrites and warnings)
+    pub backend: Option<Backend>,
+    /// Morsels of input the operator streams
+    pub morsels: usize,
+}
+
+/// Text form: one `Operator: detail` line per step, CTE steps indented
+/// under a `CTE name:` header
+pub(super) fn render(steps: &[PlanStep]) -> String {
+    let mut lines = Vec::new();
+    let mut current_cte = None;
+    for step in steps {
+        if step.cte.is_some() && step.cte != current_cte {
+            lines.push(format!("CTE {}:", step.cte.as_deref().unwrap_or_default()));
+        }
+        current_cte.clone_from(&step.cte);
+        let indent = if step.cte.is_some() { "  " } else { "" };
+        lines.push(format!("{indent}{}: {}", step.operator, step.detail));
+    }
+    lines.join("\n")
+}
+
+/// Structured form (see the module docs for the schema)
+pub(super) fn to_batch(steps: &[PlanStep]) -> Result<RecordBatch> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("cte", DataType::Utf8, true),
+        Field::new("operator", DataType::Utf8, false),
+        Field::new("detail", DataType::Utf8, false),
+        Field::new("estimated_rows", DataType::UInt64, false),
+        Field::new("estimated_flops", DataType::Float64, false),
+        Field::new("backend", DataType::Utf8, true),
+        Field::new("morsels", DataType::UInt64, false),
+    ]));
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(steps.iter().map(|step| step.cte.as_deref()).collect::<StringArray>()),
+        Arc::new(steps.iter().map(|step| Some(step.operator)).collect::<StringArray>()),
+        Arc::new(steps.iter().map(|step| Some(step.detail.as_str())).collect::<StringArray>()),
+        Arc::new(steps.iter().map(|step| step.estimated_rows as u64).collect::<UInt64Array>()),
+        Arc::new(steps.iter().map(|step| step.estimated_flops).collect::<Float64Array>()),
+        Arc::new(steps.iter().map(|step| step.backend.map(Backend::name)).collect::<StringArray>()),
+        Arc::new(steps.iter().map(|step| step.morsels as u64).collect::<UInt64Array>()),
+    ];
+    RecordBatch::try_new(schema, columns)
+        .map_err(|e| Error::StorageError(format!("Failed to build EXPLAIN result: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Array, AsArray};
+
+    fn step(cte: Option<&str>, operator: &'static str, detail: &str) -> PlanStep {
+        PlanStep {
+            cte: cte.map(str::to_string),
+            operator,
+            detail: detail.to_string(),
+            estimated_rows: 10,
+            estimated_flops: 20.0,
+            backend: (operator != "Warning").then_some(Backend::Simd),
+            morsels: 1,
+        }
+    }
+
+    #[test]
+    fn test_render_indents_cte_steps() {
+        let steps = vec![
+            step(Some("big"), "Scan", "t (~10 rows)"),
+            step(Some("big"), "Filter", "v > 1"),
+            step(None, "Scan", "big (~10 rows)"),
+        ];
+        assert_eq!(
+            render(&steps),
+            "CTE big:\n  Scan: t (~10 rows)\n  Filter: v > 1\nScan: big (~10 rows)"
+        );
+    }
+
+    #[test]
+    fn test_batch_has_null_backend_for_warnings() {
+        let steps = vec![step(None, "Scan", "t (~10 rows)"), step(None, "Warning", "lint")];
+        let batch = to_batch(&steps).unwrap();
+
+        assert_eq!(batch.num_rows(), 2);
+        assert!(batch.column(0).is_null(0));
+        let backend = batch.column_by_name("backend").unwrap().as_string::<i32>();
+        assert_eq!(backend.value(0), "simd");
+        assert!(backend.is_null(1));
+    }
+}
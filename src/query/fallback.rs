@@ -0,0 +1,395 @@
+//! Backend fallback chain: GPU → SIMD → Scalar
+//!
+//! [`FallbackExecutor`] runs a plan on the best backend available and
+//! degrades at runtime instead of failing the query:
+//!
+//! 1. **GPU** (`gpu` feature) — `WHERE` + `GROUP BY` plans the fused kernel
+//!    supports ([`FusedGroupBy`](crate::gpu::jit::FusedGroupBy)), and
+//!    ungrouped SUM/AVG/COUNT/MIN/MAX over integer columns (one
+//!    [`multi_aggregate`](crate::gpu::GpuEngine::multi_aggregate) pass per
+//!    column), when the executor's backend asks for the GPU. The engine is created on first
+//!    use; if that fails ([`Error::GpuInitFailed`]) the GPU tier is skipped
+//!    from then on. A lost device is re-created on the next query.
+//! 2. **SIMD** — the wrapped [`QueryExecutor`], with its morsel worker pool.
+//! 3. **Scalar** — the same executor on one thread, the reference path.
+//!
+//! A tier's failure (an error or a panic) moves the query to the next tier.
+//! Errors caused by the query rather than the backend (parse errors,
+//! invalid input, access denied, cancellation, timeouts) would fail on
+//! every tier, so they are returned immediately. Each result records the
+//! backend that produced it and the failures on the way.
+//!
+//! Toyota Way: Jidoka (a failing backend stops itself, the query carries on)
+
+use super::executor::QueryExecutor;
+#[cfg(feature = "gpu")]
+use super::stats::ExecutionReport;
+use super::QueryPlan;
+use crate::metrics::{self, Counter};
+use crate::storage::StorageEngine;
+use crate::{Error, Result};
+use arrow::record_batch::RecordBatch;
+use std::panic::{self, AssertUnwindSafe};
+#[cfg(feature = "gpu")]
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Backend that actually executed a query
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExecutionBackend {
+    /// Fused GPU kernel
+    Gpu,
+    /// Vectorized executor (morsel-parallel)
+    Simd,
+    /// Single-threaded executor
+    Scalar,
+}
+
+impl ExecutionBackend {
+    /// Stable lowercase name (`gpu`, `simd`, `scalar`)
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Gpu => "gpu",
+            Self::Simd => "simd",
+            Self::Scalar => "scalar",
+        }
+    }
+}
+
+/// A tier that failed before the result was produced
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackendFailure {
+    /// Tier that failed
+    pub backend: ExecutionBackend,
+    /// Error message (or panic message)
+    pub reason: String,
+}
+
+/// Result of [`FallbackExecutor::execute`]
+#[derive(Debug, Clone)]
+pub struct FallbackResult {
+    /// Query result
+    pub batch: RecordBatch,
+    /// Tier that produced `batch`
+    pub backend: ExecutionBackend,
+    /// Tiers that failed first, in the order they were tried
+    pub failures: Vec<BackendFailure>,
+}
+
+impl FallbackResult {
+    /// Whether the query ran on a lower tier than first attempted
+    #[must_use]
+    pub fn degraded(&self) -> bool {
+        !self.failures.is_empty()
+    }
+}
+
+/// GPU engine of the GPU tier
+#[cfg(feature = "gpu")]
+enum GpuState {
+    /// Not created yet (or dropped after the device was lost)
+    Untried,
+    Ready(Arc<crate::gpu::GpuEngine>),
+    /// Creation failed; the GPU tier is skipped
+    Unavailable,
+}
+
+/// Query executor that degrades GPU → SIMD → Scalar instead of failing
+///
+/// # Example
+/// ```
+/// use arrow::array::{Int32Array, RecordBatch};
+/// use arrow::datatypes::{DataType, Field, Schema};
+/// use std::sync::Arc;
+/// use trueno_db::query::{ExecutionBackend, FallbackExecutor, QueryEngine, QueryExecutor};
+/// use trueno_db::storage::StorageEngine;
+/// use trueno_db::Backend;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let schema = Arc::new(Schema::new(vec![Field::new("v", DataType::Int32, false)]));
+/// let batch = RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![1, 2, 3]))])?;
+/// let storage = StorageEngine::new(vec![batch]);
+///
+/// let executor = FallbackExecutor::new(QueryExecutor::with_backend(Backend::Simd));
+/// let plan = QueryEngine::new().parse("SELECT SUM(v) FROM t")?;
+/// let result = executor.execute(&plan, &storage)?;
+/// assert_eq!(result.backend, ExecutionBackend::Simd);
+/// assert!(!result.degraded());
+/// # Ok(())
+/// # }
+/// ```
+pub struct FallbackExecutor {
+    executor: QueryExecutor,
+    #[cfg(feature = "gpu")]
+    gpu: Mutex<GpuState>,
+}
+
+impl FallbackExecutor {
+    /// Wrap `executor`; its backend decides whether the GPU tier is tried
+    /// ([`Backend::Gpu`](crate::Backend::Gpu) always,
+    /// [`Backend::CostBased`](crate::Backend::CostBased) when the cost model
+    /// picks the GPU, [`Backend::Simd`](crate::Backend::Simd) never)
+    #[must_use]
+    pub const fn new(executor: QueryExecutor) -> Self {
+        Self {
+            executor,
+            #[cfg(feature = "gpu")]
+            gpu: Mutex::new(GpuState::Untried),
+        }
+    }
+
+    /// Use `engine` for the GPU tier instead of creating one on first use
+    #[cfg(feature = "gpu")]
+    #[must_use]
+    pub fn with_gpu(self, engine: crate::gpu::GpuEngine) -> Self {
+        *self.gpu.lock().unwrap_or_else(PoisonError::into_inner) =
+            GpuState::Ready(Arc::new(engine));
+        self
+    }
+
+    /// The wrapped (SIMD tier) executor
+    #[must_use]
+    pub const fn executor(&self) -> &QueryExecutor {
+        &self.executor
+    }
+
+    /// Execute `plan`, falling back to lower tiers on backend failures
+    ///
+    /// # Errors
+    /// Returns the query's error if it is not a backend failure (see the
+    /// module documentation), or the scalar tier's error if every tier failed
+    pub fn execute(&self, plan: &QueryPlan, storage: &StorageEngine) -> Result<FallbackResult> {
+        let mut failures = Vec::new();
+
+        #[cfg(feature = "gpu")]
+        if let Some(batch) = self.try_gpu(plan, storage, &mut failures)? {
+            return Ok(FallbackResult { batch, backend: ExecutionBackend::Gpu, failures });
+        }
+
+        let serial = self.executor.clone().with_parallelism(1);
+        let tiers = [(ExecutionBackend::Simd, &self.executor), (ExecutionBackend::Scalar, &serial)];
+        let mut last_error = None;
+        for (backend, executor) in tiers {
+            match run_tier(|| executor.execute(plan, storage)) {
+                Ok(batch) => return Ok(FallbackResult { batch, backend, failures }),
+                Err(e) if !is_backend_failure(&e) => return Err(e),
+                Err(e) => {
+                    tracing::warn!("{} backend failed: {e}", backend.name());
+                    metrics::count(Counter::FallbackEvents, 1);
+                    failures.push(BackendFailure { backend, reason: e.to_string() });
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| Error::Other("No backend ran the query".to_string())))
+    }
+
+    /// Run `plan` on a GPU kernel if one applies; `None` moves on to SIMD
+    #[cfg(feature = "gpu")]
+    fn try_gpu(
+        &self,
+        plan: &QueryPlan,
+        storage: &StorageEngine,
+        failures: &mut Vec<BackendFailure>,
+    ) -> Result<Option<RecordBatch>> {
+        let batches = storage.batches();
+        let (Some(first), crate::Backend::Gpu | crate::Backend::CostBased) =
+            (batches.first(), self.executor.backend())
+        else {
+            return Ok(None);
+        };
+        // Ungrouped plans use the fused kernel only to filter on the GPU too,
+        // and only without NULLs (multi_aggregate covers the rest)
+        let fused =
+            crate::gpu::jit::FusedGroupBy::from_plan(plan, &first.schema()).is_ok_and(|fused| {
+                fused.is_grouped()
+                    || (plan.filter.is_some()
+                        && batches.iter().all(|batch| fused.inputs(batch).is_ok()))
+            });
+        if !fused && !QueryExecutor::gpu_aggregates_apply(plan, batches) {
+            // Not a plan the GPU runs: nothing failed
+            return Ok(None);
+        }
+        if self.executor.backend() == crate::Backend::CostBased {
+            let rows = batches.iter().map(RecordBatch::num_rows).sum();
+            let bytes = batches.iter().map(RecordBatch::get_array_memory_size).sum();
+            let backend = if fused && !plan.group_by.is_empty() {
+                self.executor.group_by_backend(rows, bytes)
+            } else {
+                self.executor.aggregate_backend(rows, bytes)
+            };
+            if backend != crate::Backend::Gpu {
+                return Ok(None);
+            }
+        }
+
+        let Some(engine) = self.gpu_engine(failures) else {
+            return Ok(None);
+        };
+        let result = run_tier(|| {
+            let batch = QueryExecutor::combine_batches(batches)?;
+            let aggregated = if fused {
+                crate::gpu::block_on(engine.fused_group_by(plan, &batch))?
+            } else {
+                QueryExecutor::execute_gpu_aggregations(&engine, &batch, plan)?
+            };
+            QueryExecutor::order_and_limit(aggregated, plan, &mut ExecutionReport::new())
+        });
+        match result {
+            Ok(batch) => Ok(Some(batch)),
+            Err(e @ Error::Cancelled(_)) => Err(e),
+            Err(e) => {
+                tracing::warn!("GPU backend failed, falling back to SIMD: {e}");
+                metrics::count(Counter::FallbackEvents, 1);
+                if matches!(e, Error::GpuDeviceLost(_)) {
+                    *self.gpu.lock().unwrap_or_else(PoisonError::into_inner) = GpuState::Untried;
+                }
+                failures
+                    .push(BackendFailure { backend: ExecutionBackend::Gpu, reason: e.to_string() });
+                Ok(None)
+            }
+        }
+    }
+
+    /// The GPU engine, created on first use (`None` if none can be)
+    #[cfg(feature = "gpu")]
+    fn gpu_engine(&self, failures: &mut Vec<BackendFailure>) -> Option<Arc<crate::gpu::GpuEngine>> {
+        let mut state = self.gpu.lock().unwrap_or_else(PoisonError::into_inner);
+        match &*state {
+            GpuState::Ready(engine) => return Some(Arc::clone(engine)),
+            GpuState::Unavailable => return None,
+            GpuState::Untried => {}
+        }
+        match crate::gpu::block_on(crate::gpu::GpuEngine::new()) {
+            Ok(engine) => {
+                let engine = Arc::new(engine);
+                *state = GpuState::Ready(Arc::clone(&engine));
+                Some(engine)
+            }
+            Err(e) => {
+                tracing::warn!("GPU unavailable, using SIMD: {e}");
+                metrics::count(Counter::FallbackEvents, 1);
+                failures
+                    .push(BackendFailure { backend: ExecutionBackend::Gpu, reason: e.to_string() });
+                *state = GpuState::Unavailable;
+                None
+            }
+        }
+    }
+}
+
+/// Run one tier, turning a panic into an error
+fn run_tier<T>(tier: impl FnOnce() -> Result<T>) -> Result<T> {
+    panic::catch_unwind(AssertUnwindSafe(tier)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(ToString::to_string)
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        Err(Error::Other(format!("Backend panicked: {message}")))
+    })
+}
+
+/// Whether `err` is the backend's fault (another backend may succeed)
+/// rather than the query's
+const fn is_backend_failure(err: &Error) -> bool {
+    !matches!(
+        err,
+        Error::ParseError(_)
+            | Error::InvalidInput(_)
+            | Error::AccessDenied(_)
+            | Error::Cancelled(_)
+            | Error::Timeout(_)
+            | Error::Overloaded(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::QueryEngine;
+    use crate::Backend;
+    use arrow::array::Int32Array;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    fn storage() -> StorageEngine {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("k", DataType::Int32, false),
+            Field::new("v", DataType::Int32, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 1, 2, 3])),
+                Arc::new(Int32Array::from(vec![10, 20, 30, 40, 50])),
+            ],
+        )
+        .unwrap();
+        StorageEngine::new(vec![batch])
+    }
+
+    #[test]
+    fn test_simd_executes_without_degrading() {
+        let executor = FallbackExecutor::new(QueryExecutor::with_backend(Backend::Simd));
+        let plan = QueryEngine::new().parse("SELECT k, SUM(v) FROM t GROUP BY k").unwrap();
+        let result = executor.execute(&plan, &storage()).unwrap();
+        assert_eq!(result.backend, ExecutionBackend::Simd);
+        assert!(result.failures.is_empty());
+        assert_eq!(result.batch.num_rows(), 3);
+    }
+
+    #[test]
+    fn test_query_errors_do_not_fall_back() {
+        let executor = FallbackExecutor::new(QueryExecutor::with_backend(Backend::Simd));
+        let plan = QueryEngine::new().parse("SELECT SUM(missing) FROM t").unwrap();
+        assert!(matches!(executor.execute(&plan, &storage()), Err(Error::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_run_tier_catches_panics() {
+        let err = run_tier::<()>(|| panic!("kernel exploded")).unwrap_err();
+        assert!(err.to_string().contains("kernel exploded"), "{err}");
+        assert!(is_backend_failure(&err));
+        assert!(!is_backend_failure(&Error::ParseError("bad".to_string())));
+        assert!(is_backend_failure(&Error::GpuInitFailed("no adapter".to_string())));
+    }
+
+    #[cfg(feature = "gpu")]
+    #[test]
+    fn test_gpu_backend_produces_result_or_degrades() {
+        let executor = FallbackExecutor::new(QueryExecutor::with_backend(Backend::Gpu));
+        let plan = QueryEngine::new()
+            .parse("SELECT k, SUM(v), COUNT(*) FROM t WHERE v > 10 GROUP BY k ORDER BY k")
+            .unwrap();
+        let result = executor.execute(&plan, &storage()).unwrap();
+        let simd = QueryExecutor::new().execute(&plan, &storage()).unwrap();
+        assert_eq!(result.batch.columns(), simd.columns());
+        match result.backend {
+            ExecutionBackend::Gpu => assert!(result.failures.is_empty()),
+            backend => {
+                // No GPU here: recorded once, then skipped without retrying
+                assert_eq!(backend, ExecutionBackend::Simd);
+                assert_eq!(result.failures[0].backend, ExecutionBackend::Gpu);
+                let again = executor.execute(&plan, &storage()).unwrap();
+                assert!(again.failures.len() <= 1);
+            }
+        }
+    }
+
+    #[cfg(feature = "gpu")]
+    #[test]
+    fn test_gpu_backend_runs_ungrouped_aggregates() {
+        let executor = FallbackExecutor::new(QueryExecutor::with_backend(Backend::Gpu));
+        let plan = QueryEngine::new()
+            .parse("SELECT MIN(v), MAX(v), SUM(v), AVG(k), COUNT(*) FROM t WHERE k < 3")
+            .unwrap();
+        let result = executor.execute(&plan, &storage()).unwrap();
+        let simd = QueryExecutor::new().execute(&plan, &storage()).unwrap();
+        assert_eq!(result.batch.schema(), simd.schema());
+        assert_eq!(result.batch.columns(), simd.columns());
+        if result.backend == ExecutionBackend::Gpu {
+            assert!(result.failures.is_empty());
+        }
+    }
+}
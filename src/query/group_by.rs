@@ -0,0 +1,181 @@
+//! Hash GROUP BY
+//!
+//! Rows are assigned to groups by hashing their key columns, row-encoded
+//! with [`arrow::row`] so any mix of key types (Int32, Int64, Utf8, ...)
+//! works without per-type code; NULL keys form one group, as in SQL. The
+//! rows are then partitioned by group with a counting sort, and each
+//! aggregate runs the executor's ordinary per-type kernel on every
+//! partition, so grouped and ungrouped results have identical types and
+//! semantics.
+//!
+//! Groups are emitted in order of first appearance.
+//!
+//! Dictionary-encoded string keys are grouped on their dictionary keys:
+//! each distinct string is hashed once and rows hash a small integer id.
+//!
+//! Toyota Way: Jidoka (one aggregation kernel for grouped and ungrouped queries)
+
+use crate::{Error, Result};
+use arrow::array::{Array, ArrayRef, AsArray, UInt32Array};
+use arrow::compute;
+use arrow::row::{Row, RowConverter, SortField};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Rows of a batch partitioned by group key
+pub(super) struct Groups {
+    /// Index of each group's first row (group order = first appearance)
+    first_rows: UInt32Array,
+    /// Row indices ordered by group
+    order: UInt32Array,
+    /// Start of each group's rows in `order` (one extra trailing entry)
+    offsets: Vec<usize>,
+}
+
+impl Groups {
+    /// Hash the rows of `keys` (equal-length key columns) into groups
+    pub(crate) fn build(keys: &[ArrayRef]) -> Result<Self> {
+        let keys: Vec<ArrayRef> = keys.iter().map(value_ids).collect();
+        let fields = keys.iter().map(|key| SortField::new(key.data_type().clone())).collect();
+        let converter = RowConverter::new(fields)
+            .map_err(|e| Error::InvalidInput(format!("Unsupported GROUP BY key: {e}")))?;
+        let rows = converter
+            .convert_columns(&keys)
+            .map_err(|e| Error::Other(format!("Failed to encode GROUP BY keys: {e}")))?;
+
+        let mut ids: HashMap<Row<'_>, usize> = HashMap::new();
+        let mut first_rows: Vec<u32> = Vec::new();
+        let mut group_of = Vec::with_capacity(rows.num_rows());
+        for (row_index, row) in rows.iter().enumerate() {
+            let next = first_rows.len();
+            let group = *ids.entry(row).or_insert(next);
+            if group == next {
+                first_rows.push(row_index_u32(row_index)?);
+            }
+            group_of.push(group);
+        }
+
+        // Counting sort of row indices by group
+        let mut offsets = vec![0; first_rows.len() + 1];
+        for &group in &group_of {
+            offsets[group + 1] += 1;
+        }
+        for group in 0..first_rows.len() {
+            offsets[group + 1] += offsets[group];
+        }
+        let mut cursor = offsets.clone();
+        let mut order = vec![0; group_of.len()];
+        for (row_index, &group) in group_of.iter().enumerate() {
+            order[cursor[group]] = row_index_u32(row_index)?;
+            cursor[group] += 1;
+        }
+
+        Ok(Self {
+            first_rows: UInt32Array::from(first_rows),
+            order: UInt32Array::from(order),
+            offsets,
+        })
+    }
+
+    /// One value of `key` per group (dictionary keys stay encoded)
+    pub(crate) fn keys(&self, key: &ArrayRef) -> Result<ArrayRef> {
+        compute::take(key.as_ref(), &self.first_rows, None)
+            .map_err(|e| Error::Other(format!("Failed to gather group keys: {e}")))
+    }
+
+    /// `column` split into one slice per group
+    pub(crate) fn partition(&self, column: &ArrayRef) -> Result<Vec<ArrayRef>> {
+        let reordered = compute::take(column.as_ref(), &self.order, None)
+            .map_err(|e| Error::Other(format!("Failed to partition rows by group: {e}")))?;
+        Ok(self
+            .offsets
+            .windows(2)
+            .map(|bounds| reordered.slice(bounds[0], bounds[1] - bounds[0]))
+            .collect())
+    }
+}
+
+/// The column rows are hashed on: a string dictionary becomes one id per
+/// distinct value (a dictionary may hold a value twice, so its raw keys
+/// are not enough); other columns are hashed as they are
+#[allow(clippy::cast_possible_truncation)]
+fn value_ids(key: &ArrayRef) -> ArrayRef {
+    let Some(dictionary) = key.as_any_dictionary_opt() else {
+        return Arc::clone(key);
+    };
+    let Some(values) = dictionary.values().as_string_opt::<i32>() else {
+        return Arc::clone(key);
+    };
+
+    let mut seen: HashMap<&str, u32> = HashMap::new();
+    let ids: Vec<u32> = values
+        .iter()
+        .map(|value| {
+            let next = seen.len() as u32;
+            *seen.entry(value.unwrap_or_default()).or_insert(next)
+        })
+        .collect();
+    // Null keys (and keys of NULL values) stay NULL
+    let keys = dictionary.normalized_keys();
+    let rows = keys.iter().map(|&k| ids.get(k).copied().unwrap_or(0));
+    Arc::new(UInt32Array::new(rows.collect(), key.logical_nulls()))
+}
+
+fn row_index_u32(row_index: usize) -> Result<u32> {
+    u32::try_from(row_index).map_err(|_| {
+        Error::InvalidInput(format!("GROUP BY input too large: row {row_index} exceeds u32"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{DictionaryArray, Int32Array, StringArray};
+
+    #[test]
+    fn test_groups_in_first_appearance_order() {
+        let region: ArrayRef = Arc::new(StringArray::from(vec!["eu", "us", "eu", "us", "eu"]));
+        let tier: ArrayRef = Arc::new(Int32Array::from(vec![1, 1, 1, 2, 2]));
+        let groups = Groups::build(&[region.clone(), tier]).unwrap();
+
+        let keys = groups.keys(&region).unwrap();
+        let keys = keys.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(keys.iter().flatten().collect::<Vec<_>>(), vec!["eu", "us", "us", "eu"]);
+
+        let values: ArrayRef = Arc::new(Int32Array::from(vec![10, 20, 30, 40, 50]));
+        let parts: Vec<Vec<i32>> = groups
+            .partition(&values)
+            .unwrap()
+            .iter()
+            .map(|part| {
+                part.as_any().downcast_ref::<Int32Array>().unwrap().iter().flatten().collect()
+            })
+            .collect();
+        assert_eq!(parts, vec![vec![10, 30], vec![20], vec![40], vec![50]]);
+    }
+
+    #[test]
+    fn test_dictionary_keys_group_by_value() {
+        // "eu" appears under two dictionary keys, as after concatenation
+        let values = StringArray::from(vec![Some("eu"), Some("us"), Some("eu"), None]);
+        let keys = Int32Array::from(vec![Some(0), Some(1), Some(2), None, Some(3)]);
+        let region: ArrayRef = Arc::new(DictionaryArray::new(keys, Arc::new(values)));
+        let groups = Groups::build(std::slice::from_ref(&region)).unwrap();
+
+        let sizes: Vec<usize> = groups.partition(&region).unwrap().iter().map(Array::len).collect();
+        assert_eq!(sizes, vec![2, 1, 2]);
+        let keys = groups.keys(&region).unwrap();
+        assert!(keys.as_any_dictionary_opt().is_some());
+        assert!(keys.is_null(2));
+    }
+
+    #[test]
+    fn test_null_keys_form_one_group() {
+        let key: ArrayRef = Arc::new(Int32Array::from(vec![None, Some(1), None]));
+        let groups = Groups::build(std::slice::from_ref(&key)).unwrap();
+
+        let sizes: Vec<usize> = groups.partition(&key).unwrap().iter().map(Array::len).collect();
+        assert_eq!(sizes, vec![2, 1]);
+        assert!(groups.keys(&key).unwrap().is_null(0));
+    }
+}
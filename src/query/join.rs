@@ -0,0 +1,283 @@
+//! Inner hash join
+//!
+//! The joined table is the build side: its key rows are hashed once
+//! (row-encoded with [`arrow::row`], so keys of any type and multi-column
+//! keys need no per-type code). The rows read so far are the probe side and
+//! drive output order: each probe row is emitted once per matching build
+//! row, in build order. NULL keys never match, as in SQL.
+//!
+//! Columns keep their bare names in the output unless two inputs share a
+//! name; those are qualified with their table's alias (or name), e.g.
+//! `c.id`. [`JoinedColumns`] maps query references (bare or qualified)
+//! onto the output names.
+//!
+//! Toyota Way: Muda elimination (one pass over each side)
+
+use super::binder::rebind_filter;
+use super::QueryPlan;
+use crate::{Error, Result};
+use arrow::array::{Array, ArrayRef, RecordBatch, UInt32Array};
+use arrow::compute;
+use arrow::datatypes::{Field, Schema};
+use arrow::row::{Row, RowConverter, SortField};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Matching (probe, build) row index pairs of an inner equi-join
+pub(super) fn hash_join(
+    probe_keys: &[ArrayRef],
+    build_keys: &[ArrayRef],
+) -> Result<(UInt32Array, UInt32Array)> {
+    // Compare keys in the build side's types (e.g. Int32 probe vs Int64 build)
+    let probe_keys = probe_keys
+        .iter()
+        .zip(build_keys)
+        .map(|(probe, build)| {
+            if probe.data_type() == build.data_type() {
+                Ok(Arc::clone(probe))
+            } else {
+                compute::cast(probe, build.data_type()).map_err(|e| {
+                    Error::InvalidInput(format!(
+                        "JOIN key types differ ({} vs {}): {e}",
+                        probe.data_type(),
+                        build.data_type()
+                    ))
+                })
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let fields = build_keys.iter().map(|key| SortField::new(key.data_type().clone())).collect();
+    let converter = RowConverter::new(fields)
+        .map_err(|e| Error::InvalidInput(format!("Unsupported JOIN key: {e}")))?;
+    let encode = |keys: &[ArrayRef]| {
+        converter
+            .convert_columns(keys)
+            .map_err(|e| Error::Other(format!("Failed to encode JOIN keys: {e}")))
+    };
+    let build_rows = encode(build_keys)?;
+    let probe_rows = encode(&probe_keys)?;
+
+    // Build: key row → build row indices
+    let mut table: HashMap<Row<'_>, Vec<u32>> = HashMap::new();
+    for (index, row) in build_rows.iter().enumerate() {
+        if !has_null_key(build_keys, index) {
+            table.entry(row).or_default().push(row_index_u32(index)?);
+        }
+    }
+
+    // Probe
+    let mut probe_indices = Vec::new();
+    let mut build_indices = Vec::new();
+    for (index, row) in probe_rows.iter().enumerate() {
+        if has_null_key(&probe_keys, index) {
+            continue;
+        }
+        if let Some(matches) = table.get(&row) {
+            let probe_index = row_index_u32(index)?;
+            probe_indices.extend(std::iter::repeat(probe_index).take(matches.len()));
+            build_indices.extend_from_slice(matches);
+        }
+    }
+
+    Ok((UInt32Array::from(probe_indices), UInt32Array::from(build_indices)))
+}
+
+fn has_null_key(keys: &[ArrayRef], index: usize) -> bool {
+    keys.iter().any(|key| key.is_null(index))
+}
+
+fn row_index_u32(row_index: usize) -> Result<u32> {
+    u32::try_from(row_index).map_err(|_| {
+        Error::InvalidInput(format!("JOIN input too large: row {row_index} exceeds u32"))
+    })
+}
+
+/// Where each column of a joined batch came from
+pub(super) struct JoinedColumns {
+    /// (qualifier, bare name) per column, in batch order
+    sources: Vec<(String, String)>,
+}
+
+impl JoinedColumns {
+    /// Columns of a single table read under `qualifier`
+    pub(crate) fn new(qualifier: &str, schema: &Schema) -> Self {
+        let mut columns = Self { sources: Vec::new() };
+        columns.extend(qualifier, schema);
+        columns
+    }
+
+    /// Append the columns of a joined table
+    pub(crate) fn extend(&mut self, qualifier: &str, schema: &Schema) {
+        self.sources.extend(
+            schema.fields().iter().map(|field| (qualifier.to_string(), field.name().clone())),
+        );
+    }
+
+    /// Output name of column `index`: bare unless another column shares it
+    fn output_name(&self, index: usize) -> String {
+        let (qualifier, name) = &self.sources[index];
+        if self.sources.iter().filter(|(_, other)| other == name).count() > 1 {
+            format!("{qualifier}.{name}")
+        } else {
+            name.clone()
+        }
+    }
+
+    /// Index of the column `name` refers to (`col` or `qualifier.col`)
+    ///
+    /// `Ok(None)` if nothing matches (e.g. `name` is an output alias).
+    ///
+    /// # Errors
+    /// Returns error if a bare name matches columns of several tables
+    pub(crate) fn resolve(&self, name: &str) -> Result<Option<usize>> {
+        if let Some((qualifier, column)) = name.rsplit_once('.') {
+            if let Some(index) =
+                self.sources.iter().position(|(q, c)| q == qualifier && c == column)
+            {
+                return Ok(Some(index));
+            }
+        }
+
+        let mut matches = self.sources.iter().enumerate().filter(|(_, (_, c))| c == name);
+        match (matches.next(), matches.next()) {
+            (Some((index, _)), None) => Ok(Some(index)),
+            (Some(_), Some(_)) => {
+                Err(Error::InvalidInput(format!("Ambiguous column (qualify it): {name}")))
+            }
+            (None, _) => Ok(None),
+        }
+    }
+
+    /// Index of the column `name` refers to, erroring if there is none
+    pub(crate) fn require(&self, name: &str) -> Result<usize> {
+        self.resolve(name)?
+            .ok_or_else(|| Error::InvalidInput(format!("JOIN column not found: {name}")))
+    }
+
+    /// Rename `batch`'s columns to their output names
+    pub(crate) fn rename(&self, batch: &RecordBatch) -> Result<RecordBatch> {
+        let fields: Vec<Field> = batch
+            .schema()
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(index, field)| field.as_ref().clone().with_name(self.output_name(index)))
+            .collect();
+        RecordBatch::try_new(Arc::new(Schema::new(fields)), batch.columns().to_vec())
+            .map_err(|e| Error::StorageError(format!("Failed to create joined batch: {e}")))
+    }
+
+    /// `plan` with every column reference rewritten to an output name
+    pub(crate) fn bind(&self, plan: &QueryPlan) -> Result<QueryPlan> {
+        let bind = |name: &String| -> Result<String> {
+            if name == "*" {
+                return Ok(name.clone());
+            }
+            Ok(self.resolve(name)?.map_or_else(|| name.clone(), |i| self.output_name(i)))
+        };
+
+        // Expressions (computed items, aggregate arguments) bind each column
+        let bind_expr = |expr: &String| rebind_filter(expr, |name| bind(&name.to_string()));
+        // GROUP BY keys and aggregate arguments: a column or an expression
+        let bind_input = |input: &String| -> Result<String> {
+            if input == "*" || self.resolve(input)?.is_some() {
+                bind(input)
+            } else {
+                Ok(bind_expr(input).unwrap_or_else(|_| input.clone()))
+            }
+        };
+
+        let mut bound = plan.clone();
+        for column in &mut bound.columns {
+            if !plan.computed.iter().any(|(name, _)| name == column) {
+                *column = bind(column)?;
+            }
+        }
+        for (_, expr) in &mut bound.computed {
+            *expr = bind_expr(expr)?;
+        }
+        for key in &mut bound.group_by {
+            if !plan.computed.iter().any(|(name, _)| name == key) {
+                *key = bind_input(key)?;
+            }
+        }
+        for (_, column, _) in &mut bound.aggregations {
+            *column = bind_input(column)?;
+        }
+        for (column, _) in &mut bound.order_by {
+            *column = bind(column)?;
+        }
+        if let Some(filter) = &mut bound.filter {
+            *filter = bind_expr(filter)?;
+        }
+        Ok(bound)
+    }
+}
+
+/// Gather `probe` and `build` rows at the matched indices, side by side
+pub(super) fn gather(
+    probe: &RecordBatch,
+    build: &RecordBatch,
+    probe_indices: &UInt32Array,
+    build_indices: &UInt32Array,
+) -> Result<RecordBatch> {
+    let take = |column: &ArrayRef, indices: &UInt32Array| {
+        compute::take(column.as_ref(), indices, None)
+            .map_err(|e| Error::Other(format!("Failed to gather joined rows: {e}")))
+    };
+
+    let mut fields: Vec<Field> =
+        probe.schema().fields().iter().map(|field| field.as_ref().clone()).collect();
+    let mut columns = probe
+        .columns()
+        .iter()
+        .map(|column| take(column, probe_indices))
+        .collect::<Result<Vec<_>>>()?;
+    for (field, column) in build.schema().fields().iter().zip(build.columns()) {
+        fields.push(field.as_ref().clone());
+        columns.push(take(column, build_indices)?);
+    }
+
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)
+        .map_err(|e| Error::StorageError(format!("Failed to create joined batch: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Int32Array, Int64Array};
+    use arrow::datatypes::DataType;
+
+    #[test]
+    fn test_hash_join_matches_duplicates_and_skips_nulls() {
+        let probe: ArrayRef = Arc::new(Int32Array::from(vec![Some(1), None, Some(2), Some(3)]));
+        let build: ArrayRef = Arc::new(Int64Array::from(vec![Some(2), Some(1), Some(2), None]));
+
+        let (probe_indices, build_indices) = hash_join(&[probe], &[build]).unwrap();
+        assert_eq!(probe_indices.values().to_vec(), vec![0, 2, 2]);
+        assert_eq!(build_indices.values().to_vec(), vec![1, 0, 2]);
+    }
+
+    #[test]
+    fn test_joined_columns_qualify_shared_names() {
+        let orders = Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("customer_id", DataType::Int32, false),
+        ]);
+        let customers = Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, false),
+        ]);
+        let mut columns = JoinedColumns::new("o", &orders);
+        columns.extend("customers", &customers);
+
+        assert_eq!(columns.resolve("customers.id").unwrap(), Some(2));
+        assert_eq!(columns.resolve("o.customer_id").unwrap(), Some(1));
+        assert_eq!(columns.resolve("name").unwrap(), Some(3));
+        assert!(columns.resolve("id").is_err());
+        assert_eq!(columns.resolve("total").unwrap(), None);
+        assert_eq!(columns.output_name(0), "o.id");
+        assert_eq!(columns.output_name(3), "name");
+    }
+}
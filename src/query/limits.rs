@@ -0,0 +1,243 @@
+//! Per-query resource limits
+//!
+//! [`QueryLimits`] bounds what one query may consume: wall-clock time, rows
+//! returned, and bytes of intermediate batches (the filtered, joined and
+//! projected rows operators hand to each other). Limits are enforced
+//! cooperatively at the same safe points as cancellation: before each
+//! operator, and between morsels in parallel and async scans. A morsel
+//! already being processed finishes before the check, so a query may run
+//! past its deadline by up to one morsel's work.
+//!
+//! A tripped limit fails the query with [`Error::ResourceLimitExceeded`],
+//! naming the [`ResourceLimit`] and how far the query got.
+//!
+//! ```
+//! use arrow::array::{Int64Array, RecordBatch};
+//! use arrow::datatypes::{DataType, Field, Schema};
+//! use std::sync::Arc;
+//! use trueno_db::query::limits::{QueryLimits, ResourceLimit};
+//! use trueno_db::query::{QueryEngine, QueryExecutor};
+//! use trueno_db::storage::StorageEngine;
+//! use trueno_db::Error;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let schema = Arc::new(Schema::new(vec![Field::new("v", DataType::Int64, false)]));
+//! let batch = RecordBatch::try_new(schema, vec![Arc::new(Int64Array::from_iter_values(0..100))])?;
+//! let storage = StorageEngine::new(vec![batch]);
+//!
+//! let executor = QueryExecutor::new().with_limits(QueryLimits::new().with_max_output_rows(10));
+//! let plan = QueryEngine::new().parse("SELECT v FROM t WHERE v >= 50")?;
+//! let err = executor.execute(&plan, &storage).unwrap_err();
+//! assert!(matches!(err, Error::ResourceLimitExceeded { limit: ResourceLimit::OutputRows, .. }));
+//!
+//! let plan = QueryEngine::new().parse("SELECT v FROM t WHERE v >= 50 LIMIT 10")?;
+//! assert_eq!(executor.execute(&plan, &storage)?.num_rows(), 10);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! References:
+//! - `PostgreSQL` `statement_timeout` and `work_mem` (per-query time and
+//!   working-memory bounds)
+//! - Leis et al. (2014): Morsel-driven parallelism (morsels as the unit of
+//!   cooperative scheduling)
+//!
+//! Toyota Way: Jidoka (a runaway query stops itself instead of starving others)
+
+use super::stats::Stopwatch;
+use crate::{CancellationToken, Error, Result};
+use arrow::record_batch::RecordBatch;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Which limit of [`QueryLimits`] a query exceeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResourceLimit {
+    /// [`QueryLimits::max_execution_time`]
+    ExecutionTime,
+    /// [`QueryLimits::max_output_rows`]
+    OutputRows,
+    /// [`QueryLimits::max_memory_bytes`]
+    Memory,
+}
+
+impl fmt::Display for ResourceLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::ExecutionTime => "max execution time",
+            Self::OutputRows => "max output rows",
+            Self::Memory => "max memory",
+        })
+    }
+}
+
+/// Bounds on a single query; `None` leaves a resource unlimited (the default)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueryLimits {
+    /// Wall-clock time from the start of execution
+    pub max_execution_time: Option<Duration>,
+    /// Rows in the result batch
+    pub max_output_rows: Option<usize>,
+    /// Bytes of intermediate batches materialized by the query's operators
+    /// (summed, so an upper bound on what is held at once)
+    pub max_memory_bytes: Option<usize>,
+}
+
+impl QueryLimits {
+    /// No limits
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { max_execution_time: None, max_output_rows: None, max_memory_bytes: None }
+    }
+
+    /// Fail queries still running after `timeout`
+    #[must_use]
+    pub const fn with_max_execution_time(mut self, timeout: Duration) -> Self {
+        self.max_execution_time = Some(timeout);
+        self
+    }
+
+    /// Fail queries returning more than `rows` rows
+    #[must_use]
+    pub const fn with_max_output_rows(mut self, rows: usize) -> Self {
+        self.max_output_rows = Some(rows);
+        self
+    }
+
+    /// Fail queries materializing more than `bytes` of intermediate batches
+    #[must_use]
+    pub const fn with_max_memory_bytes(mut self, bytes: usize) -> Self {
+        self.max_memory_bytes = Some(bytes);
+        self
+    }
+
+    /// Whether no limit is set
+    #[must_use]
+    pub const fn is_unlimited(&self) -> bool {
+        self.max_execution_time.is_none()
+            && self.max_output_rows.is_none()
+            && self.max_memory_bytes.is_none()
+    }
+}
+
+/// One query's progress against its [`QueryLimits`], shared by its workers
+pub(crate) struct LimitGuard {
+    limits: QueryLimits,
+    stopwatch: Stopwatch,
+    /// Intermediate bytes materialized so far
+    memory: AtomicUsize,
+    /// Token of an async query, checked along with the deadline
+    token: Option<CancellationToken>,
+}
+
+impl LimitGuard {
+    /// Start the clock for a query bounded by `limits`
+    pub(crate) fn start(limits: QueryLimits) -> Self {
+        Self { limits, stopwatch: Stopwatch::start(), memory: AtomicUsize::new(0), token: None }
+    }
+
+    /// Also stop the query once `token` is cancelled
+    #[cfg(feature = "tokio")]
+    pub(crate) fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// Fail if the query was cancelled or has run past its deadline
+    pub(crate) fn check(&self) -> Result<()> {
+        if self.token.as_ref().is_some_and(CancellationToken::is_cancelled) {
+            return Err(Error::Cancelled(format!(
+                "query cancelled after {} ms",
+                self.stopwatch.elapsed().as_millis()
+            )));
+        }
+        let Some(max) = self.limits.max_execution_time else {
+            return Ok(());
+        };
+        let elapsed = self.stopwatch.elapsed();
+        if elapsed > max {
+            return Err(Error::ResourceLimitExceeded {
+                limit: ResourceLimit::ExecutionTime,
+                detail: format!("ran {} ms, limit {} ms", elapsed.as_millis(), max.as_millis()),
+            });
+        }
+        Ok(())
+    }
+
+    /// Account for intermediate `batch`, failing if the query's total
+    /// exceeds its memory limit
+    pub(crate) fn reserve(&self, batch: &RecordBatch) -> Result<()> {
+        let Some(max) = self.limits.max_memory_bytes else {
+            return Ok(());
+        };
+        let bytes = batch.get_array_memory_size();
+        let total = self.memory.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        if total > max {
+            return Err(Error::ResourceLimitExceeded {
+                limit: ResourceLimit::Memory,
+                detail: format!("intermediate batches reached {total} bytes, limit {max} bytes"),
+            });
+        }
+        Ok(())
+    }
+
+    /// Fail if the result has more rows than allowed
+    pub(crate) fn check_output(&self, rows: usize) -> Result<()> {
+        match self.limits.max_output_rows {
+            Some(max) if rows > max => Err(Error::ResourceLimitExceeded {
+                limit: ResourceLimit::OutputRows,
+                detail: format!("result has {rows} rows, limit {max} rows"),
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::Int64Array;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    fn batch(rows: i64) -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![Field::new("v", DataType::Int64, false)]));
+        RecordBatch::try_new(schema, vec![Arc::new(Int64Array::from_iter_values(0..rows))]).unwrap()
+    }
+
+    #[test]
+    fn test_guard_trips_each_limit() {
+        let guard = LimitGuard::start(QueryLimits::new());
+        assert!(guard.check().is_ok());
+        assert!(guard.reserve(&batch(1000)).is_ok());
+        assert!(guard.check_output(usize::MAX).is_ok());
+
+        let one_batch = batch(1000).get_array_memory_size();
+        let limits = QueryLimits::new()
+            .with_max_execution_time(Duration::ZERO)
+            .with_max_output_rows(5)
+            .with_max_memory_bytes(one_batch);
+        let guard = LimitGuard::start(limits);
+        std::thread::sleep(Duration::from_millis(1));
+        let limit_of = |result: Result<()>| match result {
+            Err(Error::ResourceLimitExceeded { limit, .. }) => Some(limit),
+            _ => None,
+        };
+        assert_eq!(limit_of(guard.check()), Some(ResourceLimit::ExecutionTime));
+        assert_eq!(limit_of(guard.check_output(5)), None);
+        assert_eq!(limit_of(guard.check_output(6)), Some(ResourceLimit::OutputRows));
+        assert_eq!(limit_of(guard.reserve(&batch(1000))), None);
+        assert_eq!(limit_of(guard.reserve(&batch(1))), Some(ResourceLimit::Memory));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_guard_stops_cancelled_queries() {
+        let token = CancellationToken::new();
+        let guard = LimitGuard::start(QueryLimits::new()).with_cancellation(token.clone());
+        assert!(guard.check().is_ok());
+        token.cancel();
+        assert!(matches!(guard.check(), Err(Error::Cancelled(_))));
+    }
+}
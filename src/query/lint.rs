@@ -0,0 +1,202 @@
+//! Query linting (static analysis of parsed plans)
+//!
+//! Lints flag queries that run correctly but waste work, so users can
+//! reshape them before they hit production data. They never fail a query:
+//! [`QueryExecutor::lint`](super::QueryExecutor::lint) returns them, and
+//! [`QueryExecutor::explain`](super::QueryExecutor::explain) appends them as
+//! `Warning:` lines.
+//!
+//! | Lint | Pattern |
+//! |------|---------|
+//! | [`Lint::SelectStar`] | `SELECT *` on a table wider than [`WIDE_TABLE_COLUMNS`] |
+//! | [`Lint::SortWithoutLimit`] | `ORDER BY` without `LIMIT` (full sort, no Top-K) |
+//! | [`Lint::UnsortedFilter`] | `WHERE` on a column not stored in sorted order |
+//!
+//! Toyota Way: Jidoka (surface the problem, let the user decide)
+
+use super::binder::split_filter;
+use super::QueryPlan;
+use crate::storage::StorageEngine;
+use arrow::array::{Array, RecordBatch};
+use arrow::row::{OwnedRow, RowConverter, SortField};
+use std::fmt;
+
+/// Tables with more columns than this are "wide" for [`Lint::SelectStar`]
+pub const WIDE_TABLE_COLUMNS: usize = 16;
+
+/// Kinds of query lint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    /// `SELECT *` on a wide table: every column is materialized and transferred
+    SelectStar,
+    /// `ORDER BY` without `LIMIT`: sorts every row instead of a Top-K selection
+    SortWithoutLimit,
+    /// `WHERE` on a column that is neither indexed nor stored sorted:
+    /// every row is scanned
+    UnsortedFilter,
+}
+
+impl Lint {
+    /// Stable snake-case name (`select_star`, ...)
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::SelectStar => "select_star",
+            Self::SortWithoutLimit => "sort_without_limit",
+            Self::UnsortedFilter => "unsorted_filter",
+        }
+    }
+}
+
+/// One lint finding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintWarning {
+    /// What was found
+    pub lint: Lint,
+    /// Explanation and suggested fix
+    pub message: String,
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.lint.name(), self.message)
+    }
+}
+
+/// Lint one SELECT (no CTEs)
+///
+/// `storage` is the table the plan reads; pass `None` when it is not known
+/// up front (e.g. a CTE), which skips the checks that need its data.
+pub(crate) fn check(plan: &QueryPlan, storage: Option<&StorageEngine>) -> Vec<LintWarning> {
+    let mut warnings = Vec::new();
+    let schema = storage.and_then(|storage| storage.batches().first()).map(RecordBatch::schema);
+
+    if let Some(schema) = &schema {
+        let width = schema.fields().len();
+        if plan.columns.iter().any(|column| column == "*")
+            && plan.aggregations.is_empty()
+            && width > WIDE_TABLE_COLUMNS
+        {
+            warnings.push(LintWarning {
+                lint: Lint::SelectStar,
+                message: format!(
+                    "SELECT * reads all {width} columns of {}; list only the columns you need",
+                    plan.table
+                ),
+            });
+        }
+    }
+
+    if let (Some((column, _)), None) = (plan.order_by.first(), plan.limit) {
+        warnings.push(LintWarning {
+            lint: Lint::SortWithoutLimit,
+            message: format!(
+                "ORDER BY {column} without LIMIT sorts every row; add a LIMIT to use Top-K selection"
+            ),
+        });
+    }
+
+    if let (Some(filter), Some(storage), Some(schema)) = (&plan.filter, storage, &schema) {
+        // Comparison filters only ("col op value"); bare Boolean flags are cheap
+        let parts = split_filter(filter);
+        if parts.len() >= 3 {
+            let column = &parts[0];
+            if let Ok(index) = schema.index_of(column) {
+                if !is_sorted(storage, index) {
+                    warnings.push(LintWarning {
+                        lint: Lint::UnsortedFilter,
+                        message: format!(
+                            "WHERE on {column}, which is not indexed or stored sorted, scans \
+                             every row; load the data sorted by {column} if it is filtered often"
+                        ),
+                    });
+                }
+            }
+        }
+    }
+
+    warnings
+}
+
+/// Whether column `index` is in ascending order across all batches
+fn is_sorted(storage: &StorageEngine, index: usize) -> bool {
+    let Some(first) = storage.batches().first() else {
+        return true;
+    };
+    let data_type = first.column(index).data_type().clone();
+    let Ok(converter) = RowConverter::new(vec![SortField::new(data_type)]) else {
+        // Unorderable type: nothing to suggest
+        return true;
+    };
+
+    let mut last: Option<OwnedRow> = None;
+    for batch in storage.batches() {
+        let column = batch.column(index);
+        if column.is_empty() {
+            continue;
+        }
+        let Ok(rows) = converter.convert_columns(std::slice::from_ref(column)) else {
+            return true;
+        };
+        if last.as_ref().is_some_and(|last| last.row() > rows.row(0)) {
+            return false;
+        }
+        let mut previous = rows.row(0);
+        for row in rows.iter().skip(1) {
+            if previous > row {
+                return false;
+            }
+            previous = row;
+        }
+        last = Some(previous.owned());
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::QueryEngine;
+    use arrow::array::{Int32Array, RecordBatch};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    fn storage(batches: &[Vec<i32>]) -> StorageEngine {
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+        StorageEngine::new(
+            batches
+                .iter()
+                .map(|ids| {
+                    RecordBatch::try_new(
+                        schema.clone(),
+                        vec![Arc::new(Int32Array::from(ids.clone()))],
+                    )
+                    .unwrap()
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_is_sorted_across_batches() {
+        assert!(is_sorted(&storage(&[vec![1, 2, 2], vec![], vec![3, 9]]), 0));
+        assert!(!is_sorted(&storage(&[vec![1, 3, 2]]), 0));
+        assert!(!is_sorted(&storage(&[vec![1, 5], vec![4, 6]]), 0));
+    }
+
+    #[test]
+    fn test_lints() {
+        let engine = QueryEngine::new();
+        let lints = |sql: &str, storage: &StorageEngine| -> Vec<Lint> {
+            check(&engine.parse(sql).unwrap(), Some(storage)).iter().map(|w| w.lint).collect()
+        };
+        let sorted = storage(&[vec![1, 2, 3]]);
+        let unsorted = storage(&[vec![3, 1, 2]]);
+
+        assert!(lints("SELECT id FROM t WHERE id > 1 ORDER BY id LIMIT 2", &sorted).is_empty());
+        assert_eq!(lints("SELECT id FROM t ORDER BY id", &sorted), vec![Lint::SortWithoutLimit]);
+        assert_eq!(lints("SELECT id FROM t WHERE id = 2", &unsorted), vec![Lint::UnsortedFilter]);
+        // One column is not wide
+        assert!(lints("SELECT * FROM t", &sorted).is_empty());
+    }
+}
@@ -0,0 +1,241 @@
+//! Late materialization (Abadi et al. 2007)
+//!
+//! The executor used to copy whole rows at every step: WHERE filtered every
+//! column of the table, and ORDER BY ... LIMIT then ranked (and copied
+//! again) every column of every surviving row, although most queries read a
+//! few columns of a wide table. Instead:
+//!
+//! 1. **Filter**: the predicate yields a [`SelectionVector`] of row ids
+//!    ([`select`]).
+//! 2. **Top-K**: a projection with ORDER BY ranks a batch of just its sort
+//!    keys at the selected rows, and keeps the row ids of the winners
+//!    ([`top_k`]).
+//! 3. **Gather**: only the columns the rest of the plan reads (projected
+//!    columns, computed-expression inputs, GROUP BY keys, aggregate inputs)
+//!    are copied, and only at the surviving row ids ([`gather`]).
+//!
+//! Columns are resolved by name before the plan runs; when one cannot be
+//! (a `*` projection, an expression the binder cannot analyze), every
+//! column is gathered, so results never depend on this optimization.
+//!
+//! Toyota Way: Muda elimination (never copy a value the query does not read)
+
+use super::access::WILDCARD;
+use super::binder;
+use super::executor::QueryExecutor;
+use super::selection::SelectionVector;
+use super::{OrderDirection, QueryPlan};
+use crate::topk::{self, SortOrder, TieBreak};
+use crate::{Error, Result};
+use arrow::record_batch::RecordBatch;
+
+/// Rows of `batch` passing the WHERE clause of `plan` (all of them without
+/// one)
+///
+/// # Errors
+/// Returns error if the filter cannot be evaluated on `batch`
+pub(super) fn select(batch: &RecordBatch, plan: &QueryPlan) -> Result<SelectionVector> {
+    plan.filter.as_deref().map_or_else(
+        || Ok(SelectionVector::All(batch.num_rows())),
+        |filter| SelectionVector::filter(batch, filter),
+    )
+}
+
+/// Rows of `batch` passing the WHERE clause of `plan`, holding only the
+/// columns the rest of the plan reads
+///
+/// # Errors
+/// Returns error if the filter cannot be evaluated on `batch`
+pub(super) fn filter(batch: &RecordBatch, plan: &QueryPlan) -> Result<RecordBatch> {
+    gather(batch, plan, &select(batch, plan)?)
+}
+
+/// The columns of `batch` that `plan` reads after WHERE, at the selected
+/// rows only
+///
+/// # Errors
+/// Returns error if the columns cannot be gathered
+pub(super) fn gather(
+    batch: &RecordBatch,
+    plan: &QueryPlan,
+    selection: &SelectionVector,
+) -> Result<RecordBatch> {
+    let narrowed = match read_columns(batch, plan) {
+        Some(indices) if indices.len() < batch.num_columns() => batch
+            .project(&indices)
+            .map_err(|e| Error::StorageError(format!("Failed to gather columns: {e}")))?,
+        _ => batch.clone(),
+    };
+    selection.gather(&narrowed)
+}
+
+/// Rows of the ORDER BY ... LIMIT result of a projection over the selected
+/// rows, ranking only the sort keys
+///
+/// `None` when `plan` is not a projection with ORDER BY, or a sort key is
+/// not an output column; the caller then sorts the projected rows, which
+/// also reports any error.
+///
+/// # Errors
+/// Returns error if the sort keys cannot be gathered, evaluated or ranked
+pub(super) fn top_k(
+    batch: &RecordBatch,
+    plan: &QueryPlan,
+    selection: &SelectionVector,
+) -> Result<Option<SelectionVector>> {
+    if plan.order_by.is_empty() || !plan.aggregations.is_empty() || !plan.group_by.is_empty() {
+        return Ok(None);
+    }
+    // As in `apply_order_by_limit`, keys name columns of the projection
+    let wildcard = plan.columns.len() == 1 && plan.columns[0] == WILDCARD;
+    let schema = batch.schema();
+    let is_output = |key: &String| {
+        if wildcard {
+            schema.index_of(key).is_ok()
+        } else {
+            plan.columns.contains(key)
+        }
+    };
+    if !plan.order_by.iter().all(|(key, _)| is_output(key)) {
+        return Ok(None);
+    }
+    // OFFSET rows are ranked too; the caller skips them afterwards
+    let k = plan.fetch().unwrap_or_else(|| selection.len());
+    if k == 0 {
+        return Ok(None);
+    }
+
+    let key_plan = QueryPlan {
+        columns: plan.order_by.iter().map(|(key, _)| key.clone()).collect(),
+        ..plan.clone()
+    };
+    let keys = QueryExecutor::project_columns(&gather(batch, &key_plan, selection)?, &key_plan)?;
+    let orders: Vec<(usize, SortOrder)> = plan
+        .order_by
+        .iter()
+        .enumerate()
+        .map(|(index, (_, direction))| match direction {
+            OrderDirection::Asc => (index, SortOrder::Ascending),
+            OrderDirection::Desc => (index, SortOrder::Descending),
+        })
+        .collect();
+    let ((column_index, order), rest) = (orders[0], &orders[1..]);
+    let ties = if rest.is_empty() { TieBreak::RowIndex } else { TieBreak::Columns(rest.to_vec()) };
+
+    let positions = topk::top_k_indices(&keys, column_index, k, order, &ties)?;
+    Ok(Some(selection.pick(&positions)))
+}
+
+/// Indices of the columns of `batch` that `plan` reads after WHERE, in
+/// schema order (`None`: all of them)
+fn read_columns(batch: &RecordBatch, plan: &QueryPlan) -> Option<Vec<usize>> {
+    let schema = batch.schema();
+    let stored = |name: &str| schema.index_of(name).is_ok();
+    let computed =
+        |name: &str| plan.computed.iter().find(|(output, _)| output == name).map(|(_, expr)| expr);
+    // A stored column, else a computed SELECT item, else an expression
+    let inputs = |name: &str| -> Vec<String> {
+        if stored(name) {
+            vec![name.to_string()]
+        } else if let Some(expr) = computed(name) {
+            binder::expression_columns(expr)
+        } else {
+            binder::expression_columns(name)
+        }
+    };
+
+    let mut names = Vec::new();
+    let mut count_star = false;
+    if plan.aggregations.is_empty() && plan.group_by.is_empty() {
+        for column in &plan.columns {
+            names.extend(inputs(column));
+        }
+    } else {
+        for key in &plan.group_by {
+            names.extend(inputs(key));
+        }
+        for (_, argument, _) in &plan.aggregations {
+            if argument == WILDCARD {
+                count_star = true;
+            } else {
+                names.extend(inputs(argument));
+            }
+        }
+    }
+
+    let mut indices = names
+        .iter()
+        .map(|name| if name == WILDCARD { None } else { schema.index_of(name).ok() })
+        .collect::<Option<Vec<usize>>>()?;
+    // `COUNT(*)` counts the rows of the first column; a batch needs one to keep them
+    if count_star || indices.is_empty() {
+        indices.push(0);
+    }
+    indices.sort_unstable();
+    indices.dedup();
+    (indices.iter().all(|&index| index < batch.num_columns())).then_some(indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::QueryEngine;
+    use arrow::array::{Int32Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    fn wide_batch() -> RecordBatch {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, false),
+            Field::new("score", DataType::Int32, true),
+            Field::new("note", DataType::Utf8, false),
+        ]);
+        RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3, 4, 5])),
+                Arc::new(StringArray::from(vec!["a", "b", "c", "d", "e"])),
+                Arc::new(Int32Array::from(vec![Some(50), None, Some(70), Some(20), Some(90)])),
+                Arc::new(StringArray::from(vec!["x"; 5])),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn parse(sql: &str) -> QueryPlan {
+        QueryEngine::new().parse(sql).unwrap()
+    }
+
+    #[test]
+    fn test_filter_gathers_only_read_columns() {
+        let batch = wide_batch();
+        let plan = parse("SELECT name, score * 2 AS doubled FROM t WHERE id > 2");
+        let filtered = filter(&batch, &plan).unwrap();
+        let schema = filtered.schema();
+        let names: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+        assert_eq!(names, vec!["name", "score"]);
+        assert_eq!(filtered.num_rows(), 3);
+
+        // NULL predicates drop the row
+        let selection = select(&batch, &parse("SELECT id FROM t WHERE score > 10")).unwrap();
+        assert_eq!(selection, SelectionVector::Rows(vec![0, 2, 3, 4].into()));
+
+        let counted = parse("SELECT COUNT(*), SUM(score) FROM t");
+        assert_eq!(read_columns(&batch, &counted), Some(vec![0, 2]));
+        assert_eq!(read_columns(&batch, &parse("SELECT * FROM t")), None);
+    }
+
+    #[test]
+    fn test_top_k_ranks_keys_and_returns_row_ids() {
+        let batch = wide_batch();
+        let plan = parse("SELECT name, score FROM t WHERE id > 1 ORDER BY score DESC LIMIT 2");
+        let selection = select(&batch, &plan).unwrap();
+        let top = top_k(&batch, &plan, &selection).unwrap().unwrap();
+        assert_eq!(top, SelectionVector::Rows(vec![4, 2].into()));
+
+        // Keys outside the projection are left to the executor (and its error)
+        let hidden = parse("SELECT name FROM t ORDER BY score");
+        assert!(top_k(&batch, &hidden, &SelectionVector::All(5)).unwrap().is_none());
+    }
+}
@@ -7,35 +7,140 @@
 //! ## Phase 1 SQL Subset
 //!
 //! Supports analytics workload (OLAP):
-//! - SELECT with column list or *
-//! - FROM single table (no JOINs in Phase 1)
-//! - WHERE with simple predicates (>, <, =, >=, <=, !=)
-//! - GROUP BY with aggregations (SUM, AVG, COUNT, MIN, MAX)
+//! - SELECT with column list or *, column aliases, and arithmetic
+//!   (`+ - * / %`), `ABS`, `ROUND`, `COALESCE`, `CAST`, `date_trunc` and
+//!   `EXTRACT`/`date_part` expressions (`SELECT price * quantity AS
+//!   revenue`), also as aggregate arguments (`SUM(price * quantity)`)
+//! - FROM a table, optionally INNER joined with others on column equality
+//!   (`JOIN t ON a = b [AND c = d]`), executed as a hash join
+//! - WITH (non-recursive CTEs, materialized as query-scoped temporary tables)
+//! - Uncorrelated subqueries in WHERE: scalar (`value > (SELECT AVG(value)
+//!   FROM t)`) and `[NOT] IN (SELECT ...)`, each run once before the outer
+//!   query and bound into its predicate as literals
+//! - WHERE with comparisons (>, <, =, >=, <=, !=) against literals or
+//!   other columns, combined with AND/OR/NOT and parentheses, plus
+//!   `[NOT] BETWEEN`, `[NOT] IN (...)`, `IS [NOT] NULL`, and bare Boolean
+//!   columns (`WHERE flag`); string columns support `=`, `!=`, `IN` and
+//!   `LIKE`/`ILIKE`; date and timestamp columns compare with ISO-8601
+//!   literals (`WHERE ts >= '2024-03-01'`). NULLs follow SQL three-valued logic
+//! - GROUP BY with aggregations (SUM, AVG, COUNT, MIN, MAX, `COUNT_IF`,
+//!   `VAR_SAMP`/`VARIANCE`, `VAR_POP`, `STDDEV_SAMP`/`STDDEV`, `STDDEV_POP`);
+//!   `SUM(CASE WHEN flag THEN 1 ELSE 0 END)` runs as `COUNT_IF(flag)`.
+//!   MIN/MAX also take date and timestamp columns.
+//!   Approximate aggregates run over mergeable sketches (see
+//!   [`sketch`](crate::sketch)): `APPROX_COUNT_DISTINCT(x)` (`HyperLogLog`)
+//!   and `APPROX_QUANTILE(x, q)` / `APPROX_MEDIAN(x)` (t-digest). Exact
+//!   `PERCENTILE_CONT(x, q)` / `MEDIAN(x)` interpolate between the nearest
+//!   ranks of numeric columns, selected with Top-K.
+//!   Grouped queries hash one or more key columns of any type and return
+//!   the key columns followed by the aggregates, one row per group. Keys
+//!   may be expressions or SELECT aliases, for time buckets such as
+//!   `SELECT date_trunc('hour', ts) AS hour, COUNT(*) ... GROUP BY hour`.
+//!   Repeated aggregates (e.g. `SUM(x)` and `SUM(x) AS total`) are
+//!   evaluated once per query and their result reused, as are repeated
+//!   subexpressions of SELECT items and of each WHERE term
 //! - ORDER BY (ASC/DESC)
-//! - LIMIT
+//! - LIMIT and OFFSET (`LIMIT 20 OFFSET 40` ranks the top 60 rows with
+//!   Top-K, then skips 40)
+//! - Quoted identifiers (`"order"`, `"unit price"`) bound the same as
+//!   unquoted ones; parser dialect chosen via [`QueryEngine::with_dialect`]
+//! - `EXPLAIN SELECT ...`: executing the plan returns its operators with
+//!   estimated rows, FLOPs, backend and morsels instead of running it
+//!   (see [`explain`])
+//! - Optional table/column access checks at bind time
+//!   ([`QueryEngine::with_access_policy`], [`QueryEngine::parse_as`])
+//! - Optional per-query time, output row and memory limits
+//!   ([`QueryExecutor::with_limits`], see [`limits`])
+//! - Optional LRU cache of parsed plans keyed by SQL text
+//!   ([`QueryEngine::with_plan_cache`], see [`plan_cache`])
+//! - Parsed plans are optimized: constant WHERE subexpressions folded,
+//!   always-true/false predicates eliminated, ORDER BY + LIMIT pushed into
+//!   CTEs as Top-K, repeated aggregates shared (see [`optimizer`])
+//! - Plans list the columns they read ([`QueryPlan::required_columns`]), so
+//!   Parquet scans decode only those ([`QueryExecutor::execute_parquet`])
 //!
 //! References:
 //! - sqlparser-rs: <https://docs.rs/sqlparser>
 //! - TPC-H queries: Analytics benchmark patterns
 
+pub mod access;
+mod approx;
+pub(crate) mod binder;
 pub mod executor;
+pub mod explain;
+pub mod fallback;
+mod group_by;
+mod join;
+pub mod limits;
+pub mod lint;
+mod materialize;
+pub mod optimizer;
+pub mod parallel;
+pub mod plan_cache;
+mod predicate;
+mod pruning;
+pub mod rows;
+mod scalar;
+mod selection;
+pub mod stats;
+pub mod stream;
+mod subquery;
 
 pub use executor::QueryExecutor;
-
+pub use fallback::{BackendFailure, ExecutionBackend, FallbackExecutor, FallbackResult};
+pub use limits::{QueryLimits, ResourceLimit};
+pub use lint::{Lint, LintWarning};
+pub use optimizer::Rewrite;
+pub use plan_cache::{PlanCache, PlanCacheStats};
+pub use rows::{RowAccessor, RowRef};
+pub use stats::{ExecutionReport, OperatorReport, QueryStats};
+pub use stream::ResultStream;
+
+use crate::variance::VarianceKind;
+use access::{AccessContext, AccessPolicy, ANONYMOUS_PRINCIPAL};
 use sqlparser::ast::{Expr, Query, Select, SelectItem, SetExpr, Statement};
-use sqlparser::dialect::GenericDialect;
+use sqlparser::dialect::{AnsiDialect, DuckDbDialect, GenericDialect};
 use sqlparser::parser::Parser;
+use std::sync::Arc;
 
 /// Type alias for aggregation tuple (function, column, optional alias)
 pub type Aggregation = (AggregateFunction, String, Option<String>);
 
+/// Type alias for a computed SELECT item (output name, canonical expression)
+pub type ComputedColumn = (String, String);
+
+/// Type alias for a common table expression (name, defining query)
+pub type CommonTableExpr = (String, QueryPlan);
+
+/// Type alias for a WHERE subquery (SQL text as written in the filter, plan)
+pub type Subquery = (String, QueryPlan);
+
+/// Inner equi-join of the rows read so far with another table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JoinClause {
+    /// Joined table
+    pub table: String,
+    /// Alias the query gives the joined table (`JOIN customers c`)
+    pub alias: Option<String>,
+    /// Equality conditions: (column of the rows read so far, column of `table`),
+    /// possibly qualified (`o.customer_id`)
+    pub on: Vec<(String, String)>,
+}
+
 /// Parsed SQL query with extracted components
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct QueryPlan {
-    /// Selected columns (or * for all)
+    /// Selected columns (or * for all), by output name
     pub columns: Vec<String>,
+    /// SELECT items that are expressions or renamed columns
+    /// (`price * quantity AS revenue`), evaluated during projection
+    pub computed: Vec<ComputedColumn>,
     /// Table name
     pub table: String,
+    /// Alias of the FROM table (`FROM orders o`)
+    pub table_alias: Option<String>,
+    /// INNER JOINs, applied in order after reading `table`
+    pub joins: Vec<JoinClause>,
     /// WHERE clause expression (optional)
     pub filter: Option<String>,
     /// GROUP BY columns (optional)
@@ -46,10 +151,132 @@ pub struct QueryPlan {
     pub order_by: Vec<(String, OrderDirection)>,
     /// LIMIT count (optional)
     pub limit: Option<usize>,
+    /// OFFSET: rows skipped before LIMIT applies (0 without OFFSET)
+    pub offset: usize,
+    /// WITH clause definitions, in declaration order
+    pub ctes: Vec<CommonTableExpr>,
+    /// Uncorrelated subqueries of the WHERE clause, one per distinct SQL
+    /// text; run before the query and bound into `filter` as literals
+    pub subqueries: Vec<Subquery>,
+    /// `EXPLAIN` prefix: executing the plan describes it instead of running it
+    pub explain: bool,
+    /// Rewrites the optimizer applied, shown by EXPLAIN (see [`optimizer`])
+    pub rewrites: Vec<Rewrite>,
+    /// Columns this SELECT reads from its FROM and JOIN tables, sorted;
+    /// `None` when every column may be needed (`*` or an expression the
+    /// binder cannot analyze)
+    pub scan_columns: Option<Vec<String>>,
+}
+
+impl QueryPlan {
+    /// Stored table the FROM clause reads, following CTE references
+    /// (`WITH recent AS (SELECT * FROM events) ... FROM recent` reads `events`)
+    #[must_use]
+    pub fn source_table(&self) -> &str {
+        let mut table = self.table.as_str();
+        while let Some((_, cte)) = self.ctes.iter().find(|(name, _)| name == table) {
+            table = cte.table.as_str();
+        }
+        table
+    }
+
+    /// Columns of [`source_table`](Self::source_table) the query needs, for
+    /// column-pruned scans (see
+    /// [`StorageEngine::load_parquet_with_projection`](crate::storage::StorageEngine::load_parquet_with_projection))
+    ///
+    /// Covers every SELECT that reads the table: the query itself, its CTEs
+    /// and its subqueries. `None` means read every column: a wildcard or
+    /// unanalyzable expression, a JOIN, a second stored table, or only
+    /// `COUNT(*)`, which still needs rows.
+    ///
+    /// # Example
+    /// ```
+    /// use trueno_db::query::QueryEngine;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let plan = QueryEngine::new()
+    ///     .parse("SELECT category, SUM(value) FROM events WHERE value > 10 GROUP BY category")?;
+    /// assert_eq!(plan.required_columns(), Some(vec!["category".to_string(), "value".to_string()]));
+    /// assert_eq!(QueryEngine::new().parse("SELECT * FROM events")?.required_columns(), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn required_columns(&self) -> Option<Vec<String>> {
+        if self.base_tables().len() > 1 {
+            return None;
+        }
+        let mut columns = Vec::new();
+        self.collect_required_columns(&[], &mut columns)?;
+        columns.sort();
+        columns.dedup();
+        (!columns.is_empty()).then_some(columns)
+    }
+
+    fn collect_required_columns(
+        &self,
+        outer_ctes: &[&str],
+        columns: &mut Vec<String>,
+    ) -> Option<()> {
+        if !self.joins.is_empty() {
+            return None;
+        }
+        let mut ctes = outer_ctes.to_vec();
+        for (name, cte) in &self.ctes {
+            cte.collect_required_columns(&ctes, columns)?;
+            ctes.push(name.as_str());
+        }
+        for (_, subquery) in &self.subqueries {
+            subquery.collect_required_columns(&ctes, columns)?;
+        }
+        if !ctes.contains(&self.table.as_str()) {
+            columns.extend(self.scan_columns.clone()?);
+        }
+        Some(())
+    }
+
+    /// Rows ORDER BY keeps before OFFSET skips some: LIMIT + OFFSET
+    /// (`None` without LIMIT)
+    pub(crate) const fn fetch(&self) -> Option<usize> {
+        match self.limit {
+            Some(limit) => Some(limit.saturating_add(self.offset)),
+            None => None,
+        }
+    }
+
+    /// Stored tables the query reads (FROM and JOINs, including inside
+    /// CTEs and subqueries; CTE names themselves excluded), in first-seen
+    /// order
+    #[must_use]
+    pub fn base_tables(&self) -> Vec<&str> {
+        let mut tables = Vec::new();
+        self.collect_base_tables(&[], &mut tables);
+        tables
+    }
+
+    fn collect_base_tables<'a>(&'a self, outer_ctes: &[&str], tables: &mut Vec<&'a str>) {
+        let mut ctes = outer_ctes.to_vec();
+        for (name, cte) in &self.ctes {
+            // A CTE sees the ones declared before it
+            cte.collect_base_tables(&ctes, tables);
+            ctes.push(name.as_str());
+        }
+        let read = std::iter::once(self.table.as_str())
+            .chain(self.joins.iter().map(|join| join.table.as_str()));
+        for table in read {
+            if !ctes.contains(&table) && !tables.contains(&table) {
+                tables.push(table);
+            }
+        }
+        // WHERE subqueries come after FROM and JOIN in the query text
+        for (_, subquery) in &self.subqueries {
+            subquery.collect_base_tables(&ctes, tables);
+        }
+    }
 }
 
 /// Supported aggregation functions
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum AggregateFunction {
     /// Sum of values
     Sum,
@@ -61,6 +288,80 @@ pub enum AggregateFunction {
     Min,
     /// Maximum value
     Max,
+    /// Count of rows where a Boolean column is true
+    CountIf,
+    /// Sample variance (`VAR_SAMP`, `VARIANCE`)
+    VarSamp,
+    /// Population variance (`VAR_POP`)
+    VarPop,
+    /// Sample standard deviation (`STDDEV_SAMP`, `STDDEV`)
+    StddevSamp,
+    /// Population standard deviation (`STDDEV_POP`)
+    StddevPop,
+    /// Estimated number of distinct non-null values (`APPROX_COUNT_DISTINCT`)
+    ApproxCountDistinct,
+    /// Estimated quantile of non-null numeric values (`APPROX_QUANTILE`,
+    /// `APPROX_MEDIAN`)
+    ApproxQuantile(Quantile),
+    /// Exact interpolated percentile of non-null numeric values
+    /// (`PERCENTILE_CONT`, `MEDIAN`)
+    PercentileCont(Quantile),
+}
+
+/// Fraction `q` in `[0, 1]` of an `APPROX_QUANTILE` or `PERCENTILE_CONT`,
+/// held in millionths so aggregates stay `Eq` and `Hash`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Quantile(u32);
+
+impl Quantile {
+    /// The median (`MEDIAN`, `APPROX_MEDIAN`)
+    pub const MEDIAN: Self = Self(500_000);
+
+    /// Quantile at fraction `q` (`None` outside `[0, 1]`), rounded to the
+    /// nearest millionth
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn new(q: f64) -> Option<Self> {
+        (0.0..=1.0).contains(&q).then(|| Self((q * 1e6).round() as u32))
+    }
+
+    /// The fraction
+    #[must_use]
+    pub fn fraction(self) -> f64 {
+        f64::from(self.0) / 1e6
+    }
+}
+
+impl AggregateFunction {
+    /// SQL function name (`SUM`, `COUNT_IF`, ...)
+    #[must_use]
+    pub const fn sql_name(self) -> &'static str {
+        match self {
+            Self::Sum => "SUM",
+            Self::Avg => "AVG",
+            Self::Count => "COUNT",
+            Self::Min => "MIN",
+            Self::Max => "MAX",
+            Self::CountIf => "COUNT_IF",
+            Self::VarSamp => "VAR_SAMP",
+            Self::VarPop => "VAR_POP",
+            Self::StddevSamp => "STDDEV_SAMP",
+            Self::StddevPop => "STDDEV_POP",
+            Self::ApproxCountDistinct => "APPROX_COUNT_DISTINCT",
+            Self::ApproxQuantile(_) => "APPROX_QUANTILE",
+            Self::PercentileCont(_) => "PERCENTILE_CONT",
+        }
+    }
+
+    /// Estimator for variance/stddev aggregates (`None` for the others)
+    #[must_use]
+    pub const fn variance_kind(self) -> Option<VarianceKind> {
+        match self {
+            Self::VarSamp | Self::StddevSamp => Some(VarianceKind::Sample),
+            Self::VarPop | Self::StddevPop => Some(VarianceKind::Population),
+            _ => None,
+        }
+    }
 }
 
 /// Sort order direction
@@ -72,9 +373,29 @@ pub enum OrderDirection {
     Desc,
 }
 
+/// SQL dialect accepted by [`QueryEngine`]
+///
+/// Identifier binding is the same for every dialect: quotes are stripped and
+/// case is preserved, so `"Total"` and `Total` name the same column. What the
+/// dialect changes is which syntax the parser accepts (e.g. backtick quoting
+/// in `Generic`, DuckDB-specific literals and functions in `DuckDb`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SqlDialect {
+    /// Permissive dialect accepting most common syntax (default)
+    #[default]
+    Generic,
+    /// ANSI SQL: double-quoted identifiers only
+    Ansi,
+    /// DuckDB-compatible syntax
+    DuckDb,
+}
+
 /// Query parser and executor
 pub struct QueryEngine {
-    dialect: GenericDialect,
+    dialect: SqlDialect,
+    policy: Option<Arc<dyn AccessPolicy>>,
+    plan_cache: Option<PlanCache>,
+    optimize: bool,
 }
 
 impl Default for QueryEngine {
@@ -87,7 +408,83 @@ impl QueryEngine {
     /// Create a new query engine
     #[must_use]
     pub const fn new() -> Self {
-        Self { dialect: GenericDialect {} }
+        Self::with_dialect(SqlDialect::Generic)
+    }
+
+    /// Create a query engine that parses with the given dialect
+    ///
+    /// # Example
+    /// ```
+    /// use trueno_db::query::{QueryEngine, SqlDialect};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let engine = QueryEngine::with_dialect(SqlDialect::Ansi);
+    /// let plan = engine.parse(r#"SELECT "order" FROM sales"#)?;
+    /// assert_eq!(plan.columns, vec!["order"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub const fn with_dialect(dialect: SqlDialect) -> Self {
+        Self { dialect, policy: None, plan_cache: None, optimize: true }
+    }
+
+    /// Apply the logical rewrites of [`optimizer`] to parsed plans (on by
+    /// default); turning them off returns plans exactly as written
+    #[must_use]
+    pub const fn with_optimizer(mut self, enabled: bool) -> Self {
+        self.optimize = enabled;
+        self
+    }
+
+    /// Check every table and column a query reads against `policy` while
+    /// binding (see [`access`])
+    #[must_use]
+    pub fn with_access_policy(mut self, policy: Arc<dyn AccessPolicy>) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Keep the plans of the last `capacity` distinct statements, so
+    /// re-running a query skips parsing (see [`plan_cache`])
+    ///
+    /// # Example
+    /// ```
+    /// use trueno_db::query::QueryEngine;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let engine = QueryEngine::new().with_plan_cache(64);
+    /// for _ in 0..3 {
+    ///     engine.parse("SELECT region, SUM(amount) FROM sales GROUP BY region")?;
+    /// }
+    /// let stats = engine.plan_cache_stats().unwrap();
+    /// assert_eq!((stats.hits, stats.misses), (2, 1));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_plan_cache(mut self, capacity: usize) -> Self {
+        self.plan_cache = Some(PlanCache::new(capacity));
+        self
+    }
+
+    /// Plan cache counters, if a cache is configured
+    #[must_use]
+    pub fn plan_cache_stats(&self) -> Option<PlanCacheStats> {
+        self.plan_cache.as_ref().map(PlanCache::stats)
+    }
+
+    /// Drop every cached plan (e.g. after the access policy's rules change)
+    pub fn clear_plan_cache(&self) {
+        if let Some(cache) = &self.plan_cache {
+            cache.clear();
+        }
+    }
+
+    /// Dialect used by [`parse`](Self::parse)
+    #[must_use]
+    pub const fn dialect(&self) -> SqlDialect {
+        self.dialect
     }
 
     /// Parse SQL query into query plan
@@ -109,51 +506,118 @@ impl QueryEngine {
     /// # Ok(())
     /// # }
     pub fn parse(&self, sql: &str) -> crate::Result<QueryPlan> {
+        self.parse_as(sql, ANONYMOUS_PRINCIPAL)
+    }
+
+    /// Parse SQL on behalf of a session principal
+    ///
+    /// With an access policy set, every table and column the query reads is
+    /// checked for `principal`; without one this is the same as
+    /// [`parse`](Self::parse).
+    ///
+    /// # Errors
+    /// Returns error if parsing fails (see [`parse`](Self::parse)) or the
+    /// policy denies access to a referenced table or column
+    pub fn parse_as(&self, sql: &str, principal: &str) -> crate::Result<QueryPlan> {
+        self.plan_cache.as_ref().map_or_else(
+            || self.parse_sql(sql, principal),
+            |cache| cache.get_or_parse(sql, principal, || self.parse_sql(sql, principal)),
+        )
+    }
+
+    fn parse_sql(&self, sql: &str, principal: &str) -> crate::Result<QueryPlan> {
         // Handle empty query
         if sql.trim().is_empty() {
             return Ok(QueryPlan {
                 columns: vec!["*".to_string()],
+                computed: Vec::new(),
                 table: String::new(),
+                table_alias: None,
+                joins: Vec::new(),
                 filter: None,
                 group_by: Vec::new(),
                 aggregations: Vec::new(),
                 order_by: Vec::new(),
                 limit: None,
+                offset: 0,
+                ctes: Vec::new(),
+                subqueries: Vec::new(),
+                explain: false,
+                rewrites: Vec::new(),
+                scan_columns: None,
             });
         }
 
         // Parse SQL
-        let statements = Parser::parse_sql(&self.dialect, sql)
-            .map_err(|e| crate::Error::ParseError(format!("SQL parse error: {e}")))?;
+        let statements = match self.dialect {
+            SqlDialect::Generic => Parser::parse_sql(&GenericDialect {}, sql),
+            SqlDialect::Ansi => Parser::parse_sql(&AnsiDialect {}, sql),
+            SqlDialect::DuckDb => Parser::parse_sql(&DuckDbDialect {}, sql),
+        }
+        .map_err(|e| crate::Error::ParseError(format!("SQL parse error: {e}")))?;
 
         // Validate single statement
         if statements.len() != 1 {
             return Err(crate::Error::ParseError("Only single statements supported".to_string()));
         }
 
+        // Unwrap EXPLAIN; the plan is described rather than run
+        let (stmt, explain) = match &statements[0] {
+            Statement::Explain { analyze: true, .. } => {
+                return Err(crate::Error::ParseError(
+                    "EXPLAIN ANALYZE not supported (use QueryExecutor::execute_with_report)"
+                        .to_string(),
+                ));
+            }
+            Statement::Explain { statement, .. } => (statement.as_ref(), true),
+            stmt => (stmt, false),
+        };
+
         // Extract SELECT statement
-        let stmt = &statements[0];
         let Statement::Query(query) = stmt else {
             return Err(crate::Error::ParseError("Only SELECT queries supported".to_string()));
         };
 
-        Self::parse_select_query(query)
+        let access = AccessContext::new(self.policy.as_deref(), principal);
+        let mut plan = Self::parse_select_query(query, &access)?;
+        if self.optimize {
+            plan = optimizer::optimize(plan);
+        }
+        plan.explain = explain;
+        Ok(plan)
     }
 
-    fn parse_select_query(query: &Query) -> crate::Result<QueryPlan> {
+    fn parse_select_query(query: &Query, access: &AccessContext<'_>) -> crate::Result<QueryPlan> {
+        // Extract WITH clause (CTEs); their names stay in scope for this query only
+        let mut access = access.clone();
+        let ctes = Self::extract_ctes(query.with.as_ref(), &mut access)?;
+
         // Extract SELECT body
         let SetExpr::Select(select) = query.body.as_ref() else {
             return Err(crate::Error::ParseError("Only SELECT queries supported".to_string()));
         };
 
-        // Extract table name (FROM clause)
-        let table = Self::extract_table_name(select)?;
+        // Extract table name and joins (FROM clause)
+        let (table, table_alias, joins) = Self::extract_from(select)?;
+        let referenced = binder::referenced_columns(select, query.order_by.as_ref());
+        access.authorize(&table, &referenced)?;
+        for join in &joins {
+            access.authorize(&join.table, &referenced)?;
+        }
+        let scan_columns =
+            (!referenced.iter().any(|column| column == access::WILDCARD)).then_some(referenced);
 
         // Extract columns and aggregations
-        let (columns, aggregations) = Self::extract_columns(&select.projection)?;
+        let (columns, computed, aggregations) = Self::extract_columns(&select.projection)?;
 
-        // Extract WHERE clause
-        let filter = select.selection.as_ref().map(ToString::to_string);
+        // Extract WHERE clause; its subqueries are planned on their own
+        let filter = select.selection.as_ref().map(binder::filter);
+        let subqueries = match &select.selection {
+            Some(selection) => {
+                subquery::plans(selection, |query| Self::parse_select_query(query, &access))?
+            }
+            None => Vec::new(),
+        };
 
         // Extract GROUP BY
         let group_by = Self::extract_group_by(&select.group_by);
@@ -163,33 +627,110 @@ impl QueryEngine {
 
         // Extract LIMIT
         let limit = Self::extract_limit(query.limit.as_ref());
+        let offset = Self::extract_limit(query.offset.as_ref().map(|offset| &offset.value));
+
+        Ok(QueryPlan {
+            columns,
+            computed,
+            table,
+            table_alias,
+            joins,
+            filter,
+            group_by,
+            aggregations,
+            order_by,
+            limit,
+            offset: offset.unwrap_or(0),
+            ctes,
+            subqueries,
+            explain: false,
+            rewrites: Vec::new(),
+            scan_columns,
+        })
+    }
+
+    fn extract_ctes(
+        with: Option<&sqlparser::ast::With>,
+        access: &mut AccessContext<'_>,
+    ) -> crate::Result<Vec<CommonTableExpr>> {
+        let Some(with) = with else {
+            return Ok(Vec::new());
+        };
 
-        Ok(QueryPlan { columns, table, filter, group_by, aggregations, order_by, limit })
+        if with.recursive {
+            return Err(crate::Error::ParseError("Recursive CTEs not supported".to_string()));
+        }
+
+        with.cte_tables
+            .iter()
+            .map(|cte| {
+                if !cte.alias.columns.is_empty() {
+                    return Err(crate::Error::ParseError(format!(
+                        "CTE column lists not supported: {}",
+                        cte.alias
+                    )));
+                }
+                let plan = Self::parse_select_query(&cte.query, access)?;
+                access.push_cte(&cte.alias.name.value);
+                Ok((cte.alias.name.value.clone(), plan))
+            })
+            .collect()
     }
 
-    fn extract_table_name(select: &Select) -> crate::Result<String> {
+    fn extract_from(select: &Select) -> crate::Result<(String, Option<String>, Vec<JoinClause>)> {
         if select.from.is_empty() {
-            return Ok(String::new());
+            return Ok((String::new(), None, Vec::new()));
         }
 
         if select.from.len() > 1 {
             return Err(crate::Error::ParseError(
-                "Multiple tables not supported in Phase 1".to_string(),
+                "Multiple tables not supported in Phase 1 (use JOIN ... ON)".to_string(),
             ));
         }
 
         let table_with_joins = &select.from[0];
-        if !table_with_joins.joins.is_empty() {
-            return Err(crate::Error::ParseError("JOINs not supported in Phase 1".to_string()));
+        let joins =
+            table_with_joins.joins.iter().map(Self::extract_join).collect::<Result<_, _>>()?;
+
+        Ok((
+            binder::table_name(&table_with_joins.relation),
+            binder::table_alias(&table_with_joins.relation),
+            joins,
+        ))
+    }
+
+    fn extract_join(join: &sqlparser::ast::Join) -> crate::Result<JoinClause> {
+        use sqlparser::ast::{JoinConstraint, JoinOperator};
+
+        let JoinOperator::Inner(JoinConstraint::On(condition)) = &join.join_operator else {
+            return Err(crate::Error::ParseError(format!(
+                "Only INNER JOIN ... ON is supported: {join}"
+            )));
+        };
+
+        let table = binder::table_name(&join.relation);
+        let alias = binder::table_alias(&join.relation);
+        let qualifier = alias.as_deref().unwrap_or(&table);
+
+        let mut on = Vec::new();
+        binder::join_keys(condition, &mut on)?;
+        // Orient each pair as (rows read so far, joined table)
+        for (left, right) in &mut on {
+            let qualified_by_joined =
+                |name: &str| name.rsplit_once('.').is_some_and(|(prefix, _)| prefix == qualifier);
+            if qualified_by_joined(left) && !qualified_by_joined(right) {
+                std::mem::swap(left, right);
+            }
         }
 
-        Ok(table_with_joins.relation.to_string())
+        Ok(JoinClause { table, alias, on })
     }
 
     fn extract_columns(
         projection: &[SelectItem],
-    ) -> crate::Result<(Vec<String>, Vec<Aggregation>)> {
+    ) -> crate::Result<(Vec<String>, Vec<ComputedColumn>, Vec<Aggregation>)> {
         let mut columns = Vec::new();
+        let mut computed = Vec::new();
         let mut aggregations = Vec::new();
 
         for item in projection {
@@ -198,17 +739,22 @@ impl QueryEngine {
                     columns.push("*".to_string());
                 }
                 SelectItem::UnnamedExpr(expr) => {
-                    if let Some((func, col)) = Self::extract_aggregate(expr) {
+                    if let Some((func, col)) = Self::extract_aggregate(expr)? {
                         aggregations.push((func, col, None));
+                    } else if binder::is_column(expr) {
+                        columns.push(binder::column_name(expr));
                     } else {
-                        columns.push(expr.to_string());
+                        let expr = binder::filter(expr);
+                        columns.push(expr.clone());
+                        computed.push((expr.clone(), expr));
                     }
                 }
                 SelectItem::ExprWithAlias { expr, alias } => {
-                    if let Some((func, col)) = Self::extract_aggregate(expr) {
+                    if let Some((func, col)) = Self::extract_aggregate(expr)? {
                         aggregations.push((func, col, Some(alias.value.clone())));
                     } else {
                         columns.push(alias.value.clone());
+                        computed.push((alias.value.clone(), binder::filter(expr)));
                     }
                 }
                 SelectItem::QualifiedWildcard(..) => {
@@ -219,10 +765,10 @@ impl QueryEngine {
             }
         }
 
-        Ok((columns, aggregations))
+        Ok((columns, computed, aggregations))
     }
 
-    fn extract_aggregate(expr: &Expr) -> Option<(AggregateFunction, String)> {
+    fn extract_aggregate(expr: &Expr) -> crate::Result<Option<(AggregateFunction, String)>> {
         if let Expr::Function(func) = expr {
             let func_name = func.name.to_string().to_uppercase();
             let agg_func = match func_name.as_str() {
@@ -231,26 +777,96 @@ impl QueryEngine {
                 "COUNT" => AggregateFunction::Count,
                 "MIN" => AggregateFunction::Min,
                 "MAX" => AggregateFunction::Max,
-                _ => return None,
+                "COUNT_IF" => AggregateFunction::CountIf,
+                "VAR_SAMP" | "VARIANCE" => AggregateFunction::VarSamp,
+                "VAR_POP" => AggregateFunction::VarPop,
+                "STDDEV_SAMP" | "STDDEV" => AggregateFunction::StddevSamp,
+                "STDDEV_POP" => AggregateFunction::StddevPop,
+                "APPROX_COUNT_DISTINCT" => AggregateFunction::ApproxCountDistinct,
+                // The fraction of APPROX_QUANTILE / PERCENTILE_CONT replaces
+                // the median below
+                "APPROX_MEDIAN" | "APPROX_QUANTILE" => {
+                    AggregateFunction::ApproxQuantile(Quantile::MEDIAN)
+                }
+                "MEDIAN" | "PERCENTILE_CONT" => AggregateFunction::PercentileCont(Quantile::MEDIAN),
+                _ => return Ok(None),
             };
 
             // Extract column name from arguments
-            let col = match &func.args {
+            let args = match &func.args {
                 sqlparser::ast::FunctionArguments::List(func_arg_list) => {
-                    func_arg_list.args.first().map_or_else(|| "*".to_string(), ToString::to_string)
+                    func_arg_list.args.as_slice()
                 }
-                _ => "*".to_string(),
+                _ => &[],
             };
-            return Some((agg_func, col));
+            let first_arg = args.first();
+
+            if agg_func == AggregateFunction::Sum {
+                if let Some(flag) = first_arg.and_then(Self::case_when_flag) {
+                    return Ok(Some((AggregateFunction::CountIf, flag)));
+                }
+            }
+
+            let col = first_arg.map_or_else(|| "*".to_string(), binder::argument_name);
+            if matches!(func_name.as_str(), "APPROX_QUANTILE" | "PERCENTILE_CONT") {
+                let q = Self::quantile_argument(args.get(1)).ok_or_else(|| {
+                    crate::Error::ParseError(format!(
+                        "{func_name} takes a column and a fraction in [0, 1]"
+                    ))
+                })?;
+                let func = match agg_func {
+                    AggregateFunction::PercentileCont(_) => AggregateFunction::PercentileCont(q),
+                    _ => AggregateFunction::ApproxQuantile(q),
+                };
+                return Ok(Some((func, col)));
+            }
+            return Ok(Some((agg_func, col)));
+        }
+        Ok(None)
+    }
+
+    /// Fraction of `APPROX_QUANTILE(x, 0.95)` or `PERCENTILE_CONT(x, 0.95)`
+    fn quantile_argument(arg: Option<&sqlparser::ast::FunctionArg>) -> Option<Quantile> {
+        use sqlparser::ast::{FunctionArg, FunctionArgExpr, Value};
+
+        match arg? {
+            FunctionArg::Unnamed(FunctionArgExpr::Expr(Expr::Value(Value::Number(n, _)))) => {
+                Quantile::new(n.parse().ok()?)
+            }
+            _ => None,
+        }
+    }
+
+    /// Column `flag` from `CASE WHEN flag THEN 1 [ELSE 0] END`
+    fn case_when_flag(arg: &sqlparser::ast::FunctionArg) -> Option<String> {
+        use sqlparser::ast::{FunctionArg, FunctionArgExpr, Value};
+
+        let FunctionArg::Unnamed(FunctionArgExpr::Expr(Expr::Case {
+            operand: None,
+            conditions,
+            results,
+            else_result,
+        })) = arg
+        else {
+            return None;
+        };
+
+        let is_number =
+            |expr: &Expr, n: &str| matches!(expr, Expr::Value(Value::Number(v, _)) if v == n);
+        let else_zero = else_result.as_deref().map_or(true, |e| is_number(e, "0"));
+        match (conditions.as_slice(), results.as_slice()) {
+            ([Expr::Identifier(flag)], [then]) if is_number(then, "1") && else_zero => {
+                Some(flag.value.clone())
+            }
+            _ => None,
         }
-        None
     }
 
     fn extract_group_by(group_by: &sqlparser::ast::GroupByExpr) -> Vec<String> {
         match group_by {
             sqlparser::ast::GroupByExpr::All(_) => Vec::new(),
             sqlparser::ast::GroupByExpr::Expressions(exprs, _) => {
-                exprs.iter().map(ToString::to_string).collect()
+                exprs.iter().map(binder::expression_name).collect()
             }
         }
     }
@@ -263,7 +879,7 @@ impl QueryEngine {
                 ob.exprs
                     .iter()
                     .map(|o| {
-                        let col = o.expr.to_string();
+                        let col = binder::expression_name(&o.expr);
                         let dir = if o.asc.unwrap_or(true) {
                             OrderDirection::Asc
                         } else {
@@ -0,0 +1,466 @@
+//! Logical plan optimization
+//!
+//! [`QueryEngine::parse`](super::QueryEngine::parse) rewrites each plan
+//! before returning it, so every execution path runs the optimized form:
+//!
+//! | Rewrite | Effect |
+//! |---------|--------|
+//! | [`Rewrite::FoldConstants`] | literal-only subexpressions of WHERE are computed once (`v > 60 * 60` → `v > 3600`, `1 = 1` → `true`), and `AND`/`OR`/`NOT` over Boolean literals simplified |
+//! | [`Rewrite::RemoveFilter`] | a WHERE clause that is always true is dropped |
+//! | [`Rewrite::EmptyFilter`] | a WHERE clause that is never true becomes `false`, which zone maps prune without reading a row |
+//! | [`Rewrite::PushTopK`] | `ORDER BY ... LIMIT k` over a plain CTE is pushed into the CTE, which then materializes its top `k` rows instead of all of them |
+//! | [`Rewrite::ShareAggregate`] | repeated aggregates (`SUM(x)`, `SUM(x) AS total`) are computed once and the result reused |
+//!
+//! Folding follows the executor's expression rules (see
+//! [`scalar`](super::scalar)): integer literals are `Int64`, mixed operands
+//! are computed as `Float64`, and expressions that would overflow, divide
+//! by zero or involve NULL are left for the executor. Bare words are never
+//! constants, since they may name a column. The rewrites applied are kept
+//! in [`QueryPlan::rewrites`] and listed by EXPLAIN as `Rewrite:` lines.
+//!
+//! ```
+//! use trueno_db::query::{QueryEngine, Rewrite};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let plan = QueryEngine::new().parse("SELECT v FROM t WHERE v > 60 * 60 AND 1 < 2")?;
+//! assert_eq!(plan.filter.as_deref(), Some("v > 3600"));
+//! assert!(matches!(plan.rewrites[0], Rewrite::FoldConstants { .. }));
+//!
+//! let plan = QueryEngine::new().parse("SELECT v FROM t WHERE 2 > 1 OR v = 3")?;
+//! assert_eq!(plan.filter, None);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! References:
+//! - Graefe et al. (1993): The Volcano optimizer generator (rule-based
+//!   logical rewrites)
+//! - `PostgreSQL` `eval_const_expressions` (planner-time constant folding)
+//!
+//! Toyota Way: Muda elimination (work decided at plan time is not repeated
+//! per row)
+
+use super::binder::parse_filter;
+use super::{AggregateFunction, QueryPlan};
+use sqlparser::ast::{BinaryOperator, Expr, UnaryOperator, Value};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// One rewrite the optimizer applied to a plan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rewrite {
+    /// Constant subexpressions of the WHERE clause were evaluated
+    FoldConstants {
+        /// WHERE clause as written
+        before: String,
+        /// WHERE clause executed
+        after: String,
+    },
+    /// The WHERE clause is always true and was removed
+    RemoveFilter {
+        /// WHERE clause as written
+        filter: String,
+    },
+    /// The WHERE clause is never true and was replaced by `false`
+    EmptyFilter {
+        /// WHERE clause as written
+        filter: String,
+    },
+    /// ORDER BY and LIMIT were pushed into the CTE the query reads, so it
+    /// keeps only its top `k` rows
+    PushTopK {
+        /// CTE now ranked by Top-K selection
+        cte: String,
+        /// Rows the CTE keeps
+        k: usize,
+    },
+    /// An aggregate repeated in the SELECT list is computed once
+    ShareAggregate {
+        /// The aggregate (`SUM(x)`)
+        aggregate: String,
+        /// Output columns reading its result
+        uses: usize,
+    },
+}
+
+impl fmt::Display for Rewrite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FoldConstants { before, after } => {
+                write!(f, "fold constants: {before} => {after}")
+            }
+            Self::RemoveFilter { filter } => write!(f, "remove always-true filter: {filter}"),
+            Self::EmptyFilter { filter } => write!(f, "never-true filter reads no rows: {filter}"),
+            Self::PushTopK { cte, k } => write!(f, "push ORDER BY + LIMIT {k} into CTE {cte}"),
+            Self::ShareAggregate { aggregate, uses } => {
+                write!(f, "compute {aggregate} once for {uses} columns")
+            }
+        }
+    }
+}
+
+/// `plan` with every applicable rewrite applied (CTEs and subqueries
+/// first), recorded in
+/// [`QueryPlan::rewrites`]
+///
+/// A WHERE clause that does not parse is left as is, for the executor to
+/// report.
+#[must_use]
+pub fn optimize(mut plan: QueryPlan) -> QueryPlan {
+    plan.ctes = std::mem::take(&mut plan.ctes)
+        .into_iter()
+        .map(|(name, cte)| (name, optimize(cte)))
+        .collect();
+    plan.subqueries = std::mem::take(&mut plan.subqueries)
+        .into_iter()
+        .map(|(sql, subquery)| (sql, optimize(subquery)))
+        .collect();
+
+    if let Some(rewrite) = simplify_filter(&mut plan) {
+        plan.rewrites.push(rewrite);
+    }
+    if let Some(rewrite) = push_top_k(&mut plan) {
+        plan.rewrites.push(rewrite);
+    }
+    let (distinct, slots) = distinct_aggregates(&plan);
+    let shared: Vec<Rewrite> = distinct
+        .iter()
+        .enumerate()
+        .filter_map(|(slot, (func, col_name))| {
+            let uses = slots.iter().filter(|&&s| s == slot).count();
+            let aggregate = format!("{}({col_name})", func.sql_name());
+            (uses > 1).then_some(Rewrite::ShareAggregate { aggregate, uses })
+        })
+        .collect();
+    plan.rewrites.extend(shared);
+    plan
+}
+
+/// Distinct (function, input) pairs among `plan`'s aggregates, in first-use
+/// order, and the index of each aggregate's pair
+pub(super) fn distinct_aggregates(
+    plan: &QueryPlan,
+) -> (Vec<(AggregateFunction, &str)>, Vec<usize>) {
+    let mut distinct: Vec<(AggregateFunction, &str)> = Vec::new();
+    let slots = plan
+        .aggregations
+        .iter()
+        .map(|(func, col_name, _)| {
+            let key = (*func, col_name.as_str());
+            distinct.iter().position(|seen| *seen == key).unwrap_or_else(|| {
+                distinct.push(key);
+                distinct.len() - 1
+            })
+        })
+        .collect();
+    (distinct, slots)
+}
+
+/// Fold the WHERE clause, dropping it if always true
+fn simplify_filter(plan: &mut QueryPlan) -> Option<Rewrite> {
+    let filter = plan.filter.clone()?;
+    let expr = parse_filter(&filter).ok()?;
+    let folded = fold(expr.clone());
+    if folded == expr {
+        return None;
+    }
+    Some(match folded {
+        Expr::Value(Value::Boolean(true)) => {
+            plan.filter = None;
+            Rewrite::RemoveFilter { filter }
+        }
+        Expr::Value(Value::Boolean(false)) => {
+            plan.filter = Some(folded.to_string());
+            Rewrite::EmptyFilter { filter }
+        }
+        folded => {
+            let after = folded.to_string();
+            plan.filter = Some(after.clone());
+            Rewrite::FoldConstants { before: filter, after }
+        }
+    })
+}
+
+/// Rank a plain CTE's rows by the query's ORDER BY + LIMIT when the query
+/// only reorders and truncates them
+///
+/// The query keeps its own ORDER BY, LIMIT and OFFSET, now over `k` rows
+/// (LIMIT + OFFSET). CTEs that aggregate, join, sort or limit themselves,
+/// or that another CTE also reads, are left alone, as are keys naming a
+/// computed column of the CTE.
+fn push_top_k(plan: &mut QueryPlan) -> Option<Rewrite> {
+    let k = plan.fetch()?;
+    if plan.order_by.is_empty()
+        || plan.filter.is_some()
+        || !plan.joins.is_empty()
+        || !plan.aggregations.is_empty()
+        || !plan.group_by.is_empty()
+        || !plan.computed.is_empty()
+    {
+        return None;
+    }
+    let name = plan.table.clone();
+    let index = plan.ctes.iter().rposition(|(cte, _)| *cte == name)?;
+    if plan.ctes.iter().any(|(_, other)| other.base_tables().contains(&name.as_str())) {
+        return None;
+    }
+
+    let cte = &mut plan.ctes[index].1;
+    let keys_are_outputs = plan.order_by.iter().all(|(key, _)| {
+        (cte.columns.iter().any(|column| column == "*" || column == key))
+            && !cte.computed.iter().any(|(output, _)| output == key)
+    });
+    if !keys_are_outputs
+        || cte.limit.is_some()
+        || cte.offset > 0
+        || !cte.order_by.is_empty()
+        || !cte.aggregations.is_empty()
+        || !cte.group_by.is_empty()
+        || !cte.joins.is_empty()
+    {
+        return None;
+    }
+    cte.order_by.clone_from(&plan.order_by);
+    cte.limit = Some(k);
+    Some(Rewrite::PushTopK { cte: name, k })
+}
+
+/// A literal value the optimizer can compute with
+#[derive(Debug, Clone, PartialEq)]
+enum Constant {
+    Int(i64),
+    Float(f64),
+    Text(String),
+    Bool(bool),
+}
+
+impl Constant {
+    /// Literal value of `expr` (`None` for NULL and anything else)
+    fn of(expr: &Expr) -> Option<Self> {
+        match expr {
+            Expr::Value(Value::Number(number, _)) => number
+                .parse::<i64>()
+                .map(Self::Int)
+                .ok()
+                .or_else(|| number.parse::<f64>().ok().map(Self::Float)),
+            Expr::Value(Value::SingleQuotedString(text)) => Some(Self::Text(text.clone())),
+            Expr::Value(Value::Boolean(value)) => Some(Self::Bool(*value)),
+            Expr::UnaryOp { op: UnaryOperator::Minus, expr } => match Self::of(expr)? {
+                Self::Int(value) => value.checked_neg().map(Self::Int),
+                Self::Float(value) => Some(Self::Float(-value)),
+                _ => None,
+            },
+            Expr::UnaryOp { op: UnaryOperator::Plus, expr } => {
+                Self::of(expr).filter(|value| matches!(value, Self::Int(_) | Self::Float(_)))
+            }
+            Expr::Nested(expr) => Self::of(expr),
+            _ => None,
+        }
+    }
+
+    fn into_expr(self) -> Expr {
+        let number = |text: String, negative: bool| {
+            let value = Expr::Value(Value::Number(text, false));
+            if negative {
+                Expr::UnaryOp { op: UnaryOperator::Minus, expr: Box::new(value) }
+            } else {
+                value
+            }
+        };
+        match self {
+            Self::Int(value) => number(value.unsigned_abs().to_string(), value < 0),
+            Self::Float(value) => number(value.abs().to_string(), value.is_sign_negative()),
+            Self::Text(text) => Expr::Value(Value::SingleQuotedString(text)),
+            Self::Bool(value) => Expr::Value(Value::Boolean(value)),
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    const fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Int(value) => Some(*value as f64),
+            Self::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// `self op other` for `+ - * / %` (`None` on overflow, division by
+    /// zero, a non-finite result or non-numeric operands)
+    fn arithmetic(&self, op: &BinaryOperator, other: &Self) -> Option<Self> {
+        if let (Self::Int(left), Self::Int(right)) = (self, other) {
+            return match op {
+                BinaryOperator::Plus => left.checked_add(*right),
+                BinaryOperator::Minus => left.checked_sub(*right),
+                BinaryOperator::Multiply => left.checked_mul(*right),
+                BinaryOperator::Divide => left.checked_div(*right),
+                BinaryOperator::Modulo => left.checked_rem(*right),
+                _ => None,
+            }
+            .map(Self::Int);
+        }
+        let (left, right) = (self.as_f64()?, other.as_f64()?);
+        let value = match op {
+            BinaryOperator::Plus => left + right,
+            BinaryOperator::Minus => left - right,
+            BinaryOperator::Multiply => left * right,
+            BinaryOperator::Divide if right != 0.0 => left / right,
+            BinaryOperator::Modulo if right != 0.0 => left % right,
+            _ => return None,
+        };
+        value.is_finite().then_some(Self::Float(value))
+    }
+
+    /// Order of two constants of the same kind (numbers compare across
+    /// `Int` and `Float`)
+    fn compare(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Self::Int(left), Self::Int(right)) => Some(left.cmp(right)),
+            (Self::Text(left), Self::Text(right)) => Some(left.cmp(right)),
+            (Self::Bool(left), Self::Bool(right)) => Some(left.cmp(right)),
+            _ => self.as_f64()?.partial_cmp(&other.as_f64()?),
+        }
+    }
+}
+
+/// Whether `ordering` satisfies comparison `op` (`None` if `op` is not one)
+fn holds(op: &BinaryOperator, ordering: Ordering) -> Option<bool> {
+    Some(match op {
+        BinaryOperator::Eq => ordering == Ordering::Equal,
+        BinaryOperator::NotEq => ordering != Ordering::Equal,
+        BinaryOperator::Lt => ordering == Ordering::Less,
+        BinaryOperator::LtEq => ordering != Ordering::Greater,
+        BinaryOperator::Gt => ordering == Ordering::Greater,
+        BinaryOperator::GtEq => ordering != Ordering::Less,
+        _ => return None,
+    })
+}
+
+const fn boolean(expr: &Expr) -> Option<bool> {
+    match expr {
+        Expr::Value(Value::Boolean(value)) => Some(*value),
+        _ => None,
+    }
+}
+
+/// `expr` with its constant subexpressions evaluated
+fn fold(expr: Expr) -> Expr {
+    let literal = |value: bool| Expr::Value(Value::Boolean(value));
+    match expr {
+        Expr::Nested(inner) => {
+            let inner = fold(*inner);
+            if Constant::of(&inner).is_some() {
+                inner
+            } else {
+                Expr::Nested(Box::new(inner))
+            }
+        }
+        Expr::UnaryOp { op: op @ (UnaryOperator::Minus | UnaryOperator::Plus), expr } => {
+            let inner = fold(*expr);
+            // A signed literal is already folded
+            let signed_literal = matches!(inner, Expr::Value(_));
+            let expr = Expr::UnaryOp { op, expr: Box::new(inner) };
+            match Constant::of(&expr) {
+                Some(value) if !signed_literal => value.into_expr(),
+                _ => expr,
+            }
+        }
+        Expr::UnaryOp { op: UnaryOperator::Not, expr } => match fold(*expr) {
+            Expr::Value(Value::Boolean(value)) => literal(!value),
+            expr => Expr::UnaryOp { op: UnaryOperator::Not, expr: Box::new(expr) },
+        },
+        // Three-valued logic: FALSE AND x is FALSE and TRUE OR x is TRUE
+        // even when x is NULL
+        Expr::BinaryOp { left, op: BinaryOperator::And, right } => {
+            let (left, right) = (fold(*left), fold(*right));
+            match (boolean(&left), boolean(&right)) {
+                (Some(false), _) | (_, Some(false)) => literal(false),
+                (Some(true), _) => right,
+                (_, Some(true)) => left,
+                _ => Expr::BinaryOp {
+                    left: Box::new(left),
+                    op: BinaryOperator::And,
+                    right: Box::new(right),
+                },
+            }
+        }
+        Expr::BinaryOp { left, op: BinaryOperator::Or, right } => {
+            let (left, right) = (fold(*left), fold(*right));
+            match (boolean(&left), boolean(&right)) {
+                (Some(true), _) | (_, Some(true)) => literal(true),
+                (Some(false), _) => right,
+                (_, Some(false)) => left,
+                _ => Expr::BinaryOp {
+                    left: Box::new(left),
+                    op: BinaryOperator::Or,
+                    right: Box::new(right),
+                },
+            }
+        }
+        Expr::BinaryOp { left, op, right } => {
+            let (left, right) = (fold(*left), fold(*right));
+            if let (Some(l), Some(r)) = (Constant::of(&left), Constant::of(&right)) {
+                let folded = match holds(&op, Ordering::Equal) {
+                    Some(_) => l.compare(&r).and_then(|ordering| holds(&op, ordering)).map(literal),
+                    None => l.arithmetic(&op, &r).map(Constant::into_expr),
+                };
+                if let Some(folded) = folded {
+                    return folded;
+                }
+            }
+            Expr::BinaryOp { left: Box::new(left), op, right: Box::new(right) }
+        }
+        Expr::Between { expr, negated, low, high } => Expr::Between {
+            expr: Box::new(fold(*expr)),
+            negated,
+            low: Box::new(fold(*low)),
+            high: Box::new(fold(*high)),
+        },
+        Expr::InList { expr, list, negated } => Expr::InList {
+            expr: Box::new(fold(*expr)),
+            list: list.into_iter().map(fold).collect(),
+            negated,
+        },
+        expr => expr,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn folded(filter: &str) -> String {
+        fold(parse_filter(filter).unwrap()).to_string()
+    }
+
+    #[test]
+    fn test_fold_follows_executor_rules() {
+        assert_eq!(folded("v > 60 * 60"), "v > 3600");
+        assert_eq!(folded("v < -(2 + 3)"), "v < -5");
+        assert_eq!(folded("v >= 7 / 2 AND w = 1.5 * 2"), "v >= 3 AND w = 3");
+        assert_eq!(folded("v BETWEEN 1 + 1 AND 10 % 4"), "v BETWEEN 2 AND 2");
+        assert_eq!(folded("1 = 1.0 AND 'a' < 'b' AND NOT false"), "true");
+        assert_eq!(folded("(v = 1 OR 2 > 3) AND TRUE"), "(v = 1)");
+        assert_eq!(folded("v = 1 OR 1 <> 1"), "v = 1");
+        assert_eq!(folded("v = 1 AND 3 < 2"), "false");
+
+        // Left for the executor: overflow, division by zero, NULL, columns
+        assert_eq!(folded("v > 9223372036854775807 + 1"), "v > 9223372036854775807 + 1");
+        assert_eq!(folded("v > 1 / 0"), "v > 1 / 0");
+        assert_eq!(folded("v > NULL + 1"), "v > NULL + 1");
+        assert_eq!(folded("v = 1 AND a = a"), "v = 1 AND a = a");
+    }
+
+    #[test]
+    fn test_distinct_aggregates() {
+        let plan = crate::query::QueryEngine::new()
+            .parse("SELECT SUM(x), COUNT(*), SUM(x) AS total, MAX(x) FROM t")
+            .unwrap();
+        let (distinct, slots) = distinct_aggregates(&plan);
+        assert_eq!(distinct.len(), 3);
+        assert_eq!(slots, vec![0, 1, 0, 2]);
+        assert_eq!(
+            plan.rewrites,
+            vec![Rewrite::ShareAggregate { aggregate: "SUM(x)".to_string(), uses: 2 }]
+        );
+    }
+}
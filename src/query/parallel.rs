@@ -0,0 +1,587 @@
+//! Morsel-driven parallel execution (Leis et al. 2014)
+//!
+//! A single-table plan is split into morsels of [`MORSEL_ROWS`] rows, which
+//! a pool of scoped worker threads pulls from a shared counter, so fast
+//! workers take more morsels and no core idles behind a slow one. Each
+//! worker filters its morsel, keeping only the columns the plan reads,
+//! and then either projects it or folds it into a **partial aggregate**
+//! per group:
+//!
+//! | Aggregate               | Partial state                  | Merge                 |
+//! |-------------------------|--------------------------------|-----------------------|
+//! | `COUNT`, `COUNT_IF`     | count                          | sum of counts         |
+//! | `SUM`                   | sum (result type)              | sum of sums           |
+//! | `MIN`, `MAX`            | min/max, NULL without values   | min/max of partials   |
+//! | `AVG`                   | `f64` sum and non-null count   | sums / counts         |
+//! | variance, stddev        | [`WelfordState`]               | [`WelfordState::merge`] |
+//! | `APPROX_*`              | `HyperLogLog` or t-digest      | sketch merge          |
+//! | `PERCENTILE_CONT`       | the group's values             | percentile of all     |
+//!
+//! Partials are merged on the calling thread in morsel order, so groups
+//! come out in order of first appearance exactly as in serial execution,
+//! and result types, names and NULL semantics match. Floating-point sums
+//! and averages may differ from serial results in the last bits, since
+//! values are added in a different order, and t-digest estimates depend
+//! on how values were split among sketches. ORDER BY and LIMIT run after
+//! the merge.
+//!
+//! Toyota Way: Heijunka (workers pull morsels, so no core waits on a slow one)
+
+use super::approx::Sketch;
+use super::executor::QueryExecutor;
+use super::group_by::Groups;
+use super::limits::LimitGuard;
+use super::materialize;
+use super::optimizer::distinct_aggregates;
+use super::scalar;
+use super::selection::SelectionVector;
+use super::{AggregateFunction, QueryPlan};
+use crate::storage::dictionary::{decode_column, logical_schema};
+use crate::variance::{welford_simd, VarianceKind, WelfordState};
+use crate::{Error, Result};
+use arrow::array::{new_null_array, Array, ArrayRef, AsArray, Float64Array};
+use arrow::compute;
+use arrow::datatypes::{DataType, Field, Float64Type, Schema};
+use arrow::record_batch::RecordBatch;
+use arrow::row::{OwnedRow, RowConverter, Rows, SortField};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Rows per morsel handed to a worker
+///
+/// Large enough to amortize scheduling and partial-state overhead, small
+/// enough that a table of a few hundred thousand rows spreads over cores.
+/// Morsels coincide with storage zones, so zone-map pruning skips whole
+/// morsels (see [`zone_map`](crate::storage::zone_map)).
+pub const MORSEL_ROWS: usize = crate::storage::ZONE_ROWS;
+
+/// One worker per available core (the `DatabaseBuilder` default)
+#[must_use]
+pub fn default_parallelism() -> usize {
+    std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get)
+}
+
+/// Filter and aggregate/project a single-table `plan` over `morsels` on the
+/// executor's workers; ORDER BY and LIMIT are left to the caller
+pub(super) fn execute(
+    executor: &QueryExecutor,
+    plan: &QueryPlan,
+    morsels: &[RecordBatch],
+    guard: &LimitGuard,
+) -> Result<RecordBatch> {
+    // Limits are checked as each worker picks up a morsel
+    let filter = |morsel: &RecordBatch| -> Result<RecordBatch> {
+        guard.check()?;
+        let filtered = materialize::filter(morsel, plan)?;
+        guard.reserve(&filtered)?;
+        Ok(filtered)
+    };
+    let threads = executor.parallelism();
+
+    if plan.aggregations.is_empty() && plan.group_by.is_empty() {
+        let parts = run_morsels(morsels, threads, |morsel| {
+            QueryExecutor::project_columns(&filter(morsel)?, plan)
+        })?;
+        QueryExecutor::combine_batches(&parts)
+    } else {
+        let sample = materialize::gather(&morsels[0].slice(0, 0), plan, &SelectionVector::All(0))?;
+        let sample = scalar::with_computed_inputs(&sample, plan)?;
+        let converter = key_converter(&sample, plan)?;
+        let partials = run_morsels(morsels, threads, |morsel| {
+            let filtered = scalar::with_computed_inputs(&filter(morsel)?, plan)?;
+            MorselPartial::build(&filtered, plan, converter.as_ref())
+        })?;
+        merge(&partials, plan)
+    }
+}
+
+/// Run `task` on every morsel with up to `threads` workers; results in morsel order
+pub(super) fn run_morsels<T, F>(morsels: &[RecordBatch], threads: usize, task: F) -> Result<Vec<T>>
+where
+    T: Send,
+    F: Fn(&RecordBatch) -> Result<T> + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let slots: Vec<Mutex<Option<Result<T>>>> = morsels.iter().map(|_| Mutex::new(None)).collect();
+    std::thread::scope(|scope| {
+        for _ in 0..threads.clamp(1, morsels.len().max(1)) {
+            let (next, slots, task) = (&next, &slots, &task);
+            scope.spawn(move || loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(morsel) = morsels.get(i) else { break };
+                let result = task(morsel);
+                *slots[i].lock().unwrap_or_else(PoisonError::into_inner) = Some(result);
+            });
+        }
+    });
+    slots
+        .into_iter()
+        .map(|slot| {
+            slot.into_inner()
+                .unwrap_or_else(PoisonError::into_inner)
+                .unwrap_or_else(|| Err(Error::Other("Morsel was not processed".to_string())))
+        })
+        .collect()
+}
+
+/// Row encoder for the GROUP BY keys, shared by all workers so encoded keys
+/// compare across morsels (`None` without GROUP BY)
+fn key_converter(sample: &RecordBatch, plan: &QueryPlan) -> Result<Option<RowConverter>> {
+    if plan.group_by.is_empty() {
+        return Ok(None);
+    }
+    let schema = logical_schema(&sample.schema());
+    let fields = plan
+        .group_by
+        .iter()
+        .map(|key| {
+            let index = schema
+                .index_of(key)
+                .map_err(|_| Error::InvalidInput(format!("GROUP BY column not found: {key}")))?;
+            Ok(SortField::new(schema.field(index).data_type().clone()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    RowConverter::new(fields)
+        .map(Some)
+        .map_err(|e| Error::InvalidInput(format!("Unsupported GROUP BY key: {e}")))
+}
+
+/// Partial aggregate state of one aggregate, one entry per group of a morsel
+enum PartialColumn {
+    /// Kernel value per group (`COUNT`, `COUNT_IF`, `SUM`; `MIN`/`MAX`,
+    /// NULL for groups without non-null values)
+    Values(ArrayRef),
+    /// Sum and count of non-null values per group (`AVG`)
+    Means(Vec<(f64, u64)>),
+    /// Welford summary per group (variance and standard deviation)
+    Welford(Vec<WelfordState>),
+    /// Sketch per group (approximate aggregates)
+    Sketches(Vec<Sketch>),
+    /// Input values per group (exact percentiles, which do not decompose)
+    Inputs(Vec<ArrayRef>),
+}
+
+/// One morsel's groups and their partial aggregates
+struct MorselPartial {
+    /// Encoded group keys (`None` without GROUP BY: a single group)
+    rows: Option<Rows>,
+    /// One value per group for each GROUP BY key
+    keys: Vec<ArrayRef>,
+    /// Output fields of the GROUP BY keys
+    key_fields: Vec<Field>,
+    /// One entry per distinct aggregate in the plan (see
+    /// [`distinct_aggregates`])
+    columns: Vec<PartialColumn>,
+}
+
+impl MorselPartial {
+    fn build(
+        batch: &RecordBatch,
+        plan: &QueryPlan,
+        converter: Option<&RowConverter>,
+    ) -> Result<Self> {
+        // Repeated aggregates share one partial
+        let (aggregates, _) = distinct_aggregates(plan);
+        let Some(converter) = converter else {
+            let columns = aggregates
+                .iter()
+                .map(|(func, col_name)| {
+                    let column = QueryExecutor::aggregate_column(batch, col_name)?;
+                    partial(*func, &column, std::slice::from_ref(&column))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(Self { rows: None, keys: Vec::new(), key_fields: Vec::new(), columns });
+        };
+
+        let schema = logical_schema(&batch.schema());
+        let mut key_columns = Vec::with_capacity(plan.group_by.len());
+        let mut key_fields = Vec::with_capacity(plan.group_by.len());
+        for key in &plan.group_by {
+            let index = schema
+                .index_of(key)
+                .map_err(|_| Error::InvalidInput(format!("GROUP BY column not found: {key}")))?;
+            key_columns.push(Arc::clone(batch.column(index)));
+            key_fields.push(schema.field(index).clone());
+        }
+
+        let groups = Groups::build(&key_columns)?;
+        let keys = key_columns
+            .iter()
+            .map(|key| decode_column(&groups.keys(key)?))
+            .collect::<Result<Vec<_>>>()?;
+        let rows = converter
+            .convert_columns(&keys)
+            .map_err(|e| Error::Other(format!("Failed to encode GROUP BY keys: {e}")))?;
+        let columns = aggregates
+            .iter()
+            .map(|(func, col_name)| {
+                let column = QueryExecutor::aggregate_column(batch, col_name)?;
+                partial(*func, &column, &groups.partition(&column)?)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { rows: Some(rows), keys, key_fields, columns })
+    }
+
+    fn num_groups(&self) -> usize {
+        self.rows.as_ref().map_or(1, Rows::num_rows)
+    }
+}
+
+/// Kernel result for `func` over no rows of `column` (checks the type is
+/// supported, exactly as serial execution would)
+fn empty_result(func: AggregateFunction, column: &ArrayRef) -> Result<ArrayRef> {
+    QueryExecutor::execute_single_aggregation(func, &column.slice(0, 0)).map(|(value, _)| value)
+}
+
+/// Partial state of `func` over each group's slice (`parts`) of `column`
+fn partial(
+    func: AggregateFunction,
+    column: &ArrayRef,
+    parts: &[ArrayRef],
+) -> Result<PartialColumn> {
+    let empty = empty_result(func, column)?;
+    match func {
+        AggregateFunction::Count
+        | AggregateFunction::CountIf
+        | AggregateFunction::Sum
+        | AggregateFunction::Min
+        | AggregateFunction::Max => {
+            let skip_empty = matches!(func, AggregateFunction::Min | AggregateFunction::Max);
+            let values = parts
+                .iter()
+                .map(|part| {
+                    if skip_empty && part.null_count() == part.len() {
+                        return Ok(new_null_array(empty.data_type(), 1));
+                    }
+                    QueryExecutor::execute_single_aggregation(func, part).map(|(value, _)| value)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(PartialColumn::Values(concat_or_empty(&values, &empty)?))
+        }
+        AggregateFunction::Avg => {
+            let means = parts
+                .iter()
+                .map(|part| {
+                    let values = as_f64(part)?;
+                    let count = (values.len() - values.null_count()) as u64;
+                    Ok((values.iter().flatten().sum(), count))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(PartialColumn::Means(means))
+        }
+        AggregateFunction::VarSamp
+        | AggregateFunction::VarPop
+        | AggregateFunction::StddevSamp
+        | AggregateFunction::StddevPop => {
+            let states = parts
+                .iter()
+                .map(|part| Ok(welford_simd(&as_f64(part)?.iter().flatten().collect::<Vec<_>>())))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(PartialColumn::Welford(states))
+        }
+        AggregateFunction::ApproxCountDistinct | AggregateFunction::ApproxQuantile(_) => {
+            let sketches = parts
+                .iter()
+                .map(|part| {
+                    Sketch::of(func, part)?
+                        .ok_or_else(|| Error::Other(format!("{} has no sketch", func.sql_name())))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(PartialColumn::Sketches(sketches))
+        }
+        AggregateFunction::PercentileCont(_) => Ok(PartialColumn::Inputs(parts.to_vec())),
+    }
+}
+
+fn as_f64(part: &ArrayRef) -> Result<Float64Array> {
+    let values = compute::cast(part, &DataType::Float64)
+        .map_err(|e| Error::Other(format!("Failed to widen aggregate input: {e}")))?;
+    Ok(values.as_primitive::<Float64Type>().clone())
+}
+
+/// Concatenate one-row values, or `empty` (typed, zero rows) if there are none
+fn concat_or_empty(values: &[ArrayRef], empty: &ArrayRef) -> Result<ArrayRef> {
+    if values.is_empty() {
+        return Ok(empty.slice(0, 0));
+    }
+    let values: Vec<&dyn Array> = values.iter().map(AsRef::as_ref).collect();
+    compute::concat(&values).map_err(|e| Error::Other(format!("Failed to combine groups: {e}")))
+}
+
+/// Merge morsel partials (in morsel order) into the aggregation result
+fn merge(partials: &[MorselPartial], plan: &QueryPlan) -> Result<RecordBatch> {
+    // (morsel, group) of each global group's first appearance, and of every
+    // morsel group that contributes to it
+    let mut first: Vec<(usize, usize)> = Vec::new();
+    let mut contributors: Vec<Vec<(usize, usize)>> = Vec::new();
+    let mut ids: HashMap<OwnedRow, usize> = HashMap::new();
+    for (m, partial) in partials.iter().enumerate() {
+        for g in 0..partial.num_groups() {
+            let id = partial.rows.as_ref().map_or(0, |rows| {
+                let next = ids.len();
+                *ids.entry(rows.row(g).owned()).or_insert(next)
+            });
+            if id == first.len() {
+                first.push((m, g));
+                contributors.push(Vec::new());
+            }
+            contributors[id].push((m, g));
+        }
+    }
+
+    let mut fields = Vec::new();
+    let mut columns = Vec::new();
+    for k in 0..plan.group_by.len() {
+        let keys: Vec<&dyn Array> = partials.iter().map(|p| p.keys[k].as_ref()).collect();
+        columns.push(
+            compute::interleave(&keys, &first)
+                .map_err(|e| Error::Other(format!("Failed to gather group keys: {e}")))?,
+        );
+        fields.push(partials[0].key_fields[k].clone());
+    }
+
+    let (aggregates, slots) = distinct_aggregates(plan);
+    let merged = aggregates
+        .iter()
+        .enumerate()
+        .map(|(a, (func, _))| merge_aggregate(*func, partials, a, &contributors))
+        .collect::<Result<Vec<_>>>()?;
+    for ((_, col_name, alias), slot) in plan.aggregations.iter().zip(slots) {
+        let value = Arc::clone(&merged[slot]);
+        let nullable = value.null_count() > 0;
+        fields.push(Field::new(
+            alias.as_deref().unwrap_or(col_name),
+            value.data_type().clone(),
+            nullable,
+        ));
+        columns.push(value);
+    }
+
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)
+        .map_err(|e| Error::StorageError(format!("Failed to create result batch: {e}")))
+}
+
+/// Final values of aggregate `a` for every global group
+#[allow(clippy::cast_precision_loss, clippy::too_many_lines)]
+fn merge_aggregate(
+    func: AggregateFunction,
+    partials: &[MorselPartial],
+    a: usize,
+    contributors: &[Vec<(usize, usize)>],
+) -> Result<ArrayRef> {
+    match &partials[0].columns[a] {
+        PartialColumn::Values(sample) => {
+            let merge_func = match func {
+                AggregateFunction::Min | AggregateFunction::Max => func,
+                _ => AggregateFunction::Sum,
+            };
+            let arrays: Vec<&dyn Array> = partials
+                .iter()
+                .map(|p| match &p.columns[a] {
+                    PartialColumn::Values(values) => values.as_ref(),
+                    _ => unreachable!("partial kinds depend only on the aggregate"),
+                })
+                .collect();
+            let empty = empty_result(merge_func, sample)?;
+            let values = contributors
+                .iter()
+                .map(|parts| {
+                    let gathered = compute::interleave(&arrays, parts)
+                        .map_err(|e| Error::Other(format!("Failed to gather partials: {e}")))?;
+                    QueryExecutor::execute_single_aggregation(merge_func, &gathered)
+                        .map(|(value, _)| value)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            concat_or_empty(&values, &empty)
+        }
+        PartialColumn::Means(_) => {
+            let averages = contributors.iter().map(|parts| {
+                let (sum, count) = parts.iter().fold((0.0, 0), |(sum, count), &(m, g)| {
+                    let PartialColumn::Means(means) = &partials[m].columns[a] else {
+                        unreachable!("partial kinds depend only on the aggregate");
+                    };
+                    (sum + means[g].0, count + means[g].1)
+                });
+                (count > 0).then(|| sum / count as f64)
+            });
+            Ok(Arc::new(averages.collect::<Float64Array>()))
+        }
+        PartialColumn::Welford(_) => {
+            let kind = func.variance_kind().unwrap_or(VarianceKind::Sample);
+            let values = contributors.iter().map(|parts| {
+                let state = parts.iter().fold(WelfordState::default(), |state, &(m, g)| {
+                    let PartialColumn::Welford(states) = &partials[m].columns[a] else {
+                        unreachable!("partial kinds depend only on the aggregate");
+                    };
+                    state.merge(states[g])
+                });
+                match func {
+                    AggregateFunction::StddevSamp | AggregateFunction::StddevPop => {
+                        state.stddev(kind)
+                    }
+                    _ => state.variance(kind),
+                }
+            });
+            Ok(Arc::new(values.collect::<Float64Array>()))
+        }
+        PartialColumn::Sketches(_) => {
+            let new_sketch = || {
+                Sketch::new(func)
+                    .ok_or_else(|| Error::Other(format!("{} has no sketch", func.sql_name())))
+            };
+            let empty = new_sketch()?.finish().0;
+            let values = contributors
+                .iter()
+                .map(|parts| {
+                    let mut sketch = new_sketch()?;
+                    for &(m, g) in parts {
+                        let PartialColumn::Sketches(sketches) = &partials[m].columns[a] else {
+                            unreachable!("partial kinds depend only on the aggregate");
+                        };
+                        sketch.merge(&sketches[g])?;
+                    }
+                    Ok(sketch.finish().0)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            concat_or_empty(&values, &empty)
+        }
+        PartialColumn::Inputs(_) => {
+            let empty: ArrayRef = Arc::new(Float64Array::from(Vec::<f64>::new()));
+            let values = contributors
+                .iter()
+                .map(|parts| {
+                    let inputs: Vec<&dyn Array> = parts
+                        .iter()
+                        .map(|&(m, g)| {
+                            let PartialColumn::Inputs(inputs) = &partials[m].columns[a] else {
+                                unreachable!("partial kinds depend only on the aggregate");
+                            };
+                            inputs[g].as_ref()
+                        })
+                        .collect();
+                    let values = compute::concat(&inputs)
+                        .map_err(|e| Error::Other(format!("Failed to gather inputs: {e}")))?;
+                    QueryExecutor::execute_single_aggregation(func, &values).map(|(value, _)| value)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            concat_or_empty(&values, &empty)
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+    use crate::query::QueryEngine;
+    use crate::storage::StorageEngine;
+    use arrow::array::{Int32Array, StringArray};
+
+    /// Rows spanning several morsels, with NULLs concentrated in one morsel
+    fn table(rows: usize) -> StorageEngine {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("region", DataType::Utf8, false),
+            Field::new("v", DataType::Int32, true),
+            Field::new("x", DataType::Float64, false),
+        ]));
+        let regions: Vec<&str> = (0..rows).map(|i| ["eu", "us", "apac"][i % 3]).collect();
+        // Rows 0..MORSEL_ROWS of "apac" are NULL: that morsel's apac MIN has no values
+        let values: Vec<Option<i32>> = (0..rows)
+            .map(|i| {
+                (i % 3 != 2 || i >= MORSEL_ROWS).then(|| i32::try_from(i % 1000).unwrap() - 500)
+            })
+            .collect();
+        let xs: Vec<f64> =
+            (0..rows).map(|i| f64::from(u32::try_from(i % 17).unwrap()) * 0.5).collect();
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(StringArray::from(regions)),
+                Arc::new(Int32Array::from(values)),
+                Arc::new(Float64Array::from(xs)),
+            ],
+        )
+        .unwrap();
+        StorageEngine::new(vec![batch])
+    }
+
+    fn run(sql: &str, storage: &StorageEngine, threads: usize) -> RecordBatch {
+        let plan = QueryEngine::new().parse(sql).unwrap();
+        QueryExecutor::new().with_parallelism(threads).execute(&plan, storage).unwrap()
+    }
+
+    fn assert_close(parallel: &RecordBatch, serial: &RecordBatch) {
+        assert_eq!(parallel.schema().fields(), serial.schema().fields());
+        for (p, s) in parallel.columns().iter().zip(serial.columns()) {
+            if p.data_type() == &DataType::Float64 {
+                let (p, s) = (p.as_primitive::<Float64Type>(), s.as_primitive::<Float64Type>());
+                for (p, s) in p.iter().zip(s.iter()) {
+                    match (p, s) {
+                        (Some(p), Some(s)) => assert!((p - s).abs() <= 1e-9 * s.abs().max(1.0)),
+                        (p, s) => assert_eq!(p, s),
+                    }
+                }
+            } else {
+                assert_eq!(p, s);
+            }
+        }
+    }
+
+    #[test]
+    fn test_morsels_split_batches() {
+        let storage = table(MORSEL_ROWS * 2 + 5);
+        let (morsels, skipped) = crate::query::pruning::morsels(&storage, None).unwrap();
+        assert_eq!((morsels.len(), skipped), (3, 0));
+        assert_eq!(morsels[2].num_rows(), 5);
+    }
+
+    #[test]
+    fn test_parallel_matches_serial() {
+        let storage = table(MORSEL_ROWS * 3 + 123);
+        for sql in [
+            "SELECT COUNT(*), SUM(v), MIN(v), MAX(v), AVG(v), VAR_SAMP(x) FROM t",
+            "SELECT region, COUNT(v), SUM(v), MIN(v), MAX(v), AVG(x), STDDEV_POP(v) \
+             FROM t WHERE x > 1.0 GROUP BY region",
+            "SELECT region, SUM(x) AS total FROM t GROUP BY region ORDER BY total DESC LIMIT 2",
+            "SELECT region FROM t GROUP BY region",
+            "SELECT v, x FROM t WHERE v > 400 LIMIT 1000",
+            "SELECT SUM(v) FROM t WHERE v > 100000",
+            "SELECT region, SUM(v) FROM t WHERE v > 100000 GROUP BY region",
+            "SELECT AVG(v), AVG(x), MIN(v) FROM t WHERE v > 100000",
+            "SELECT region, AVG(v) FROM t WHERE v IS NULL GROUP BY region",
+        ] {
+            let serial = run(sql, &storage, 1);
+            for threads in [2, 4] {
+                assert_close(&run(sql, &storage, threads), &serial);
+            }
+        }
+    }
+
+    #[test]
+    fn test_avg_without_values_is_null() {
+        let storage = table(MORSEL_ROWS * 2 + 5);
+        let result = run("SELECT AVG(v), AVG(x) FROM t WHERE v > 100000", &storage, 4);
+        assert!(result.column(0).is_null(0));
+        assert!(result.column(1).is_null(0));
+    }
+
+    #[test]
+    fn test_min_ignores_morsels_without_values() {
+        let storage = table(MORSEL_ROWS * 2);
+        let sql = "SELECT region, MIN(v) FROM t GROUP BY region";
+        let parallel = run(sql, &storage, 4);
+        assert_eq!(parallel, run(sql, &storage, 1));
+
+        let mins = parallel.column(1).as_primitive::<arrow::datatypes::Int32Type>();
+        assert!(mins.iter().all(|min| min.unwrap() < 0));
+    }
+
+    #[test]
+    fn test_parallel_errors_match_serial() {
+        let storage = table(MORSEL_ROWS * 2);
+        let plan = QueryEngine::new().parse("SELECT SUM(region) FROM t").unwrap();
+        let err = QueryExecutor::new().with_parallelism(4).execute(&plan, &storage).unwrap_err();
+        assert!(err.to_string().contains("Aggregation not supported for data type"), "{err}");
+    }
+}
@@ -0,0 +1,202 @@
+//! Parsed-plan cache keyed by SQL text
+//!
+//! Dashboards re-run the same handful of queries, and each run used to
+//! parse and bind its SQL again. With
+//! [`QueryEngine::with_plan_cache`](super::QueryEngine::with_plan_cache)
+//! the engine keeps the plans of recently parsed statements and later
+//! parses of the same text (byte for byte) return a clone:
+//!
+//! - **Key**: the SQL text and the principal it was parsed for, so a plan
+//!   that passed one principal's access checks is never handed to another.
+//!   Parse errors are not cached.
+//! - **Eviction**: least recently used once the capacity is reached.
+//! - **Kernels**: fused GPU kernels need no entry here; the GPU engine's
+//!   pipeline cache already compiles each plan shape once.
+//!
+//! Toyota Way: Muda elimination (parse once, run many times)
+
+use super::QueryPlan;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Plans kept when no capacity is given
+pub const DEFAULT_PLAN_CACHE_CAPACITY: usize = 256;
+
+/// Lookup counts of a [`PlanCache`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlanCacheStats {
+    /// Plans held
+    pub entries: usize,
+    /// Most plans held
+    pub capacity: usize,
+    /// Parses served from the cache
+    pub hits: u64,
+    /// Parses that ran the parser
+    pub misses: u64,
+    /// Plans dropped to stay within capacity
+    pub evictions: u64,
+}
+
+impl PlanCacheStats {
+    /// Fraction of lookups served from the cache (`0.0` before any lookup)
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+struct Entry {
+    plan: QueryPlan,
+    /// Tick of the last lookup (larger is more recent)
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Entries {
+    plans: HashMap<(String, String), Entry>,
+    tick: u64,
+}
+
+/// Least recently used cache of parsed plans
+///
+/// Thread-safe; concurrent misses of the same statement may both parse it.
+pub struct PlanCache {
+    entries: Mutex<Entries>,
+    capacity: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl PlanCache {
+    /// Cache holding at most `capacity` plans (`0` caches nothing)
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(Entries::default()),
+            capacity,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    /// Plan of `sql` parsed for `principal`, running `parse` on a miss
+    ///
+    /// # Errors
+    /// Returns the error of `parse` (which is not cached)
+    pub fn get_or_parse(
+        &self,
+        sql: &str,
+        principal: &str,
+        parse: impl FnOnce() -> crate::Result<QueryPlan>,
+    ) -> crate::Result<QueryPlan> {
+        let key = (principal.to_string(), sql.to_string());
+        {
+            let mut entries = self.entries();
+            entries.tick += 1;
+            let tick = entries.tick;
+            if let Some(entry) = entries.plans.get_mut(&key) {
+                entry.last_used = tick;
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(entry.plan.clone());
+            }
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        // Parse outside the lock so other statements are not held up
+        let plan = parse()?;
+        if self.capacity > 0 {
+            let mut entries = self.entries();
+            if !entries.plans.contains_key(&key) && entries.plans.len() >= self.capacity {
+                let oldest = entries
+                    .plans
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.last_used)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    entries.plans.remove(&oldest);
+                    self.evictions.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            let last_used = entries.tick;
+            entries.plans.insert(key, Entry { plan: plan.clone(), last_used });
+        }
+        Ok(plan)
+    }
+
+    /// Drop every plan (counters are kept)
+    pub fn clear(&self) {
+        self.entries().plans.clear();
+    }
+
+    /// Current counters
+    #[must_use]
+    pub fn stats(&self) -> PlanCacheStats {
+        PlanCacheStats {
+            entries: self.entries().plans.len(),
+            capacity: self.capacity,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+
+    fn entries(&self) -> MutexGuard<'_, Entries> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::QueryEngine;
+
+    fn parse(sql: &str) -> crate::Result<QueryPlan> {
+        QueryEngine::new().parse(sql)
+    }
+
+    #[test]
+    fn test_hits_and_lru_eviction() {
+        let cache = PlanCache::new(2);
+        let (a, b, c) = ("SELECT a FROM t", "SELECT b FROM t", "SELECT c FROM t");
+
+        cache.get_or_parse(a, "", || parse(a)).unwrap();
+        cache.get_or_parse(b, "", || parse(b)).unwrap();
+        // Touch `a`, so `b` is the least recently used
+        let hit = cache.get_or_parse(a, "", || panic!("cached plan reparsed")).unwrap();
+        assert_eq!(hit.columns, vec!["a"]);
+        cache.get_or_parse(c, "", || parse(c)).unwrap();
+
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.hits, stats.misses, stats.evictions), (2, 1, 3, 1));
+        assert!((stats.hit_rate() - 0.25).abs() < f64::EPSILON);
+        cache.get_or_parse(a, "", || panic!("`a` was evicted")).unwrap();
+        cache.get_or_parse(b, "", || parse(b)).unwrap();
+        assert_eq!(cache.stats().misses, 4);
+    }
+
+    #[test]
+    fn test_keyed_by_principal_and_errors_not_cached() {
+        let cache = PlanCache::new(8);
+        let sql = "SELECT a FROM t";
+        cache.get_or_parse(sql, "alice", || parse(sql)).unwrap();
+        cache.get_or_parse(sql, "bob", || parse(sql)).unwrap();
+        assert_eq!(cache.stats().misses, 2);
+
+        assert!(cache.get_or_parse("SELEC", "", || parse("SELEC")).is_err());
+        assert!(cache.get_or_parse("SELEC", "", || parse("SELEC")).is_err());
+        assert_eq!(cache.stats().entries, 2);
+
+        cache.clear();
+        assert_eq!(cache.stats().entries, 0);
+        assert_eq!(PlanCache::new(0).get_or_parse(sql, "", || parse(sql)).unwrap().table, "t");
+    }
+}
@@ -0,0 +1,746 @@
+//! WHERE clause evaluation
+//!
+//! Filters reach the executor as canonical text (see
+//! [`binder::filter`](super::binder::filter)); [`filter_mask`] parses the
+//! text back into an expression and evaluates it over a batch.
+//!
+//! Supported predicates:
+//! - comparisons (`=`, `!=`/`<>`, `<`, `<=`, `>`, `>=`) between a column
+//!   and a literal, in either order, or between two columns
+//! - `AND`, `OR`, `NOT` and parentheses
+//! - `[NOT] BETWEEN low AND high` and `[NOT] IN (a, b, ...)`
+//! - `IS [NOT] NULL` and bare Boolean columns (`WHERE flag`)
+//! - on string columns (plain or dictionary-encoded): `=`, `!=`, `IN` and
+//!   `[NOT] LIKE`/`ILIKE` with `%` (any run) and `_` (one character)
+//!   wildcards; ordering comparisons on strings are rejected
+//! - on `Date32`, `Date64` and `Timestamp` columns: comparisons with
+//!   ISO-8601 string literals (`d >= '2024-03-01'`,
+//!   `ts < '2024-03-01T12:00:00Z'`), parsed into the column's type; times
+//!   without an offset are read as UTC
+//!
+//! Against a literal, a dictionary-encoded column is tested once per
+//! distinct value and the result is spread to rows through its keys, so
+//! the strings of a million-row column with ten values are compared ten
+//! times. A subpredicate repeated within one WHERE term (`(a > 1 AND b)
+//! OR (a > 1 AND c)`) is evaluated once per batch.
+//!
+//! Logic is three-valued as in SQL: comparing with NULL gives NULL,
+//! `NULL AND false` is false, `NULL OR true` is true, and rows whose
+//! predicate is NULL are filtered out.
+//!
+//! Toyota Way: Jidoka (unsupported predicates are errors, never "true")
+
+use super::binder::{column_name, parse_filter};
+use super::scalar::Subexpressions;
+use crate::{Error, Result};
+use arrow::array::{
+    make_array, AnyDictionaryArray, Array, ArrayRef, AsArray, BooleanArray, Datum,
+    LargeStringArray, RecordBatch, Scalar, StringArray,
+};
+use arrow::compute::kernels::{cmp, comparison};
+use arrow::compute::{self, CastOptions};
+use arrow::datatypes::{
+    ArrowPrimitiveType, DataType, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type,
+    Int8Type, TimeUnit, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+};
+use sqlparser::ast::{BinaryOperator, Expr, UnaryOperator, Value};
+use std::sync::Arc;
+
+/// Rows of `batch` that satisfy the canonical filter `filter`
+///
+/// NULL slots in the mask are rows whose predicate is unknown; arrow's
+/// filter kernels drop them like `false`.
+pub(super) fn filter_mask(batch: &RecordBatch, filter: &str) -> Result<BooleanArray> {
+    evaluate(batch, &parse_filter(filter)?)
+}
+
+/// Top-level `AND` terms of the canonical filter `filter`
+///
+/// A row passes the filter exactly when it passes every term, so terms can
+/// be applied one after another, each to the survivors of the last.
+pub(super) fn conjuncts(filter: &str) -> Result<Vec<Expr>> {
+    let mut terms = Vec::new();
+    split_conjuncts(parse_filter(filter)?, &mut terms);
+    Ok(terms)
+}
+
+fn split_conjuncts(expr: Expr, terms: &mut Vec<Expr>) {
+    match expr {
+        Expr::BinaryOp { left, op: BinaryOperator::And, right } => {
+            split_conjuncts(*left, terms);
+            split_conjuncts(*right, terms);
+        }
+        Expr::Nested(inner)
+            if matches!(inner.as_ref(), Expr::BinaryOp { op: BinaryOperator::And, .. }) =>
+        {
+            split_conjuncts(*inner, terms);
+        }
+        expr => terms.push(expr),
+    }
+}
+
+/// Comparison operator of a predicate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Comparison {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+}
+
+impl Comparison {
+    pub(super) const fn from_operator(op: &BinaryOperator) -> Option<Self> {
+        match op {
+            BinaryOperator::Eq => Some(Self::Eq),
+            BinaryOperator::NotEq => Some(Self::NotEq),
+            BinaryOperator::Lt => Some(Self::Lt),
+            BinaryOperator::LtEq => Some(Self::LtEq),
+            BinaryOperator::Gt => Some(Self::Gt),
+            BinaryOperator::GtEq => Some(Self::GtEq),
+            _ => None,
+        }
+    }
+
+    /// The same test with its operands swapped (`5 < x` is `x > 5`)
+    pub(super) const fn flip(self) -> Self {
+        match self {
+            Self::Eq | Self::NotEq => self,
+            Self::Lt => Self::Gt,
+            Self::LtEq => Self::GtEq,
+            Self::Gt => Self::Lt,
+            Self::GtEq => Self::LtEq,
+        }
+    }
+
+    /// The opposite test (`NOT x < 5` is `x >= 5`; NULL stays unknown)
+    pub(super) const fn negate(self) -> Self {
+        match self {
+            Self::Eq => Self::NotEq,
+            Self::NotEq => Self::Eq,
+            Self::Lt => Self::GtEq,
+            Self::LtEq => Self::Gt,
+            Self::Gt => Self::LtEq,
+            Self::GtEq => Self::Lt,
+        }
+    }
+
+    fn test<T: PartialOrd + Copy>(self, left: T, right: T) -> bool {
+        match self {
+            Self::Eq => left == right,
+            Self::NotEq => left != right,
+            Self::Lt => left < right,
+            Self::LtEq => left <= right,
+            Self::Gt => left > right,
+            Self::GtEq => left >= right,
+        }
+    }
+
+    /// Float test; `=` and `!=` allow for `epsilon` of rounding
+    fn test_float(self, left: f64, right: f64, epsilon: f64) -> bool {
+        match self {
+            Self::Eq => (left - right).abs() < epsilon,
+            Self::NotEq => (left - right).abs() >= epsilon,
+            _ => self.test(left, right),
+        }
+    }
+}
+
+/// Truth of `expr` for each row of `batch`
+pub(super) fn evaluate(batch: &RecordBatch, expr: &Expr) -> Result<BooleanArray> {
+    truth(batch, &mut Subexpressions::default(), expr)
+}
+
+/// Truth of `expr`, reusing subpredicates already in `subexpressions`
+fn truth(
+    batch: &RecordBatch,
+    subexpressions: &mut Subexpressions,
+    expr: &Expr,
+) -> Result<BooleanArray> {
+    match expr {
+        Expr::Nested(inner) => truth(batch, subexpressions, inner),
+        Expr::Identifier(_) | Expr::CompoundIdentifier(_) | Expr::Value(_) => {
+            predicate(batch, subexpressions, expr)
+        }
+        _ => {
+            let mask = subexpressions.get_or_compute(expr, |subexpressions| {
+                Ok(Arc::new(predicate(batch, subexpressions, expr)?))
+            })?;
+            Ok(mask.as_boolean().clone())
+        }
+    }
+}
+
+/// Truth of `expr` itself (its subpredicates still go through the cache)
+fn predicate(
+    batch: &RecordBatch,
+    subexpressions: &mut Subexpressions,
+    expr: &Expr,
+) -> Result<BooleanArray> {
+    match expr {
+        Expr::Nested(inner) => truth(batch, subexpressions, inner),
+        Expr::BinaryOp { left, op: BinaryOperator::And, right } => Ok(compute::and_kleene(
+            &truth(batch, subexpressions, left)?,
+            &truth(batch, subexpressions, right)?,
+        )?),
+        Expr::BinaryOp { left, op: BinaryOperator::Or, right } => Ok(compute::or_kleene(
+            &truth(batch, subexpressions, left)?,
+            &truth(batch, subexpressions, right)?,
+        )?),
+        Expr::BinaryOp { left, op, right } => {
+            let op = Comparison::from_operator(op)
+                .ok_or_else(|| Error::ParseError(format!("Unsupported filter operator: {op}")))?;
+            compare(batch, subexpressions, left, op, right)
+        }
+        Expr::UnaryOp { op: UnaryOperator::Not, expr } => {
+            Ok(compute::not(&truth(batch, subexpressions, expr)?)?)
+        }
+        Expr::Between { expr, negated, low, high } => {
+            let within = compute::and_kleene(
+                &compare(batch, subexpressions, expr, Comparison::GtEq, low)?,
+                &compare(batch, subexpressions, expr, Comparison::LtEq, high)?,
+            )?;
+            negate_if(within, *negated)
+        }
+        Expr::InList { expr, list, negated } => {
+            let mut any = BooleanArray::from(vec![false; batch.num_rows()]);
+            for item in list {
+                any = compute::or_kleene(
+                    &any,
+                    &compare(batch, subexpressions, expr, Comparison::Eq, item)?,
+                )?;
+            }
+            negate_if(any, *negated)
+        }
+        Expr::Like { negated, expr, pattern, escape_char, .. } => {
+            let matched = like(batch, subexpressions, expr, pattern, escape_char.is_some(), false)?;
+            negate_if(matched, *negated)
+        }
+        Expr::ILike { negated, expr, pattern, escape_char, .. } => {
+            let matched = like(batch, subexpressions, expr, pattern, escape_char.is_some(), true)?;
+            negate_if(matched, *negated)
+        }
+        Expr::IsNull(expr) => Ok(compute::is_null(require_column(batch, expr)?.as_ref())?),
+        Expr::IsNotNull(expr) => Ok(compute::is_not_null(require_column(batch, expr)?.as_ref())?),
+        Expr::Identifier(_) | Expr::CompoundIdentifier(_) => {
+            let column = require_column(batch, expr)?;
+            column.as_boolean_opt().cloned().ok_or_else(|| {
+                Error::InvalidInput(format!(
+                    "Predicate column must be Boolean: {} is {:?}",
+                    column_name(expr),
+                    column.data_type()
+                ))
+            })
+        }
+        Expr::Value(Value::Boolean(value)) => {
+            Ok(BooleanArray::from(vec![*value; batch.num_rows()]))
+        }
+        _ => Err(Error::ParseError(format!("Unsupported filter expression: {expr}"))),
+    }
+}
+
+fn negate_if(mask: BooleanArray, negated: bool) -> Result<BooleanArray> {
+    if negated {
+        Ok(compute::not(&mask)?)
+    } else {
+        Ok(mask)
+    }
+}
+
+/// `expr [I]LIKE pattern` for a string column and a literal pattern
+fn like(
+    batch: &RecordBatch,
+    subexpressions: &mut Subexpressions,
+    expr: &Expr,
+    pattern: &Expr,
+    has_escape: bool,
+    case_insensitive: bool,
+) -> Result<BooleanArray> {
+    if has_escape {
+        return Err(Error::ParseError(format!(
+            "LIKE ... ESCAPE is not supported (\\ escapes % and _): {pattern}"
+        )));
+    }
+    let column = require_column(batch, expr)?;
+    let dictionary = string_dictionary(&column);
+    let strings = match dictionary {
+        Some(dictionary) => Some(Arc::clone(dictionary.values())),
+        None => as_strings(&column)?,
+    };
+    let strings = strings.ok_or_else(|| {
+        Error::InvalidInput(format!(
+            "LIKE needs a string column: {} is {:?}",
+            column_name(expr),
+            column.data_type()
+        ))
+    })?;
+    if operand(batch, subexpressions, pattern)?.is_some() {
+        return Err(Error::ParseError(format!("LIKE pattern must be a string literal: {pattern}")));
+    }
+    let Some(pattern) = literal(pattern)? else {
+        return Ok(BooleanArray::new_null(column.len()));
+    };
+
+    let pattern = Scalar::new(string_literal(strings.data_type(), &pattern));
+    let matched = if case_insensitive {
+        comparison::ilike(&strings, &pattern)?
+    } else {
+        comparison::like(&strings, &pattern)?
+    };
+    match dictionary {
+        Some(dictionary) => by_key(dictionary, &matched),
+        None => Ok(matched),
+    }
+}
+
+/// `left op right`, where at least one side reads a column
+fn compare(
+    batch: &RecordBatch,
+    subexpressions: &mut Subexpressions,
+    left: &Expr,
+    op: Comparison,
+    right: &Expr,
+) -> Result<BooleanArray> {
+    match (operand(batch, subexpressions, left)?, operand(batch, subexpressions, right)?) {
+        (Some(left), Some(right)) => compare_columns(&left, op, &right),
+        (Some(column), None) => compare_literal(&column, op, literal(right)?.as_deref()),
+        (None, Some(column)) => compare_literal(&column, op.flip(), literal(left)?.as_deref()),
+        (None, None) => {
+            let name = [left, right].into_iter().find(|expr| is_column_ref(expr));
+            Err(name.map_or_else(
+                || Error::ParseError(format!("Comparison needs a column: {left} {op:?} {right}")),
+                |expr| Error::InvalidInput(format!("Column not found: {}", column_name(expr))),
+            ))
+        }
+    }
+}
+
+/// Values an operand reads: a column, or a nested predicate's truth
+///
+/// `None` for literals, and for bare words that name no column, which
+/// compare as literal text.
+fn operand(
+    batch: &RecordBatch,
+    subexpressions: &mut Subexpressions,
+    expr: &Expr,
+) -> Result<Option<ArrayRef>> {
+    match expr {
+        Expr::Nested(inner) => operand(batch, subexpressions, inner),
+        Expr::Identifier(_) | Expr::CompoundIdentifier(_) => {
+            Ok(batch.schema().index_of(&column_name(expr)).ok().map(|i| batch.column(i).clone()))
+        }
+        Expr::Value(_) => Ok(None),
+        Expr::UnaryOp { op: UnaryOperator::Minus | UnaryOperator::Plus, expr: inner }
+            if matches!(inner.as_ref(), Expr::Value(_)) =>
+        {
+            Ok(None)
+        }
+        _ => Ok(Some(Arc::new(truth(batch, subexpressions, expr)?))),
+    }
+}
+
+/// Text of a literal operand, or `None` for NULL
+pub(super) fn literal(expr: &Expr) -> Result<Option<String>> {
+    match expr {
+        Expr::Value(Value::Null) => Ok(None),
+        Expr::Value(Value::Number(number, _)) => Ok(Some(number.clone())),
+        Expr::Value(Value::SingleQuotedString(text) | Value::DoubleQuotedString(text)) => {
+            Ok(Some(text.clone()))
+        }
+        Expr::Value(Value::Boolean(value)) => Ok(Some(value.to_string())),
+        Expr::UnaryOp { op: UnaryOperator::Minus, expr } => {
+            Ok(literal(expr)?.map(|value| format!("-{value}")))
+        }
+        Expr::UnaryOp { op: UnaryOperator::Plus, expr } | Expr::Nested(expr) => literal(expr),
+        Expr::Identifier(_) | Expr::CompoundIdentifier(_) => Ok(Some(column_name(expr))),
+        _ => Err(Error::ParseError(format!("Unsupported filter operand: {expr}"))),
+    }
+}
+
+const fn is_column_ref(expr: &Expr) -> bool {
+    matches!(expr, Expr::Identifier(_) | Expr::CompoundIdentifier(_))
+}
+
+fn require_column(batch: &RecordBatch, expr: &Expr) -> Result<ArrayRef> {
+    let name = column_name(expr);
+    let index = batch
+        .schema()
+        .index_of(&name)
+        .map_err(|_| Error::InvalidInput(format!("Column not found: {name}")))?;
+    Ok(batch.column(index).clone())
+}
+
+fn unsupported(data_type: &DataType) -> Error {
+    Error::InvalidInput(format!("Filter not supported for data type: {data_type:?}"))
+}
+
+/// `column op literal`; a NULL literal makes every row unknown
+fn compare_literal(
+    column: &ArrayRef,
+    op: Comparison,
+    literal: Option<&str>,
+) -> Result<BooleanArray> {
+    let Some(literal) = literal else {
+        return Ok(BooleanArray::new_null(column.len()));
+    };
+    if let Some(dictionary) = string_dictionary(column) {
+        let by_value = compare_literal(dictionary.values(), op, Some(literal))?;
+        return by_key(dictionary, &by_value);
+    }
+    if let Some(strings) = as_strings(column)? {
+        let value = Scalar::new(string_literal(strings.data_type(), literal));
+        return compare_strings(&strings, op, &value);
+    }
+    if is_date_or_timestamp(column.data_type()) {
+        let value = Scalar::new(temporal_literal(column.data_type(), literal)?);
+        return compare_datums(column, op, &value);
+    }
+
+    let mask = match column.data_type() {
+        DataType::Boolean => {
+            let value: bool = literal
+                .to_ascii_lowercase()
+                .parse()
+                .map_err(|_| Error::ParseError(format!("Invalid Boolean value: {literal}")))?;
+            BooleanArray::from_unary(column.as_boolean(), |v| op.test(v, value))
+        }
+        DataType::Int8 => compare_int::<Int8Type>(column, op, literal)?,
+        DataType::Int16 => compare_int::<Int16Type>(column, op, literal)?,
+        DataType::Int32 => compare_int::<Int32Type>(column, op, literal)?,
+        DataType::Int64 => compare_int::<Int64Type>(column, op, literal)?,
+        DataType::UInt8 => compare_int::<UInt8Type>(column, op, literal)?,
+        DataType::UInt16 => compare_int::<UInt16Type>(column, op, literal)?,
+        DataType::UInt32 => compare_int::<UInt32Type>(column, op, literal)?,
+        DataType::UInt64 => compare_int::<UInt64Type>(column, op, literal)?,
+        DataType::Float32 => {
+            let value: f32 = literal
+                .parse()
+                .map_err(|_| Error::ParseError(format!("Invalid Float32 value: {literal}")))?;
+            BooleanArray::from_unary(column.as_primitive::<Float32Type>(), |v| {
+                op.test_float(f64::from(v), f64::from(value), f64::from(f32::EPSILON))
+            })
+        }
+        DataType::Float64 => {
+            let value: f64 = literal
+                .parse()
+                .map_err(|_| Error::ParseError(format!("Invalid Float64 value: {literal}")))?;
+            BooleanArray::from_unary(column.as_primitive::<Float64Type>(), |v| {
+                op.test_float(v, value, f64::EPSILON)
+            })
+        }
+        dt => return Err(unsupported(dt)),
+    };
+
+    // Comparisons with NULL are unknown, not false (matters under NOT)
+    Ok(BooleanArray::new(mask.values().clone(), column.logical_nulls()))
+}
+
+/// `column` as a plain string array (dictionaries decoded), or `None` if it
+/// does not hold strings
+fn as_strings(column: &ArrayRef) -> Result<Option<ArrayRef>> {
+    match column.data_type() {
+        DataType::Utf8 | DataType::LargeUtf8 => Ok(Some(column.clone())),
+        DataType::Dictionary(_, value)
+            if matches!(value.as_ref(), DataType::Utf8 | DataType::LargeUtf8) =>
+        {
+            Ok(Some(compute::cast(column, value)?))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// `column` as a dictionary of strings, if it is one
+fn string_dictionary(column: &ArrayRef) -> Option<&dyn AnyDictionaryArray> {
+    column.as_any_dictionary_opt().filter(|dictionary| {
+        matches!(dictionary.values().data_type(), DataType::Utf8 | DataType::LargeUtf8)
+    })
+}
+
+/// Each row's entry of `by_value`, a result per dictionary value (NULL for
+/// NULL keys)
+fn by_key(dictionary: &dyn AnyDictionaryArray, by_value: &BooleanArray) -> Result<BooleanArray> {
+    Ok(compute::take(by_value, dictionary.keys(), None)?.as_boolean().clone())
+}
+
+/// One-element array holding `literal`, typed to compare with `data_type`
+fn string_literal(data_type: &DataType, literal: &str) -> ArrayRef {
+    if data_type == &DataType::LargeUtf8 {
+        Arc::new(LargeStringArray::from(vec![literal]))
+    } else {
+        Arc::new(StringArray::from(vec![literal]))
+    }
+}
+
+/// Whether values of `data_type` are dates or timestamps
+pub(super) const fn is_date_or_timestamp(data_type: &DataType) -> bool {
+    matches!(data_type, DataType::Date32 | DataType::Date64 | DataType::Timestamp(..))
+}
+
+/// One-element array of `data_type` (a date or timestamp type) holding the
+/// ISO-8601 date or date-time `literal`
+///
+/// Dates are parsed through a timestamp, so `'2024-03-01'` and
+/// `'2024-03-01T00:00:00'` both name midnight of that day. The literal is
+/// parsed without a time zone (naive times are UTC) and then labelled with
+/// the column's, so zone names need no time zone database.
+pub(super) fn temporal_literal(data_type: &DataType, literal: &str) -> Result<ArrayRef> {
+    let options = CastOptions { safe: false, ..CastOptions::default() };
+    let invalid = || Error::ParseError(format!("Invalid {data_type} value: {literal}"));
+    let naive = match data_type {
+        DataType::Timestamp(unit, _) => DataType::Timestamp(*unit, None),
+        _ => DataType::Timestamp(TimeUnit::Microsecond, None),
+    };
+    let parsed = compute::cast_with_options(&StringArray::from(vec![literal]), &naive, &options)
+        .map_err(|_| invalid())?;
+    match data_type {
+        DataType::Timestamp(..) => {
+            Ok(make_array(parsed.to_data().into_builder().data_type(data_type.clone()).build()?))
+        }
+        _ => compute::cast_with_options(&parsed, data_type, &options).map_err(|_| invalid()),
+    }
+}
+
+/// Equality of strings; ordering comparisons are not supported
+fn compare_strings(strings: &dyn Datum, op: Comparison, value: &dyn Datum) -> Result<BooleanArray> {
+    match op {
+        Comparison::Eq => Ok(cmp::eq(strings, value)?),
+        Comparison::NotEq => Ok(cmp::neq(strings, value)?),
+        _ => Err(Error::InvalidInput(format!(
+            "Filter not supported for data type: strings with {op:?} \
+             (strings support =, !=, IN and LIKE)"
+        ))),
+    }
+}
+
+/// `column op literal` for integer columns
+///
+/// Values and the literal are widened to `i128`, so literals outside the
+/// column's range (e.g. `u8_col > 300`) compare correctly instead of
+/// failing to parse. Fractional literals (e.g. a bound `AVG` subquery)
+/// compare as `Float64`; whole ones like `300.0` stay exact.
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+fn compare_int<T>(column: &ArrayRef, op: Comparison, literal: &str) -> Result<BooleanArray>
+where
+    T: ArrowPrimitiveType,
+    T::Native: Into<i128>,
+{
+    let invalid = || Error::ParseError(format!("Invalid {:?} value: {literal}", T::DATA_TYPE));
+    let values = column.as_primitive::<T>();
+    if let Ok(value) = literal.parse::<i128>() {
+        return Ok(BooleanArray::from_unary(values, |v| op.test(v.into(), value)));
+    }
+    let value: f64 = literal.parse().ok().filter(|v: &f64| v.is_finite()).ok_or_else(invalid)?;
+    if value.fract() == 0.0 && value.abs() < 2f64.powi(127) {
+        let value = value as i128;
+        return Ok(BooleanArray::from_unary(values, |v| op.test(v.into(), value)));
+    }
+    Ok(BooleanArray::from_unary(values, |v| op.test(v.into() as f64, value)))
+}
+
+/// `left op right` for two columns, cast to a common type
+fn compare_columns(left: &ArrayRef, op: Comparison, right: &ArrayRef) -> Result<BooleanArray> {
+    if let (Some(left), Some(right)) = (as_strings(left)?, as_strings(right)?) {
+        let left = compute::cast(&left, &DataType::LargeUtf8)?;
+        let right = compute::cast(&right, &DataType::LargeUtf8)?;
+        return compare_strings(&left, op, &right);
+    }
+    let common = common_type(left.data_type(), right.data_type())?;
+    let left = compute::cast(left, &common)?;
+    let right = compute::cast(right, &common)?;
+    compare_datums(&left, op, &right)
+}
+
+/// `left op right` with arrow's comparison kernels (NULL in, NULL out)
+fn compare_datums(left: &dyn Datum, op: Comparison, right: &dyn Datum) -> Result<BooleanArray> {
+    let mask = match op {
+        Comparison::Eq => cmp::eq(left, right),
+        Comparison::NotEq => cmp::neq(left, right),
+        Comparison::Lt => cmp::lt(left, right),
+        Comparison::LtEq => cmp::lt_eq(left, right),
+        Comparison::Gt => cmp::gt(left, right),
+        Comparison::GtEq => cmp::gt_eq(left, right),
+    }?;
+    Ok(mask)
+}
+
+/// Type two columns are compared as: integers exactly (as `Decimal128`),
+/// anything involving a float as `Float64`, dates and timestamps of
+/// different types as microsecond timestamps (UTC instants)
+fn common_type(left: &DataType, right: &DataType) -> Result<DataType> {
+    let filterable = |dt: &DataType| {
+        dt == &DataType::Boolean || dt.is_integer() || dt.is_floating() || is_date_or_timestamp(dt)
+    };
+    if !filterable(left) {
+        return Err(unsupported(left));
+    }
+    if !filterable(right) {
+        return Err(unsupported(right));
+    }
+
+    if left == right {
+        Ok(left.clone())
+    } else if is_date_or_timestamp(left) && is_date_or_timestamp(right) {
+        Ok(DataType::Timestamp(TimeUnit::Microsecond, None))
+    } else if left.is_integer() && right.is_integer() {
+        Ok(DataType::Decimal128(38, 0))
+    } else if left.is_numeric() && right.is_numeric() {
+        Ok(DataType::Float64)
+    } else {
+        Err(Error::InvalidInput(format!("Cannot compare {left:?} with {right:?}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{DictionaryArray, Float64Array, Int32Array, UInt8Array};
+    use arrow::datatypes::{Field, Schema};
+
+    fn batch() -> RecordBatch {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::UInt8, false),
+            Field::new("x", DataType::Float64, false),
+        ]);
+        RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(1), Some(5), None, Some(9)])),
+                Arc::new(UInt8Array::from(vec![2, 5, 7, 3])),
+                Arc::new(Float64Array::from(vec![0.5, 5.0, 2.5, 9.5])),
+            ],
+        )
+        .unwrap()
+    }
+
+    /// Rows the filter keeps (NULL counts as not kept)
+    fn rows(filter: &str) -> Vec<usize> {
+        let mask = filter_mask(&batch(), filter).unwrap();
+        (0..mask.len()).filter(|&i| mask.is_valid(i) && mask.value(i)).collect()
+    }
+
+    #[test]
+    fn test_three_valued_logic() {
+        assert_eq!(rows("a > 2"), vec![1, 3]);
+        // NULL > 2 is unknown, and so is its negation
+        assert_eq!(rows("NOT a > 2"), vec![0]);
+        assert_eq!(rows("a > 2 OR b = 7"), vec![1, 2, 3]);
+        assert_eq!(rows("a IS NULL AND b = 7"), vec![2]);
+        assert_eq!(rows("a = NULL"), Vec::<usize>::new());
+        // The repeated `a > 2` is reused, not re-evaluated
+        assert_eq!(rows("(a > 2 AND b = 5) OR (a > 2 AND x > 9)"), vec![1, 3]);
+        assert_eq!(rows("NOT (a > 2) OR a > 2"), vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn test_column_comparisons_across_types() {
+        assert_eq!(rows("a = b"), vec![1]);
+        assert_eq!(rows("b < a"), vec![3]);
+        assert_eq!(rows("x > b"), vec![3]);
+        assert_eq!(rows("3 <= b"), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_integer_columns_against_float_literals() {
+        assert_eq!(rows("a > 4.5"), vec![1, 3]);
+        assert_eq!(rows("a = 5.0"), vec![1]);
+        assert_eq!(rows("b <= 2.9"), vec![0]);
+        assert_eq!(rows("a IN (1.0, 9.5)"), vec![0]);
+        assert!(filter_mask(&batch(), "a > 'abc'").is_err());
+    }
+
+    #[test]
+    fn test_between_and_in_lists() {
+        assert_eq!(rows("b BETWEEN 3 AND 5"), vec![1, 3]);
+        assert_eq!(rows("b NOT BETWEEN 3 AND 5"), vec![0, 2]);
+        assert_eq!(rows("a IN (1, 9)"), vec![0, 3]);
+        // x NOT IN (.., NULL) is never true
+        assert_eq!(rows("a NOT IN (1, NULL)"), Vec::<usize>::new());
+        assert_eq!(rows("a NOT IN (1, 2)"), vec![1, 3]);
+        assert_eq!(rows("(a < 2 OR a > 8) AND NOT (b IN (2))"), vec![3]);
+    }
+
+    #[test]
+    fn test_string_predicates() {
+        let names: DictionaryArray<Int32Type> =
+            vec![Some("apple"), Some("Banana"), None, Some("apricot")].into_iter().collect();
+        let schema = Schema::new(vec![
+            Field::new("name", names.data_type().clone(), true),
+            Field::new("alias", DataType::Utf8, false),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(names),
+                Arc::new(StringArray::from(vec!["apple", "banana", "cherry", "fig"])),
+            ],
+        )
+        .unwrap();
+        let rows = |filter: &str| -> Vec<usize> {
+            let mask = filter_mask(&batch, filter).unwrap();
+            (0..mask.len()).filter(|&i| mask.is_valid(i) && mask.value(i)).collect()
+        };
+
+        assert_eq!(rows("name = 'apple'"), vec![0]);
+        assert_eq!(rows("name <> 'apple'"), vec![1, 3]);
+        assert_eq!(rows("name IN ('Banana', 'fig')"), vec![1]);
+        assert_eq!(rows("name LIKE 'ap%'"), vec![0, 3]);
+        assert_eq!(rows("name NOT LIKE 'ap%'"), vec![1]);
+        assert_eq!(rows("alias LIKE '_i%'"), vec![3]);
+        assert_eq!(rows("name ILIKE 'b%'"), vec![1]);
+        assert_eq!(rows("name = alias"), vec![0]);
+
+        assert!(filter_mask(&batch, "name > 'a'").is_err());
+        assert!(filter_mask(&batch, "name LIKE alias").is_err());
+    }
+
+    #[test]
+    fn test_temporal_predicates() {
+        use arrow::array::{Date32Array, TimestampMillisecondArray};
+
+        // 2024-03-01, 2024-03-02, NULL; 2024-03-01T00:00, T12:00, T23:59:59.999 UTC
+        let schema = Schema::new(vec![
+            Field::new("day", DataType::Date32, true),
+            Field::new("ts", DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())), false),
+        ]);
+        let noon = 1_709_294_400_000;
+        let batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Date32Array::from(vec![Some(19_783), Some(19_784), None])),
+                Arc::new(
+                    TimestampMillisecondArray::from(vec![
+                        noon - 43_200_000,
+                        noon,
+                        noon + 43_199_999,
+                    ])
+                    .with_timezone("UTC"),
+                ),
+            ],
+        )
+        .unwrap();
+        let rows = |filter: &str| -> Vec<usize> {
+            let mask = filter_mask(&batch, filter).unwrap();
+            (0..mask.len()).filter(|&i| mask.is_valid(i) && mask.value(i)).collect()
+        };
+
+        assert_eq!(rows("day = '2024-03-01'"), vec![0]);
+        assert_eq!(rows("day > '2024-03-01'"), vec![1]);
+        assert_eq!(rows("'2024-03-02' <= day"), vec![1]);
+        assert_eq!(rows("ts >= '2024-03-01T12:00:00Z'"), vec![1, 2]);
+        assert_eq!(rows("ts BETWEEN '2024-03-01 06:00:00' AND '2024-03-01 18:00:00'"), vec![1]);
+        assert_eq!(rows("ts < '2024-03-02'"), vec![0, 1, 2]);
+        assert_eq!(rows("ts >= day"), vec![0]);
+        assert!(filter_mask(&batch, "day > 'yesterday'").is_err());
+        assert!(filter_mask(&batch, "day > 5").is_err());
+    }
+
+    #[test]
+    fn test_unsupported_predicates_are_errors() {
+        assert!(filter_mask(&batch(), "a + 1 > 2").is_err());
+        assert!(filter_mask(&batch(), "1 = 1").is_err());
+        let err = filter_mask(&batch(), "missing > 1").unwrap_err();
+        assert!(err.to_string().contains("Column not found: missing"));
+    }
+}
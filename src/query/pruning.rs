@@ -0,0 +1,290 @@
+//! Zone-map pruning of WHERE predicates
+//!
+//! Before a single-table query filters its rows, every storage zone (see
+//! [`zone_map`](crate::storage::zone_map)) is checked against the
+//! predicate, and zones that cannot contain a passing row are skipped
+//! without being read. The check is conservative: anything it cannot
+//! reason about (column-to-column comparisons, LIKE, Boolean columns,
+//! columns without statistics) keeps the zone.
+//!
+//! NOT is pushed down to the comparisons (`NOT x < 5` is `x >= 5`, De
+//! Morgan for AND/OR), which is exact under SQL's three-valued logic: a
+//! NULL value fails a comparison and its negation alike.
+//!
+//! Toyota Way: Muda elimination (skip what cannot match)
+
+use super::binder::{column_name, parse_filter};
+use super::predicate::{self, Comparison};
+use crate::storage::{ColumnZone, StorageEngine, ZoneMap, ZoneValue};
+use crate::Result;
+use arrow::datatypes::{DataType, Schema};
+use arrow::record_batch::RecordBatch;
+use sqlparser::ast::{BinaryOperator, Expr, UnaryOperator, Value};
+
+/// Zones of `storage` that may hold rows passing `filter`, as morsels, and
+/// the number of zones skipped
+///
+/// Without a filter every zone is returned. The filter is first evaluated
+/// on an empty slice, so an invalid predicate is still an error when every
+/// zone would be skipped.
+pub(super) fn morsels(
+    storage: &StorageEngine,
+    filter: Option<&str>,
+) -> Result<(Vec<RecordBatch>, usize)> {
+    let batches = storage.batches();
+    let expr = match (filter, batches.first()) {
+        (Some(filter), Some(first)) => {
+            predicate::filter_mask(&first.slice(0, 0), filter)?;
+            Some(parse_filter(filter)?)
+        }
+        _ => None,
+    };
+
+    let mut morsels = Vec::new();
+    let mut skipped = 0;
+    for (batch, zones) in batches.iter().zip(storage.zone_maps()) {
+        let schema = batch.schema();
+        for zone in zones {
+            if expr.as_ref().map_or(true, |expr| may_match(expr, &schema, zone)) {
+                morsels.push(batch.slice(zone.offset, zone.rows));
+            } else {
+                skipped += 1;
+            }
+        }
+    }
+    Ok((morsels, skipped))
+}
+
+/// False only if no row of `zone` can satisfy `expr`
+pub(super) fn may_match(expr: &Expr, schema: &Schema, zone: &ZoneMap) -> bool {
+    possible(expr, false, schema, zone)
+}
+
+/// Whether `expr` (or `NOT expr` when `negated`) may be true for a row
+fn possible(expr: &Expr, negated: bool, schema: &Schema, zone: &ZoneMap) -> bool {
+    let branch = |expr: &Expr| possible(expr, negated, schema, zone);
+    match expr {
+        Expr::Nested(inner) => possible(inner, negated, schema, zone),
+        Expr::UnaryOp { op: UnaryOperator::Not, expr } => possible(expr, !negated, schema, zone),
+        Expr::BinaryOp { left, op: BinaryOperator::And, right } => {
+            if negated {
+                branch(left) || branch(right)
+            } else {
+                branch(left) && branch(right)
+            }
+        }
+        Expr::BinaryOp { left, op: BinaryOperator::Or, right } => {
+            if negated {
+                branch(left) && branch(right)
+            } else {
+                branch(left) || branch(right)
+            }
+        }
+        Expr::BinaryOp { left, op, right } => Comparison::from_operator(op).map_or(true, |op| {
+            let op = if negated { op.negate() } else { op };
+            compare(left, op, right, schema, zone)
+        }),
+        Expr::Between { expr, negated: not_between, low, high } => {
+            let test = |op, bound| compare(expr, op, bound, schema, zone);
+            if *not_between == negated {
+                test(Comparison::GtEq, low) && test(Comparison::LtEq, high)
+            } else {
+                test(Comparison::Lt, low) || test(Comparison::Gt, high)
+            }
+        }
+        Expr::InList { expr, list, negated: not_in } => {
+            if *not_in == negated {
+                list.iter().any(|item| compare(expr, Comparison::Eq, item, schema, zone))
+            } else {
+                list.iter().all(|item| compare(expr, Comparison::NotEq, item, schema, zone))
+            }
+        }
+        Expr::IsNull(operand) | Expr::IsNotNull(operand) => {
+            let Some(stats) = column(operand, schema).and_then(|index| zone.column(index)) else {
+                return true;
+            };
+            // IS NOT NULL, or NOT (IS NULL)
+            if matches!(expr, Expr::IsNull(_)) == negated {
+                stats.null_count < zone.rows
+            } else {
+                stats.null_count > 0
+            }
+        }
+        Expr::Value(Value::Boolean(value)) => *value != negated,
+        _ => true,
+    }
+}
+
+/// Whether `left op right` may be true, where one side is a column with
+/// statistics and the other a literal
+fn compare(left: &Expr, op: Comparison, right: &Expr, schema: &Schema, zone: &ZoneMap) -> bool {
+    let (index, op, value) = match (column(left, schema), column(right, schema)) {
+        (Some(index), None) => (index, op, right),
+        (None, Some(index)) => (index, op.flip(), left),
+        _ => return true,
+    };
+    let Some(stats) = zone.column(index) else {
+        return true;
+    };
+    let value = match predicate::literal(value) {
+        Ok(Some(value)) => value,
+        // Comparing with NULL is unknown for every row
+        Ok(None) => return false,
+        Err(_) => return true,
+    };
+    let (Some(min), Some(max)) = (&stats.min, &stats.max) else {
+        // Every value is NULL
+        return false;
+    };
+
+    match (min, max) {
+        (ZoneValue::Int(min), ZoneValue::Int(max)) => {
+            let data_type = schema.field(index).data_type();
+            let value = if predicate::is_date_or_timestamp(data_type) {
+                stored_temporal(data_type, &value)
+            } else {
+                value.parse::<i128>().ok()
+            };
+            value.map_or(true, |value| in_range(op, min, max, &value))
+        }
+        (ZoneValue::Float(min), ZoneValue::Float(max)) => {
+            value.parse::<f64>().map_or(true, |value| float_in_range(op, *min, *max, value))
+        }
+        (ZoneValue::Utf8(min), ZoneValue::Utf8(max)) => match op {
+            Comparison::Eq | Comparison::NotEq => {
+                in_range(op, min.as_str(), max.as_str(), value.as_str())
+            }
+            // Ordering comparisons on strings are rejected by the evaluator
+            _ => true,
+        },
+        _ => true,
+    }
+}
+
+/// Stored integer of a date or timestamp literal, as the zone map keeps it
+fn stored_temporal(data_type: &DataType, literal: &str) -> Option<i128> {
+    let value = predicate::temporal_literal(data_type, literal).ok()?;
+    match ColumnZone::of(&value)?.min {
+        Some(ZoneValue::Int(value)) => Some(value),
+        _ => None,
+    }
+}
+
+/// Index of the column `expr` names, if it is a column of `schema`
+fn column(expr: &Expr, schema: &Schema) -> Option<usize> {
+    match expr {
+        Expr::Nested(inner) => column(inner, schema),
+        Expr::Identifier(_) | Expr::CompoundIdentifier(_) => {
+            schema.index_of(&column_name(expr)).ok()
+        }
+        _ => None,
+    }
+}
+
+/// Whether some value in `[min, max]` may satisfy `x op value`
+fn in_range<T: PartialOrd + ?Sized>(op: Comparison, min: &T, max: &T, value: &T) -> bool {
+    match op {
+        Comparison::Eq => min <= value && value <= max,
+        Comparison::NotEq => !(min == value && max == value),
+        Comparison::Lt => min < value,
+        Comparison::LtEq => min <= value,
+        Comparison::Gt => max > value,
+        Comparison::GtEq => max >= value,
+    }
+}
+
+/// [`in_range`] for floats, whose `=` and `!=` allow for rounding
+///
+/// Uses the `Float32` epsilon, the larger of the two the evaluator applies.
+fn float_in_range(op: Comparison, min: f64, max: f64, value: f64) -> bool {
+    let epsilon = f64::from(f32::EPSILON);
+    match op {
+        Comparison::Eq => max > value - epsilon && min < value + epsilon,
+        Comparison::NotEq => !(min > value - epsilon && max < value + epsilon),
+        _ => in_range(op, &min, &max, &value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Int32Array, StringArray};
+    use arrow::datatypes::Field;
+    use std::sync::Arc;
+
+    /// One zone: `v` in 10..=20 with one NULL, `s` in "b"..="d"
+    fn zone() -> (Arc<Schema>, ZoneMap) {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("v", DataType::Int32, true),
+            Field::new("s", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(10), None, Some(20)])),
+                Arc::new(StringArray::from(vec!["b", "c", "d"])),
+            ],
+        )
+        .unwrap();
+        (schema, ZoneMap::build(&batch).remove(0))
+    }
+
+    fn check(filter: &str) -> bool {
+        let (schema, zone) = zone();
+        may_match(&parse_filter(filter).unwrap(), &schema, &zone)
+    }
+
+    #[test]
+    fn test_comparisons_against_range() {
+        assert!(check("v > 15"));
+        assert!(!check("v > 20"));
+        assert!(!check("5 > v"));
+        assert!(check("v = 10"));
+        assert!(!check("v = 21"));
+        assert!(!check("v = NULL"));
+        assert!(!check("s = 'a'"));
+        assert!(check("s = 'c'"));
+    }
+
+    #[test]
+    fn test_boolean_structure() {
+        assert!(!check("v > 20 OR v < 10"));
+        assert!(!check("v > 15 AND s = 'z'"));
+        assert!(!check("NOT (v <= 20)"));
+        assert!(check("NOT (v > 20 AND v < 10)"));
+        assert!(!check("v BETWEEN 30 AND 40"));
+        assert!(check("v NOT BETWEEN 12 AND 18"));
+        assert!(!check("v NOT BETWEEN 0 AND 30"));
+        assert!(!check("v IN (1, 2, 3)"));
+        assert!(check("v IN (1, 12)"));
+    }
+
+    #[test]
+    fn test_date_ranges() {
+        use arrow::array::Date32Array;
+
+        // 2024-03-01 ..= 2024-03-10
+        let schema = Arc::new(Schema::new(vec![Field::new("day", DataType::Date32, false)]));
+        let days = Date32Array::from(vec![19_783, 19_792]);
+        let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(days)]).unwrap();
+        let zone = ZoneMap::build(&batch).remove(0);
+        let check = |filter: &str| may_match(&parse_filter(filter).unwrap(), &schema, &zone);
+
+        assert!(check("day >= '2024-03-10'"));
+        assert!(!check("day > '2024-03-10'"));
+        assert!(!check("day < '2024-03-01T00:00:00'"));
+        assert!(check("day = '2024-03-05'"));
+        // Unparseable literals keep the zone (the evaluator reports them)
+        assert!(check("day = 'soon'"));
+    }
+
+    #[test]
+    fn test_null_checks_and_unknown_expressions() {
+        assert!(check("v IS NULL"));
+        assert!(!check("s IS NULL"));
+        assert!(!check("NOT (s IS NOT NULL)"));
+        // Column-to-column and LIKE are never pruned
+        assert!(check("v > v"));
+        assert!(check("s LIKE 'z%'"));
+    }
+}
@@ -0,0 +1,300 @@
+//! Typed, row-oriented access to query results
+//!
+//! Results are Arrow `RecordBatch`es, which are column-oriented: reading a
+//! value means finding the column, downcasting it to the right array type,
+//! and checking for NULL. [`RowAccessor`] does that once per call so
+//! application code can read results by row position and column name:
+//!
+//! ```
+//! use arrow::array::{Int64Array, RecordBatch, StringArray};
+//! use arrow::datatypes::{DataType, Field, Schema};
+//! use std::sync::Arc;
+//! use trueno_db::query::RowAccessor;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let schema = Arc::new(Schema::new(vec![
+//!     Field::new("region", DataType::Utf8, false),
+//!     Field::new("orders", DataType::Int64, true),
+//! ]));
+//! let batch = RecordBatch::try_new(
+//!     schema,
+//!     vec![
+//!         Arc::new(StringArray::from(vec!["eu", "us"])),
+//!         Arc::new(Int64Array::from(vec![Some(3), None])),
+//!     ],
+//! )?;
+//!
+//! let rows = RowAccessor::new(&batch);
+//! assert_eq!(rows.get::<&str>(0, "region")?, Some("eu"));
+//! for row in rows.rows() {
+//!     let orders: Option<i64> = row.get("orders")?;
+//!     println!("{:?} {orders:?}", row.get::<&str>("region")?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Getters are strict about types (an `Int32` column is read as `i32`, not
+//! `i64`) and return `Ok(None)` for NULL.
+//!
+//! Toyota Way: Respect for People (no boilerplate between the user and the data)
+
+use crate::{Error, Result};
+use arrow::array::{Array, AsArray, RecordBatch};
+use arrow::datatypes::{
+    ArrowPrimitiveType, DataType, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type,
+    Int8Type, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+};
+
+/// A Rust type that can be read from one cell of an Arrow column
+pub trait ColumnValue<'a>: Sized {
+    /// Value of `column` at `row` (`Ok(None)` if NULL)
+    ///
+    /// # Errors
+    /// Returns error if the column's Arrow type does not hold this type
+    fn read(column: &'a dyn Array, row: usize) -> Result<Option<Self>>;
+}
+
+fn type_mismatch(column: &dyn Array, wanted: &str) -> Error {
+    Error::InvalidInput(format!("Column of type {} cannot be read as {wanted}", column.data_type()))
+}
+
+fn read_primitive<T: ArrowPrimitiveType>(
+    column: &dyn Array,
+    row: usize,
+    wanted: &str,
+) -> Result<Option<T::Native>> {
+    let array = column.as_primitive_opt::<T>().ok_or_else(|| type_mismatch(column, wanted))?;
+    Ok(array.is_valid(row).then(|| array.value(row)))
+}
+
+macro_rules! primitive_column_value {
+    ($($native:ty => $arrow:ty),* $(,)?) => {
+        $(
+            impl ColumnValue<'_> for $native {
+                fn read(column: &dyn Array, row: usize) -> Result<Option<Self>> {
+                    read_primitive::<$arrow>(column, row, stringify!($native))
+                }
+            }
+        )*
+    };
+}
+
+primitive_column_value! {
+    i8 => Int8Type,
+    i16 => Int16Type,
+    i32 => Int32Type,
+    i64 => Int64Type,
+    u8 => UInt8Type,
+    u16 => UInt16Type,
+    u32 => UInt32Type,
+    u64 => UInt64Type,
+    f32 => Float32Type,
+    f64 => Float64Type,
+}
+
+impl ColumnValue<'_> for bool {
+    fn read(column: &dyn Array, row: usize) -> Result<Option<Self>> {
+        let array = column.as_boolean_opt().ok_or_else(|| type_mismatch(column, "bool"))?;
+        Ok(array.is_valid(row).then(|| array.value(row)))
+    }
+}
+
+impl<'a> ColumnValue<'a> for &'a str {
+    fn read(column: &'a dyn Array, row: usize) -> Result<Option<Self>> {
+        if !column.is_valid(row) {
+            return Ok(None);
+        }
+        match column.data_type() {
+            DataType::Utf8 => Ok(Some(column.as_string::<i32>().value(row))),
+            DataType::LargeUtf8 => Ok(Some(column.as_string::<i64>().value(row))),
+            _ => Err(type_mismatch(column, "str")),
+        }
+    }
+}
+
+impl ColumnValue<'_> for String {
+    fn read(column: &dyn Array, row: usize) -> Result<Option<Self>> {
+        Ok(<&str>::read(column, row)?.map(str::to_string))
+    }
+}
+
+/// Typed getters over the rows of a result batch
+#[derive(Debug, Clone, Copy)]
+pub struct RowAccessor<'a> {
+    batch: &'a RecordBatch,
+}
+
+impl<'a> RowAccessor<'a> {
+    /// Wrap a result batch
+    #[must_use]
+    pub const fn new(batch: &'a RecordBatch) -> Self {
+        Self { batch }
+    }
+
+    /// Number of rows
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.batch.num_rows()
+    }
+
+    /// Whether the batch has no rows
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.batch.num_rows() == 0
+    }
+
+    /// Position of the column named `name`
+    ///
+    /// # Errors
+    /// Returns error if there is no such column
+    pub fn column_index(&self, name: &str) -> Result<usize> {
+        self.batch
+            .schema()
+            .index_of(name)
+            .map_err(|_| Error::InvalidInput(format!("Column not found: {name}")))
+    }
+
+    /// Value at `row` of the column named `column` (`Ok(None)` if NULL)
+    ///
+    /// # Errors
+    /// Returns error if the column does not exist, `row` is out of range,
+    /// or the column's type does not hold `T`
+    pub fn get<T: ColumnValue<'a>>(&self, row: usize, column: &str) -> Result<Option<T>> {
+        self.get_at(row, self.column_index(column)?)
+    }
+
+    /// Value at `row` of the column at position `column`
+    ///
+    /// # Errors
+    /// Returns error if `row` or `column` is out of range, or the column's
+    /// type does not hold `T`
+    pub fn get_at<T: ColumnValue<'a>>(&self, row: usize, column: usize) -> Result<Option<T>> {
+        if row >= self.batch.num_rows() {
+            return Err(Error::InvalidInput(format!(
+                "Row {row} out of range ({} rows)",
+                self.batch.num_rows()
+            )));
+        }
+        let array = self.batch.columns().get(column).ok_or_else(|| {
+            Error::InvalidInput(format!(
+                "Column {column} out of range ({} columns)",
+                self.batch.num_columns()
+            ))
+        })?;
+        T::read(array.as_ref(), row)
+    }
+
+    /// Row at position `index`
+    ///
+    /// # Errors
+    /// Returns error if `index` is out of range
+    pub fn row(&self, index: usize) -> Result<RowRef<'a>> {
+        if index >= self.batch.num_rows() {
+            return Err(Error::InvalidInput(format!(
+                "Row {index} out of range ({} rows)",
+                self.batch.num_rows()
+            )));
+        }
+        Ok(RowRef { rows: *self, index })
+    }
+
+    /// All rows, in order
+    pub fn rows(&self) -> impl Iterator<Item = RowRef<'a>> + 'a {
+        let rows = *self;
+        (0..self.batch.num_rows()).map(move |index| RowRef { rows, index })
+    }
+}
+
+/// One row of a result batch
+#[derive(Debug, Clone, Copy)]
+pub struct RowRef<'a> {
+    rows: RowAccessor<'a>,
+    index: usize,
+}
+
+impl<'a> RowRef<'a> {
+    /// Position of this row in the batch
+    #[must_use]
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Value of the column named `column` (`Ok(None)` if NULL)
+    ///
+    /// # Errors
+    /// Returns error if the column does not exist or its type does not hold `T`
+    pub fn get<T: ColumnValue<'a>>(&self, column: &str) -> Result<Option<T>> {
+        self.rows.get(self.index, column)
+    }
+
+    /// Value of the column at position `column`
+    ///
+    /// # Errors
+    /// Returns error if `column` is out of range or its type does not hold `T`
+    pub fn get_at<T: ColumnValue<'a>>(&self, column: usize) -> Result<Option<T>> {
+        self.rows.get_at(self.index, column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{BooleanArray, Float64Array, Int32Array, StringArray};
+    use arrow::datatypes::{Field, Schema};
+    use std::sync::Arc;
+
+    fn batch() -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, true),
+            Field::new("score", DataType::Float64, false),
+            Field::new("active", DataType::Boolean, false),
+        ]));
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])),
+                Arc::new(StringArray::from(vec![Some("ada"), None])),
+                Arc::new(Float64Array::from(vec![0.5, 1.5])),
+                Arc::new(BooleanArray::from(vec![true, false])),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_typed_getters() {
+        let batch = batch();
+        let rows = RowAccessor::new(&batch);
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows.get::<i32>(1, "id").unwrap(), Some(2));
+        assert_eq!(rows.get::<String>(0, "name").unwrap(), Some("ada".to_string()));
+        assert_eq!(rows.get::<&str>(1, "name").unwrap(), None);
+        assert_eq!(rows.get_at::<f64>(0, 2).unwrap(), Some(0.5));
+        assert_eq!(rows.get::<bool>(1, "active").unwrap(), Some(false));
+    }
+
+    #[test]
+    fn test_iteration_as_rows() {
+        let batch = batch();
+        let ids: Vec<(usize, i32)> = RowAccessor::new(&batch)
+            .rows()
+            .map(|row| (row.index(), row.get::<i32>("id").unwrap().unwrap()))
+            .collect();
+        assert_eq!(ids, vec![(0, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let batch = batch();
+        let rows = RowAccessor::new(&batch);
+
+        assert!(rows.get::<i64>(0, "id").is_err(), "Int32 is not read as i64");
+        assert!(rows.get::<i32>(0, "missing").is_err());
+        assert!(rows.get::<i32>(2, "id").is_err());
+        assert!(rows.get_at::<i32>(0, 9).is_err());
+        assert!(rows.row(2).is_err());
+    }
+}
@@ -0,0 +1,641 @@
+//! Scalar expression evaluation for SELECT items and aggregate arguments
+//!
+//! Like filters, computed SELECT items (`price * quantity AS revenue`) and
+//! expression arguments of aggregates (`SUM(price * quantity)`) reach the
+//! executor as canonical text (see [`binder::filter`](super::binder::filter)).
+//! [`evaluate`] parses the text back and computes one value per row with
+//! Arrow's compute kernels:
+//!
+//! - column references and literals (numbers, strings, Booleans, NULL)
+//! - `+`, `-`, `*`, `/`, `%` and unary minus; operands of different numeric
+//!   types are widened to `Int64`, or to `Float64` if either is floating
+//!   point. Integer literals are `Int64`. Integer overflow and division by
+//!   zero are errors
+//! - `ABS(x)`, `ROUND(x[, digits])` (half away from zero; negative digits
+//!   round left of the decimal point) and `COALESCE(a, b, ...)`
+//! - `CAST(x AS type)` and `x::type` to integer, floating-point, string and
+//!   Boolean types; `TRY_CAST` yields NULL where a value does not convert
+//! - `date_trunc('second' | 'minute' | 'hour' | 'day' | 'week' | 'month' |
+//!   'quarter' | 'year', t)` on dates and timestamps, keeping the input
+//!   type, and `EXTRACT(YEAR FROM t)` / `date_part('year', t)` (also
+//!   QUARTER, MONTH, WEEK, DAY, DOW, DOY, HOUR, MINUTE, SECOND) as `Int32`.
+//!   Both work on UTC instants whatever the column's time zone label;
+//!   fixed-width truncation is one vectorized floor per value
+//!
+//! NULL operands give NULL results, as in SQL. A subexpression repeated
+//! within or across the expressions evaluated over one batch (`price *
+//! quantity` in `revenue` and `ROUND(price * quantity, 2)`) is computed
+//! once (see [`Subexpressions`]).
+//!
+//! Toyota Way: Jidoka (unsupported expressions are errors, never guesses)
+
+use super::binder::{column_name, is_column, parse_filter};
+use super::predicate::{is_date_or_timestamp, literal};
+use super::QueryPlan;
+use crate::storage::dictionary::decode_column;
+use crate::{Error, Result};
+use arrow::array::{
+    make_array, new_null_array, Array, ArrayRef, AsArray, BooleanArray, Float64Array, Int64Array,
+    RecordBatch, Scalar, StringArray,
+};
+use arrow::compute::kernels::temporal::{date_part, DatePart};
+use arrow::compute::kernels::{cmp, numeric, zip::zip};
+use arrow::compute::{self, CastOptions};
+use arrow::datatypes::{DataType, Field, Float64Type, Int64Type, Schema, TimeUnit};
+use arrow::error::ArrowError;
+use chrono::{Datelike, NaiveDate};
+use sqlparser::ast::{
+    BinaryOperator, CastKind, Expr, Function, FunctionArg, FunctionArgExpr, FunctionArguments,
+    UnaryOperator, Value,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Arrays computed over one batch, keyed by canonical expression text
+///
+/// Evaluating through the same cache reuses the array of any compound
+/// subexpression seen before instead of recomputing it. Column references
+/// and literals are not cached. A cache must only ever see one batch.
+#[derive(Debug, Default)]
+pub(super) struct Subexpressions {
+    arrays: HashMap<String, ArrayRef>,
+}
+
+impl Subexpressions {
+    /// Values of the canonical expression `expr` for each row of `batch`
+    pub(super) fn evaluate(&mut self, batch: &RecordBatch, expr: &str) -> Result<ArrayRef> {
+        evaluate_expr(batch, self, &parse_filter(expr)?).map_err(|e| match e {
+            Error::Arrow(e) => Error::InvalidInput(format!("Cannot evaluate {expr}: {e}")),
+            e => e,
+        })
+    }
+
+    /// The cached array for `expr`, computing and caching it if missing
+    pub(super) fn get_or_compute(
+        &mut self,
+        expr: &Expr,
+        compute: impl FnOnce(&mut Self) -> Result<ArrayRef>,
+    ) -> Result<ArrayRef> {
+        let key = expr.to_string();
+        if let Some(array) = self.arrays.get(&key) {
+            return Ok(Arc::clone(array));
+        }
+        let array = compute(self)?;
+        self.arrays.insert(key, Arc::clone(&array));
+        Ok(array)
+    }
+}
+
+/// `batch` with a column appended for every GROUP BY key and aggregate
+/// argument that is an expression rather than a stored column
+/// (`GROUP BY date_trunc('day', ts)`, `SUM(price * quantity)`)
+///
+/// Each column is named by the key or argument as the plan holds it, so
+/// grouping and aggregation find it like any stored input. A key naming a
+/// computed SELECT item (`SELECT date_trunc('day', ts) AS day ... GROUP BY
+/// day`) evaluates that item's expression.
+pub(super) fn with_computed_inputs(batch: &RecordBatch, plan: &QueryPlan) -> Result<RecordBatch> {
+    let schema = batch.schema();
+    let mut fields: Vec<Field> = schema.fields().iter().map(|f| f.as_ref().clone()).collect();
+    let mut columns = batch.columns().to_vec();
+    let mut subexpressions = Subexpressions::default();
+
+    let keys = plan
+        .group_by
+        .iter()
+        .map(|key| (key, plan.computed.iter().find(|(name, _)| name == key).map(|(_, expr)| expr)));
+    let arguments = plan.aggregations.iter().map(|(_, argument, _)| (argument, None));
+    for (name, item) in keys.chain(arguments) {
+        if name == "*" || fields.iter().any(|field| field.name() == name) {
+            continue;
+        }
+        // Unknown columns are reported by grouping/aggregation as not found
+        let expr = match (item, parse_filter(name)) {
+            (Some(expr), _) => expr,
+            (None, Ok(parsed)) if !is_column(&parsed) => name,
+            _ => continue,
+        };
+        let column = subexpressions.evaluate(batch, expr)?;
+        fields.push(Field::new(name, column.data_type().clone(), true));
+        columns.push(column);
+    }
+
+    if columns.len() == batch.num_columns() {
+        return Ok(batch.clone());
+    }
+    let schema = Schema::new_with_metadata(fields, schema.metadata().clone());
+    RecordBatch::try_new(Arc::new(schema), columns)
+        .map_err(|e| Error::StorageError(format!("Failed to add computed inputs: {e}")))
+}
+
+fn evaluate_expr(
+    batch: &RecordBatch,
+    subexpressions: &mut Subexpressions,
+    expr: &Expr,
+) -> Result<ArrayRef> {
+    match expr {
+        Expr::Identifier(_) | Expr::CompoundIdentifier(_) => {
+            let name = column_name(expr);
+            let index = batch
+                .schema()
+                .index_of(&name)
+                .map_err(|_| Error::InvalidInput(format!("Column not found: {name}")))?;
+            decode_column(batch.column(index))
+        }
+        Expr::Nested(inner) | Expr::UnaryOp { op: UnaryOperator::Plus, expr: inner } => {
+            evaluate_expr(batch, subexpressions, inner)
+        }
+        Expr::Value(value) => constant(value, batch.num_rows()),
+        _ => subexpressions
+            .get_or_compute(expr, |subexpressions| compound(batch, subexpressions, expr)),
+    }
+}
+
+/// Values of an operator, cast or function call
+fn compound(
+    batch: &RecordBatch,
+    subexpressions: &mut Subexpressions,
+    expr: &Expr,
+) -> Result<ArrayRef> {
+    match expr {
+        Expr::UnaryOp { op: UnaryOperator::Minus, expr: inner } => match inner.as_ref() {
+            Expr::Value(Value::Number(number, _)) => {
+                number_constant(&format!("-{number}"), batch.num_rows())
+            }
+            _ => Ok(numeric::neg(&evaluate_expr(batch, subexpressions, inner)?)?),
+        },
+        Expr::BinaryOp { left, op, right } => {
+            let left = evaluate_expr(batch, subexpressions, left)?;
+            arithmetic(&left, op, &evaluate_expr(batch, subexpressions, right)?)
+        }
+        Expr::Cast { kind, expr: inner, data_type, .. } => {
+            let value = evaluate_expr(batch, subexpressions, inner)?;
+            let options = CastOptions {
+                safe: matches!(kind, CastKind::TryCast | CastKind::SafeCast),
+                ..CastOptions::default()
+            };
+            Ok(compute::cast_with_options(&value, &cast_type(data_type)?, &options)?)
+        }
+        Expr::Extract { field, expr: inner, .. } => {
+            extract(&field.to_string(), &evaluate_expr(batch, subexpressions, inner)?)
+        }
+        Expr::Function(func) => function(batch, subexpressions, func),
+        _ => Err(Error::InvalidInput(format!("Unsupported expression: {expr}"))),
+    }
+}
+
+/// A literal repeated for `rows` rows
+fn constant(value: &Value, rows: usize) -> Result<ArrayRef> {
+    match value {
+        Value::Number(number, _) => number_constant(number, rows),
+        Value::SingleQuotedString(text) | Value::DoubleQuotedString(text) => {
+            Ok(Arc::new(StringArray::from_iter_values(std::iter::repeat(text).take(rows))))
+        }
+        Value::Boolean(value) => Ok(Arc::new(BooleanArray::from(vec![*value; rows]))),
+        Value::Null => Ok(new_null_array(&DataType::Null, rows)),
+        _ => Err(Error::InvalidInput(format!("Unsupported literal: {value}"))),
+    }
+}
+
+/// `Int64` if `number` is an integer, `Float64` otherwise
+fn number_constant(number: &str, rows: usize) -> Result<ArrayRef> {
+    if let Ok(value) = number.parse::<i64>() {
+        return Ok(Arc::new(Int64Array::from_value(value, rows)));
+    }
+    number
+        .parse::<f64>()
+        .map(|value| Arc::new(Float64Array::from_value(value, rows)) as ArrayRef)
+        .map_err(|_| Error::ParseError(format!("Invalid number: {number}")))
+}
+
+fn arithmetic(left: &ArrayRef, op: &BinaryOperator, right: &ArrayRef) -> Result<ArrayRef> {
+    let kernel = match op {
+        BinaryOperator::Plus => numeric::add,
+        BinaryOperator::Minus => numeric::sub,
+        BinaryOperator::Multiply => numeric::mul,
+        BinaryOperator::Divide => numeric::div,
+        BinaryOperator::Modulo => numeric::rem,
+        _ => return Err(Error::InvalidInput(format!("Unsupported operator in expression: {op}"))),
+    };
+    let (left_type, right_type) = (left.data_type(), right.data_type());
+    if !is_numeric(left_type) || !is_numeric(right_type) {
+        return Err(Error::InvalidInput(format!(
+            "Arithmetic not supported for {left_type:?} and {right_type:?}"
+        )));
+    }
+    let data_type = common_numeric_type(left_type, right_type);
+    Ok(kernel(&compute::cast(left, &data_type)?, &compute::cast(right, &data_type)?)?)
+}
+
+fn is_numeric(data_type: &DataType) -> bool {
+    data_type.is_integer() || data_type.is_floating() || data_type == &DataType::Null
+}
+
+/// Type two numeric operands are cast to before they are combined
+fn common_numeric_type(left: &DataType, right: &DataType) -> DataType {
+    match (left, right) {
+        (DataType::Null, DataType::Null) => DataType::Int64,
+        (DataType::Null, other) | (other, DataType::Null) => other.clone(),
+        _ if left == right => left.clone(),
+        _ if left.is_floating() || right.is_floating() => DataType::Float64,
+        _ => DataType::Int64,
+    }
+}
+
+fn function(
+    batch: &RecordBatch,
+    subexpressions: &mut Subexpressions,
+    func: &Function,
+) -> Result<ArrayRef> {
+    let name = func.name.to_string().to_uppercase();
+    let args = function_args(func)?;
+    match (name.as_str(), args.as_slice()) {
+        ("ABS", [value]) => abs(&evaluate_expr(batch, subexpressions, value)?),
+        ("ROUND", [value]) => round(&evaluate_expr(batch, subexpressions, value)?, 0),
+        ("ROUND", [value, digits]) => {
+            let digits =
+                literal(digits)?.and_then(|digits| digits.parse().ok()).ok_or_else(|| {
+                    Error::InvalidInput(format!("ROUND digits must be an integer: {digits}"))
+                })?;
+            round(&evaluate_expr(batch, subexpressions, value)?, digits)
+        }
+        ("COALESCE", [_, ..]) => coalesce(
+            args.iter()
+                .map(|arg| evaluate_expr(batch, subexpressions, arg))
+                .collect::<Result<_>>()?,
+        ),
+        ("DATE_TRUNC", [unit, value]) => {
+            date_trunc(&text_argument(unit)?, &evaluate_expr(batch, subexpressions, value)?)
+        }
+        ("DATE_PART", [field, value]) => {
+            extract(&text_argument(field)?, &evaluate_expr(batch, subexpressions, value)?)
+        }
+        ("ABS" | "ROUND" | "COALESCE" | "DATE_TRUNC" | "DATE_PART", _) => {
+            Err(Error::InvalidInput(format!("Wrong number of arguments: {func}")))
+        }
+        _ => Err(Error::InvalidInput(format!("Unsupported function: {name}"))),
+    }
+}
+
+fn function_args(func: &Function) -> Result<Vec<&Expr>> {
+    match &func.args {
+        FunctionArguments::None => Ok(Vec::new()),
+        FunctionArguments::List(list) => list
+            .args
+            .iter()
+            .map(|arg| match arg {
+                FunctionArg::Unnamed(FunctionArgExpr::Expr(expr)) => Ok(expr),
+                _ => Err(Error::InvalidInput(format!("Unsupported function argument: {arg}"))),
+            })
+            .collect(),
+        FunctionArguments::Subquery(_) => {
+            Err(Error::InvalidInput(format!("Unsupported function argument: {func}")))
+        }
+    }
+}
+
+/// Text of a literal argument (`'day'` in `date_trunc('day', ts)`)
+fn text_argument(arg: &Expr) -> Result<String> {
+    literal(arg)?.ok_or_else(|| Error::InvalidInput(format!("Expected a text argument: {arg}")))
+}
+
+fn abs(value: &ArrayRef) -> Result<ArrayRef> {
+    let data_type = value.data_type();
+    if data_type.is_unsigned_integer() || data_type == &DataType::Null {
+        return Ok(Arc::clone(value));
+    }
+    if !is_numeric(data_type) {
+        return Err(Error::InvalidInput(format!("ABS not supported for {data_type:?}")));
+    }
+    let zero = compute::cast(&Int64Array::from_value(0, 1), data_type)?;
+    let negative = cmp::lt(value, &Scalar::new(zero))?;
+    Ok(zip(&negative, &numeric::neg(value)?, value)?)
+}
+
+fn round(value: &ArrayRef, digits: i32) -> Result<ArrayRef> {
+    let data_type = value.data_type();
+    if data_type == &DataType::Null || (data_type.is_integer() && digits >= 0) {
+        return Ok(Arc::clone(value));
+    }
+    if !is_numeric(data_type) {
+        return Err(Error::InvalidInput(format!("ROUND not supported for {data_type:?}")));
+    }
+    let scale = 10f64.powi(digits);
+    let floats = compute::cast(value, &DataType::Float64)?;
+    let rounded: Float64Array =
+        floats.as_primitive::<Float64Type>().unary(|v| (v * scale).round() / scale);
+    Ok(compute::cast(&rounded, data_type)?)
+}
+
+/// First non-NULL value of each row, in the arguments' common type
+fn coalesce(values: Vec<ArrayRef>) -> Result<ArrayRef> {
+    let data_type = values.iter().try_fold(DataType::Null, |common, value| {
+        let next = value.data_type();
+        match (&common, next) {
+            _ if &common == next => Ok(common),
+            (DataType::Null, _) => Ok(next.clone()),
+            (_, DataType::Null) => Ok(common),
+            _ if is_string(&common) && is_string(next) => Ok(DataType::Utf8),
+            _ if is_numeric(&common) && is_numeric(next) => Ok(common_numeric_type(&common, next)),
+            _ => Err(Error::InvalidInput(format!(
+                "COALESCE arguments have incompatible types: {common:?} and {next:?}"
+            ))),
+        }
+    })?;
+
+    let mut values = values.into_iter();
+    let mut result = match values.next() {
+        Some(first) => compute::cast(&first, &data_type)?,
+        None => return Err(Error::InvalidInput("COALESCE needs an argument".to_string())),
+    };
+    for value in values {
+        let present = compute::is_not_null(&result)?;
+        result = zip(&present, &result, &compute::cast(&value, &data_type)?)?;
+    }
+    Ok(result)
+}
+
+const fn is_string(data_type: &DataType) -> bool {
+    matches!(data_type, DataType::Utf8 | DataType::LargeUtf8)
+}
+
+/// Days from 0001-01-01 (chrono's day 1) to the Unix epoch
+const EPOCH_DAYS_FROM_CE: i32 = 719_163;
+
+/// `date_trunc('unit', value)`: start of the second, minute, hour, day,
+/// week (from Monday), month, quarter or year holding each value, in the
+/// input's type
+fn date_trunc(unit: &str, value: &ArrayRef) -> Result<ArrayRef> {
+    let data_type = value.data_type().clone();
+    let (stored, ticks_per_day) = match &data_type {
+        DataType::Date32 => (DataType::Int32, 1),
+        DataType::Date64 => (DataType::Int64, 86_400_000),
+        DataType::Timestamp(time_unit, _) => {
+            let per_second = match time_unit {
+                TimeUnit::Second => 1,
+                TimeUnit::Millisecond => 1_000,
+                TimeUnit::Microsecond => 1_000_000,
+                TimeUnit::Nanosecond => 1_000_000_000,
+            };
+            (DataType::Int64, 86_400 * per_second)
+        }
+        other => {
+            return Err(Error::InvalidInput(format!(
+                "date_trunc needs a date or timestamp, got {other:?}"
+            )))
+        }
+    };
+    let unit = unit.to_lowercase();
+    let width: Option<i64> = match unit.as_str() {
+        "second" => Some(ticks_per_day / 86_400),
+        "minute" => Some(ticks_per_day / 1_440),
+        "hour" => Some(ticks_per_day / 24),
+        "day" => Some(ticks_per_day),
+        "week" | "month" | "quarter" | "year" => None,
+        _ => return Err(Error::InvalidInput(format!("Unsupported date_trunc unit: {unit}"))),
+    };
+
+    let ticks = compute::cast(&relabel(value, stored.clone())?, &DataType::Int64)?;
+    let ticks = ticks.as_primitive::<Int64Type>();
+    let truncated: Int64Array = match width {
+        // Fixed-width units: one branch-free floor per value (vectorized)
+        Some(width) => {
+            let width = width.max(1);
+            ticks.unary(|v| v.div_euclid(width) * width)
+        }
+        None => ticks.try_unary::<_, Int64Type, ArrowError>(|v| {
+            truncate_day(v.div_euclid(ticks_per_day), &unit)
+                .map(|day| day * ticks_per_day)
+                .ok_or_else(|| ArrowError::ComputeError(format!("Date out of range: {v}")))
+        })?,
+    };
+    relabel(&compute::cast(&truncated, &stored)?, data_type)
+}
+
+/// First day (since the epoch) of the week, month, quarter or year
+/// holding `day`
+fn truncate_day(day: i64, unit: &str) -> Option<i64> {
+    if unit == "week" {
+        // 1970-01-05 was a Monday
+        return Some((day - 4).div_euclid(7) * 7 + 4);
+    }
+    let days_from_ce = i32::try_from(day).ok()?.checked_add(EPOCH_DAYS_FROM_CE)?;
+    let date = NaiveDate::from_num_days_from_ce_opt(days_from_ce)?;
+    let month = match unit {
+        "month" => date.month(),
+        "quarter" => (date.month() - 1) / 3 * 3 + 1,
+        _ => 1,
+    };
+    let start = NaiveDate::from_ymd_opt(date.year(), month, 1)?;
+    Some(i64::from(start.num_days_from_ce() - EPOCH_DAYS_FROM_CE))
+}
+
+/// `EXTRACT(field FROM value)` and `date_part('field', value)`, as `Int32`
+fn extract(field: &str, value: &ArrayRef) -> Result<ArrayRef> {
+    let part = match field.to_uppercase().as_str() {
+        "YEAR" => DatePart::Year,
+        "QUARTER" => DatePart::Quarter,
+        "MONTH" => DatePart::Month,
+        "WEEK" => DatePart::Week,
+        "DAY" => DatePart::Day,
+        "DOW" => DatePart::DayOfWeekSunday0,
+        "DOY" => DatePart::DayOfYear,
+        "HOUR" => DatePart::Hour,
+        "MINUTE" => DatePart::Minute,
+        "SECOND" => DatePart::Second,
+        _ => return Err(Error::InvalidInput(format!("Unsupported date part: {field}"))),
+    };
+    if !is_date_or_timestamp(value.data_type()) {
+        return Err(Error::InvalidInput(format!(
+            "EXTRACT needs a date or timestamp, got {:?}",
+            value.data_type()
+        )));
+    }
+    // Timestamps are UTC instants: dropping the zone label changes no value
+    let value = match value.data_type() {
+        DataType::Timestamp(unit, Some(_)) => relabel(value, DataType::Timestamp(*unit, None))?,
+        _ => Arc::clone(value),
+    };
+    Ok(date_part(value.as_ref(), part)?)
+}
+
+/// `value`'s buffers read as `data_type`, which has the same layout
+fn relabel(value: &ArrayRef, data_type: DataType) -> Result<ArrayRef> {
+    Ok(make_array(value.to_data().into_builder().data_type(data_type).build()?))
+}
+
+/// Arrow type of a SQL CAST target (`INT`, `DOUBLE`, `VARCHAR(20)`, ...)
+fn cast_type(data_type: &sqlparser::ast::DataType) -> Result<DataType> {
+    let name = data_type.to_string().to_uppercase();
+    let base = name.split('(').next().unwrap_or_default().trim();
+    match base {
+        "TINYINT" | "INT1" => Ok(DataType::Int8),
+        "SMALLINT" | "INT2" => Ok(DataType::Int16),
+        "INT" | "INTEGER" | "INT4" => Ok(DataType::Int32),
+        "BIGINT" | "INT8" | "INT64" => Ok(DataType::Int64),
+        "REAL" | "FLOAT4" | "FLOAT32" => Ok(DataType::Float32),
+        "FLOAT" | "FLOAT8" | "FLOAT64" | "DOUBLE" | "DOUBLE PRECISION" => Ok(DataType::Float64),
+        "VARCHAR" | "CHAR" | "CHARACTER" | "CHARACTER VARYING" | "TEXT" | "STRING" => {
+            Ok(DataType::Utf8)
+        }
+        "BOOLEAN" | "BOOL" => Ok(DataType::Boolean),
+        _ => Err(Error::InvalidInput(format!("Unsupported CAST type: {data_type}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Float32Array, Int32Array};
+
+    fn evaluate(batch: &RecordBatch, expr: &str) -> Result<ArrayRef> {
+        Subexpressions::default().evaluate(batch, expr)
+    }
+
+    fn batch() -> RecordBatch {
+        let schema = Schema::new(vec![
+            Field::new("price", DataType::Int32, false),
+            Field::new("quantity", DataType::Int32, true),
+            Field::new("x", DataType::Float32, false),
+            Field::new("name", DataType::Utf8, true),
+        ]);
+        RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int32Array::from(vec![10, -3, 7])),
+                Arc::new(Int32Array::from(vec![Some(2), None, Some(4)])),
+                Arc::new(Float32Array::from(vec![1.256, -2.5, 3.0])),
+                Arc::new(StringArray::from(vec![Some("a"), None, Some("c")])),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn int64s(array: &ArrayRef) -> Vec<Option<i64>> {
+        compute::cast(array, &DataType::Int64).unwrap().as_primitive::<Int64Type>().iter().collect()
+    }
+
+    #[test]
+    fn test_arithmetic_keeps_or_widens_types() {
+        let revenue = evaluate(&batch(), "price * quantity").unwrap();
+        assert_eq!(revenue.data_type(), &DataType::Int32);
+        assert_eq!(int64s(&revenue), vec![Some(20), None, Some(28)]);
+
+        let shifted = evaluate(&batch(), "-price + 1").unwrap();
+        assert_eq!(shifted.data_type(), &DataType::Int64);
+        assert_eq!(int64s(&shifted), vec![Some(-9), Some(4), Some(-6)]);
+
+        let scaled = evaluate(&batch(), "(x + price) / 2").unwrap();
+        assert_eq!(scaled.data_type(), &DataType::Float64);
+        assert!((scaled.as_primitive::<Float64Type>().value(2) - 5.0).abs() < 1e-9);
+
+        assert!(matches!(evaluate(&batch(), "price / 0"), Err(Error::InvalidInput(_))));
+        assert!(matches!(evaluate(&batch(), "name + 1"), Err(Error::InvalidInput(_))));
+        assert!(matches!(evaluate(&batch(), "missing * 2"), Err(Error::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_repeated_subexpressions_evaluated_once() {
+        let batch = batch();
+        let mut subexpressions = Subexpressions::default();
+        let revenue = subexpressions.evaluate(&batch, "price * quantity").unwrap();
+        let rounded = subexpressions.evaluate(&batch, "ROUND(price * quantity, -1)").unwrap();
+        assert_eq!(int64s(&rounded), vec![Some(20), None, Some(30)]);
+
+        // Bare and parenthesized repeats get the first array back
+        let again = subexpressions.evaluate(&batch, "(price * quantity)").unwrap();
+        assert!(Arc::ptr_eq(&revenue, &again));
+        let doubled = subexpressions.evaluate(&batch, "price * quantity + price * quantity");
+        assert_eq!(int64s(&doubled.unwrap()), vec![Some(40), None, Some(56)]);
+    }
+
+    #[test]
+    fn test_scalar_functions() {
+        let abs = evaluate(&batch(), "ABS(price)").unwrap();
+        assert_eq!(int64s(&abs), vec![Some(10), Some(3), Some(7)]);
+
+        let rounded = evaluate(&batch(), "ROUND(x, 2)").unwrap();
+        assert_eq!(rounded.data_type(), &DataType::Float32);
+        let rounded = rounded.as_primitive::<arrow::datatypes::Float32Type>();
+        assert!((rounded.value(0) - 1.26).abs() < 1e-6);
+        assert!((rounded.value(1) + 2.5).abs() < 1e-6);
+        let whole = evaluate(&batch(), "ROUND(x)").unwrap();
+        assert!(
+            (whole.as_primitive::<arrow::datatypes::Float32Type>().value(1) + 3.0).abs() < 1e-6
+        );
+        assert_eq!(
+            int64s(&evaluate(&batch(), "ROUND(price, -1)").unwrap()),
+            vec![Some(10), Some(0), Some(10)]
+        );
+
+        let quantity = evaluate(&batch(), "COALESCE(quantity, 0)").unwrap();
+        assert_eq!(int64s(&quantity), vec![Some(2), Some(0), Some(4)]);
+        let name = evaluate(&batch(), "COALESCE(name, 'unknown')").unwrap();
+        assert_eq!(name.as_string::<i32>().value(1), "unknown");
+        assert!(evaluate(&batch(), "COALESCE(name, 1)").is_err());
+        assert!(evaluate(&batch(), "UPPER(name)").is_err());
+    }
+
+    #[test]
+    fn test_cast() {
+        let text = evaluate(&batch(), "CAST(price AS VARCHAR)").unwrap();
+        assert_eq!(text.as_string::<i32>().value(1), "-3");
+        assert_eq!(evaluate(&batch(), "x::INT").unwrap().data_type(), &DataType::Int32);
+        assert!(evaluate(&batch(), "CAST(name AS INT)").is_err());
+        assert_eq!(evaluate(&batch(), "TRY_CAST(name AS INT)").unwrap().null_count(), 3);
+    }
+
+    #[test]
+    fn test_date_trunc_and_extract() {
+        use arrow::array::{Date32Array, TimestampMicrosecondArray};
+        use arrow::datatypes::TimestampMicrosecondType;
+
+        let micros = |date: &str| {
+            chrono::NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S")
+                .unwrap()
+                .and_utc()
+                .timestamp_micros()
+        };
+        let schema = Schema::new(vec![
+            Field::new("ts", DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())), true),
+            Field::new("day", DataType::Date32, true),
+        ]);
+        let ts = TimestampMicrosecondArray::from(vec![
+            Some(micros("2024-03-15 13:45:30")),
+            Some(micros("1969-12-31 23:10:00")),
+            None,
+        ])
+        .with_timezone("UTC");
+        // 2024-03-15, 1969-12-31
+        let day = Date32Array::from(vec![Some(19_797), Some(-1), None]);
+        let batch =
+            RecordBatch::try_new(Arc::new(schema), vec![Arc::new(ts), Arc::new(day)]).unwrap();
+
+        let truncated = |unit: &str| {
+            let array = evaluate(&batch, &format!("date_trunc('{unit}', ts)")).unwrap();
+            assert_eq!(array.data_type(), batch.column(0).data_type());
+            let array = array.as_primitive::<TimestampMicrosecondType>();
+            assert!(array.is_null(2));
+            (0..2).map(|i| array.value_as_datetime(i).unwrap().to_string()).collect::<Vec<_>>()
+        };
+        assert_eq!(truncated("hour"), ["2024-03-15 13:00:00", "1969-12-31 23:00:00"]);
+        assert_eq!(truncated("DAY"), ["2024-03-15 00:00:00", "1969-12-31 00:00:00"]);
+        assert_eq!(truncated("week"), ["2024-03-11 00:00:00", "1969-12-29 00:00:00"]);
+        assert_eq!(truncated("month"), ["2024-03-01 00:00:00", "1969-12-01 00:00:00"]);
+        assert_eq!(truncated("quarter"), ["2024-01-01 00:00:00", "1969-10-01 00:00:00"]);
+        assert_eq!(truncated("year"), ["2024-01-01 00:00:00", "1969-01-01 00:00:00"]);
+
+        let month = evaluate(&batch, "date_trunc('month', day)").unwrap();
+        assert_eq!(month.data_type(), &DataType::Date32);
+        assert_eq!(int64s(&month), vec![Some(19_783), Some(-31), None]);
+
+        let hour = evaluate(&batch, "EXTRACT(HOUR FROM ts)").unwrap();
+        assert_eq!(hour.data_type(), &DataType::Int32);
+        assert_eq!(int64s(&hour), vec![Some(13), Some(23), None]);
+        let year = evaluate(&batch, "date_part('year', day)").unwrap();
+        assert_eq!(int64s(&year), vec![Some(2024), Some(1969), None]);
+
+        assert!(evaluate(&batch, "date_trunc('fortnight', ts)").is_err());
+        assert!(evaluate(&batch, "EXTRACT(YEAR FROM 1)").is_err());
+    }
+}
@@ -0,0 +1,215 @@
+//! Selection vectors
+//!
+//! WHERE used to materialize a filtered copy of the batch, which the next
+//! operator then copied again. A [`SelectionVector`] instead names the
+//! surviving rows by id; operators pass it along (filter → top-k →
+//! gather) and column values are copied once, by whichever operator
+//! finally needs them.
+//!
+//! A filter is applied one top-level `AND` term at a time, each narrowing
+//! the selection of the last. While the selection is dense a term is
+//! evaluated over the whole batch and tested at the selected rows; once
+//! fewer than 1 in [`SPARSE_RATIO`] rows remain, it is evaluated over its
+//! own columns gathered at those rows, so selective leading terms make the
+//! later ones cheap.
+//!
+//! Toyota Way: Muda elimination (no intermediate copies between operators)
+
+use super::binder;
+use super::predicate;
+use crate::{Error, Result};
+use arrow::array::{Array, BooleanArray, UInt32Array};
+use arrow::compute;
+use arrow::record_batch::RecordBatch;
+use sqlparser::ast::Expr;
+
+/// Below 1 in this many rows selected, a term is evaluated on gathered
+/// copies of its columns instead of the whole batch
+const SPARSE_RATIO: usize = 8;
+
+/// Rows of a batch that survive the operators applied so far
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum SelectionVector {
+    /// Every row of a batch with this many rows
+    All(usize),
+    /// These row ids, ascending unless reordered by top-k
+    Rows(UInt32Array),
+}
+
+impl SelectionVector {
+    /// Rows of `batch` for which the canonical filter `filter` is true
+    /// (NULL counts as false)
+    ///
+    /// # Errors
+    /// Returns error if the filter cannot be parsed or evaluated on `batch`
+    pub(super) fn filter(batch: &RecordBatch, filter: &str) -> Result<Self> {
+        let mut selection = Self::All(batch.num_rows());
+        for term in predicate::conjuncts(filter)? {
+            if selection.is_empty() {
+                break;
+            }
+            selection = selection.refine(batch, &term)?;
+        }
+        Ok(selection)
+    }
+
+    /// Rows set in `mask` (NULL counts as false)
+    #[allow(clippy::cast_possible_truncation)]
+    pub(super) fn from_mask(mask: &BooleanArray) -> Self {
+        if mask.true_count() == mask.len() {
+            return Self::All(mask.len());
+        }
+        let rows = (0..mask.len()).filter(|&row| mask.is_valid(row) && mask.value(row));
+        Self::Rows(UInt32Array::from_iter_values(rows.map(|row| row as u32)))
+    }
+
+    /// Selected rows among those selected now, by position in this selection
+    #[allow(clippy::cast_possible_truncation)]
+    pub(super) fn pick(&self, positions: &[usize]) -> Self {
+        let rows = positions.iter().map(|&position| match self {
+            Self::All(_) => position as u32,
+            Self::Rows(rows) => rows.value(position),
+        });
+        Self::Rows(UInt32Array::from_iter_values(rows))
+    }
+
+    /// Number of selected rows
+    pub(super) fn len(&self) -> usize {
+        match self {
+            Self::All(num_rows) => *num_rows,
+            Self::Rows(rows) => rows.len(),
+        }
+    }
+
+    /// Whether no row is selected
+    pub(super) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Copy of the selected rows of `batch`, in selection order (a cheap
+    /// clone when every row is selected)
+    ///
+    /// # Errors
+    /// Returns error if a row id is out of bounds for `batch`
+    pub(super) fn gather(&self, batch: &RecordBatch) -> Result<RecordBatch> {
+        match self {
+            Self::All(_) => Ok(batch.clone()),
+            Self::Rows(rows) => compute::take_record_batch(batch, rows)
+                .map_err(|e| Error::StorageError(format!("Failed to gather rows: {e}"))),
+        }
+    }
+
+    /// The selected rows of `batch` that also pass `term`
+    fn refine(self, batch: &RecordBatch, term: &Expr) -> Result<Self> {
+        let rows = match self {
+            Self::All(_) => return Ok(Self::from_mask(&predicate::evaluate(batch, term)?)),
+            Self::Rows(rows) => rows,
+        };
+
+        if rows.len() * SPARSE_RATIO < batch.num_rows() {
+            if let Some(columns) = Self::term_columns(batch, term) {
+                let gathered = compute::take_record_batch(&columns, &rows)
+                    .map_err(|e| Error::StorageError(format!("Failed to gather rows: {e}")))?;
+                let mask = predicate::evaluate(&gathered, term)?;
+                let kept = rows
+                    .values()
+                    .iter()
+                    .enumerate()
+                    .filter(|&(position, _)| mask.is_valid(position) && mask.value(position))
+                    .map(|(_, &row)| row);
+                return Ok(Self::Rows(UInt32Array::from_iter_values(kept)));
+            }
+        }
+
+        let mask = predicate::evaluate(batch, term)?;
+        let kept = rows.values().iter().copied().filter(|&row| {
+            let row = row as usize;
+            mask.is_valid(row) && mask.value(row)
+        });
+        Ok(Self::Rows(UInt32Array::from_iter_values(kept)))
+    }
+
+    /// `batch` narrowed to the columns `term` reads (`None` if they cannot
+    /// be resolved by name)
+    fn term_columns(batch: &RecordBatch, term: &Expr) -> Option<RecordBatch> {
+        let schema = batch.schema();
+        let mut indices = binder::expr_columns(term)
+            .iter()
+            .map(|name| schema.index_of(name).ok())
+            .collect::<Option<Vec<usize>>>()?;
+        indices.sort_unstable();
+        indices.dedup();
+        batch.project(&indices).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Int32Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    fn batch(rows: i32) -> RecordBatch {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("score", DataType::Int32, true),
+            Field::new("tag", DataType::Utf8, false),
+        ]);
+        let scores: Vec<Option<i32>> =
+            (0..rows).map(|id| if id % 5 == 0 { None } else { Some(id % 10) }).collect();
+        let tags: Vec<&str> =
+            (0..rows).map(|id| if id % 2 == 0 { "even" } else { "odd" }).collect();
+        RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int32Array::from_iter_values(0..rows)),
+                Arc::new(Int32Array::from(scores)),
+                Arc::new(StringArray::from(tags)),
+            ],
+        )
+        .unwrap()
+    }
+
+    /// Row ids the whole filter selects, evaluated as one mask
+    fn expected(batch: &RecordBatch, filter: &str) -> SelectionVector {
+        SelectionVector::from_mask(&predicate::filter_mask(batch, filter).unwrap())
+    }
+
+    #[test]
+    fn test_conjunction_matches_single_mask() {
+        let batch = batch(1000);
+        for filter in [
+            // Dense: every term is evaluated over the batch
+            "score > 3 AND tag = 'odd'",
+            // Sparse after the first term: later terms see gathered rows
+            "id < 40 AND (score >= 3 AND tag = 'even') AND score IS NOT NULL",
+            "id < 40 AND (score < 2 OR tag = 'odd')",
+            // NULL scores are dropped by either term
+            "id >= 990 AND NOT score = 1",
+        ] {
+            let selection = SelectionVector::filter(&batch, filter).unwrap();
+            assert_eq!(selection, expected(&batch, filter), "{filter}");
+        }
+
+        let none = SelectionVector::filter(&batch, "id < 0 AND score > 1").unwrap();
+        assert!(none.is_empty());
+        let all = SelectionVector::filter(&batch, "id >= 0").unwrap();
+        assert_eq!(all, SelectionVector::All(1000));
+        assert_eq!(all.gather(&batch).unwrap().num_rows(), 1000);
+    }
+
+    #[test]
+    fn test_pick_and_gather() {
+        let batch = batch(10);
+        let odd = SelectionVector::filter(&batch, "tag = 'odd'").unwrap();
+        assert_eq!(odd.len(), 5);
+
+        // Positions 3 and 0 of the selection are rows 7 and 1
+        let picked = odd.pick(&[3, 0]);
+        let gathered = picked.gather(&batch).unwrap();
+        let ids = gathered.column(0).as_any().downcast_ref::<Int32Array>().unwrap();
+        assert_eq!(ids.values(), &[7, 1]);
+        assert_eq!(SelectionVector::All(10).pick(&[2]), SelectionVector::Rows(vec![2].into()));
+    }
+}
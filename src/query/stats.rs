@@ -0,0 +1,342 @@
+//! Execution statistics attached to query results
+//!
+//! An executor built with
+//! [`with_query_stats`](super::QueryExecutor::with_query_stats) records how
+//! much data a query touched and how long it took in the result's Arrow
+//! schema metadata. Because the stats travel with the `RecordBatch` (and its
+//! schema survives IPC/Flight), the CLI, WASM demo, and Flight server can
+//! display them without a separate profiling call. It is opt-in so that
+//! results otherwise compare equal across runs.
+//!
+//! [`QueryExecutor::execute_with_report`](super::QueryExecutor::execute_with_report)
+//! also returns an [`ExecutionReport`] breaking the query down by operator:
+//! rows in and out, time spent, the backend the cost model picked and the
+//! `PCIe` bytes [`BackendDispatcher`] estimates a GPU run would move.
+//!
+//! Toyota Way Principles:
+//! - Genchi Genbutsu: Every result carries its own measurements
+//! - Visual management: Stats are visible wherever the result goes
+
+use crate::backend::BackendDispatcher;
+use crate::{Backend, Error, Result};
+use arrow::record_batch::RecordBatch;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Schema metadata key: rows read from storage (including CTE sources)
+pub const ROWS_SCANNED_KEY: &str = "trueno.rows_scanned";
+
+/// Schema metadata key: rows in the result
+pub const ROWS_RETURNED_KEY: &str = "trueno.rows_returned";
+
+/// Schema metadata key: wall-clock execution time in milliseconds
+pub const ELAPSED_MS_KEY: &str = "trueno.elapsed_ms";
+
+/// Schema metadata key: backend that executed the query
+pub const BACKEND_KEY: &str = "trueno.backend";
+
+/// Execution statistics for one query
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct QueryStats {
+    /// Rows read from storage, summed over the main query and its CTEs
+    pub rows_scanned: usize,
+    /// Rows in the result batch
+    pub rows_returned: usize,
+    /// Wall-clock execution time in milliseconds
+    pub elapsed_ms: f64,
+    /// Backend that executed the operators
+    pub backend: Backend,
+}
+
+impl QueryStats {
+    /// Read stats from a result batch's schema metadata
+    ///
+    /// Returns `None` if the batch did not come from the executor (or the
+    /// metadata was stripped).
+    #[must_use]
+    pub fn from_batch(batch: &RecordBatch) -> Option<Self> {
+        Self::from_metadata(batch.schema().metadata())
+    }
+
+    /// Parse stats from schema metadata
+    #[must_use]
+    pub fn from_metadata(metadata: &HashMap<String, String>) -> Option<Self> {
+        Some(Self {
+            rows_scanned: metadata.get(ROWS_SCANNED_KEY)?.parse().ok()?,
+            rows_returned: metadata.get(ROWS_RETURNED_KEY)?.parse().ok()?,
+            elapsed_ms: metadata.get(ELAPSED_MS_KEY)?.parse().ok()?,
+            backend: Backend::from_name(metadata.get(BACKEND_KEY)?)?,
+        })
+    }
+
+    /// Stats as schema metadata entries
+    #[must_use]
+    pub fn to_metadata(&self) -> HashMap<String, String> {
+        HashMap::from([
+            (ROWS_SCANNED_KEY.to_string(), self.rows_scanned.to_string()),
+            (ROWS_RETURNED_KEY.to_string(), self.rows_returned.to_string()),
+            (ELAPSED_MS_KEY.to_string(), format!("{:.3}", self.elapsed_ms)),
+            (BACKEND_KEY.to_string(), self.backend.name().to_string()),
+        ])
+    }
+
+    /// Return `batch` with these stats merged into its schema metadata
+    ///
+    /// Existing metadata is kept; stats keys are overwritten.
+    ///
+    /// # Errors
+    /// Returns error if the batch cannot be rebuilt with the new schema
+    pub fn attach(&self, batch: RecordBatch) -> Result<RecordBatch> {
+        let schema = batch.schema();
+        let mut metadata = schema.metadata().clone();
+        metadata.extend(self.to_metadata());
+
+        let schema = std::sync::Arc::new(schema.as_ref().clone().with_metadata(metadata));
+        batch
+            .with_schema(schema)
+            .map_err(|e| Error::StorageError(format!("Failed to attach query stats: {e}")))
+    }
+}
+
+/// Measurements for one operator of an executed query
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OperatorReport {
+    /// Operator name, as in EXPLAIN (`Scan`, `HashJoin`, `Filter`, `Project`,
+    /// `Aggregate`, `HashAggregate`, `TopK`, `Limit`, `MorselPipeline` for
+    /// the fused per-morsel filter and aggregate of parallel execution, or
+    /// `ExternalSort` for the fused per-morsel filter, projection and
+    /// spilling sort of ORDER BY without LIMIT under a sort memory budget)
+    pub operator: String,
+    /// Operator arguments (table, predicate, aggregates, sort keys)
+    pub detail: String,
+    /// Backend the cost model picked for the operator
+    pub planned_backend: Backend,
+    /// Backend that executed the operator
+    pub backend: Backend,
+    /// Rows the operator read (both sides for a join)
+    pub rows_in: usize,
+    /// Rows the operator produced
+    pub rows_out: usize,
+    /// Wall-clock time in milliseconds
+    pub elapsed_ms: f64,
+    /// Host-to-GPU bytes the dispatcher estimates for the planned backend
+    /// (0 unless it picked [`Backend::Gpu`])
+    pub transfer_bytes: usize,
+}
+
+impl OperatorReport {
+    /// An operator executed on the CPU path that has no GPU alternative
+    pub(crate) fn simd(
+        operator: &str,
+        detail: String,
+        rows_in: usize,
+        rows_out: usize,
+        elapsed_ms: f64,
+    ) -> Self {
+        Self {
+            operator: operator.to_string(),
+            detail,
+            planned_backend: Backend::Simd,
+            backend: Backend::Simd,
+            rows_in,
+            rows_out,
+            elapsed_ms,
+            transfer_bytes: 0,
+        }
+    }
+
+    /// Record the cost model's choice for an operator over `total_bytes`
+    #[must_use]
+    pub(crate) const fn planned(mut self, backend: Backend, total_bytes: usize) -> Self {
+        self.planned_backend = backend;
+        self.transfer_bytes = BackendDispatcher::pcie_transfer_bytes(backend, total_bytes);
+        self
+    }
+}
+
+/// Per-operator breakdown of one executed query
+///
+/// Operators are listed in execution order, CTEs first. The totals match the
+/// [`QueryStats`] attached to the result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionReport {
+    /// Operators in execution order
+    pub operators: Vec<OperatorReport>,
+    /// Rows read from storage, summed over the main query and its CTEs
+    pub rows_scanned: usize,
+    /// Rows in the result batch
+    pub rows_returned: usize,
+    /// Wall-clock execution time in milliseconds
+    pub elapsed_ms: f64,
+    /// Backend that executed the operators
+    pub backend: Backend,
+}
+
+impl ExecutionReport {
+    /// Empty report, filled in as operators run
+    pub(crate) const fn new() -> Self {
+        Self {
+            operators: Vec::new(),
+            rows_scanned: 0,
+            rows_returned: 0,
+            elapsed_ms: 0.0,
+            backend: Backend::Simd,
+        }
+    }
+
+    pub(crate) fn push(&mut self, operator: OperatorReport) {
+        self.operators.push(operator);
+    }
+
+    /// First operator named `name`
+    #[must_use]
+    pub fn operator(&self, name: &str) -> Option<&OperatorReport> {
+        self.operators.iter().find(|op| op.operator == name)
+    }
+
+    /// Estimated host-to-GPU bytes summed over all operators
+    #[must_use]
+    pub fn transfer_bytes(&self) -> usize {
+        self.operators.iter().map(|op| op.transfer_bytes).sum()
+    }
+
+    /// Query-level totals
+    #[must_use]
+    pub const fn stats(&self) -> QueryStats {
+        QueryStats {
+            rows_scanned: self.rows_scanned,
+            rows_returned: self.rows_returned,
+            elapsed_ms: self.elapsed_ms,
+            backend: self.backend,
+        }
+    }
+}
+
+impl fmt::Display for ExecutionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for op in &self.operators {
+            write!(
+                f,
+                "{}: {} rows={}->{} time={:.3}ms backend={}",
+                op.operator,
+                op.detail,
+                op.rows_in,
+                op.rows_out,
+                op.elapsed_ms,
+                op.backend.name()
+            )?;
+            if op.planned_backend != op.backend {
+                write!(f, " planned={}", op.planned_backend.name())?;
+            }
+            if op.transfer_bytes > 0 {
+                write!(f, " pcie={}B", op.transfer_bytes)?;
+            }
+            writeln!(f)?;
+        }
+        write!(
+            f,
+            "Total: rows_scanned={} rows_returned={} time={:.3}ms pcie={}B",
+            self.rows_scanned,
+            self.rows_returned,
+            self.elapsed_ms,
+            self.transfer_bytes()
+        )
+    }
+}
+
+/// Wall-clock timer that also works on `wasm32` (where `Instant` panics)
+pub(crate) struct Stopwatch {
+    #[cfg(not(target_arch = "wasm32"))]
+    start: std::time::Instant,
+    #[cfg(target_arch = "wasm32")]
+    start_ms: f64,
+}
+
+impl Stopwatch {
+    pub(crate) fn start() -> Self {
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            start: std::time::Instant::now(),
+            #[cfg(target_arch = "wasm32")]
+            start_ms: js_sys::Date::now(),
+        }
+    }
+
+    pub(crate) fn elapsed(&self) -> std::time::Duration {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.start.elapsed()
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            std::time::Duration::from_secs_f64((self.elapsed_ms() / 1000.0).max(0.0))
+        }
+    }
+
+    pub(crate) fn elapsed_ms(&self) -> f64 {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.start.elapsed().as_secs_f64() * 1000.0
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            js_sys::Date::now() - self.start_ms
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::Int32Array;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    fn create_batch() -> RecordBatch {
+        let schema = Schema::new(vec![Field::new("id", DataType::Int32, false)])
+            .with_metadata(HashMap::from([("owner".to_string(), "etl".to_string())]));
+        RecordBatch::try_new(Arc::new(schema), vec![Arc::new(Int32Array::from(vec![1, 2]))])
+            .unwrap()
+    }
+
+    #[test]
+    fn test_stats_round_trip_through_metadata() {
+        let stats = QueryStats {
+            rows_scanned: 10,
+            rows_returned: 2,
+            elapsed_ms: 1.5,
+            backend: Backend::Simd,
+        };
+        let batch = stats.attach(create_batch()).unwrap();
+
+        assert_eq!(QueryStats::from_batch(&batch), Some(stats));
+        assert_eq!(batch.schema().metadata().get("owner").map(String::as_str), Some("etl"));
+    }
+
+    #[test]
+    fn test_report_totals_and_display() {
+        let mut report = ExecutionReport::new();
+        report.push(OperatorReport::simd("Scan", "t".to_string(), 100, 100, 0.5));
+        report.push(
+            OperatorReport::simd("HashAggregate", "SUM(v) GROUP BY k".to_string(), 100, 4, 1.0)
+                .planned(Backend::Gpu, 800),
+        );
+        report.rows_scanned = 100;
+        report.rows_returned = 4;
+
+        assert_eq!(report.transfer_bytes(), 800);
+        assert_eq!(report.operator("Scan").map(|op| op.rows_out), Some(100));
+        assert_eq!(report.stats().rows_returned, 4);
+
+        let text = report.to_string();
+        assert!(text.contains("HashAggregate: SUM(v) GROUP BY k rows=100->4"));
+        assert!(text.contains("planned=gpu pcie=800B"));
+        assert!(text.ends_with("pcie=800B"));
+    }
+
+    #[test]
+    fn test_stats_missing_from_plain_batch() {
+        assert_eq!(QueryStats::from_batch(&create_batch()), None);
+    }
+}
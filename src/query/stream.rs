@@ -0,0 +1,148 @@
+//! Incremental query results
+//!
+//! [`QueryExecutor::execute`](super::QueryExecutor::execute) returns one
+//! combined `RecordBatch`, so a large `SELECT *` holds its whole result in
+//! memory at once. A [`ResultStream`] (from
+//! [`QueryExecutor::execute_stream`](super::QueryExecutor::execute_stream))
+//! yields the result batch by batch instead:
+//!
+//! - **Plain scans** (projection, WHERE and LIMIT without aggregation,
+//!   GROUP BY, ORDER BY, JOINs or CTEs) are filtered and projected one
+//!   storage zone at a time, as the consumer asks for the next batch. Zones
+//!   the WHERE clause rules out (see zone maps) are never read, OFFSET
+//!   rows are dropped as they pass, and the stream ends as soon as LIMIT
+//!   rows have been returned.
+//! - **Plain scans with ORDER BY but no LIMIT**, under a sort memory budget
+//!   (`ipc-io` feature), push each filtered and projected zone into an
+//!   [`ExternalSorter`](crate::external_sort::ExternalSorter) and yield the
+//!   merged sorted runs batch by batch.
+//! - **Everything else** needs all input rows before its first output row,
+//!   so it runs to completion up front and the stream yields its result as
+//!   one batch.
+//!
+//! Toyota Way: Just-in-Time (produce each batch when it is consumed)
+
+use super::{QueryExecutor, QueryPlan};
+#[cfg(feature = "ipc-io")]
+use crate::external_sort::SortedBatches;
+use crate::Result;
+use arrow::array::RecordBatch;
+use arrow::datatypes::SchemaRef;
+
+/// Iterator over the result batches of one query
+///
+/// Every batch has the same [`schema`](Self::schema). After an error the
+/// stream yields nothing more.
+#[derive(Debug)]
+pub struct ResultStream {
+    schema: SchemaRef,
+    /// Plan whose filter and projection still apply to `pending`
+    /// (`None` once the result is materialized)
+    plan: Option<QueryPlan>,
+    pending: Pending,
+    /// Rows LIMIT still allows
+    remaining: usize,
+    /// Rows OFFSET still skips
+    skip: usize,
+}
+
+/// Batches a [`ResultStream`] has yet to yield
+#[derive(Debug)]
+enum Pending {
+    Batches(std::vec::IntoIter<RecordBatch>),
+    #[cfg(feature = "ipc-io")]
+    Sorted(SortedBatches),
+}
+
+impl ResultStream {
+    /// Stream `morsels` through `plan`'s filter and projection
+    ///
+    /// The projection is checked against an empty slice first, so unknown
+    /// columns fail here rather than on the first batch.
+    pub(super) fn scan(
+        plan: &QueryPlan,
+        morsels: Vec<RecordBatch>,
+        sample: &RecordBatch,
+    ) -> Result<Self> {
+        let schema = QueryExecutor::project_columns(&sample.slice(0, 0), plan)?.schema();
+        Ok(Self {
+            schema,
+            plan: Some(plan.clone()),
+            pending: Pending::Batches(morsels.into_iter()),
+            remaining: plan.limit.unwrap_or(usize::MAX),
+            skip: plan.offset,
+        })
+    }
+
+    /// Stream the output of an external sort, skipping `offset` rows
+    #[cfg(feature = "ipc-io")]
+    pub(super) const fn sorted(schema: SchemaRef, sorted: SortedBatches, offset: usize) -> Self {
+        Self {
+            schema,
+            plan: None,
+            pending: Pending::Sorted(sorted),
+            remaining: usize::MAX,
+            skip: offset,
+        }
+    }
+
+    /// Stream an already computed result as a single batch
+    pub(super) fn materialized(result: RecordBatch) -> Self {
+        Self {
+            schema: result.schema(),
+            plan: None,
+            pending: Pending::Batches(vec![result].into_iter()),
+            remaining: usize::MAX,
+            skip: 0,
+        }
+    }
+
+    /// Schema of every batch the stream yields
+    #[must_use]
+    pub fn schema(&self) -> SchemaRef {
+        SchemaRef::clone(&self.schema)
+    }
+
+    /// Filter and project one morsel
+    pub(super) fn process(plan: &QueryPlan, morsel: &RecordBatch) -> Result<RecordBatch> {
+        let filtered = match &plan.filter {
+            Some(filter_expr) => QueryExecutor::apply_filter(morsel, filter_expr)?,
+            None => morsel.clone(),
+        };
+        QueryExecutor::project_columns(&filtered, plan)
+    }
+}
+
+impl Iterator for ResultStream {
+    type Item = Result<RecordBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let batch = match &mut self.pending {
+            Pending::Batches(batches) => {
+                let morsel = batches.next()?;
+                match &self.plan {
+                    Some(plan) => Self::process(plan, &morsel),
+                    None => Ok(morsel),
+                }
+            }
+            #[cfg(feature = "ipc-io")]
+            Pending::Sorted(sorted) => sorted.next()?,
+        };
+        match batch {
+            Ok(batch) => {
+                let skipped = batch.num_rows().min(self.skip);
+                self.skip -= skipped;
+                let rows = (batch.num_rows() - skipped).min(self.remaining);
+                self.remaining -= rows;
+                Some(Ok(batch.slice(skipped, rows)))
+            }
+            Err(e) => {
+                self.remaining = 0;
+                Some(Err(e))
+            }
+        }
+    }
+}
@@ -0,0 +1,240 @@
+//! Uncorrelated subqueries in WHERE
+//!
+//! `WHERE value > (SELECT AVG(value) FROM t)` and `WHERE id [NOT] IN
+//! (SELECT id FROM vip)` are planned as queries of their own
+//! ([`QueryPlan::subqueries`]), keyed by their SQL text in the filter. The
+//! executor runs each one once, before the outer query, and binds its
+//! result into the predicate as literals:
+//!
+//! - a scalar subquery becomes its single value (NULL if it returned no
+//!   rows; more than one row is an error)
+//! - an `IN` subquery becomes a value list; an empty result makes `IN`
+//!   false and `NOT IN` true
+//!
+//! Subqueries cannot reference the outer query's columns. Their FROM
+//! tables resolve like a CTE's: earlier CTEs, then joinable tables, then
+//! the queried storage.
+//!
+//! Toyota Way: Muda elimination (each subquery runs once, not once per row)
+
+use super::binder::parse_filter;
+use super::{QueryPlan, Subquery};
+use crate::{Error, Result};
+use arrow::array::{Array, ArrayRef, RecordBatch};
+use arrow::datatypes::DataType;
+use arrow::util::display::array_value_to_string;
+use sqlparser::ast::{Expr, FunctionArg, FunctionArgExpr, FunctionArguments, Query, Value};
+use std::collections::HashSet;
+
+/// Plans for the subqueries of a WHERE expression, one per distinct SQL text
+pub(super) fn plans(
+    selection: &Expr,
+    mut plan: impl FnMut(&Query) -> Result<QueryPlan>,
+) -> Result<Vec<Subquery>> {
+    let mut subqueries: Vec<Subquery> = Vec::new();
+    visit(&mut selection.clone(), &mut |node| {
+        if let Some(query) = query_of(node) {
+            let sql = query.to_string();
+            if !subqueries.iter().any(|(seen, _)| *seen == sql) {
+                subqueries.push((sql, plan(query)?));
+            }
+        }
+        Ok(())
+    })?;
+    Ok(subqueries)
+}
+
+/// `filter` with every subquery replaced by its result from `results`
+/// (keyed by SQL text)
+///
+/// # Errors
+/// Returns error if a result has more than one column, a scalar subquery
+/// returned several rows, or a value has no SQL literal form
+pub(super) fn bind(filter: &str, results: &[(&str, RecordBatch)]) -> Result<String> {
+    let mut expr = parse_filter(filter)?;
+    visit(&mut expr, &mut |node| {
+        let Some(query) = query_of(node) else {
+            return Ok(());
+        };
+        let sql = query.to_string();
+        let (_, result) = results
+            .iter()
+            .find(|(planned, _)| *planned == sql)
+            .ok_or_else(|| Error::InvalidInput(format!("Subquery was not planned: {sql}")))?;
+        let column = single_column(result, &sql)?;
+        *node = match std::mem::replace(node, Expr::Value(Value::Null)) {
+            Expr::InSubquery { expr, negated, .. } => in_list(*expr, column, negated)?,
+            _ => scalar(column, &sql)?,
+        };
+        Ok(())
+    })?;
+    Ok(expr.to_string())
+}
+
+/// Query of a scalar or `IN` subquery node
+fn query_of(expr: &Expr) -> Option<&Query> {
+    match expr {
+        Expr::Subquery(query) | Expr::InSubquery { subquery: query, .. } => Some(query),
+        _ => None,
+    }
+}
+
+/// Call `f` on every subquery node of a filter expression, innermost
+/// operands first
+fn visit(expr: &mut Expr, f: &mut impl FnMut(&mut Expr) -> Result<()>) -> Result<()> {
+    match expr {
+        Expr::Subquery(_) => f(expr),
+        Expr::InSubquery { expr: operand, .. } => {
+            visit(operand, f)?;
+            f(expr)
+        }
+        Expr::BinaryOp { left, right, .. } => {
+            visit(left, f)?;
+            visit(right, f)
+        }
+        Expr::UnaryOp { expr, .. }
+        | Expr::Nested(expr)
+        | Expr::IsNull(expr)
+        | Expr::IsNotNull(expr)
+        | Expr::IsTrue(expr)
+        | Expr::IsFalse(expr)
+        | Expr::Cast { expr, .. }
+        | Expr::Extract { expr, .. } => visit(expr, f),
+        Expr::Between { expr, low, high, .. } => {
+            visit(expr, f)?;
+            visit(low, f)?;
+            visit(high, f)
+        }
+        Expr::InList { expr, list, .. } => {
+            visit(expr, f)?;
+            list.iter_mut().try_for_each(|item| visit(item, f))
+        }
+        Expr::Like { expr, pattern, .. } | Expr::ILike { expr, pattern, .. } => {
+            visit(expr, f)?;
+            visit(pattern, f)
+        }
+        Expr::Function(func) => match &mut func.args {
+            FunctionArguments::List(list) => list.args.iter_mut().try_for_each(|arg| match arg {
+                FunctionArg::Unnamed(FunctionArgExpr::Expr(expr))
+                | FunctionArg::Named { arg: FunctionArgExpr::Expr(expr), .. } => visit(expr, f),
+                _ => Ok(()),
+            }),
+            _ => Ok(()),
+        },
+        _ => Ok(()),
+    }
+}
+
+fn single_column<'a>(result: &'a RecordBatch, sql: &str) -> Result<&'a ArrayRef> {
+    match result.columns() {
+        [column] => Ok(column),
+        columns => Err(Error::InvalidInput(format!(
+            "Subquery must return one column, got {}: {sql}",
+            columns.len()
+        ))),
+    }
+}
+
+/// The single value of a scalar subquery (NULL for no rows)
+fn scalar(column: &ArrayRef, sql: &str) -> Result<Expr> {
+    match column.len() {
+        0 => Ok(Expr::Value(Value::Null)),
+        1 => literal(column, 0),
+        rows => Err(Error::InvalidInput(format!("Scalar subquery returned {rows} rows: {sql}"))),
+    }
+}
+
+/// `expr [NOT] IN (values...)`, each distinct value listed once
+fn in_list(expr: Expr, column: &ArrayRef, negated: bool) -> Result<Expr> {
+    let mut seen = HashSet::new();
+    let mut list = Vec::new();
+    for row in 0..column.len() {
+        let value = literal(column, row)?;
+        if seen.insert(value.to_string()) {
+            list.push(value);
+        }
+    }
+    if list.is_empty() {
+        // Nothing is IN an empty set, so NOT IN holds even for NULL
+        return Ok(Expr::Value(Value::Boolean(negated)));
+    }
+    Ok(Expr::InList { expr: Box::new(expr), list, negated })
+}
+
+/// SQL literal for one value; numbers and Booleans stay typed, everything
+/// else (strings, dates, timestamps) is quoted text
+fn literal(column: &ArrayRef, row: usize) -> Result<Expr> {
+    if column.is_null(row) {
+        return Ok(Expr::Value(Value::Null));
+    }
+    let text = array_value_to_string(column, row)?;
+    let data_type = match column.data_type() {
+        DataType::Dictionary(_, values) => values.as_ref(),
+        data_type => data_type,
+    };
+    let value = match data_type {
+        DataType::Boolean => Value::Boolean(text == "true"),
+        data_type if data_type.is_numeric() => {
+            if !text.parse::<f64>().is_ok_and(f64::is_finite) {
+                return Err(Error::InvalidInput(format!(
+                    "Subquery value has no SQL literal: {text}"
+                )));
+            }
+            Value::Number(text, false)
+        }
+        _ => Value::SingleQuotedString(text),
+    };
+    Ok(Expr::Value(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Float64Array, Int32Array, StringArray};
+    use arrow::datatypes::{Field, Schema};
+    use std::sync::Arc;
+
+    fn batch(column: ArrayRef) -> RecordBatch {
+        let schema = Schema::new(vec![Field::new("v", column.data_type().clone(), true)]);
+        RecordBatch::try_new(Arc::new(schema), vec![column]).unwrap()
+    }
+
+    fn sql_of(filter: &str) -> Vec<String> {
+        let mut expr = parse_filter(filter).unwrap();
+        let mut sql = Vec::new();
+        visit(&mut expr, &mut |node| {
+            sql.extend(query_of(node).map(ToString::to_string));
+            Ok(())
+        })
+        .unwrap();
+        sql
+    }
+
+    #[test]
+    fn test_bind_scalar_and_in_subqueries() {
+        let filter = "v > (SELECT AVG(v) FROM t) AND id NOT IN (SELECT id FROM vip)";
+        let sql = sql_of(filter);
+        assert_eq!(sql, vec!["SELECT AVG(v) FROM t", "SELECT id FROM vip"]);
+
+        let average = batch(Arc::new(Float64Array::from(vec![2.5])));
+        let ids = batch(Arc::new(Int32Array::from(vec![Some(3), Some(1), Some(3), None])));
+        let results = [(sql[0].as_str(), average), (sql[1].as_str(), ids)];
+        assert_eq!(bind(filter, &results).unwrap(), "v > 2.5 AND id NOT IN (3, 1, NULL)");
+    }
+
+    #[test]
+    fn test_bind_edge_results() {
+        let empty = batch(Arc::new(StringArray::from(Vec::<&str>::new())));
+        let quoted = batch(Arc::new(StringArray::from(vec!["o'neil"])));
+
+        let sql = "SELECT v FROM t";
+        assert_eq!(bind("name IN (SELECT v FROM t)", &[(sql, empty.clone())]).unwrap(), "false");
+        assert_eq!(bind("name NOT IN (SELECT v FROM t)", &[(sql, empty.clone())]).unwrap(), "true");
+        assert_eq!(bind("name = (SELECT v FROM t)", &[(sql, empty)]).unwrap(), "name = NULL");
+        assert_eq!(bind("name = (SELECT v FROM t)", &[(sql, quoted)]).unwrap(), "name = 'o''neil'");
+
+        let two_rows = batch(Arc::new(Int32Array::from(vec![1, 2])));
+        assert!(bind("id = (SELECT v FROM t)", &[(sql, two_rows)]).is_err());
+        assert!(bind("id = (SELECT v FROM t)", &[]).is_err());
+    }
+}
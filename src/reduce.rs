@@ -0,0 +1,158 @@
+//! SIMD floating-point reductions for the CPU aggregation path
+//!
+//! SUM, AVG, MIN and MAX over `Float32`/`Float64` columns used to run as
+//! one-element-at-a-time iterator loops, whose serial dependency on the
+//! running total keeps the compiler from vectorizing them. These kernels
+//! break that dependency:
+//!
+//! - **`f32`**: trueno's [`Vector`] (AVX-512/AVX2/SSE2/NEON, chosen at
+//!   runtime) with Kahan-compensated summation ([`sum_f32`], [`min_f32`],
+//!   [`max_f32`])
+//! - **`f64`**: trueno's vectors are `f32`, so [`SIMD_LANES`] independent
+//!   Kahan accumulators are updated in lockstep instead (the layout
+//!   [`welford_simd`](crate::variance::welford_simd) uses), which the
+//!   compiler vectorizes ([`sum_f64`], [`min_f64`], [`max_f64`])
+//!
+//! Compensation keeps long sums accurate: a plain running `f32` total of
+//! a million values loses the low digits of every addend once it is large.
+//! NULLs are skipped by the caller; empty input has no minimum or maximum.
+//!
+//! Toyota Way: Kaizen (same results, every lane busy)
+
+use crate::variance::SIMD_LANES;
+use trueno::Vector;
+
+/// Kahan-compensated sum of `values` (trueno SIMD)
+#[must_use]
+pub fn sum_f32(values: &[f32]) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    Vector::from_slice(values).sum_kahan().unwrap_or_else(|_| values.iter().sum())
+}
+
+/// Smallest of `values` (trueno SIMD), or `None` if empty
+#[must_use]
+pub fn min_f32(values: &[f32]) -> Option<f32> {
+    if values.is_empty() {
+        return None;
+    }
+    Vector::from_slice(values).min().ok()
+}
+
+/// Largest of `values` (trueno SIMD), or `None` if empty
+#[must_use]
+pub fn max_f32(values: &[f32]) -> Option<f32> {
+    if values.is_empty() {
+        return None;
+    }
+    Vector::from_slice(values).max().ok()
+}
+
+/// Kahan-compensated sum of `values` in `f64`, over [`SIMD_LANES`]
+/// interleaved accumulators
+///
+/// Lane `i` sees elements `i, i + LANES, ...`; the lanes and the tail are
+/// then combined with the same compensation.
+#[must_use]
+pub fn sum_f64<T: Copy + Into<f64>>(values: &[T]) -> f64 {
+    let mut sums = [0.0_f64; SIMD_LANES];
+    let mut errors = [0.0_f64; SIMD_LANES];
+
+    let chunks = values.chunks_exact(SIMD_LANES);
+    let tail = chunks.remainder();
+    for chunk in chunks {
+        for lane in 0..SIMD_LANES {
+            let value = chunk[lane].into() - errors[lane];
+            let total = sums[lane] + value;
+            errors[lane] = (total - sums[lane]) - value;
+            sums[lane] = total;
+        }
+    }
+
+    let mut total = Kahan::default();
+    for lane in 0..SIMD_LANES {
+        total.add(sums[lane]);
+        total.add(-errors[lane]);
+    }
+    for &value in tail {
+        total.add(value.into());
+    }
+    total.sum
+}
+
+/// Smallest of `values` over [`SIMD_LANES`] lanes, or `None` if empty
+#[must_use]
+pub fn min_f64(values: &[f64]) -> Option<f64> {
+    fold_lanes(values, f64::min)
+}
+
+/// Largest of `values` over [`SIMD_LANES`] lanes, or `None` if empty
+#[must_use]
+pub fn max_f64(values: &[f64]) -> Option<f64> {
+    fold_lanes(values, f64::max)
+}
+
+fn fold_lanes(values: &[f64], op: impl Fn(f64, f64) -> f64) -> Option<f64> {
+    let first = *values.first()?;
+    let mut lanes = [first; SIMD_LANES];
+    let chunks = values.chunks_exact(SIMD_LANES);
+    let tail = chunks.remainder();
+    for chunk in chunks {
+        for lane in 0..SIMD_LANES {
+            lanes[lane] = op(lanes[lane], chunk[lane]);
+        }
+    }
+    Some(lanes.into_iter().chain(tail.iter().copied()).fold(first, op))
+}
+
+/// Running Kahan sum for combining lanes
+#[derive(Default)]
+struct Kahan {
+    sum: f64,
+    error: f64,
+}
+
+impl Kahan {
+    fn add(&mut self, value: f64) {
+        let value = value - self.error;
+        let total = self.sum + value;
+        self.error = (total - self.sum) - value;
+        self.sum = total;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compensated_sums_beat_naive_loops() {
+        // 1e8 + 10_000 * 0.5: a plain f32 total drops every 0.5
+        let mut values = vec![1e8_f32];
+        values.resize(10_001, 0.5);
+        let naive: f32 = values.iter().sum();
+        assert!((naive - 1e8).abs() < 1.0);
+        assert!((f64::from(sum_f32(&values)) - 100_005_000.0).abs() < 16.0);
+
+        let values: Vec<f64> = (0..1003).map(|i| if i == 0 { 1e16 } else { 1.0 }).collect();
+        assert!((sum_f64(&values) - (1e16 + 1002.0)).abs() < 1e-6);
+        assert!((sum_f64(&[0.5_f32, 0.25]) - 0.75).abs() < 1e-12);
+        assert!(sum_f64::<f64>(&[]).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    #[allow(clippy::cast_possible_truncation)]
+    fn test_min_max_with_tail() {
+        let values: Vec<f64> = (0..21).map(|i| f64::from((i * 7) % 23) - 3.0).collect();
+        assert_eq!(min_f64(&values), values.iter().copied().reduce(f64::min));
+        assert_eq!(max_f64(&values), values.iter().copied().reduce(f64::max));
+        assert_eq!(min_f64(&[]), None);
+
+        let floats: Vec<f32> = values.iter().map(|&v| v as f32).collect();
+        assert_eq!(min_f32(&floats), Some(-3.0));
+        assert_eq!(max_f32(&floats), Some(19.0));
+        assert_eq!(max_f32(&[]), None);
+        assert!(sum_f32(&[]).abs() < f32::EPSILON);
+    }
+}
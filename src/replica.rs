@@ -0,0 +1,463 @@
+//! Shared database directory: one writer process, many read-only replicas
+//!
+//! A writer publishes immutable snapshots of a [`Catalog`]; readers in other
+//! processes attach the same directory and only ever see fully committed
+//! snapshots.
+//!
+//! ## On-disk layout
+//!
+//! ```text
+//! <db>/
+//!   WRITER.lock                       exclusive writer lock (holds writer pid)
+//!   CURRENT                           id of the latest committed snapshot
+//!   snapshots/
+//!     00000000000000000007/
+//!       MANIFEST.json                 table names → data files
+//!       table_0000.parquet
+//!   readers/
+//!     <pid>-<n>.lease                 snapshot id pinned by a live reader
+//! ```
+//!
+//! ## Protocol
+//!
+//! - **Writer lock**: `WRITER.lock` is created with `O_EXCL` semantics, so at
+//!   most one writer exists. It is removed when the writer is dropped.
+//! - **Commit**: the snapshot is written to a hidden temp directory, fsynced,
+//!   then renamed into `snapshots/`. `CURRENT` is replaced via
+//!   write-temp-then-rename, which is the atomic commit point.
+//! - **Read**: a reader writes its lease *before* re-checking `CURRENT`; if
+//!   `CURRENT` moved in between it retries. A snapshot is therefore either
+//!   pinned by a lease or still current when the writer garbage-collects.
+//! - **GC**: after each commit the writer deletes snapshots that are neither
+//!   among the most recent `retain` nor pinned by a lease.
+//!
+//! Toyota Way: Poka-Yoke (readers cannot observe a half-written snapshot)
+
+use crate::catalog::Catalog;
+use crate::storage::{ParquetWriteOptions, StorageEngine};
+use crate::{Error, Result};
+use arrow::array::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const WRITER_LOCK: &str = "WRITER.lock";
+const CURRENT: &str = "CURRENT";
+const SNAPSHOTS_DIR: &str = "snapshots";
+const READERS_DIR: &str = "readers";
+const MANIFEST: &str = "MANIFEST.json";
+const LEASE_EXTENSION: &str = "lease";
+
+/// Default number of most recent snapshots kept regardless of leases
+pub const DEFAULT_RETAINED_SNAPSHOTS: usize = 2;
+
+/// Maximum attempts to pin a snapshot while the writer keeps committing
+const MAX_PIN_ATTEMPTS: usize = 16;
+
+/// Distinguishes leases of multiple readers within one process
+static LEASE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Snapshot manifest (`MANIFEST.json`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    /// Snapshot id (monotonically increasing)
+    pub id: u64,
+    /// Commit time
+    pub committed_at: chrono::DateTime<chrono::Utc>,
+    /// Tables in this snapshot, in name order
+    pub tables: Vec<ManifestTable>,
+}
+
+/// One table entry in a snapshot manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestTable {
+    /// Table name
+    pub name: String,
+    /// Data file relative to the snapshot directory (`None` for empty tables)
+    pub file: Option<String>,
+    /// Total row count
+    pub num_rows: usize,
+}
+
+/// Exclusive writer handle for a database directory
+pub struct DatabaseWriter {
+    root: PathBuf,
+    retain: usize,
+    write_options: ParquetWriteOptions,
+}
+
+impl DatabaseWriter {
+    /// Open (or create) a database directory for writing
+    ///
+    /// # Errors
+    /// Returns error if another writer holds the lock or the directory
+    /// cannot be created
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let root = path.as_ref().to_path_buf();
+        fs::create_dir_all(root.join(SNAPSHOTS_DIR))?;
+        fs::create_dir_all(root.join(READERS_DIR))?;
+
+        let lock_path = root.join(WRITER_LOCK);
+        let mut lock =
+            OpenOptions::new().write(true).create_new(true).open(&lock_path).map_err(|e| {
+                if e.kind() == std::io::ErrorKind::AlreadyExists {
+                    let holder = fs::read_to_string(&lock_path).unwrap_or_default();
+                    Error::StorageError(format!(
+                        "Database {} is locked by writer pid {}. \
+                         If no writer is running, remove {}",
+                        root.display(),
+                        holder.trim(),
+                        lock_path.display()
+                    ))
+                } else {
+                    Error::Io(e)
+                }
+            })?;
+        writeln!(lock, "{}", std::process::id())?;
+        lock.sync_all()?;
+
+        Ok(Self {
+            root,
+            retain: DEFAULT_RETAINED_SNAPSHOTS,
+            write_options: ParquetWriteOptions::new(),
+        })
+    }
+
+    /// Number of most recent snapshots always kept (minimum 1)
+    #[must_use]
+    pub fn with_retained_snapshots(mut self, retain: usize) -> Self {
+        self.retain = retain.max(1);
+        self
+    }
+
+    /// Compression and dictionary settings for table files in new snapshots
+    #[must_use]
+    pub fn with_write_options(mut self, options: ParquetWriteOptions) -> Self {
+        self.write_options = options;
+        self
+    }
+
+    /// Id of the latest committed snapshot (`None` before the first commit)
+    ///
+    /// # Errors
+    /// Returns error if `CURRENT` exists but cannot be read
+    pub fn current_snapshot(&self) -> Result<Option<u64>> {
+        read_current(&self.root)
+    }
+
+    /// Commit the catalog as a new snapshot and return its id
+    ///
+    /// Temporary tables are session-scoped and are not included.
+    ///
+    /// # Errors
+    /// Returns error if any table fails to serialize or the commit cannot
+    /// be made durable
+    pub fn commit(&mut self, catalog: &Catalog) -> Result<u64> {
+        let id = self.current_snapshot()?.map_or(1, |current| current + 1);
+        let snapshots = self.root.join(SNAPSHOTS_DIR);
+        let tmp_dir = snapshots.join(format!(".tmp-{}", snapshot_dir_name(id)));
+        if tmp_dir.exists() {
+            fs::remove_dir_all(&tmp_dir)?;
+        }
+        fs::create_dir_all(&tmp_dir)?;
+
+        // Evicted tables are read from their spill files without being reloaded
+        let mut tables = Vec::with_capacity(catalog.len());
+        for (idx, name) in catalog.persistent_table_names().into_iter().enumerate() {
+            let entry = catalog.with_table(name, |storage| -> Result<ManifestTable> {
+                let file = if storage.batches().is_empty() {
+                    None
+                } else {
+                    let file_name = format!("table_{idx:04}.parquet");
+                    write_table(&tmp_dir.join(&file_name), storage, &self.write_options).map_err(
+                        |e| Error::StorageError(format!("Failed to write table '{name}': {e}")),
+                    )?;
+                    Some(file_name)
+                };
+                let num_rows = storage.batches().iter().map(RecordBatch::num_rows).sum();
+                Ok(ManifestTable { name: name.to_string(), file, num_rows })
+            })?;
+            if let Some(entry) = entry {
+                tables.push(entry?);
+            }
+        }
+
+        let manifest = SnapshotManifest { id, committed_at: chrono::Utc::now(), tables };
+        let json = serde_json::to_vec_pretty(&manifest)
+            .map_err(|e| Error::StorageError(format!("Failed to encode manifest: {e}")))?;
+        write_durable(&tmp_dir.join(MANIFEST), &json)?;
+        sync_dir(&tmp_dir)?;
+
+        fs::rename(&tmp_dir, snapshots.join(snapshot_dir_name(id)))?;
+        sync_dir(&snapshots)?;
+
+        // Commit point: readers switch to the new snapshot once CURRENT moves
+        let current_tmp = self.root.join(format!(".{CURRENT}.tmp"));
+        write_durable(&current_tmp, id.to_string().as_bytes())?;
+        fs::rename(&current_tmp, self.root.join(CURRENT))?;
+        sync_dir(&self.root)?;
+
+        self.collect_garbage(id)?;
+        Ok(id)
+    }
+
+    /// Delete snapshots that are neither recent nor pinned by a reader lease
+    fn collect_garbage(&self, current: u64) -> Result<()> {
+        let pinned = pinned_snapshots(&self.root)?;
+        let oldest_retained = current.saturating_sub(self.retain as u64 - 1);
+
+        for id in list_snapshots(&self.root)? {
+            if id < oldest_retained && !pinned.contains(&id) {
+                fs::remove_dir_all(self.root.join(SNAPSHOTS_DIR).join(snapshot_dir_name(id)))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for DatabaseWriter {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(self.root.join(WRITER_LOCK));
+    }
+}
+
+/// Read-only replica of a database directory
+///
+/// Holds a lease on the snapshot it has loaded so the writer will not
+/// delete it; call [`refresh`](Self::refresh) to move to the latest commit.
+pub struct DatabaseReader {
+    root: PathBuf,
+    lease_path: PathBuf,
+    manifest: SnapshotManifest,
+    catalog: Catalog,
+}
+
+impl DatabaseReader {
+    /// Attach a database directory read-only at its latest committed snapshot
+    ///
+    /// # Errors
+    /// Returns error if nothing has been committed yet or the snapshot
+    /// cannot be loaded
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let root = path.as_ref().to_path_buf();
+        let lease_path = root.join(READERS_DIR).join(format!(
+            "{}-{}.{LEASE_EXTENSION}",
+            std::process::id(),
+            LEASE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let id = pin_current(&root, &lease_path)?;
+        let (manifest, catalog) = match load_snapshot(&root, id) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                let _ = fs::remove_file(&lease_path);
+                return Err(e);
+            }
+        };
+
+        Ok(Self { root, lease_path, manifest, catalog })
+    }
+
+    /// Id of the snapshot this reader is pinned to
+    #[must_use]
+    pub const fn snapshot_id(&self) -> u64 {
+        self.manifest.id
+    }
+
+    /// Manifest of the loaded snapshot
+    #[must_use]
+    pub const fn manifest(&self) -> &SnapshotManifest {
+        &self.manifest
+    }
+
+    /// Tables of the loaded snapshot
+    #[must_use]
+    pub const fn catalog(&self) -> &Catalog {
+        &self.catalog
+    }
+
+    /// Move to the latest committed snapshot
+    ///
+    /// # Returns
+    /// `true` if a newer snapshot was loaded
+    ///
+    /// # Errors
+    /// Returns error if the new snapshot cannot be loaded (the reader keeps
+    /// its previous snapshot and lease)
+    pub fn refresh(&mut self) -> Result<bool> {
+        if read_current(&self.root)? == Some(self.manifest.id) {
+            return Ok(false);
+        }
+
+        let id = pin_current(&self.root, &self.lease_path)?;
+        match load_snapshot(&self.root, id) {
+            Ok((manifest, catalog)) => {
+                self.manifest = manifest;
+                self.catalog = catalog;
+                Ok(true)
+            }
+            Err(e) => {
+                write_durable(&self.lease_path, self.manifest.id.to_string().as_bytes())?;
+                Err(e)
+            }
+        }
+    }
+}
+
+impl Drop for DatabaseReader {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.lease_path);
+    }
+}
+
+/// Pin the current snapshot with a lease, retrying while the writer commits
+fn pin_current(root: &Path, lease_path: &Path) -> Result<u64> {
+    for _ in 0..MAX_PIN_ATTEMPTS {
+        let Some(id) = read_current(root)? else {
+            return Err(Error::StorageError(format!(
+                "Database {} has no committed snapshot",
+                root.display()
+            )));
+        };
+
+        write_durable(lease_path, id.to_string().as_bytes())?;
+        if read_current(root)? == Some(id) {
+            return Ok(id);
+        }
+    }
+
+    Err(Error::StorageError(format!(
+        "Failed to pin a snapshot of {} after {MAX_PIN_ATTEMPTS} attempts",
+        root.display()
+    )))
+}
+
+fn load_snapshot(root: &Path, id: u64) -> Result<(SnapshotManifest, Catalog)> {
+    let dir = root.join(SNAPSHOTS_DIR).join(snapshot_dir_name(id));
+    let bytes = fs::read(dir.join(MANIFEST)).map_err(|e| {
+        Error::StorageError(format!("Failed to read manifest of snapshot {id}: {e}"))
+    })?;
+    let manifest: SnapshotManifest = serde_json::from_slice(&bytes)
+        .map_err(|e| Error::StorageError(format!("Invalid manifest of snapshot {id}: {e}")))?;
+
+    let mut catalog = Catalog::new();
+    for table in &manifest.tables {
+        let storage = match &table.file {
+            Some(file) => StorageEngine::load_parquet(dir.join(file))?,
+            None => StorageEngine::new(Vec::new()),
+        };
+        catalog.register(table.name.clone(), storage);
+    }
+
+    Ok((manifest, catalog))
+}
+
+fn read_current(root: &Path) -> Result<Option<u64>> {
+    match fs::read_to_string(root.join(CURRENT)) {
+        Ok(contents) => contents.trim().parse().map(Some).map_err(|e| {
+            Error::StorageError(format!("Corrupt CURRENT file in {}: {e}", root.display()))
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Error::Io(e)),
+    }
+}
+
+fn list_snapshots(root: &Path) -> Result<Vec<u64>> {
+    let mut ids = Vec::new();
+    for entry in fs::read_dir(root.join(SNAPSHOTS_DIR))? {
+        if let Some(id) = entry?.file_name().to_str().and_then(|name| name.parse().ok()) {
+            ids.push(id);
+        }
+    }
+    ids.sort_unstable();
+    Ok(ids)
+}
+
+fn pinned_snapshots(root: &Path) -> Result<HashSet<u64>> {
+    let mut pinned = HashSet::new();
+    for entry in fs::read_dir(root.join(READERS_DIR))? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(LEASE_EXTENSION) {
+            continue;
+        }
+        // A lease may vanish between listing and reading (reader dropped)
+        if let Ok(contents) = fs::read_to_string(&path) {
+            if let Ok(id) = contents.trim().parse() {
+                pinned.insert(id);
+            }
+        }
+    }
+    Ok(pinned)
+}
+
+fn snapshot_dir_name(id: u64) -> String {
+    format!("{id:020}")
+}
+
+fn write_table(path: &Path, storage: &StorageEngine, options: &ParquetWriteOptions) -> Result<()> {
+    let batches = storage.batches();
+    let props = options.writer_properties(batches)?;
+    let file = File::create(path)?;
+    let mut writer = ArrowWriter::try_new(file, batches[0].schema(), Some(props))
+        .map_err(|e| Error::StorageError(e.to_string()))?;
+    for batch in batches {
+        writer.write(batch).map_err(|e| Error::StorageError(e.to_string()))?;
+    }
+    let file = writer.into_inner().map_err(|e| Error::StorageError(e.to_string()))?;
+    file.sync_all()?;
+    Ok(())
+}
+
+fn write_durable(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Persist directory entries (renames) on platforms that support it
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> Result<()> {
+    File::open(path)?.sync_all()?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+#[allow(clippy::unnecessary_wraps)]
+fn sync_dir(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_dir_name_sorts_numerically() {
+        assert_eq!(snapshot_dir_name(7), "00000000000000000007");
+        assert!(snapshot_dir_name(9) < snapshot_dir_name(10));
+    }
+
+    #[test]
+    fn test_read_current_missing_is_none() {
+        let dir = std::env::temp_dir().join("trueno_test_replica_no_current");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        assert_eq!(read_current(&dir).unwrap(), None);
+    }
+
+    #[test]
+    fn test_read_current_rejects_garbage() {
+        let dir = std::env::temp_dir().join("trueno_test_replica_bad_current");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(CURRENT), "not-a-number").unwrap();
+
+        assert!(read_current(&dir).is_err());
+    }
+}
@@ -0,0 +1,175 @@
+//! Thread-safe database handle: concurrent queries and appends
+//!
+//! [`Database`] takes `&mut self` to query and to append, so an ingest task
+//! and query tasks cannot hold it at the same time. A [`SharedDatabase`]
+//! wraps it in `Arc<RwLock<_>>` and keeps the lock out of the way of query
+//! execution:
+//!
+//! - **Queries** take the read lock only to parse and to snapshot the tables
+//!   they read (the batch lists are copied, batches are shared), then run
+//!   with no lock held.
+//! - **Appends** take the write lock for as long as the append itself.
+//!
+//! ## Snapshot semantics
+//!
+//! A query sees every batch appended before its snapshot and none appended
+//! after, even if appends land while it runs. All the tables one query reads
+//! are snapshotted under a single lock acquisition, so a join never sees one
+//! table before an append and another after it. Batches are immutable, so a
+//! snapshot costs one `Arc` clone per batch whatever the table size (see
+//! [`StorageSnapshot`](crate::storage::StorageSnapshot)).
+//!
+//! Evicted tables (memory limit, `ipc-io` feature) are reloaded under the
+//! write lock, since reloading changes the catalog.
+//!
+//! References:
+//! - Berenson et al. (1995): A critique of ANSI SQL isolation levels
+//!   (snapshot isolation)
+//!
+//! Toyota Way: Heijunka (ingest and analytics flow together instead of taking turns)
+
+use crate::query::{QueryExecutor, QueryPlan};
+use crate::storage::StorageEngine;
+use crate::{Catalog, Database, Result};
+use arrow::record_batch::RecordBatch;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Instant;
+
+/// Cloneable, thread-safe handle to a [`Database`]
+///
+/// Clones share the same database. Queries run concurrently with each other
+/// and with appends; each sees a consistent snapshot of the tables it reads
+/// (see the [module docs](self)).
+///
+/// # Example
+/// ```
+/// use arrow::array::{Int32Array, RecordBatch};
+/// use arrow::datatypes::{DataType, Field, Schema};
+/// use std::sync::Arc;
+/// use trueno_db::storage::StorageEngine;
+/// use trueno_db::Database;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+/// let batch = |ids: Vec<i32>| {
+///     RecordBatch::try_new(Arc::clone(&schema), vec![Arc::new(Int32Array::from(ids))])
+/// };
+///
+/// let mut db = Database::builder().build()?;
+/// db.register_table("events", StorageEngine::new(vec![batch(vec![1, 2])?]))?;
+/// let db = db.into_shared();
+///
+/// let (ingest, more) = (db.clone(), batch(vec![3])?);
+/// std::thread::spawn(move || ingest.append_batch("events", more)).join().unwrap()?;
+/// assert_eq!(db.sql("SELECT id FROM events")?.num_rows(), 3);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct SharedDatabase {
+    inner: Arc<RwLock<Database>>,
+}
+
+impl From<Database> for SharedDatabase {
+    fn from(db: Database) -> Self {
+        Self::new(db)
+    }
+}
+
+impl SharedDatabase {
+    /// Share `db`
+    #[must_use]
+    pub fn new(db: Database) -> Self {
+        Self { inner: Arc::new(RwLock::new(db)) }
+    }
+
+    /// Run a query against a snapshot of the tables it reads
+    ///
+    /// Like [`Database::sql`]: the query holds an admission slot while it
+    /// runs and is recorded if workload capture is on. No lock is held
+    /// while it executes or waits for admission.
+    ///
+    /// # Errors
+    /// Returns error if admission is refused, the query fails to parse,
+    /// reads an unknown table, or fails to execute
+    pub fn sql(&self, query: &str) -> Result<RecordBatch> {
+        let admission = self.read().admission().clone();
+        let _permit = admission.acquire()?;
+        let started = Instant::now();
+        let mut tables = Catalog::new();
+        let result = self
+            .snapshot(query, &mut tables)
+            .and_then(|(plan, executor)| executor.execute_catalog(&plan, &tables));
+        if self.read().capture.is_some() {
+            if let Some(workload) = &mut self.write().capture {
+                workload.record(query, tables.iter(), &result, started.elapsed());
+            }
+        }
+        result
+    }
+
+    /// Append `batch` to the table registered as `name`
+    ///
+    /// Queries already running keep their snapshot; queries started after
+    /// this returns see the new rows.
+    ///
+    /// # Errors
+    /// As [`Database::append_batch`]
+    pub fn append_batch(&self, name: &str, batch: RecordBatch) -> Result<()> {
+        self.write().append_batch(name, batch)
+    }
+
+    /// Append several batches to `name` as one version (a bulk load): a
+    /// query sees all of them or none
+    ///
+    /// # Errors
+    /// As [`Database::append_batches`]
+    pub fn append_batches(&self, name: &str, batches: Vec<RecordBatch>) -> Result<()> {
+        self.write().append_batches(name, batches)
+    }
+
+    /// Register a persistent table, returning the table it replaces
+    ///
+    /// # Errors
+    /// As [`Database::register_table`]
+    pub fn register_table(
+        &self,
+        name: impl Into<String>,
+        storage: StorageEngine,
+    ) -> Result<Option<StorageEngine>> {
+        self.write().register_table(name, storage)
+    }
+
+    /// Shared access to the database (blocks appends while held)
+    pub fn read(&self) -> RwLockReadGuard<'_, Database> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Exclusive access to the database (blocks queries from starting while
+    /// held)
+    pub fn write(&self) -> RwLockWriteGuard<'_, Database> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Parse `query` and snapshot the tables it reads into `tables`
+    fn snapshot(&self, query: &str, tables: &mut Catalog) -> Result<(QueryPlan, QueryExecutor)> {
+        let plan = {
+            let db = self.read();
+            let plan = db.engine.parse(query)?;
+            let names = plan.base_tables();
+            if names.iter().all(|&name| db.catalog.get(name).is_some()) {
+                for name in names {
+                    let storage = db.catalog.get(name).expect("checked above");
+                    tables.register(name, storage.snapshot().into_storage());
+                }
+                return Ok((plan, db.executor.clone()));
+            }
+            plan
+        };
+
+        // A table is evicted (or missing): reloading changes the catalog
+        let mut db = self.write();
+        *tables = db.resident_tables(&plan.base_tables())?;
+        Ok((plan, db.executor.clone()))
+    }
+}
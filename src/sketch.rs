@@ -0,0 +1,489 @@
+//! Mergeable sketches for approximate aggregates
+//!
+//! An exact `COUNT(DISTINCT x)` keeps every distinct value, and an exact
+//! percentile sorts the column; over hundreds of millions of rows neither
+//! is interactive. A sketch summarizes a column in bounded memory, and two
+//! sketches merge into the sketch of the concatenated input, so morsels,
+//! partitions and replicas can each sketch their rows independently:
+//!
+//! - **Distinct counts**: [`HyperLogLog`], `2^precision` one-byte registers
+//!   (16 KiB at [`DEFAULT_PRECISION`]), relative standard error
+//!   `1.04 / sqrt(2^precision)` (about 0.8%)
+//! - **Quantiles**: [`TDigest`], at most a few times `compression`
+//!   centroids, most accurate near the tails (p99 is closer than p50)
+//!
+//! Both serialize with serde, so sketches built elsewhere can be merged.
+//!
+//! References:
+//! - Flajolet et al. (2007): `HyperLogLog`: the analysis of a near-optimal
+//!   cardinality estimation algorithm
+//! - Dunning & Ertl (2019): Computing extremely accurate quantiles using
+//!   t-digests
+//!
+//! Toyota Way: Muda elimination (answer within the error bar, not beyond it)
+
+use crate::{Error, Result};
+use arrow::array::{Array, ArrayRef, AsArray};
+use arrow::compute;
+use arrow::datatypes::{DataType, Float64Type};
+use arrow::row::{RowConverter, SortField};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// Register index bits of a [`HyperLogLog`] when none are given
+pub const DEFAULT_PRECISION: u8 = 14;
+
+/// Fewest register index bits of a [`HyperLogLog`]
+pub const MIN_PRECISION: u8 = 4;
+
+/// Most register index bits of a [`HyperLogLog`]
+pub const MAX_PRECISION: u8 = 18;
+
+/// Compression of a [`TDigest`] when none is given
+pub const DEFAULT_COMPRESSION: f64 = 100.0;
+
+/// Values buffered per unit of compression before a [`TDigest`] merges them
+const BUFFER_FACTOR: f64 = 5.0;
+
+/// `HyperLogLog` distinct-count sketch
+///
+/// Deserializing checks the precision and register count, so a malformed
+/// sketch from elsewhere is an error rather than a panic on insert.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "RawHyperLogLog")]
+pub struct HyperLogLog {
+    precision: u8,
+    /// Longest run of leading zeros (plus one) seen per register
+    registers: Vec<u8>,
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        Self { precision: DEFAULT_PRECISION, registers: vec![0; 1 << DEFAULT_PRECISION] }
+    }
+}
+
+impl HyperLogLog {
+    /// Empty sketch with `2^precision` registers
+    ///
+    /// # Errors
+    /// Returns error if `precision` is outside
+    /// [`MIN_PRECISION`]..=[`MAX_PRECISION`]
+    pub fn new(precision: u8) -> Result<Self> {
+        if !(MIN_PRECISION..=MAX_PRECISION).contains(&precision) {
+            return Err(Error::InvalidInput(format!(
+                "HyperLogLog precision must be {MIN_PRECISION}..={MAX_PRECISION}, got {precision}"
+            )));
+        }
+        Ok(Self { precision, registers: vec![0; 1 << precision] })
+    }
+
+    /// Register index bits
+    #[must_use]
+    pub const fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    /// Relative standard error of [`estimate`](Self::estimate)
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn relative_error(&self) -> f64 {
+        1.04 / (self.registers.len() as f64).sqrt()
+    }
+
+    /// Add a value by its bytes
+    pub fn insert(&mut self, bytes: &[u8]) {
+        self.insert_hash(hash64(bytes));
+    }
+
+    /// Add a value by its 64-bit hash (which must be well mixed, as from
+    /// [`hash64`])
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn insert_hash(&mut self, hash: u64) {
+        let index = (hash >> (64 - self.precision)) as usize;
+        // The guard bit bounds the rank when the remaining bits are all zero
+        let rest = (hash << self.precision) | (1 << (self.precision - 1));
+        let rank = rest.leading_zeros() as u8 + 1;
+        self.registers[index] = self.registers[index].max(rank);
+    }
+
+    /// Add the non-null values of `array` (any type Arrow can row-encode)
+    ///
+    /// Values are hashed by their row encoding, so a column must be
+    /// sketched with one data type throughout.
+    ///
+    /// # Errors
+    /// Returns error if the data type cannot be row-encoded
+    pub fn insert_array(&mut self, array: &ArrayRef) -> Result<()> {
+        let converter = RowConverter::new(vec![SortField::new(array.data_type().clone())])
+            .map_err(|e| {
+                Error::InvalidInput(format!("Cannot sketch {}: {e}", array.data_type()))
+            })?;
+        let rows = converter.convert_columns(std::slice::from_ref(array)).map_err(|e| {
+            Error::InvalidInput(format!("Cannot sketch {}: {e}", array.data_type()))
+        })?;
+        for (row, encoded) in rows.iter().enumerate() {
+            if array.is_valid(row) {
+                self.insert(encoded.as_ref());
+            }
+        }
+        Ok(())
+    }
+
+    /// Fold `other` in, as if its values had been inserted here
+    ///
+    /// # Errors
+    /// Returns error if the sketches have different precisions
+    pub fn merge(&mut self, other: &Self) -> Result<()> {
+        if self.precision != other.precision {
+            return Err(Error::InvalidInput(format!(
+                "Cannot merge HyperLogLog sketches of precision {} and {}",
+                self.precision, other.precision
+            )));
+        }
+        for (register, &theirs) in self.registers.iter_mut().zip(&other.registers) {
+            *register = (*register).max(theirs);
+        }
+        Ok(())
+    }
+
+    /// Estimated number of distinct values inserted
+    ///
+    /// Small cardinalities, where registers are still empty, use linear
+    /// counting; 64-bit hashes need no large-range correction.
+    #[must_use]
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::naive_bytecount
+    )]
+    pub fn estimate(&self) -> u64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let sum: f64 = self.registers.iter().map(|&r| (-f64::from(r)).exp2()).sum();
+        let zeros = self.registers.iter().filter(|&&r| r == 0).count();
+        let raw = alpha * m * m / sum;
+        let estimate = if raw <= 2.5 * m && zeros > 0 { m * (m / zeros as f64).ln() } else { raw };
+        estimate.round() as u64
+    }
+}
+
+/// Unvalidated serialized form of a [`HyperLogLog`]
+#[derive(Deserialize)]
+struct RawHyperLogLog {
+    precision: u8,
+    registers: Vec<u8>,
+}
+
+impl TryFrom<RawHyperLogLog> for HyperLogLog {
+    type Error = Error;
+
+    fn try_from(raw: RawHyperLogLog) -> Result<Self> {
+        let mut sketch = Self::new(raw.precision)?;
+        if raw.registers.len() != sketch.registers.len() {
+            return Err(Error::InvalidInput(format!(
+                "HyperLogLog of precision {} needs {} registers, got {}",
+                raw.precision,
+                sketch.registers.len(),
+                raw.registers.len()
+            )));
+        }
+        // A rank counts the leading zeros of the 64 - precision bits left
+        // after the index, plus the guard bit
+        let max_rank = 65 - raw.precision;
+        if let Some(&rank) = raw.registers.iter().find(|&&rank| rank > max_rank) {
+            return Err(Error::InvalidInput(format!(
+                "HyperLogLog register {rank} exceeds the largest rank {max_rank}"
+            )));
+        }
+        sketch.registers = raw.registers;
+        Ok(sketch)
+    }
+}
+
+/// Cluster of nearby values in a [`TDigest`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct Centroid {
+    /// Mean of the values
+    mean: f64,
+    /// Number of values
+    weight: f64,
+}
+
+/// Merging t-digest quantile sketch
+///
+/// Values are buffered and periodically merged into centroids whose
+/// size is bounded by the `k1` scale function: centroids near the median
+/// may hold many values, those in the tails only a few.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TDigest {
+    compression: f64,
+    /// Merged centroids, ascending by mean
+    centroids: Vec<Centroid>,
+    /// Values not yet merged
+    buffer: Vec<f64>,
+    count: u64,
+    min: f64,
+    max: f64,
+}
+
+impl Default for TDigest {
+    fn default() -> Self {
+        Self::new(DEFAULT_COMPRESSION)
+    }
+}
+
+impl TDigest {
+    /// Empty digest; higher `compression` keeps more centroids and is more
+    /// accurate (clamped to at least 10)
+    #[must_use]
+    pub fn new(compression: f64) -> Self {
+        Self {
+            compression: compression.max(10.0),
+            centroids: Vec::new(),
+            buffer: Vec::new(),
+            count: 0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    /// Values added (NaNs are ignored)
+    #[must_use]
+    pub const fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Whether no value has been added
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Add a value (NaN is ignored)
+    #[allow(clippy::cast_precision_loss)]
+    pub fn push(&mut self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        self.buffer.push(value);
+        self.count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        if self.buffer.len() as f64 >= BUFFER_FACTOR * self.compression {
+            self.compress();
+        }
+    }
+
+    /// Add the non-null values of a numeric `array`
+    ///
+    /// # Errors
+    /// Returns error if `array` is not numeric
+    pub fn push_array(&mut self, array: &ArrayRef) -> Result<()> {
+        if !array.data_type().is_numeric() {
+            return Err(Error::InvalidInput(format!(
+                "Quantiles need a numeric column, got {}",
+                array.data_type()
+            )));
+        }
+        let values = compute::cast(array, &DataType::Float64)?;
+        for value in values.as_primitive::<Float64Type>().iter().flatten() {
+            self.push(value);
+        }
+        Ok(())
+    }
+
+    /// Fold `other` in, as if its values had been pushed here
+    pub fn merge(&mut self, other: &Self) {
+        if other.is_empty() {
+            return;
+        }
+        self.buffer.extend_from_slice(&other.buffer);
+        self.centroids.extend_from_slice(&other.centroids);
+        self.count += other.count;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.compress();
+    }
+
+    /// Estimated value at fraction `q` of the sorted values (clamped to
+    /// `[0, 1]`), or `None` if the digest is empty
+    #[must_use]
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        if self.is_empty() {
+            return None;
+        }
+        if !self.buffer.is_empty() {
+            let mut merged = self.clone();
+            merged.compress();
+            return merged.quantile(q);
+        }
+        let q = q.clamp(0.0, 1.0);
+        if q <= 0.0 {
+            return Some(self.min);
+        }
+        if q >= 1.0 {
+            return Some(self.max);
+        }
+
+        // Interpolate between centroid centers; the extremes anchor the tails
+        let total: f64 = self.centroids.iter().map(|c| c.weight).sum();
+        let index = q * total;
+        let first = self.centroids[0];
+        if index < first.weight / 2.0 {
+            return Some(self.min + (first.mean - self.min) * index / (first.weight / 2.0));
+        }
+        let mut position = first.weight / 2.0;
+        for pair in self.centroids.windows(2) {
+            let gap = (pair[0].weight + pair[1].weight) / 2.0;
+            if index < position + gap {
+                let t = (index - position) / gap;
+                return Some(t.mul_add(pair[1].mean - pair[0].mean, pair[0].mean));
+            }
+            position += gap;
+        }
+        let last = self.centroids[self.centroids.len() - 1];
+        let t = ((index - position) / (last.weight / 2.0)).min(1.0);
+        Some(t.mul_add(self.max - last.mean, last.mean))
+    }
+
+    /// Merge the buffer into the centroids
+    fn compress(&mut self) {
+        if self.buffer.is_empty() && self.centroids.len() <= 1 {
+            return;
+        }
+        let mut all = std::mem::take(&mut self.centroids);
+        all.extend(self.buffer.drain(..).map(|mean| Centroid { mean, weight: 1.0 }));
+        all.sort_by(|a, b| a.mean.total_cmp(&b.mean));
+
+        let total: f64 = all.iter().map(|c| c.weight).sum();
+        let mut merged = Vec::with_capacity(all.len().min(self.max_centroids()));
+        let mut current = all[0];
+        let mut before = 0.0;
+        let mut limit = total * self.next_quantile(0.0);
+        for &next in &all[1..] {
+            if before + current.weight + next.weight <= limit {
+                current.weight += next.weight;
+                current.mean += (next.mean - current.mean) * next.weight / current.weight;
+            } else {
+                before += current.weight;
+                merged.push(current);
+                limit = total * self.next_quantile(before / total);
+                current = next;
+            }
+        }
+        merged.push(current);
+        self.centroids = merged;
+    }
+
+    /// Quantile one unit of the `k1` scale above `q`, bounding the centroid
+    /// that starts at `q`
+    fn next_quantile(&self, q: f64) -> f64 {
+        let k = (self.compression / (2.0 * PI)).mul_add(2.0f64.mul_add(q, -1.0).asin(), 1.0);
+        if k >= self.compression / 4.0 {
+            1.0
+        } else {
+            (f64::sin(k * 2.0 * PI / self.compression) + 1.0) / 2.0
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn max_centroids(&self) -> usize {
+        (self.compression * 2.0).ceil() as usize
+    }
+}
+
+/// Well-mixed 64-bit hash of `bytes`, stable across runs and platforms
+/// (FNV-1a, then the `MurmurHash3` finalizer)
+#[must_use]
+pub fn hash64(bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    for &byte in bytes {
+        hash = (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+    }
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^ (hash >> 33)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Int64Array, StringArray};
+    use std::sync::Arc;
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn test_hyperloglog_estimates_and_merges() {
+        let mut halves = [HyperLogLog::default(), HyperLogLog::default()];
+        for i in 0..200_000_usize {
+            // Every value twice, once in each sketch
+            halves[i % 2].insert(&(i / 2).to_le_bytes());
+        }
+        let [mut merged, other] = halves;
+        merged.merge(&other).unwrap();
+        let error = (merged.estimate() as f64 - 100_000.0).abs() / 100_000.0;
+        assert!(error < 4.0 * merged.relative_error(), "estimate {}", merged.estimate());
+
+        let mut small = HyperLogLog::default();
+        let names: ArrayRef =
+            Arc::new(StringArray::from(vec![Some("a"), None, Some("b"), Some("a")]));
+        small.insert_array(&names).unwrap();
+        assert_eq!(small.estimate(), 2);
+        assert_eq!(HyperLogLog::default().estimate(), 0);
+
+        assert!(HyperLogLog::new(3).is_err());
+        assert!(merged.merge(&HyperLogLog::new(10).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_hyperloglog_rejects_malformed_sketches() {
+        let mut sketch = HyperLogLog::new(MIN_PRECISION).unwrap();
+        sketch.insert(b"value");
+        let json = serde_json::to_string(&sketch).unwrap();
+        assert_eq!(serde_json::from_str::<HyperLogLog>(&json).unwrap(), sketch);
+
+        for malformed in [
+            r#"{"precision": 4, "registers": [0, 0, 0]}"#,
+            r#"{"precision": 2, "registers": [0, 0, 0, 0]}"#,
+            r#"{"precision": 30, "registers": []}"#,
+            r#"{"precision": 4, "registers": [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 62]}"#,
+        ] {
+            assert!(serde_json::from_str::<HyperLogLog>(malformed).is_err(), "{malformed}");
+        }
+    }
+
+    #[test]
+    fn test_tdigest_quantiles_and_merge() {
+        let mut parts = [TDigest::default(), TDigest::default(), TDigest::default()];
+        for i in 0..100_000 {
+            parts[i % 3].push(f64::from(u32::try_from(i).unwrap()));
+        }
+        let [mut digest, b, c] = parts;
+        digest.merge(&b);
+        digest.merge(&c);
+        assert_eq!(digest.count(), 100_000);
+        for q in [0.01, 0.25, 0.5, 0.9, 0.999] {
+            let estimate = digest.quantile(q).unwrap();
+            assert!(q.mul_add(-100_000.0, estimate).abs() < 1000.0, "q={q}: {estimate}");
+        }
+        assert_eq!(digest.quantile(0.0), Some(0.0));
+        assert_eq!(digest.quantile(1.0), Some(99_999.0));
+        assert!(digest.centroids.len() <= digest.max_centroids());
+
+        let mut small = TDigest::default();
+        let values: ArrayRef = Arc::new(Int64Array::from(vec![Some(5), None, Some(1), Some(3)]));
+        small.push_array(&values).unwrap();
+        assert_eq!(small.quantile(0.5), Some(3.0));
+        assert_eq!(TDigest::default().quantile(0.5), None);
+        assert!(small.push_array(&(Arc::new(StringArray::from(vec!["x"])) as ArrayRef)).is_err());
+    }
+}
@@ -0,0 +1,340 @@
+//! Per-column Parquet compression and dictionary settings
+//!
+//! Persisted partitions are scanned far more often than they are written, so
+//! the right codec differs per column: high-cardinality strings compress well
+//! under heavy zstd, floats barely compress and are better served by a cheap
+//! codec that decodes at memory speed, and low-cardinality strings are mostly
+//! handled by dictionary pages.
+//!
+//! [`ParquetWriteOptions`] takes explicit per-column settings and can derive
+//! the rest from [`ColumnStatistics`] at write time.
+//!
+//! Codecs must be compiled in: enable `parquet-zstd`, `parquet-lz4`, or
+//! `parquet-snappy`. Writing with a disabled codec fails with a storage error.
+//!
+//! Toyota Way Principles:
+//! - Genchi Genbutsu: Codec choice follows measured column statistics
+//! - Muda elimination: No heavy compression where it buys nothing
+
+use super::dictionary::{compute_statistics, ColumnStatistics, DEFAULT_DICTIONARY_THRESHOLD};
+use crate::{Error, Result};
+use arrow::datatypes::DataType;
+use arrow::record_batch::RecordBatch;
+use parquet::basic::{Compression, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use parquet::schema::types::ColumnPath;
+use std::collections::HashMap;
+
+/// Zstd level for high-cardinality strings (size over write speed)
+pub const STRING_ZSTD_LEVEL: i32 = 9;
+
+/// Zstd level for dictionary-friendly strings (dictionary does most of the work)
+pub const DICTIONARY_ZSTD_LEVEL: i32 = 3;
+
+/// Compression codec for one Parquet column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnCodec {
+    /// No compression
+    Uncompressed,
+    /// Snappy (requires `parquet-snappy`)
+    Snappy,
+    /// LZ4 raw block format (requires `parquet-lz4`)
+    Lz4,
+    /// Zstandard at the given level, 1-22 (requires `parquet-zstd`)
+    Zstd(i32),
+}
+
+impl ColumnCodec {
+    fn to_parquet(self) -> Result<Compression> {
+        Ok(match self {
+            Self::Uncompressed => Compression::UNCOMPRESSED,
+            Self::Snappy => Compression::SNAPPY,
+            Self::Lz4 => Compression::LZ4_RAW,
+            Self::Zstd(level) => Compression::ZSTD(
+                ZstdLevel::try_new(level)
+                    .map_err(|e| Error::InvalidInput(format!("Invalid zstd level {level}: {e}")))?,
+            ),
+        })
+    }
+}
+
+/// Settings for one column; `None` falls back to the table-wide default
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ColumnOptions {
+    /// Compression codec
+    pub codec: Option<ColumnCodec>,
+    /// Whether to write dictionary pages
+    pub dictionary: Option<bool>,
+}
+
+impl ColumnOptions {
+    /// Recommended settings for a column given its statistics
+    ///
+    /// - Strings at or below [`DEFAULT_DICTIONARY_THRESHOLD`] cardinality
+    ///   (or already dictionary-encoded): dictionary pages + zstd-3
+    /// - Other strings: no dictionary, zstd-9
+    /// - Floats: no dictionary, LZ4
+    /// - Everything else: LZ4, dictionary left to the default
+    #[must_use]
+    pub fn recommended(stats: &ColumnStatistics) -> Self {
+        match &stats.data_type {
+            DataType::Utf8 | DataType::LargeUtf8 => {
+                let low_cardinality = stats.dictionary_encoded
+                    || stats.cardinality_ratio().is_some_and(|r| r <= DEFAULT_DICTIONARY_THRESHOLD);
+                if low_cardinality {
+                    Self {
+                        codec: Some(ColumnCodec::Zstd(DICTIONARY_ZSTD_LEVEL)),
+                        dictionary: Some(true),
+                    }
+                } else {
+                    Self {
+                        codec: Some(ColumnCodec::Zstd(STRING_ZSTD_LEVEL)),
+                        dictionary: Some(false),
+                    }
+                }
+            }
+            DataType::Float16 | DataType::Float32 | DataType::Float64 => {
+                Self { codec: Some(ColumnCodec::Lz4), dictionary: Some(false) }
+            }
+            _ => Self { codec: Some(ColumnCodec::Lz4), dictionary: None },
+        }
+    }
+
+    /// Overlay `other` on top of `self` (fields set in `other` win)
+    const fn merge(self, other: Self) -> Self {
+        Self {
+            codec: if other.codec.is_some() { other.codec } else { self.codec },
+            dictionary: if other.dictionary.is_some() { other.dictionary } else { self.dictionary },
+        }
+    }
+}
+
+/// Parquet write configuration for persisted partitions
+///
+/// The default writes uncompressed with dictionary pages enabled (the Parquet
+/// writer's own defaults).
+///
+/// # Example
+/// ```
+/// use trueno_db::storage::{ColumnCodec, ParquetWriteOptions};
+///
+/// let options = ParquetWriteOptions::new()
+///     .with_statistics_driven(true)
+///     .with_column_codec("payload", ColumnCodec::Zstd(12))
+///     .with_column_dictionary("category", true);
+/// assert_eq!(options.column("payload").codec, Some(ColumnCodec::Zstd(12)));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParquetWriteOptions {
+    default_codec: Option<ColumnCodec>,
+    dictionary_page_size_limit: Option<usize>,
+    max_row_group_size: Option<usize>,
+    statistics_driven: bool,
+    columns: HashMap<String, ColumnOptions>,
+}
+
+impl ParquetWriteOptions {
+    /// Writer defaults: uncompressed, dictionary pages enabled
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Codec for columns without a more specific setting
+    #[must_use]
+    pub const fn with_default_codec(mut self, codec: ColumnCodec) -> Self {
+        self.default_codec = Some(codec);
+        self
+    }
+
+    /// Codec for one column
+    #[must_use]
+    pub fn with_column_codec(mut self, column: impl Into<String>, codec: ColumnCodec) -> Self {
+        self.columns.entry(column.into()).or_default().codec = Some(codec);
+        self
+    }
+
+    /// Enable or disable dictionary pages for one column
+    #[must_use]
+    pub fn with_column_dictionary(mut self, column: impl Into<String>, enabled: bool) -> Self {
+        self.columns.entry(column.into()).or_default().dictionary = Some(enabled);
+        self
+    }
+
+    /// Maximum dictionary page size in bytes before falling back to plain encoding
+    #[must_use]
+    pub const fn with_dictionary_page_size_limit(mut self, bytes: usize) -> Self {
+        self.dictionary_page_size_limit = Some(bytes);
+        self
+    }
+
+    /// Maximum rows per row group (the Parquet writer defaults to 1M rows)
+    ///
+    /// Smaller row groups let readers skip and parallelize at a finer grain
+    /// (see [`StorageEngine::load_parquet_parallel`](super::StorageEngine::load_parquet_parallel));
+    /// larger ones compress better.
+    #[must_use]
+    pub const fn with_max_row_group_size(mut self, rows: usize) -> Self {
+        self.max_row_group_size = Some(rows);
+        self
+    }
+
+    /// Derive codec and dictionary settings from column statistics at write
+    /// time (see [`ColumnOptions::recommended`]); explicit settings still win
+    #[must_use]
+    pub const fn with_statistics_driven(mut self, enabled: bool) -> Self {
+        self.statistics_driven = enabled;
+        self
+    }
+
+    /// Explicit settings for a column (statistics-driven choices excluded)
+    #[must_use]
+    pub fn column(&self, name: &str) -> ColumnOptions {
+        self.columns.get(name).copied().unwrap_or_default()
+    }
+
+    /// Resolve per-column settings for `batches`, applying statistics first
+    /// and explicit settings on top
+    ///
+    /// # Errors
+    /// Returns error if statistics cannot be computed
+    pub fn resolve(&self, batches: &[RecordBatch]) -> Result<Vec<(String, ColumnOptions)>> {
+        let Some(first) = batches.first() else {
+            return Ok(Vec::new());
+        };
+
+        let stats = if self.statistics_driven { compute_statistics(batches)? } else { Vec::new() };
+
+        Ok(first
+            .schema()
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(idx, field)| {
+                let derived = stats.get(idx).map(ColumnOptions::recommended).unwrap_or_default();
+                (field.name().clone(), derived.merge(self.column(field.name())))
+            })
+            .collect())
+    }
+
+    /// Build Parquet writer properties for `batches`
+    ///
+    /// # Errors
+    /// Returns error if statistics cannot be computed, a zstd level is
+    /// invalid, or the maximum row group size is zero
+    pub fn writer_properties(&self, batches: &[RecordBatch]) -> Result<WriterProperties> {
+        let mut builder = WriterProperties::builder();
+        if let Some(codec) = self.default_codec {
+            builder = builder.set_compression(codec.to_parquet()?);
+        }
+        if let Some(rows) = self.max_row_group_size {
+            if rows == 0 {
+                return Err(Error::InvalidInput("Row group size must be at least 1".to_string()));
+            }
+            builder = builder.set_max_row_group_size(rows);
+        }
+        if let Some(limit) = self.dictionary_page_size_limit {
+            builder = builder.set_dictionary_page_size_limit(limit);
+        }
+
+        for (name, options) in self.resolve(batches)? {
+            let path = ColumnPath::from(name.as_str());
+            if let Some(codec) = options.codec {
+                builder = builder.set_column_compression(path.clone(), codec.to_parquet()?);
+            }
+            if let Some(enabled) = options.dictionary {
+                builder = builder.set_column_dictionary_enabled(path, enabled);
+            }
+        }
+
+        Ok(builder.build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Float64Array, StringArray};
+    use arrow::datatypes::{Field, Schema};
+    use std::sync::Arc;
+
+    fn create_batch() -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("category", DataType::Utf8, false),
+            Field::new("payload", DataType::Utf8, false),
+            Field::new("price", DataType::Float64, false),
+        ]));
+        let category: Vec<&str> = (0..100).map(|i| if i % 2 == 0 { "a" } else { "b" }).collect();
+        let payload: Vec<String> = (0..100).map(|i| format!("row-{i}")).collect();
+        let price: Vec<f64> = (0..100).map(f64::from).collect();
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(StringArray::from(category)),
+                Arc::new(StringArray::from(payload)),
+                Arc::new(Float64Array::from(price)),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_statistics_driven_recommendations() {
+        let options = ParquetWriteOptions::new().with_statistics_driven(true);
+        let resolved: HashMap<String, ColumnOptions> =
+            options.resolve(&[create_batch()]).unwrap().into_iter().collect();
+
+        assert_eq!(resolved["category"].codec, Some(ColumnCodec::Zstd(DICTIONARY_ZSTD_LEVEL)));
+        assert_eq!(resolved["category"].dictionary, Some(true));
+        assert_eq!(resolved["payload"].codec, Some(ColumnCodec::Zstd(STRING_ZSTD_LEVEL)));
+        assert_eq!(resolved["payload"].dictionary, Some(false));
+        assert_eq!(resolved["price"].codec, Some(ColumnCodec::Lz4));
+    }
+
+    #[test]
+    fn test_explicit_settings_override_statistics() {
+        let options = ParquetWriteOptions::new()
+            .with_statistics_driven(true)
+            .with_column_codec("price", ColumnCodec::Uncompressed)
+            .with_column_dictionary("payload", true);
+        let resolved: HashMap<String, ColumnOptions> =
+            options.resolve(&[create_batch()]).unwrap().into_iter().collect();
+
+        assert_eq!(resolved["price"].codec, Some(ColumnCodec::Uncompressed));
+        assert_eq!(resolved["payload"].codec, Some(ColumnCodec::Zstd(STRING_ZSTD_LEVEL)));
+        assert_eq!(resolved["payload"].dictionary, Some(true));
+    }
+
+    #[test]
+    fn test_writer_properties_apply_per_column() {
+        let options = ParquetWriteOptions::new()
+            .with_column_codec("payload", ColumnCodec::Zstd(9))
+            .with_column_dictionary("payload", false)
+            .with_dictionary_page_size_limit(4096);
+        let props = options.writer_properties(&[create_batch()]).unwrap();
+
+        let payload = ColumnPath::from("payload");
+        assert_eq!(props.compression(&payload), Compression::ZSTD(ZstdLevel::try_new(9).unwrap()));
+        assert!(!props.dictionary_enabled(&payload));
+        assert_eq!(props.compression(&ColumnPath::from("price")), Compression::UNCOMPRESSED);
+        assert_eq!(props.dictionary_page_size_limit(), 4096);
+    }
+
+    #[test]
+    fn test_invalid_zstd_level_rejected() {
+        let options = ParquetWriteOptions::new().with_default_codec(ColumnCodec::Zstd(99));
+        assert!(options.writer_properties(&[create_batch()]).is_err());
+    }
+
+    #[test]
+    fn test_max_row_group_size() {
+        let props = ParquetWriteOptions::new()
+            .with_max_row_group_size(1000)
+            .writer_properties(&[create_batch()])
+            .unwrap();
+        assert_eq!(props.max_row_group_size(), 1000);
+
+        let zero = ParquetWriteOptions::new().with_max_row_group_size(0);
+        assert!(zero.writer_properties(&[create_batch()]).is_err());
+    }
+}
@@ -0,0 +1,472 @@
+//! Compressed in-memory column cache
+//!
+//! Wide Parquet datasets held in memory are mostly repetition: sorted or
+//! clustered columns hold long runs of one value, ids and counters span a
+//! small range of a wide integer type, and strings repeat a few distinct
+//! values. [`CompressedTable`] keeps a table in that compact form and
+//! decompresses it on the fly, one morsel at a time, when it is scanned.
+//!
+//! Each column of each batch gets the first encoding that pays off:
+//!
+//! | Encoding                         | Chosen when                                  | Columns                     |
+//! |----------------------------------|----------------------------------------------|-----------------------------|
+//! | [`ColumnEncoding::RunLength`]    | runs average [`MIN_RUN_LENGTH`] rows or more | any                         |
+//! | [`ColumnEncoding::Dictionary`]   | distinct values ≤ 10% of rows                | `Utf8` (or already encoded) |
+//! | [`ColumnEncoding::BitPacked`]    | `max - min` fits in fewer bits than the type | integers                    |
+//! | [`ColumnEncoding::Plain`]        | otherwise                                    | any                         |
+//!
+//! Decompression restores the original schema exactly, so queries over
+//! [`CompressedTable::morsels`] or [`CompressedTable::decompress`] see the
+//! same data as the table that was compressed. Floats and high-cardinality
+//! strings rarely compress this way and stay plain.
+//!
+//! References:
+//! - Abadi et al. (2006): Integrating compression and execution in column stores
+//! - Lemire & Boytsov (2015): Decoding billions of integers per second
+//!   through vectorization
+//!
+//! Toyota Way: Muda elimination (hold each value in the bits it needs)
+
+use super::dictionary::{self, DEFAULT_DICTIONARY_THRESHOLD};
+use super::zone_map::ZONE_ROWS;
+use super::StorageEngine;
+use crate::{Error, Result};
+use arrow::array::{Array, ArrayRef, Int64Array, StringArray, UInt32Array};
+use arrow::buffer::NullBuffer;
+use arrow::compute::kernels::partition::partition;
+use arrow::compute::{self, cast, cast_with_options, CastOptions};
+use arrow::datatypes::{DataType, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Average rows per run at which run-length encoding is used
+pub const MIN_RUN_LENGTH: usize = 4;
+
+/// How one column of one batch is held by a [`CompressedTable`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnEncoding {
+    /// The Arrow array as is
+    Plain,
+    /// One value per run of equal values, plus where each run ends
+    RunLength,
+    /// Offsets from the column minimum, `bits` wide each
+    BitPacked {
+        /// Bits per value
+        bits: u32,
+    },
+    /// Distinct strings once, plus an `Int32` key per row
+    Dictionary,
+}
+
+/// One column of one batch in its chosen encoding
+#[derive(Debug)]
+enum EncodedColumn {
+    Plain(ArrayRef),
+    RunLength {
+        /// First value of each run
+        values: ArrayRef,
+        /// Row (exclusive) at which each run ends, ascending
+        run_ends: Vec<u32>,
+    },
+    BitPacked {
+        /// Column minimum; values are stored as `value - base`
+        base: i64,
+        bits: u32,
+        words: Vec<u64>,
+        nulls: Option<NullBuffer>,
+    },
+    Dictionary(ArrayRef),
+}
+
+impl EncodedColumn {
+    /// Pick and apply the encoding for `array`
+    #[allow(clippy::cast_possible_truncation)]
+    fn encode(array: &ArrayRef) -> Result<Self> {
+        if array.is_empty() {
+            return Ok(Self::Plain(Arc::clone(array)));
+        }
+        let runs = partition(std::slice::from_ref(array))
+            .map_err(|e| Error::StorageError(format!("Failed to find column runs: {e}")))?;
+        if runs.len() * MIN_RUN_LENGTH <= array.len() {
+            let run_ends = runs.ranges().iter().map(|run| run.end as u32).collect();
+            let starts: UInt32Array =
+                runs.ranges().iter().map(|run| Some(run.start as u32)).collect();
+            let values = compute::take(array.as_ref(), &starts, None)
+                .map_err(|e| Error::StorageError(format!("Failed to encode runs: {e}")))?;
+            return Ok(Self::RunLength { values, run_ends });
+        }
+
+        let data_type = array.data_type();
+        if dictionary::is_string_dictionary(data_type) {
+            return Ok(Self::Dictionary(Arc::clone(array)));
+        }
+        if data_type == &DataType::Utf8 && Self::low_cardinality(array) {
+            let encoded = cast(array, &dictionary::dictionary_type())
+                .map_err(|e| Error::StorageError(format!("Failed to encode dictionary: {e}")))?;
+            return Ok(Self::Dictionary(encoded));
+        }
+        if data_type.is_integer() {
+            if let Some(packed) = Self::bit_pack(array) {
+                return Ok(packed);
+            }
+        }
+        Ok(Self::Plain(Arc::clone(array)))
+    }
+
+    /// Whether distinct strings are at most 10% of the non-null rows
+    #[allow(clippy::cast_precision_loss)]
+    fn low_cardinality(array: &ArrayRef) -> bool {
+        let Some(strings) = array.as_any().downcast_ref::<StringArray>() else {
+            return false;
+        };
+        let distinct: HashSet<&str> = strings.iter().flatten().collect();
+        let non_null = strings.len() - strings.null_count();
+        non_null > 0 && distinct.len() as f64 <= non_null as f64 * DEFAULT_DICTIONARY_THRESHOLD
+    }
+
+    /// Bit-packed offsets of an integer column (`None` if they would not
+    /// be narrower than the type)
+    #[allow(clippy::cast_sign_loss)]
+    fn bit_pack(array: &ArrayRef) -> Option<Self> {
+        // Integers beyond i64 (large UInt64 values) fail the cast: stay plain
+        let strict = CastOptions { safe: false, ..CastOptions::default() };
+        let wide = cast_with_options(array, &DataType::Int64, &strict).ok()?;
+        let wide = wide.as_any().downcast_ref::<Int64Array>()?;
+        let (Some(min), Some(max)) = (compute::min(wide), compute::max(wide)) else {
+            return None;
+        };
+        let range = u64::try_from(i128::from(max) - i128::from(min)).ok()?;
+        let bits = u64::BITS - range.leading_zeros();
+        let width = array.data_type().primitive_width()? * 8;
+        if bits as usize >= width {
+            return None;
+        }
+
+        let mut words = vec![0u64; (wide.len() * bits as usize).div_ceil(64)];
+        if bits > 0 {
+            for (row, value) in wide.values().iter().enumerate() {
+                // Null slots hold arbitrary values: store 0 for them
+                let offset = if wide.is_valid(row) { value.wrapping_sub(min) as u64 } else { 0 };
+                pack(&mut words, row, bits, offset);
+            }
+        }
+        Some(Self::BitPacked { base: min, bits, words, nulls: array.logical_nulls() })
+    }
+
+    /// The encoding chosen
+    const fn encoding(&self) -> ColumnEncoding {
+        match self {
+            Self::Plain(_) => ColumnEncoding::Plain,
+            Self::RunLength { .. } => ColumnEncoding::RunLength,
+            Self::BitPacked { bits, .. } => ColumnEncoding::BitPacked { bits: *bits },
+            Self::Dictionary(_) => ColumnEncoding::Dictionary,
+        }
+    }
+
+    /// Bytes held
+    fn memory_size(&self) -> usize {
+        match self {
+            Self::Plain(array) | Self::Dictionary(array) => array.get_array_memory_size(),
+            Self::RunLength { values, run_ends } => {
+                values.get_array_memory_size() + run_ends.len() * std::mem::size_of::<u32>()
+            }
+            Self::BitPacked { words, nulls, .. } => {
+                words.len() * std::mem::size_of::<u64>()
+                    + nulls.as_ref().map_or(0, |nulls| nulls.buffer().len())
+            }
+        }
+    }
+
+    /// Rows `offset..offset + len` as an array of `data_type`
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    fn decode(&self, data_type: &DataType, offset: usize, len: usize) -> Result<ArrayRef> {
+        let failed = |e: arrow::error::ArrowError| {
+            Error::StorageError(format!("Failed to decompress column: {e}"))
+        };
+        match self {
+            Self::Plain(array) => Ok(array.slice(offset, len)),
+            Self::Dictionary(array) if array.data_type() == data_type => {
+                Ok(array.slice(offset, len))
+            }
+            Self::Dictionary(array) => cast(&array.slice(offset, len), data_type).map_err(failed),
+            Self::RunLength { values, run_ends } => {
+                let mut run = run_ends.partition_point(|&end| end as usize <= offset);
+                let indices = UInt32Array::from_iter_values((offset..offset + len).map(|row| {
+                    while run_ends[run] as usize <= row {
+                        run += 1;
+                    }
+                    run as u32
+                }));
+                compute::take(values.as_ref(), &indices, None).map_err(failed)
+            }
+            Self::BitPacked { base, bits, words, nulls } => {
+                let values = (offset..offset + len)
+                    .map(|row| base.wrapping_add(unpack(words, row, *bits) as i64));
+                let nulls = nulls.as_ref().map(|nulls| nulls.slice(offset, len));
+                let wide = Int64Array::new(values.collect(), nulls);
+                cast(&wide, data_type).map_err(failed)
+            }
+        }
+    }
+}
+
+/// Write the low `bits` of `value` as entry `index` of `words`
+#[allow(clippy::cast_possible_truncation)]
+fn pack(words: &mut [u64], index: usize, bits: u32, value: u64) {
+    let bit = index * bits as usize;
+    let (word, shift) = (bit / 64, (bit % 64) as u32);
+    words[word] |= value << shift;
+    if shift + bits > 64 {
+        words[word + 1] |= value >> (64 - shift);
+    }
+}
+
+/// Entry `index` of `words`, `bits` wide
+#[allow(clippy::cast_possible_truncation)]
+fn unpack(words: &[u64], index: usize, bits: u32) -> u64 {
+    if bits == 0 {
+        return 0;
+    }
+    let bit = index * bits as usize;
+    let (word, shift) = (bit / 64, (bit % 64) as u32);
+    let mut value = words[word] >> shift;
+    if shift + bits > 64 {
+        value |= words[word + 1] << (64 - shift);
+    }
+    if bits == 64 {
+        value
+    } else {
+        value & ((1u64 << bits) - 1)
+    }
+}
+
+/// One batch of a [`CompressedTable`]
+#[derive(Debug)]
+struct CompressedBatch {
+    num_rows: usize,
+    columns: Vec<EncodedColumn>,
+}
+
+impl CompressedBatch {
+    /// Rows `offset..offset + len` with `schema`
+    fn decode(&self, schema: &SchemaRef, offset: usize, len: usize) -> Result<RecordBatch> {
+        let columns = self
+            .columns
+            .iter()
+            .zip(schema.fields())
+            .map(|(column, field)| column.decode(field.data_type(), offset, len))
+            .collect::<Result<Vec<_>>>()?;
+        RecordBatch::try_new(Arc::clone(schema), columns)
+            .map_err(|e| Error::StorageError(format!("Failed to decompress batch: {e}")))
+    }
+}
+
+/// A table held in compressed columns, decompressed on scan
+///
+/// # Example
+///
+/// ```rust
+/// # use trueno_db::storage::{ColumnEncoding, StorageEngine};
+/// # use arrow::array::{Int64Array, RecordBatch, StringArray};
+/// # use arrow::datatypes::{DataType, Field, Schema};
+/// # use std::sync::Arc;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let schema = Arc::new(Schema::new(vec![
+///     Field::new("region", DataType::Utf8, false),
+///     Field::new("latency_ms", DataType::Int64, false),
+/// ]));
+/// let regions = StringArray::from_iter_values((0..1000).map(|i| ["eu", "us"][i / 500]));
+/// let latencies = Int64Array::from_iter_values((0..1000).map(|i| 20 + i % 200));
+/// let batch = RecordBatch::try_new(schema, vec![Arc::new(regions), Arc::new(latencies)])?;
+/// let storage = StorageEngine::new(vec![batch]);
+///
+/// let compressed = storage.compress()?;
+/// assert_eq!(compressed.encodings()[0][0], ColumnEncoding::RunLength);
+/// assert_eq!(compressed.encodings()[0][1], ColumnEncoding::BitPacked { bits: 8 });
+/// assert!(compressed.memory_size() < storage.memory_size() / 4);
+///
+/// let rows = compressed.morsels().map(|morsel| morsel.map(|m| m.num_rows()));
+/// assert_eq!(rows.sum::<trueno_db::Result<usize>>()?, 1000);
+/// assert_eq!(compressed.decompress()?.batches(), storage.batches());
+/// # Ok(())
+/// # }
+/// ```
+pub struct CompressedTable {
+    /// Table settings, with no batches
+    shell: StorageEngine,
+    schema: SchemaRef,
+    batches: Vec<CompressedBatch>,
+    uncompressed_bytes: usize,
+}
+
+impl CompressedTable {
+    /// Compress every batch of `storage`
+    ///
+    /// # Errors
+    /// Returns error if a column cannot be encoded
+    pub fn compress(storage: &StorageEngine) -> Result<Self> {
+        let batches = storage
+            .batches()
+            .iter()
+            .map(|batch| {
+                let columns =
+                    batch.columns().iter().map(EncodedColumn::encode).collect::<Result<_>>()?;
+                Ok(CompressedBatch { num_rows: batch.num_rows(), columns })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            shell: storage.with_batches(Vec::new()),
+            schema: storage
+                .batches()
+                .first()
+                .map_or_else(|| Arc::new(Schema::empty()), RecordBatch::schema),
+            batches,
+            uncompressed_bytes: storage.memory_size(),
+        })
+    }
+
+    /// Bytes held by the compressed columns
+    #[must_use]
+    pub fn memory_size(&self) -> usize {
+        self.batches.iter().flat_map(|batch| &batch.columns).map(EncodedColumn::memory_size).sum()
+    }
+
+    /// Bytes the table held before compression
+    #[must_use]
+    pub const fn uncompressed_size(&self) -> usize {
+        self.uncompressed_bytes
+    }
+
+    /// Total rows
+    #[must_use]
+    pub fn num_rows(&self) -> usize {
+        self.batches.iter().map(|batch| batch.num_rows).sum()
+    }
+
+    /// Encoding of each column, per batch
+    #[must_use]
+    pub fn encodings(&self) -> Vec<Vec<ColumnEncoding>> {
+        self.batches
+            .iter()
+            .map(|batch| batch.columns.iter().map(EncodedColumn::encoding).collect())
+            .collect()
+    }
+
+    /// Decompressed morsels of up to [`ZONE_ROWS`] rows, in table order
+    ///
+    /// Only the morsel being consumed is held decompressed.
+    pub fn morsels(&self) -> impl Iterator<Item = Result<RecordBatch>> + '_ {
+        self.batches.iter().flat_map(move |batch| {
+            (0..batch.num_rows).step_by(ZONE_ROWS).map(move |offset| {
+                let len = ZONE_ROWS.min(batch.num_rows - offset);
+                batch.decode(&self.schema, offset, len)
+            })
+        })
+    }
+
+    /// The table as a [`StorageEngine`] with its original batches and settings
+    ///
+    /// # Errors
+    /// Returns error if a column cannot be decompressed
+    pub fn decompress(&self) -> Result<StorageEngine> {
+        let batches = self
+            .batches
+            .iter()
+            .map(|batch| batch.decode(&self.schema, 0, batch.num_rows))
+            .collect::<Result<_>>()?;
+        Ok(self.shell.with_batches(batches))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Float64Array, Int16Array, Int32Array, StringArray, UInt64Array};
+    use arrow::datatypes::{Field, Schema};
+
+    fn batch(rows: usize) -> RecordBatch {
+        let schema = Schema::new(vec![
+            Field::new("day", DataType::Int32, false),
+            Field::new("id", DataType::Int64, false),
+            Field::new("delta", DataType::Int16, true),
+            Field::new("status", DataType::Utf8, false),
+            Field::new("price", DataType::Float64, false),
+            Field::new("hash", DataType::UInt64, false),
+        ]);
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_possible_wrap,
+            clippy::cast_precision_loss
+        )]
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Int32Array::from_iter_values((0..rows).map(|i| (i / 100) as i32))),
+            Arc::new(Int64Array::from_iter_values((0..rows).map(|i| 1_000_000 + i as i64))),
+            Arc::new(
+                (0..rows)
+                    .map(|i| (i % 7 != 0).then_some((i % 50) as i16 - 25))
+                    .collect::<Int16Array>(),
+            ),
+            Arc::new(StringArray::from_iter_values(
+                (0..rows).map(|i| ["ok", "retry", "error"][(i * 7) % 3]),
+            )),
+            Arc::new(Float64Array::from_iter_values((0..rows).map(|i| i as f64 * 0.37))),
+            Arc::new(UInt64Array::from_iter_values((0..rows).map(|i| u64::MAX - i as u64 * 3))),
+        ];
+        RecordBatch::try_new(Arc::new(schema), columns).unwrap()
+    }
+
+    #[test]
+    fn test_encodings_round_trip() {
+        let original = batch(5000);
+        let storage = StorageEngine::new(vec![original.clone(), batch(0)]);
+        let compressed = storage.compress().unwrap();
+
+        assert_eq!(
+            compressed.encodings()[0],
+            vec![
+                ColumnEncoding::RunLength,
+                ColumnEncoding::BitPacked { bits: 13 },
+                ColumnEncoding::BitPacked { bits: 6 },
+                ColumnEncoding::Dictionary,
+                ColumnEncoding::Plain,
+                ColumnEncoding::Plain,
+            ]
+        );
+        assert!(compressed.memory_size() < compressed.uncompressed_size());
+        assert_eq!(compressed.num_rows(), 5000);
+
+        let restored = compressed.decompress().unwrap();
+        assert_eq!(restored.batches()[0], original);
+        assert_eq!(restored.batches()[1], batch(0));
+    }
+
+    #[test]
+    fn test_morsels_decode_ranges() {
+        let original = batch(ZONE_ROWS + 1234);
+        let compressed = StorageEngine::new(vec![original.clone()]).compress().unwrap();
+
+        let morsels: Vec<RecordBatch> = compressed.morsels().collect::<Result<_>>().unwrap();
+        assert_eq!(morsels.len(), 2);
+        // Runs and packed words straddle the morsel boundary
+        assert_eq!(morsels[1], original.slice(ZONE_ROWS, 1234));
+        let joined = compute::concat_batches(&original.schema(), &morsels).unwrap();
+        assert_eq!(joined, original);
+    }
+
+    #[test]
+    fn test_pack_unpack_widths() {
+        for bits in [1, 7, 13, 63, 64] {
+            let mask = if bits == 64 { u64::MAX } else { (1u64 << bits) - 1 };
+            let values: Vec<u64> =
+                (0..200u64).map(|i| i.wrapping_mul(0x9E37_79B9) & mask).collect();
+            let mut words = vec![0u64; (values.len() * bits as usize).div_ceil(64)];
+            for (index, &value) in values.iter().enumerate() {
+                pack(&mut words, index, bits, value);
+            }
+            for (index, &value) in values.iter().enumerate() {
+                assert_eq!(unpack(&words, index, bits), value, "bits={bits} index={index}");
+            }
+        }
+    }
+}
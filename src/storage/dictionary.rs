@@ -0,0 +1,286 @@
+//! Column statistics and automatic dictionary encoding
+//!
+//! Low-cardinality string columns (categories, status codes, country names)
+//! repeat a handful of values across millions of rows. Dictionary encoding
+//! stores each distinct string once plus an `Int32` key per row, which cuts
+//! memory and turns string comparisons into integer comparisons.
+//!
+//! Encoding is a storage detail: [`decode_dictionaries`] restores plain `Utf8`
+//! columns before results leave the query executor.
+//!
+//! References:
+//! - Abadi et al. (2006): Integrating compression and execution in column stores
+
+use crate::{Error, Result};
+use arrow::array::{Array, ArrayRef, StringArray};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Default cardinality threshold: encode when distinct values ≤ 10% of rows
+pub const DEFAULT_DICTIONARY_THRESHOLD: f64 = 0.1;
+
+/// Physical type used for dictionary-encoded string columns
+#[must_use]
+pub fn dictionary_type() -> DataType {
+    DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8))
+}
+
+/// Check whether a data type is a dictionary of strings
+#[must_use]
+pub fn is_string_dictionary(data_type: &DataType) -> bool {
+    matches!(data_type, DataType::Dictionary(_, value) if value.as_ref() == &DataType::Utf8)
+}
+
+/// Per-column statistics gathered by [`StorageEngine::analyze`](super::StorageEngine::analyze)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnStatistics {
+    /// Column name
+    pub name: String,
+    /// Logical data type (dictionary-encoded columns report `Utf8`)
+    pub data_type: DataType,
+    /// Total number of rows across all batches
+    pub row_count: usize,
+    /// Number of NULL values
+    pub null_count: usize,
+    /// Number of distinct non-null values (string columns only)
+    pub distinct_count: Option<usize>,
+    /// Whether the column is currently stored dictionary-encoded
+    pub dictionary_encoded: bool,
+}
+
+impl ColumnStatistics {
+    /// Ratio of distinct values to non-null rows
+    ///
+    /// Returns `None` for columns without a distinct count.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn cardinality_ratio(&self) -> Option<f64> {
+        let non_null = self.row_count - self.null_count;
+        self.distinct_count
+            .map(|distinct| if non_null == 0 { 0.0 } else { distinct as f64 / non_null as f64 })
+    }
+}
+
+/// Compute column statistics over a set of batches sharing one schema
+pub(crate) fn compute_statistics(batches: &[RecordBatch]) -> Result<Vec<ColumnStatistics>> {
+    let Some(first) = batches.first() else {
+        return Ok(Vec::new());
+    };
+    let schema = first.schema();
+
+    schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(col_idx, field)| {
+            let dictionary_encoded = is_string_dictionary(field.data_type());
+            let is_string = dictionary_encoded || field.data_type() == &DataType::Utf8;
+
+            let row_count = batches.iter().map(|b| b.column(col_idx).len()).sum();
+            let null_count = batches.iter().map(|b| b.column(col_idx).null_count()).sum();
+
+            let distinct_count =
+                if is_string { Some(count_distinct_strings(batches, col_idx)?) } else { None };
+
+            Ok(ColumnStatistics {
+                name: field.name().clone(),
+                data_type: logical_type(field.data_type()),
+                row_count,
+                null_count,
+                distinct_count,
+                dictionary_encoded,
+            })
+        })
+        .collect()
+}
+
+/// Count distinct non-null strings in one column across all batches
+fn count_distinct_strings(batches: &[RecordBatch], col_idx: usize) -> Result<usize> {
+    let arrays: Vec<ArrayRef> = batches
+        .iter()
+        .map(|b| cast(b.column(col_idx), &DataType::Utf8))
+        .collect::<std::result::Result<_, _>>()?;
+
+    let mut distinct = HashSet::new();
+    for array in &arrays {
+        let strings = array.as_any().downcast_ref::<StringArray>().ok_or_else(|| {
+            Error::Other("Failed to downcast Utf8 column to StringArray".to_string())
+        })?;
+        distinct.extend(strings.iter().flatten());
+    }
+    Ok(distinct.len())
+}
+
+/// Pick the string columns whose cardinality ratio is at or below `threshold`
+pub(crate) fn select_dictionary_columns(stats: &[ColumnStatistics], threshold: f64) -> Vec<usize> {
+    stats
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.row_count > s.null_count)
+        .filter(|(_, s)| s.cardinality_ratio().is_some_and(|ratio| ratio <= threshold))
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// Map dictionary-encoded string types back to `Utf8`
+pub(crate) fn logical_type(data_type: &DataType) -> DataType {
+    if is_string_dictionary(data_type) {
+        DataType::Utf8
+    } else {
+        data_type.clone()
+    }
+}
+
+/// Schema as seen by queries (dictionary-encoded strings reported as `Utf8`)
+#[must_use]
+pub fn logical_schema(schema: &Schema) -> Schema {
+    let fields: Vec<Field> = schema
+        .fields()
+        .iter()
+        .map(|f| f.as_ref().clone().with_data_type(logical_type(f.data_type())))
+        .collect();
+    Schema::new_with_metadata(fields, schema.metadata().clone())
+}
+
+/// Re-encode a batch so that exactly `columns` are dictionary-encoded
+///
+/// String columns listed in `columns` are encoded; any other dictionary
+/// columns are decoded back to `Utf8`. Non-string columns are untouched.
+pub(crate) fn apply_encoding(batch: &RecordBatch, columns: &[usize]) -> Result<RecordBatch> {
+    let schema = batch.schema();
+    let mut new_fields = Vec::with_capacity(batch.num_columns());
+    let mut new_columns: Vec<ArrayRef> = Vec::with_capacity(batch.num_columns());
+
+    for (col_idx, field) in schema.fields().iter().enumerate() {
+        let column = batch.column(col_idx);
+        let is_string =
+            is_string_dictionary(field.data_type()) || field.data_type() == &DataType::Utf8;
+
+        let target = if !is_string {
+            field.data_type().clone()
+        } else if columns.contains(&col_idx) {
+            dictionary_type()
+        } else {
+            DataType::Utf8
+        };
+
+        if &target == field.data_type() {
+            new_columns.push(Arc::clone(column));
+        } else {
+            new_columns.push(cast(column, &target)?);
+        }
+        new_fields.push(field.as_ref().clone().with_data_type(target));
+    }
+
+    let new_schema = Arc::new(Schema::new_with_metadata(new_fields, schema.metadata().clone()));
+    RecordBatch::try_new(new_schema, new_columns)
+        .map_err(|e| Error::StorageError(format!("Failed to apply dictionary encoding: {e}")))
+}
+
+/// Decode one dictionary-encoded string column back to plain `Utf8`
+///
+/// Other columns are returned unchanged (cheap `Arc` clone).
+pub(crate) fn decode_column(column: &ArrayRef) -> Result<ArrayRef> {
+    if is_string_dictionary(column.data_type()) {
+        Ok(cast(column, &DataType::Utf8)?)
+    } else {
+        Ok(Arc::clone(column))
+    }
+}
+
+/// Decode all dictionary-encoded string columns back to plain `Utf8`
+///
+/// Returns the batch unchanged (cheap `Arc` clone) when nothing is encoded.
+pub fn decode_dictionaries(batch: &RecordBatch) -> Result<RecordBatch> {
+    if !batch.schema().fields().iter().any(|f| is_string_dictionary(f.data_type())) {
+        return Ok(batch.clone());
+    }
+    apply_encoding(batch, &[])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{DictionaryArray, Int32Array};
+    use arrow::datatypes::Int32Type;
+
+    fn create_batch(categories: Vec<&str>) -> RecordBatch {
+        let ids: Vec<i32> = (0..).take(categories.len()).collect();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("category", DataType::Utf8, false),
+        ]));
+        RecordBatch::try_new(
+            schema,
+            vec![Arc::new(Int32Array::from(ids)), Arc::new(StringArray::from(categories))],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_compute_statistics_distinct_count() {
+        let batch = create_batch(vec!["a", "b", "a", "a", "b", "c"]);
+        let stats = compute_statistics(&[batch]).unwrap();
+
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].distinct_count, None);
+        assert_eq!(stats[1].row_count, 6);
+        assert_eq!(stats[1].distinct_count, Some(3));
+        assert_eq!(stats[1].cardinality_ratio(), Some(0.5));
+    }
+
+    #[test]
+    fn test_compute_statistics_across_batches() {
+        let b1 = create_batch(vec!["a", "b"]);
+        let b2 = create_batch(vec!["b", "c"]);
+        let stats = compute_statistics(&[b1, b2]).unwrap();
+
+        assert_eq!(stats[1].row_count, 4);
+        assert_eq!(stats[1].distinct_count, Some(3));
+    }
+
+    #[test]
+    fn test_select_dictionary_columns_threshold() {
+        let batch = create_batch(vec!["x"; 100]);
+        let stats = compute_statistics(&[batch]).unwrap();
+
+        assert_eq!(select_dictionary_columns(&stats, 0.1), vec![1]);
+        assert!(select_dictionary_columns(&stats, 0.001).is_empty());
+    }
+
+    #[test]
+    fn test_apply_encoding_roundtrip() {
+        let batch = create_batch(vec!["a", "b", "a", "c"]);
+        let encoded = apply_encoding(&batch, &[1]).unwrap();
+
+        assert!(is_string_dictionary(encoded.schema().field(1).data_type()));
+        let dict = encoded.column(1).as_any().downcast_ref::<DictionaryArray<Int32Type>>().unwrap();
+        assert_eq!(dict.values().len(), 3);
+
+        let decoded = decode_dictionaries(&encoded).unwrap();
+        assert_eq!(decoded.schema(), batch.schema());
+        assert_eq!(decoded.column(1).as_ref(), batch.column(1).as_ref());
+    }
+
+    #[test]
+    fn test_logical_schema_hides_encoding() {
+        let batch = create_batch(vec!["a", "a"]);
+        let encoded = apply_encoding(&batch, &[1]).unwrap();
+
+        assert_eq!(logical_schema(&encoded.schema()), *batch.schema());
+    }
+
+    #[test]
+    fn test_statistics_report_encoded_columns() {
+        let batch = create_batch(vec!["a", "a", "b"]);
+        let encoded = apply_encoding(&batch, &[1]).unwrap();
+        let stats = compute_statistics(&[encoded]).unwrap();
+
+        assert!(stats[1].dictionary_encoded);
+        assert_eq!(stats[1].data_type, DataType::Utf8);
+        assert_eq!(stats[1].distinct_count, Some(2));
+    }
+}
@@ -0,0 +1,325 @@
+//! Cold table eviction to disk
+//!
+//! Long-lived embedded processes accumulate tables that are queried once
+//! and then sit idle. With a memory limit set
+//! ([`Catalog::with_memory_limit`](crate::Catalog::with_memory_limit)), the
+//! catalog keeps resident tables under the limit by spilling the least
+//! recently used ones to Arrow IPC files and reloading them on access
+//! ([`Catalog::table`](crate::Catalog::table)):
+//!
+//! - **Evict**: batches are written to `<spill_dir>/trueno_evict_*/table_N.arrow`
+//!   and dropped; table settings (dictionary threshold, provenance) stay
+//!   resident so a reloaded table behaves exactly as before
+//! - **Reload**: the file is read back, deleted, and the table becomes the
+//!   most recently used (possibly evicting others)
+//!
+//! The table being accessed is never evicted, so a single table larger
+//! than the limit still loads; the limit is then exceeded until another
+//! table is accessed.
+//!
+//! Toyota Way: Heijunka (level memory use instead of growing without bound)
+
+use super::StorageEngine;
+use crate::external_sort::SpillDir;
+use crate::{Error, Result};
+use arrow::ipc::reader::FileReader;
+use arrow::ipc::writer::FileWriter;
+use arrow::record_batch::RecordBatch;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+/// Memory limit and spill location for table eviction
+#[derive(Debug, Clone)]
+pub struct EvictionConfig {
+    /// Resident table bytes above which cold tables are evicted
+    pub max_resident_bytes: usize,
+    /// Directory under which spill files are created
+    pub spill_dir: PathBuf,
+}
+
+impl EvictionConfig {
+    /// Limit resident tables to `max_resident_bytes`, spilling to the system temp dir
+    #[must_use]
+    pub fn new(max_resident_bytes: usize) -> Self {
+        Self { max_resident_bytes, spill_dir: std::env::temp_dir() }
+    }
+
+    /// Directory under which spill files are created (default: system temp dir)
+    #[must_use]
+    pub fn with_spill_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.spill_dir = dir.as_ref().to_path_buf();
+        self
+    }
+}
+
+/// Eviction counters, as reported by [`Catalog::eviction_metrics`](crate::Catalog::eviction_metrics)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+pub struct EvictionMetrics {
+    /// Bytes held by resident tables
+    pub resident_bytes: usize,
+    /// In-memory bytes of the tables currently on disk
+    pub spilled_bytes: usize,
+    /// Tables currently on disk
+    pub spilled_tables: usize,
+    /// Tables evicted since the limit was set
+    pub evictions: u64,
+    /// Tables reloaded since the limit was set
+    pub reloads: u64,
+}
+
+/// A table whose batches live in a spill file (deleted on drop)
+struct SpilledTable {
+    /// Table settings, with no batches
+    shell: StorageEngine,
+    path: PathBuf,
+    /// In-memory size at eviction time
+    bytes: usize,
+}
+
+impl SpilledTable {
+    /// Read the table back without consuming the spill file
+    fn read(&self) -> Result<StorageEngine> {
+        Ok(self.shell.with_batches(read_batches(&self.path)?))
+    }
+}
+
+impl Drop for SpilledTable {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// LRU bookkeeping and spilled tables for one catalog
+///
+/// Operates on the catalog's map of resident tables; a table is either in
+/// that map or in `evicted`, never both.
+pub(crate) struct MemoryManager {
+    config: EvictionConfig,
+    /// Created on first eviction, removed with the manager
+    dir: Option<SpillDir>,
+    evicted: HashMap<String, SpilledTable>,
+    /// Logical clock advanced on every access
+    clock: u64,
+    last_access: HashMap<String, u64>,
+    next_file: u64,
+    evictions: u64,
+    reloads: u64,
+}
+
+impl MemoryManager {
+    pub(crate) fn new(config: EvictionConfig) -> Self {
+        Self {
+            config,
+            dir: None,
+            evicted: HashMap::new(),
+            clock: 0,
+            last_access: HashMap::new(),
+            next_file: 0,
+            evictions: 0,
+            reloads: 0,
+        }
+    }
+
+    /// Mark `name` as most recently used
+    pub(crate) fn touch(&mut self, name: &str) {
+        self.clock += 1;
+        self.last_access.insert(name.to_string(), self.clock);
+    }
+
+    /// Stop tracking `name`, deleting its spill file if it was evicted
+    pub(crate) fn forget(&mut self, name: &str) {
+        self.last_access.remove(name);
+        self.evicted.remove(name);
+    }
+
+    /// Whether `name` is currently evicted to disk
+    pub(crate) fn is_evicted(&self, name: &str) -> bool {
+        self.evicted.contains_key(name)
+    }
+
+    /// Names of the evicted tables (unordered)
+    pub(crate) fn evicted_names(&self) -> impl Iterator<Item = &str> {
+        self.evicted.keys().map(String::as_str)
+    }
+
+    /// Read an evicted table without making it resident
+    pub(crate) fn read(&self, name: &str) -> Result<Option<StorageEngine>> {
+        self.evicted.get(name).map(SpilledTable::read).transpose()
+    }
+
+    /// Move `name` back into `tables` if it is evicted
+    ///
+    /// The spill file is only deleted once the reload succeeded.
+    pub(crate) fn reload(
+        &mut self,
+        tables: &mut HashMap<String, StorageEngine>,
+        name: &str,
+    ) -> Result<()> {
+        let Some(storage) = self.read(name)? else {
+            return Ok(());
+        };
+        self.evicted.remove(name);
+        tables.insert(name.to_string(), storage);
+        self.reloads += 1;
+        Ok(())
+    }
+
+    /// Evict least recently used tables from `tables` until they fit the limit
+    ///
+    /// `pinned` (the table being accessed) is never evicted. Returns the
+    /// number of tables evicted.
+    pub(crate) fn enforce(
+        &mut self,
+        tables: &mut HashMap<String, StorageEngine>,
+        pinned: Option<&str>,
+    ) -> Result<usize> {
+        let resident: Vec<(&str, usize)> =
+            tables.iter().map(|(name, storage)| (name.as_str(), storage.memory_size())).collect();
+        let victims = self.victims(&resident, pinned);
+
+        for name in &victims {
+            if let Some(storage) = tables.get(name) {
+                let spilled = self.spill(storage)?;
+                tables.remove(name);
+                self.evicted.insert(name.clone(), spilled);
+            }
+        }
+        Ok(victims.len())
+    }
+
+    /// Least recently used names to evict so `resident` fits the limit
+    ///
+    /// Empty tables are skipped: evicting them frees nothing.
+    fn victims(&self, resident: &[(&str, usize)], pinned: Option<&str>) -> Vec<String> {
+        let mut total: usize = resident.iter().map(|(_, bytes)| bytes).sum();
+        let mut candidates: Vec<&(&str, usize)> =
+            resident.iter().filter(|(name, bytes)| *bytes > 0 && Some(*name) != pinned).collect();
+        candidates
+            .sort_by_key(|(name, _)| (self.last_access.get(*name).copied().unwrap_or(0), *name));
+
+        let mut victims = Vec::new();
+        for (name, bytes) in candidates {
+            if total <= self.config.max_resident_bytes {
+                break;
+            }
+            total -= bytes;
+            victims.push((*name).to_string());
+        }
+        victims
+    }
+
+    /// Write `storage`'s batches to a new spill file
+    fn spill(&mut self, storage: &StorageEngine) -> Result<SpilledTable> {
+        if self.dir.is_none() {
+            self.dir = Some(SpillDir::create(&self.config.spill_dir, "trueno_evict")?);
+        }
+        let dir = self.dir.as_ref().map_or(self.config.spill_dir.as_path(), |d| d.path.as_path());
+        let path = dir.join(format!("table_{:06}.arrow", self.next_file));
+        self.next_file += 1;
+
+        write_batches(&path, storage.batches())?;
+        self.evictions += 1;
+        Ok(SpilledTable {
+            shell: storage.with_batches(Vec::new()),
+            path,
+            bytes: storage.memory_size(),
+        })
+    }
+
+    /// Counters, given the catalog's resident tables
+    pub(crate) fn metrics(&self, tables: &HashMap<String, StorageEngine>) -> EvictionMetrics {
+        EvictionMetrics {
+            resident_bytes: tables.values().map(StorageEngine::memory_size).sum(),
+            spilled_bytes: self.evicted.values().map(|table| table.bytes).sum(),
+            spilled_tables: self.evicted.len(),
+            evictions: self.evictions,
+            reloads: self.reloads,
+        }
+    }
+}
+
+/// Write batches to an IPC file (an empty file records no schema)
+fn write_batches(path: &Path, batches: &[RecordBatch]) -> Result<()> {
+    let file = File::create(path)
+        .map_err(|e| Error::StorageError(format!("Failed to create eviction file: {e}")))?;
+    let Some(first) = batches.first() else {
+        return Ok(());
+    };
+    let mut writer = FileWriter::try_new(BufWriter::new(file), &first.schema())
+        .map_err(|e| Error::StorageError(format!("Failed to write eviction file: {e}")))?;
+    for batch in batches {
+        writer
+            .write(batch)
+            .map_err(|e| Error::StorageError(format!("Failed to write eviction file: {e}")))?;
+    }
+    writer.finish().map_err(|e| Error::StorageError(format!("Failed to write eviction file: {e}")))
+}
+
+/// Read every batch from an eviction file
+fn read_batches(path: &Path) -> Result<Vec<RecordBatch>> {
+    let file = File::open(path)
+        .map_err(|e| Error::StorageError(format!("Failed to open eviction file: {e}")))?;
+    if file.metadata()?.len() == 0 {
+        return Ok(Vec::new());
+    }
+    FileReader::try_new(BufReader::new(file), None)
+        .and_then(Iterator::collect)
+        .map_err(|e| Error::StorageError(format!("Failed to reload evicted table: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::Int32Array;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    fn storage(values: Vec<i32>) -> StorageEngine {
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(values))]).unwrap();
+        StorageEngine::new(vec![batch])
+    }
+
+    #[test]
+    fn test_victims_are_least_recently_used() {
+        let mut manager = MemoryManager::new(EvictionConfig::new(100));
+        for name in ["a", "b", "c"] {
+            manager.touch(name);
+        }
+        manager.touch("a");
+
+        let resident = [("a", 60), ("b", 60), ("c", 60)];
+        assert_eq!(manager.victims(&resident, None), vec!["b", "c"]);
+        assert_eq!(manager.victims(&resident, Some("b")), vec!["c", "a"]);
+        assert!(manager.victims(&resident[..1], None).is_empty());
+    }
+
+    #[test]
+    fn test_enforce_and_reload_round_trip() {
+        let dir = std::env::temp_dir().join("trueno_test_eviction_round_trip");
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut manager = MemoryManager::new(EvictionConfig::new(0).with_spill_dir(&dir));
+
+        let mut tables = HashMap::new();
+        tables.insert("events".to_string(), storage(vec![1, 2, 3]));
+        tables.insert("empty".to_string(), StorageEngine::new(Vec::new()));
+        manager.touch("events");
+
+        assert_eq!(manager.enforce(&mut tables, Some("events")).unwrap(), 0);
+        assert_eq!(manager.enforce(&mut tables, None).unwrap(), 1);
+        assert!(manager.is_evicted("events"));
+        assert!(!manager.is_evicted("empty"), "empty tables free nothing");
+        assert_eq!(manager.read("events").unwrap().unwrap().batches()[0].num_rows(), 3);
+
+        manager.reload(&mut tables, "events").unwrap();
+        manager.reload(&mut tables, "missing").unwrap();
+        assert_eq!(tables["events"].batches()[0].num_rows(), 3);
+        assert!(!manager.is_evicted("events"));
+
+        let metrics = manager.metrics(&tables);
+        assert_eq!((metrics.evictions, metrics.reloads, metrics.spilled_tables), (1, 1, 0));
+        assert!(metrics.resident_bytes > 0);
+    }
+}
@@ -0,0 +1,315 @@
+//! Schema evolution on append
+//!
+//! By default [`append_batch`](super::StorageEngine::append_batch) rejects a
+//! batch whose schema differs from the table's. With a [`SchemaEvolution`]
+//! the table's schema follows its data instead:
+//!
+//! - **New columns**: a batch may carry columns the table lacks. They are
+//!   added (nullable) at the end of the schema and earlier rows read as NULL.
+//! - **Missing columns**: a batch may omit nullable columns; its rows read
+//!   them as NULL.
+//! - **Safe widening**: `Int32` widens to `Int64` and `Float32` to `Float64`,
+//!   whichever side holds the narrower type. Every value converts exactly.
+//!
+//! Each column follows a [`ColumnEvolution`], so a key column can stay
+//! fixed while metric columns evolve. Existing batches are rewritten only
+//! when the table schema changes; snapshots taken before keep the old one.
+//!
+//! References:
+//! - Curino et al. (2008): Schema evolution in Wikipedia — toward a web
+//!   information system benchmark
+//!
+//! Toyota Way: Kaizen (tables grow new metrics without a reload)
+
+use crate::{Error, Result};
+use arrow::array::{new_null_array, ArrayRef};
+use arrow::compute;
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::dictionary;
+
+/// How one column may change between the table and an appended batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColumnEvolution {
+    /// Must be present on both sides with the same type and nullability
+    Strict,
+    /// May be added by a batch or missing from it (read as NULL); the type
+    /// must match
+    Nullable,
+    /// As `Nullable`, and the type may widen (`Int32`→`Int64`,
+    /// `Float32`→`Float64`)
+    #[default]
+    Widen,
+}
+
+/// Per-column schema evolution policy for appends
+///
+/// # Example
+/// ```
+/// use arrow::array::{Float64Array, Int32Array, Int64Array, RecordBatch};
+/// use arrow::datatypes::{DataType, Field, Schema};
+/// use std::sync::Arc;
+/// use trueno_db::storage::{ColumnEvolution, SchemaEvolution, StorageEngine};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let v1 = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+/// let v2 = Arc::new(Schema::new(vec![
+///     Field::new("id", DataType::Int64, false),
+///     Field::new("latency", DataType::Float64, true),
+/// ]));
+/// let first = RecordBatch::try_new(v1, vec![Arc::new(Int32Array::from(vec![1]))])?;
+/// let second = RecordBatch::try_new(
+///     v2,
+///     vec![Arc::new(Int64Array::from(vec![2])), Arc::new(Float64Array::from(vec![0.5]))],
+/// )?;
+///
+/// let evolution = SchemaEvolution::default().with_column("tenant", ColumnEvolution::Strict);
+/// let mut storage = StorageEngine::new(vec![first]).with_schema_evolution(evolution);
+/// storage.append_batch(second)?;
+///
+/// let schema = storage.batches()[0].schema();
+/// assert_eq!(schema.field(0).data_type(), &DataType::Int64);
+/// assert_eq!(storage.batches()[0].column(1).null_count(), 1);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SchemaEvolution {
+    default: ColumnEvolution,
+    columns: HashMap<String, ColumnEvolution>,
+}
+
+impl SchemaEvolution {
+    /// Apply `default` to every column without its own policy
+    #[must_use]
+    pub fn new(default: ColumnEvolution) -> Self {
+        Self { default, columns: HashMap::new() }
+    }
+
+    /// Apply `policy` to `column`
+    #[must_use]
+    pub fn with_column(mut self, column: impl Into<String>, policy: ColumnEvolution) -> Self {
+        self.columns.insert(column.into(), policy);
+        self
+    }
+
+    /// Policy for `column`
+    #[must_use]
+    pub fn policy(&self, column: &str) -> ColumnEvolution {
+        self.columns.get(column).copied().unwrap_or(self.default)
+    }
+
+    /// Table schema after appending a batch with schema `incoming`
+    ///
+    /// Both schemas are logical (dictionary columns decoded).
+    fn merge(&self, table: &Schema, incoming: &Schema) -> Result<Schema> {
+        let mut fields = Vec::with_capacity(table.fields().len());
+        for field in table.fields() {
+            let policy = self.policy(field.name());
+            let merged = match incoming.field_with_name(field.name()) {
+                Ok(other) if other == field.as_ref() => field.as_ref().clone(),
+                Ok(other) if policy != ColumnEvolution::Strict => {
+                    let data_type = merge_types(field.data_type(), other.data_type(), policy)
+                        .ok_or_else(|| mismatch(field, Some(other)))?;
+                    field
+                        .as_ref()
+                        .clone()
+                        .with_data_type(data_type)
+                        .with_nullable(field.is_nullable() || other.is_nullable())
+                }
+                Err(_) if policy != ColumnEvolution::Strict && field.is_nullable() => {
+                    field.as_ref().clone()
+                }
+                Ok(other) => return Err(mismatch(field, Some(other))),
+                Err(_) => return Err(mismatch(field, None)),
+            };
+            fields.push(merged);
+        }
+
+        for field in incoming.fields() {
+            if table.field_with_name(field.name()).is_ok() {
+                continue;
+            }
+            if self.policy(field.name()) == ColumnEvolution::Strict {
+                return Err(Error::StorageError(format!(
+                    "Schema mismatch: column {} is not in the table",
+                    field.name()
+                )));
+            }
+            fields.push(field.as_ref().clone().with_nullable(true));
+        }
+        Ok(Schema::new_with_metadata(fields, table.metadata().clone()))
+    }
+}
+
+/// Wider of two column types, if `policy` allows the change
+fn merge_types(table: &DataType, incoming: &DataType, policy: ColumnEvolution) -> Option<DataType> {
+    if table == incoming {
+        return Some(table.clone());
+    }
+    if policy != ColumnEvolution::Widen {
+        return None;
+    }
+    match (table, incoming) {
+        (DataType::Int32, DataType::Int64) | (DataType::Int64, DataType::Int32) => {
+            Some(DataType::Int64)
+        }
+        (DataType::Float32, DataType::Float64) | (DataType::Float64, DataType::Float32) => {
+            Some(DataType::Float64)
+        }
+        _ => None,
+    }
+}
+
+fn mismatch(field: &Field, incoming: Option<&Field>) -> Error {
+    Error::StorageError(incoming.map_or_else(
+        || format!("Schema mismatch: batch is missing column {}", field.name()),
+        |other| {
+            format!(
+                "Schema mismatch: column {} is {:?} (nullable: {}), got {:?} (nullable: {})",
+                field.name(),
+                field.data_type(),
+                field.is_nullable(),
+                other.data_type(),
+                other.is_nullable()
+            )
+        },
+    ))
+}
+
+/// Evolve the table held in `batches` to accept `batch`
+///
+/// Returns `batch` conformed to the (possibly new) table schema and whether
+/// `batches` were rewritten. `batches` are left untouched on error.
+///
+/// # Errors
+/// Returns error if `evolution` does not allow a difference between the
+/// schemas or a column cannot be widened
+pub(super) fn evolve(
+    batches: &mut [RecordBatch],
+    batch: RecordBatch,
+    evolution: &SchemaEvolution,
+) -> Result<(RecordBatch, bool)> {
+    let Some(first) = batches.first() else {
+        return Ok((batch, false));
+    };
+    let table = dictionary::logical_schema(&first.schema());
+    let incoming = dictionary::logical_schema(&batch.schema());
+    if incoming == table {
+        return Ok((batch, false));
+    }
+
+    let target = Arc::new(evolution.merge(&table, &incoming)?);
+    let batch = conform(&batch, &target)?;
+    if target.as_ref() == &table {
+        return Ok((batch, false));
+    }
+    let rewritten = batches.iter().map(|b| conform(b, &target)).collect::<Result<Vec<_>>>()?;
+    for (slot, rewritten) in batches.iter_mut().zip(rewritten) {
+        *slot = rewritten;
+    }
+    Ok((batch, true))
+}
+
+/// `batch` with the columns of `target`: widened, NULL where missing
+///
+/// Columns whose logical type already matches keep their physical type, so
+/// dictionary-encoded columns stay encoded.
+fn conform(batch: &RecordBatch, target: &SchemaRef) -> Result<RecordBatch> {
+    let schema = batch.schema();
+    let mut fields = Vec::with_capacity(target.fields().len());
+    let mut columns: Vec<ArrayRef> = Vec::with_capacity(target.fields().len());
+    for field in target.fields() {
+        let column = match schema.index_of(field.name()) {
+            Ok(index) => {
+                let column = batch.column(index);
+                if &dictionary::logical_type(column.data_type()) == field.data_type() {
+                    Arc::clone(column)
+                } else {
+                    compute::cast(column, field.data_type()).map_err(|e| {
+                        Error::StorageError(format!("Failed to widen {}: {e}", field.name()))
+                    })?
+                }
+            }
+            Err(_) => new_null_array(field.data_type(), batch.num_rows()),
+        };
+        fields.push(field.as_ref().clone().with_data_type(column.data_type().clone()));
+        columns.push(column);
+    }
+    let schema = Arc::new(Schema::new_with_metadata(fields, target.metadata().clone()));
+    RecordBatch::try_new(schema, columns)
+        .map_err(|e| Error::StorageError(format!("Failed to evolve batch: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::StorageEngine;
+    use arrow::array::{Array, Float32Array, Float64Array, Int32Array, Int64Array};
+
+    fn batch(columns: Vec<(&str, ArrayRef, bool)>) -> RecordBatch {
+        RecordBatch::try_from_iter_with_nullable(columns).unwrap()
+    }
+
+    fn ids(ids: Vec<i32>) -> RecordBatch {
+        batch(vec![("id", Arc::new(Int32Array::from(ids)), false)])
+    }
+
+    fn with_score(id: i64, score: f32) -> RecordBatch {
+        batch(vec![
+            ("id", Arc::new(Int64Array::from(vec![id])), false),
+            ("score", Arc::new(Float32Array::from(vec![score])), true),
+        ])
+    }
+
+    #[test]
+    fn test_add_column_and_widen() {
+        let mut storage = StorageEngine::new(vec![ids(vec![1, 2])])
+            .with_schema_evolution(SchemaEvolution::default());
+        storage.append_batch(with_score(3, 0.5)).unwrap();
+        // Older producers keep sending the original schema
+        storage.append_batch(ids(vec![4])).unwrap();
+
+        let schema = storage.batches()[0].schema();
+        assert!(storage.batches().iter().all(|b| b.schema() == schema));
+        assert_eq!(schema.field(0).data_type(), &DataType::Int64);
+        assert_eq!(schema.field(1).data_type(), &DataType::Float32);
+        assert!(schema.field(1).is_nullable());
+        assert_eq!(storage.batches()[0].column(1).null_count(), 2);
+        assert_eq!(storage.batches()[2].column(1).null_count(), 1);
+
+        // Float32 -> Float64 widens the existing rows
+        storage
+            .append_batch(batch(vec![
+                ("id", Arc::new(Int64Array::from(vec![5])), false),
+                ("score", Arc::new(Float64Array::from(vec![1.5])), true),
+            ]))
+            .unwrap();
+        let scores = storage.batches()[1].column(1);
+        let scores = scores.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert!((scores.value(0) - 0.5).abs() < f64::EPSILON);
+
+        // `id` is not nullable, so a batch cannot omit it
+        let scores_only = batch(vec![("score", Arc::new(Float64Array::from(vec![2.0])), true)]);
+        assert!(storage.append_batch(scores_only).is_err());
+    }
+
+    #[test]
+    fn test_per_column_policy_rejects() {
+        let strict = SchemaEvolution::default().with_column("id", ColumnEvolution::Strict);
+        let mut storage = StorageEngine::new(vec![ids(vec![1])]).with_schema_evolution(strict);
+        assert!(storage.append_batch(with_score(2, 1.0)).is_err());
+        assert_eq!(storage.batches()[0].schema().field(0).data_type(), &DataType::Int32);
+
+        let no_widening = SchemaEvolution::new(ColumnEvolution::Nullable);
+        let mut storage = StorageEngine::new(vec![ids(vec![1])]).with_schema_evolution(no_widening);
+        assert!(storage.append_batch(with_score(2, 1.0)).is_err());
+
+        // Without evolution any difference is rejected
+        let mut storage = StorageEngine::new(vec![ids(vec![1])]);
+        assert!(storage.append_batch(with_score(2, 1.0)).is_err());
+    }
+}
@@ -0,0 +1,589 @@
+//! Micro-batch ingestion for row-at-a-time producers
+//!
+//! Storage is append-only and columnar: every append is a whole
+//! `RecordBatch`, and a table built from one-row batches scans slowly and
+//! wastes per-batch overhead. [`MicroBatcher`] sits between producers that
+//! emit single rows (KV-style event streams, SQL `INSERT`) and the table:
+//! rows are buffered and turned into one batch when either threshold in
+//! [`MicroBatchConfig`] is reached.
+//!
+//! - **Row count**: [`push`](MicroBatcher::push) returns a batch as soon as
+//!   `max_rows` rows are buffered.
+//! - **Time**: [`tick`](MicroBatcher::tick) returns a batch once the oldest
+//!   buffered row has waited `max_delay`. Call it from a timer;
+//!   [`time_until_due`](MicroBatcher::time_until_due) says how long to sleep.
+//!
+//! ```
+//! use arrow::datatypes::{DataType, Field, Schema};
+//! use std::sync::Arc;
+//! use trueno_db::storage::ingest::{MicroBatchConfig, MicroBatcher, Value};
+//! use trueno_db::storage::StorageEngine;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let schema = Arc::new(Schema::new(vec![
+//!     Field::new("sensor", DataType::Utf8, false),
+//!     Field::new("reading", DataType::Float64, true),
+//! ]));
+//! let config = MicroBatchConfig { max_rows: 2, ..MicroBatchConfig::default() };
+//! let mut batcher = MicroBatcher::new("readings", schema, config);
+//! let mut table = StorageEngine::new(vec![]);
+//!
+//! assert!(batcher.push(vec![Value::from("a"), Value::from(1.5)])?.is_none());
+//! if let Some(batch) = batcher.push(vec![Value::from("b"), Value::Null])? {
+//!     table.append_batch(batch)?;
+//! }
+//! for batch in batcher.insert_sql("INSERT INTO readings VALUES ('c', 2.0)")? {
+//!     table.append_batch(batch)?;
+//! }
+//! // Shutdown: flush whatever is still buffered
+//! if let Some(batch) = batcher.flush()? {
+//!     table.append_batch(batch)?;
+//! }
+//! assert_eq!(table.batches().iter().map(|b| b.num_rows()).sum::<usize>(), 3);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Rows are type-checked on push, so a bad row is rejected on its own and
+//! never poisons the batch it would have joined.
+//!
+//! Toyota Way: Heijunka (level a trickle of rows into steady batches)
+
+use crate::query::stats::Stopwatch;
+use crate::{Error, Result};
+use arrow::array::{
+    ArrayRef, BooleanArray, Float32Array, Float64Array, Int16Array, Int32Array, Int64Array,
+    Int8Array, StringArray, UInt16Array, UInt32Array, UInt64Array, UInt8Array,
+};
+use arrow::datatypes::{DataType, Field, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use sqlparser::ast::{Expr, SetExpr, Statement, UnaryOperator};
+use sqlparser::dialect::GenericDialect;
+use sqlparser::parser::Parser;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Default row-count threshold (one morsel-friendly batch)
+pub const DEFAULT_MAX_ROWS: usize = 8192;
+
+/// Default time threshold
+pub const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(1);
+
+/// One cell of an ingested row
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// SQL NULL (the column must be nullable)
+    Null,
+    /// Boolean
+    Boolean(bool),
+    /// Any integer; narrowed to the column type on push
+    Int(i64),
+    /// Any float (float columns only)
+    Float(f64),
+    /// Text
+    Utf8(String),
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Self::Boolean(value)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Self::Int(i64::from(value))
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Self::Utf8(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Self::Utf8(value)
+    }
+}
+
+impl<T: Into<Self>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Self::Null, Into::into)
+    }
+}
+
+/// Flush thresholds for a [`MicroBatcher`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MicroBatchConfig {
+    /// Flush once this many rows are buffered
+    pub max_rows: usize,
+    /// Flush once the oldest buffered row has waited this long
+    pub max_delay: Duration,
+}
+
+impl Default for MicroBatchConfig {
+    fn default() -> Self {
+        Self { max_rows: DEFAULT_MAX_ROWS, max_delay: DEFAULT_MAX_DELAY }
+    }
+}
+
+/// Buffers single-row inserts into micro-batches for one table
+pub struct MicroBatcher {
+    table: String,
+    schema: SchemaRef,
+    config: MicroBatchConfig,
+    rows: Vec<Vec<Value>>,
+    /// Started when the first row of the current batch was buffered
+    oldest: Option<Stopwatch>,
+}
+
+impl MicroBatcher {
+    /// Create a batcher for `table` with rows shaped like `schema`
+    ///
+    /// A `max_rows` of 0 is treated as 1.
+    #[must_use]
+    pub fn new(table: impl Into<String>, schema: SchemaRef, config: MicroBatchConfig) -> Self {
+        let config = MicroBatchConfig { max_rows: config.max_rows.max(1), ..config };
+        Self { table: table.into(), schema, config, rows: Vec::new(), oldest: None }
+    }
+
+    /// Table the batches are for
+    #[must_use]
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    /// Schema of the produced batches
+    #[must_use]
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    /// Rows buffered and not yet flushed
+    #[must_use]
+    pub fn pending_rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Buffer one row (values in schema order)
+    ///
+    /// Returns the flushed batch if this row reached `max_rows`.
+    ///
+    /// # Errors
+    /// Returns error if the row has the wrong number of values, a value does
+    /// not fit its column type, or NULL is given for a non-nullable column;
+    /// nothing is buffered in that case
+    pub fn push(&mut self, row: Vec<Value>) -> Result<Option<RecordBatch>> {
+        if row.len() != self.schema.fields().len() {
+            return Err(Error::InvalidInput(format!(
+                "Row has {} values but {} has {} columns",
+                row.len(),
+                self.table,
+                self.schema.fields().len()
+            )));
+        }
+        let row = self
+            .schema
+            .fields()
+            .iter()
+            .zip(row)
+            .map(|(field, value)| coerce(field, value))
+            .collect::<Result<Vec<_>>>()?;
+
+        if self.rows.is_empty() {
+            self.oldest = Some(Stopwatch::start());
+        }
+        self.rows.push(row);
+        if self.rows.len() >= self.config.max_rows {
+            return self.flush();
+        }
+        Ok(None)
+    }
+
+    /// Buffer the rows of an `INSERT INTO <table> [(columns)] VALUES ...`
+    ///
+    /// Columns left out of the column list are NULL. Values must be
+    /// literals (numbers, strings, booleans, NULL, negated numbers).
+    /// Returns every batch flushed along the way.
+    ///
+    /// # Errors
+    /// Returns error if the statement is not a single INSERT ... VALUES into
+    /// this batcher's table, names an unknown column, or any row is rejected
+    /// by [`push`](Self::push). Rows are checked before any is buffered.
+    pub fn insert_sql(&mut self, sql: &str) -> Result<Vec<RecordBatch>> {
+        let rows = self.parse_insert(sql)?;
+        let mut flushed = Vec::new();
+        for row in rows {
+            if let Some(batch) = self.push(row)? {
+                flushed.push(batch);
+            }
+        }
+        Ok(flushed)
+    }
+
+    /// Time left before [`tick`](Self::tick) flushes (`None` when empty)
+    ///
+    /// Zero means a flush is already due.
+    #[must_use]
+    pub fn time_until_due(&self) -> Option<Duration> {
+        let waited = Duration::from_secs_f64(self.oldest.as_ref()?.elapsed_ms().max(0.0) / 1000.0);
+        Some(self.config.max_delay.saturating_sub(waited))
+    }
+
+    /// Flush if the oldest buffered row has waited at least `max_delay`
+    ///
+    /// # Errors
+    /// Returns error if the batch cannot be built
+    pub fn tick(&mut self) -> Result<Option<RecordBatch>> {
+        match self.time_until_due() {
+            Some(wait) if wait.is_zero() => self.flush(),
+            _ => Ok(None),
+        }
+    }
+
+    /// Flush every buffered row now (`None` when empty)
+    ///
+    /// # Errors
+    /// Returns error if the batch cannot be built
+    pub fn flush(&mut self) -> Result<Option<RecordBatch>> {
+        if self.rows.is_empty() {
+            return Ok(None);
+        }
+        self.oldest = None;
+        let rows = std::mem::take(&mut self.rows);
+        build_batch(&self.schema, &rows).map(Some)
+    }
+
+    /// Rows of an INSERT statement, in schema order, checked against the schema
+    fn parse_insert(&self, sql: &str) -> Result<Vec<Vec<Value>>> {
+        let statements = Parser::parse_sql(&GenericDialect {}, sql)
+            .map_err(|e| Error::ParseError(format!("SQL parse error: {e}")))?;
+        let [Statement::Insert(insert)] = statements.as_slice() else {
+            return Err(Error::ParseError("Expected a single INSERT statement".to_string()));
+        };
+
+        let target = insert.table_name.to_string();
+        if target != self.table {
+            return Err(Error::InvalidInput(format!(
+                "INSERT targets {target}, but this batcher ingests {}",
+                self.table
+            )));
+        }
+
+        let Some(SetExpr::Values(values)) = insert.source.as_ref().map(|q| q.body.as_ref()) else {
+            return Err(Error::ParseError("Only INSERT ... VALUES is supported".to_string()));
+        };
+
+        // Position of each listed column in the schema (all columns if none listed)
+        let positions = if insert.columns.is_empty() {
+            (0..self.schema.fields().len()).collect()
+        } else {
+            insert
+                .columns
+                .iter()
+                .map(|ident| {
+                    self.schema.index_of(&ident.value).map_err(|_| {
+                        Error::InvalidInput(format!("Column not found: {}", ident.value))
+                    })
+                })
+                .collect::<Result<Vec<usize>>>()?
+        };
+
+        values
+            .rows
+            .iter()
+            .map(|exprs| {
+                if exprs.len() != positions.len() {
+                    return Err(Error::InvalidInput(format!(
+                        "INSERT row has {} values for {} columns",
+                        exprs.len(),
+                        positions.len()
+                    )));
+                }
+                let mut row = vec![Value::Null; self.schema.fields().len()];
+                for (&position, expr) in positions.iter().zip(exprs) {
+                    row[position] = literal(expr)?;
+                }
+                // Check now so a bad row rejects the whole statement
+                for (field, value) in self.schema.fields().iter().zip(&row) {
+                    coerce(field, value.clone())?;
+                }
+                Ok(row)
+            })
+            .collect()
+    }
+}
+
+/// Value of a literal SQL expression
+fn literal(expr: &Expr) -> Result<Value> {
+    use sqlparser::ast::Value as Sql;
+
+    match expr {
+        Expr::Value(Sql::Null) => Ok(Value::Null),
+        Expr::Value(Sql::Boolean(b)) => Ok(Value::Boolean(*b)),
+        Expr::Value(Sql::Number(n, _)) => n
+            .parse::<i64>()
+            .map(Value::Int)
+            .or_else(|_| n.parse::<f64>().map(Value::Float))
+            .map_err(|_| Error::ParseError(format!("Invalid number: {n}"))),
+        Expr::Value(Sql::SingleQuotedString(s) | Sql::DoubleQuotedString(s)) => {
+            Ok(Value::Utf8(s.clone()))
+        }
+        Expr::UnaryOp { op: UnaryOperator::Minus, expr } => match literal(expr)? {
+            Value::Int(i) => Ok(Value::Int(-i)),
+            Value::Float(f) => Ok(Value::Float(-f)),
+            _ => Err(Error::ParseError(format!("Cannot negate {expr}"))),
+        },
+        other => Err(Error::ParseError(format!("INSERT values must be literals, got {other}"))),
+    }
+}
+
+/// Check `value` against `field`, normalizing it to the column's kind
+///
+/// Integers widen to float columns and narrow (range-checked) to smaller
+/// integer columns; everything else must match the column type exactly.
+fn coerce(field: &Field, value: Value) -> Result<Value> {
+    let mismatch = |value: &Value| {
+        Error::InvalidInput(format!(
+            "Value {value:?} does not fit column {} of type {}",
+            field.name(),
+            field.data_type()
+        ))
+    };
+
+    match (field.data_type(), value) {
+        (_, Value::Null) if field.is_nullable() => Ok(Value::Null),
+        (_, Value::Null) => {
+            Err(Error::InvalidInput(format!("Column {} is not nullable", field.name())))
+        }
+        (DataType::Boolean, value @ Value::Boolean(_))
+        | (DataType::Utf8, value @ Value::Utf8(_))
+        | (DataType::Float32 | DataType::Float64, value @ Value::Float(_)) => Ok(value),
+        #[allow(clippy::cast_precision_loss)]
+        (DataType::Float32 | DataType::Float64, Value::Int(i)) => Ok(Value::Float(i as f64)),
+        (data_type, Value::Int(i)) if is_integer(data_type) => {
+            let fits = match data_type {
+                DataType::Int8 => i8::try_from(i).is_ok(),
+                DataType::Int16 => i16::try_from(i).is_ok(),
+                DataType::Int32 => i32::try_from(i).is_ok(),
+                DataType::Int64 => true,
+                DataType::UInt8 => u8::try_from(i).is_ok(),
+                DataType::UInt16 => u16::try_from(i).is_ok(),
+                DataType::UInt32 => u32::try_from(i).is_ok(),
+                DataType::UInt64 => u64::try_from(i).is_ok(),
+                _ => false,
+            };
+            if fits {
+                Ok(Value::Int(i))
+            } else {
+                Err(mismatch(&Value::Int(i)))
+            }
+        }
+        (data_type, value) if is_supported(data_type) => Err(mismatch(&value)),
+        (data_type, _) => Err(Error::InvalidInput(format!(
+            "Micro-batch ingestion does not support column {} of type {data_type}",
+            field.name()
+        ))),
+    }
+}
+
+const fn is_integer(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+    )
+}
+
+const fn is_supported(data_type: &DataType) -> bool {
+    matches!(data_type, DataType::Boolean | DataType::Utf8 | DataType::Float32 | DataType::Float64)
+        || is_integer(data_type)
+}
+
+/// Build a batch from rows already checked by [`coerce`]
+fn build_batch(schema: &SchemaRef, rows: &[Vec<Value>]) -> Result<RecordBatch> {
+    let columns = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(index, field)| build_column(field.data_type(), rows.iter().map(|row| &row[index])))
+        .collect::<Result<Vec<_>>>()?;
+
+    RecordBatch::try_new(schema.clone(), columns)
+        .map_err(|e| Error::StorageError(format!("Failed to build micro-batch: {e}")))
+}
+
+/// One column of a micro-batch (integer narrowing was range-checked on push)
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn build_column<'a>(
+    data_type: &DataType,
+    values: impl Iterator<Item = &'a Value>,
+) -> Result<ArrayRef> {
+    let int = |value: &Value| match value {
+        Value::Int(i) => Some(*i),
+        _ => None,
+    };
+    let float = |value: &Value| match value {
+        Value::Float(f) => Some(*f),
+        _ => None,
+    };
+
+    let array: ArrayRef = match data_type {
+        DataType::Boolean => Arc::new(
+            values
+                .map(|value| match value {
+                    Value::Boolean(b) => Some(*b),
+                    _ => None,
+                })
+                .collect::<BooleanArray>(),
+        ),
+        DataType::Utf8 => Arc::new(
+            values
+                .map(|value| match value {
+                    Value::Utf8(s) => Some(s.as_str()),
+                    _ => None,
+                })
+                .collect::<StringArray>(),
+        ),
+        DataType::Float32 => {
+            Arc::new(values.map(|v| float(v).map(|f| f as f32)).collect::<Float32Array>())
+        }
+        DataType::Float64 => Arc::new(values.map(float).collect::<Float64Array>()),
+        DataType::Int8 => Arc::new(values.map(|v| int(v).map(|i| i as i8)).collect::<Int8Array>()),
+        DataType::Int16 => {
+            Arc::new(values.map(|v| int(v).map(|i| i as i16)).collect::<Int16Array>())
+        }
+        DataType::Int32 => {
+            Arc::new(values.map(|v| int(v).map(|i| i as i32)).collect::<Int32Array>())
+        }
+        DataType::Int64 => Arc::new(values.map(int).collect::<Int64Array>()),
+        DataType::UInt8 => {
+            Arc::new(values.map(|v| int(v).map(|i| i as u8)).collect::<UInt8Array>())
+        }
+        DataType::UInt16 => {
+            Arc::new(values.map(|v| int(v).map(|i| i as u16)).collect::<UInt16Array>())
+        }
+        DataType::UInt32 => {
+            Arc::new(values.map(|v| int(v).map(|i| i as u32)).collect::<UInt32Array>())
+        }
+        DataType::UInt64 => {
+            Arc::new(values.map(|v| int(v).map(|i| i as u64)).collect::<UInt64Array>())
+        }
+        other => {
+            return Err(Error::InvalidInput(format!(
+                "Micro-batch ingestion does not support type {other}"
+            )))
+        }
+    };
+    Ok(array)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::Array;
+    use arrow::datatypes::Schema;
+
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("score", DataType::Float64, true),
+            Field::new("tag", DataType::Utf8, true),
+        ]))
+    }
+
+    fn row(id: i32, score: Option<f64>, tag: Option<&str>) -> Vec<Value> {
+        vec![id.into(), score.into(), tag.into()]
+    }
+
+    #[test]
+    fn test_flushes_on_row_count() {
+        let config = MicroBatchConfig { max_rows: 3, max_delay: Duration::from_secs(3600) };
+        let mut batcher = MicroBatcher::new("t", schema(), config);
+
+        assert!(batcher.push(row(1, Some(0.5), None)).unwrap().is_none());
+        assert!(batcher.push(row(2, None, Some("x"))).unwrap().is_none());
+        assert_eq!(batcher.pending_rows(), 2);
+        assert!(batcher.tick().unwrap().is_none());
+
+        let batch = batcher.push(row(3, Some(2.0), Some("y"))).unwrap().unwrap();
+        assert_eq!(batch.num_rows(), 3);
+        assert_eq!(batch.schema(), schema());
+        assert_eq!(batcher.pending_rows(), 0);
+        assert_eq!(batcher.time_until_due(), None);
+
+        let scores = batch.column(1).as_any().downcast_ref::<Float64Array>().unwrap();
+        assert!(scores.is_null(1));
+        assert!((scores.value(2) - 2.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_flushes_on_time() {
+        let config = MicroBatchConfig { max_rows: 100, max_delay: Duration::from_millis(20) };
+        let mut batcher = MicroBatcher::new("t", schema(), config);
+        assert!(batcher.tick().unwrap().is_none());
+
+        batcher.push(row(1, None, None)).unwrap();
+        assert!(batcher.time_until_due().unwrap() <= Duration::from_millis(20));
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(batcher.time_until_due(), Some(Duration::ZERO));
+        assert_eq!(batcher.tick().unwrap().unwrap().num_rows(), 1);
+        assert!(batcher.flush().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_rejects_bad_rows_without_buffering() {
+        let mut batcher = MicroBatcher::new("t", schema(), MicroBatchConfig::default());
+
+        assert!(batcher.push(vec![Value::from(1)]).is_err());
+        assert!(batcher.push(vec![Value::Null, Value::Null, Value::Null]).is_err());
+        assert!(batcher.push(vec![Value::from(i64::MAX), Value::Null, Value::Null]).is_err());
+        assert!(batcher.push(vec![Value::from("1"), Value::Null, Value::Null]).is_err());
+        assert_eq!(batcher.pending_rows(), 0);
+
+        // Integers widen into float columns
+        batcher.push(vec![Value::from(1), Value::from(2), Value::Null]).unwrap();
+        assert_eq!(batcher.pending_rows(), 1);
+    }
+
+    #[test]
+    fn test_insert_sql() {
+        let mut batcher = MicroBatcher::new("t", schema(), MicroBatchConfig::default());
+        batcher.insert_sql("INSERT INTO t (tag, id) VALUES ('a', 1), (NULL, -2)").unwrap();
+        batcher.insert_sql("INSERT INTO t VALUES (3, -1.5, 'c')").unwrap();
+
+        let batch = batcher.flush().unwrap().unwrap();
+        let ids = batch.column(0).as_any().downcast_ref::<Int32Array>().unwrap();
+        assert_eq!(ids.values(), &[1, -2, 3]);
+        assert!(batch.column(1).is_null(0));
+        assert_eq!(batch.column(2).null_count(), 1);
+
+        // Wrong table, unknown column, non-literal, and a bad second row: nothing buffered
+        assert!(batcher.insert_sql("INSERT INTO other VALUES (1, 1.0, 'x')").is_err());
+        assert!(batcher.insert_sql("INSERT INTO t (nope) VALUES (1)").is_err());
+        assert!(batcher.insert_sql("INSERT INTO t (id) VALUES (1 + 1)").is_err());
+        assert!(batcher.insert_sql("INSERT INTO t (id) VALUES (1), (NULL)").is_err());
+        assert!(batcher.insert_sql("SELECT 1").is_err());
+        assert_eq!(batcher.pending_rows(), 0);
+    }
+}
@@ -0,0 +1,121 @@
+//! Zero-copy Arrow IPC loading
+//!
+//! [`StorageEngine::load_ipc`](super::StorageEngine::load_ipc) decodes the
+//! file through a reader, copying every column into fresh buffers. Here the
+//! file is memory-mapped instead and each batch is decoded as slices of the
+//! mapping: column values stay in the page cache and are paged in on first
+//! access, so loading a Feather file written by pyarrow or pandas costs its
+//! metadata only. The mapping is released once the last batch referencing
+//! it is dropped.
+//!
+//! Loading is `unsafe`: the batches read the file's pages directly, so the
+//! file must not be truncated or modified while any of them is alive.
+//!
+//! Buffers the writer did not align to their type are copied (the decoder
+//! does not require alignment); files written by Arrow implementations are
+//! aligned to 8 or 64 bytes, so in practice nothing is.
+
+use crate::{Error, Result};
+use arrow::buffer::Buffer;
+use arrow::ipc::convert::fb_to_schema;
+use arrow::ipc::reader::{read_footer_length, FileDecoder};
+use arrow::ipc::{root_as_footer, Block};
+use arrow::record_batch::RecordBatch;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Footer length (4 bytes) followed by the `ARROW1` magic
+const TRAILER_LEN: usize = 10;
+
+/// Map `path` and decode every record batch from the mapping
+///
+/// # Safety
+/// The batches reference the mapped file: it must not be truncated or
+/// modified (by this or any other process) while any of them is alive.
+/// Reading a batch after that is undefined behaviour.
+///
+/// # Errors
+/// Returns error if the file cannot be opened, mapped or parsed
+#[allow(unsafe_code)]
+pub unsafe fn load_ipc_mmap(path: &Path) -> Result<Vec<RecordBatch>> {
+    let file = File::open(path)
+        .map_err(|e| Error::StorageError(format!("Failed to open IPC file: {e}")))?;
+    // SAFETY: the caller keeps the file unchanged while the batches live
+    let buffer = unsafe { map(&file)? };
+    decode(&buffer)
+}
+
+/// Read-only mapping of `file` as an Arrow buffer
+///
+/// # Safety
+/// As for [`load_ipc_mmap`]: the file must stay unchanged while the buffer
+/// or any slice of it is alive.
+#[allow(unsafe_code)]
+unsafe fn map(file: &File) -> Result<Buffer> {
+    // SAFETY: the mapping is read-only, and our caller guarantees the file
+    // is not truncated or rewritten while it is mapped
+    let mmap = unsafe { memmap2::Mmap::map(file) }
+        .map_err(|e| Error::StorageError(format!("Failed to map IPC file: {e}")))?;
+    Ok(Buffer::from(bytes::Bytes::from_owner(mmap)))
+}
+
+/// Decode the dictionaries and record batches listed in the footer
+fn decode(buffer: &Buffer) -> Result<Vec<RecordBatch>> {
+    let invalid =
+        |e: arrow::error::ArrowError| Error::StorageError(format!("Failed to parse IPC file: {e}"));
+
+    let trailer_start = buffer.len().checked_sub(TRAILER_LEN).ok_or_else(|| {
+        Error::StorageError("Failed to parse IPC file: shorter than its trailer".to_string())
+    })?;
+    let mut trailer = [0u8; TRAILER_LEN];
+    trailer.copy_from_slice(&buffer[trailer_start..]);
+    let footer_len = read_footer_length(trailer).map_err(invalid)?;
+    let footer_start = trailer_start.checked_sub(footer_len).ok_or_else(|| {
+        Error::StorageError("Failed to parse IPC file: footer exceeds file".to_string())
+    })?;
+    let footer = root_as_footer(&buffer[footer_start..trailer_start])
+        .map_err(|e| Error::StorageError(format!("Failed to parse IPC footer: {e}")))?;
+    let schema =
+        footer.schema().ok_or_else(|| Error::StorageError("IPC file has no schema".to_string()))?;
+
+    let mut decoder = FileDecoder::new(Arc::new(fb_to_schema(schema)), footer.version());
+    for block in footer.dictionaries().iter().flatten() {
+        decoder.read_dictionary(block, &block_data(buffer, block)?).map_err(invalid)?;
+    }
+    let mut batches = Vec::new();
+    for block in footer.recordBatches().iter().flatten() {
+        if let Some(batch) =
+            decoder.read_record_batch(block, &block_data(buffer, block)?).map_err(invalid)?
+        {
+            batches.push(batch);
+        }
+    }
+    Ok(batches)
+}
+
+/// Metadata and body of one footer block, as a slice of the mapping
+fn block_data(buffer: &Buffer, block: &Block) -> Result<Buffer> {
+    let out_of_range =
+        || Error::StorageError("Failed to parse IPC file: block exceeds file".to_string());
+    let offset = usize::try_from(block.offset()).map_err(|_| out_of_range())?;
+    let len = usize::try_from(block.bodyLength() + i64::from(block.metaDataLength()))
+        .map_err(|_| out_of_range())?;
+    if !matches!(offset.checked_add(len), Some(end) if end <= buffer.len()) {
+        return Err(out_of_range());
+    }
+    Ok(buffer.slice_with_length(offset, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncated_file_is_rejected() {
+        let short = Buffer::from_vec(vec![0u8; 4]);
+        assert!(matches!(decode(&short), Err(Error::StorageError(_))));
+        let garbage = Buffer::from_vec(vec![0xFFu8; 64]);
+        assert!(matches!(decode(&garbage), Err(Error::StorageError(_))));
+    }
+}
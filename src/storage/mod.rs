@@ -12,10 +12,55 @@
 //! - Poka-Yoke: Morsel-based paging prevents VRAM OOM (Funke et al. 2018)
 //! - Muda elimination: Late materialization (Abadi et al. 2008)
 
+#[cfg(feature = "parquet-io")]
+pub mod codec;
+pub mod compression;
+pub mod dictionary;
+#[cfg(feature = "ipc-io")]
+pub mod eviction;
+pub mod evolution;
+pub mod ingest;
+#[cfg(feature = "ipc-mmap")]
+pub mod mmap;
+#[cfg(feature = "parquet-io")]
+pub mod parallel;
+pub mod provenance;
+#[cfg(feature = "parquet-io")]
+pub mod sink;
+pub mod snapshot;
+#[cfg(feature = "parquet-io")]
+pub mod streaming;
+pub mod zone_map;
+
+#[cfg(feature = "parquet-io")]
+pub use codec::{ColumnCodec, ColumnOptions, ParquetWriteOptions};
+
+pub use compression::{ColumnEncoding, CompressedTable};
+pub use dictionary::{decode_dictionaries, ColumnStatistics, DEFAULT_DICTIONARY_THRESHOLD};
+#[cfg(feature = "ipc-io")]
+pub use eviction::{EvictionConfig, EvictionMetrics};
+pub use evolution::{ColumnEvolution, SchemaEvolution};
+pub use ingest::{MicroBatchConfig, MicroBatcher};
+#[cfg(feature = "parquet-io")]
+pub use parallel::{AppendOrder, ParallelLoad, ParallelLoadOptions};
+pub use provenance::PROVENANCE_COLUMNS;
+#[cfg(feature = "parquet-io")]
+pub use sink::{ParquetSink, ParquetWriteSummary};
+pub use snapshot::{
+    CompactionPolicy, CompactionSummary, StorageSnapshot, DEFAULT_COMPACTION_MAX_BATCHES,
+    DEFAULT_COMPACTION_TARGET_ROWS,
+};
+#[cfg(feature = "parquet-io")]
+pub use streaming::ParquetMorselReader;
+pub use zone_map::{ColumnZone, ZoneMap, ZoneValue, ZONE_ROWS};
+
+use crate::metrics::{self, Counter};
 use crate::{Error, Result};
+use arrow::datatypes::DataType;
 use arrow::record_batch::RecordBatch;
-#[cfg(feature = "parquet-io")]
+#[cfg(any(feature = "parquet-io", feature = "ipc-io", feature = "csv-io"))]
 use std::path::Path;
+use std::sync::OnceLock;
 
 /// Morsel size for out-of-core execution (128MB chunks)
 /// Based on: Leis et al. (2014) morsel-driven parallelism
@@ -29,6 +74,22 @@ const MAX_IN_FLIGHT_TRANSFERS: usize = 2;
 /// Storage engine for Arrow/Parquet data
 pub struct StorageEngine {
     batches: Vec<RecordBatch>,
+    /// Cardinality ratio at or below which Utf8 columns are dictionary-encoded
+    dictionary_threshold: Option<f64>,
+    /// Column indices currently stored dictionary-encoded
+    dictionary_columns: Vec<usize>,
+    /// Inject provenance system columns into ingested batches
+    provenance: bool,
+    /// Id assigned to the next ingested batch (provenance only)
+    next_batch_id: u64,
+    /// Zone maps per batch, built on first use and extended on append
+    zone_maps: OnceLock<Vec<Vec<ZoneMap>>>,
+    /// Bumped by every append and compaction (see [`snapshot`])
+    version: u64,
+    /// Compact automatically after appends (see [`snapshot`])
+    compaction: Option<CompactionPolicy>,
+    /// Let appends add and widen columns (see [`evolution`])
+    schema_evolution: Option<SchemaEvolution>,
 }
 
 impl StorageEngine {
@@ -37,7 +98,205 @@ impl StorageEngine {
     /// Useful for testing and benchmarking
     #[must_use]
     pub const fn new(batches: Vec<RecordBatch>) -> Self {
-        Self { batches }
+        Self {
+            batches,
+            dictionary_threshold: None,
+            dictionary_columns: Vec::new(),
+            provenance: false,
+            next_batch_id: 0,
+            zone_maps: OnceLock::new(),
+            version: 0,
+            compaction: None,
+            schema_evolution: None,
+        }
+    }
+
+    /// Enable provenance columns (`_source_file`, `_batch_id`, `_ingest_ts`)
+    ///
+    /// Batches already held (e.g. from `load_parquet`) are tagged with
+    /// `source_file`; later appends are tagged as they arrive. Use
+    /// [`append_batch_from`](Self::append_batch_from) to record a source
+    /// file for appended batches.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use trueno_db::storage::StorageEngine;
+    /// # #[cfg(feature = "parquet-io")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let path = "data/events.parquet";
+    /// let storage = StorageEngine::load_parquet(path)?.with_provenance(Some(path))?;
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "parquet-io"))]
+    /// # fn main() {}
+    /// ```
+    ///
+    /// # Errors
+    /// Returns error if existing batches already use a reserved column name
+    pub fn with_provenance(mut self, source_file: Option<&str>) -> Result<Self> {
+        if self.provenance {
+            return Ok(self);
+        }
+
+        let ingest_ts = chrono::Utc::now().timestamp_micros();
+        let batches = std::mem::take(&mut self.batches);
+        let tagged = batches
+            .iter()
+            .map(|batch| self.tag(batch, source_file, ingest_ts))
+            .collect::<Result<_>>()?;
+        self.batches = tagged;
+        self.provenance = true;
+        self.zone_maps = OnceLock::new();
+        Ok(self)
+    }
+
+    /// Whether provenance columns are injected on ingest
+    #[must_use]
+    pub const fn has_provenance(&self) -> bool {
+        self.provenance
+    }
+
+    /// Inject provenance columns using the next batch id
+    fn tag(
+        &mut self,
+        batch: &RecordBatch,
+        source_file: Option<&str>,
+        ingest_ts: i64,
+    ) -> Result<RecordBatch> {
+        let batch_id = self.next_batch_id;
+        self.next_batch_id += 1;
+        provenance::inject(batch, &provenance::Provenance { source_file, batch_id, ingest_ts })
+    }
+
+    /// Enable automatic dictionary encoding for low-cardinality string columns
+    ///
+    /// A `Utf8` column is encoded when `distinct / non_null_rows <= threshold`.
+    /// The decision is made on the first appended batch and revisited by
+    /// [`analyze`](Self::analyze). Queries always see plain `Utf8` columns.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use trueno_db::storage::{StorageEngine, DEFAULT_DICTIONARY_THRESHOLD};
+    /// let storage = StorageEngine::new(vec![])
+    ///     .with_dictionary_threshold(DEFAULT_DICTIONARY_THRESHOLD);
+    /// ```
+    #[must_use]
+    pub fn with_dictionary_threshold(mut self, threshold: f64) -> Self {
+        self.dictionary_threshold = Some(threshold.clamp(0.0, 1.0));
+        self
+    }
+
+    /// Let appended batches add columns, omit nullable ones, and widen
+    /// column types as `evolution` allows (see [`evolution`])
+    ///
+    /// Without it any schema difference is rejected.
+    #[must_use]
+    pub fn with_schema_evolution(mut self, evolution: SchemaEvolution) -> Self {
+        self.schema_evolution = Some(evolution);
+        self
+    }
+
+    /// Schema evolution policy, if enabled
+    #[must_use]
+    pub const fn schema_evolution(&self) -> Option<&SchemaEvolution> {
+        self.schema_evolution.as_ref()
+    }
+
+    /// Column indices currently stored dictionary-encoded
+    #[must_use]
+    pub fn dictionary_columns(&self) -> &[usize] {
+        &self.dictionary_columns
+    }
+
+    /// Dictionary-encode the string column `column` in every batch
+    ///
+    /// For ingesting a column known to be low-cardinality without running
+    /// [`analyze`](Self::analyze). Batches appended later are encoded too
+    /// and may arrive as plain `Utf8`. With a dictionary threshold set, the
+    /// next `analyze` re-decides the column against the threshold.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use trueno_db::storage::StorageEngine;
+    /// # use arrow::array::{RecordBatch, StringArray};
+    /// # use arrow::datatypes::{DataType, Field, Schema};
+    /// # use std::sync::Arc;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let schema = Arc::new(Schema::new(vec![Field::new("status", DataType::Utf8, false)]));
+    /// let statuses = StringArray::from(vec!["ok", "ok", "error", "ok"]);
+    /// let batch = RecordBatch::try_new(schema, vec![Arc::new(statuses)])?;
+    ///
+    /// let mut storage = StorageEngine::new(vec![batch]);
+    /// storage.dictionary_encode("status")?;
+    /// assert_eq!(storage.dictionary_columns(), &[0]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns error if storage is empty, the column does not exist, or it
+    /// is not a `Utf8` column
+    pub fn dictionary_encode(&mut self, column: &str) -> Result<()> {
+        let Some(first) = self.batches.first() else {
+            return Err(Error::InvalidInput(format!(
+                "Cannot dictionary-encode {column}: storage is empty"
+            )));
+        };
+        let schema = first.schema();
+        let index = schema
+            .index_of(column)
+            .map_err(|_| Error::InvalidInput(format!("Column not found: {column}")))?;
+        let data_type = schema.field(index).data_type();
+        if data_type != &DataType::Utf8 && !dictionary::is_string_dictionary(data_type) {
+            return Err(Error::InvalidInput(format!(
+                "Dictionary encoding needs a Utf8 column: {column} is {data_type:?}"
+            )));
+        }
+        if self.dictionary_columns.contains(&index) {
+            return Ok(());
+        }
+
+        let mut columns = self.dictionary_columns.clone();
+        columns.push(index);
+        columns.sort_unstable();
+        self.batches = self
+            .batches
+            .iter()
+            .map(|b| dictionary::apply_encoding(b, &columns))
+            .collect::<Result<_>>()?;
+        self.dictionary_columns = columns;
+        Ok(())
+    }
+
+    /// Gather per-column statistics and re-evaluate dictionary encoding
+    ///
+    /// When a dictionary threshold is configured, columns that crossed the
+    /// threshold since the last decision are re-encoded (or decoded).
+    ///
+    /// # Errors
+    /// Returns error if a column cannot be cast during re-encoding
+    pub fn analyze(&mut self) -> Result<Vec<ColumnStatistics>> {
+        let mut stats = dictionary::compute_statistics(&self.batches)?;
+
+        if let Some(threshold) = self.dictionary_threshold {
+            let columns = dictionary::select_dictionary_columns(&stats, threshold);
+            if columns != self.dictionary_columns {
+                self.batches = self
+                    .batches
+                    .iter()
+                    .map(|b| dictionary::apply_encoding(b, &columns))
+                    .collect::<Result<_>>()?;
+                for (col_idx, stat) in stats.iter_mut().enumerate() {
+                    stat.dictionary_encoded = columns.contains(&col_idx);
+                }
+                self.dictionary_columns = columns;
+            }
+        }
+
+        Ok(stats)
     }
 
     /// Load table from Parquet file
@@ -46,14 +305,76 @@ impl StorageEngine {
     /// Returns error if file cannot be read or parsed
     #[cfg(feature = "parquet-io")]
     pub fn load_parquet<P: AsRef<Path>>(path: P) -> Result<Self> {
-        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
-        use std::fs::File;
+        let _span = tracing::debug_span!("load_parquet", path = %path.as_ref().display()).entered();
+        let file = std::fs::File::open(path.as_ref())
+            .map_err(|e| Error::StorageError(format!("Failed to open Parquet file: {e}")))?;
+        Self::from_parquet(file, None::<&[&str]>)
+    }
 
-        let file = File::open(path.as_ref())
+    /// Load only `columns` from a Parquet file
+    ///
+    /// Column chunks outside the projection are never read or decoded, so a
+    /// query touching two columns of a wide file pays for two. Columns keep
+    /// the order they have in the file, not the order given. See
+    /// [`QueryPlan::required_columns`](crate::query::QueryPlan::required_columns)
+    /// for the columns a query needs.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use trueno_db::storage::StorageEngine;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let storage =
+    ///     StorageEngine::load_parquet_with_projection("data/events.parquet", &["category", "value"])?;
+    /// assert_eq!(storage.batches()[0].num_columns(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns error if the file cannot be read or parsed, or a column is
+    /// not in the file
+    #[cfg(feature = "parquet-io")]
+    pub fn load_parquet_with_projection<P: AsRef<Path>, S: AsRef<str>>(
+        path: P,
+        columns: &[S],
+    ) -> Result<Self> {
+        let _span = tracing::debug_span!(
+            "load_parquet",
+            path = %path.as_ref().display(),
+            columns = columns.len()
+        )
+        .entered();
+        let file = std::fs::File::open(path.as_ref())
             .map_err(|e| Error::StorageError(format!("Failed to open Parquet file: {e}")))?;
+        Self::from_parquet(file, Some(columns))
+    }
+
+    /// Load table from Parquet bytes already in memory (e.g. a file handed
+    /// to the WASM module by the browser)
+    ///
+    /// # Errors
+    /// Returns error if the bytes are not a valid Parquet file
+    #[cfg(feature = "parquet-io")]
+    pub fn read_parquet(data: impl Into<bytes::Bytes>) -> Result<Self> {
+        Self::from_parquet(data.into(), None::<&[&str]>)
+    }
 
-        let builder = ParquetRecordBatchReaderBuilder::try_new(file)
+    /// Decode every row group of a Parquet source into memory, keeping only
+    /// `columns` when given
+    #[cfg(feature = "parquet-io")]
+    fn from_parquet<R: parquet::file::reader::ChunkReader + 'static, S: AsRef<str>>(
+        source: R,
+        columns: Option<&[S]>,
+    ) -> Result<Self> {
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let mut builder = ParquetRecordBatchReaderBuilder::try_new(source)
             .map_err(|e| Error::StorageError(format!("Failed to parse Parquet file: {e}")))?;
+        if let Some(columns) = columns {
+            let (mask, _) = streaming::projection(&builder, columns)?;
+            builder = builder.with_projection(mask);
+        }
 
         let reader = builder
             .build()
@@ -67,7 +388,318 @@ impl StorageEngine {
             batches.push(batch);
         }
 
-        Ok(Self { batches })
+        // Statistics are built as part of loading, not by the first query
+        let storage = Self::new(batches);
+        let _ = storage.zone_maps();
+        Ok(storage)
+    }
+
+    /// Load several Parquet files (or one with many row groups) in parallel
+    ///
+    /// Footers and row groups are decoded on a pool of threads sized by
+    /// `options`. A file that cannot be read, or whose columns differ from
+    /// the first readable file, is skipped and reported in
+    /// [`ParallelLoad::failed`] without affecting the others. See
+    /// [`parallel`] for details.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use trueno_db::storage::{ParallelLoadOptions, StorageEngine};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let parts = ["data/part-0.parquet", "data/part-1.parquet"];
+    /// let load = StorageEngine::load_parquet_parallel(&parts, &ParallelLoadOptions::default());
+    /// for (path, e) in &load.failed {
+    ///     eprintln!("skipped {}: {e}", path.display());
+    /// }
+    /// println!("loaded {} batches", load.storage.batches().len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "parquet-io")]
+    pub fn load_parquet_parallel<P: AsRef<Path>>(
+        paths: &[P],
+        options: &ParallelLoadOptions,
+    ) -> ParallelLoad {
+        parallel::load_parquet_parallel(paths, options)
+    }
+
+    /// Open a Parquet file as a lazy stream of ~128MB morsels
+    ///
+    /// Unlike [`load_parquet`](Self::load_parquet), nothing is read up front:
+    /// each morsel is decoded from its row group when the iterator reaches
+    /// it, so files larger than RAM can be scanned with bounded memory. Use
+    /// [`ParquetMorselReader::open`] for another morsel size.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use trueno_db::storage::StorageEngine;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut rows = 0;
+    /// for morsel in StorageEngine::open_parquet_streaming("data/events.parquet")? {
+    ///     rows += morsel?.num_rows();
+    /// }
+    /// println!("Scanned {rows} rows");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns error if the file cannot be opened or its footer parsed
+    #[cfg(feature = "parquet-io")]
+    pub fn open_parquet_streaming<P: AsRef<Path>>(path: P) -> Result<ParquetMorselReader> {
+        ParquetMorselReader::open(path, MORSEL_SIZE_BYTES)
+    }
+
+    /// Open a Parquet file as a lazy stream of morsels holding only `columns`
+    ///
+    /// The streaming counterpart of
+    /// [`load_parquet_with_projection`](Self::load_parquet_with_projection);
+    /// morsels are sized from the projected columns' bytes, so pruning also
+    /// makes each morsel cover more rows.
+    ///
+    /// # Errors
+    /// Returns error if the file cannot be opened, its footer parsed, or a
+    /// column is not in the file
+    #[cfg(feature = "parquet-io")]
+    pub fn open_parquet_streaming_with_projection<P: AsRef<Path>, S: AsRef<str>>(
+        path: P,
+        columns: &[S],
+    ) -> Result<ParquetMorselReader> {
+        ParquetMorselReader::open_with_projection(path, MORSEL_SIZE_BYTES, Some(columns))
+    }
+
+    /// Load table from Arrow IPC file (`.arrow` / `.feather` v2)
+    ///
+    /// # Errors
+    /// Returns error if file cannot be read or parsed
+    #[cfg(feature = "ipc-io")]
+    pub fn load_ipc<P: AsRef<Path>>(path: P) -> Result<Self> {
+        use arrow::ipc::reader::FileReader;
+        use std::fs::File;
+
+        let file = File::open(path.as_ref())
+            .map_err(|e| Error::StorageError(format!("Failed to open IPC file: {e}")))?;
+
+        let reader = FileReader::try_new(file, None)
+            .map_err(|e| Error::StorageError(format!("Failed to parse IPC file: {e}")))?;
+
+        let batches = reader
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| Error::StorageError(format!("Failed to read record batch: {e}")))?;
+
+        Ok(Self::new(batches))
+    }
+
+    /// Load table from an Arrow IPC file without copying its columns
+    ///
+    /// The file is memory-mapped and the batches reference the mapping
+    /// directly (see [`mmap`]), so loading costs the metadata only and
+    /// columns are paged in as queries touch them. Prefer this over
+    /// [`load_ipc`](Self::load_ipc) for large Feather files that are read
+    /// but never rewritten.
+    ///
+    /// # Safety
+    /// The file must not be truncated or modified while any batch of the
+    /// returned table is alive; reading one afterwards is undefined
+    /// behaviour. Replace such a file by writing a new one and renaming it
+    /// into place (as [`write_ipc`](Self::write_ipc) does).
+    ///
+    /// # Errors
+    /// Returns error if file cannot be mapped or parsed
+    #[cfg(feature = "ipc-mmap")]
+    #[allow(unsafe_code)]
+    pub unsafe fn load_ipc_mmap<P: AsRef<Path>>(path: P) -> Result<Self> {
+        // SAFETY: forwarded to our caller
+        Ok(Self::new(unsafe { mmap::load_ipc_mmap(path.as_ref())? }))
+    }
+
+    /// Write the table to an Arrow IPC file (`.arrow` / `.feather` v2)
+    ///
+    /// Readable by [`load_ipc`](Self::load_ipc) and by pyarrow
+    /// (`pyarrow.feather.read_table`) or pandas (`pandas.read_feather`).
+    /// Columns are written uncompressed, so the file can be memory-mapped.
+    /// The file is written to a temporary sibling and renamed into place, so
+    /// an existing file at `path` is replaced only once the new one is
+    /// complete.
+    ///
+    /// # Errors
+    /// Returns error if the table is empty (no schema) or the file cannot be
+    /// written
+    #[cfg(feature = "ipc-io")]
+    pub fn write_ipc<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        use arrow::ipc::writer::FileWriter;
+        use std::fs::{self, File};
+        use std::io::BufWriter;
+
+        let Some(first) = self.batches.first() else {
+            return Err(Error::InvalidInput("Cannot write an empty table to IPC".to_string()));
+        };
+        let path = path.as_ref();
+        let name = path.file_name().ok_or_else(|| {
+            Error::InvalidInput(format!("IPC path has no file name: {}", path.display()))
+        })?;
+        let tmp_path = path.with_file_name(format!(".{}.tmp", name.to_string_lossy()));
+
+        let write = || -> Result<()> {
+            let file = File::create(&tmp_path)?;
+            let mut writer = FileWriter::try_new(BufWriter::new(file), &first.schema())
+                .map_err(|e| Error::StorageError(format!("Failed to write IPC file: {e}")))?;
+            for batch in &self.batches {
+                writer
+                    .write(batch)
+                    .map_err(|e| Error::StorageError(format!("Failed to write IPC file: {e}")))?;
+            }
+            writer
+                .finish()
+                .map_err(|e| Error::StorageError(format!("Failed to write IPC file: {e}")))?;
+            let file = writer
+                .into_inner()
+                .map_err(|e| Error::StorageError(format!("Failed to write IPC file: {e}")))?
+                .into_inner()
+                .map_err(|e| Error::Io(e.into_error()))?;
+            file.sync_all()?;
+            Ok(())
+        };
+        if let Err(e) = write() {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Load table from CSV file with a header row (schema is inferred)
+    ///
+    /// # Errors
+    /// Returns error if file cannot be read or parsed
+    #[cfg(feature = "csv-io")]
+    pub fn load_csv<P: AsRef<Path>>(path: P) -> Result<Self> {
+        use arrow::csv::reader::Format;
+        use arrow::csv::ReaderBuilder;
+        use std::fs::File;
+        use std::io::{Seek, SeekFrom};
+        use std::sync::Arc;
+
+        let _span = tracing::debug_span!("load_csv", path = %path.as_ref().display()).entered();
+        let mut file = File::open(path.as_ref())
+            .map_err(|e| Error::StorageError(format!("Failed to open CSV file: {e}")))?;
+
+        let (schema, _) = Format::default()
+            .with_header(true)
+            .infer_schema(&mut file, None)
+            .map_err(|e| Error::StorageError(format!("Failed to infer CSV schema: {e}")))?;
+        file.seek(SeekFrom::Start(0))?;
+
+        let reader = ReaderBuilder::new(Arc::new(schema))
+            .with_header(true)
+            .build(file)
+            .map_err(|e| Error::StorageError(format!("Failed to create CSV reader: {e}")))?;
+
+        let batches = reader
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| Error::StorageError(format!("Failed to read record batch: {e}")))?;
+
+        Ok(Self::new(batches))
+    }
+
+    /// Load table from an Arrow IPC stream (e.g. stdin of a pipeline)
+    ///
+    /// Unlike [`load_ipc`](Self::load_ipc) this reads the streaming format,
+    /// which needs no footer and so can be consumed without seeking.
+    ///
+    /// # Errors
+    /// Returns error if the stream cannot be read or parsed
+    #[cfg(feature = "ipc-io")]
+    pub fn read_ipc_stream<R: std::io::Read>(reader: R) -> Result<Self> {
+        use arrow::ipc::reader::StreamReader;
+
+        let reader = StreamReader::try_new(reader, None)
+            .map_err(|e| Error::StorageError(format!("Failed to parse IPC stream: {e}")))?;
+
+        let batches = reader
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| Error::StorageError(format!("Failed to read record batch: {e}")))?;
+
+        Ok(Self::new(batches))
+    }
+
+    /// Load table from CSV with a header row read from any reader (schema is inferred)
+    ///
+    /// Inference needs a second pass over the data, so the input is buffered
+    /// in memory first; prefer [`load_csv`](Self::load_csv) for files.
+    ///
+    /// # Errors
+    /// Returns error if the input cannot be read or parsed
+    #[cfg(feature = "csv-io")]
+    pub fn read_csv<R: std::io::Read>(mut reader: R) -> Result<Self> {
+        use arrow::csv::reader::Format;
+        use arrow::csv::ReaderBuilder;
+        use std::io::Cursor;
+        use std::sync::Arc;
+
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        let (schema, _) = Format::default()
+            .with_header(true)
+            .infer_schema(Cursor::new(&data), None)
+            .map_err(|e| Error::StorageError(format!("Failed to infer CSV schema: {e}")))?;
+
+        let reader = ReaderBuilder::new(Arc::new(schema))
+            .with_header(true)
+            .build(Cursor::new(data))
+            .map_err(|e| Error::StorageError(format!("Failed to create CSV reader: {e}")))?;
+
+        let batches = reader
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| Error::StorageError(format!("Failed to read record batch: {e}")))?;
+
+        Ok(Self::new(batches))
+    }
+
+    /// Write the table to a Parquet file
+    ///
+    /// Compression, row group size and dictionary settings come from
+    /// `options`; statistics-driven settings are derived from all batches.
+    /// The file is written to a temporary sibling and renamed into place
+    /// (see [`ParquetSink`]), so an existing file at `path` is replaced only
+    /// once the new one is complete.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use trueno_db::storage::{ColumnCodec, ParquetWriteOptions, StorageEngine};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let storage = StorageEngine::load_parquet("data/events.parquet")?;
+    /// let options = ParquetWriteOptions::new()
+    ///     .with_default_codec(ColumnCodec::Zstd(3))
+    ///     .with_max_row_group_size(64 * 1024);
+    /// let summary = storage.write_parquet("data/events.zstd.parquet", &options)?;
+    /// println!("{} rows in {} row groups", summary.rows, summary.row_groups);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns error if the table is empty (no schema) or the file cannot be
+    /// written
+    #[cfg(feature = "parquet-io")]
+    pub fn write_parquet<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: &ParquetWriteOptions,
+    ) -> Result<ParquetWriteSummary> {
+        if self.batches.is_empty() {
+            return Err(Error::InvalidInput("Cannot write an empty table to Parquet".to_string()));
+        }
+        let mut sink = ParquetSink::create(path, options.clone())?;
+        sink.open(&self.batches)?;
+        for batch in &self.batches {
+            sink.write(batch)?;
+        }
+        sink.finish()
     }
 
     /// Get all record batches
@@ -76,12 +708,147 @@ impl StorageEngine {
         &self.batches
     }
 
+    /// Zone maps of each batch, in batch order (see [`zone_map`])
+    ///
+    /// Built on first call (or on Parquet load) and kept up to date as
+    /// batches are appended.
+    #[must_use]
+    pub fn zone_maps(&self) -> &[Vec<ZoneMap>] {
+        self.zone_maps.get_or_init(|| self.batches.iter().map(ZoneMap::build).collect())
+    }
+
+    /// Table version: the number of appends and compactions so far
+    #[must_use]
+    pub const fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Immutable view of the current version
+    ///
+    /// Shares the batches (no copy); appends and compactions after this
+    /// call are not visible through it.
+    #[must_use]
+    pub fn snapshot(&self) -> StorageSnapshot {
+        let mut pinned = self.with_batches(self.batches.clone());
+        pinned.zone_maps = self.zone_maps.clone();
+        StorageSnapshot::new(pinned)
+    }
+
+    /// Merge runs of adjacent batches smaller than `target_rows` into
+    /// batches of up to `target_rows` rows (vacuum after many small appends)
+    ///
+    /// Row order is preserved. The version is bumped only if batches were
+    /// merged; snapshots taken before keep the old batches.
+    ///
+    /// # Errors
+    /// Returns error if batches cannot be concatenated
+    pub fn compact(&mut self, target_rows: usize) -> Result<CompactionSummary> {
+        let batches_before = self.batches.len();
+        let compacted = snapshot::compact_batches(&self.batches, target_rows)?;
+        let summary = CompactionSummary { batches_before, batches_after: compacted.len() };
+        if summary.merged() {
+            self.batches = compacted;
+            self.zone_maps = OnceLock::new();
+            self.version += 1;
+        }
+        Ok(summary)
+    }
+
+    /// Sort the whole table by `cluster_by` (ascending, NULLs last) and
+    /// re-split it into batches of `target_rows` rows
+    ///
+    /// Clustering narrows each batch's and zone's range of the column, so
+    /// filters on it skip more zones. Row order changes and the whole table
+    /// is rewritten; the version is bumped unless the table is empty.
+    ///
+    /// # Errors
+    /// Returns error if `cluster_by` does not exist, or batches have
+    /// different schemas or cannot be sorted
+    pub fn compact_by(
+        &mut self,
+        target_rows: usize,
+        cluster_by: &str,
+    ) -> Result<CompactionSummary> {
+        let batches_before = self.batches.len();
+        if batches_before > 0 {
+            self.batches = snapshot::cluster_batches(&self.batches, target_rows, cluster_by)?;
+            self.zone_maps = OnceLock::new();
+            self.version += 1;
+        }
+        Ok(CompactionSummary { batches_before, batches_after: self.batches.len() })
+    }
+
+    /// Compact automatically once an append leaves more than
+    /// `policy.max_batches` batches smaller than `policy.target_rows`
+    #[must_use]
+    pub fn with_auto_compaction(mut self, policy: CompactionPolicy) -> Self {
+        self.compaction = Some(policy);
+        self
+    }
+
+    /// Automatic compaction policy, if set
+    #[must_use]
+    pub const fn compaction_policy(&self) -> Option<&CompactionPolicy> {
+        self.compaction.as_ref()
+    }
+
+    /// Run the automatic compaction if it is due
+    fn auto_compact(&mut self) -> Result<()> {
+        let Some(policy) = self.compaction.as_ref().filter(|policy| policy.is_due(&self.batches))
+        else {
+            return Ok(());
+        };
+        match policy.cluster_by.clone() {
+            Some(column) => self.compact_by(policy.target_rows, &column)?,
+            None => self.compact(policy.target_rows)?,
+        };
+        Ok(())
+    }
+
+    /// Bytes held in memory by all batches
+    #[must_use]
+    pub fn memory_size(&self) -> usize {
+        self.batches.iter().map(RecordBatch::get_array_memory_size).sum()
+    }
+
+    /// Compressed copy of this table, decompressed on scan (see [`compression`])
+    ///
+    /// # Errors
+    /// Returns error if a column cannot be encoded
+    pub fn compress(&self) -> Result<CompressedTable> {
+        CompressedTable::compress(self)
+    }
+
+    /// Copy of this engine's settings holding `batches`
+    fn with_batches(&self, batches: Vec<RecordBatch>) -> Self {
+        Self {
+            batches,
+            dictionary_threshold: self.dictionary_threshold,
+            dictionary_columns: self.dictionary_columns.clone(),
+            provenance: self.provenance,
+            next_batch_id: self.next_batch_id,
+            zone_maps: OnceLock::new(),
+            version: self.version,
+            compaction: self.compaction.clone(),
+            schema_evolution: self.schema_evolution.clone(),
+        }
+    }
+
     /// Create iterator over morsels (128MB chunks)
     #[must_use]
     pub fn morsels(&self) -> MorselIterator<'_> {
         MorselIterator::new(&self.batches)
     }
 
+    /// Create iterator over morsels of about `bytes` each (at least one row)
+    ///
+    /// [`Database`](crate::Database) uses this with the size set by
+    /// `DatabaseBuilder::morsel_size_mb`.
+    #[must_use]
+    pub fn morsels_with_size(&self, bytes: usize) -> MorselIterator<'_> {
+        MorselIterator::with_morsel_bytes(&self.batches, bytes)
+    }
+
     /// Append batches to storage (OLAP-optimized)
     ///
     /// **WARNING**: This is the ONLY supported write operation.
@@ -118,24 +885,126 @@ impl StorageEngine {
     ///
     /// # Errors
     ///
-    /// Returns error if batch schema doesn't match existing batches
+    /// Returns error if batch schema doesn't match existing batches (and
+    /// [schema evolution](Self::with_schema_evolution) does not allow the
+    /// difference), or if an automatic compaction (see
+    /// [`with_auto_compaction`](Self::with_auto_compaction)) fails; the batch
+    /// stays appended in that case
     pub fn append_batch(&mut self, batch: RecordBatch) -> Result<()> {
-        // Validate schema compatibility
+        self.append_tagged(batch, None)?;
+        self.version += 1;
+        self.auto_compact()
+    }
+
+    /// Append several batches as one version (a bulk load)
+    ///
+    /// Either every batch is appended or, if one is rejected, none is: a
+    /// [`snapshot`](Self::snapshot) never holds part of the load.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a batch schema doesn't match the table's
+    pub fn append_batches(&mut self, batches: impl IntoIterator<Item = RecordBatch>) -> Result<()> {
+        let (len, next_batch_id) = (self.batches.len(), self.next_batch_id);
+        let dictionary_columns = self.dictionary_columns.clone();
+        // Schema evolution may rewrite the batches already held
+        let evolved_from = self.schema_evolution.is_some().then(|| self.batches.clone());
+        for batch in batches {
+            if let Err(e) = self.append_tagged(batch, None) {
+                if let Some(batches) = evolved_from {
+                    self.batches = batches;
+                    self.zone_maps = OnceLock::new();
+                } else {
+                    self.batches.truncate(len);
+                    if let Some(zone_maps) = self.zone_maps.get_mut() {
+                        zone_maps.truncate(len);
+                    }
+                }
+                self.next_batch_id = next_batch_id;
+                self.dictionary_columns = dictionary_columns;
+                return Err(e);
+            }
+        }
+        self.version += 1;
+        self.auto_compact()
+    }
+
+    /// Append a batch, recording `source_file` in the `_source_file` column
+    ///
+    /// Identical to [`append_batch`](Self::append_batch) when provenance is
+    /// not enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if batch schema doesn't match existing batches
+    pub fn append_batch_from(&mut self, batch: RecordBatch, source_file: &str) -> Result<()> {
+        self.append_tagged(batch, Some(source_file))?;
+        self.version += 1;
+        self.auto_compact()
+    }
+
+    fn append_tagged(&mut self, batch: RecordBatch, source_file: Option<&str>) -> Result<()> {
+        let batch = if self.provenance {
+            self.tag(&batch, source_file, chrono::Utc::now().timestamp_micros())?
+        } else {
+            batch
+        };
+        let batch = match &self.schema_evolution {
+            Some(evolution) => {
+                let (batch, rewritten) = evolution::evolve(&mut self.batches, batch, evolution)?;
+                if rewritten {
+                    self.zone_maps = OnceLock::new();
+                }
+                batch
+            }
+            None => batch,
+        };
+
+        if self.dictionary_threshold.is_none() && self.dictionary_columns.is_empty() {
+            // Validate schema compatibility
+            if !self.batches.is_empty() {
+                let existing_schema = self.batches[0].schema();
+                if batch.schema() != existing_schema {
+                    return Err(Error::StorageError(format!(
+                        "Schema mismatch: expected {:?}, got {:?}",
+                        existing_schema,
+                        batch.schema()
+                    )));
+                }
+            }
+
+            self.push_batch(batch);
+            return Ok(());
+        }
+
+        // Dictionary encoding in use: compare logical (decoded) schemas
         if !self.batches.is_empty() {
-            let existing_schema = self.batches[0].schema();
-            if batch.schema() != existing_schema {
+            let existing_schema = dictionary::logical_schema(&self.batches[0].schema());
+            let new_schema = dictionary::logical_schema(&batch.schema());
+            if new_schema != existing_schema {
                 return Err(Error::StorageError(format!(
-                    "Schema mismatch: expected {:?}, got {:?}",
-                    existing_schema,
-                    batch.schema()
+                    "Schema mismatch: expected {existing_schema:?}, got {new_schema:?}"
                 )));
             }
+        } else if let Some(threshold) = self.dictionary_threshold {
+            let stats = dictionary::compute_statistics(std::slice::from_ref(&batch))?;
+            self.dictionary_columns = dictionary::select_dictionary_columns(&stats, threshold);
         }
 
-        self.batches.push(batch);
+        let batch = dictionary::apply_encoding(&batch, &self.dictionary_columns)?;
+        self.push_batch(batch);
         Ok(())
     }
 
+    /// Store an ingested batch, extending the zone maps if they are built
+    fn push_batch(&mut self, batch: RecordBatch) {
+        metrics::count(Counter::RowsAppended, batch.num_rows());
+        if let Some(zone_maps) = self.zone_maps.get_mut() {
+            zone_maps.push(ZoneMap::build(&batch));
+        }
+        self.batches.push(batch);
+    }
+
     /// **DEPRECATED**: Single-row update not supported
     ///
     /// Trueno-DB is OLAP-only (columnar storage). Use [`append_batch`](Self::append_batch) instead.
@@ -199,14 +1068,22 @@ pub struct MorselIterator<'a> {
 impl<'a> MorselIterator<'a> {
     /// Create new morsel iterator
     fn new(batches: &'a [RecordBatch]) -> Self {
-        // Calculate morsel size based on first batch
-        let morsel_rows = batches.first().map_or(0, Self::calculate_morsel_rows);
+        Self::with_morsel_bytes(batches, MORSEL_SIZE_BYTES)
+    }
+
+    /// Create a morsel iterator with `morsel_bytes` per morsel
+    fn with_morsel_bytes(batches: &'a [RecordBatch], morsel_bytes: usize) -> Self {
+        // Calculate morsel size based on first non-empty batch
+        let morsel_rows = batches
+            .iter()
+            .find(|batch| batch.num_rows() > 0)
+            .map_or(0, |batch| Self::calculate_morsel_rows(batch, morsel_bytes));
 
         Self { batches, current_batch_idx: 0, current_offset: 0, morsel_rows }
     }
 
-    /// Calculate how many rows fit in a 128MB morsel
-    fn calculate_morsel_rows(batch: &RecordBatch) -> usize {
+    /// Calculate how many rows fit in a morsel (at least one)
+    fn calculate_morsel_rows(batch: &RecordBatch, morsel_bytes: usize) -> usize {
         let num_rows = batch.num_rows();
         if num_rows == 0 {
             return 0;
@@ -219,7 +1096,7 @@ impl<'a> MorselIterator<'a> {
             return num_rows; // Avoid division by zero
         }
 
-        MORSEL_SIZE_BYTES / bytes_per_row
+        (morsel_bytes / bytes_per_row).max(1)
     }
 }
 
@@ -303,6 +1180,31 @@ impl GpuTransferQueue {
     pub fn sender(&self) -> tokio::sync::mpsc::Sender<RecordBatch> {
         self.sender.clone()
     }
+
+    /// Dequeue a record batch and upload its fixed-width columns to the GPU
+    ///
+    /// Uploads go through the engine's buffer pool (see
+    /// [`GpuEngine::upload_column`](crate::gpu::GpuEngine::upload_column)):
+    /// once the buffers of the previous batch are dropped, the next batch of
+    /// the same shape reuses them instead of allocating. Returns one entry per
+    /// column, `None` for columns that are not fixed-width (e.g. strings).
+    ///
+    /// # Returns
+    /// Next batch and its device buffers if available, None if queue is
+    /// empty and closed
+    #[cfg(feature = "gpu")]
+    pub async fn dequeue_to_gpu(
+        &mut self,
+        engine: &crate::gpu::GpuEngine,
+    ) -> Option<(RecordBatch, Vec<Option<crate::gpu::buffers::PooledBuffer>>)> {
+        let batch = self.receiver.recv().await?;
+        let columns = batch
+            .columns()
+            .iter()
+            .map(|column| engine.upload_column(column.as_ref()).ok())
+            .collect();
+        Some((batch, columns))
+    }
 }
 
 #[cfg(feature = "tokio")]
@@ -415,6 +1317,101 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("Single-row updates not supported"));
     }
 
+    #[test]
+    fn test_provenance_tags_loaded_and_appended_batches() {
+        use arrow::array::{Array, UInt64Array};
+
+        let mut storage = StorageEngine::new(vec![create_test_batch(10)])
+            .with_provenance(Some("events.parquet"))
+            .unwrap();
+        storage.append_batch_from(create_test_batch(5), "more.parquet").unwrap();
+        storage.append_batch(create_test_batch(2)).unwrap();
+
+        let batches = storage.batches();
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches[0].num_columns(), 6);
+        for (idx, name) in PROVENANCE_COLUMNS.iter().enumerate() {
+            assert_eq!(batches[0].schema().field(3 + idx).name(), name);
+        }
+
+        let source =
+            |b: &RecordBatch| b.column(3).as_any().downcast_ref::<StringArray>().unwrap().clone();
+        assert_eq!(source(&batches[0]).value(0), "events.parquet");
+        assert_eq!(source(&batches[1]).value(4), "more.parquet");
+        assert_eq!(source(&batches[2]).null_count(), 2);
+
+        let batch_ids: Vec<u64> = batches
+            .iter()
+            .map(|b| b.column(4).as_any().downcast_ref::<UInt64Array>().unwrap().value(0))
+            .collect();
+        assert_eq!(batch_ids, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_provenance_disabled_by_default() {
+        let mut storage = StorageEngine::new(vec![]);
+        storage.append_batch_from(create_test_batch(3), "ignored.parquet").unwrap();
+
+        assert!(!storage.has_provenance());
+        assert_eq!(storage.batches()[0].num_columns(), 3);
+    }
+
+    #[test]
+    fn test_append_batch_auto_dictionary_encoding() {
+        let mut storage = StorageEngine::new(vec![]).with_dictionary_threshold(0.5);
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("region", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3, 4])),
+                Arc::new(StringArray::from(vec!["eu", "us", "eu", "us"])),
+            ],
+        )
+        .unwrap();
+
+        storage.append_batch(batch.clone()).unwrap();
+        assert_eq!(storage.dictionary_columns(), &[1]);
+        assert!(dictionary::is_string_dictionary(
+            storage.batches()[0].schema().field(1).data_type()
+        ));
+
+        // Plain Utf8 batches with the same logical schema are still accepted
+        storage.append_batch(batch).unwrap();
+        assert_eq!(storage.batches().len(), 2);
+        assert_eq!(storage.batches()[0].schema(), storage.batches()[1].schema());
+    }
+
+    #[test]
+    fn test_dictionary_encode_named_column() {
+        let mut storage = StorageEngine::new(vec![create_test_batch(10)]);
+        assert!(storage.dictionary_encode("missing").is_err());
+        assert!(storage.dictionary_encode("id").is_err());
+
+        storage.dictionary_encode("name").unwrap();
+        assert_eq!(storage.dictionary_columns(), &[2]);
+        let schema = storage.batches()[0].schema();
+        assert!(dictionary::is_string_dictionary(schema.field(2).data_type()));
+
+        // Later plain batches are accepted and encoded
+        storage.append_batch(create_test_batch(5)).unwrap();
+        assert_eq!(storage.batches()[1].schema(), schema);
+    }
+
+    #[test]
+    fn test_analyze_encodes_low_cardinality_columns() {
+        let batch = create_test_batch(100);
+        let mut storage = StorageEngine::new(vec![batch]).with_dictionary_threshold(0.1);
+
+        // "name" is unique per row: stays Utf8
+        let stats = storage.analyze().unwrap();
+        assert_eq!(stats[2].distinct_count, Some(100));
+        assert!(storage.dictionary_columns().is_empty());
+        assert_eq!(storage.batches()[0].schema().field(2).data_type(), &DataType::Utf8);
+    }
+
     #[test]
     fn test_morsel_iterator_multiple_batches() {
         let batch1 = create_test_batch(500);
@@ -0,0 +1,479 @@
+//! Parallel Parquet loading across files and row groups
+//!
+//! [`StorageEngine::load_parquet`](super::StorageEngine::load_parquet) reads
+//! one file on one thread. [`load_parquet_parallel`] spreads the work of a
+//! multi-file (or multi-row-group) dataset over a scoped thread pool in two
+//! phases:
+//!
+//! 1. **Footers** — every file's footer is read concurrently
+//!    ([`ParallelLoadOptions::io_concurrency`] threads). The first readable
+//!    file, in input order, fixes the table schema; files whose columns
+//!    differ are rejected.
+//! 2. **Row groups** — each (file, row group) pair is an independent decode
+//!    task ([`ParallelLoadOptions::decode_concurrency`] threads).
+//!
+//! Failures are isolated per file: a missing, corrupt or mismatched file is
+//! reported in [`ParallelLoad::failed`] and none of its rows are appended,
+//! while every other file still loads. A file's batches are appended only
+//! once all of its row groups decoded, either in input order
+//! ([`AppendOrder::Ordered`]) or as files complete
+//! ([`AppendOrder::Unordered`]).
+//!
+//! Toyota Way: Heijunka (level the load across cores)
+
+use super::StorageEngine;
+use crate::{Error, Result};
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::arrow_reader::{
+    ArrowReaderMetadata, ArrowReaderOptions, ParquetRecordBatchReaderBuilder,
+};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+
+/// Order in which loaded files' batches are appended
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AppendOrder {
+    /// Input order: file by file, row groups in file order. The resulting
+    /// table is identical to loading the files serially.
+    #[default]
+    Ordered,
+    /// Completion order: each file is appended (row groups still in file
+    /// order) as soon as its last row group decodes, so a slow file never
+    /// holds finished ones back. The file order varies run to run.
+    Unordered,
+}
+
+/// Concurrency and ordering for [`load_parquet_parallel`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParallelLoadOptions {
+    /// Threads reading footers (IO bound; may exceed the core count)
+    pub io_concurrency: usize,
+    /// Threads decoding row groups (CPU bound)
+    pub decode_concurrency: usize,
+    /// Append order of loaded files
+    pub order: AppendOrder,
+}
+
+impl Default for ParallelLoadOptions {
+    /// One thread per available core for both phases, ordered appends
+    fn default() -> Self {
+        let cores = std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get);
+        Self { io_concurrency: cores, decode_concurrency: cores, order: AppendOrder::Ordered }
+    }
+}
+
+/// Outcome of [`load_parquet_parallel`]
+pub struct ParallelLoad {
+    /// Rows of every file that loaded
+    pub storage: StorageEngine,
+    /// Files that loaded, in the order they were appended
+    pub loaded: Vec<PathBuf>,
+    /// Files that were skipped, in input order, with the reason
+    pub failed: Vec<(PathBuf, Error)>,
+}
+
+impl ParallelLoad {
+    /// Whether every input file loaded
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// The loaded table, or the first failure if any file was skipped
+    ///
+    /// # Errors
+    /// Returns the first (in input order) file's error, naming the file
+    pub fn into_result(self) -> Result<StorageEngine> {
+        match self.failed.into_iter().next() {
+            None => Ok(self.storage),
+            Some((path, e)) => {
+                Err(Error::StorageError(format!("Failed to load {}: {e}", path.display())))
+            }
+        }
+    }
+}
+
+/// Load `paths` into one table using a pool of threads
+///
+/// See the [module docs](self) for the phases and failure semantics. Never
+/// fails as a whole: per-file errors are collected in
+/// [`ParallelLoad::failed`]; use [`ParallelLoad::into_result`] to treat any
+/// failure as fatal.
+pub fn load_parquet_parallel<P: AsRef<Path>>(
+    paths: &[P],
+    options: &ParallelLoadOptions,
+) -> ParallelLoad {
+    let paths: Vec<PathBuf> = paths.iter().map(|p| p.as_ref().to_path_buf()).collect();
+
+    let mut footers: Vec<Option<Result<ArrowReaderMetadata>>> =
+        paths.iter().map(|_| None).collect();
+    run_parallel(
+        paths.len(),
+        options.io_concurrency,
+        |i| read_footer(&paths[i]),
+        |i, footer| footers[i] = Some(footer),
+    );
+
+    let mut reference: Option<SchemaRef> = None;
+    let mut slots: Vec<Slot> = footers
+        .into_iter()
+        .map(|footer| {
+            let footer = footer.expect("every footer task runs");
+            let checked = footer.and_then(|meta| {
+                let schema = meta.schema();
+                if let Some(expected) = &reference {
+                    if expected.fields() != schema.fields() {
+                        return Err(Error::StorageError(format!(
+                            "Schema mismatch: expected {expected:?}, found {schema:?}"
+                        )));
+                    }
+                } else {
+                    reference = Some(schema.clone());
+                }
+                Ok(meta)
+            });
+            Slot::new(checked)
+        })
+        .collect();
+
+    let metadata: Vec<Option<ArrowReaderMetadata>> =
+        slots.iter().map(|slot| slot.metadata.clone()).collect();
+    let tasks: Vec<(usize, usize)> = metadata
+        .iter()
+        .enumerate()
+        .filter_map(|(file, meta)| meta.as_ref().map(|meta| (file, meta)))
+        .flat_map(|(file, meta)| {
+            (0..meta.metadata().num_row_groups()).map(move |row_group| (file, row_group))
+        })
+        .collect();
+
+    let mut appender = Appender::new(reference, options.order, paths.len());
+    for file in 0..slots.len() {
+        if slots[file].is_done() {
+            appender.finish(file, &mut slots);
+        }
+    }
+    run_parallel(
+        tasks.len(),
+        options.decode_concurrency,
+        |t| {
+            let (file, row_group) = tasks[t];
+            let meta = metadata[file].clone().expect("tasks only cover readable files");
+            decode_row_group(&paths[file], meta, row_group)
+        },
+        |t, decoded| {
+            let (file, row_group) = tasks[t];
+            slots[file].record(row_group, decoded);
+            if slots[file].is_done() {
+                appender.finish(file, &mut slots);
+            }
+        },
+    );
+
+    appender.into_load(&paths, slots)
+}
+
+/// Per-file decode progress
+struct Slot {
+    /// Footer, or `None` if it could not be read or checked
+    metadata: Option<ArrowReaderMetadata>,
+    /// Decoded batches per row group
+    groups: Vec<Option<Vec<RecordBatch>>>,
+    /// Row groups still decoding
+    remaining: usize,
+    /// First error seen for this file
+    error: Option<Error>,
+}
+
+impl Slot {
+    fn new(footer: Result<ArrowReaderMetadata>) -> Self {
+        match footer {
+            Ok(meta) => {
+                let row_groups = meta.metadata().num_row_groups();
+                Self {
+                    metadata: Some(meta),
+                    groups: (0..row_groups).map(|_| None).collect(),
+                    remaining: row_groups,
+                    error: None,
+                }
+            }
+            Err(e) => Self { metadata: None, groups: Vec::new(), remaining: 0, error: Some(e) },
+        }
+    }
+
+    fn record(&mut self, row_group: usize, decoded: Result<Vec<RecordBatch>>) {
+        self.remaining -= 1;
+        match decoded {
+            Ok(batches) if self.error.is_none() => self.groups[row_group] = Some(batches),
+            Ok(_) => {}
+            Err(e) => {
+                // Keep the first error; the file's other row groups are moot
+                self.error.get_or_insert(e);
+                self.groups.clear();
+            }
+        }
+    }
+
+    const fn is_done(&self) -> bool {
+        self.remaining == 0
+    }
+}
+
+/// Appends finished files in the configured order
+struct Appender {
+    reference: Option<SchemaRef>,
+    order: AppendOrder,
+    batches: Vec<RecordBatch>,
+    /// Indices of appended files, in append order
+    loaded: Vec<usize>,
+    /// Files finished but not yet appended (ordered mode)
+    finished: Vec<bool>,
+    /// Next file to append (ordered mode)
+    cursor: usize,
+}
+
+impl Appender {
+    fn new(reference: Option<SchemaRef>, order: AppendOrder, files: usize) -> Self {
+        Self {
+            reference,
+            order,
+            batches: Vec::new(),
+            loaded: Vec::new(),
+            finished: vec![false; files],
+            cursor: 0,
+        }
+    }
+
+    fn finish(&mut self, file: usize, slots: &mut [Slot]) {
+        match self.order {
+            AppendOrder::Unordered => self.append(file, &mut slots[file]),
+            AppendOrder::Ordered => {
+                self.finished[file] = true;
+                while self.cursor < self.finished.len() && self.finished[self.cursor] {
+                    self.append(self.cursor, &mut slots[self.cursor]);
+                    self.cursor += 1;
+                }
+            }
+        }
+    }
+
+    fn append(&mut self, file: usize, slot: &mut Slot) {
+        if slot.error.is_some() {
+            return;
+        }
+        let Some(reference) = &self.reference else { return };
+        for batch in std::mem::take(&mut slot.groups).into_iter().flatten().flatten() {
+            // Field-equal schemas may still differ in metadata; one table,
+            // one schema
+            let batch = RecordBatch::try_new(reference.clone(), batch.columns().to_vec())
+                .expect("columns match the reference schema");
+            self.batches.push(batch);
+        }
+        self.loaded.push(file);
+    }
+
+    fn into_load(self, paths: &[PathBuf], slots: Vec<Slot>) -> ParallelLoad {
+        let failed = slots
+            .into_iter()
+            .zip(paths)
+            .filter_map(|(slot, path)| slot.error.map(|e| (path.clone(), e)))
+            .collect();
+        ParallelLoad {
+            storage: StorageEngine::new(self.batches),
+            loaded: self.loaded.into_iter().map(|i| paths[i].clone()).collect(),
+            failed,
+        }
+    }
+}
+
+fn read_footer(path: &Path) -> Result<ArrowReaderMetadata> {
+    let file = File::open(path)
+        .map_err(|e| Error::StorageError(format!("Failed to open Parquet file: {e}")))?;
+    ArrowReaderMetadata::load(&file, ArrowReaderOptions::new())
+        .map_err(|e| Error::StorageError(format!("Failed to parse Parquet file: {e}")))
+}
+
+fn decode_row_group(
+    path: &Path,
+    meta: ArrowReaderMetadata,
+    row_group: usize,
+) -> Result<Vec<RecordBatch>> {
+    let file = File::open(path)
+        .map_err(|e| Error::StorageError(format!("Failed to open Parquet file: {e}")))?;
+    let reader = ParquetRecordBatchReaderBuilder::new_with_metadata(file, meta)
+        .with_row_groups(vec![row_group])
+        .build()
+        .map_err(|e| Error::StorageError(format!("Failed to create Parquet reader: {e}")))?;
+    reader
+        .map(|batch| {
+            batch.map_err(|e| Error::StorageError(format!("Failed to read record batch: {e}")))
+        })
+        .collect()
+}
+
+/// Run `tasks` indexed tasks on up to `threads` scoped workers
+///
+/// Results are handed to `sink` on the calling thread as they complete, so
+/// the sink needs no synchronization.
+fn run_parallel<T, F, S>(tasks: usize, threads: usize, task: F, mut sink: S)
+where
+    T: Send,
+    F: Fn(usize) -> T + Sync,
+    S: FnMut(usize, T),
+{
+    let next = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel();
+    std::thread::scope(|scope| {
+        for _ in 0..threads.clamp(1, tasks.max(1)) {
+            let tx = tx.clone();
+            let (next, task) = (&next, &task);
+            scope.spawn(move || loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= tasks || tx.send((i, task(i))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(tx);
+        for (i, result) in rx {
+            sink(i, result);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Int32Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use parquet::arrow::ArrowWriter;
+    use parquet::file::properties::WriterProperties;
+    use std::sync::Arc;
+
+    /// Write `ids` to `name` in row groups of `group_rows`
+    fn write_ids(dir: &Path, name: &str, ids: std::ops::Range<i32>, group_rows: usize) -> PathBuf {
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+        let batch =
+            RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from_iter_values(ids))])
+                .unwrap();
+        let path = dir.join(name);
+        let props = WriterProperties::builder().set_max_row_group_size(group_rows).build();
+        let mut writer =
+            ArrowWriter::try_new(File::create(&path).unwrap(), schema, Some(props)).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        path
+    }
+
+    fn ids(storage: &StorageEngine) -> Vec<i32> {
+        storage
+            .batches()
+            .iter()
+            .flat_map(|b| {
+                b.column(0).as_any().downcast_ref::<Int32Array>().unwrap().values().to_vec()
+            })
+            .collect()
+    }
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_ordered_load_matches_serial_order() {
+        let dir = scratch("trueno_test_parallel_ordered");
+        let paths: Vec<_> = (0..5)
+            .map(|f| write_ids(&dir, &format!("part-{f}.parquet"), f * 100..(f + 1) * 100, 30))
+            .collect();
+
+        let options = ParallelLoadOptions { decode_concurrency: 3, ..Default::default() };
+        let load = load_parquet_parallel(&paths, &options);
+
+        assert!(load.is_complete());
+        assert_eq!(load.loaded, paths);
+        assert_eq!(ids(&load.storage), (0..500).collect::<Vec<_>>());
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_unordered_load_keeps_files_contiguous() {
+        let dir = scratch("trueno_test_parallel_unordered");
+        let paths: Vec<_> = (0..4)
+            .map(|f| write_ids(&dir, &format!("part-{f}.parquet"), f * 50..(f + 1) * 50, 20))
+            .collect();
+
+        let options = ParallelLoadOptions {
+            io_concurrency: 4,
+            decode_concurrency: 4,
+            order: AppendOrder::Unordered,
+        };
+        let load = load_parquet_parallel(&paths, &options);
+
+        assert!(load.is_complete());
+        let mut loaded = load.loaded.clone();
+        loaded.sort();
+        assert_eq!(loaded, paths);
+
+        // Each file's rows stay together and in row-group order
+        let expected: Vec<i32> = load
+            .loaded
+            .iter()
+            .flat_map(|path| {
+                let f: i32 = paths.iter().position(|p| p == path).unwrap().try_into().unwrap();
+                f * 50..(f + 1) * 50
+            })
+            .collect();
+        assert_eq!(ids(&load.storage), expected);
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_bad_files_are_isolated() {
+        let dir = scratch("trueno_test_parallel_isolation");
+        let good_a = write_ids(&dir, "a.parquet", 0..10, 4);
+        let corrupt = dir.join("corrupt.parquet");
+        std::fs::write(&corrupt, b"not a parquet file").unwrap();
+        let missing = dir.join("missing.parquet");
+        let good_b = write_ids(&dir, "b.parquet", 10..20, 4);
+
+        // Same rows, different column name
+        let schema = Arc::new(Schema::new(vec![Field::new("name", DataType::Utf8, false)]));
+        let batch =
+            RecordBatch::try_new(schema.clone(), vec![Arc::new(StringArray::from(vec!["x"]))])
+                .unwrap();
+        let mismatched = dir.join("mismatched.parquet");
+        let mut writer =
+            ArrowWriter::try_new(File::create(&mismatched).unwrap(), schema, None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let paths =
+            [good_a.clone(), corrupt.clone(), missing.clone(), mismatched.clone(), good_b.clone()];
+        let load = load_parquet_parallel(&paths, &ParallelLoadOptions::default());
+
+        assert!(!load.is_complete());
+        assert_eq!(load.loaded, vec![good_a, good_b]);
+        let failed: Vec<_> = load.failed.iter().map(|(p, _)| p.clone()).collect();
+        assert_eq!(failed, vec![corrupt.clone(), missing, mismatched]);
+        assert!(load.failed[2].1.to_string().contains("Schema mismatch"));
+        assert_eq!(ids(&load.storage), (0..20).collect::<Vec<_>>());
+
+        let err = load.into_result().err().unwrap();
+        assert!(err.to_string().contains(&corrupt.display().to_string()));
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_empty_input_loads_nothing() {
+        let load = load_parquet_parallel::<PathBuf>(&[], &ParallelLoadOptions::default());
+        assert!(load.is_complete());
+        assert!(load.storage.batches().is_empty());
+        assert!(load.into_result().is_ok());
+    }
+}
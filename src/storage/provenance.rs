@@ -0,0 +1,122 @@
+//! Row-level provenance system columns
+//!
+//! When enabled on a [`StorageEngine`](super::StorageEngine), every ingested
+//! batch gets three extra columns so results over combined datasets can be
+//! traced back to where the rows came from:
+//!
+//! - `_source_file` (`Utf8`, nullable): file the rows were loaded from
+//! - `_batch_id` (`UInt64`): sequence number of the ingested batch
+//! - `_ingest_ts` (`Timestamp(µs, UTC)`): when the batch was ingested
+
+use crate::{Error, Result};
+use arrow::array::{ArrayRef, StringArray, TimestampMicrosecondArray, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use std::sync::Arc;
+
+/// Source file system column
+pub const SOURCE_FILE_COLUMN: &str = "_source_file";
+
+/// Batch id system column
+pub const BATCH_ID_COLUMN: &str = "_batch_id";
+
+/// Ingest timestamp system column
+pub const INGEST_TS_COLUMN: &str = "_ingest_ts";
+
+/// All provenance system column names, in the order they are appended
+pub const PROVENANCE_COLUMNS: [&str; 3] = [SOURCE_FILE_COLUMN, BATCH_ID_COLUMN, INGEST_TS_COLUMN];
+
+/// Provenance recorded for one ingested batch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Provenance<'a> {
+    pub source_file: Option<&'a str>,
+    pub batch_id: u64,
+    /// Microseconds since the Unix epoch
+    pub ingest_ts: i64,
+}
+
+/// Check whether a schema already carries any provenance column
+#[must_use]
+pub fn has_provenance_columns(schema: &Schema) -> bool {
+    schema.fields().iter().any(|f| PROVENANCE_COLUMNS.contains(&f.name().as_str()))
+}
+
+/// Append the provenance columns to a batch
+///
+/// # Errors
+/// Returns error if the batch already contains a column with a reserved name
+pub(crate) fn inject(batch: &RecordBatch, provenance: &Provenance<'_>) -> Result<RecordBatch> {
+    let schema = batch.schema();
+    if has_provenance_columns(&schema) {
+        return Err(Error::InvalidInput(format!(
+            "Batch already contains a reserved provenance column ({})",
+            PROVENANCE_COLUMNS.join(", ")
+        )));
+    }
+
+    let num_rows = batch.num_rows();
+    let source: ArrayRef = Arc::new(StringArray::from(vec![provenance.source_file; num_rows]));
+    let batch_id: ArrayRef = Arc::new(UInt64Array::from(vec![provenance.batch_id; num_rows]));
+    let ingest_ts: ArrayRef = Arc::new(
+        TimestampMicrosecondArray::from(vec![provenance.ingest_ts; num_rows]).with_timezone("UTC"),
+    );
+
+    let mut fields: Vec<Field> = schema.fields().iter().map(|f| f.as_ref().clone()).collect();
+    fields.push(Field::new(SOURCE_FILE_COLUMN, DataType::Utf8, true));
+    fields.push(Field::new(BATCH_ID_COLUMN, DataType::UInt64, false));
+    fields.push(Field::new(
+        INGEST_TS_COLUMN,
+        DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+        false,
+    ));
+
+    let mut columns = batch.columns().to_vec();
+    columns.extend([source, batch_id, ingest_ts]);
+
+    let new_schema = Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()));
+    RecordBatch::try_new(new_schema, columns)
+        .map_err(|e| Error::StorageError(format!("Failed to inject provenance columns: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Array, Int32Array};
+
+    fn create_batch() -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+        RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![1, 2, 3]))]).unwrap()
+    }
+
+    #[test]
+    fn test_inject_appends_system_columns() {
+        let provenance = Provenance { source_file: Some("a.parquet"), batch_id: 7, ingest_ts: 42 };
+        let batch = inject(&create_batch(), &provenance).unwrap();
+
+        assert_eq!(batch.num_columns(), 4);
+        assert_eq!(batch.schema().field(1).name(), SOURCE_FILE_COLUMN);
+
+        let source = batch.column(1).as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(source.value(2), "a.parquet");
+        let batch_id = batch.column(2).as_any().downcast_ref::<UInt64Array>().unwrap();
+        assert_eq!(batch_id.value(0), 7);
+        let ts = batch.column(3).as_any().downcast_ref::<TimestampMicrosecondArray>().unwrap();
+        assert_eq!(ts.value(1), 42);
+    }
+
+    #[test]
+    fn test_inject_without_source_is_null() {
+        let provenance = Provenance { source_file: None, batch_id: 0, ingest_ts: 0 };
+        let batch = inject(&create_batch(), &provenance).unwrap();
+
+        assert_eq!(batch.column(1).null_count(), 3);
+    }
+
+    #[test]
+    fn test_inject_rejects_reserved_names() {
+        let provenance = Provenance { source_file: None, batch_id: 0, ingest_ts: 0 };
+        let once = inject(&create_batch(), &provenance).unwrap();
+
+        assert!(inject(&once, &provenance).is_err());
+    }
+}
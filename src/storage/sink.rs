@@ -0,0 +1,257 @@
+//! Streaming Parquet writer for tables and query results
+//!
+//! A [`ParquetSink`] accepts record batches one at a time and writes them as
+//! row groups, so exporting a large result never needs it all in memory.
+//! Rows go to a hidden temporary file next to the destination, which is
+//! fsynced and renamed into place by [`ParquetSink::finish`]: readers see
+//! either the old file or the complete new one, never a partial write. A sink
+//! dropped without `finish` removes its temporary file.
+//!
+//! Writer settings (codec, row group size, dictionaries) come from
+//! [`ParquetWriteOptions`]; statistics-driven settings are derived from the
+//! first batch written.
+//!
+//! Toyota Way: Poka-Yoke (a crashed export never leaves a truncated file behind)
+
+use super::codec::ParquetWriteOptions;
+use crate::{Error, Result};
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+/// What a finished [`ParquetSink`] wrote
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParquetWriteSummary {
+    /// Rows written
+    pub rows: usize,
+    /// Row groups in the file
+    pub row_groups: usize,
+    /// File size in bytes
+    pub bytes: u64,
+}
+
+/// Streaming, atomically committed Parquet file writer
+///
+/// # Example
+/// ```
+/// use arrow::array::{Int32Array, RecordBatch};
+/// use arrow::datatypes::{DataType, Field, Schema};
+/// use std::sync::Arc;
+/// use trueno_db::storage::{ParquetSink, ParquetWriteOptions};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+/// let path = std::env::temp_dir().join("trueno_doc_sink.parquet");
+///
+/// let mut sink = ParquetSink::create(&path, ParquetWriteOptions::new())?;
+/// for chunk in [vec![1, 2], vec![3]] {
+///     let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(chunk))])?;
+///     sink.write(&batch)?;
+/// }
+/// let summary = sink.finish()?;
+/// assert_eq!(summary.rows, 3);
+/// # std::fs::remove_file(&path)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ParquetSink {
+    path: PathBuf,
+    tmp_path: PathBuf,
+    options: ParquetWriteOptions,
+    /// Opened on the first write, once the schema is known
+    writer: Option<(SchemaRef, File, ArrowWriter<File>)>,
+    rows: usize,
+}
+
+impl ParquetSink {
+    /// Prepare to write `path` (nothing is created until the first batch)
+    ///
+    /// # Errors
+    /// Returns error if `path` has no file name
+    pub fn create<P: AsRef<Path>>(path: P, options: ParquetWriteOptions) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let name = path.file_name().ok_or_else(|| {
+            Error::InvalidInput(format!("Parquet path has no file name: {}", path.display()))
+        })?;
+        let tmp_path = path.with_file_name(format!(".{}.tmp", name.to_string_lossy()));
+        Ok(Self { path, tmp_path, options, writer: None, rows: 0 })
+    }
+
+    /// Append a batch; every batch must have the first batch's columns
+    ///
+    /// # Errors
+    /// Returns error if the columns differ from earlier batches, or the file
+    /// cannot be written
+    pub fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        if self.writer.is_none() {
+            self.open(std::slice::from_ref(batch))?;
+        }
+        let Some((schema, _, writer)) = &mut self.writer else {
+            unreachable!("writer opened above");
+        };
+        if batch.schema().fields() != schema.fields() {
+            return Err(Error::InvalidInput(format!(
+                "Batch columns {:?} do not match the Parquet file's columns {:?}",
+                batch.schema().fields(),
+                schema.fields()
+            )));
+        }
+        let batch = batch.clone().with_schema(schema.clone())?;
+        writer.write(&batch).map_err(|e| Error::StorageError(e.to_string()))?;
+        self.rows += batch.num_rows();
+        Ok(())
+    }
+
+    /// Open the temporary file, deriving statistics-driven settings from
+    /// `sample` (which must be non-empty)
+    pub(crate) fn open(&mut self, sample: &[RecordBatch]) -> Result<()> {
+        let schema = sample[0].schema();
+        let props = self.options.writer_properties(sample)?;
+        let file = File::create(&self.tmp_path)?;
+        let writer = ArrowWriter::try_new(file.try_clone()?, schema.clone(), Some(props))
+            .map_err(|e| Error::StorageError(e.to_string()))?;
+        self.writer = Some((schema, file, writer));
+        Ok(())
+    }
+
+    /// Flush, fsync, and atomically move the file into place
+    ///
+    /// # Errors
+    /// Returns error if no batch was written (the schema is unknown), or the
+    /// file cannot be completed
+    pub fn finish(mut self) -> Result<ParquetWriteSummary> {
+        let Some((_, file, writer)) = self.writer.take() else {
+            return Err(Error::InvalidInput(
+                "No batches written to Parquet sink (schema unknown)".to_string(),
+            ));
+        };
+        let committed = self.commit(file, writer);
+        if committed.is_err() {
+            let _ = fs::remove_file(&self.tmp_path);
+        }
+        committed
+    }
+
+    fn commit(&self, file: File, writer: ArrowWriter<File>) -> Result<ParquetWriteSummary> {
+        let metadata = writer.close().map_err(|e| Error::StorageError(e.to_string()))?;
+        file.sync_all()?;
+        let bytes = file.metadata()?.len();
+        drop(file);
+
+        fs::rename(&self.tmp_path, &self.path)?;
+        #[cfg(unix)]
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            File::open(dir)?.sync_all()?;
+        }
+        Ok(ParquetWriteSummary { rows: self.rows, row_groups: metadata.row_groups.len(), bytes })
+    }
+}
+
+impl Drop for ParquetSink {
+    fn drop(&mut self) {
+        // Only unfinished sinks still hold a writer
+        if self.writer.take().is_some() {
+            let _ = fs::remove_file(&self.tmp_path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{ColumnCodec, StorageEngine};
+    use arrow::array::{Int32Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use std::sync::Arc;
+
+    fn batch(start: i32, len: i32) -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, false),
+        ]));
+        let ids: Vec<i32> = (start..start + len).collect();
+        let names: Vec<String> = ids.iter().map(|i| format!("n{i}")).collect();
+        RecordBatch::try_new(
+            schema,
+            vec![Arc::new(Int32Array::from(ids)), Arc::new(StringArray::from(names))],
+        )
+        .unwrap()
+    }
+
+    fn test_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("trueno_test_sink_{name}.parquet"))
+    }
+
+    #[test]
+    fn test_sink_streams_row_groups() {
+        let path = test_path("row_groups");
+        let options = ParquetWriteOptions::new()
+            .with_max_row_group_size(100)
+            .with_default_codec(ColumnCodec::Uncompressed);
+        let mut sink = ParquetSink::create(&path, options).unwrap();
+        for start in (0..250).step_by(50) {
+            sink.write(&batch(start, 50)).unwrap();
+        }
+        let summary = sink.finish().unwrap();
+        assert_eq!(summary.rows, 250);
+        assert_eq!(summary.row_groups, 3);
+        assert_eq!(summary.bytes, fs::metadata(&path).unwrap().len());
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+        assert_eq!(reader.metadata().num_row_groups(), 3);
+        let loaded = StorageEngine::load_parquet(&path).unwrap();
+        assert_eq!(loaded.batches().iter().map(RecordBatch::num_rows).sum::<usize>(), 250);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_storage_write_parquet_roundtrip() {
+        let path = test_path("storage");
+        let storage = StorageEngine::new(vec![batch(0, 20), batch(20, 5)]);
+        let summary = storage.write_parquet(&path, &ParquetWriteOptions::new()).unwrap();
+        assert_eq!(summary.rows, 25);
+        assert_eq!(summary.row_groups, 1);
+
+        let loaded = StorageEngine::load_parquet(&path).unwrap();
+        let expected = arrow::compute::concat_batches(&batch(0, 1).schema(), storage.batches());
+        assert_eq!(loaded.batches()[0].columns(), expected.unwrap().columns());
+        fs::remove_file(&path).unwrap();
+
+        assert!(StorageEngine::new(vec![])
+            .write_parquet(&path, &ParquetWriteOptions::new())
+            .is_err());
+    }
+
+    #[test]
+    fn test_sink_rejects_mismatched_batch() {
+        let path = test_path("mismatch");
+        let mut sink = ParquetSink::create(&path, ParquetWriteOptions::new()).unwrap();
+        sink.write(&batch(0, 3)).unwrap();
+
+        let other = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)])),
+            vec![Arc::new(Int32Array::from(vec![1]))],
+        )
+        .unwrap();
+        assert!(matches!(sink.write(&other), Err(Error::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_unfinished_sink_leaves_no_file() {
+        let path = test_path("dropped");
+        let _ = fs::remove_file(&path);
+        let mut sink = ParquetSink::create(&path, ParquetWriteOptions::new()).unwrap();
+        sink.write(&batch(0, 10)).unwrap();
+        let tmp_path = sink.tmp_path.clone();
+        assert!(tmp_path.exists());
+        drop(sink);
+
+        assert!(!tmp_path.exists());
+        assert!(!path.exists());
+        let empty = ParquetSink::create(&path, ParquetWriteOptions::new()).unwrap();
+        assert!(empty.finish().is_err());
+    }
+}
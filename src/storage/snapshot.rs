@@ -0,0 +1,345 @@
+//! Versioned table reads and compaction of append-only batches
+//!
+//! Every append (one batch, or a whole bulk load with
+//! [`append_batches`](super::StorageEngine::append_batches)) bumps the
+//! table's version. A [`StorageSnapshot`] pins the batches of one version:
+//! batches are immutable and only ever added, so the snapshot is the batch
+//! list at that moment (shared, not copied) and later appends never show up
+//! in it, however long the query reading it runs. A bulk load is one
+//! version, so a snapshot holds all of it or none of it.
+//!
+//! Many small appends (micro-batches, streaming ingest) leave many small
+//! batches, which cost per-batch overhead in every scan.
+//! [`compact`](super::StorageEngine::compact) merges runs of them into
+//! batches of a target size; snapshots taken before keep the old layout.
+//! [`compact_by`](super::StorageEngine::compact_by) also sorts the table by
+//! a clustering column, so each batch (and each zone of its
+//! [zone map](super::zone_map)) covers a narrow range of that column. With a
+//! [`CompactionPolicy`] the table compacts itself once appends leave more
+//! than `max_batches` small batches.
+//!
+//! References:
+//! - Berenson et al. (1995): A critique of ANSI SQL isolation levels
+//!   (snapshot isolation)
+//!
+//! Toyota Way: Jidoka (a reader never sees a half-finished load)
+
+use super::{StorageEngine, ZONE_ROWS};
+use crate::{Error, Result};
+use arrow::compute::{self, SortOptions};
+use arrow::record_batch::RecordBatch;
+
+/// Default rows per compacted batch (one zone)
+pub const DEFAULT_COMPACTION_TARGET_ROWS: usize = ZONE_ROWS;
+
+/// Default number of small batches tolerated before compacting automatically
+pub const DEFAULT_COMPACTION_MAX_BATCHES: usize = 64;
+
+/// Immutable view of a table pinned to one version
+///
+/// # Example
+/// ```
+/// use arrow::array::{Int32Array, RecordBatch};
+/// use arrow::datatypes::{DataType, Field, Schema};
+/// use std::sync::Arc;
+/// use trueno_db::storage::StorageEngine;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+/// let batch = |ids: Vec<i32>| {
+///     RecordBatch::try_new(Arc::clone(&schema), vec![Arc::new(Int32Array::from(ids))])
+/// };
+///
+/// let mut storage = StorageEngine::new(vec![]);
+/// storage.append_batch(batch(vec![1, 2])?)?;
+/// let snapshot = storage.snapshot();
+/// storage.append_batches(vec![batch(vec![3])?, batch(vec![4])?])?;
+///
+/// assert_eq!((snapshot.version(), snapshot.num_rows()), (1, 2));
+/// assert_eq!((storage.version(), storage.snapshot().num_rows()), (2, 4));
+/// # Ok(())
+/// # }
+/// ```
+pub struct StorageSnapshot {
+    storage: StorageEngine,
+}
+
+impl StorageSnapshot {
+    pub(super) const fn new(storage: StorageEngine) -> Self {
+        Self { storage }
+    }
+
+    /// Table version the snapshot is pinned to
+    #[must_use]
+    pub const fn version(&self) -> u64 {
+        self.storage.version
+    }
+
+    /// Batches of the pinned version
+    #[must_use]
+    pub fn batches(&self) -> &[RecordBatch] {
+        self.storage.batches()
+    }
+
+    /// Rows in the pinned version
+    #[must_use]
+    pub fn num_rows(&self) -> usize {
+        self.storage.batches().iter().map(RecordBatch::num_rows).sum()
+    }
+
+    /// The pinned version as a read-only table (for the query executor)
+    #[must_use]
+    pub const fn storage(&self) -> &StorageEngine {
+        &self.storage
+    }
+
+    /// The pinned version as a table of its own
+    #[must_use]
+    pub fn into_storage(self) -> StorageEngine {
+        self.storage
+    }
+}
+
+/// What [`compact`](super::StorageEngine::compact) did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactionSummary {
+    /// Batches before compaction
+    pub batches_before: usize,
+    /// Batches after compaction
+    pub batches_after: usize,
+}
+
+impl CompactionSummary {
+    /// Whether any batches were merged
+    #[must_use]
+    pub const fn merged(&self) -> bool {
+        self.batches_after < self.batches_before
+    }
+}
+
+/// When a table compacts itself (see
+/// [`with_auto_compaction`](super::StorageEngine::with_auto_compaction))
+///
+/// # Example
+/// ```
+/// use trueno_db::storage::{CompactionPolicy, StorageEngine};
+///
+/// let storage = StorageEngine::new(vec![]).with_auto_compaction(CompactionPolicy {
+///     cluster_by: Some("ts".to_string()),
+///     ..CompactionPolicy::default()
+/// });
+/// assert_eq!(storage.compaction_policy().unwrap().max_batches, 64);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompactionPolicy {
+    /// Rows per compacted batch
+    pub target_rows: usize,
+    /// Compact after an append leaves more than this many batches smaller
+    /// than `target_rows`
+    pub max_batches: usize,
+    /// Sort the whole table by this column on each compaction (see
+    /// [`compact_by`](super::StorageEngine::compact_by))
+    pub cluster_by: Option<String>,
+}
+
+impl Default for CompactionPolicy {
+    fn default() -> Self {
+        Self {
+            target_rows: DEFAULT_COMPACTION_TARGET_ROWS,
+            max_batches: DEFAULT_COMPACTION_MAX_BATCHES,
+            cluster_by: None,
+        }
+    }
+}
+
+impl CompactionPolicy {
+    /// Whether `batches` hold enough small batches to compact
+    pub(super) fn is_due(&self, batches: &[RecordBatch]) -> bool {
+        batches.iter().filter(|batch| batch.num_rows() < self.target_rows).count()
+            > self.max_batches
+    }
+}
+
+/// `batches` with each run of adjacent same-schema batches smaller than
+/// `target_rows` merged into batches of up to `target_rows` rows
+///
+/// Batches already at least `target_rows` long are kept as they are; row
+/// order is preserved.
+pub(super) fn compact_batches(
+    batches: &[RecordBatch],
+    target_rows: usize,
+) -> Result<Vec<RecordBatch>> {
+    let mut compacted = Vec::with_capacity(batches.len());
+    let mut run: Vec<&RecordBatch> = Vec::new();
+    let mut run_rows = 0;
+    for batch in batches {
+        let fits = !run.first().is_some_and(|first| first.schema() != batch.schema())
+            && run_rows + batch.num_rows() <= target_rows;
+        if !fits {
+            flush(&mut run, &mut compacted)?;
+            run_rows = 0;
+        }
+        if batch.num_rows() >= target_rows {
+            compacted.push(batch.clone());
+        } else {
+            run.push(batch);
+            run_rows += batch.num_rows();
+        }
+    }
+    flush(&mut run, &mut compacted)?;
+    Ok(compacted)
+}
+
+/// All rows of `batches` sorted by `column` (ascending, NULLs last), in
+/// batches of `target_rows` rows (the last may be shorter)
+///
+/// # Errors
+/// Returns error if `column` does not exist or the batches cannot be
+/// concatenated (different schemas) or sorted
+pub(super) fn cluster_batches(
+    batches: &[RecordBatch],
+    target_rows: usize,
+    column: &str,
+) -> Result<Vec<RecordBatch>> {
+    let Some(first) = batches.first() else {
+        return Ok(Vec::new());
+    };
+    let schema = first.schema();
+    let index = schema
+        .index_of(column)
+        .map_err(|_| Error::InvalidInput(format!("Column not found: {column}")))?;
+    let table = compute::concat_batches(&schema, batches)
+        .map_err(|e| Error::StorageError(format!("Failed to compact batches: {e}")))?;
+    let options = SortOptions { descending: false, nulls_first: false };
+    let sorted = compute::sort_to_indices(table.column(index), Some(options), None)
+        .and_then(|order| compute::take_record_batch(&table, &order))
+        .map_err(|e| Error::StorageError(format!("Failed to sort by {column}: {e}")))?;
+
+    let target_rows = target_rows.max(1);
+    Ok((0..sorted.num_rows())
+        .step_by(target_rows)
+        .map(|offset| sorted.slice(offset, target_rows.min(sorted.num_rows() - offset)))
+        .collect())
+}
+
+/// Append `run`, merged into one batch, to `compacted`
+fn flush(run: &mut Vec<&RecordBatch>, compacted: &mut Vec<RecordBatch>) -> Result<()> {
+    match run.as_slice() {
+        [] => {}
+        [single] => compacted.push((*single).clone()),
+        [first, ..] => compacted.push(
+            compute::concat_batches(&first.schema(), run.iter().copied())
+                .map_err(|e| Error::StorageError(format!("Failed to compact batches: {e}")))?,
+        ),
+    }
+    run.clear();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Array, Int32Array};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    fn batch(start: i32, rows: i32) -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+        RecordBatch::try_new(
+            schema,
+            vec![Arc::new(Int32Array::from_iter_values(start..start + rows))],
+        )
+        .unwrap()
+    }
+
+    fn ids(batches: &[RecordBatch]) -> Vec<i32> {
+        batches
+            .iter()
+            .flat_map(|batch| {
+                let ids = batch.column(0).as_any().downcast_ref::<Int32Array>().unwrap();
+                ids.values().to_vec()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_compact_batches_merges_small_runs_in_order() {
+        // 3 + 3 + 3 | 10 | 2 + 1
+        let batches =
+            vec![batch(0, 3), batch(3, 3), batch(6, 3), batch(9, 10), batch(19, 2), batch(21, 1)];
+        let compacted = compact_batches(&batches, 9).unwrap();
+        let sizes: Vec<usize> = compacted.iter().map(RecordBatch::num_rows).collect();
+        assert_eq!(sizes, vec![9, 10, 3]);
+        assert_eq!(ids(&compacted), (0..22).collect::<Vec<_>>());
+
+        // Runs never grow past the target
+        let sizes: Vec<usize> =
+            compact_batches(&batches, 5).unwrap().iter().map(RecordBatch::num_rows).collect();
+        assert_eq!(sizes, vec![3, 3, 3, 10, 3]);
+        assert!(compact_batches(&[], 5).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_pins_version_across_appends_and_compaction() {
+        let mut storage = StorageEngine::new(vec![]);
+        assert_eq!(storage.version(), 0);
+        for start in 0..8 {
+            storage.append_batch(batch(start * 2, 2)).unwrap();
+        }
+        let before = storage.snapshot();
+        assert_eq!((before.version(), before.batches().len()), (8, 8));
+
+        // A failed bulk load appends nothing and keeps the version
+        let other = Arc::new(Schema::new(vec![Field::new("name", DataType::Utf8, false)]));
+        let bad = RecordBatch::new_empty(other);
+        assert!(storage.append_batches(vec![batch(16, 2), bad]).is_err());
+        assert_eq!((storage.version(), storage.batches().len()), (8, 8));
+
+        let summary = storage.compact(8).unwrap();
+        assert_eq!(summary, CompactionSummary { batches_before: 8, batches_after: 2 });
+        assert!(summary.merged());
+        assert_eq!(storage.version(), 9);
+        assert_eq!(storage.zone_maps().len(), 2);
+        assert_eq!(ids(storage.batches()), ids(before.batches()));
+
+        // Nothing left to merge: the version stays
+        assert!(!storage.compact(8).unwrap().merged());
+        assert_eq!(storage.version(), 9);
+        assert_eq!(before.batches().len(), 8);
+        assert_eq!(before.into_storage().batches()[0].column(0).len(), 2);
+    }
+
+    #[test]
+    fn test_compact_by_sorts_and_rechunks() {
+        let mut storage = StorageEngine::new(vec![batch(5, 3), batch(0, 5), batch(8, 2)]);
+        let summary = storage.compact_by(4, "id").unwrap();
+        assert_eq!(summary, CompactionSummary { batches_before: 3, batches_after: 3 });
+        let sizes: Vec<usize> = storage.batches().iter().map(RecordBatch::num_rows).collect();
+        assert_eq!(sizes, vec![4, 4, 2]);
+        assert_eq!(ids(storage.batches()), (0..10).collect::<Vec<_>>());
+        assert_eq!(storage.version(), 1);
+        assert!(storage.compact_by(4, "missing").is_err());
+    }
+
+    #[test]
+    fn test_auto_compaction_beyond_threshold() {
+        let policy = CompactionPolicy { target_rows: 4, max_batches: 3, cluster_by: None };
+        let mut storage = StorageEngine::new(vec![]).with_auto_compaction(policy.clone());
+        for id in 0..3 {
+            storage.append_batch(batch(id, 1)).unwrap();
+        }
+        assert_eq!(storage.batches().len(), 3);
+        // The fourth small batch crosses the threshold: one batch of 4 rows
+        storage.append_batch(batch(3, 1)).unwrap();
+        assert_eq!(storage.batches().len(), 1);
+        assert_eq!(ids(storage.batches()), vec![0, 1, 2, 3]);
+        assert_eq!(storage.version(), 5);
+
+        // Clustered: rows come back sorted whatever the append order
+        let clustered = CompactionPolicy { cluster_by: Some("id".to_string()), ..policy };
+        let mut storage = StorageEngine::new(vec![]).with_auto_compaction(clustered);
+        storage.append_batches((0..6).rev().map(|id| batch(id, 1))).unwrap();
+        assert_eq!(ids(storage.batches()), (0..6).collect::<Vec<_>>());
+        assert_eq!(storage.batches().len(), 2);
+    }
+}
@@ -0,0 +1,180 @@
+//! Streaming (out-of-core) Parquet scans
+//!
+//! [`StorageEngine::load_parquet`](super::StorageEngine::load_parquet)
+//! materializes every batch before the first morsel is produced, so a file
+//! must fit in RAM. [`ParquetMorselReader`] instead decodes the file lazily,
+//! one row group at a time, and yields morsels sized from the row groups'
+//! uncompressed byte counts. Peak memory is roughly one row group's column
+//! chunks plus the morsel being handed out, whatever the file size.
+//!
+//! [`open_with_projection`](ParquetMorselReader::open_with_projection)
+//! prunes columns as well: only the projected column chunks are read, and
+//! morsels are sized from their bytes alone.
+//!
+//! Toyota Way: Poka-Yoke (the scan cannot outgrow memory)
+
+use crate::{Error, Result};
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::arrow_reader::{ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder};
+use parquet::arrow::ProjectionMask;
+use parquet::file::metadata::RowGroupMetaData;
+use parquet::file::reader::ChunkReader;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Lazily decoded morsels of a Parquet file
+///
+/// Created by [`StorageEngine::open_parquet_streaming`](super::StorageEngine::open_parquet_streaming)
+/// or [`open`](Self::open); iterating yields each morsel as it is decoded.
+pub struct ParquetMorselReader {
+    reader: ParquetRecordBatchReader,
+    schema: SchemaRef,
+    num_rows: usize,
+    num_row_groups: usize,
+    morsel_rows: usize,
+}
+
+impl ParquetMorselReader {
+    /// Open `path` for streaming in morsels of about `morsel_bytes` each
+    ///
+    /// # Errors
+    /// Returns error if the file cannot be opened or its footer parsed
+    pub fn open<P: AsRef<Path>>(path: P, morsel_bytes: usize) -> Result<Self> {
+        Self::open_with_projection(path, morsel_bytes, None::<&[&str]>)
+    }
+
+    /// Open `path` for streaming, decoding only `columns` when given
+    ///
+    /// # Errors
+    /// Returns error if the file cannot be opened, its footer parsed, or a
+    /// column is not in the file
+    pub fn open_with_projection<P: AsRef<Path>, S: AsRef<str>>(
+        path: P,
+        morsel_bytes: usize,
+        columns: Option<&[S]>,
+    ) -> Result<Self> {
+        let file = File::open(path.as_ref())
+            .map_err(|e| Error::StorageError(format!("Failed to open Parquet file: {e}")))?;
+
+        let mut builder = ParquetRecordBatchReaderBuilder::try_new(file)
+            .map_err(|e| Error::StorageError(format!("Failed to parse Parquet file: {e}")))?;
+        let projection = columns.map(|columns| projection(&builder, columns)).transpose()?;
+
+        let row_groups = builder.metadata().row_groups();
+        let num_rows: i64 = row_groups.iter().map(RowGroupMetaData::num_rows).sum();
+        let total_bytes: i64 = match &projection {
+            // Only the projected column chunks count towards a morsel
+            Some((mask, _)) => row_groups
+                .iter()
+                .flat_map(|rg| rg.columns().iter().enumerate())
+                .filter(|(leaf, _)| mask.leaf_included(*leaf))
+                .map(|(_, chunk)| chunk.uncompressed_size())
+                .sum(),
+            None => row_groups.iter().map(RowGroupMetaData::total_byte_size).sum(),
+        };
+        let num_rows = usize::try_from(num_rows).unwrap_or(0);
+        let num_row_groups = row_groups.len();
+        let morsel_rows = Self::rows_per_morsel(
+            num_rows,
+            usize::try_from(total_bytes).unwrap_or(0),
+            morsel_bytes,
+        );
+
+        let schema = match projection {
+            Some((mask, schema)) => {
+                builder = builder.with_projection(mask);
+                schema
+            }
+            None => builder.schema().clone(),
+        };
+        let reader = builder
+            .with_batch_size(morsel_rows)
+            .build()
+            .map_err(|e| Error::StorageError(format!("Failed to create Parquet reader: {e}")))?;
+
+        Ok(Self { reader, schema, num_rows, num_row_groups, morsel_rows })
+    }
+
+    /// Rows per morsel so one morsel holds about `morsel_bytes`
+    fn rows_per_morsel(num_rows: usize, total_bytes: usize, morsel_bytes: usize) -> usize {
+        if num_rows == 0 {
+            return 1;
+        }
+        let bytes_per_row = (total_bytes / num_rows).max(1);
+        (morsel_bytes / bytes_per_row).max(1)
+    }
+
+    /// Schema of every morsel
+    #[must_use]
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    /// Total rows in the file (from the footer; nothing is decoded)
+    #[must_use]
+    pub const fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    /// Row groups in the file
+    #[must_use]
+    pub const fn num_row_groups(&self) -> usize {
+        self.num_row_groups
+    }
+
+    /// Maximum rows per morsel
+    #[must_use]
+    pub const fn morsel_rows(&self) -> usize {
+        self.morsel_rows
+    }
+}
+
+/// Projection selecting the top-level `columns` of the file behind
+/// `builder`, with the schema of the batches it produces (file order)
+///
+/// # Errors
+/// Returns error if a column is not in the file
+pub(super) fn projection<T: ChunkReader, S: AsRef<str>>(
+    builder: &ParquetRecordBatchReaderBuilder<T>,
+    columns: &[S],
+) -> Result<(ProjectionMask, SchemaRef)> {
+    let schema = builder.schema();
+    let mut roots = columns
+        .iter()
+        .map(|column| {
+            let column = column.as_ref();
+            schema
+                .index_of(column)
+                .map_err(|_| Error::InvalidInput(format!("Column not found: {column}")))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    roots.sort_unstable();
+    roots.dedup();
+    let projected = Arc::new(schema.project(&roots)?);
+    Ok((ProjectionMask::roots(builder.parquet_schema(), roots), projected))
+}
+
+impl Iterator for ParquetMorselReader {
+    type Item = Result<RecordBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.reader.next().map(|batch| {
+            batch.map_err(|e| Error::StorageError(format!("Failed to read record batch: {e}")))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MORSEL_SIZE_BYTES;
+
+    #[test]
+    fn test_morsel_rows_from_row_group_sizes() {
+        assert_eq!(ParquetMorselReader::rows_per_morsel(1_000, 8_000, 800), 100);
+        assert_eq!(ParquetMorselReader::rows_per_morsel(1_000, 8_000, 4), 1);
+        assert_eq!(ParquetMorselReader::rows_per_morsel(0, 0, MORSEL_SIZE_BYTES), 1);
+    }
+}
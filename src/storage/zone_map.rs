@@ -0,0 +1,215 @@
+//! Zone maps: per-zone min/max statistics for predicate pushdown
+//!
+//! Each batch is divided into zones of [`ZONE_ROWS`] rows, and each zone
+//! records the minimum, maximum and NULL count of every column. Before
+//! filtering, the query executor checks the WHERE clause against the zone
+//! maps and skips zones whose ranges cannot satisfy it (`WHERE ts > 1000` on
+//! a zone whose `ts` tops out at 900), so selective queries on clustered
+//! data read a fraction of the table.
+//!
+//! Statistics are kept for integer, floating-point, `Utf8`/`LargeUtf8`,
+//! date and timestamp columns (the latter as their stored integers: days,
+//! milliseconds or timestamp units since the epoch). Other types, dictionary-encoded strings and float zones holding
+//! NaN have none, and never cause a zone to be skipped.
+//!
+//! References:
+//! - Moerkotte (1998): Small Materialized Aggregates
+//!
+//! Toyota Way: Muda elimination (rows that cannot match are never read)
+
+use arrow::array::{Array, ArrayRef, AsArray, OffsetSizeTrait};
+use arrow::compute;
+use arrow::datatypes::{
+    ArrowPrimitiveType, DataType, Date32Type, Date64Type, Float32Type, Float64Type, Int16Type,
+    Int32Type, Int64Type, Int8Type, TimeUnit, TimestampMicrosecondType, TimestampMillisecondType,
+    TimestampNanosecondType, TimestampSecondType, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+};
+use arrow::record_batch::RecordBatch;
+
+/// Rows per zone (the last zone of a batch may be shorter)
+///
+/// Equal to [`MORSEL_ROWS`](crate::query::parallel::MORSEL_ROWS), so every
+/// morsel of parallel execution is covered by exactly one zone.
+pub const ZONE_ROWS: usize = 64 * 1024;
+
+/// A column's minimum or maximum
+#[derive(Debug, Clone, PartialEq)]
+pub enum ZoneValue {
+    /// Any integer type, widened as in predicate evaluation; dates and
+    /// timestamps as their stored integers
+    Int(i128),
+    /// `Float32` or `Float64`
+    Float(f64),
+    /// `Utf8` or `LargeUtf8` (byte-wise order)
+    Utf8(String),
+}
+
+/// Statistics of one column over one zone
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnZone {
+    /// Smallest non-NULL value (`None` if every value is NULL)
+    pub min: Option<ZoneValue>,
+    /// Largest non-NULL value (`None` if every value is NULL)
+    pub max: Option<ZoneValue>,
+    /// NULL values in the zone
+    pub null_count: usize,
+}
+
+impl ColumnZone {
+    /// Statistics of `column`, or `None` for types without statistics
+    #[must_use]
+    pub fn of(column: &ArrayRef) -> Option<Self> {
+        let (min, max) = match column.data_type() {
+            DataType::Int8 => int_range::<Int8Type>(column),
+            DataType::Int16 => int_range::<Int16Type>(column),
+            DataType::Int32 => int_range::<Int32Type>(column),
+            DataType::Int64 => int_range::<Int64Type>(column),
+            DataType::UInt8 => int_range::<UInt8Type>(column),
+            DataType::UInt16 => int_range::<UInt16Type>(column),
+            DataType::UInt32 => int_range::<UInt32Type>(column),
+            DataType::UInt64 => int_range::<UInt64Type>(column),
+            DataType::Float32 => float_range::<Float32Type>(column)?,
+            DataType::Float64 => float_range::<Float64Type>(column)?,
+            DataType::Utf8 => string_range::<i32>(column),
+            DataType::LargeUtf8 => string_range::<i64>(column),
+            DataType::Date32 => int_range::<Date32Type>(column),
+            DataType::Date64 => int_range::<Date64Type>(column),
+            DataType::Timestamp(unit, _) => match unit {
+                TimeUnit::Second => int_range::<TimestampSecondType>(column),
+                TimeUnit::Millisecond => int_range::<TimestampMillisecondType>(column),
+                TimeUnit::Microsecond => int_range::<TimestampMicrosecondType>(column),
+                TimeUnit::Nanosecond => int_range::<TimestampNanosecondType>(column),
+            },
+            _ => return None,
+        };
+        Some(Self { min, max, null_count: column.null_count() })
+    }
+}
+
+/// Statistics of one zone of a batch
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZoneMap {
+    /// First row of the zone within its batch
+    pub offset: usize,
+    /// Rows in the zone
+    pub rows: usize,
+    /// Per column, in schema order; `None` for columns without statistics
+    pub columns: Vec<Option<ColumnZone>>,
+}
+
+impl ZoneMap {
+    /// Zone maps covering `batch`, one per [`ZONE_ROWS`] rows
+    #[must_use]
+    pub fn build(batch: &RecordBatch) -> Vec<Self> {
+        (0..batch.num_rows())
+            .step_by(ZONE_ROWS)
+            .map(|offset| {
+                let rows = ZONE_ROWS.min(batch.num_rows() - offset);
+                let columns = batch
+                    .columns()
+                    .iter()
+                    .map(|column| ColumnZone::of(&column.slice(offset, rows)))
+                    .collect();
+                Self { offset, rows, columns }
+            })
+            .collect()
+    }
+
+    /// Statistics of column `index`, if it has any
+    #[must_use]
+    pub fn column(&self, index: usize) -> Option<&ColumnZone> {
+        self.columns.get(index).and_then(Option::as_ref)
+    }
+}
+
+type Range = (Option<ZoneValue>, Option<ZoneValue>);
+
+fn int_range<T>(column: &ArrayRef) -> Range
+where
+    T: ArrowPrimitiveType,
+    T::Native: Into<i128>,
+{
+    let values = column.as_primitive::<T>();
+    let mut range: Option<(i128, i128)> = None;
+    for value in values.iter().flatten() {
+        let value = value.into();
+        range = Some(range.map_or((value, value), |(min, max)| (min.min(value), max.max(value))));
+    }
+    range.map_or((None, None), |(min, max)| (Some(ZoneValue::Int(min)), Some(ZoneValue::Int(max))))
+}
+
+/// `None` if the zone holds NaN: NaN fails every comparison but passes
+/// its negation, which a min/max range cannot express
+fn float_range<T>(column: &ArrayRef) -> Option<Range>
+where
+    T: ArrowPrimitiveType,
+    T::Native: Into<f64>,
+{
+    let values = column.as_primitive::<T>();
+    let mut range: Option<(f64, f64)> = None;
+    for value in values.iter().flatten() {
+        let value: f64 = value.into();
+        if value.is_nan() {
+            return None;
+        }
+        range = Some(range.map_or((value, value), |(min, max)| (min.min(value), max.max(value))));
+    }
+    Some(range.map_or((None, None), |(min, max)| {
+        (Some(ZoneValue::Float(min)), Some(ZoneValue::Float(max)))
+    }))
+}
+
+fn string_range<O: OffsetSizeTrait>(column: &ArrayRef) -> Range {
+    let strings = column.as_string::<O>();
+    let value = |s: Option<&str>| s.map(|s| ZoneValue::Utf8(s.to_string()));
+    (value(compute::min_string(strings)), value(compute::max_string(strings)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Float64Array, Int32Array, StringArray};
+    use arrow::datatypes::{Field, Schema};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_zones_split_batch_and_track_ranges() {
+        let values: Vec<Option<i32>> =
+            (0..100_000).map(|i| if i % 10 == 0 { None } else { Some(i) }).collect();
+        let schema = Arc::new(Schema::new(vec![Field::new("v", DataType::Int32, true)]));
+        let batch = RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(values))]).unwrap();
+
+        let zones = ZoneMap::build(&batch);
+        assert_eq!(zones.len(), 2);
+        assert_eq!((zones[1].offset, zones[1].rows), (ZONE_ROWS, 100_000 - ZONE_ROWS));
+
+        let first = zones[0].column(0).unwrap();
+        assert_eq!(first.min, Some(ZoneValue::Int(1)));
+        assert_eq!(first.max, Some(ZoneValue::Int(65_535)));
+        assert_eq!(first.null_count, ZONE_ROWS / 10 + 1);
+    }
+
+    #[test]
+    fn test_column_zone_types() {
+        let strings: ArrayRef = Arc::new(StringArray::from(vec![Some("pear"), None, Some("fig")]));
+        let zone = ColumnZone::of(&strings).unwrap();
+        assert_eq!(zone.min, Some(ZoneValue::Utf8("fig".to_string())));
+        assert_eq!(zone.max, Some(ZoneValue::Utf8("pear".to_string())));
+
+        let nulls: ArrayRef = Arc::new(Int32Array::from(vec![None, None]));
+        let zone = ColumnZone::of(&nulls).unwrap();
+        assert_eq!((zone.min, zone.null_count), (None, 2));
+
+        let nan: ArrayRef = Arc::new(Float64Array::from(vec![1.0, f64::NAN]));
+        assert_eq!(ColumnZone::of(&nan), None);
+        let bools: ArrayRef = Arc::new(arrow::array::BooleanArray::from(vec![true]));
+        assert_eq!(ColumnZone::of(&bools), None);
+
+        let days: ArrayRef = Arc::new(arrow::array::Date32Array::from(vec![19_784, 19_783]));
+        let zone = ColumnZone::of(&days).unwrap();
+        assert_eq!(
+            (zone.min, zone.max),
+            (Some(ZoneValue::Int(19_783)), Some(ZoneValue::Int(19_784)))
+        );
+    }
+}
@@ -9,6 +9,20 @@
 //! - Top-K selection: 0.08 seconds
 //! - **Speedup**: 28.75x
 //!
+//! **Key types**: integers, floats, strings (`Utf8`/`LargeUtf8`, compared
+//! bytewise), dates and timestamps. [`TopKSelection::top_k_multi`] orders by
+//! several keys (`ORDER BY a DESC, b ASC LIMIT k`) on the same heap path.
+//!
+//! **Ties**: rows with equal sort keys come back in a deterministic order
+//! chosen by [`TieBreak`] (row index by default), identically on the heap
+//! path, the sort fallback, and the external sorter, so results do not
+//! depend on the strategy or backend that produced them.
+//!
+//! **NULLs and NaN**: NULLs sort last in either direction and are kept, so
+//! `LIMIT k` returns `min(k, N)` rows. Floats compare by `total_cmp`, as in
+//! Arrow's sort kernels: NaN ranks above every number (last ascending,
+//! first descending).
+//!
 //! Toyota Way Principles:
 //! - **Kaizen**: Algorithmic improvement (O(N log N) → O(N))
 //! - **Muda elimination**: Avoid unnecessary full sort
@@ -19,14 +33,68 @@
 
 use crate::Error;
 use arrow::array::{
-    Array, ArrayRef, Float32Array, Float64Array, Int32Array, Int64Array, StringArray,
+    Array, ArrayRef, ArrowNativeTypeOp, AsArray, Float32Array, Float64Array, Int32Array,
+    Int64Array, PrimitiveArray, UInt64Array,
+};
+use arrow::compute::{self, lexsort_to_indices, SortColumn, SortOptions};
+use arrow::datatypes::{
+    ArrowPrimitiveType, Date32Type, Date64Type, Int16Type, Int8Type, TimeUnit,
+    TimestampMicrosecondType, TimestampMillisecondType, TimestampNanosecondType,
+    TimestampSecondType, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
 };
-use arrow::compute::SortOptions;
 use arrow::record_batch::RecordBatch;
+use arrow::row::{Row, RowConverter, Rows, SortField};
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
 use std::sync::Arc;
 
+/// Above this fraction of the input (`k > c·N`), Top-K falls back to a sort
+///
+/// Each heap push costs O(log K) with poor cache locality; once K is a large
+/// share of N the heap does nearly as much work as sorting, without the
+/// sort kernel's tight inner loop.
+pub const SORT_FALLBACK_RATIO: f64 = 0.1;
+
+/// Inputs smaller than this always use the heap (both paths are trivially fast)
+pub const SORT_FALLBACK_MIN_ROWS: usize = 4096;
+
+/// Physical strategy used to answer a Top-K request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopKStrategy {
+    /// Bounded heap selection, O(N log K)
+    Heap,
+    /// Arrow (partial) sort, O(N log N)
+    Sort,
+}
+
+impl TopKStrategy {
+    /// Choose a strategy for selecting `k` of `num_rows` rows
+    ///
+    /// Sorts when `k >= num_rows`, or when `k > SORT_FALLBACK_RATIO · num_rows`
+    /// on inputs of at least [`SORT_FALLBACK_MIN_ROWS`] rows.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn choose(k: usize, num_rows: usize) -> Self {
+        if k >= num_rows
+            || (num_rows >= SORT_FALLBACK_MIN_ROWS
+                && k as f64 > SORT_FALLBACK_RATIO * num_rows as f64)
+        {
+            Self::Sort
+        } else {
+            Self::Heap
+        }
+    }
+
+    /// Lowercase name for EXPLAIN output
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Heap => "heap",
+            Self::Sort => "sort",
+        }
+    }
+}
+
 /// Sort order for Top-K selection
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SortOrder {
@@ -42,6 +110,73 @@ impl From<SortOrder> for SortOptions {
     }
 }
 
+/// How rows with equal sort keys are ordered
+///
+/// Every Top-K path (heap, sort fallback, external sort, and any GPU
+/// kernel) must honor this, so equal keys never make output depend on the
+/// strategy or backend chosen.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum TieBreak {
+    /// Earlier rows first (stable selection)
+    #[default]
+    RowIndex,
+    /// Compare these `(column_index, order)` keys in turn, then row index
+    ///
+    /// This is how `ORDER BY a, b, c` maps onto Top-K on `a`.
+    Columns(Vec<(usize, SortOrder)>),
+}
+
+impl TieBreak {
+    /// Secondary sort keys (empty for [`TieBreak::RowIndex`])
+    #[must_use]
+    pub fn columns(&self) -> &[(usize, SortOrder)] {
+        match self {
+            Self::RowIndex => &[],
+            Self::Columns(columns) => columns,
+        }
+    }
+
+    /// Check that every tie-break column exists in a batch of `num_columns`
+    ///
+    /// # Errors
+    /// Returns error if a column index is out of bounds
+    pub fn validate(&self, num_columns: usize) -> crate::Result<()> {
+        match self.columns().iter().find(|(index, _)| *index >= num_columns) {
+            Some((index, _)) => Err(Error::InvalidInput(format!(
+                "Tie-break column index {index} out of bounds (batch has {num_columns} columns)"
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Comparable row encodings of the tie-break columns (`None` for row index only)
+    ///
+    /// # Errors
+    /// Returns error if a tie-break column type is not sortable
+    pub(crate) fn encode(&self, batch: &RecordBatch) -> crate::Result<Option<Rows>> {
+        if self.columns().is_empty() {
+            return Ok(None);
+        }
+        let (fields, columns): (Vec<_>, Vec<_>) = self
+            .columns()
+            .iter()
+            .map(|&(index, order)| {
+                let column = batch.column(index);
+                (
+                    SortField::new_with_options(column.data_type().clone(), order.into()),
+                    column.clone(),
+                )
+            })
+            .unzip();
+        let converter = RowConverter::new(fields)
+            .map_err(|e| Error::InvalidInput(format!("Unsortable tie-break column: {e}")))?;
+        converter
+            .convert_columns(&columns)
+            .map(Some)
+            .map_err(|e| Error::StorageError(format!("Failed to encode tie-break columns: {e}")))
+    }
+}
+
 /// Trait for Top-K selection on record batches
 pub trait TopKSelection {
     /// Select top K rows by a specific column
@@ -83,202 +218,437 @@ pub trait TopKSelection {
     /// # Ok(())
     /// # }
     /// ```
-    fn top_k(&self, column_index: usize, k: usize, order: SortOrder) -> crate::Result<RecordBatch>;
+    fn top_k(&self, column_index: usize, k: usize, order: SortOrder) -> crate::Result<RecordBatch> {
+        self.top_k_with_ties(column_index, k, order, &TieBreak::RowIndex)
+    }
+
+    /// Select top K rows by a column, ordering equal keys by `ties`
+    ///
+    /// [`top_k`](Self::top_k) is this with [`TieBreak::RowIndex`].
+    ///
+    /// # Errors
+    /// Same as [`top_k`](Self::top_k), plus an out-of-bounds or unsortable
+    /// tie-break column
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use trueno_db::topk::{SortOrder, TieBreak, TopKSelection};
+    /// use arrow::array::{Int32Array, RecordBatch};
+    /// use arrow::datatypes::{DataType, Field, Schema};
+    /// use std::sync::Arc;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let schema = Arc::new(Schema::new(vec![
+    ///     Field::new("score", DataType::Int32, false),
+    ///     Field::new("id", DataType::Int32, false),
+    /// ]));
+    /// let batch = RecordBatch::try_new(
+    ///     schema,
+    ///     vec![
+    ///         Arc::new(Int32Array::from(vec![7, 9, 7, 7])),
+    ///         Arc::new(Int32Array::from(vec![3, 1, 4, 2])),
+    ///     ],
+    /// )?;
+    ///
+    /// // ORDER BY score DESC, id DESC LIMIT 3
+    /// let ties = TieBreak::Columns(vec![(1, SortOrder::Descending)]);
+    /// let top = batch.top_k_with_ties(0, 3, SortOrder::Descending, &ties)?;
+    /// let ids = top.column(1).as_any().downcast_ref::<Int32Array>().unwrap();
+    /// assert_eq!(ids.values(), &[1, 4, 3]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn top_k_with_ties(
+        &self,
+        column_index: usize,
+        k: usize,
+        order: SortOrder,
+        ties: &TieBreak,
+    ) -> crate::Result<RecordBatch>;
+
+    /// Select top K rows ordered by several `(column_index, order)` keys
+    ///
+    /// The first key drives the selection and the rest break its ties, then
+    /// row index (`ORDER BY a DESC, b ASC LIMIT k`); this is
+    /// [`top_k_with_ties`](Self::top_k_with_ties) with
+    /// [`TieBreak::Columns`] for the remaining keys.
+    ///
+    /// # Errors
+    /// Same as [`top_k_with_ties`](Self::top_k_with_ties), plus an empty `keys`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use trueno_db::topk::{SortOrder, TopKSelection};
+    /// use arrow::array::{Int32Array, RecordBatch, StringArray};
+    /// use arrow::datatypes::{DataType, Field, Schema};
+    /// use std::sync::Arc;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let schema = Arc::new(Schema::new(vec![
+    ///     Field::new("team", DataType::Utf8, false),
+    ///     Field::new("score", DataType::Int32, false),
+    /// ]));
+    /// let batch = RecordBatch::try_new(
+    ///     schema,
+    ///     vec![
+    ///         Arc::new(StringArray::from(vec!["red", "blue", "red", "blue"])),
+    ///         Arc::new(Int32Array::from(vec![4, 7, 9, 2])),
+    ///     ],
+    /// )?;
+    ///
+    /// // ORDER BY team DESC, score ASC LIMIT 3
+    /// let keys = [(0, SortOrder::Descending), (1, SortOrder::Ascending)];
+    /// let top = batch.top_k_multi(&keys, 3)?;
+    /// let scores = top.column(1).as_any().downcast_ref::<Int32Array>().unwrap();
+    /// assert_eq!(scores.values(), &[4, 9, 2]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn top_k_multi(&self, keys: &[(usize, SortOrder)], k: usize) -> crate::Result<RecordBatch> {
+        let Some((&(column_index, order), rest)) = keys.split_first() else {
+            return Err(Error::InvalidInput("Top-K needs at least one sort key".to_string()));
+        };
+        let ties =
+            if rest.is_empty() { TieBreak::RowIndex } else { TieBreak::Columns(rest.to_vec()) };
+        self.top_k_with_ties(column_index, k, order, &ties)
+    }
 }
 
 impl TopKSelection for RecordBatch {
-    fn top_k(&self, column_index: usize, k: usize, order: SortOrder) -> crate::Result<RecordBatch> {
-        // Validate inputs
-        if k == 0 {
-            return Err(Error::InvalidInput("k must be greater than 0".to_string()));
-        }
+    fn top_k_with_ties(
+        &self,
+        column_index: usize,
+        k: usize,
+        order: SortOrder,
+        ties: &TieBreak,
+    ) -> crate::Result<RecordBatch> {
+        let indices = top_k_indices(self, column_index, k, order, ties)?;
 
-        if column_index >= self.num_columns() {
-            return Err(Error::InvalidInput(format!(
-                "Column index {} out of bounds (batch has {} columns)",
-                column_index,
-                self.num_columns()
-            )));
-        }
+        // Build result batch from selected indices
+        build_batch_from_indices(self, &indices)
+    }
+}
 
-        // If k >= num_rows, just sort and return all rows
-        if k >= self.num_rows() {
-            return sort_all_rows(self, column_index, order);
-        }
+/// Rows [`TopKSelection::top_k_with_ties`] selects, in output order
+///
+/// Lets callers rank on a narrow batch of sort keys and gather the other
+/// columns for the selected rows only (late materialization).
+///
+/// # Errors
+/// Returns error if `k` is 0, a key column is out of bounds, or its type
+/// is not sortable
+pub(crate) fn top_k_indices(
+    batch: &RecordBatch,
+    column_index: usize,
+    k: usize,
+    order: SortOrder,
+    ties: &TieBreak,
+) -> crate::Result<Vec<usize>> {
+    // Validate inputs
+    if k == 0 {
+        return Err(Error::InvalidInput("k must be greater than 0".to_string()));
+    }
 
-        // Use heap-based Top-K selection
-        let column = self.column(column_index);
-        let indices = select_top_k_indices(column, k, order)?;
+    if column_index >= batch.num_columns() {
+        return Err(Error::InvalidInput(format!(
+            "Column index {} out of bounds (batch has {} columns)",
+            column_index,
+            batch.num_columns()
+        )));
+    }
+    ties.validate(batch.num_columns())?;
 
-        // Build result batch from selected indices
-        build_batch_from_indices(self, &indices)
+    // Sort when k covers all (or a large fraction of) the rows
+    if TopKStrategy::choose(k, batch.num_rows()) == TopKStrategy::Sort {
+        let limit = (k < batch.num_rows()).then_some(k);
+        let indices = sorted_indices(batch, column_index, order, ties, limit)?;
+        return Ok(indices.values().iter().map(|&i| i as usize).collect());
     }
+
+    // Use heap-based Top-K selection
+    let column = batch.column(column_index);
+    let tie_rows = ties.encode(batch)?;
+    select_top_k_indices(column, k, order, tie_rows.as_ref())
 }
 
 /// Select top K indices using min-heap algorithm
 ///
 /// Time complexity: O(N log K) where N = number of rows, K = selection size
 /// Space complexity: O(K) for the heap
+#[allow(clippy::too_many_lines)]
 fn select_top_k_indices(
     column: &ArrayRef,
     k: usize,
     order: SortOrder,
+    ties: Option<&Rows>,
 ) -> crate::Result<Vec<usize>> {
     match column.data_type() {
         arrow::datatypes::DataType::Int32 => {
             let array = column.as_any().downcast_ref::<Int32Array>().ok_or_else(|| {
                 Error::Other("Failed to downcast Int32 column to Int32Array".to_string())
             })?;
-            select_top_k_typed(array.len(), k, order, |i| array.is_null(i), |i| array.value(i))
+            select_top_k_typed(
+                array.len(),
+                k,
+                order,
+                ties,
+                |i| array.is_null(i),
+                |i| array.value(i),
+            )
         }
         arrow::datatypes::DataType::Int64 => {
             let array = column.as_any().downcast_ref::<Int64Array>().ok_or_else(|| {
                 Error::Other("Failed to downcast Int64 column to Int64Array".to_string())
             })?;
-            select_top_k_typed(array.len(), k, order, |i| array.is_null(i), |i| array.value(i))
+            select_top_k_typed(
+                array.len(),
+                k,
+                order,
+                ties,
+                |i| array.is_null(i),
+                |i| array.value(i),
+            )
         }
         arrow::datatypes::DataType::Float32 => {
             let array = column.as_any().downcast_ref::<Float32Array>().ok_or_else(|| {
                 Error::Other("Failed to downcast Float32 column to Float32Array".to_string())
             })?;
-            select_top_k_typed(array.len(), k, order, |i| array.is_null(i), |i| array.value(i))
+            select_top_k_typed(
+                array.len(),
+                k,
+                order,
+                ties,
+                |i| array.is_null(i),
+                |i| TotalOrder(array.value(i)),
+            )
         }
         arrow::datatypes::DataType::Float64 => {
             let array = column.as_any().downcast_ref::<Float64Array>().ok_or_else(|| {
                 Error::Other("Failed to downcast Float64 column to Float64Array".to_string())
             })?;
-            select_top_k_typed(array.len(), k, order, |i| array.is_null(i), |i| array.value(i))
+            select_top_k_typed(
+                array.len(),
+                k,
+                order,
+                ties,
+                |i| array.is_null(i),
+                |i| TotalOrder(array.value(i)),
+            )
+        }
+        arrow::datatypes::DataType::Int8 => {
+            select_top_k_primitive::<Int8Type>(column, k, order, ties)
+        }
+        arrow::datatypes::DataType::Int16 => {
+            select_top_k_primitive::<Int16Type>(column, k, order, ties)
+        }
+        arrow::datatypes::DataType::UInt8 => {
+            select_top_k_primitive::<UInt8Type>(column, k, order, ties)
         }
+        arrow::datatypes::DataType::UInt16 => {
+            select_top_k_primitive::<UInt16Type>(column, k, order, ties)
+        }
+        arrow::datatypes::DataType::UInt32 => {
+            select_top_k_primitive::<UInt32Type>(column, k, order, ties)
+        }
+        arrow::datatypes::DataType::UInt64 => {
+            select_top_k_primitive::<UInt64Type>(column, k, order, ties)
+        }
+        arrow::datatypes::DataType::Utf8 => {
+            let array = column.as_string_opt::<i32>().ok_or_else(|| {
+                Error::Other("Failed to downcast Utf8 column to StringArray".to_string())
+            })?;
+            select_top_k_typed(
+                array.len(),
+                k,
+                order,
+                ties,
+                |i| array.is_null(i),
+                |i| array.value(i),
+            )
+        }
+        arrow::datatypes::DataType::LargeUtf8 => {
+            let array = column.as_string_opt::<i64>().ok_or_else(|| {
+                Error::Other("Failed to downcast LargeUtf8 column to LargeStringArray".to_string())
+            })?;
+            select_top_k_typed(
+                array.len(),
+                k,
+                order,
+                ties,
+                |i| array.is_null(i),
+                |i| array.value(i),
+            )
+        }
+        arrow::datatypes::DataType::Dictionary(_, value)
+            if value.as_ref() == &arrow::datatypes::DataType::Utf8 =>
+        {
+            // Compare the decoded strings without materializing them
+            let dictionary = column.as_any_dictionary();
+            let values = dictionary.values().as_string_opt::<i32>().ok_or_else(|| {
+                Error::Other("Failed to downcast dictionary values to StringArray".to_string())
+            })?;
+            let keys = dictionary.normalized_keys();
+            let nulls = column.logical_nulls();
+            select_top_k_typed(
+                column.len(),
+                k,
+                order,
+                ties,
+                |i| nulls.as_ref().is_some_and(|nulls| nulls.is_null(i)),
+                |i| values.value(keys[i]),
+            )
+        }
+        arrow::datatypes::DataType::Date32 => {
+            select_top_k_primitive::<Date32Type>(column, k, order, ties)
+        }
+        arrow::datatypes::DataType::Date64 => {
+            select_top_k_primitive::<Date64Type>(column, k, order, ties)
+        }
+        arrow::datatypes::DataType::Timestamp(unit, _) => match unit {
+            TimeUnit::Second => {
+                select_top_k_primitive::<TimestampSecondType>(column, k, order, ties)
+            }
+            TimeUnit::Millisecond => {
+                select_top_k_primitive::<TimestampMillisecondType>(column, k, order, ties)
+            }
+            TimeUnit::Microsecond => {
+                select_top_k_primitive::<TimestampMicrosecondType>(column, k, order, ties)
+            }
+            TimeUnit::Nanosecond => {
+                select_top_k_primitive::<TimestampNanosecondType>(column, k, order, ties)
+            }
+        },
         dt => Err(Error::InvalidInput(format!("Top-K not supported for data type: {dt:?}"))),
     }
 }
 
-// Heap item for descending order (min-heap: keep smallest at top, so we can find largest K)
-#[derive(Debug)]
-struct MinHeapItem<V> {
-    value: V,
-    index: usize,
-}
+/// Float compared by `total_cmp`, the order Arrow's sort kernels use
+#[derive(Debug, Clone, Copy)]
+struct TotalOrder<T>(T);
 
-impl<V: PartialOrd> PartialEq for MinHeapItem<V> {
+impl<T: ArrowNativeTypeOp> PartialEq for TotalOrder<T> {
     fn eq(&self, other: &Self) -> bool {
-        self.value.partial_cmp(&other.value) == Some(Ordering::Equal)
-    }
-}
-
-impl<V: PartialOrd> Eq for MinHeapItem<V> {}
-
-impl<V: PartialOrd> Ord for MinHeapItem<V> {
-    fn cmp(&self, other: &Self) -> Ordering {
-        // Reverse comparison for min-heap (smallest at top)
-        other.value.partial_cmp(&self.value).unwrap_or(Ordering::Equal)
+        self.0.is_eq(other.0)
     }
 }
 
-impl<V: PartialOrd> PartialOrd for MinHeapItem<V> {
+impl<T: ArrowNativeTypeOp> PartialOrd for TotalOrder<T> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+        Some(self.0.compare(other.0))
     }
 }
 
-// Heap item for ascending order (max-heap: keep largest at top, so we can find smallest K)
+/// Candidate row in the bounded heap
+///
+/// Orders by rank: `Greater` means the row comes later in the output, so a
+/// max-heap keeps the worst of the current K on top. Ranks compare the
+/// sort value (NULL last in either direction, like the sort fallback), then
+/// the tie-break columns, then the row index, which makes every rank
+/// distinct and the selection deterministic.
 #[derive(Debug)]
-struct MaxHeapItem<V> {
-    value: V,
+struct Ranked<'a, V> {
+    /// Sort value (`None` for NULL)
+    value: Option<V>,
+    tie: Option<Row<'a>>,
     index: usize,
+    descending: bool,
 }
 
-impl<V: PartialOrd> PartialEq for MaxHeapItem<V> {
+impl<V: PartialOrd> PartialEq for Ranked<'_, V> {
     fn eq(&self, other: &Self) -> bool {
-        self.value.partial_cmp(&other.value) == Some(Ordering::Equal)
+        self.cmp(other) == Ordering::Equal
     }
 }
 
-impl<V: PartialOrd> Eq for MaxHeapItem<V> {}
+impl<V: PartialOrd> Eq for Ranked<'_, V> {}
 
-impl<V: PartialOrd> Ord for MaxHeapItem<V> {
+impl<V: PartialOrd> Ord for Ranked<'_, V> {
     fn cmp(&self, other: &Self) -> Ordering {
-        // Normal comparison for max-heap (largest at top)
-        self.value.partial_cmp(&other.value).unwrap_or(Ordering::Equal)
+        let primary = match (&self.value, &other.value) {
+            (Some(value), Some(other)) => {
+                let primary = value.partial_cmp(other).unwrap_or(Ordering::Equal);
+                if self.descending {
+                    primary.reverse()
+                } else {
+                    primary
+                }
+            }
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        primary.then_with(|| self.tie.cmp(&other.tie)).then_with(|| self.index.cmp(&other.index))
     }
 }
 
-impl<V: PartialOrd> PartialOrd for MaxHeapItem<V> {
+impl<V: PartialOrd> PartialOrd for Ranked<'_, V> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-/// Collect top-K indices from a min-heap (descending order: find largest K values)
-fn collect_top_k_descending<V: PartialOrd>(
+/// Generic top-K selection for any Arrow array with `PartialOrd` values
+#[allow(clippy::unnecessary_wraps)]
+fn select_top_k_typed<V: PartialOrd>(
     len: usize,
     k: usize,
+    order: SortOrder,
+    ties: Option<&Rows>,
     is_null: impl Fn(usize) -> bool,
     get_value: impl Fn(usize) -> V,
-) -> Vec<usize> {
-    let mut heap: BinaryHeap<MinHeapItem<V>> = BinaryHeap::with_capacity(k);
+) -> crate::Result<Vec<usize>> {
+    let descending = matches!(order, SortOrder::Descending);
+    let mut heap: BinaryHeap<Ranked<'_, V>> = BinaryHeap::with_capacity(k);
 
     for index in 0..len {
-        if !is_null(index) {
-            let value = get_value(index);
-            if heap.len() < k {
-                heap.push(MinHeapItem { value, index });
-            } else if let Some(top) = heap.peek() {
-                if value.partial_cmp(&top.value) == Some(Ordering::Greater) {
-                    heap.pop();
-                    heap.push(MinHeapItem { value, index });
-                }
-            }
+        let candidate = Ranked {
+            value: (!is_null(index)).then(|| get_value(index)),
+            tie: ties.map(|rows| rows.row(index)),
+            index,
+            descending,
+        };
+        if heap.len() < k {
+            heap.push(candidate);
+        } else if heap.peek().is_some_and(|worst| candidate < *worst) {
+            heap.pop();
+            heap.push(candidate);
         }
     }
 
-    let mut result: Vec<_> = heap.into_vec();
-    result.sort_by(|a, b| b.value.partial_cmp(&a.value).unwrap_or(Ordering::Equal));
-    result.into_iter().map(|item| item.index).collect()
+    Ok(heap.into_sorted_vec().into_iter().map(|item| item.index).collect())
 }
 
-/// Collect top-K indices from a max-heap (ascending order: find smallest K values)
-fn collect_top_k_ascending<V: PartialOrd>(
-    len: usize,
+/// Top-K over narrow and unsigned integer columns, compared in their native type
+fn select_top_k_primitive<T>(
+    column: &ArrayRef,
     k: usize,
-    is_null: impl Fn(usize) -> bool,
-    get_value: impl Fn(usize) -> V,
-) -> Vec<usize> {
-    let mut heap: BinaryHeap<MaxHeapItem<V>> = BinaryHeap::with_capacity(k);
-
-    for index in 0..len {
-        if !is_null(index) {
-            let value = get_value(index);
-            if heap.len() < k {
-                heap.push(MaxHeapItem { value, index });
-            } else if let Some(top) = heap.peek() {
-                if value.partial_cmp(&top.value) == Some(Ordering::Less) {
-                    heap.pop();
-                    heap.push(MaxHeapItem { value, index });
-                }
-            }
-        }
-    }
+    order: SortOrder,
+    ties: Option<&Rows>,
+) -> crate::Result<Vec<usize>>
+where
+    T: ArrowPrimitiveType,
+    T::Native: PartialOrd,
+{
+    let array = downcast_primitive::<T>(column)?;
+    select_top_k_typed(array.len(), k, order, ties, |i| array.is_null(i), |i| array.value(i))
+}
 
-    let mut result: Vec<_> = heap.into_vec();
-    result.sort_by(|a, b| a.value.partial_cmp(&b.value).unwrap_or(Ordering::Equal));
-    result.into_iter().map(|item| item.index).collect()
+fn downcast_primitive<T: ArrowPrimitiveType>(
+    column: &ArrayRef,
+) -> crate::Result<&PrimitiveArray<T>> {
+    column.as_primitive_opt::<T>().ok_or_else(|| {
+        Error::Other(format!("Failed to downcast {:?} column to PrimitiveArray", T::DATA_TYPE))
+    })
 }
 
-/// Generic top-K selection for any Arrow array with `PartialOrd` values
-#[allow(clippy::unnecessary_wraps)]
-fn select_top_k_typed<V: PartialOrd>(
-    len: usize,
-    k: usize,
-    order: SortOrder,
-    is_null: impl Fn(usize) -> bool,
-    get_value: impl Fn(usize) -> V,
-) -> crate::Result<Vec<usize>> {
-    let indices = match order {
-        SortOrder::Descending => collect_top_k_descending(len, k, is_null, get_value),
-        SortOrder::Ascending => collect_top_k_ascending(len, k, is_null, get_value),
-    };
-    Ok(indices)
+/// Gather rows of a column by index with Arrow's `take`, preserving the
+/// column type (including a timestamp's time zone) and its NULLs
+fn take_rows(column: &ArrayRef, indices: &[usize]) -> crate::Result<ArrayRef> {
+    let indices = UInt64Array::from_iter_values(indices.iter().map(|&idx| idx as u64));
+    compute::take(column.as_ref(), &indices, None)
+        .map_err(|e| Error::StorageError(format!("Failed to gather Top-K rows: {e}")))
 }
 
 /// Build a new record batch from selected row indices
@@ -287,45 +657,25 @@ fn build_batch_from_indices(batch: &RecordBatch, indices: &[usize]) -> crate::Re
 
     let mut new_columns: Vec<ArrayRef> = Vec::with_capacity(batch.num_columns());
 
-    for col_idx in 0..batch.num_columns() {
-        let column = batch.column(col_idx);
-
-        let new_array: ArrayRef = match column.data_type() {
-            DataType::Int32 => {
-                let array = column.as_any().downcast_ref::<Int32Array>().ok_or_else(|| {
-                    Error::Other("Failed to downcast Int32 column to Int32Array".to_string())
-                })?;
-                let values: Vec<i32> = indices.iter().map(|&idx| array.value(idx)).collect();
-                Arc::new(Int32Array::from(values))
-            }
-            DataType::Int64 => {
-                let array = column.as_any().downcast_ref::<Int64Array>().ok_or_else(|| {
-                    Error::Other("Failed to downcast Int64 column to Int64Array".to_string())
-                })?;
-                let values: Vec<i64> = indices.iter().map(|&idx| array.value(idx)).collect();
-                Arc::new(Int64Array::from(values))
-            }
-            DataType::Float32 => {
-                let array = column.as_any().downcast_ref::<Float32Array>().ok_or_else(|| {
-                    Error::Other("Failed to downcast Float32 column to Float32Array".to_string())
-                })?;
-                let values: Vec<f32> = indices.iter().map(|&idx| array.value(idx)).collect();
-                Arc::new(Float32Array::from(values))
-            }
-            DataType::Float64 => {
-                let array = column.as_any().downcast_ref::<Float64Array>().ok_or_else(|| {
-                    Error::Other("Failed to downcast Float64 column to Float64Array".to_string())
-                })?;
-                let values: Vec<f64> = indices.iter().map(|&idx| array.value(idx)).collect();
-                Arc::new(Float64Array::from(values))
-            }
-            DataType::Utf8 => {
-                let array = column.as_any().downcast_ref::<StringArray>().ok_or_else(|| {
-                    Error::Other("Failed to downcast Utf8 column to StringArray".to_string())
-                })?;
-                let values: Vec<&str> = indices.iter().map(|&idx| array.value(idx)).collect();
-                Arc::new(StringArray::from(values))
-            }
+    for column in batch.columns() {
+        let new_array = match column.data_type() {
+            DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+            | DataType::Float32
+            | DataType::Float64
+            | DataType::Boolean
+            | DataType::Utf8
+            | DataType::LargeUtf8
+            | DataType::Date32
+            | DataType::Date64
+            | DataType::Timestamp(..)
+            | DataType::Dictionary(..) => take_rows(column, indices)?,
             dt => {
                 return Err(Error::InvalidInput(format!(
                     "Top-K not implemented for column data type: {dt:?}"
@@ -340,30 +690,77 @@ fn build_batch_from_indices(batch: &RecordBatch, indices: &[usize]) -> crate::Re
         .map_err(|e| Error::StorageError(format!("Failed to create result batch: {e}")))
 }
 
-/// Fallback: sort all rows when k >= `num_rows`
-fn sort_all_rows(
+/// Row indices in `order` on `column_index`, ties broken by `ties` (first `limit` only)
+///
+/// A lexicographic sort on (key, tie-break columns, row index); the row
+/// index makes it stable, unlike a single-column Arrow sort.
+///
+/// # Errors
+/// Returns error if a sort column type is not sortable
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) fn sorted_indices(
     batch: &RecordBatch,
     column_index: usize,
     order: SortOrder,
-) -> crate::Result<RecordBatch> {
-    use arrow::compute::sort_to_indices;
-
-    let sort_options = SortOptions::from(order);
-    let indices = sort_to_indices(batch.column(column_index).as_ref(), Some(sort_options), None)
-        .map_err(|e| Error::StorageError(format!("Failed to sort: {e}")))?;
-
-    // Convert indices to usize vec
-    let indices_array =
-        indices.as_any().downcast_ref::<arrow::array::UInt32Array>().ok_or_else(|| {
-            Error::Other(
-                "Failed to downcast sort indices to UInt32Array (expected from sort_to_indices)"
-                    .to_string(),
-            )
-        })?;
-    let indices_vec: Vec<usize> =
-        (0..indices_array.len()).map(|i| indices_array.value(i) as usize).collect();
+    ties: &TieBreak,
+    limit: Option<usize>,
+) -> crate::Result<arrow::array::UInt32Array> {
+    let key = |index: usize, order: SortOrder| SortColumn {
+        values: batch.column(index).clone(),
+        options: Some(order.into()),
+    };
+    let mut columns = vec![key(column_index, order)];
+    columns.extend(ties.columns().iter().map(|&(index, order)| key(index, order)));
+    columns.push(SortColumn {
+        values: Arc::new(arrow::array::UInt32Array::from_iter_values(0..batch.num_rows() as u32)),
+        options: None,
+    });
+
+    lexsort_to_indices(&columns, limit)
+        .map_err(|e| Error::StorageError(format!("Failed to sort: {e}")))
+}
 
-    build_batch_from_indices(batch, &indices_vec)
+/// Exact `PERCENTILE_CONT`: the value at fraction `p` (clamped to `[0, 1]`)
+/// of the non-null values of numeric `column`, interpolated linearly
+/// between the two nearest ranks (`None` without values)
+///
+/// Only the ranks around `p` are selected, by Top-K from the nearer end:
+/// a small heap for tail percentiles such as p99, a partial sort near the
+/// median.
+///
+/// # Errors
+/// Returns error if `column` is not numeric
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss, clippy::cast_sign_loss)]
+pub(crate) fn percentile_cont(column: &ArrayRef, p: f64) -> crate::Result<Option<f64>> {
+    if !column.data_type().is_numeric() {
+        return Err(Error::InvalidInput(format!(
+            "PERCENTILE_CONT needs a numeric column, got {}",
+            column.data_type()
+        )));
+    }
+    let values = arrow::compute::cast(column, &arrow::datatypes::DataType::Float64)?;
+    let n = values.len() - values.null_count();
+    if n == 0 {
+        return Ok(None);
+    }
+
+    let position = p.clamp(0.0, 1.0) * (n - 1) as f64;
+    let lower = position.floor() as usize;
+    let upper = (lower + 1).min(n - 1);
+    let batch = RecordBatch::try_from_iter([("value", Arc::clone(&values))])?;
+    let (lower, upper) = if upper < n - lower {
+        let ranked =
+            top_k_indices(&batch, 0, upper + 1, SortOrder::Ascending, &TieBreak::RowIndex)?;
+        (ranked[lower], ranked[upper])
+    } else {
+        let ranked =
+            top_k_indices(&batch, 0, n - lower, SortOrder::Descending, &TieBreak::RowIndex)?;
+        (ranked[n - 1 - lower], ranked[n - 1 - upper])
+    };
+
+    let values = values.as_primitive::<arrow::datatypes::Float64Type>();
+    let (low, high) = (values.value(lower), values.value(upper));
+    Ok(Some((position - position.floor()).mul_add(high - low, low)))
 }
 
 #[cfg(test)]
@@ -376,6 +773,7 @@ fn sort_all_rows(
 )]
 mod tests {
     use super::*;
+    use arrow::array::{LargeStringArray, StringArray};
     use arrow::datatypes::{DataType, Field, Schema};
     use std::sync::Arc;
 
@@ -694,14 +1092,126 @@ mod tests {
         assert!((col.value(2) - 2.7).abs() < 0.001);
     }
 
+    #[test]
+    fn test_top_k_uint8() {
+        use arrow::array::UInt8Array;
+
+        let schema = Schema::new(vec![Field::new("value", DataType::UInt8, false)]);
+        let values = UInt8Array::from(vec![5u8, 200, 8, 1, 255, 3]);
+        let batch = RecordBatch::try_new(Arc::new(schema), vec![Arc::new(values)]).unwrap();
+
+        let result = batch.top_k(0, 3, SortOrder::Descending).unwrap();
+        assert_eq!(result.schema().field(0).data_type(), &DataType::UInt8);
+
+        let col = result.column(0).as_any().downcast_ref::<UInt8Array>().unwrap();
+        assert_eq!(col.values().to_vec(), vec![255, 200, 8]);
+    }
+
+    #[test]
+    fn test_top_k_int16_ascending_with_negatives() {
+        use arrow::array::Int16Array;
+
+        let schema = Schema::new(vec![
+            Field::new("value", DataType::Int16, false),
+            Field::new("tag", DataType::Int8, false),
+        ]);
+        let values = Int16Array::from(vec![-300i16, 20, -5, 1000, 0]);
+        let tags = arrow::array::Int8Array::from(vec![0i8, 1, 2, 3, 4]);
+        let batch =
+            RecordBatch::try_new(Arc::new(schema), vec![Arc::new(values), Arc::new(tags)]).unwrap();
+
+        let result = batch.top_k(0, 2, SortOrder::Ascending).unwrap();
+
+        let col = result.column(0).as_any().downcast_ref::<Int16Array>().unwrap();
+        assert_eq!(col.values().to_vec(), vec![-300, -5]);
+        let tags = result.column(1).as_any().downcast_ref::<arrow::array::Int8Array>().unwrap();
+        assert_eq!(tags.values().to_vec(), vec![0, 2]);
+    }
+
+    #[test]
+    fn test_top_k_uint64_above_i64_max() {
+        use arrow::array::UInt64Array;
+
+        let schema = Schema::new(vec![Field::new("value", DataType::UInt64, false)]);
+        let values = UInt64Array::from(vec![1u64, u64::MAX, 42, u64::MAX - 1]);
+        let batch = RecordBatch::try_new(Arc::new(schema), vec![Arc::new(values)]).unwrap();
+
+        let result = batch.top_k(0, 2, SortOrder::Descending).unwrap();
+
+        let col = result.column(0).as_any().downcast_ref::<UInt64Array>().unwrap();
+        assert_eq!(col.values().to_vec(), vec![u64::MAX, u64::MAX - 1]);
+    }
+
+    #[test]
+    fn test_strategy_heuristic() {
+        assert_eq!(TopKStrategy::choose(10, 10), TopKStrategy::Sort);
+        assert_eq!(TopKStrategy::choose(5, 100), TopKStrategy::Heap);
+        assert_eq!(TopKStrategy::choose(100, 1_000_000), TopKStrategy::Heap);
+        assert_eq!(TopKStrategy::choose(500_000, 1_000_000), TopKStrategy::Sort);
+        // Small inputs stay on the heap even for a large fraction
+        assert_eq!(TopKStrategy::choose(900, 1000), TopKStrategy::Heap);
+    }
+
+    #[test]
+    fn test_sort_fallback_matches_heap() {
+        let n = SORT_FALLBACK_MIN_ROWS * 2;
+        let values: Vec<f64> = (0..n).map(|i| ((i * 7919) % n) as f64).collect();
+        let batch = create_test_batch(values);
+
+        let k = n / 2;
+        assert_eq!(TopKStrategy::choose(k, n), TopKStrategy::Sort);
+        let result = batch.top_k(1, k, SortOrder::Descending).unwrap();
+
+        assert_eq!(result.num_rows(), k);
+        let scores = result.column(1).as_any().downcast_ref::<Float64Array>().unwrap();
+        for i in 0..k {
+            assert_eq!(scores.value(i), (n - 1 - i) as f64);
+        }
+    }
+
+    #[test]
+    fn test_nulls_and_nan_agree_across_strategies() {
+        for n in [10, SORT_FALLBACK_MIN_ROWS * 2] {
+            // NULLs, NaN and many duplicate values
+            let values: Float64Array = (0..n)
+                .map(|i| match i {
+                    _ if i % 5 == 2 => None,
+                    _ if i % 7 == 3 => Some(f64::NAN),
+                    _ => Some(((i * 7919) % 13) as f64),
+                })
+                .collect();
+            let batch = RecordBatch::try_from_iter([("v", Arc::new(values) as ArrayRef)]).unwrap();
+
+            for order in [SortOrder::Ascending, SortOrder::Descending] {
+                let sorted = sorted_indices(&batch, 0, order, &TieBreak::RowIndex, None).unwrap();
+                let sorted: Vec<usize> = sorted.values().iter().map(|&i| i as usize).collect();
+                // Both sides of the k > N/10 threshold, and k >= N
+                for k in [1, 2, n / 10, n / 10 + 1, n / 2, n - 1, n, n + 5] {
+                    let strategy = TopKStrategy::choose(k, n);
+                    let indices = top_k_indices(&batch, 0, k, order, &TieBreak::RowIndex).unwrap();
+                    assert_eq!(indices.len(), k.min(n), "n={n} k={k} {order:?} {strategy:?}");
+                    assert_eq!(indices, sorted[..k.min(n)], "n={n} k={k} {order:?} {strategy:?}");
+                }
+            }
+        }
+
+        // NULLs last either way; NaN above every number
+        let values = Float64Array::from(vec![Some(2.0), None, Some(f64::NAN), Some(1.0)]);
+        let batch = RecordBatch::try_from_iter([("v", Arc::new(values) as ArrayRef)]).unwrap();
+        let ascending = top_k_indices(&batch, 0, 3, SortOrder::Ascending, &TieBreak::RowIndex);
+        assert_eq!(ascending.unwrap(), vec![3, 0, 2]);
+        let descending = top_k_indices(&batch, 0, 3, SortOrder::Descending, &TieBreak::RowIndex);
+        assert_eq!(descending.unwrap(), vec![2, 0, 3]);
+    }
+
     #[test]
     fn test_top_k_unsupported_type() {
-        use arrow::array::StringArray;
+        use arrow::array::BooleanArray;
         use arrow::datatypes::{DataType, Field, Schema};
         use std::sync::Arc;
 
-        let schema = Schema::new(vec![Field::new("value", DataType::Utf8, false)]);
-        let values = StringArray::from(vec!["a", "b", "c"]);
+        let schema = Schema::new(vec![Field::new("value", DataType::Boolean, false)]);
+        let values = BooleanArray::from(vec![true, false, true]);
         let batch = RecordBatch::try_new(Arc::new(schema), vec![Arc::new(values)]).unwrap();
 
         let result = batch.top_k(0, 2, SortOrder::Descending);
@@ -710,83 +1220,220 @@ mod tests {
     }
 
     // ========================================================================
-    // Heap Item Trait Tests (for coverage of MinHeapItem/MaxHeapItem)
+    // Ranked Heap Item and Tie-Break Tests
     // ========================================================================
 
-    #[test]
-    fn test_min_heap_item_eq() {
-        let item1 = MinHeapItem { value: 42i32, index: 0 };
-        let item2 = MinHeapItem { value: 42i32, index: 1 };
-        let item3 = MinHeapItem { value: 43i32, index: 2 };
-
-        assert_eq!(item1, item2);
-        assert_ne!(item1, item3);
+    fn ranked<V>(value: V, index: usize, descending: bool) -> Ranked<'static, V> {
+        Ranked { value: Some(value), tie: None, index, descending }
     }
 
     #[test]
-    fn test_min_heap_item_ord() {
-        let item1 = MinHeapItem { value: 10i32, index: 0 };
-        let item2 = MinHeapItem { value: 20i32, index: 1 };
-        let item3 = MinHeapItem { value: 30i32, index: 2 };
+    fn test_ranked_orders_by_value_then_index() {
+        // Descending: larger values rank first
+        assert!(ranked(30, 2, true) < ranked(20, 1, true));
+        assert!(ranked(10, 0, false) < ranked(20, 1, false));
+        // Equal values: earlier row ranks first in either direction
+        assert!(ranked(42, 0, true) < ranked(42, 1, true));
+        assert!(ranked(3.25f64, 0, false) < ranked(3.25f64, 1, false));
+        assert_ne!(ranked(42, 0, true), ranked(42, 1, true));
+        assert_eq!(ranked(1.5f64, 4, true), ranked(1.5f64, 4, true));
+    }
+
+    fn tie_batch() -> RecordBatch {
+        let schema = Schema::new(vec![
+            Field::new("score", DataType::Int32, false),
+            Field::new("id", DataType::Int32, false),
+        ]);
+        let scores = Int32Array::from((0..100).map(|i| i % 3).collect::<Vec<i32>>());
+        let ids = Int32Array::from((0..100).map(|i| (i * 37) % 100).collect::<Vec<i32>>());
+        RecordBatch::try_new(Arc::new(schema), vec![Arc::new(scores), Arc::new(ids)]).unwrap()
+    }
 
-        // Min-heap: reverse ordering (smaller values at top)
-        assert!(item3 < item2); // 30 < 20 in min-heap ordering
-        assert!(item2 < item1); // 20 < 10 in min-heap ordering
+    fn ids(batch: &RecordBatch) -> Vec<i32> {
+        batch.column(1).as_any().downcast_ref::<Int32Array>().unwrap().values().to_vec()
     }
 
     #[test]
-    fn test_min_heap_item_partial_ord() {
-        let item1 = MinHeapItem { value: 5i32, index: 0 };
-        let item2 = MinHeapItem { value: 10i32, index: 1 };
+    fn test_ties_by_row_index_match_across_strategies() {
+        let batch = tie_batch();
+        // Heap (k=5) and sort fallback (k=100) agree on the stable prefix
+        for order in [SortOrder::Descending, SortOrder::Ascending] {
+            let heap = batch.top_k(0, 5, order).unwrap();
+            let sorted = batch.top_k(0, 100, order).unwrap();
+            assert_eq!(ids(&heap), ids(&sorted)[..5]);
+        }
 
-        assert!(item1.partial_cmp(&item2) == Some(Ordering::Greater));
+        // Score 2 is at rows 2, 5, 8, ...: earliest rows first
+        let top = batch.top_k(0, 3, SortOrder::Descending).unwrap();
+        assert_eq!(ids(&top), vec![74, 85, 96]);
     }
 
     #[test]
-    fn test_max_heap_item_eq() {
-        let item1 = MaxHeapItem { value: 42i32, index: 0 };
-        let item2 = MaxHeapItem { value: 42i32, index: 1 };
-        let item3 = MaxHeapItem { value: 43i32, index: 2 };
+    fn test_ties_by_secondary_column() {
+        let batch = tie_batch();
+        let ties = TieBreak::Columns(vec![(1, SortOrder::Ascending)]);
+        let heap = batch.top_k_with_ties(0, 4, SortOrder::Descending, &ties).unwrap();
+        let sorted = batch.top_k_with_ties(0, 100, SortOrder::Descending, &ties).unwrap();
+
+        assert_eq!(ids(&heap), ids(&sorted)[..4]);
+        // Among the score-2 rows, smallest ids first
+        assert_eq!(ids(&heap), vec![4, 5, 6, 7]);
+
+        let out_of_bounds = TieBreak::Columns(vec![(2, SortOrder::Ascending)]);
+        assert!(batch.top_k_with_ties(0, 4, SortOrder::Descending, &out_of_bounds).is_err());
+    }
 
-        assert_eq!(item1, item2);
-        assert_ne!(item1, item3);
+    // ========================================================================
+    // String, Temporal, and Multi-Key Tests
+    // ========================================================================
+
+    #[test]
+    fn test_top_k_utf8() {
+        let schema = Schema::new(vec![
+            Field::new("name", DataType::Utf8, false),
+            Field::new("id", DataType::Int32, false),
+        ]);
+        let names = StringArray::from(vec!["pear", "apple", "zucchini", "Banana", "fig"]);
+        let id_col = Int32Array::from(vec![0, 1, 2, 3, 4]);
+        let batch = RecordBatch::try_new(Arc::new(schema), vec![Arc::new(names), Arc::new(id_col)])
+            .unwrap();
+
+        let top = batch.top_k(0, 2, SortOrder::Descending).unwrap();
+        assert_eq!(ids(&top), vec![2, 0]);
+        // Bytewise: uppercase sorts before lowercase
+        let bottom = batch.top_k(0, 2, SortOrder::Ascending).unwrap();
+        assert_eq!(ids(&bottom), vec![3, 1]);
+        let sorted = batch.top_k(0, 5, SortOrder::Ascending).unwrap();
+        assert_eq!(ids(&sorted)[..2], ids(&bottom));
     }
 
     #[test]
-    fn test_max_heap_item_ord() {
-        let item1 = MaxHeapItem { value: 10i32, index: 0 };
-        let item2 = MaxHeapItem { value: 20i32, index: 1 };
-        let item3 = MaxHeapItem { value: 30i32, index: 2 };
+    fn test_top_k_large_utf8_roundtrips_type() {
+        let schema = Schema::new(vec![Field::new("name", DataType::LargeUtf8, false)]);
+        let names = LargeStringArray::from(vec!["b", "d", "a", "c"]);
+        let batch = RecordBatch::try_new(Arc::new(schema), vec![Arc::new(names)]).unwrap();
+
+        let top = batch.top_k(0, 2, SortOrder::Descending).unwrap();
+        let names = top.column(0).as_string::<i64>();
+        assert_eq!(names.iter().flatten().collect::<Vec<_>>(), vec!["d", "c"]);
+    }
 
-        // Max-heap: normal ordering (larger values at top)
-        assert!(item3 > item2);
-        assert!(item2 > item1);
+    #[test]
+    fn test_top_k_dictionary_strings() {
+        use arrow::array::DictionaryArray;
+        use arrow::datatypes::Int32Type;
+
+        let names: DictionaryArray<Int32Type> =
+            vec![Some("pear"), None, Some("apple"), Some("pear"), Some("fig")]
+                .into_iter()
+                .collect();
+        let schema = Schema::new(vec![Field::new("name", names.data_type().clone(), true)]);
+        let batch = RecordBatch::try_new(Arc::new(schema), vec![Arc::new(names)]).unwrap();
+
+        let top = batch.top_k(0, 3, SortOrder::Descending).unwrap();
+        let names = arrow::compute::cast(top.column(0), &DataType::Utf8).unwrap();
+        let names = names.as_string::<i32>();
+        assert_eq!(names.iter().flatten().collect::<Vec<_>>(), vec!["pear", "pear", "fig"]);
     }
 
     #[test]
-    fn test_max_heap_item_partial_ord() {
-        let item1 = MaxHeapItem { value: 5i32, index: 0 };
-        let item2 = MaxHeapItem { value: 10i32, index: 1 };
+    fn test_top_k_temporal_columns() {
+        use arrow::array::{Date32Array, Date64Array, TimestampMillisecondArray};
 
-        assert!(item1.partial_cmp(&item2) == Some(Ordering::Less));
+        let tz: Arc<str> = Arc::from("+02:00");
+        let schema = Schema::new(vec![
+            Field::new("day", DataType::Date32, false),
+            Field::new("id", DataType::Int32, false),
+            Field::new("at", DataType::Timestamp(TimeUnit::Millisecond, Some(tz.clone())), false),
+            Field::new("day64", DataType::Date64, false),
+        ]);
+        let at = TimestampMillisecondArray::from(vec![5_000, 1_000, 9_000, 3_000])
+            .with_timezone(tz.clone());
+        let batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Date32Array::from(vec![19_000, 19_500, 18_000, 19_250])),
+                Arc::new(Int32Array::from(vec![0, 1, 2, 3])),
+                Arc::new(at),
+                Arc::new(Date64Array::from(vec![40, 10, 30, 20])),
+            ],
+        )
+        .unwrap();
+
+        let latest_day = batch.top_k(0, 2, SortOrder::Descending).unwrap();
+        assert_eq!(ids(&latest_day), vec![1, 3]);
+        // Timestamp keys keep their unit and time zone in the output
+        let earliest = batch.top_k(2, 2, SortOrder::Ascending).unwrap();
+        assert_eq!(ids(&earliest), vec![1, 3]);
+        assert_eq!(
+            earliest.schema().field(2).data_type(),
+            &DataType::Timestamp(TimeUnit::Millisecond, Some(tz))
+        );
+        let by_day64 = batch.top_k(3, 1, SortOrder::Descending).unwrap();
+        assert_eq!(ids(&by_day64), vec![0]);
     }
 
     #[test]
-    fn test_heap_item_with_floats() {
-        let item1 = MinHeapItem { value: 1.5f64, index: 0 };
-        let item2 = MinHeapItem { value: 2.5f64, index: 1 };
+    fn test_top_k_multi_matches_sort() {
+        let batch = tie_batch();
+        let keys = [(0, SortOrder::Descending), (1, SortOrder::Ascending)];
+        let heap = batch.top_k_multi(&keys, 4).unwrap();
+        let sorted = batch.top_k_multi(&keys, 100).unwrap();
+        assert_eq!(ids(&heap), ids(&sorted)[..4]);
+        assert_eq!(ids(&heap), vec![4, 5, 6, 7]);
+
+        // A single key is plain Top-K
+        let single = batch.top_k_multi(&keys[..1], 3).unwrap();
+        assert_eq!(ids(&single), ids(&batch.top_k(0, 3, SortOrder::Descending).unwrap()));
+        assert!(batch.top_k_multi(&[], 3).is_err());
+    }
 
-        assert_ne!(item1, item2);
-        assert!(item2 < item1); // Min-heap: reverse ordering
+    #[test]
+    fn test_top_k_multi_string_then_number() {
+        let schema = Schema::new(vec![
+            Field::new("team", DataType::Utf8, false),
+            Field::new("id", DataType::Int32, false),
+        ]);
+        let teams = StringArray::from(vec!["red", "blue", "red", "blue", "red"]);
+        let ids_col = Int32Array::from(vec![9, 7, 3, 8, 5]);
+        let batch =
+            RecordBatch::try_new(Arc::new(schema), vec![Arc::new(teams), Arc::new(ids_col)])
+                .unwrap();
+
+        let keys = [(0, SortOrder::Ascending), (1, SortOrder::Descending)];
+        assert_eq!(ids(&batch.top_k_multi(&keys, 3).unwrap()), vec![8, 7, 9]);
     }
 
     #[test]
-    fn test_heap_item_eq_method_with_floats() {
-        let item1 = MaxHeapItem { value: 3.25f64, index: 0 };
-        let item2 = MaxHeapItem { value: 3.25f64, index: 1 };
-        let item3 = MaxHeapItem { value: 2.75f64, index: 2 };
+    fn test_percentile_cont_interpolates_from_either_end() {
+        // Unsorted 1..=10_000, with a NULL after every fifth value
+        let values: Vec<Option<i64>> = (0..10_000)
+            .flat_map(|i| {
+                let value = Some((i * 7919) % 10_000 + 1);
+                if i % 5 == 4 {
+                    vec![value, None]
+                } else {
+                    vec![value]
+                }
+            })
+            .collect();
+        let column: ArrayRef = Arc::new(Int64Array::from(values));
+        for (p, expected) in [(0.0, 1.0), (0.5, 5000.5), (0.99, 9900.01), (1.0, 10_000.0)] {
+            let value = percentile_cont(&column, p).unwrap().unwrap();
+            assert!((value - expected).abs() < 1e-6, "p={p}: {value}");
+        }
+
+        // Ranks near the low end come from an ascending Top-K, near the
+        // high end from a descending one
+        let small: ArrayRef = Arc::new(Float64Array::from(vec![4.0, 1.0, 3.0, 2.0]));
+        for (p, expected) in [(0.25, 1.75), (0.9, 3.7)] {
+            let value = percentile_cont(&small, p).unwrap().unwrap();
+            assert!((value - expected).abs() < 1e-9, "p={p}: {value}");
+        }
 
-        assert!(item1.eq(&item2));
-        assert!(!item1.eq(&item3));
+        let empty: ArrayRef = Arc::new(Int32Array::from(vec![None, None]));
+        assert_eq!(percentile_cont(&empty, 0.5).unwrap(), None);
+        let names: ArrayRef = Arc::new(StringArray::from(vec!["a"]));
+        assert!(percentile_cont(&names, 0.5).is_err());
     }
 }
@@ -0,0 +1,201 @@
+//! Numerically stable variance / standard deviation (Welford)
+//!
+//! The textbook `E[x²] - E[x]²` formula cancels catastrophically when the
+//! mean is large relative to the spread (timestamps, prices in cents).
+//! Welford's online update keeps a running `(count, mean, M2)` instead, and
+//! Chan et al.'s merge combines two such states exactly, which is what makes
+//! the computation parallel:
+//!
+//! - **Scalar**: one state, one element at a time ([`welford_scalar`])
+//! - **SIMD**: [`SIMD_LANES`] independent states updated in lockstep (laid out
+//!   so the compiler vectorizes the loop), merged at the end ([`welford_simd`])
+//! - **GPU**: one state per workgroup, tree-merged in shared memory, partials
+//!   merged on the host in f64 (see `gpu::kernels::welford_f32`)
+//!
+//! All three produce a [`WelfordState`] and share [`WelfordState::merge`],
+//! so results agree up to floating-point rounding.
+//!
+//! References:
+//! - Welford (1962): Note on a method for calculating corrected sums of squares
+//! - Chan, Golub & `LeVeque` (1979): Updating formulae and a pairwise algorithm
+//!
+//! Toyota Way: Jidoka (one merge rule for every backend)
+
+/// Independent accumulators in the SIMD path (one AVX-512 register of f64)
+pub const SIMD_LANES: usize = 8;
+
+/// Which variance estimator to report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VarianceKind {
+    /// Divide by `n` (`VAR_POP`, `STDDEV_POP`)
+    Population,
+    /// Divide by `n - 1` (`VAR_SAMP`, `STDDEV_SAMP`, `VARIANCE`, `STDDEV`)
+    Sample,
+}
+
+/// Running `(count, mean, M2)` summary of a sequence of values
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct WelfordState {
+    /// Values seen
+    pub count: u64,
+    /// Mean of the values seen
+    pub mean: f64,
+    /// Sum of squared deviations from the mean
+    pub m2: f64,
+}
+
+impl WelfordState {
+    /// Fold one value into the state
+    #[allow(clippy::cast_precision_loss)]
+    pub fn push(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    /// Combine two states as if their inputs had been pushed into one
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn merge(self, other: Self) -> Self {
+        if other.count == 0 {
+            return self;
+        }
+        if self.count == 0 {
+            return other;
+        }
+        let count = self.count + other.count;
+        let (n_a, n_b, n) = (self.count as f64, other.count as f64, count as f64);
+        let delta = other.mean - self.mean;
+        Self {
+            count,
+            mean: self.mean + delta * n_b / n,
+            m2: self.m2 + other.m2 + delta * delta * n_a * n_b / n,
+        }
+    }
+
+    /// Variance, or `None` when undefined (no values; one value for `Sample`)
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn variance(&self, kind: VarianceKind) -> Option<f64> {
+        let denominator = match kind {
+            VarianceKind::Population => self.count,
+            VarianceKind::Sample => self.count.checked_sub(1)?,
+        };
+        (denominator > 0).then(|| self.m2 / denominator as f64)
+    }
+
+    /// Standard deviation, or `None` when undefined
+    #[must_use]
+    pub fn stddev(&self, kind: VarianceKind) -> Option<f64> {
+        self.variance(kind).map(f64::sqrt)
+    }
+}
+
+/// Welford summary computed one element at a time (reference implementation)
+pub fn welford_scalar<T: Copy + Into<f64>>(values: &[T]) -> WelfordState {
+    let mut state = WelfordState::default();
+    for &value in values {
+        state.push(value.into());
+    }
+    state
+}
+
+/// Welford summary computed with [`SIMD_LANES`] interleaved accumulators
+///
+/// Lane `i` sees elements `i, i + LANES, ...`; the per-lane update has no
+/// cross-lane dependency so it vectorizes, and the lanes (plus the tail)
+/// are combined with [`WelfordState::merge`].
+#[allow(clippy::cast_precision_loss)]
+pub fn welford_simd<T: Copy + Into<f64>>(values: &[T]) -> WelfordState {
+    let mut counts = [0.0_f64; SIMD_LANES];
+    let mut means = [0.0_f64; SIMD_LANES];
+    let mut m2s = [0.0_f64; SIMD_LANES];
+
+    let chunks = values.chunks_exact(SIMD_LANES);
+    let tail = chunks.remainder();
+    for chunk in chunks {
+        for lane in 0..SIMD_LANES {
+            let value: f64 = chunk[lane].into();
+            counts[lane] += 1.0;
+            let delta = value - means[lane];
+            means[lane] += delta / counts[lane];
+            m2s[lane] += delta * (value - means[lane]);
+        }
+    }
+
+    let lanes_count = (values.len() / SIMD_LANES) as u64;
+    let mut state = WelfordState::default();
+    for lane in 0..SIMD_LANES {
+        state = state.merge(WelfordState { count: lanes_count, mean: means[lane], m2: m2s[lane] });
+    }
+    state.merge(welford_scalar(tail))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[allow(clippy::cast_precision_loss)]
+    fn two_pass_variance(values: &[f64], kind: VarianceKind) -> f64 {
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let ss: f64 = values.iter().map(|v| (v - mean) * (v - mean)).sum();
+        match kind {
+            VarianceKind::Population => ss / n,
+            VarianceKind::Sample => ss / (n - 1.0),
+        }
+    }
+
+    #[test]
+    fn test_known_variance() {
+        let values = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        let state = welford_scalar(&values);
+        assert_eq!(state.count, 8);
+        assert!((state.mean - 5.0).abs() < 1e-12);
+        assert!((state.variance(VarianceKind::Population).unwrap() - 4.0).abs() < 1e-12);
+        assert!((state.stddev(VarianceKind::Population).unwrap() - 2.0).abs() < 1e-12);
+        assert!((state.variance(VarianceKind::Sample).unwrap() - 32.0 / 7.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_undefined_variance() {
+        assert_eq!(welford_scalar::<f64>(&[]).variance(VarianceKind::Population), None);
+        assert_eq!(welford_scalar(&[3.0]).variance(VarianceKind::Sample), None);
+        assert_eq!(welford_scalar(&[3.0]).variance(VarianceKind::Population), Some(0.0));
+    }
+
+    #[test]
+    fn test_simd_matches_scalar_with_tail() {
+        let values: Vec<f64> = (0..1003).map(|i| f64::from(i % 97) * 0.5 - 7.0).collect();
+        let scalar = welford_scalar(&values);
+        let simd = welford_simd(&values);
+
+        assert_eq!(simd.count, scalar.count);
+        assert!((simd.mean - scalar.mean).abs() < 1e-9);
+        let expected = two_pass_variance(&values, VarianceKind::Sample);
+        assert!((simd.variance(VarianceKind::Sample).unwrap() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_stable_with_large_offset() {
+        // Naive E[x²] - E[x]² loses every significant digit here
+        let values: Vec<f64> = (0..1000).map(|i| 1e9 + f64::from(i % 10)).collect();
+        let expected = two_pass_variance(&values, VarianceKind::Population);
+        let simd = welford_simd(&values).variance(VarianceKind::Population).unwrap();
+        assert!((simd - expected).abs() < 1e-6, "{simd} vs {expected}");
+    }
+
+    #[test]
+    fn test_merge_is_order_independent() {
+        let values: Vec<f64> = (0..100).map(f64::from).collect();
+        let (left, right) = values.split_at(37);
+        let merged = welford_scalar(left).merge(welford_scalar(right));
+        let reversed = welford_scalar(right).merge(welford_scalar(left));
+        let whole = welford_scalar(&values);
+
+        assert_eq!(merged.count, whole.count);
+        assert!((merged.m2 - whole.m2).abs() < 1e-9);
+        assert!((reversed.m2 - whole.m2).abs() < 1e-9);
+    }
+}
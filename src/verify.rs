@@ -0,0 +1,254 @@
+//! Backend equivalence harness over the real backends
+//!
+//! `tests/backend_equivalence_tests.rs` checks equivalence properties with
+//! mock backends, which cannot catch a GPU kernel that diverges. An
+//! [`EquivalenceHarness`] runs one aggregation three ways and fails with
+//! [`Error::BackendMismatch`] unless they agree:
+//!
+//! - **GPU**: the [`GpuEngine`] kernels
+//! - **SIMD**: the query executor's CPU path (trueno and the
+//!   [`reduce`](crate::reduce) kernels)
+//! - **Scalar**: a plain loop in `f64`, the reference
+//!
+//! Integer results of GPU and SIMD must be identical; float results, and
+//! every comparison with the scalar reference, must agree within a
+//! relative tolerance ([`DEFAULT_TOLERANCE`]). The harness works in tests
+//! and as a runtime self-check: [`EquivalenceHarness::self_check`] runs a
+//! fixed set of columns through every supported kernel, so a service can
+//! refuse the GPU backend on startup when a driver miscompiles a shader.
+//!
+//! Toyota Way: Jidoka (stop on the first wrong answer, on the real hardware)
+
+use crate::gpu::GpuEngine;
+use crate::query::{AggregateFunction, QueryExecutor};
+use crate::{Error, Result};
+use arrow::array::{
+    make_array, Array, ArrayRef, Float32Array, Float64Array, Int32Array, Int64Array,
+};
+use arrow::compute;
+use arrow::datatypes::DataType;
+use std::sync::Arc;
+
+/// Relative difference allowed between float results (and against the
+/// scalar reference)
+pub const DEFAULT_TOLERANCE: f64 = 1e-5;
+
+/// Rows in each [`self_check`](EquivalenceHarness::self_check) column:
+/// several workgroups plus a ragged tail
+const SELF_CHECK_ROWS: i32 = 100_003;
+
+/// Results of one aggregation on every backend, as `f64`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Equivalence {
+    /// Aggregation checked
+    pub function: AggregateFunction,
+    /// Arrow type of the input column
+    pub data_type: DataType,
+    /// [`GpuEngine`] result
+    pub gpu: f64,
+    /// SIMD executor result
+    pub simd: f64,
+    /// Scalar reference result
+    pub scalar: f64,
+}
+
+/// Runs aggregations on the GPU, SIMD and scalar backends and compares them
+pub struct EquivalenceHarness<'a> {
+    engine: &'a GpuEngine,
+    tolerance: f64,
+}
+
+impl<'a> EquivalenceHarness<'a> {
+    /// Harness over `engine` with [`DEFAULT_TOLERANCE`]
+    #[must_use]
+    pub const fn new(engine: &'a GpuEngine) -> Self {
+        Self { engine, tolerance: DEFAULT_TOLERANCE }
+    }
+
+    /// Allow a relative difference of `tolerance` between float results
+    #[must_use]
+    pub const fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Run `func` over `data` on every backend and compare the results
+    ///
+    /// GPU kernels exist for SUM, AVG, COUNT, MIN and MAX over `Int32`,
+    /// `Int64`, `UInt32` and `UInt64`, and SUM, AVG and COUNT over `Float32`
+    /// and `Float64`.
+    ///
+    /// # Errors
+    /// Returns [`Error::BackendMismatch`] if the backends disagree,
+    /// [`Error::InvalidInput`] if `data` has NULLs or no GPU kernel covers
+    /// `func` over its type, or error if a backend fails
+    pub async fn check(&self, func: AggregateFunction, data: &dyn Array) -> Result<Equivalence> {
+        if data.null_count() > 0 {
+            return Err(Error::InvalidInput(
+                "Equivalence checks need a column without NULLs".to_string(),
+            ));
+        }
+        let column = make_array(data.to_data());
+        let gpu = first_value(&self.gpu(func, &column).await?)?;
+        let (simd, _) = QueryExecutor::execute_single_aggregation(func, &column)?;
+        let simd = first_value(&simd)?;
+        let scalar = scalar_reference(func, &column)?;
+
+        let exact = column.data_type().is_integer() && func != AggregateFunction::Avg;
+        let tolerance = if exact { 0.0 } else { self.tolerance };
+        let agree = close(gpu, simd, tolerance)
+            && close(gpu, scalar, self.tolerance)
+            && close(simd, scalar, self.tolerance);
+        if !agree {
+            return Err(Error::BackendMismatch {
+                gpu_result: format!("{}({:?}) = {gpu}", func.sql_name(), column.data_type()),
+                simd_result: format!("{simd} (scalar reference {scalar})"),
+            });
+        }
+        Ok(Equivalence { function: func, data_type: column.data_type().clone(), gpu, simd, scalar })
+    }
+
+    /// Check SUM, AVG, COUNT, MIN and MAX over a built-in `Int32` column,
+    /// and SUM, AVG and COUNT over `Float32` and `Float64` ones
+    ///
+    /// Meant for startup: a GPU that fails here should not serve queries.
+    ///
+    /// # Errors
+    /// Returns the first failing [`check`](Self::check)
+    pub async fn self_check(&self) -> Result<Vec<Equivalence>> {
+        // Mixed signs, no overflow, and floats exact in binary
+        let ints: Vec<i32> = (0..SELF_CHECK_ROWS).map(|i| i * 7919 % 20_011 - 10_000).collect();
+        let floats: Vec<f32> = ints
+            .iter()
+            .map(|&v| i16::try_from(v).map(|v| f32::from(v) * 0.25))
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|e| Error::Other(format!("Self-check value out of range: {e}")))?;
+        let columns: [ArrayRef; 3] = [
+            Arc::new(Int32Array::from(ints)),
+            Arc::new(Float64Array::from(floats.iter().copied().map(f64::from).collect::<Vec<_>>())),
+            Arc::new(Float32Array::from(floats)),
+        ];
+
+        let mut checks = Vec::new();
+        for column in &columns {
+            for func in [
+                AggregateFunction::Sum,
+                AggregateFunction::Avg,
+                AggregateFunction::Count,
+                AggregateFunction::Min,
+                AggregateFunction::Max,
+            ] {
+                let float = !column.data_type().is_integer();
+                if float && matches!(func, AggregateFunction::Min | AggregateFunction::Max) {
+                    continue;
+                }
+                checks.push(self.check(func, column.as_ref()).await?);
+            }
+        }
+        Ok(checks)
+    }
+
+    /// `func` over `data` on the GPU, as a one-row column
+    #[allow(clippy::cast_possible_wrap)]
+    async fn gpu(&self, func: AggregateFunction, data: &ArrayRef) -> Result<ArrayRef> {
+        use AggregateFunction::{Avg, Count, Max, Min, Sum};
+        let data_type = data.data_type();
+        Ok(match (data_type, func) {
+            (
+                DataType::Int32 | DataType::Int64 | DataType::UInt32 | DataType::UInt64,
+                Sum | Avg | Count | Min | Max,
+            ) => self.engine.multi_aggregate(data.as_ref(), &[func]).await?.remove(0),
+            (_, Count) => {
+                Arc::new(Int64Array::from(vec![self.engine.count(data.as_ref()).await? as i64]))
+            }
+            (DataType::Float32, Sum | Avg) => {
+                let data = data.as_any().downcast_ref::<Float32Array>().ok_or_else(|| {
+                    Error::Other("Failed to downcast to Float32Array".to_string())
+                })?;
+                let value = if func == Sum {
+                    self.engine.sum_f32(data).await?
+                } else {
+                    self.engine.avg_f32(data).await?
+                };
+                Arc::new(Float32Array::from(vec![value]))
+            }
+            (DataType::Float64, Sum | Avg) => {
+                let data = data.as_any().downcast_ref::<Float64Array>().ok_or_else(|| {
+                    Error::Other("Failed to downcast to Float64Array".to_string())
+                })?;
+                let value = if func == Sum {
+                    self.engine.sum_f64(data).await?
+                } else {
+                    self.engine.avg_f64(data).await?
+                };
+                Arc::new(Float64Array::from(vec![value]))
+            }
+            _ => {
+                return Err(Error::InvalidInput(format!(
+                    "No GPU kernel for {} over {data_type:?}",
+                    func.sql_name()
+                )))
+            }
+        })
+    }
+}
+
+/// `func` over `data` as a plain `f64` loop (empty input yields 0, as in
+/// the executor)
+#[allow(clippy::cast_precision_loss)]
+fn scalar_reference(func: AggregateFunction, data: &ArrayRef) -> Result<f64> {
+    let values = compute::cast(data, &DataType::Float64)?;
+    let values = values
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .ok_or_else(|| Error::Other("Failed to downcast to Float64Array".to_string()))?;
+    let values = values.values();
+    let sum = || values.iter().fold(0.0, |sum, value| sum + value);
+    Ok(match func {
+        AggregateFunction::Sum => sum(),
+        AggregateFunction::Avg if values.is_empty() => 0.0,
+        AggregateFunction::Avg => sum() / values.len() as f64,
+        AggregateFunction::Count => values.len() as f64,
+        AggregateFunction::Min => values.iter().copied().reduce(f64::min).unwrap_or(0.0),
+        AggregateFunction::Max => values.iter().copied().reduce(f64::max).unwrap_or(0.0),
+        _ => {
+            return Err(Error::InvalidInput(format!("No scalar reference for {}", func.sql_name())))
+        }
+    })
+}
+
+/// The single value of an aggregate result, as `f64`
+fn first_value(result: &ArrayRef) -> Result<f64> {
+    let value = compute::cast(result, &DataType::Float64)?;
+    value
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .filter(|value| value.len() == 1 && value.is_valid(0))
+        .map(|value| value.value(0))
+        .ok_or_else(|| Error::Other(format!("Expected one aggregate value, got {result:?}")))
+}
+
+/// Whether `a` and `b` differ by at most `tolerance` relative to the larger
+/// magnitude (absolute below 1)
+fn close(a: f64, b: f64, tolerance: f64) -> bool {
+    (a.is_nan() && b.is_nan()) || (a - b).abs() <= tolerance * a.abs().max(b.abs()).max(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scalar_reference_and_tolerance() {
+        let data: ArrayRef = Arc::new(Int32Array::from(vec![4, -2, 7]));
+        assert!(close(scalar_reference(AggregateFunction::Sum, &data).unwrap(), 9.0, 0.0));
+        assert!(close(scalar_reference(AggregateFunction::Avg, &data).unwrap(), 3.0, 0.0));
+        assert!(close(scalar_reference(AggregateFunction::Min, &data).unwrap(), -2.0, 0.0));
+        assert!(scalar_reference(AggregateFunction::VarPop, &data).is_err());
+
+        assert!(close(1e9, 1e9 + 1.0, DEFAULT_TOLERANCE));
+        assert!(!close(1e9, 1e9 + 1.0, 0.0));
+        assert!(!close(0.0, 0.001, DEFAULT_TOLERANCE));
+        assert!(close(f64::NAN, f64::NAN, 0.0));
+    }
+}
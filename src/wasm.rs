@@ -1,7 +1,9 @@
 //! WebAssembly bindings for trueno-db browser deployment.
 //!
 //! Provides JavaScript-accessible API with tiered compute:
-//! - Tier 1: WebGPU compute shaders (if available)
+//! - Tier 1: WebGPU compute shaders (experimental: `gpu` feature, after
+//!   `init_gpu`; used by `query_async` for the aggregations the GPU kernels
+//!   support)
 //! - Tier 2: WASM SIMD128 via trueno backend
 //! - Tier 3: Scalar fallback
 //!
@@ -27,7 +29,7 @@ use web_sys::{console, window};
 
 use wasm_bindgen_futures::JsFuture;
 
-use crate::query::{QueryEngine, QueryExecutor};
+use crate::query::{QueryEngine, QueryExecutor, QueryStats};
 use crate::storage::StorageEngine;
 
 pub mod http_range;
@@ -85,11 +87,13 @@ impl Default for DatabaseConfig {
 /// In-browser analytics database with GPU/SIMD acceleration
 #[wasm_bindgen]
 pub struct Database {
-    #[allow(dead_code)]
     config: DatabaseConfig,
     tables: HashMap<String, StorageEngine>,
     query_engine: QueryEngine,
     executor: QueryExecutor,
+    /// WebGPU engine once [`Database::init_gpu`] succeeded
+    #[cfg(feature = "gpu")]
+    gpu: Option<crate::gpu::GpuEngine>,
 }
 
 #[wasm_bindgen]
@@ -104,10 +108,52 @@ impl Database {
             config,
             tables: HashMap::new(),
             query_engine: QueryEngine::new(),
-            executor: QueryExecutor::new(),
+            executor: QueryExecutor::new().with_query_stats(true),
+            #[cfg(feature = "gpu")]
+            gpu: None,
         }
     }
 
+    /// Acquire a WebGPU device for aggregations (Tier 1, experimental)
+    ///
+    /// Resolves to `false` when the configured backend is "simd128" or
+    /// "scalar", or WebGPU is unavailable; queries then stay on SIMD128.
+    #[wasm_bindgen]
+    pub async fn init_gpu(&mut self) -> bool {
+        if matches!(self.config.backend.as_str(), "simd128" | "scalar") {
+            return false;
+        }
+        #[cfg(feature = "gpu")]
+        {
+            match crate::gpu::GpuEngine::new().await {
+                Ok(engine) => {
+                    console::log_1(&"WebGPU engine ready".into());
+                    self.gpu = Some(engine);
+                    true
+                }
+                Err(e) => {
+                    console::warn_1(&format!("WebGPU unavailable, using SIMD128: {e}").into());
+                    false
+                }
+            }
+        }
+        #[cfg(not(feature = "gpu"))]
+        {
+            console::warn_1(&"Built without the gpu feature, using SIMD128".into());
+            false
+        }
+    }
+
+    /// Compute tier queries currently run on: "webgpu", "simd128" or "scalar"
+    #[wasm_bindgen]
+    pub fn tier(&self) -> String {
+        #[cfg(feature = "gpu")]
+        if self.gpu.is_some() {
+            return "webgpu".to_string();
+        }
+        if cfg!(target_feature = "simd128") { "simd128" } else { "scalar" }.to_string()
+    }
+
     /// Load table from URL (supports HTTP range requests for streaming)
     #[wasm_bindgen]
     pub async fn load_table(&mut self, name: String, url: String) -> Result<(), JsValue> {
@@ -208,15 +254,122 @@ impl Database {
         Ok(())
     }
 
+    /// Load table from Parquet bytes (an `ArrayBuffer` or `Uint8Array`,
+    /// e.g. from `File.arrayBuffer()` on a dropped or selected file)
+    #[cfg(feature = "parquet-io")]
+    #[wasm_bindgen]
+    pub fn load_parquet(&mut self, name: String, data: JsValue) -> Result<(), JsValue> {
+        let bytes = bytes_from_js(&data)?;
+        console::log_1(
+            &format!("Loading table '{}' from Parquet ({} bytes)", name, bytes.len()).into(),
+        );
+
+        let storage = StorageEngine::read_parquet(bytes)
+            .map_err(|e| JsValue::from_str(&format!("Parquet parse error: {e}")))?;
+        self.register(name, storage);
+        Ok(())
+    }
+
+    /// Load table from CSV bytes (an `ArrayBuffer` or `Uint8Array`) with a
+    /// header row; column types are inferred
+    #[cfg(feature = "csv-io")]
+    #[wasm_bindgen]
+    pub fn load_csv(&mut self, name: String, data: JsValue) -> Result<(), JsValue> {
+        let bytes = bytes_from_js(&data)?;
+        console::log_1(
+            &format!("Loading table '{}' from CSV ({} bytes)", name, bytes.len()).into(),
+        );
+
+        let storage = StorageEngine::read_csv(bytes.as_slice())
+            .map_err(|e| JsValue::from_str(&format!("CSV parse error: {e}")))?;
+        self.register(name, storage);
+        Ok(())
+    }
+
     /// Execute SQL query and return JSON result
     #[wasm_bindgen]
     pub fn query(&self, sql: String) -> Result<String, JsValue> {
+        let result = self.execute(&sql)?;
+        record_batch_to_json(&result)
+            .map_err(|e| JsValue::from_str(&format!("JSON conversion error: {e}")))
+    }
+
+    /// Execute SQL query and keep the result columnar
+    ///
+    /// Unlike [`query`](Self::query) nothing is converted up front: read
+    /// the result as Arrow IPC bytes, per-column typed arrays, or (for a
+    /// bounded number of rows) plain objects.
+    #[wasm_bindgen]
+    pub fn query_arrow(&self, sql: String) -> Result<QueryResult, JsValue> {
+        self.execute(&sql).map(|batch| QueryResult { batch })
+    }
+
+    /// Execute SQL query on the best tier and return JSON result
+    ///
+    /// Aggregations the GPU kernels support run on WebGPU after
+    /// [`init_gpu`](Self::init_gpu); everything else, and any query whose GPU
+    /// run fails, runs like [`query`](Self::query). A lost device is dropped
+    /// so later queries stay on SIMD128.
+    #[wasm_bindgen]
+    pub async fn query_async(&mut self, sql: String) -> Result<String, JsValue> {
+        #[cfg(feature = "gpu")]
+        if let Some(engine) = &self.gpu {
+            let plan = self
+                .query_engine
+                .parse(&sql)
+                .map_err(|e| JsValue::from_str(&format!("Parse error: {e}")))?;
+            let storage = self
+                .tables
+                .get(&plan.table)
+                .ok_or_else(|| JsValue::from_str(&format!("Table not found: {}", plan.table)))?;
+
+            let outcome = self.executor.execute_on_gpu(engine, &plan, storage).await;
+            match outcome {
+                Ok(Some(result)) => {
+                    console::log_1(
+                        &format!("Query returned {} rows on webgpu", result.num_rows()).into(),
+                    );
+                    return record_batch_to_json(&result)
+                        .map_err(|e| JsValue::from_str(&format!("JSON conversion error: {e}")));
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    console::warn_1(&format!("WebGPU failed, falling back to SIMD128: {e}").into());
+                    if matches!(e, crate::Error::GpuDeviceLost(_)) {
+                        self.gpu = None;
+                    }
+                }
+            }
+        }
+        self.query(sql)
+    }
+
+    /// Get query execution plan (for debugging)
+    #[wasm_bindgen]
+    pub fn explain(&self, sql: String) -> Result<String, JsValue> {
+        console::log_1(&format!("EXPLAIN: {}", sql).into());
+
+        let plan = self
+            .query_engine
+            .parse(&sql)
+            .map_err(|e| JsValue::from_str(&format!("Parse error: {e}")))?;
+
+        match self.tables.get(&plan.table) {
+            Some(storage) => Ok(self.executor.explain(&plan, storage)),
+            None => Ok(format!("{plan:#?}")),
+        }
+    }
+}
+
+impl Database {
+    /// Parse and run `sql`, logging its statistics
+    fn execute(&self, sql: &str) -> Result<RecordBatch, JsValue> {
         console::log_1(&format!("Executing query: {}", sql).into());
 
         // Parse SQL
         let plan = self
             .query_engine
-            .parse(&sql)
+            .parse(sql)
             .map_err(|e| JsValue::from_str(&format!("Parse error: {e}")))?;
 
         // Get table
@@ -231,28 +384,192 @@ impl Database {
             .execute(&plan, storage)
             .map_err(|e| JsValue::from_str(&format!("Execution error: {e}")))?;
 
-        // Convert to JSON
-        let json = record_batch_to_json(&result)
-            .map_err(|e| JsValue::from_str(&format!("JSON conversion error: {e}")))?;
+        match QueryStats::from_batch(&result) {
+            Some(stats) => console::log_1(
+                &format!(
+                    "Query returned {} rows ({} scanned) in {:.2} ms on {}",
+                    stats.rows_returned,
+                    stats.rows_scanned,
+                    stats.elapsed_ms,
+                    stats.backend.name()
+                )
+                .into(),
+            ),
+            None => console::log_1(&format!("Query returned {} rows", result.num_rows()).into()),
+        }
+        Ok(result)
+    }
+
+    /// Register `storage` under `name`, replacing any table of that name
+    #[cfg(any(feature = "parquet-io", feature = "csv-io"))]
+    fn register(&mut self, name: String, storage: StorageEngine) {
+        let rows: usize = storage.batches().iter().map(RecordBatch::num_rows).sum();
+        let columns = storage.batches().first().map_or(0, RecordBatch::num_columns);
+        console::log_1(&format!("Table '{name}' loaded: {rows} rows, {columns} columns").into());
+        self.tables.insert(name, storage);
+    }
+}
 
-        console::log_1(&format!("Query returned {} rows", result.num_rows()).into());
-        Ok(json)
+/// Copy the bytes of an `ArrayBuffer` or `Uint8Array` into WASM memory
+#[cfg(any(feature = "parquet-io", feature = "csv-io"))]
+fn bytes_from_js(data: &JsValue) -> Result<Vec<u8>, JsValue> {
+    if let Some(array) = data.dyn_ref::<js_sys::Uint8Array>() {
+        Ok(array.to_vec())
+    } else if let Some(buffer) = data.dyn_ref::<js_sys::ArrayBuffer>() {
+        Ok(js_sys::Uint8Array::new(buffer).to_vec())
+    } else {
+        Err(JsValue::from_str("Expected an ArrayBuffer or Uint8Array"))
     }
+}
 
-    /// Get query execution plan (for debugging)
+/// Rows [`QueryResult::to_objects`] converts when no limit is given
+pub const DEFAULT_OBJECT_ROWS: usize = 10_000;
+
+/// Columnar query result held in WASM memory
+///
+/// Hand large results to JS with [`to_ipc`](Self::to_ipc) (for arrow-js
+/// `tableFromIPC`) or [`column`](Self::column) (a typed-array view, no
+/// copy). Free it with `free()` once its views are no longer read.
+#[wasm_bindgen]
+pub struct QueryResult {
+    batch: RecordBatch,
+}
+
+#[wasm_bindgen]
+impl QueryResult {
+    /// Number of rows
     #[wasm_bindgen]
-    pub fn explain(&self, sql: String) -> Result<String, JsValue> {
-        console::log_1(&format!("EXPLAIN: {}", sql).into());
+    pub fn num_rows(&self) -> usize {
+        self.batch.num_rows()
+    }
 
-        let plan = self
-            .query_engine
-            .parse(&sql)
-            .map_err(|e| JsValue::from_str(&format!("Parse error: {e}")))?;
+    /// Number of columns
+    #[wasm_bindgen]
+    pub fn num_columns(&self) -> usize {
+        self.batch.num_columns()
+    }
+
+    /// Column names, in order
+    #[wasm_bindgen]
+    pub fn column_names(&self) -> js_sys::Array {
+        self.batch.schema().fields().iter().map(|f| JsValue::from_str(f.name())).collect()
+    }
+
+    /// Result as an Arrow IPC stream (copied into a `Uint8Array`)
+    #[cfg(feature = "ipc-io")]
+    #[wasm_bindgen]
+    pub fn to_ipc(&self) -> Result<Vec<u8>, JsValue> {
+        use arrow::ipc::writer::StreamWriter;
+
+        let failed = |e: arrow::error::ArrowError| {
+            JsValue::from_str(&format!("IPC serialization error: {e}"))
+        };
+        let mut bytes = Vec::new();
+        let mut writer = StreamWriter::try_new(&mut bytes, &self.batch.schema()).map_err(failed)?;
+        writer.write(&self.batch).map_err(failed)?;
+        writer.finish().map_err(failed)?;
+        drop(writer);
+        Ok(bytes)
+    }
+
+    /// Typed-array view over the values of a numeric column, without copying
+    ///
+    /// `Int64`/`UInt64` columns become `BigInt64Array`/`BigUint64Array`.
+    /// NULL slots hold arbitrary values. The view reads WASM memory
+    /// directly: it is invalidated when this result is freed or WASM memory
+    /// grows (any allocation may grow it), so copy it with `.slice()` to
+    /// keep it.
+    #[wasm_bindgen]
+    pub fn column(&self, index: usize) -> Result<JsValue, JsValue> {
+        if index >= self.batch.num_columns() {
+            return Err(JsValue::from_str(&format!("Column index out of range: {index}")));
+        }
+        column_view(self.batch.column(index).as_ref()).ok_or_else(|| {
+            JsValue::from_str(&format!(
+                "Column {index} ({:?}) has no typed-array view; use toObjects or to_ipc",
+                self.batch.column(index).data_type()
+            ))
+        })
+    }
 
-        Ok(format!("{plan:#?}"))
+    /// The first `limit` rows (default [`DEFAULT_OBJECT_ROWS`]) as plain
+    /// objects keyed by column name
+    #[wasm_bindgen(js_name = toObjects)]
+    pub fn to_objects(&self, limit: Option<usize>) -> Result<JsValue, JsValue> {
+        use serde::Serialize;
+
+        let rows = self.batch.num_rows().min(limit.unwrap_or(DEFAULT_OBJECT_ROWS));
+        let batch = self.batch.slice(0, rows);
+        let schema = batch.schema();
+        let objects = js_sys::Array::new_with_length(rows as u32);
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        for row_idx in 0..rows {
+            let mut row = serde_json::Map::new();
+            for (field, col) in schema.fields().iter().zip(batch.columns()) {
+                let value = array_value_to_json(col.as_ref(), row_idx)
+                    .map_err(|e| JsValue::from_str(&e))?;
+                row.insert(field.name().clone(), value);
+            }
+            let object = serde_json::Value::Object(row)
+                .serialize(&serializer)
+                .map_err(|e| JsValue::from_str(&format!("Conversion error: {e}")))?;
+            objects.set(row_idx as u32, object);
+        }
+        Ok(objects.into())
     }
 }
 
+/// Typed array aliasing the values buffer of a numeric column
+#[allow(unsafe_code)]
+fn column_view(array: &dyn Array) -> Option<JsValue> {
+    use arrow::array::AsArray;
+    use arrow::datatypes::{
+        Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, UInt16Type,
+        UInt32Type, UInt64Type, UInt8Type,
+    };
+
+    // SAFETY: each view aliases a buffer owned by the `QueryResult` that
+    // returned it. JS must not read it after freeing the result or after
+    // WASM memory grows, as `QueryResult::column` documents; no Rust code
+    // runs between creating the view and handing it to JS.
+    let view: JsValue = unsafe {
+        match array.data_type() {
+            DataType::Int8 => {
+                js_sys::Int8Array::view(array.as_primitive::<Int8Type>().values()).into()
+            }
+            DataType::Int16 => {
+                js_sys::Int16Array::view(array.as_primitive::<Int16Type>().values()).into()
+            }
+            DataType::Int32 => {
+                js_sys::Int32Array::view(array.as_primitive::<Int32Type>().values()).into()
+            }
+            DataType::Int64 => {
+                js_sys::BigInt64Array::view(array.as_primitive::<Int64Type>().values()).into()
+            }
+            DataType::UInt8 => {
+                js_sys::Uint8Array::view(array.as_primitive::<UInt8Type>().values()).into()
+            }
+            DataType::UInt16 => {
+                js_sys::Uint16Array::view(array.as_primitive::<UInt16Type>().values()).into()
+            }
+            DataType::UInt32 => {
+                js_sys::Uint32Array::view(array.as_primitive::<UInt32Type>().values()).into()
+            }
+            DataType::UInt64 => {
+                js_sys::BigUint64Array::view(array.as_primitive::<UInt64Type>().values()).into()
+            }
+            DataType::Float32 => {
+                js_sys::Float32Array::view(array.as_primitive::<Float32Type>().values()).into()
+            }
+            DataType::Float64 => {
+                js_sys::Float64Array::view(array.as_primitive::<Float64Type>().values()).into()
+            }
+            _ => return None,
+        }
+    };
+    Some(view)
+}
+
 /// Convert Arrow RecordBatch to JSON string
 fn record_batch_to_json(batch: &RecordBatch) -> Result<String, String> {
     let schema = batch.schema();
@@ -357,6 +674,7 @@ async fn check_webgpu(navigator: &web_sys::Navigator) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
 
     #[test]
     fn test_database_config_builder() {
@@ -365,4 +683,36 @@ mod tests {
         assert_eq!(config.backend, "simd128");
         assert_eq!(config.cache_size_mb, 512);
     }
+
+    fn sales() -> Database {
+        let mut db = Database::new(None);
+        db.load_json(
+            "sales".to_string(),
+            r#"[{"store": 1, "qty": 3}, {"store": 2, "qty": 5}, {"store": 1, "qty": 4}]"#
+                .to_string(),
+        )
+        .unwrap();
+        db
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_cpu_backends_never_start_webgpu() {
+        for backend in ["simd128", "scalar"] {
+            let mut db = Database::new(Some(DatabaseConfig::new().backend(backend)));
+            assert!(!db.init_gpu().await);
+            assert_ne!(db.tier(), "webgpu");
+        }
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_query_async_without_gpu_matches_query() {
+        let mut db = sales();
+        assert_ne!(db.tier(), "webgpu");
+
+        for sql in ["SELECT SUM(qty) FROM sales WHERE store = 1", "SELECT store FROM sales"] {
+            let expected = db.query(sql.to_string()).unwrap();
+            assert_eq!(db.query_async(sql.to_string()).await.unwrap(), expected);
+        }
+        assert!(db.query_async("SELECT SUM(qty) FROM missing".to_string()).await.is_err());
+    }
 }
@@ -0,0 +1,580 @@
+//! Workload capture and replay for upgrade validation
+//!
+//! While capture is on ([`Database::start_capture`]), every query run through
+//! [`Database::sql`] or [`Database::sql_batch`] is recorded with its timing, a
+//! fingerprint of each table it read, and a fingerprint of its result (or its
+//! error). [`Workload::save`] writes the recording as JSON; a newer build of
+//! the crate loads it with [`Workload::load`] and [`Workload::replay`]s it
+//! against the same data, reporting queries whose results changed, that
+//! started failing, or that got slower.
+//!
+//! ## Fingerprints
+//!
+//! A [`Fingerprint`] is the row count, a hash of the column names and types,
+//! and two hashes of the rows (row-encoded with [`arrow::row`]): one that
+//! ignores row order, and one that does not. Replay compares the ordered hash
+//! only for queries with an ORDER BY, since hash GROUP BY output order is not
+//! part of the contract. Values are compared bit for bit, so a float
+//! aggregate that rounds differently counts as a changed result.
+//! Dictionary-encoded columns fingerprint like their plain value type.
+//!
+//! Toyota Way: Genchi Genbutsu (validate upgrades on the real workload)
+
+use crate::query::QueryEngine;
+use crate::storage::StorageEngine;
+use crate::{Database, Error, Result};
+use arrow::datatypes::{DataType, Schema};
+use arrow::record_batch::RecordBatch;
+use arrow::row::{RowConverter, SortField};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Write as _};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Version of the workload file format written by [`Workload::save`]
+pub const WORKLOAD_FORMAT_VERSION: u32 = 1;
+
+/// Content fingerprint of a table or query result
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    /// Number of rows
+    pub rows: usize,
+    /// Hash of column names and types
+    pub schema: u64,
+    /// Hash of the rows, ignoring their order
+    pub unordered: u64,
+    /// Hash of the rows in order
+    pub ordered: u64,
+}
+
+impl Fingerprint {
+    /// Fingerprint the rows of `batches` (all sharing one schema)
+    ///
+    /// # Errors
+    /// Returns error if a column type cannot be row-encoded
+    pub fn of_batches(batches: &[RecordBatch]) -> Result<Self> {
+        let Some(first) = batches.first() else {
+            return Ok(Self { rows: 0, schema: 0, unordered: 0, ordered: 0 });
+        };
+        let schema = schema_hash(&first.schema());
+        let mut fingerprint = Self { rows: 0, schema, unordered: 0, ordered: 0 };
+        if first.num_columns() == 0 {
+            fingerprint.rows = batches.iter().map(RecordBatch::num_rows).sum();
+            return Ok(fingerprint);
+        }
+
+        let fields = first
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| SortField::new(field.data_type().clone()))
+            .collect();
+        let converter = RowConverter::new(fields)
+            .map_err(|e| Error::InvalidInput(format!("Cannot fingerprint column type: {e}")))?;
+        for batch in batches {
+            let rows = converter.convert_columns(batch.columns())?;
+            for row in &rows {
+                let hash = crate::kv::hash_bytes(row.as_ref());
+                fingerprint.unordered = fingerprint.unordered.wrapping_add(hash);
+                fingerprint.ordered = combine(fingerprint.ordered, hash);
+            }
+            fingerprint.rows += batch.num_rows();
+        }
+        Ok(fingerprint)
+    }
+
+    /// Same rows and columns, ignoring row order
+    #[must_use]
+    pub const fn same_rows(&self, other: &Self) -> bool {
+        self.rows == other.rows && self.schema == other.schema && self.unordered == other.unordered
+    }
+}
+
+/// Fingerprint of one table a captured query read
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableFingerprint {
+    /// Table name
+    pub name: String,
+    /// Table contents when the query ran
+    pub fingerprint: Fingerprint,
+}
+
+/// What a captured query produced
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CapturedOutcome {
+    /// The query succeeded with this result
+    Rows(Fingerprint),
+    /// The query failed with this message
+    Error(String),
+}
+
+/// One captured query
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkloadEntry {
+    /// SQL text as executed
+    pub sql: String,
+    /// Tables the query read, in name order (empty if it failed to parse)
+    pub tables: Vec<TableFingerprint>,
+    /// Result or error
+    pub outcome: CapturedOutcome,
+    /// Execution time in microseconds (for `sql_batch`, the batch time
+    /// split evenly across its queries)
+    pub elapsed_us: u64,
+}
+
+impl WorkloadEntry {
+    /// Captured execution time
+    #[must_use]
+    pub const fn elapsed(&self) -> Duration {
+        Duration::from_micros(self.elapsed_us)
+    }
+}
+
+/// A recorded sequence of queries (see the [module docs](self))
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Workload {
+    /// File format version ([`WORKLOAD_FORMAT_VERSION`])
+    pub format_version: u32,
+    /// Version of trueno-db that captured the workload
+    pub crate_version: String,
+    /// When capture started
+    pub captured_at: chrono::DateTime<chrono::Utc>,
+    /// Captured queries in execution order
+    pub entries: Vec<WorkloadEntry>,
+}
+
+impl Default for Workload {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Workload {
+    /// Create an empty workload stamped with this crate's version
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            format_version: WORKLOAD_FORMAT_VERSION,
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            captured_at: chrono::Utc::now(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Number of captured queries
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if no queries were captured
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Record a query that read `tables` and produced `result`
+    ///
+    /// Capture never fails the query: if a table or the result cannot be
+    /// fingerprinted the query is skipped with a warning.
+    pub(crate) fn record<'a>(
+        &mut self,
+        sql: &str,
+        tables: impl IntoIterator<Item = (&'a str, &'a StorageEngine)>,
+        result: &Result<RecordBatch>,
+        elapsed: Duration,
+    ) {
+        match fingerprint_query(tables, result) {
+            Ok((tables, outcome)) => self.entries.push(WorkloadEntry {
+                sql: sql.to_string(),
+                tables,
+                outcome,
+                elapsed_us: u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX),
+            }),
+            Err(e) => tracing::warn!(error = %e, sql, "skipping query in workload capture"),
+        }
+    }
+
+    /// Write the workload as JSON
+    ///
+    /// # Errors
+    /// Returns error if the file cannot be written
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let json = serde_json::to_vec_pretty(self)
+            .map_err(|e| Error::StorageError(format!("Failed to encode workload: {e}")))?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Read a workload written by [`save`](Self::save)
+    ///
+    /// # Errors
+    /// Returns error if the file cannot be read, is not a workload, or uses a
+    /// newer format version
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)?;
+        let workload: Self = serde_json::from_slice(&bytes).map_err(|e| {
+            Error::StorageError(format!("Invalid workload file {}: {e}", path.display()))
+        })?;
+        if workload.format_version > WORKLOAD_FORMAT_VERSION {
+            return Err(Error::StorageError(format!(
+                "Workload file {} has format version {}, this build reads up to {}",
+                path.display(),
+                workload.format_version,
+                WORKLOAD_FORMAT_VERSION
+            )));
+        }
+        Ok(workload)
+    }
+
+    /// Re-run every captured query against `db` and compare
+    ///
+    /// Before each query the tables it read are fingerprinted again; if any
+    /// differ from capture the result cannot be compared and the query is
+    /// reported as [`ReplayStatus::DatasetChanged`]. Queries run through
+    /// [`Database::sql`], so they take admission slots like any other query.
+    ///
+    /// # Example
+    /// ```
+    /// use arrow::array::{Int32Array, RecordBatch};
+    /// use arrow::datatypes::{DataType, Field, Schema};
+    /// use std::sync::Arc;
+    /// use trueno_db::workload::ReplayOptions;
+    /// use trueno_db::Database;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+    /// let batch = RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![1, 2]))])?;
+    /// let mut db = Database::builder().build()?;
+    /// db.register_batch("events", batch)?;
+    ///
+    /// db.start_capture();
+    /// db.sql("SELECT COUNT(*) FROM events")?;
+    /// let workload = db.finish_capture().unwrap();
+    ///
+    /// let report = workload.replay(&mut db, &ReplayOptions::default());
+    /// assert!(report.regressions().next().is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn replay(&self, db: &mut Database, options: &ReplayOptions) -> ReplayReport {
+        let engine = QueryEngine::new();
+        let results = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let changed: Vec<String> = entry
+                    .tables
+                    .iter()
+                    .filter(|table| !table_unchanged(db, table))
+                    .map(|table| table.name.clone())
+                    .collect();
+
+                let mut fastest = Duration::MAX;
+                let mut result = Err(Error::Other("query was not run".to_string()));
+                for _ in 0..options.runs.max(1) {
+                    let started = Instant::now();
+                    result = db.sql(&entry.sql);
+                    fastest = fastest.min(started.elapsed());
+                }
+
+                let ordered = engine.parse(&entry.sql).is_ok_and(|plan| !plan.order_by.is_empty());
+                let status = if changed.is_empty() {
+                    compare(&entry.outcome, result, ordered)
+                } else {
+                    ReplayStatus::DatasetChanged { tables: changed }
+                };
+                let captured = entry.elapsed();
+                let slower = fastest > options.min_duration
+                    && fastest.as_secs_f64() > captured.as_secs_f64() * options.slowdown_threshold;
+                ReplayResult { sql: entry.sql.clone(), status, captured, replayed: fastest, slower }
+            })
+            .collect();
+        ReplayReport { results }
+    }
+}
+
+/// Settings for [`Workload::replay`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplayOptions {
+    /// Times to run each query; the fastest run is reported (default 1)
+    pub runs: usize,
+    /// Replay time over captured time above which a query counts as slower
+    /// (default 1.5)
+    pub slowdown_threshold: f64,
+    /// Replays at or under this time are never reported as slower, so
+    /// microsecond queries don't flag on timer noise (default 1ms)
+    pub min_duration: Duration,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        Self { runs: 1, slowdown_threshold: 1.5, min_duration: Duration::from_millis(1) }
+    }
+}
+
+/// How a replayed query compares with its capture
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayStatus {
+    /// Same result (or the same query failed in both, see `StillFailing`)
+    Matched,
+    /// The query succeeded in both runs with different results
+    ResultChanged {
+        /// Captured result
+        expected: Fingerprint,
+        /// Replayed result
+        actual: Fingerprint,
+    },
+    /// The query succeeded at capture but now fails
+    NewError(String),
+    /// The query failed at capture but now succeeds
+    Fixed,
+    /// The query failed at capture and still fails (messages may differ)
+    StillFailing(String),
+    /// Tables the query reads differ from capture, so results aren't compared
+    DatasetChanged {
+        /// Names of the changed (or missing) tables
+        tables: Vec<String>,
+    },
+}
+
+impl ReplayStatus {
+    /// Whether this status means the new version behaves worse
+    #[must_use]
+    pub const fn is_regression(&self) -> bool {
+        matches!(self, Self::ResultChanged { .. } | Self::NewError(_))
+    }
+}
+
+/// Replay outcome of one captured query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayResult {
+    /// SQL text
+    pub sql: String,
+    /// Result comparison
+    pub status: ReplayStatus,
+    /// Captured execution time
+    pub captured: Duration,
+    /// Fastest replayed execution time
+    pub replayed: Duration,
+    /// Replay exceeded [`ReplayOptions::slowdown_threshold`]
+    pub slower: bool,
+}
+
+/// Results of [`Workload::replay`], in capture order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayReport {
+    /// One result per captured query
+    pub results: Vec<ReplayResult>,
+}
+
+impl ReplayReport {
+    /// Queries whose results changed, that now fail, or that got slower
+    pub fn regressions(&self) -> impl Iterator<Item = &ReplayResult> {
+        self.results.iter().filter(|result| result.status.is_regression() || result.slower)
+    }
+
+    /// Queries whose input tables changed, so their results went unchecked
+    pub fn unverified(&self) -> impl Iterator<Item = &ReplayResult> {
+        self.results
+            .iter()
+            .filter(|result| matches!(result.status, ReplayStatus::DatasetChanged { .. }))
+    }
+
+    /// No regressions and every query was verified
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.regressions().next().is_none() && self.unverified().next().is_none()
+    }
+
+    /// Total captured and replayed execution time
+    #[must_use]
+    pub fn total_time(&self) -> (Duration, Duration) {
+        self.results
+            .iter()
+            .fold((Duration::ZERO, Duration::ZERO), |(captured, replayed), result| {
+                (captured + result.captured, replayed + result.replayed)
+            })
+    }
+}
+
+impl fmt::Display for ReplayReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, result) in self.results.iter().enumerate() {
+            let verdict = match &result.status {
+                ReplayStatus::Matched => "ok".to_string(),
+                ReplayStatus::ResultChanged { expected, actual } => {
+                    format!("RESULT CHANGED ({} rows -> {} rows)", expected.rows, actual.rows)
+                }
+                ReplayStatus::NewError(e) => format!("NEW ERROR: {e}"),
+                ReplayStatus::Fixed => "fixed (failed at capture)".to_string(),
+                ReplayStatus::StillFailing(_) => "still failing".to_string(),
+                ReplayStatus::DatasetChanged { tables } => {
+                    format!("unverified (changed tables: {})", tables.join(", "))
+                }
+            };
+            let slower = if result.slower { " SLOWER" } else { "" };
+            writeln!(
+                f,
+                "#{index} {verdict}{slower} [{:?} -> {:?}] {}",
+                result.captured, result.replayed, result.sql
+            )?;
+        }
+        let (captured, replayed) = self.total_time();
+        write!(
+            f,
+            "{} queries, {} regressions, {} unverified, total {captured:?} -> {replayed:?}",
+            self.results.len(),
+            self.regressions().count(),
+            self.unverified().count()
+        )
+    }
+}
+
+/// Fingerprint the tables a query read (in name order) and its outcome
+fn fingerprint_query<'a>(
+    tables: impl IntoIterator<Item = (&'a str, &'a StorageEngine)>,
+    result: &Result<RecordBatch>,
+) -> Result<(Vec<TableFingerprint>, CapturedOutcome)> {
+    let mut fingerprints = tables
+        .into_iter()
+        .map(|(name, storage)| {
+            Ok(TableFingerprint {
+                name: name.to_string(),
+                fingerprint: Fingerprint::of_batches(storage.batches())?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    fingerprints.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+    let outcome = match result {
+        Ok(batch) => CapturedOutcome::Rows(Fingerprint::of_batches(std::slice::from_ref(batch))?),
+        Err(e) => CapturedOutcome::Error(e.to_string()),
+    };
+    Ok((fingerprints, outcome))
+}
+
+/// Compare a replayed result with the captured outcome
+fn compare(
+    captured: &CapturedOutcome,
+    replayed: Result<RecordBatch>,
+    ordered: bool,
+) -> ReplayStatus {
+    match (captured, replayed) {
+        (CapturedOutcome::Rows(expected), Ok(batch)) => {
+            match Fingerprint::of_batches(std::slice::from_ref(&batch)) {
+                Ok(actual)
+                    if expected.same_rows(&actual)
+                        && (!ordered || expected.ordered == actual.ordered) =>
+                {
+                    ReplayStatus::Matched
+                }
+                Ok(actual) => ReplayStatus::ResultChanged { expected: *expected, actual },
+                Err(e) => ReplayStatus::NewError(e.to_string()),
+            }
+        }
+        (CapturedOutcome::Rows(_), Err(e)) => ReplayStatus::NewError(e.to_string()),
+        (CapturedOutcome::Error(_), Ok(_)) => ReplayStatus::Fixed,
+        (CapturedOutcome::Error(_), Err(e)) => ReplayStatus::StillFailing(e.to_string()),
+    }
+}
+
+/// Whether `db` still holds the table contents recorded at capture
+fn table_unchanged(db: &mut Database, table: &TableFingerprint) -> bool {
+    match db.table(&table.name) {
+        Ok(Some(storage)) => Fingerprint::of_batches(storage.batches())
+            .is_ok_and(|current| current.same_rows(&table.fingerprint)),
+        _ => false,
+    }
+}
+
+/// Hash column names and types, reading dictionaries as their value type
+fn schema_hash(schema: &Schema) -> u64 {
+    let mut description = String::new();
+    for field in schema.fields() {
+        let data_type = match field.data_type() {
+            DataType::Dictionary(_, value) => value.as_ref(),
+            data_type => data_type,
+        };
+        let _ = write!(description, "{}:{data_type:?};", field.name());
+    }
+    crate::kv::hash_bytes(description.as_bytes())
+}
+
+/// Order-sensitive hash combine (`FxHash` step)
+const fn combine(seed: u64, hash: u64) -> u64 {
+    (seed.rotate_left(5) ^ hash).wrapping_mul(0x51_7c_c1_b7_27_22_0a_95)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Int32Array, StringArray};
+    use arrow::datatypes::Field;
+    use std::sync::Arc;
+
+    fn batch(ids: Vec<i32>, names: Vec<&str>) -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, false),
+        ]));
+        RecordBatch::try_new(
+            schema,
+            vec![Arc::new(Int32Array::from(ids)), Arc::new(StringArray::from(names))],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_fingerprint_row_order() {
+        let a = Fingerprint::of_batches(&[batch(vec![1, 2], vec!["x", "y"])]).unwrap();
+        let b = Fingerprint::of_batches(&[batch(vec![2, 1], vec!["y", "x"])]).unwrap();
+        let c = Fingerprint::of_batches(&[batch(vec![1, 2], vec!["x", "z"])]).unwrap();
+
+        assert!(a.same_rows(&b));
+        assert_ne!(a.ordered, b.ordered);
+        assert!(!a.same_rows(&c));
+        assert_eq!(a.rows, 2);
+    }
+
+    #[test]
+    fn test_fingerprint_splits_and_dictionaries() {
+        let whole = Fingerprint::of_batches(&[batch(vec![1, 2, 3], vec!["a", "b", "a"])]).unwrap();
+        let split = Fingerprint::of_batches(&[
+            batch(vec![1], vec!["a"]),
+            batch(vec![2, 3], vec!["b", "a"]),
+        ])
+        .unwrap();
+        assert_eq!(whole, split);
+
+        let plain = batch(vec![1, 2, 3], vec!["a", "b", "a"]);
+        let dict_type = DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+        let names = arrow::compute::cast(plain.column(1), &dict_type).unwrap();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", dict_type, false),
+        ]));
+        let dict = RecordBatch::try_new(schema, vec![plain.column(0).clone(), names]).unwrap();
+        assert_eq!(Fingerprint::of_batches(&[dict]).unwrap(), whole);
+    }
+
+    #[test]
+    fn test_compare_outcomes() {
+        let rows = batch(vec![1, 2], vec!["x", "y"]);
+        let expected = Fingerprint::of_batches(std::slice::from_ref(&rows)).unwrap();
+        let reversed = batch(vec![2, 1], vec!["y", "x"]);
+        let captured = CapturedOutcome::Rows(expected);
+
+        assert_eq!(compare(&captured, Ok(reversed.clone()), false), ReplayStatus::Matched);
+        assert!(compare(&captured, Ok(reversed), true).is_regression());
+        assert!(compare(&captured, Err(Error::Other("boom".into())), false).is_regression());
+
+        let failed = CapturedOutcome::Error("parse".into());
+        assert_eq!(compare(&failed, Ok(rows), false), ReplayStatus::Fixed);
+        assert!(matches!(
+            compare(&failed, Err(Error::Other("parse".into())), false),
+            ReplayStatus::StillFailing(_)
+        ));
+    }
+}
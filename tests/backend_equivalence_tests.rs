@@ -14,6 +14,9 @@
 //! 3. **Edge Cases**: NaN, infinity, overflow, empty inputs
 //! 4. **CI Integration**: Tests fail on any backend mismatch
 //!
+//! The backends here are mocks. `equivalence_harness_test.rs` runs the same
+//! comparison on the real `GpuEngine` (`verify` feature).
+//!
 //! ## Acceptance Criteria (from roadmap)
 //!
 //! - [x] Property-based tests with quickcheck/proptest
@@ -466,3 +469,58 @@ mod edge_cases {
         assert_eq!(scalar_count, 1_000_000);
     }
 }
+
+// ============================================================================
+// Variance / Stddev (Welford parallel merge)
+// ============================================================================
+
+mod variance_equivalence {
+    use super::*;
+    use trueno_db::variance::{welford_scalar, welford_simd, VarianceKind, WelfordState};
+
+    /// GPU decomposition on the CPU: one Welford state per 256-element
+    /// workgroup, partials merged in order (stage 2 of `welford_f32`)
+    fn workgroup_welford(data: &[f32]) -> WelfordState {
+        data.chunks(256).map(welford_scalar).fold(WelfordState::default(), WelfordState::merge)
+    }
+
+    fn assert_close(a: Option<f64>, b: Option<f64>) -> Result<(), TestCaseError> {
+        match (a, b) {
+            (Some(a), Some(b)) => {
+                let tolerance = 1e-9 * a.abs().max(b.abs()).max(1.0);
+                prop_assert!((a - b).abs() <= tolerance, "{} vs {}", a, b);
+            }
+            (a, b) => prop_assert_eq!(a, b),
+        }
+        Ok(())
+    }
+
+    proptest! {
+        /// Test: GPU-style variance == SIMD variance == Scalar variance (f32)
+        #[test]
+        fn prop_variance_equivalence_f32(data in prop::collection::vec(-1e6_f32..1e6, 0..2000)) {
+            let scalar = welford_scalar(&data);
+            let simd = welford_simd(&data);
+            let gpu = workgroup_welford(&data);
+
+            prop_assert_eq!(scalar.count, simd.count);
+            prop_assert_eq!(scalar.count, gpu.count);
+            for kind in [VarianceKind::Population, VarianceKind::Sample] {
+                assert_close(gpu.variance(kind), simd.variance(kind))?;
+                assert_close(simd.variance(kind), scalar.variance(kind))?;
+                assert_close(simd.stddev(kind), scalar.stddev(kind))?;
+            }
+        }
+
+        /// Test: variance is shift-invariant (numerical stability with large offsets)
+        #[test]
+        fn prop_variance_shift_invariant(data in prop::collection::vec(-100_i32..100, 2..500)) {
+            let base: Vec<f64> = data.iter().map(|&x| f64::from(x)).collect();
+            let shifted: Vec<f64> = base.iter().map(|x| x + 1e9).collect();
+
+            let expected = welford_scalar(&base).variance(VarianceKind::Sample).unwrap();
+            let actual = welford_simd(&shifted).variance(VarianceKind::Sample).unwrap();
+            prop_assert!((expected - actual).abs() <= 1e-4 * expected.max(1.0), "{} vs {}", expected, actual);
+        }
+    }
+}
@@ -9,7 +9,12 @@
 //!
 //! Toyota Way: Genchi Genbutsu (Go and See - physics-based cost model)
 
-use trueno_db::backend::BackendDispatcher;
+use trueno_db::backend::{
+    BackendDispatcher, CostModel, DispatchPolicy, GigaflopsPerSecond, HardwareProfile,
+    PhysicsCostModel, SimdWidth,
+};
+use trueno_db::query::QueryExecutor;
+use trueno_db::{Backend, Database};
 
 /// `PCIe` Gen4 x16 bandwidth: 32 GB/s
 const PCIE_BANDWIDTH_GBPS: f64 = 32.0;
@@ -240,3 +245,79 @@ fn test_realistic_sql_operations_backend_selection() {
     // 5ms < 62.5ms, so CPU
     assert!(matches!(join_backend, trueno_db::Backend::Simd), "Moderate JOIN should use SIMD");
 }
+
+#[test]
+fn test_default_cost_model_matches_dispatcher() {
+    let model = PhysicsCostModel::default();
+    for (bytes, flops) in [(1_000_000, 1e6), (1_000_000_000, 1e9), (1_000_000_000, 1e11)] {
+        assert_eq!(
+            format!("{:?}", model.select(bytes, flops)),
+            format!("{:?}", BackendDispatcher::select(bytes, flops))
+        );
+    }
+}
+
+#[test]
+fn test_cpu_only_profile_never_selects_gpu() {
+    let model = PhysicsCostModel::new(HardwareProfile::cpu_only(SimdWidth::Bits256));
+    let backend = model.select(10_000_000_000, 1e15);
+    assert!(matches!(backend, trueno_db::Backend::Simd), "No GPU means SIMD");
+}
+
+#[test]
+fn test_integrated_gpu_lowers_dispatch_threshold() {
+    // 5 MB, 10 GFLOP: below the discrete 10 MB floor, but an iGPU has no PCIe hop
+    let bytes = 5_000_000;
+    let flops = 10_000_000_000.0;
+
+    let discrete = PhysicsCostModel::new(HardwareProfile::discrete_desktop());
+    let integrated = PhysicsCostModel::new(HardwareProfile::integrated_laptop());
+
+    assert!(matches!(discrete.select(bytes, flops), trueno_db::Backend::Simd));
+    assert!(matches!(integrated.select(bytes, flops), trueno_db::Backend::Gpu));
+}
+
+#[test]
+fn test_datacenter_gpu_faster_compute_estimate() {
+    let bytes = 1_000_000_000;
+    let flops = 1e11;
+
+    let desktop = PhysicsCostModel::new(HardwareProfile::discrete_desktop()).estimate(bytes, flops);
+    let server = PhysicsCostModel::new(HardwareProfile::datacenter_server()).estimate(bytes, flops);
+
+    // Same PCIe link, 10x compute throughput
+    assert!((desktop.transfer.as_millis() - server.transfer.as_millis()).abs() < 1e-9);
+    assert!((desktop.gpu_compute.as_millis() / server.gpu_compute.as_millis() - 10.0).abs() < 1e-9);
+    assert!((desktop.transfer.as_millis() - 31.25).abs() < 1e-9);
+}
+
+#[test]
+fn test_dispatch_policy_threads_through_executor() {
+    // 5 MB GROUP BY over 625K rows: below the default 10 MB floor
+    let (rows, bytes) = (625_000, 5_000_000);
+    assert_eq!(QueryExecutor::new().group_by_backend(rows, bytes), Backend::Simd);
+
+    let policy = DispatchPolicy {
+        min_gpu_bytes: Some(1_000_000),
+        gpu_gflops: Some(GigaflopsPerSecond(1.0)),
+        ..DispatchPolicy::new()
+    };
+    let tuned = QueryExecutor::new().with_dispatch_policy(policy);
+    assert_eq!(tuned.group_by_backend(rows, bytes), Backend::Gpu);
+    assert_eq!(
+        BackendDispatcher::select_with(&policy, bytes, 3_750_000.0),
+        tuned.group_by_backend(rows, bytes)
+    );
+
+    // A forced executor backend wins over the policy
+    let forced = QueryExecutor::with_backend(Backend::Simd).with_dispatch_policy(policy);
+    assert_eq!(forced.group_by_backend(rows, bytes), Backend::Simd);
+}
+
+#[test]
+fn test_policy_force_backend_sets_database_backend() {
+    let policy = DispatchPolicy { force_backend: Some(Backend::Simd), ..DispatchPolicy::new() };
+    let db = Database::builder().dispatch_policy(policy).build().unwrap();
+    assert_eq!(db.backend(), Backend::Simd);
+    assert_eq!(db.executor().dispatch_policy(), &policy);
+}
@@ -339,6 +339,44 @@ mod gpu_tests {
             "GPU fused filter+sum should equal Scalar implementation"
         );
     }
+
+    /// Test the non-blocking GPU query path (the WASM WebGPU tier) matches `execute`
+    #[tokio::test]
+    async fn test_gpu_async_query_matches_execute() {
+        use arrow::datatypes::{DataType, Field, Schema};
+        use arrow::record_batch::RecordBatch;
+        use std::sync::Arc;
+        use trueno_db::query::{QueryEngine, QueryExecutor};
+        use trueno_db::storage::StorageEngine;
+
+        let Ok(engine) = GpuEngine::new().await else {
+            eprintln!("Skipping GPU test (no GPU available)");
+            return;
+        };
+
+        let schema = Arc::new(Schema::new(vec![Field::new("v", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![Arc::new(Int32Array::from((1..=100).collect::<Vec<_>>()))],
+        )
+        .unwrap();
+        let storage = StorageEngine::new(vec![batch]);
+        let executor = QueryExecutor::new();
+        let parser = QueryEngine::new();
+
+        let plan = parser.parse("SELECT SUM(v), MAX(v) FROM t WHERE v > 10").unwrap();
+        let gpu = executor
+            .execute_on_gpu(&engine, &plan, &storage)
+            .await
+            .expect("GPU query should work")
+            .expect("ungrouped SUM/MAX runs on the GPU");
+        let simd = executor.execute(&plan, &storage).unwrap();
+        assert_eq!(gpu.columns(), simd.columns(), "GPU query should equal SIMD query");
+
+        // GROUP BY is not a multi_aggregate plan: left to `execute`
+        let grouped = parser.parse("SELECT v, COUNT(*) FROM t GROUP BY v").unwrap();
+        assert!(executor.execute_on_gpu(&engine, &grouped, &storage).await.unwrap().is_none());
+    }
 }
 
 // ============================================================================
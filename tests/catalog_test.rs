@@ -0,0 +1,226 @@
+//! Integration tests for `Database::attach_dir` and catalog refresh
+//!
+//! Toyota Way: Genchi Genbutsu (go and see the real files on disk)
+
+use arrow::array::Int32Array;
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use trueno_db::Database;
+
+fn write_parquet<P: AsRef<Path>>(path: P, values: Vec<i32>) {
+    let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+    let batch = RecordBatch::try_new(Arc::clone(&schema), vec![Arc::new(Int32Array::from(values))])
+        .unwrap();
+
+    let file = File::create(path).unwrap();
+    let mut writer = ArrowWriter::try_new(file, schema, None).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+}
+
+fn fresh_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(format!("/tmp/trueno_test_catalog_{name}"));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn row_count(db: &Database, table: &str) -> usize {
+    db.catalog().get(table).unwrap().batches().iter().map(RecordBatch::num_rows).sum()
+}
+
+#[test]
+fn test_attach_dir_registers_files_and_subdirectories() {
+    let dir = fresh_dir("attach");
+    write_parquet(dir.join("events.parquet"), vec![1, 2, 3]);
+    write_parquet(dir.join("users.parquet"), vec![10]);
+    fs::write(dir.join("README.md"), "not data").unwrap();
+    fs::write(dir.join(".hidden.parquet"), "ignored").unwrap();
+
+    let sales = dir.join("sales");
+    fs::create_dir_all(sales.join("year=2024")).unwrap();
+    write_parquet(sales.join("part-0.parquet"), vec![1, 2]);
+    write_parquet(sales.join("year=2024").join("part-1.parquet"), vec![3, 4, 5]);
+
+    let mut db = Database::builder().build().unwrap();
+    let tables = db.attach_dir(&dir).unwrap();
+
+    assert_eq!(tables, vec!["events", "sales", "users"]);
+    assert_eq!(db.catalog().table_names(), vec!["events", "sales", "users"]);
+    assert_eq!(row_count(&db, "events"), 3);
+    assert_eq!(row_count(&db, "users"), 1);
+    assert_eq!(row_count(&db, "sales"), 5);
+}
+
+#[test]
+fn test_refresh_picks_up_added_and_removed_files() {
+    let dir = fresh_dir("refresh");
+    write_parquet(dir.join("a.parquet"), vec![1]);
+
+    let mut db = Database::builder().build().unwrap();
+    db.attach_dir(&dir).unwrap();
+    assert_eq!(db.catalog().table_names(), vec!["a"]);
+
+    write_parquet(dir.join("b.parquet"), vec![1, 2]);
+    fs::remove_file(dir.join("a.parquet")).unwrap();
+
+    let tables = db.refresh().unwrap();
+    assert_eq!(tables, vec!["b"]);
+    assert_eq!(db.catalog().table_names(), vec!["b"]);
+    assert_eq!(row_count(&db, "b"), 2);
+}
+
+#[test]
+fn test_failed_refresh_keeps_previous_tables() {
+    let dir = fresh_dir("refresh_failure");
+    write_parquet(dir.join("a.parquet"), vec![1, 2]);
+
+    let mut db = Database::builder().build().unwrap();
+    db.attach_dir(&dir).unwrap();
+    db.register_batch(
+        "b",
+        RecordBatch::try_new(
+            Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)])),
+            vec![Arc::new(Int32Array::from(vec![7]))],
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    // A corrupt file fails the scan
+    fs::write(dir.join("broken.parquet"), "not parquet").unwrap();
+    assert!(db.refresh().is_err());
+    assert_eq!(db.catalog().table_names(), vec!["a", "b"]);
+    assert_eq!(row_count(&db, "a"), 2);
+    fs::remove_file(dir.join("broken.parquet")).unwrap();
+
+    // A new file clashing with a table registered elsewhere is rejected
+    write_parquet(dir.join("b.parquet"), vec![1, 2, 3]);
+    assert!(db.refresh().is_err());
+    assert_eq!(db.catalog().table_names(), vec!["a", "b"]);
+    assert_eq!(row_count(&db, "a"), 2);
+    assert_eq!(row_count(&db, "b"), 1);
+
+    fs::remove_file(dir.join("b.parquet")).unwrap();
+    write_parquet(dir.join("a.parquet"), vec![1, 2, 3, 4]);
+    assert_eq!(db.refresh().unwrap(), vec!["a"]);
+    assert_eq!(row_count(&db, "a"), 4);
+}
+
+#[test]
+fn test_attach_dir_rejects_duplicate_table_names() {
+    let dir = fresh_dir("duplicate");
+    write_parquet(dir.join("events.parquet"), vec![1]);
+    fs::create_dir_all(dir.join("events")).unwrap();
+    write_parquet(dir.join("events").join("part-0.parquet"), vec![2]);
+
+    let mut db = Database::builder().build().unwrap();
+    let result = db.attach_dir(&dir);
+
+    assert!(result.is_err());
+    assert!(db.catalog().is_empty());
+}
+
+#[test]
+fn test_attach_dir_with_provenance_tags_source_files() {
+    use arrow::array::{Array, StringArray};
+    use trueno_db::storage::PROVENANCE_COLUMNS;
+    use trueno_db::Catalog;
+
+    let dir = fresh_dir("provenance");
+    let sales = dir.join("sales");
+    fs::create_dir_all(&sales).unwrap();
+    write_parquet(sales.join("part-0.parquet"), vec![1, 2]);
+    write_parquet(sales.join("part-1.parquet"), vec![3]);
+
+    let mut catalog = Catalog::new().with_provenance(true);
+    catalog.attach_dir(&dir).unwrap();
+
+    let batches = catalog.get("sales").unwrap().batches();
+    let schema = batches[0].schema();
+    let names: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+    assert_eq!(names[1..], PROVENANCE_COLUMNS);
+
+    let sources: Vec<String> = batches
+        .iter()
+        .flat_map(|b| {
+            let column = b.column(1).as_any().downcast_ref::<StringArray>().unwrap().clone();
+            (0..column.len()).map(move |i| column.value(i).to_string())
+        })
+        .collect();
+    assert_eq!(sources.len(), 3);
+    assert!(sources[0].ends_with("part-0.parquet"));
+    assert!(sources[2].ends_with("part-1.parquet"));
+}
+
+#[test]
+fn test_register_batch_alongside_attached_tables() {
+    use trueno_db::query::{QueryEngine, QueryExecutor};
+
+    let dir = fresh_dir("register_batch");
+    write_parquet(dir.join("events.parquet"), vec![1, 2, 3]);
+
+    let mut db = Database::builder().build().unwrap();
+    db.attach_dir(&dir).unwrap();
+
+    let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+    let batch =
+        RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![2, 3, 4, 5]))]).unwrap();
+    db.register_batch("app_ids", batch.clone()).unwrap();
+
+    // Persistent tables cannot be shadowed
+    assert!(db.register_batch("events", batch).is_err());
+    assert_eq!(row_count(&db, "events"), 3);
+
+    let plan = QueryEngine::new().parse("SELECT COUNT(*) FROM app_ids WHERE id > 3").unwrap();
+    let result =
+        QueryExecutor::new().execute(&plan, db.catalog().get(&plan.table).unwrap()).unwrap();
+    assert_eq!(result.num_rows(), 1);
+    assert_eq!(db.catalog().table_names(), vec!["app_ids", "events"]);
+
+    assert!(!db.drop_temporary("events"));
+    assert!(db.drop_temporary("app_ids"));
+    assert_eq!(db.catalog().table_names(), vec!["events"]);
+}
+
+#[cfg(feature = "ipc-io")]
+#[test]
+fn test_memory_limit_evicts_and_reloads_cold_tables() {
+    use trueno_db::storage::{EvictionConfig, StorageEngine};
+
+    let dir = fresh_dir("eviction");
+    let spill = fresh_dir("eviction_spill");
+    write_parquet(dir.join("a.parquet"), (0..10_000).collect());
+    write_parquet(dir.join("b.parquet"), (0..10_000).collect());
+
+    // Room for one table at a time
+    let table_bytes = StorageEngine::load_parquet(dir.join("a.parquet")).unwrap().memory_size();
+    let config = EvictionConfig::new(table_bytes * 3 / 2).with_spill_dir(&spill);
+    let mut db = Database::builder().memory_limit(config).build().unwrap();
+
+    // Attaching over the limit evicts the least recently registered table
+    db.attach_dir(&dir).unwrap();
+    assert!(db.catalog().is_evicted("a"));
+    assert!(db.catalog().get("a").is_none());
+    assert_eq!(db.catalog().table_names(), vec!["a", "b"]);
+
+    // Access reloads transparently and evicts the now-colder table
+    let rows: usize =
+        db.table("a").unwrap().unwrap().batches().iter().map(RecordBatch::num_rows).sum();
+    assert_eq!(rows, 10_000);
+    assert!(!db.catalog().is_evicted("a"));
+    assert!(db.catalog().is_evicted("b"));
+
+    let metrics = db.catalog().eviction_metrics().unwrap();
+    assert_eq!((metrics.evictions, metrics.reloads, metrics.spilled_tables), (2, 1, 1));
+    assert!(metrics.resident_bytes <= table_bytes * 3 / 2);
+
+    // Evicted tables are still readable without reloading
+    let rows = db.catalog().with_table("b", |storage| storage.batches().len()).unwrap();
+    assert!(rows.is_some_and(|batches| batches > 0));
+    assert!(db.catalog().is_evicted("b"));
+}
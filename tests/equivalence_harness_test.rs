@@ -0,0 +1,53 @@
+//! Backend equivalence on the real GPU (`verify` feature)
+//!
+//! Unlike `backend_equivalence_tests.rs`, nothing here is mocked: every
+//! check runs the `GpuEngine` kernels. Tests skip when no adapter exists.
+
+#![cfg(feature = "verify")]
+
+use arrow::array::{Float32Array, Int32Array, Int64Array};
+use trueno_db::gpu::GpuEngine;
+use trueno_db::query::AggregateFunction;
+use trueno_db::verify::EquivalenceHarness;
+use trueno_db::Error;
+
+#[tokio::test]
+async fn test_self_check_passes_on_real_gpu() {
+    let Ok(engine) = GpuEngine::new().await else {
+        eprintln!("Skipping GPU test (no GPU available)");
+        return;
+    };
+
+    let checks = EquivalenceHarness::new(&engine).self_check().await.unwrap();
+    // 5 Int32 aggregates, 3 each for Float64 and Float32
+    assert_eq!(checks.len(), 11);
+    assert!(checks.iter().all(|check| check.gpu.is_finite()));
+}
+
+#[tokio::test]
+async fn test_check_covers_wide_integers_and_floats() {
+    let Ok(engine) = GpuEngine::new().await else {
+        eprintln!("Skipping GPU test (no GPU available)");
+        return;
+    };
+    let harness = EquivalenceHarness::new(&engine);
+
+    let wide = Int64Array::from(vec![i64::from(i32::MAX) * 4, -7, 12]);
+    let sum = harness.check(AggregateFunction::Sum, &wide).await.unwrap();
+    assert!((sum.scalar - (f64::from(i32::MAX) * 4.0 + 5.0)).abs() < 1.0);
+
+    let floats = Float32Array::from((0..10_000).map(|i| i as f32 * 0.1).collect::<Vec<_>>());
+    let avg = harness.check(AggregateFunction::Avg, &floats).await.unwrap();
+    assert!((avg.simd - 499.95).abs() < 1e-2);
+
+    // MIN over floats has no GPU kernel; NULLs are rejected up front
+    assert!(matches!(
+        harness.check(AggregateFunction::Min, &floats).await,
+        Err(Error::InvalidInput(_))
+    ));
+    let nulls = Int32Array::from(vec![Some(1), None]);
+    assert!(matches!(
+        harness.check(AggregateFunction::Sum, &nulls).await,
+        Err(Error::InvalidInput(_))
+    ));
+}
@@ -18,6 +18,16 @@ fn test_vram_exhausted_error() {
     assert!(error_str.contains("Please report this issue"));
 }
 
+#[test]
+fn test_vram_exceeded_error() {
+    let error =
+        Error::VramExceeded { requested: 4096, available: 1024, suggested_morsel_bytes: 1024 };
+    let error_str = format!("{error}");
+    assert!(error_str.contains("VRAM budget exceeded"));
+    assert!(error_str.contains("4096 bytes requested"));
+    assert!(error_str.contains("morsels of at most 1024 bytes"));
+}
+
 #[test]
 fn test_backend_mismatch_error() {
     let error =
@@ -36,6 +46,35 @@ fn test_parse_error() {
     assert!(error_str.contains("invalid SQL"));
 }
 
+#[test]
+fn test_overloaded_error() {
+    let error = Error::Overloaded("8 queries running".to_string());
+    let error_str = format!("{error}");
+    assert!(error_str.contains("overloaded"));
+    assert!(error_str.contains("8 queries running"));
+}
+
+#[test]
+fn test_gpu_submission_errors_are_distinct() {
+    let lost = Error::GpuDeviceLost("Unknown: driver reset".to_string());
+    assert!(format!("{lost}").contains("GPU device lost"));
+    assert!(format!("{lost}").contains("driver reset"));
+
+    let timeout = Error::Timeout("GPU submission did not complete within 5s".to_string());
+    assert!(format!("{timeout}").starts_with("Timed out"));
+
+    let cancelled = Error::Cancelled("GPU submission cancelled".to_string());
+    assert!(format!("{cancelled}").starts_with("Cancelled"));
+}
+
+#[test]
+fn test_access_denied_error() {
+    let error = Error::AccessDenied("principal 'agent' may not read table 'payroll'".to_string());
+    let error_str = format!("{error}");
+    assert!(error_str.contains("Access denied"));
+    assert!(error_str.contains("payroll"));
+}
+
 #[test]
 fn test_storage_error() {
     let error = Error::StorageError("file not found".to_string());
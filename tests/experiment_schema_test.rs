@@ -461,3 +461,112 @@ fn test_experiment_store_get_runs_for_experiment() {
         assert_eq!(run.experiment_id(), "exp-001");
     }
 }
+
+// =============================================================================
+// Parquet Persistence Tests
+// =============================================================================
+
+#[cfg(feature = "parquet-io")]
+#[test]
+fn test_experiment_store_parquet_round_trip() {
+    use chrono::{TimeZone, Utc};
+    use trueno_db::experiment::METRICS_FILE;
+    use trueno_db::storage::StorageEngine;
+
+    let at = Utc.with_ymd_and_hms(2025, 3, 1, 12, 0, 0).unwrap();
+    let config = serde_json::json!({"learning_rate": 0.01});
+    let mut store = ExperimentStore::new();
+    store.add_experiment(
+        ExperimentRecord::builder("exp-001", "Baseline")
+            .config(config.clone())
+            .created_at(at)
+            .build(),
+    );
+    store.add_experiment(ExperimentRecord::builder("exp-002", "Ablation").created_at(at).build());
+    store.add_run(
+        RunRecord::builder("run-001", "exp-001")
+            .status(RunStatus::Success)
+            .started_at(at)
+            .ended_at(at + chrono::Duration::minutes(5))
+            .renacer_span_id("span-1")
+            .build(),
+    );
+    store.add_run(RunRecord::new("run-002", "exp-002"));
+    for step in 0..10 {
+        let metric = MetricRecord::builder("run-001", "loss", step, 1.0 / (step as f64 + 1.0));
+        store.add_metric(metric.timestamp(at).build());
+    }
+    store.add_artifact(
+        ArtifactRecord::builder("run-001", "model.pt", "sha256:abc123", 1024)
+            .created_at(at)
+            .build(),
+    );
+
+    let dir = std::env::temp_dir().join("trueno_test_experiment_store");
+    let _ = std::fs::remove_dir_all(&dir);
+    store.flush_to_parquet(&dir).unwrap();
+    let loaded = ExperimentStore::load_from_parquet(&dir).unwrap();
+
+    assert_eq!(loaded.get_experiment("exp-001"), store.get_experiment("exp-001"));
+    assert_eq!(loaded.get_experiment("exp-001").unwrap().config(), Some(&config));
+    assert_eq!(loaded.get_run("run-001"), store.get_run("run-001"));
+    assert_eq!(loaded.get_run("run-002").unwrap().status(), RunStatus::Pending);
+    assert_eq!(
+        loaded.get_metrics_for_run("run-001", "loss"),
+        store.get_metrics_for_run("run-001", "loss")
+    );
+    assert_eq!(loaded.get_artifacts_for_run("run-001"), store.get_artifacts_for_run("run-001"));
+
+    // The tables are plain Parquet, ready for SQL analysis
+    let metrics = StorageEngine::load_parquet(dir.join(METRICS_FILE)).unwrap();
+    assert_eq!(metrics.batches().iter().map(|b| b.num_rows()).sum::<usize>(), 10);
+
+    // An empty store round-trips too
+    ExperimentStore::new().flush_to_parquet(&dir).unwrap();
+    assert!(ExperimentStore::load_from_parquet(&dir).unwrap().is_empty());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// =============================================================================
+// SQL over Experiment Tables
+// =============================================================================
+
+#[test]
+fn test_experiment_tables_queryable_with_sql() {
+    use arrow::array::{AsArray, Float64Array};
+    use trueno_db::Database;
+
+    let mut store = ExperimentStore::new();
+    store.add_experiment(ExperimentRecord::new("exp-001", "Sweep"));
+    for (run, scale) in [("run-001", 1.0), ("run-002", 0.5)] {
+        let mut record = RunRecord::new(run, "exp-001");
+        record.start();
+        record.complete(RunStatus::Success);
+        store.add_run(record);
+        for step in 0..5 {
+            store.add_metric(MetricRecord::new(run, "loss", step, scale / (step as f64 + 1.0)));
+            store.add_metric(MetricRecord::new(run, "accuracy", step, 0.1 * step as f64));
+        }
+    }
+
+    let tables = store.as_record_batches().unwrap();
+    let names: Vec<&str> = tables.iter().map(|(name, _)| *name).collect();
+    assert_eq!(names, vec!["experiments", "runs", "metrics", "artifacts"]);
+    assert_eq!(tables[2].1.num_rows(), 20);
+
+    let mut db = Database::builder().build().unwrap();
+    db.register_experiments(&store).unwrap();
+    let best = db
+        .sql("SELECT run_id, MIN(value) FROM metrics WHERE key = 'loss' GROUP BY run_id")
+        .unwrap();
+    assert_eq!(best.num_rows(), 2);
+    let runs = best.column(0).as_string::<i32>();
+    let mins = best.column(1).as_any().downcast_ref::<Float64Array>().unwrap();
+    for row in 0..best.num_rows() {
+        let expected = if runs.value(row) == "run-001" { 0.2 } else { 0.1 };
+        assert!((mins.value(row) - expected).abs() < 1e-9);
+    }
+
+    let finished = db.sql("SELECT COUNT(*) FROM runs WHERE status = 'success'").unwrap();
+    assert_eq!(finished.num_rows(), 1);
+}
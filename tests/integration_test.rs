@@ -15,7 +15,8 @@ use parquet::file::properties::WriterProperties;
 use std::fs::File;
 use std::path::Path;
 use std::sync::Arc;
-use trueno_db::storage::StorageEngine;
+use trueno_db::query::{QueryEngine, QueryExecutor};
+use trueno_db::storage::{ParquetMorselReader, StorageEngine};
 
 /// Create a test Parquet file with 10,000 rows
 #[allow(clippy::cast_precision_loss)]
@@ -106,6 +107,87 @@ fn test_morsel_iterator_with_real_data() {
     std::fs::remove_file(test_file).ok();
 }
 
+#[test]
+fn test_streaming_parquet_yields_lazy_morsels() {
+    let test_file = "/tmp/trueno_test_streaming.parquet";
+
+    // Create test Parquet file
+    create_test_parquet(test_file).expect("Failed to create test Parquet file");
+
+    // Footer metadata is available before any morsel is decoded
+    let reader = ParquetMorselReader::open(test_file, 16 * 1024).expect("Failed to open Parquet");
+    assert_eq!(reader.num_rows(), 10_000);
+    assert_eq!(reader.num_row_groups(), 2);
+    assert!(reader.morsel_rows() < 5_000, "16KB morsels should split row groups");
+    let morsel_rows = reader.morsel_rows();
+
+    let morsels: Vec<_> = reader.collect::<Result<_, _>>().expect("Failed to stream morsels");
+    assert!(morsels.len() > 2, "Expected several morsels, got {}", morsels.len());
+    assert!(morsels.iter().all(|m| m.num_rows() <= morsel_rows));
+
+    // Same rows, in the same order, as the eager loader
+    let loaded = StorageEngine::load_parquet(test_file).expect("Failed to load Parquet file");
+    let ids = |batches: &[RecordBatch]| -> Vec<i32> {
+        batches
+            .iter()
+            .flat_map(|b| {
+                b.column(0).as_any().downcast_ref::<Int32Array>().unwrap().values().to_vec()
+            })
+            .collect()
+    };
+    assert_eq!(ids(&morsels), ids(loaded.batches()));
+
+    // Default (128MB) morsels
+    let rows: usize = StorageEngine::open_parquet_streaming(test_file)
+        .expect("Failed to open Parquet")
+        .map(|m| m.unwrap().num_rows())
+        .sum();
+    assert_eq!(rows, 10_000);
+
+    // Clean up
+    std::fs::remove_file(test_file).ok();
+}
+
+#[test]
+fn test_parquet_projection_pushdown() {
+    let test_file = "/tmp/trueno_test_projection.parquet";
+    create_test_parquet(test_file).expect("Failed to create test Parquet file");
+
+    // Columns come back in file order, whatever order they were asked for
+    let storage = StorageEngine::load_parquet_with_projection(test_file, &["category", "id"])
+        .expect("Failed to load projected Parquet");
+    let schema = storage.batches()[0].schema();
+    let names: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+    assert_eq!(names, vec!["id", "category"]);
+    let total_rows: usize = storage.batches().iter().map(RecordBatch::num_rows).sum();
+    assert_eq!(total_rows, 10_000);
+
+    let unknown = StorageEngine::load_parquet_with_projection(test_file, &["missing"]);
+    assert!(unknown.is_err());
+
+    // Streaming morsels carry the projected schema
+    let reader = StorageEngine::open_parquet_streaming_with_projection(test_file, &["value"])
+        .expect("Failed to open projected Parquet");
+    assert_eq!(reader.schema().fields().len(), 1);
+    let morsels: Vec<_> = reader.collect::<Result<_, _>>().expect("Failed to stream morsels");
+    assert!(morsels.iter().all(|m| m.num_columns() == 1));
+
+    // The executor reads only what the plan needs and matches a full load
+    let plan = QueryEngine::new()
+        .parse("SELECT category, COUNT(*) AS n FROM t WHERE id >= 5000 GROUP BY category ORDER BY category")
+        .unwrap();
+    assert_eq!(plan.required_columns(), Some(vec!["category".to_string(), "id".to_string()]));
+    let executor = QueryExecutor::new();
+    let pruned = executor.execute_parquet(&plan, test_file).expect("Failed to execute");
+    let full = executor
+        .execute(&plan, &StorageEngine::load_parquet(test_file).unwrap())
+        .expect("Failed to execute");
+    assert_eq!(pruned, full);
+    assert_eq!(pruned.num_rows(), 10);
+
+    std::fs::remove_file(test_file).ok();
+}
+
 #[tokio::test]
 async fn test_full_pipeline_with_gpu_queue() {
     let test_file = "/tmp/trueno_test_pipeline.parquet";
@@ -131,3 +213,82 @@ async fn test_full_pipeline_with_gpu_queue() {
     // Clean up
     std::fs::remove_file(test_file).ok();
 }
+
+#[test]
+fn test_read_parquet_from_memory() {
+    let test_file = "/tmp/trueno_test_in_memory.parquet";
+    create_test_parquet(test_file).expect("Failed to create test Parquet file");
+    let bytes = std::fs::read(test_file).expect("Failed to read Parquet file");
+    std::fs::remove_file(test_file).ok();
+
+    let storage = StorageEngine::read_parquet(bytes).expect("Failed to parse Parquet bytes");
+    let total_rows: usize = storage.batches().iter().map(RecordBatch::num_rows).sum();
+    assert_eq!(total_rows, 10_000, "Expected 10,000 rows");
+
+    let garbage = StorageEngine::read_parquet(b"not parquet".to_vec());
+    assert!(garbage.is_err());
+}
+
+#[cfg(feature = "csv-io")]
+#[test]
+fn test_read_csv_from_unseekable_reader() {
+    let input: &[u8] = b"id,region,amount\n1,eu,1.5\n2,us,2.5\n3,eu,4.0\n";
+
+    let storage = StorageEngine::read_csv(input).expect("Failed to read CSV");
+    let batch = &storage.batches()[0];
+    assert_eq!(batch.num_rows(), 3);
+    assert_eq!(batch.schema().field(0).data_type(), &DataType::Int64);
+    assert_eq!(batch.schema().field(1).data_type(), &DataType::Utf8);
+    assert_eq!(batch.schema().field(2).data_type(), &DataType::Float64);
+}
+
+#[cfg(feature = "ipc-io")]
+#[test]
+fn test_read_ipc_stream_round_trip() {
+    use arrow::ipc::writer::StreamWriter;
+
+    let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+    let batches: Vec<RecordBatch> = [vec![1, 2], vec![3]]
+        .into_iter()
+        .map(|ids| {
+            RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(ids))]).unwrap()
+        })
+        .collect();
+
+    let mut stream = Vec::new();
+    let mut writer = StreamWriter::try_new(&mut stream, &schema).unwrap();
+    for batch in &batches {
+        writer.write(batch).unwrap();
+    }
+    writer.finish().unwrap();
+    drop(writer);
+
+    let storage = StorageEngine::read_ipc_stream(stream.as_slice()).expect("Failed to read stream");
+    assert_eq!(storage.batches(), batches.as_slice());
+}
+
+#[cfg(feature = "ipc-io")]
+#[test]
+fn test_write_ipc_round_trip() {
+    let test_file =
+        std::env::temp_dir().join(format!("trueno_test_{}.feather", std::process::id()));
+    create_test_parquet("/tmp/trueno_test_ipc_source.parquet").expect("Failed to create test file");
+    let storage = StorageEngine::load_parquet("/tmp/trueno_test_ipc_source.parquet").unwrap();
+
+    storage.write_ipc(&test_file).expect("Failed to write IPC file");
+    let loaded = StorageEngine::load_ipc(&test_file).expect("Failed to load IPC file");
+    assert_eq!(loaded.batches(), storage.batches());
+
+    #[cfg(feature = "ipc-mmap")]
+    #[allow(unsafe_code)]
+    {
+        // SAFETY: the file is not modified until the mapped table is dropped
+        let mapped =
+            unsafe { StorageEngine::load_ipc_mmap(&test_file) }.expect("Failed to map IPC file");
+        assert_eq!(mapped.batches(), storage.batches());
+    }
+
+    assert!(StorageEngine::new(Vec::new()).write_ipc(&test_file).is_err());
+    std::fs::remove_file(&test_file).ok();
+    std::fs::remove_file("/tmp/trueno_test_ipc_source.parquet").ok();
+}
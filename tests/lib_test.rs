@@ -1,5 +1,9 @@
 //! Tests for top-level Database API
 
+use arrow::array::{Float64Array, Int32Array, Int64Array, RecordBatch, StringArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use std::sync::Arc;
+use trueno_db::storage::StorageEngine;
 use trueno_db::{Backend, Database};
 
 #[test]
@@ -45,6 +49,62 @@ fn test_database_build_with_config() {
     assert!(result.is_ok(), "Database build with config should succeed");
 }
 
+#[test]
+fn test_database_build_applies_config() {
+    let db = Database::builder().backend(Backend::Simd).morsel_size_mb(2).build().unwrap();
+    assert_eq!(db.backend(), Backend::Simd);
+    assert_eq!(db.morsel_size_bytes(), 2 * 1024 * 1024);
+    assert_eq!(db.executor().join_backend(1_000_000, 1_000_000, 1 << 30), Backend::Simd);
+
+    let db = Database::builder().build().unwrap();
+    assert_eq!(db.backend(), Backend::CostBased);
+    assert_eq!(db.morsel_size_bytes(), trueno_db::storage::MORSEL_SIZE_BYTES);
+
+    assert!(Database::builder().morsel_size_mb(0).build().is_err());
+}
+
+#[test]
+fn test_database_build_parallelism() {
+    let db = Database::builder().parallelism(3).build().unwrap();
+    assert_eq!(db.parallelism(), 3);
+    assert_eq!(db.executor().parallelism(), 3);
+
+    let db = Database::builder().build().unwrap();
+    assert_eq!(db.parallelism(), trueno_db::query::parallel::default_parallelism());
+
+    assert!(matches!(
+        Database::builder().parallelism(0).build(),
+        Err(trueno_db::Error::InvalidInput(_))
+    ));
+}
+
+#[cfg(not(feature = "gpu"))]
+#[test]
+fn test_database_build_gpu_requires_feature() {
+    let result = Database::builder().backend(Backend::Gpu).build();
+    assert!(matches!(result, Err(trueno_db::Error::GpuInitFailed(_))));
+}
+
+#[test]
+fn test_query_returns_morsel_sized_batches() {
+    // ~4 MB of Int64 values with 1 MB morsels
+    let rows = 512 * 1024;
+    let schema = Arc::new(Schema::new(vec![Field::new("v", DataType::Int64, false)]));
+    let batch = RecordBatch::try_new(schema, vec![Arc::new(Int64Array::from_iter_values(0..rows))])
+        .unwrap();
+    let mut db = Database::builder().backend(Backend::Simd).morsel_size_mb(1).build().unwrap();
+    db.register_batch("big", batch).unwrap();
+
+    let batches = db.query("SELECT v FROM big").unwrap();
+    assert!(batches.len() >= 4, "{} batches", batches.len());
+    assert_eq!(batches.iter().map(RecordBatch::num_rows).sum::<usize>(), 512 * 1024);
+
+    let empty = db.query("SELECT v FROM big WHERE v < 0").unwrap();
+    assert_eq!(empty.len(), 1);
+    assert_eq!(empty[0].num_rows(), 0);
+    assert_eq!(empty[0].schema().field(0).name(), "v");
+}
+
 #[test]
 fn test_backend_enum_clone() {
     // Test Backend enum is Clone
@@ -67,3 +127,264 @@ fn test_backend_enum_debug() {
     let debug_str = format!("{backend:?}");
     assert!(debug_str.contains("Simd"));
 }
+
+#[test]
+fn test_database_admission_limits() {
+    let db = Database::builder()
+        .max_concurrent_queries(1)
+        .max_queued_queries(0)
+        .queue_timeout(std::time::Duration::from_millis(10))
+        .build()
+        .unwrap();
+
+    let permit = db.admit().unwrap();
+    assert!(matches!(db.admit(), Err(trueno_db::Error::Overloaded(_))));
+    drop(permit);
+    assert!(db.admit().is_ok());
+
+    let metrics = db.admission_metrics();
+    assert_eq!(metrics.admitted, 2);
+    assert_eq!(metrics.rejected, 1);
+    assert_eq!(metrics.in_flight, 0);
+}
+
+fn sales_batch() -> RecordBatch {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("region", DataType::Utf8, false),
+        Field::new("amount", DataType::Float64, false),
+    ]));
+    RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(StringArray::from(vec!["eu", "us", "eu", "apac"])),
+            Arc::new(Float64Array::from(vec![1.0, 2.0, 4.0, 8.0])),
+        ],
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_sql_batch_returns_results_in_query_order() {
+    let mut db = Database::builder().build().unwrap();
+    db.register_batch("sales", sales_batch()).unwrap();
+    let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+    let users =
+        RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![1, 2, 3]))]).unwrap();
+    db.register_batch("users", users).unwrap();
+
+    let results = db
+        .sql_batch(&[
+            "SELECT SUM(amount) FROM sales WHERE amount > 1.5",
+            "SELECT COUNT(*) FROM users",
+            "SELECT MAX(amount) FROM sales WHERE amount > 1.5",
+            "SELECT region, SUM(amount) FROM sales GROUP BY region",
+        ])
+        .unwrap();
+
+    assert_eq!(results.len(), 4);
+    let sum = results[0].column(0).as_any().downcast_ref::<Float64Array>().unwrap();
+    assert!((sum.value(0) - 14.0).abs() < f64::EPSILON);
+    let count = results[1].column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(count.value(0), 3);
+    let max = results[2].column(0).as_any().downcast_ref::<Float64Array>().unwrap();
+    assert!((max.value(0) - 8.0).abs() < f64::EPSILON);
+    assert_eq!(results[3].num_rows(), 3);
+
+    // The whole batch took a single admission slot
+    assert_eq!(db.admission_metrics().admitted, 1);
+}
+
+#[test]
+fn test_sql_batch_parses_everything_before_running() {
+    let mut db = Database::builder().build().unwrap();
+    db.register_batch("sales", sales_batch()).unwrap();
+
+    let err = db.sql_batch(&["SELECT SUM(amount) FROM sales", "SELEKT nonsense"]).unwrap_err();
+    assert!(matches!(err, trueno_db::Error::ParseError(_)));
+
+    let err = db.sql_batch(&["SELECT COUNT(*) FROM missing"]).unwrap_err();
+    assert!(matches!(err, trueno_db::Error::InvalidInput(_)));
+}
+
+fn id_table(ids: Vec<i32>) -> StorageEngine {
+    let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+    let batch = RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(ids))]).unwrap();
+    StorageEngine::new(vec![batch])
+}
+
+#[test]
+fn test_sql_resolves_from_table_by_name() {
+    let mut db = Database::builder().build().unwrap();
+    db.register_table("events", id_table(vec![1, 2, 3])).unwrap();
+    db.register_table("users", id_table(vec![10])).unwrap();
+
+    let count = |db: &mut Database, sql: &str| {
+        let result = db.sql(sql).unwrap();
+        result.column(0).as_any().downcast_ref::<Int64Array>().unwrap().value(0)
+    };
+    assert_eq!(count(&mut db, "SELECT COUNT(*) FROM events"), 3);
+    assert_eq!(count(&mut db, "SELECT COUNT(*) FROM users"), 1);
+    assert_eq!(
+        count(
+            &mut db,
+            "WITH recent AS (SELECT id FROM events WHERE id > 1) SELECT COUNT(*) FROM recent"
+        ),
+        2
+    );
+
+    let err = db.sql("SELECT COUNT(*) FROM missing").unwrap_err();
+    assert!(err.to_string().contains("Table not found: missing"), "{err}");
+
+    // Re-registering replaces the table
+    assert!(db.register_table("users", id_table(vec![1, 2])).unwrap().is_some());
+    assert_eq!(count(&mut db, "SELECT COUNT(*) FROM users"), 2);
+}
+
+#[test]
+fn test_sql_and_sql_batch_join_catalog_tables() {
+    let mut db = Database::builder().build().unwrap();
+    db.register_batch("sales", sales_batch()).unwrap();
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("code", DataType::Utf8, false),
+        Field::new("manager", DataType::Utf8, false),
+    ]));
+    let regions = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(StringArray::from(vec!["eu", "us"])),
+            Arc::new(StringArray::from(vec!["ada", "bob"])),
+        ],
+    )
+    .unwrap();
+    db.register_batch("regions", regions).unwrap();
+
+    let join = "SELECT manager, COUNT(*) FROM sales s JOIN regions r ON s.region = r.code \
+                GROUP BY manager ORDER BY manager";
+    assert_eq!(db.sql(join).unwrap().num_rows(), 2);
+
+    let results = db.sql_batch(&["SELECT COUNT(*) FROM sales", join]).unwrap();
+    assert_eq!(results[1].num_rows(), 2);
+
+    let err =
+        db.sql("SELECT * FROM sales JOIN nowhere ON sales.region = nowhere.code").unwrap_err();
+    assert!(err.to_string().contains("Table not found: nowhere"), "{err}");
+}
+
+#[test]
+fn test_shared_database_appends_while_querying() {
+    let mut db = Database::builder().build().unwrap();
+    db.register_batch("sales", sales_batch()).unwrap();
+    let db = db.into_shared();
+
+    let count = |db: &trueno_db::SharedDatabase| {
+        let result = db.sql("SELECT COUNT(*) FROM sales").unwrap();
+        result.column(0).as_any().downcast_ref::<Int64Array>().unwrap().value(0)
+    };
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let db = db.clone();
+            std::thread::spawn(move || {
+                let mut last = 0;
+                for _ in 0..20 {
+                    // Whole batches only, never fewer rows than before
+                    let rows = count(&db);
+                    assert_eq!(rows % 4, 0);
+                    assert!(rows >= last);
+                    last = rows;
+                }
+            })
+        })
+        .collect();
+    let ingest = db.clone();
+    let writer = std::thread::spawn(move || {
+        for _ in 0..50 {
+            ingest.append_batch("sales", sales_batch()).unwrap();
+        }
+    });
+    writer.join().unwrap();
+    for reader in readers {
+        reader.join().unwrap();
+    }
+    assert_eq!(count(&db), 51 * 4);
+
+    assert!(db.append_batch("missing", sales_batch()).is_err());
+    let ids = id_table(vec![1]).batches()[0].clone();
+    assert!(db.append_batch("sales", ids.clone()).is_err());
+    assert!(db.append_batches("sales", vec![sales_batch(), ids]).is_err());
+    assert_eq!(count(&db), 51 * 4);
+    db.append_batches("sales", vec![sales_batch(), sales_batch()]).unwrap();
+    assert_eq!(count(&db), 53 * 4);
+}
+
+#[test]
+fn test_workload_capture_and_replay() {
+    use trueno_db::workload::{ReplayOptions, ReplayStatus, Workload};
+
+    let mut db = Database::builder().build().unwrap();
+    db.register_batch("sales", sales_batch()).unwrap();
+
+    assert!(db.finish_capture().is_none());
+    db.start_capture();
+    db.sql("SELECT region, SUM(amount) FROM sales GROUP BY region").unwrap();
+    db.sql("SELECT COUNT(*) FROM missing").unwrap_err();
+    db.sql_batch(&["SELECT COUNT(*) FROM sales", "SELECT MAX(amount) FROM sales"]).unwrap();
+    let workload = db.finish_capture().unwrap();
+    assert_eq!(workload.len(), 4);
+    assert_eq!(workload.entries[0].tables[0].name, "sales");
+    assert!(workload.entries[1].tables.is_empty());
+
+    let path = std::env::temp_dir().join("trueno_test_workload_capture.json");
+    workload.save(&path).unwrap();
+    let loaded = Workload::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, workload);
+
+    // Same data: every query matches (the failing one still fails)
+    let options = ReplayOptions { slowdown_threshold: f64::INFINITY, ..Default::default() };
+    let report = loaded.replay(&mut db, &options);
+    assert!(report.is_clean(), "{report}");
+    assert!(matches!(report.results[1].status, ReplayStatus::StillFailing(_)));
+
+    // Changed data: results can no longer be verified
+    db.register_batch("sales", id_table(vec![1]).batches()[0].clone()).unwrap();
+    let report = loaded.replay(&mut db, &options);
+    assert_eq!(report.unverified().count(), 3);
+    assert!(!report.is_clean());
+}
+
+#[cfg(feature = "parquet-io")]
+#[test]
+fn test_export_parquet_streams_and_materializes() {
+    use trueno_db::storage::{ColumnCodec, ParquetWriteOptions};
+
+    let mut db = Database::builder().build().unwrap();
+    let sales = StorageEngine::new(vec![sales_batch(), sales_batch(), sales_batch()]);
+    db.register_table("sales", sales).unwrap();
+    let options = ParquetWriteOptions::new()
+        .with_default_codec(ColumnCodec::Uncompressed)
+        .with_max_row_group_size(4);
+
+    // Plain scan: streamed batch by batch, LIMIT spans batches
+    let path = std::env::temp_dir().join("trueno_test_export_scan.parquet");
+    let summary = db
+        .export_parquet("SELECT amount FROM sales WHERE amount > 1 LIMIT 5", &path, &options)
+        .unwrap();
+    assert_eq!(summary.rows, 5);
+    assert_eq!(summary.row_groups, 2);
+    let loaded = StorageEngine::load_parquet(&path).unwrap();
+    let schema = loaded.batches()[0].schema();
+    assert_eq!(schema.fields().len(), 1);
+    assert_eq!(schema.field(0).name(), "amount");
+    std::fs::remove_file(&path).unwrap();
+
+    // Aggregation: executed, then written
+    let path = std::env::temp_dir().join("trueno_test_export_agg.parquet");
+    let summary = db
+        .export_parquet("SELECT region, SUM(amount) FROM sales GROUP BY region", &path, &options)
+        .unwrap();
+    assert_eq!(summary.rows, 3);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(db.export_parquet("SELECT * FROM missing", &path, &options).is_err());
+    assert!(!path.exists());
+}
@@ -3,11 +3,16 @@
 //! These tests validate the complete query pipeline:
 //! SQL → Parser → Executor → Results
 
-use arrow::array::{Float64Array, Int32Array, RecordBatch, StringArray};
+use arrow::array::{
+    Array, ArrayRef, BooleanArray, Float64Array, Int16Array, Int32Array, Int64Array, Int8Array,
+    RecordBatch, StringArray, UInt64Array, UInt8Array,
+};
 use arrow::datatypes::{DataType, Field, Schema};
+use std::collections::HashMap;
 use std::sync::Arc;
-use trueno_db::query::{QueryEngine, QueryExecutor};
+use trueno_db::query::{Lint, QueryEngine, QueryExecutor, QueryStats, RowAccessor};
 use trueno_db::storage::StorageEngine;
+use trueno_db::Backend;
 
 /// Helper function to create test data
 fn create_test_data() -> StorageEngine {
@@ -120,6 +125,230 @@ fn test_aggregation_min_max() {
     assert!((max_col.value(0) - 50.0).abs() < 0.01);
 }
 
+#[test]
+fn test_aggregates_skip_nulls() {
+    // A NULL-able first column, so `COUNT(*)` cannot be answered by it
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("score", DataType::Int32, true),
+        Field::new("region", DataType::Utf8, false),
+        Field::new("label", DataType::Utf8, true),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int32Array::from(vec![None, Some(4), Some(1), None, Some(7), Some(2)])),
+            Arc::new(StringArray::from(vec!["east", "east", "west", "west", "west", "east"])),
+            Arc::new(StringArray::from(vec![Some("a"), None, None, Some("b"), Some("c"), None])),
+        ],
+    )
+    .unwrap();
+    let storage = StorageEngine::new(vec![batch]);
+
+    let engine = QueryEngine::new();
+    for threads in [1, 4] {
+        let executor = QueryExecutor::new().with_parallelism(threads);
+        let run = |sql: &str| executor.execute(&engine.parse(sql).unwrap(), &storage).unwrap();
+
+        let result = run("SELECT COUNT(*), COUNT(score), COUNT(label), MIN(score), MAX(score), \
+             AVG(score) FROM t");
+        let int64 = |i: usize| result.column(i).as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!((int64(0).value(0), int64(1).value(0), int64(2).value(0)), (6, 4, 3));
+        let score = |i: usize| result.column(i).as_any().downcast_ref::<Int32Array>().unwrap();
+        assert_eq!((score(3).value(0), score(4).value(0)), (1, 7));
+        let avg = result.column(5).as_any().downcast_ref::<Float64Array>().unwrap();
+        assert!((avg.value(0) - 3.5).abs() < f64::EPSILON);
+
+        let result = run("SELECT region, COUNT(*), COUNT(score) FROM t GROUP BY region \
+             ORDER BY region");
+        let counts = |i: usize| {
+            result.column(i).as_any().downcast_ref::<Int64Array>().unwrap().values().to_vec()
+        };
+        assert_eq!((counts(1), counts(2)), (vec![3, 3], vec![2, 2]));
+
+        let count =
+            |sql: &str| run(sql).column(0).as_any().downcast_ref::<Int64Array>().unwrap().value(0);
+        assert_eq!(count("SELECT COUNT(*) FROM t WHERE score IS NULL"), 2);
+        assert_eq!(count("SELECT COUNT(*) FROM t WHERE label IS NOT NULL"), 3);
+        assert_eq!(count("SELECT COUNT(label) FROM t WHERE score IS NOT NULL"), 1);
+    }
+}
+
+#[test]
+fn test_aggregates_of_no_values_are_null() {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("g", DataType::Utf8, false),
+        Field::new("i", DataType::Int32, true),
+        Field::new("l", DataType::Int64, true),
+        Field::new("s", DataType::Int8, true),
+        Field::new("b", DataType::Boolean, true),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(StringArray::from(vec!["a", "a", "b"])),
+            Arc::new(Int32Array::from(vec![None, None, Some(3)])),
+            Arc::new(Int64Array::from(vec![None, None, Some(5)])),
+            Arc::new(Int8Array::from(vec![None, None, Some(1)])),
+            Arc::new(BooleanArray::from(vec![None, None, Some(true)])),
+        ],
+    )
+    .unwrap();
+    let storage = StorageEngine::new(vec![batch]);
+
+    let engine = QueryEngine::new();
+    for threads in [1, 4] {
+        let executor = QueryExecutor::new().with_parallelism(threads);
+        let run = |sql: &str| executor.execute(&engine.parse(sql).unwrap(), &storage).unwrap();
+
+        // All-NULL inputs and no rows at all
+        for filter in ["g = 'a'", "i > 100"] {
+            let result = run(&format!(
+                "SELECT MIN(i), MAX(i), AVG(i), MIN(l), MAX(l), AVG(l), MIN(s), MAX(s), AVG(s), \
+                 MIN(b), MAX(b), AVG(b) FROM t WHERE {filter}"
+            ));
+            for i in 0..result.num_columns() {
+                assert!(result.column(i).is_null(0), "column {i} WHERE {filter}");
+                assert!(result.schema().field(i).is_nullable());
+            }
+        }
+
+        // A group whose values are all NULL
+        let result = run("SELECT g, MIN(i), MAX(l), AVG(s), COUNT(i) FROM t GROUP BY g ORDER BY g");
+        assert_eq!(result.num_rows(), 2);
+        for i in 1..4 {
+            assert!(result.column(i).is_null(0), "column {i}");
+            assert!(result.column(i).is_valid(1), "column {i}");
+        }
+        let min = result.column(1).as_any().downcast_ref::<Int32Array>().unwrap();
+        assert_eq!(min.value(1), 3);
+        let counts = result.column(4).as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(counts.values(), &[0, 1]);
+    }
+}
+
+#[test]
+fn test_float_aggregates_of_no_values_are_null() {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("f", DataType::Float64, true),
+        Field::new("h", DataType::Float32, true),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Float64Array::from(vec![None, Some(1.5), None])),
+            Arc::new(arrow::array::Float32Array::from(vec![None, Some(2.5), None])),
+        ],
+    )
+    .unwrap();
+    let storage = StorageEngine::new(vec![batch]);
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine
+        .parse(
+            "SELECT AVG(f), MIN(f), MAX(f), AVG(h), MIN(h), MAX(h), SUM(f) FROM t \
+             WHERE f IS NULL",
+        )
+        .unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+    for i in 0..6 {
+        assert!(result.column(i).is_null(0), "column {i}");
+    }
+    let sum = result.column(6).as_any().downcast_ref::<Float64Array>().unwrap();
+    assert_eq!(sum.value(0), 0.0);
+}
+
+#[test]
+fn test_approximate_aggregates() {
+    // Several morsels; 1000 visitors, latencies 0..=9_999 (NULL every 10th row)
+    let rows = 200_000;
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("region", DataType::Utf8, false),
+        Field::new("visitor", DataType::Utf8, false),
+        Field::new("latency", DataType::Int64, true),
+    ]));
+    let regions: Vec<&str> = (0..rows).map(|i| if i % 2 == 0 { "eu" } else { "us" }).collect();
+    let visitors: Vec<String> = (0..rows).map(|i| format!("visitor-{}", i % 1000)).collect();
+    let latencies: Vec<Option<i64>> =
+        (0..rows).map(|i| (i % 10 != 0).then_some(i64::from(i % 10_000))).collect();
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(StringArray::from(regions)),
+            Arc::new(StringArray::from(visitors)),
+            Arc::new(Int64Array::from(latencies)),
+        ],
+    )
+    .unwrap();
+    let storage = StorageEngine::new(vec![batch]);
+
+    let engine = QueryEngine::new();
+    for threads in [1, 4] {
+        let executor = QueryExecutor::new().with_parallelism(threads);
+        let run = |sql: &str| executor.execute(&engine.parse(sql).unwrap(), &storage).unwrap();
+
+        let result = run("SELECT APPROX_COUNT_DISTINCT(visitor) AS visitors, \
+             APPROX_MEDIAN(latency) AS p50, APPROX_QUANTILE(latency, 0.99) AS p99 FROM t");
+        let visitors = result.column(0).as_any().downcast_ref::<Int64Array>().unwrap().value(0);
+        assert!((970..=1030).contains(&visitors), "{visitors}");
+        let quantile =
+            |i: usize| result.column(i).as_any().downcast_ref::<Float64Array>().unwrap().value(0);
+        assert!((quantile(1) - 5_000.0).abs() < 100.0, "p50 {}", quantile(1));
+        assert!((quantile(2) - 9_900.0).abs() < 50.0, "p99 {}", quantile(2));
+
+        // Even rows are "eu", so every visitor appears in one region only
+        let result = run("SELECT region, APPROX_COUNT_DISTINCT(visitor) FROM t GROUP BY region \
+             ORDER BY region");
+        let per_region = result.column(1).as_any().downcast_ref::<Int64Array>().unwrap();
+        assert!(per_region.values().iter().all(|&n| (485..=515).contains(&n)), "{per_region:?}");
+    }
+
+    assert!(engine.parse("SELECT APPROX_QUANTILE(latency, 1.5) FROM t").is_err());
+    assert!(engine.parse("SELECT APPROX_QUANTILE(latency) FROM t").is_err());
+    let plan = engine.parse("SELECT APPROX_MEDIAN(visitor) FROM t").unwrap();
+    assert!(QueryExecutor::new().execute(&plan, &storage).is_err());
+}
+
+#[test]
+fn test_exact_percentiles() {
+    // Several morsels; each region holds latencies 0..100_000 once
+    let rows = 200_000_i64;
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("region", DataType::Utf8, false),
+        Field::new("latency", DataType::Int64, true),
+    ]));
+    let regions: Vec<&str> = (0..rows).map(|i| if i % 2 == 0 { "eu" } else { "us" }).collect();
+    let latencies: Vec<i64> = (0..rows).map(|i| (i / 2 * 7919) % 100_000).collect();
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![Arc::new(StringArray::from(regions)), Arc::new(Int64Array::from(latencies))],
+    )
+    .unwrap();
+    let storage = StorageEngine::new(vec![batch]);
+
+    let engine = QueryEngine::new();
+    for threads in [1, 4] {
+        let executor = QueryExecutor::new().with_parallelism(threads);
+        let run = |sql: &str| executor.execute(&engine.parse(sql).unwrap(), &storage).unwrap();
+
+        let result = run("SELECT MEDIAN(latency) AS p50, PERCENTILE_CONT(latency, 0.95) AS p95 \
+             FROM t WHERE region = 'eu'");
+        let value =
+            |i: usize| result.column(i).as_any().downcast_ref::<Float64Array>().unwrap().value(0);
+        assert!((value(0) - 49_999.5).abs() < 1e-6, "p50 {}", value(0));
+        assert!((value(1) - 94_999.05).abs() < 1e-6, "p95 {}", value(1));
+
+        let result = run("SELECT region, PERCENTILE_CONT(latency, 0.5) FROM t GROUP BY region \
+             ORDER BY region");
+        let medians = result.column(1).as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(medians.values().to_vec(), vec![49_999.5, 49_999.5]);
+    }
+
+    assert!(engine.parse("SELECT PERCENTILE_CONT(latency, -0.1) FROM t").is_err());
+    let plan = engine.parse("SELECT MEDIAN(region) FROM t").unwrap();
+    assert!(QueryExecutor::new().execute(&plan, &storage).is_err());
+}
+
 #[test]
 fn test_where_filter_greater_than() {
     let storage = create_test_data();
@@ -160,6 +389,67 @@ fn test_where_filter_equals() {
     assert_eq!(id_col.value(0), 3);
 }
 
+#[test]
+fn test_where_compound_predicates() {
+    let storage = create_test_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let ids = |sql: &str| -> Vec<i32> {
+        let plan = engine.parse(sql).unwrap();
+        let result = executor.execute(&plan, &storage).unwrap();
+        result.column(0).as_any().downcast_ref::<Int32Array>().unwrap().values().to_vec()
+    };
+
+    assert_eq!(
+        ids("SELECT id FROM table1 WHERE (id <= 2 OR value > 35.0) AND NOT quantity = 500"),
+        vec![1, 2, 4]
+    );
+    assert_eq!(
+        ids("SELECT id FROM table1 WHERE id BETWEEN 2 AND 4 AND quantity NOT IN (300)"),
+        vec![2, 4]
+    );
+    assert_eq!(
+        ids("SELECT id FROM table1 WHERE id IN (1, 5) OR value BETWEEN 25.0 AND 35.0"),
+        vec![1, 3, 5]
+    );
+    // Column-to-column comparison across types (Int32 vs Float64)
+    assert_eq!(ids("SELECT id FROM table1 WHERE value < quantity AND id > 3"), vec![4, 5]);
+    assert!(ids("SELECT id FROM table1 WHERE value >= quantity").is_empty());
+}
+
+#[test]
+fn test_where_string_predicates() {
+    let storage = create_test_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let ids = |sql: &str| -> Vec<i32> {
+        let plan = engine.parse(sql).unwrap();
+        let result = executor.execute(&plan, &storage).unwrap();
+        result.column(0).as_any().downcast_ref::<Int32Array>().unwrap().values().to_vec()
+    };
+
+    assert_eq!(ids("SELECT id FROM table1 WHERE category = 'A'"), vec![1, 3]);
+    assert_eq!(ids("SELECT id FROM table1 WHERE category != 'A'"), vec![2, 4, 5]);
+    assert_eq!(
+        ids("SELECT id FROM table1 WHERE category IN ('B', 'C') AND value > 30.0"),
+        vec![4, 5]
+    );
+    assert_eq!(ids("SELECT id FROM table1 WHERE category LIKE 'C%' OR id = 1"), vec![1, 4]);
+
+    // Dictionary-encoded strings filter the same way
+    let mut storage = StorageEngine::new(vec![]).with_dictionary_threshold(0.9);
+    for batch in create_test_data().batches() {
+        storage.append_batch(batch.clone()).unwrap();
+    }
+    assert_eq!(storage.dictionary_columns(), &[1]);
+    let plan = engine.parse("SELECT id FROM table1 WHERE category = 'B'").unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+    let ids = result.column(0).as_any().downcast_ref::<Int32Array>().unwrap();
+    assert_eq!(ids.values().to_vec(), vec![2, 5]);
+}
+
 #[test]
 fn test_order_by_limit_top_k() {
     let storage = create_test_data();
@@ -194,6 +484,54 @@ fn test_order_by_ascending() {
     assert!((value_col.value(1) - 20.0).abs() < 0.01);
 }
 
+#[test]
+fn test_order_by_string_then_number_limit() {
+    let storage = create_test_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine
+        .parse("SELECT id, category FROM table1 ORDER BY category DESC, id ASC LIMIT 3")
+        .unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+
+    // C first, then both B rows by ascending id
+    let ids = result.column(0).as_any().downcast_ref::<Int32Array>().unwrap();
+    assert_eq!(ids.values(), &[4, 2, 5]);
+    let categories = result.column(1).as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(categories.value(0), "C");
+}
+
+#[test]
+fn test_order_by_mixed_directions() {
+    let storage = create_test_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    // Every key counts: categories ascending, values descending within each
+    let ids_of = |sql: &str| -> Vec<i32> {
+        let result = executor.execute(&engine.parse(sql).unwrap(), &storage).unwrap();
+        result.column(0).as_any().downcast_ref::<Int32Array>().unwrap().values().to_vec()
+    };
+    let select = "SELECT id, category, value FROM table1";
+    let top = ids_of(&format!("{select} ORDER BY category ASC, value DESC LIMIT 10"));
+    assert_eq!(top, vec![3, 1, 5, 2, 4]);
+    assert_eq!(ids_of(&format!("{select} ORDER BY category, value DESC")), top);
+    assert_eq!(ids_of(&format!("{select} ORDER BY category, value DESC LIMIT 3")), vec![3, 1, 5]);
+
+    // Grouped output: equal counts fall through to the second key
+    let plan = engine
+        .parse(
+            "SELECT category, COUNT(*) AS n, SUM(value) AS total FROM table1 \
+             GROUP BY category ORDER BY n DESC, total DESC",
+        )
+        .unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+    let categories = result.column(0).as_any().downcast_ref::<StringArray>().unwrap();
+    let categories: Vec<&str> = categories.iter().map(Option::unwrap).collect();
+    assert_eq!(categories, vec!["B", "A", "C"]);
+}
+
 #[test]
 fn test_limit_without_order_by() {
     let storage = create_test_data();
@@ -206,6 +544,60 @@ fn test_limit_without_order_by() {
     assert_eq!(result.num_rows(), 3);
 }
 
+#[test]
+fn test_limit_offset_pagination() {
+    let storage = create_test_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+    let ids_of = |sql: &str| -> Vec<i32> {
+        let result = executor.execute(&engine.parse(sql).unwrap(), &storage).unwrap();
+        result.column(0).as_any().downcast_ref::<Int32Array>().unwrap().values().to_vec()
+    };
+
+    // Pages of a sorted result, the last one short
+    let page = |offset: usize| {
+        ids_of(&format!("SELECT id FROM table1 ORDER BY id LIMIT 2 OFFSET {offset}"))
+    };
+    assert_eq!(page(0), vec![1, 2]);
+    assert_eq!(page(2), vec![3, 4]);
+    assert_eq!(page(4), vec![5]);
+    assert!(page(10).is_empty());
+
+    // Top-K over LIMIT + OFFSET rows, then the first OFFSET are skipped
+    let plan =
+        engine.parse("SELECT id, value FROM table1 ORDER BY value DESC LIMIT 2 OFFSET 1").unwrap();
+    assert_eq!(plan.offset, 1);
+    assert!(executor.explain(&plan, &storage).contains("k=3 OFFSET 1"));
+    assert_eq!(
+        ids_of("SELECT id, value FROM table1 ORDER BY value DESC LIMIT 2 OFFSET 1"),
+        vec![4, 3]
+    );
+
+    // Without ORDER BY or without LIMIT
+    assert_eq!(ids_of("SELECT id FROM table1 LIMIT 2 OFFSET 3"), vec![4, 5]);
+    assert_eq!(ids_of("SELECT id FROM table1 ORDER BY id OFFSET 3"), vec![4, 5]);
+
+    // Grouped results page too
+    let plan = engine
+        .parse(
+            "SELECT category, SUM(value) AS total FROM table1 GROUP BY category \
+             ORDER BY category LIMIT 1 OFFSET 1",
+        )
+        .unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+    let categories = result.column(0).as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(result.num_rows(), 1);
+    assert_eq!(categories.value(0), "B");
+
+    // Top-K pushed into a CTE keeps the OFFSET rows
+    let sql = "WITH r AS (SELECT id, value FROM table1) \
+               SELECT id, value FROM r ORDER BY value DESC LIMIT 2 OFFSET 1";
+    let plan = engine.parse(sql).unwrap();
+    assert_eq!(plan.rewrites.len(), 1);
+    assert_eq!(plan.rewrites[0].to_string(), "push ORDER BY + LIMIT 3 into CTE r");
+    assert_eq!(ids_of(sql), vec![4, 3]);
+}
+
 #[test]
 fn test_aggregation_with_filter() {
     let storage = create_test_data();
@@ -249,99 +641,1043 @@ fn test_multiple_aggregations() {
     assert!((max - 50.0).abs() < 0.01);
 }
 
-// Property-based tests using proptest
-#[cfg(test)]
-mod property_tests {
-    use super::*;
-    use proptest::prelude::*;
-
-    fn create_property_test_data(values: Vec<f64>) -> StorageEngine {
-        let schema = Arc::new(Schema::new(vec![
-            Field::new("id", DataType::Int32, false),
-            Field::new("value", DataType::Float64, false),
-        ]));
+#[test]
+fn test_select_arithmetic_and_scalar_functions() {
+    let storage = create_test_data();
+    let plan = QueryEngine::new()
+        .parse(
+            "SELECT id, value * quantity AS revenue, ABS(id - 3) AS distance, \
+             ROUND(value / 3, 1) FROM table1 ORDER BY revenue DESC LIMIT 2",
+        )
+        .unwrap();
+    let result = QueryExecutor::new().execute(&plan, &storage).unwrap();
+
+    let schema = result.schema();
+    let names: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+    assert_eq!(names, vec!["id", "revenue", "distance", "ROUND(value / 3, 1)"]);
+    let revenue = result.column(1).as_any().downcast_ref::<Float64Array>().unwrap();
+    assert_eq!(revenue.values().to_vec(), vec![25_000.0, 16_000.0]);
+    let distance = result.column(2).as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(distance.values().to_vec(), vec![2, 1]);
+    let rounded = result.column(3).as_any().downcast_ref::<Float64Array>().unwrap();
+    assert!((rounded.value(0) - 16.7).abs() < 1e-9);
+    assert!((rounded.value(1) - 13.3).abs() < 1e-9);
+}
 
-        let ids: Vec<i32> = (0..values.len() as i32).collect();
+#[test]
+fn test_aggregate_over_expressions() {
+    let storage = create_test_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
 
-        let batch = RecordBatch::try_new(
-            schema,
-            vec![Arc::new(Int32Array::from(ids)), Arc::new(Float64Array::from(values))],
+    let plan = engine
+        .parse(
+            "SELECT SUM(value * quantity) AS total, MAX(quantity - id) FROM table1 \
+             WHERE category = 'A'",
         )
         .unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+    let total = result.column(0).as_any().downcast_ref::<Float64Array>().unwrap();
+    assert!((total.value(0) - 10_000.0).abs() < 0.01);
+    let spread = result.column(1).as_any().downcast_ref::<Int32Array>().unwrap();
+    assert_eq!(spread.value(0), 297);
 
-        let mut storage = StorageEngine::new(vec![]);
-        storage.append_batch(batch).unwrap();
-        storage
-    }
+    let plan = engine
+        .parse("SELECT category, SUM(COALESCE(quantity, 0) * 2) AS doubled FROM table1 GROUP BY category")
+        .unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+    let doubled = result.column(1).as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(doubled.values().to_vec(), vec![800, 1400, 800]);
 
-    proptest! {
-        /// Property: COUNT(*) returns correct number of rows
-        #[test]
-        fn prop_count_returns_correct_rows(
-            values in prop::collection::vec(0.0f64..1000.0, 1..1000)
-        ) {
-            let storage = create_property_test_data(values.clone());
-            let engine = QueryEngine::new();
-            let executor = QueryExecutor::new();
+    assert!(executor
+        .execute(&engine.parse("SELECT SUM(value * category) FROM table1").unwrap(), &storage)
+        .is_err());
+}
 
-            let plan = engine.parse("SELECT COUNT(*) FROM table1").unwrap();
-            let result = executor.execute(&plan, &storage).unwrap();
+fn create_event_data() -> StorageEngine {
+    use arrow::array::{Date32Array, TimestampMicrosecondArray};
+    use arrow::datatypes::TimeUnit;
 
-            let count = result.column(0).as_any().downcast_ref::<arrow::array::Int64Array>().unwrap().value(0);
-            prop_assert_eq!(count as usize, values.len());
-        }
+    let utc = Some("UTC".into());
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("day", DataType::Date32, false),
+        Field::new("ts", DataType::Timestamp(TimeUnit::Microsecond, utc), false),
+        Field::new("value", DataType::Int32, false),
+    ]));
+    // 2024-03-01 and 2024-03-02 at 09:00, 12:00 and 18:00 UTC
+    let day_micros = 86_400_000_000_i64;
+    let first = 19_783_i64 * day_micros;
+    let hours = [9, 12, 18, 9, 12, 18];
+    let ts = hours
+        .iter()
+        .enumerate()
+        .map(|(i, h)| first + (i as i64 / 3) * day_micros + h * 3_600_000_000)
+        .collect::<Vec<_>>();
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Date32Array::from(vec![19_783, 19_783, 19_783, 19_784, 19_784, 19_784])),
+            Arc::new(TimestampMicrosecondArray::from(ts).with_timezone("UTC")),
+            Arc::new(Int32Array::from(vec![1, 2, 3, 4, 5, 6])),
+        ],
+    )
+    .unwrap();
+    StorageEngine::new(vec![batch])
+}
 
-        /// Property: SUM aggregation matches scalar sum
-        #[test]
-        fn prop_sum_matches_scalar(
-            values in prop::collection::vec(0.0f64..1000.0, 1..1000)
-        ) {
-            let storage = create_property_test_data(values.clone());
-            let engine = QueryEngine::new();
-            let executor = QueryExecutor::new();
+#[test]
+fn test_date_and_timestamp_filter_sort_and_aggregate() {
+    use arrow::array::{AsArray, Date32Array};
+    use arrow::datatypes::TimestampMicrosecondType;
 
-            let plan = engine.parse("SELECT SUM(value) FROM table1").unwrap();
-            let result = executor.execute(&plan, &storage).unwrap();
+    let storage = create_event_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+    let run = |sql: &str| executor.execute(&engine.parse(sql).unwrap(), &storage).unwrap();
+
+    let result =
+        run("SELECT value FROM events WHERE day = '2024-03-02' AND ts < '2024-03-02T15:00:00Z'");
+    let values = result.column(0).as_any().downcast_ref::<Int32Array>().unwrap();
+    assert_eq!(values.values().to_vec(), vec![4, 5]);
+
+    let result = run(
+        "SELECT MIN(ts), MAX(ts), MAX(day), COUNT(*) FROM events WHERE ts >= '2024-03-01 12:00:00'",
+    );
+    let min = result.column(0).as_primitive::<TimestampMicrosecondType>();
+    assert_eq!(min.timezone(), Some("UTC"));
+    assert_eq!(min.value_as_datetime(0).unwrap().to_string(), "2024-03-01 12:00:00");
+    let max = result.column(1).as_primitive::<TimestampMicrosecondType>();
+    assert_eq!(max.value_as_datetime(0).unwrap().to_string(), "2024-03-02 18:00:00");
+    let max_day = result.column(2).as_any().downcast_ref::<Date32Array>().unwrap();
+    assert_eq!(max_day.value(0), 19_784);
+    let count = result.column(3).as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(count.value(0), 5);
+
+    let result = run("SELECT value, ts FROM events ORDER BY ts DESC LIMIT 2");
+    let values = result.column(0).as_any().downcast_ref::<Int32Array>().unwrap();
+    assert_eq!(values.values().to_vec(), vec![6, 5]);
+
+    let result = run("SELECT day, COUNT(*), MIN(ts) FROM events GROUP BY day");
+    assert_eq!(result.num_rows(), 2);
+    let first_event = result.column(2).as_primitive::<TimestampMicrosecondType>();
+    assert_eq!(first_event.value_as_datetime(1).unwrap().to_string(), "2024-03-02 09:00:00");
+    assert!(executor
+        .execute(&engine.parse("SELECT SUM(ts) FROM events").unwrap(), &storage)
+        .is_err());
+}
 
-            let db_sum = result.column(0).as_any().downcast_ref::<Float64Array>().unwrap().value(0);
-            let expected_sum: f64 = values.iter().sum();
+#[test]
+fn test_time_bucketed_group_by() {
+    use arrow::array::{AsArray, Date32Array};
+    use arrow::datatypes::{Int32Type, TimestampMicrosecondType};
 
-            prop_assert!((db_sum - expected_sum).abs() < 0.001, "DB sum: {}, Expected: {}", db_sum, expected_sum);
-        }
+    let storage = create_event_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+    let run = |sql: &str| executor.execute(&engine.parse(sql).unwrap(), &storage).unwrap();
+
+    let result = run("SELECT date_trunc('day', ts) AS bucket, COUNT(*), SUM(value) FROM events \
+         GROUP BY bucket ORDER BY bucket");
+    assert_eq!(result.schema().field(0).name(), "bucket");
+    let buckets = result.column(0).as_primitive::<TimestampMicrosecondType>();
+    assert_eq!(buckets.value_as_datetime(1).unwrap().to_string(), "2024-03-02 00:00:00");
+    let sums = result.column(2).as_primitive::<arrow::datatypes::Int64Type>();
+    assert_eq!(sums.values().to_vec(), vec![6, 15]);
+
+    // Grouping by the expression itself, across days
+    let result = run("SELECT EXTRACT(HOUR FROM ts), SUM(value) FROM events \
+         GROUP BY EXTRACT(HOUR FROM ts) ORDER BY EXTRACT(HOUR FROM ts)");
+    let hours = result.column(0).as_primitive::<Int32Type>();
+    assert_eq!(hours.values().to_vec(), vec![9, 12, 18]);
+    let sums = result.column(1).as_primitive::<arrow::datatypes::Int64Type>();
+    assert_eq!(sums.values().to_vec(), vec![5, 7, 9]);
+
+    let result =
+        run("SELECT date_trunc('month', day) AS month, COUNT(*) FROM events GROUP BY month");
+    assert_eq!(result.num_rows(), 1);
+    let month = result.column(0).as_any().downcast_ref::<Date32Array>().unwrap();
+    assert_eq!(month.value(0), 19_783);
+}
 
-        /// Property: AVG aggregation matches scalar average
-        #[test]
-        fn prop_avg_matches_scalar(
-            values in prop::collection::vec(1.0f64..1000.0, 1..1000)
-        ) {
-            let storage = create_property_test_data(values.clone());
-            let engine = QueryEngine::new();
-            let executor = QueryExecutor::new();
+#[test]
+fn test_execute_stream_yields_batches_incrementally() {
+    let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+    let batches = (0..3)
+        .map(|i| {
+            let ids = Int32Array::from((i * 10..i * 10 + 10).collect::<Vec<_>>());
+            RecordBatch::try_new(schema.clone(), vec![Arc::new(ids)]).unwrap()
+        })
+        .collect();
+    let storage = StorageEngine::new(batches);
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
 
-            let plan = engine.parse("SELECT AVG(value) FROM table1").unwrap();
-            let result = executor.execute(&plan, &storage).unwrap();
+    // The first batch is ruled out by its zone map and never read
+    let plan = engine.parse("SELECT id FROM t WHERE id >= 15").unwrap();
+    let stream = executor.execute_stream(&plan, &storage).unwrap();
+    assert_eq!(stream.schema().field(0).name(), "id");
+    let streamed = stream.collect::<trueno_db::Result<Vec<_>>>().unwrap();
+    assert_eq!(streamed.iter().map(RecordBatch::num_rows).collect::<Vec<_>>(), vec![5, 10]);
+    let ids = arrow::compute::concat_batches(&streamed[0].schema(), &streamed).unwrap();
+    let expected = executor.execute(&plan, &storage).unwrap();
+    assert_eq!(ids.column(0).as_ref(), expected.column(0).as_ref());
+
+    // LIMIT ends the stream inside the first batch
+    let plan = engine.parse("SELECT * FROM t LIMIT 7").unwrap();
+    let mut stream = executor.execute_stream(&plan, &storage).unwrap();
+    assert_eq!(stream.next().unwrap().unwrap().num_rows(), 7);
+    assert!(stream.next().is_none());
+
+    // OFFSET rows are dropped as the stream passes them
+    let plan = engine.parse("SELECT id FROM t LIMIT 5 OFFSET 8").unwrap();
+    let streamed = executor.execute_stream(&plan, &storage).unwrap();
+    let streamed = streamed.collect::<trueno_db::Result<Vec<_>>>().unwrap();
+    let ids = arrow::compute::concat_batches(&streamed[0].schema(), &streamed).unwrap();
+    let ids = ids.column(0).as_any().downcast_ref::<Int32Array>().unwrap();
+    assert_eq!(ids.values(), &[8, 9, 10, 11, 12]);
+
+    // Aggregates need every row: one materialized batch
+    let plan = engine.parse("SELECT COUNT(*) FROM t WHERE id < 25").unwrap();
+    let streamed: Vec<_> = executor.execute_stream(&plan, &storage).unwrap().collect();
+    assert_eq!(streamed.len(), 1);
+    let count = streamed[0].as_ref().unwrap();
+    assert_eq!(count.column(0).as_any().downcast_ref::<Int64Array>().unwrap().value(0), 25);
+
+    let plan = engine.parse("SELECT missing FROM t").unwrap();
+    assert!(executor.execute_stream(&plan, &storage).is_err());
+}
 
-            let db_avg = result.column(0).as_any().downcast_ref::<Float64Array>().unwrap().value(0);
-            let expected_avg: f64 = values.iter().sum::<f64>() / values.len() as f64;
+#[test]
+fn test_dictionary_encoded_column_decoded_on_projection() {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("category", DataType::Utf8, false),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int32Array::from(vec![1, 2, 3, 4])),
+            Arc::new(StringArray::from(vec!["A", "B", "A", "B"])),
+        ],
+    )
+    .unwrap();
 
-            prop_assert!((db_avg - expected_avg).abs() < 0.001);
-        }
+    let mut storage = StorageEngine::new(vec![]).with_dictionary_threshold(0.5);
+    storage.append_batch(batch).unwrap();
+    assert_eq!(storage.dictionary_columns(), &[1]);
 
-        /// Property: ORDER BY + LIMIT returns values in correct order
-        #[test]
-        fn prop_order_by_limit_sorted(
-            values in prop::collection::vec(0.0f64..1000.0, 10..1000),
-            limit in 1usize..50
-        ) {
-            let storage = create_property_test_data(values.clone());
-            let engine = QueryEngine::new();
-            let executor = QueryExecutor::new();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+    let plan = engine.parse("SELECT category FROM table1 WHERE id > 2").unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
 
-            let sql = format!("SELECT value FROM table1 ORDER BY value DESC LIMIT {limit}");
-            let plan = engine.parse(&sql).unwrap();
-            let result = executor.execute(&plan, &storage).unwrap();
+    assert_eq!(result.schema().field(0).data_type(), &DataType::Utf8);
+    let categories = result.column(0).as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(categories.value(0), "A");
+    assert_eq!(categories.value(1), "B");
+}
 
-            // Verify descending order
+#[test]
+fn test_dictionary_encode_filters_and_groups_encoded() {
+    // Two morsels' worth of rows over three statuses
+    let rows = 100_000;
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("status", DataType::Utf8, true),
+        Field::new("latency", DataType::Int64, false),
+    ]));
+    let statuses: Vec<Option<&str>> =
+        (0..rows).map(|i| [Some("ok"), Some("error"), None, Some("ok")][i % 4]).collect();
+    let latencies: Vec<i64> = (0..rows as i64).map(|i| i % 10).collect();
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![Arc::new(StringArray::from(statuses)), Arc::new(Int64Array::from(latencies))],
+    )
+    .unwrap();
+    let mut storage = StorageEngine::new(vec![batch]);
+    storage.dictionary_encode("status").unwrap();
+    assert_eq!(storage.dictionary_columns(), &[0]);
+
+    let engine = QueryEngine::new();
+    let count = |sql: &str| {
+        let plan = engine.parse(sql).unwrap();
+        let result = QueryExecutor::new().execute(&plan, &storage).unwrap();
+        result.column(0).as_any().downcast_ref::<Int64Array>().unwrap().value(0)
+    };
+    assert_eq!(count("SELECT COUNT(latency) FROM t WHERE status = 'ok'"), 50_000);
+    assert_eq!(count("SELECT COUNT(latency) FROM t WHERE status != 'ok'"), 25_000);
+    assert_eq!(count("SELECT COUNT(latency) FROM t WHERE status LIKE 'err%'"), 25_000);
+    assert_eq!(count("SELECT COUNT(latency) FROM t WHERE status IS NULL"), 25_000);
+
+    let plan = engine
+        .parse("SELECT status, COUNT(latency) AS n FROM t GROUP BY status ORDER BY n DESC")
+        .unwrap();
+    for threads in [1, 4] {
+        let result =
+            QueryExecutor::new().with_parallelism(threads).execute(&plan, &storage).unwrap();
+        assert_eq!(result.schema().field(0).data_type(), &DataType::Utf8);
+        let status = result.column(0).as_any().downcast_ref::<StringArray>().unwrap();
+        let n = result.column(1).as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(status.value(0), "ok");
+        assert_eq!(n.values().to_vec(), vec![50_000, 25_000, 25_000]);
+        assert_eq!(status.null_count(), 1);
+    }
+}
+
+#[test]
+fn test_with_cte_materialized_as_temp_table() {
+    let storage = create_test_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine
+        .parse(
+            "WITH big AS (SELECT id, value FROM table1 WHERE value > 15.0) \
+             SELECT SUM(value) FROM big",
+        )
+        .unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+
+    let sum = result.column(0).as_any().downcast_ref::<Float64Array>().unwrap().value(0);
+    assert!((sum - 140.0).abs() < 0.01);
+}
+
+#[test]
+fn test_with_chained_ctes() {
+    let storage = create_test_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine
+        .parse(
+            "WITH a AS (SELECT id, value FROM table1 WHERE id > 1), \
+                  b AS (SELECT id, value FROM a WHERE value < 50.0) \
+             SELECT id FROM b ORDER BY id DESC LIMIT 2",
+        )
+        .unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+
+    let ids = result.column(0).as_any().downcast_ref::<Int32Array>().unwrap();
+    assert_eq!(result.num_rows(), 2);
+    assert_eq!(ids.value(0), 4);
+    assert_eq!(ids.value(1), 3);
+}
+
+/// Narrow and unsigned integer columns, as produced by categorical encoding
+fn create_narrow_int_data() -> StorageEngine {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("level", DataType::Int8, false),
+        Field::new("delta", DataType::Int16, false),
+        Field::new("code", DataType::UInt8, false),
+        Field::new("bytes", DataType::UInt64, false),
+    ]));
+
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int8Array::from(vec![100i8, 100, -20, 7])),
+            Arc::new(Int16Array::from(vec![-30000i16, -30000, 5, 12])),
+            Arc::new(UInt8Array::from(vec![250u8, 3, 255, 10])),
+            Arc::new(UInt64Array::from(vec![u64::MAX / 2, u64::MAX / 2, 1, 0])),
+        ],
+    )
+    .unwrap();
+
+    StorageEngine::new(vec![batch])
+}
+
+#[test]
+fn test_narrow_int_sum_widens() {
+    let storage = create_narrow_int_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine.parse("SELECT SUM(level), SUM(delta), SUM(code) FROM t").unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+
+    // 100 + 100 - 20 + 7 would overflow i8; -60000 + 17 would overflow i16
+    let level = result.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(level.value(0), 187);
+    let delta = result.column(1).as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(delta.value(0), -59983);
+    let code = result.column(2).as_any().downcast_ref::<UInt64Array>().unwrap();
+    assert_eq!(code.value(0), 518);
+}
+
+#[test]
+fn test_unsigned_sum_overflow_is_error() {
+    let storage = create_narrow_int_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine.parse("SELECT SUM(bytes) FROM t").unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+    let bytes = result.column(0).as_any().downcast_ref::<UInt64Array>().unwrap();
+    assert_eq!(bytes.value(0), u64::MAX);
+
+    let overflowing = StorageEngine::new(vec![RecordBatch::try_new(
+        Arc::new(Schema::new(vec![Field::new("bytes", DataType::UInt64, false)])),
+        vec![Arc::new(UInt64Array::from(vec![u64::MAX, 1]))],
+    )
+    .unwrap()]);
+    let plan = engine.parse("SELECT SUM(bytes) FROM t").unwrap();
+    assert!(executor.execute(&plan, &overflowing).is_err());
+}
+
+#[test]
+fn test_narrow_int_min_max_avg() {
+    let storage = create_narrow_int_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine.parse("SELECT MIN(code), MAX(level), AVG(code) FROM t").unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+
+    assert_eq!(result.schema().field(0).data_type(), &DataType::UInt8);
+    let min = result.column(0).as_any().downcast_ref::<UInt8Array>().unwrap();
+    assert_eq!(min.value(0), 3);
+    let max = result.column(1).as_any().downcast_ref::<Int8Array>().unwrap();
+    assert_eq!(max.value(0), 100);
+    let avg = result.column(2).as_any().downcast_ref::<Float64Array>().unwrap();
+    assert!((avg.value(0) - 129.5).abs() < f64::EPSILON);
+}
+
+#[test]
+fn test_narrow_int_min_max_of_nulls_is_null() {
+    let storage = StorageEngine::new(vec![RecordBatch::try_new(
+        Arc::new(Schema::new(vec![Field::new("code", DataType::UInt8, true)])),
+        vec![Arc::new(UInt8Array::from(vec![None, None]))],
+    )
+    .unwrap()]);
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine.parse("SELECT MIN(code), MAX(code) FROM t").unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+
+    assert!(result.column(0).is_null(0));
+    assert!(result.column(1).is_null(0));
+}
+
+#[test]
+fn test_narrow_int_filters() {
+    let storage = create_narrow_int_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine.parse("SELECT code FROM t WHERE code > 200").unwrap();
+    assert_eq!(executor.execute(&plan, &storage).unwrap().num_rows(), 2);
+
+    let plan = engine.parse("SELECT level FROM t WHERE level < 0").unwrap();
+    assert_eq!(executor.execute(&plan, &storage).unwrap().num_rows(), 1);
+
+    // Literal outside the column's range compares instead of failing to parse
+    let plan = engine.parse("SELECT code FROM t WHERE code < 1000").unwrap();
+    assert_eq!(executor.execute(&plan, &storage).unwrap().num_rows(), 4);
+}
+
+#[test]
+fn test_narrow_int_order_by_limit() {
+    let storage = create_narrow_int_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine.parse("SELECT code, delta FROM t ORDER BY code DESC LIMIT 2").unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+
+    let code = result.column(0).as_any().downcast_ref::<UInt8Array>().unwrap();
+    assert_eq!(code.values().to_vec(), vec![255, 250]);
+    let delta = result.column(1).as_any().downcast_ref::<Int16Array>().unwrap();
+    assert_eq!(delta.values().to_vec(), vec![5, -30000]);
+}
+
+#[test]
+fn test_narrow_int_order_by_keeps_nulls() {
+    let storage = StorageEngine::new(vec![RecordBatch::try_new(
+        Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("code", DataType::UInt8, true),
+        ])),
+        vec![
+            Arc::new(Int32Array::from(vec![1, 2, 3])),
+            Arc::new(UInt8Array::from(vec![Some(4), None, Some(9)])),
+        ],
+    )
+    .unwrap()]);
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine.parse("SELECT id, code FROM t ORDER BY id DESC LIMIT 2").unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+
+    let code = result.column(1).as_any().downcast_ref::<UInt8Array>().unwrap();
+    assert_eq!(code.iter().collect::<Vec<_>>(), vec![Some(9), None]);
+}
+
+/// Event data with a nullable Boolean marker
+fn create_boolean_data() -> StorageEngine {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("is_error", DataType::Boolean, true),
+    ]));
+
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int32Array::from(vec![1, 2, 3, 4, 5])),
+            Arc::new(BooleanArray::from(vec![
+                Some(true),
+                Some(false),
+                None,
+                Some(true),
+                Some(false),
+            ])),
+        ],
+    )
+    .unwrap();
+
+    StorageEngine::new(vec![batch])
+}
+
+#[test]
+fn test_where_bare_boolean_column() {
+    let storage = create_boolean_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine.parse("SELECT id FROM events WHERE is_error").unwrap();
+    let ids = executor.execute(&plan, &storage).unwrap();
+    let ids = ids.column(0).as_any().downcast_ref::<Int32Array>().unwrap();
+    assert_eq!(ids.values().to_vec(), vec![1, 4]);
+
+    // NULL is neither true nor false
+    let plan = engine.parse("SELECT id FROM events WHERE NOT is_error").unwrap();
+    let ids = executor.execute(&plan, &storage).unwrap();
+    let ids = ids.column(0).as_any().downcast_ref::<Int32Array>().unwrap();
+    assert_eq!(ids.values().to_vec(), vec![2, 5]);
+
+    let plan = engine.parse("SELECT id FROM events WHERE is_error = false").unwrap();
+    assert_eq!(executor.execute(&plan, &storage).unwrap().num_rows(), 2);
+}
+
+#[test]
+fn test_where_bare_non_boolean_column_fails() {
+    let storage = create_boolean_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine.parse("SELECT id FROM events WHERE id").unwrap();
+    let err = executor.execute(&plan, &storage).unwrap_err();
+    assert!(err.to_string().contains("must be Boolean"));
+}
+
+#[test]
+fn test_boolean_projection_with_order_by() {
+    let storage = create_boolean_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine.parse("SELECT id, is_error FROM events ORDER BY id DESC LIMIT 2").unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+
+    let flags = result.column(1).as_any().downcast_ref::<BooleanArray>().unwrap();
+    assert!(!flags.value(0));
+    assert!(flags.value(1));
+}
+
+#[test]
+fn test_boolean_aggregates() {
+    let storage = create_boolean_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine
+        .parse(
+            "SELECT COUNT_IF(is_error), SUM(CASE WHEN is_error THEN 1 ELSE 0 END), \
+             AVG(is_error), MAX(is_error), MIN(is_error) FROM events",
+        )
+        .unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+
+    let count_if = result.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(count_if.value(0), 2);
+    let sum_case = result.column(1).as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(sum_case.value(0), 2);
+    let avg = result.column(2).as_any().downcast_ref::<Float64Array>().unwrap();
+    assert!((avg.value(0) - 0.5).abs() < f64::EPSILON);
+    let max = result.column(3).as_any().downcast_ref::<BooleanArray>().unwrap();
+    assert!(max.value(0));
+    let min = result.column(4).as_any().downcast_ref::<BooleanArray>().unwrap();
+    assert!(!min.value(0));
+}
+
+#[test]
+fn test_count_if_requires_boolean() {
+    let storage = create_boolean_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine.parse("SELECT COUNT_IF(id) FROM events").unwrap();
+    let err = executor.execute(&plan, &storage).unwrap_err();
+    assert!(err.to_string().contains("COUNT_IF requires a Boolean column"));
+}
+
+#[test]
+fn test_result_carries_execution_stats() {
+    let storage = create_test_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new().with_query_stats(true);
+
+    let plan = engine.parse("SELECT id FROM table1 WHERE value > 25.0").unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+
+    let stats = QueryStats::from_batch(&result).expect("executor attaches stats");
+    assert_eq!(stats.rows_scanned, 5);
+    assert_eq!(stats.rows_returned, 3);
+    assert!(stats.elapsed_ms >= 0.0);
+    assert_eq!(stats.backend, trueno_db::Backend::Simd);
+
+    // Off by default: repeated runs return identical results
+    let untracked = QueryExecutor::new();
+    let first = untracked.execute(&plan, &storage).unwrap();
+    assert!(first.schema().metadata().is_empty());
+    assert_eq!(first, untracked.execute(&plan, &storage).unwrap());
+}
+
+#[test]
+fn test_execution_stats_count_cte_scans() {
+    let storage = create_test_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new().with_query_stats(true);
+
+    let plan = engine
+        .parse("WITH big AS (SELECT id FROM table1 WHERE id > 2) SELECT id FROM big")
+        .unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+
+    // 5 rows from table1, then 3 from the materialized CTE
+    let stats = QueryStats::from_batch(&result).unwrap();
+    assert_eq!(stats.rows_scanned, 8);
+    assert_eq!(stats.rows_returned, 3);
+}
+
+#[test]
+fn test_execution_report_lists_operators() {
+    let storage = create_test_data();
+    let plan = QueryEngine::new()
+        .parse(
+            "SELECT category, SUM(quantity) AS total FROM t WHERE id > 1 \
+             GROUP BY category ORDER BY total DESC LIMIT 2",
+        )
+        .unwrap();
+
+    let (result, report) = QueryExecutor::new().execute_with_report(&plan, &storage).unwrap();
+    let names: Vec<&str> = report.operators.iter().map(|op| op.operator.as_str()).collect();
+    assert_eq!(names, vec!["Scan", "Filter", "HashAggregate", "TopK"]);
+    assert_eq!(report.operator("Filter").map(|op| (op.rows_in, op.rows_out)), Some((5, 4)));
+    assert_eq!(report.operator("HashAggregate").map(|op| op.rows_out), Some(3));
+    assert_eq!(report.operator("TopK").map(|op| op.detail.as_str()), Some("total DESC k=2"));
+    let stats = report.stats();
+    assert_eq!((report.rows_scanned, report.rows_returned), (5, 2));
+    assert_eq!((stats.rows_scanned, result.num_rows()), (5, 2));
+
+    // Five rows never pay for a PCIe transfer
+    assert_eq!(report.transfer_bytes(), 0);
+
+    // A forced GPU pick ships the key and aggregate columns (4 rows x 2 x 8 bytes)
+    let (_, report) =
+        QueryExecutor::with_backend(Backend::Gpu).execute_with_report(&plan, &storage).unwrap();
+    let aggregate = report.operator("HashAggregate").unwrap();
+    assert_eq!(aggregate.planned_backend, Backend::Gpu);
+    assert_eq!(aggregate.backend, Backend::Simd);
+    assert_eq!(report.transfer_bytes(), 64);
+    assert!(report.to_string().contains("planned=gpu pcie=64B"), "{report}");
+}
+
+#[test]
+fn test_top_k_projection_gathers_only_top_rows() {
+    let storage = create_test_data();
+    let plan = QueryEngine::new()
+        .parse(
+            "SELECT category, value * 2 AS doubled FROM t WHERE id > 1 \
+             ORDER BY doubled DESC, category LIMIT 2",
+        )
+        .unwrap();
+
+    let (result, report) = QueryExecutor::new().execute_with_report(&plan, &storage).unwrap();
+    // Ranked on the sort key before the projection, which sees 2 rows
+    let names: Vec<&str> = report.operators.iter().map(|op| op.operator.as_str()).collect();
+    assert_eq!(names, vec!["Scan", "Filter", "TopK", "Project"]);
+    assert_eq!(report.operator("TopK").map(|op| (op.rows_in, op.rows_out)), Some((4, 2)));
+    assert_eq!(report.operator("Project").map(|op| op.rows_in), Some(2));
+
+    assert_eq!(result.num_columns(), 2);
+    let categories = result.column(0).as_any().downcast_ref::<StringArray>().unwrap();
+    let doubled = result.column(1).as_any().downcast_ref::<Float64Array>().unwrap();
+    assert_eq!((categories.value(0), categories.value(1)), ("B", "C"));
+    assert_eq!((doubled.value(0), doubled.value(1)), (100.0, 80.0));
+}
+
+#[test]
+fn test_execution_report_parallel_pipeline() {
+    let values: Vec<i32> = (0..100_000).collect();
+    let storage = StorageEngine::new(vec![RecordBatch::try_new(
+        Arc::new(Schema::new(vec![Field::new("v", DataType::Int32, false)])),
+        vec![Arc::new(Int32Array::from(values))],
+    )
+    .unwrap()]);
+    let plan = QueryEngine::new().parse("SELECT COUNT(*) FROM t WHERE v >= 50000").unwrap();
+
+    let executor = QueryExecutor::new().with_parallelism(2);
+    let (_, report) = executor.execute_with_report(&plan, &storage).unwrap();
+    let pipeline = report.operator("MorselPipeline").expect("parallel path taken");
+    assert_eq!(pipeline.rows_in, 100_000);
+    assert_eq!(pipeline.detail, "t (2 morsels, 2 workers)");
+    assert_eq!(report.rows_scanned, 100_000);
+}
+
+#[test]
+fn test_quoted_reserved_and_spaced_columns() {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("order", DataType::Int32, false),
+        Field::new("unit price", DataType::Float64, false),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int32Array::from(vec![1, 2, 3])),
+            Arc::new(Float64Array::from(vec![9.5, 1.0, 4.0])),
+        ],
+    )
+    .unwrap();
+    let storage = StorageEngine::new(vec![batch]);
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine
+        .parse(r#"SELECT "order" FROM t WHERE "unit price" > 2.0 ORDER BY "order" DESC LIMIT 1"#)
+        .unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+
+    assert_eq!(result.schema().field(0).name(), "order");
+    let order = result.column(0).as_any().downcast_ref::<Int32Array>().unwrap();
+    assert_eq!(order.values().to_vec(), vec![3]);
+}
+
+#[test]
+fn test_explain_reports_top_k_strategy() {
+    let values: Vec<i32> = (0..10_000).collect();
+    let storage = StorageEngine::new(vec![RecordBatch::try_new(
+        Arc::new(Schema::new(vec![Field::new("score", DataType::Int32, false)])),
+        vec![Arc::new(Int32Array::from(values))],
+    )
+    .unwrap()]);
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let small = engine.parse("SELECT score FROM t ORDER BY score DESC LIMIT 10").unwrap();
+    let explain = executor.explain(&small, &storage);
+    assert!(explain.contains("Scan: t (~10000 rows)"), "{explain}");
+    assert!(explain.contains("TopK: score DESC k=10 strategy=heap"), "{explain}");
+
+    // LIMIT is half the table: partial sort beats the heap
+    let large = engine.parse("SELECT score FROM t ORDER BY score DESC LIMIT 5000").unwrap();
+    let explain = executor.explain(&large, &storage);
+    assert!(explain.contains("strategy=sort"), "{explain}");
+
+    let result = executor.execute(&large, &storage).unwrap();
+    let scores = result.column(0).as_any().downcast_ref::<Int32Array>().unwrap();
+    assert_eq!(result.num_rows(), 5000);
+    assert_eq!(scores.value(0), 9999);
+    assert_eq!(scores.value(4999), 5000);
+}
+
+#[test]
+fn test_explain_with_cte_and_aggregate() {
+    let storage = create_test_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine
+        .parse(
+            "WITH big AS (SELECT value FROM table1 WHERE value > 15.0) SELECT SUM(value) FROM big",
+        )
+        .unwrap();
+    let explain = executor.explain(&plan, &storage);
+
+    assert_eq!(
+        explain,
+        "CTE big:\n  Scan: table1 (~5 rows)\n  Filter: value > 15.0\n  Project: value\n\
+         Scan: big (~5 rows)\nAggregate: SUM(value)"
+    );
+}
+
+#[test]
+fn test_explain_group_by_reports_backend() {
+    let storage = create_test_data();
+    let plan = QueryEngine::new()
+        .parse("SELECT category, SUM(quantity) FROM t GROUP BY category")
+        .unwrap();
+
+    // Five rows never pay for a PCIe transfer
+    let explain = QueryExecutor::new().explain(&plan, &storage);
+    assert!(
+        explain.ends_with("HashAggregate: SUM(quantity) GROUP BY category backend=simd"),
+        "{explain}"
+    );
+    assert_eq!(QueryExecutor::new().group_by_backend(5, 80), Backend::Simd);
+
+    let forced = QueryExecutor::with_backend(Backend::Gpu);
+    assert!(forced.explain(&plan, &storage).ends_with("backend=gpu"));
+    assert_eq!(forced.group_by_backend(5, 80), Backend::Gpu);
+}
+
+#[test]
+fn test_explain_statement_returns_plan_without_executing() {
+    let values: Vec<i32> = (0..100_000).collect();
+    let storage = StorageEngine::new(vec![RecordBatch::try_new(
+        Arc::new(Schema::new(vec![
+            Field::new("k", DataType::Int32, false),
+            Field::new("v", DataType::Int32, false),
+        ])),
+        vec![Arc::new(Int32Array::from(values.clone())), Arc::new(Int32Array::from(values))],
+    )
+    .unwrap()]);
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new().with_query_stats(true);
+
+    let plan =
+        engine.parse("EXPLAIN SELECT k, SUM(v) FROM t WHERE v > 10 GROUP BY k LIMIT 5").unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+    assert_eq!(result.num_rows(), 4);
+    assert_eq!(QueryStats::from_batch(&result).unwrap().rows_scanned, 0);
+
+    let text = |name: &str| {
+        let column = result.column_by_name(name).unwrap();
+        let column = column.as_any().downcast_ref::<StringArray>().unwrap();
+        (0..column.len()).map(|i| column.value(i).to_string()).collect::<Vec<_>>()
+    };
+    assert_eq!(text("operator"), vec!["Scan", "Filter", "HashAggregate", "Limit"]);
+    assert_eq!(text("backend"), vec!["simd"; 4]);
+
+    let flops = result.column_by_name("estimated_flops").unwrap();
+    let flops = flops.as_any().downcast_ref::<Float64Array>().unwrap();
+    assert_eq!(flops.value(1), 200_000.0);
+    assert_eq!(flops.value(2), 600_000.0);
+    let morsels = result.column_by_name("morsels").unwrap();
+    let morsels = morsels.as_any().downcast_ref::<UInt64Array>().unwrap();
+    assert_eq!(morsels.value(0), 2);
+
+    // The text form of the same plan
+    let explain = executor.explain(&plan, &storage);
+    assert!(explain.starts_with("Scan: t (~100000 rows)\nFilter: v > 10\n"), "{explain}");
+}
+
+#[test]
+fn test_lint_warnings_follow_explain() {
+    // 20 columns; c0 ascending, c1 shuffled
+    let fields: Vec<Field> =
+        (0..20).map(|i| Field::new(format!("c{i}"), DataType::Int32, false)).collect();
+    let columns: Vec<ArrayRef> = (0..20)
+        .map(|i| {
+            let values: Vec<i32> =
+                (0..100).map(|row| if i == 1 { (row * 37) % 100 } else { row }).collect();
+            Arc::new(Int32Array::from(values)) as ArrayRef
+        })
+        .collect();
+    let storage =
+        StorageEngine::new(vec![
+            RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).unwrap()
+        ]);
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine.parse("SELECT * FROM wide WHERE c1 > 50 ORDER BY c0").unwrap();
+    let kinds: Vec<Lint> = executor.lint(&plan, &storage).iter().map(|w| w.lint).collect();
+    assert_eq!(kinds, vec![Lint::SelectStar, Lint::SortWithoutLimit, Lint::UnsortedFilter]);
+
+    let explain = executor.explain(&plan, &storage);
+    assert!(explain.contains("Warning: select_star: SELECT * reads all 20 columns"), "{explain}");
+    assert!(explain.contains("Warning: unsorted_filter: WHERE on c1"), "{explain}");
+
+    // Narrow projection, sorted filter column, bounded sort: nothing to say
+    let plan = engine.parse("SELECT c0, c2 FROM wide WHERE c0 > 50 ORDER BY c0 LIMIT 5").unwrap();
+    assert!(executor.lint(&plan, &storage).is_empty());
+    assert!(!executor.explain(&plan, &storage).contains("Warning"));
+
+    // Linting never changes results
+    assert_eq!(executor.execute(&plan, &storage).unwrap().num_rows(), 5);
+}
+
+#[cfg(feature = "ipc-io")]
+#[test]
+fn test_order_by_without_limit_spills_over_budget() {
+    let values: Vec<i32> = (0..50_000).map(|i| (i * 7919) % 50_000).collect();
+    let storage = StorageEngine::new(vec![RecordBatch::try_new(
+        Arc::new(Schema::new(vec![Field::new("score", DataType::Int32, false)])),
+        vec![Arc::new(Int32Array::from(values))],
+    )
+    .unwrap()]);
+    let plan = QueryEngine::new().parse("SELECT score FROM t ORDER BY score DESC").unwrap();
+
+    // 16 KB budget forces several sorted runs for a ~200 KB column
+    let budget = 16 * 1024;
+    let external = QueryExecutor::new().with_sort_memory_budget(budget);
+    let (result, report) = external.execute_with_report(&plan, &storage).unwrap();
+    let in_memory = QueryExecutor::new().execute(&plan, &storage).unwrap();
+
+    assert_eq!(result.num_rows(), 50_000);
+    assert_eq!(result.column(0), in_memory.column(0));
+
+    // The scan feeds the sorter directly, which never buffers past the budget
+    let sort = report.operators.iter().find(|op| op.operator == "ExternalSort").unwrap();
+    let peak: usize =
+        sort.detail.split("peak ").nth(1).unwrap().split(' ').next().unwrap().parse().unwrap();
+    assert!(peak > 0 && peak <= budget, "peak {peak} over budget");
+    assert!(!sort.detail.contains("(0 runs"));
+
+    // The stream yields the merged runs batch by batch, OFFSET applied
+    let offset_plan =
+        QueryEngine::new().parse("SELECT score FROM t ORDER BY score DESC OFFSET 10").unwrap();
+    let batches: Vec<RecordBatch> =
+        external.execute_stream(&offset_plan, &storage).unwrap().collect::<Result<_, _>>().unwrap();
+    assert!(batches.len() > 1);
+    assert!(batches.iter().all(|b| b.num_rows() <= 8192));
+    let streamed: Vec<i32> = batches
+        .iter()
+        .flat_map(|b| b.column(0).as_any().downcast_ref::<Int32Array>().unwrap().values().to_vec())
+        .collect();
+    let expected = in_memory.column(0).as_any().downcast_ref::<Int32Array>().unwrap();
+    assert_eq!(streamed, expected.values()[10..]);
+}
+
+#[test]
+fn test_order_by_secondary_key_breaks_ties() {
+    // Three distinct grades over 60 rows, ids shuffled
+    let grades: Vec<i32> = (0..60).map(|i| i % 3).collect();
+    let ids: Vec<i32> = (0..60).map(|i| (i * 7) % 60).collect();
+    let storage = StorageEngine::new(vec![RecordBatch::try_new(
+        Arc::new(Schema::new(vec![
+            Field::new("grade", DataType::Int32, false),
+            Field::new("id", DataType::Int32, false),
+        ])),
+        vec![Arc::new(Int32Array::from(grades)), Arc::new(Int32Array::from(ids))],
+    )
+    .unwrap()]);
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+    let ids_of = |sql: &str| -> Vec<i32> {
+        let result = executor.execute(&engine.parse(sql).unwrap(), &storage).unwrap();
+        result.column(1).as_any().downcast_ref::<Int32Array>().unwrap().values().to_vec()
+    };
+
+    // Heap (LIMIT 4) and full sort agree, and grade-2 ids come out ascending
+    let heap = ids_of("SELECT grade, id FROM t ORDER BY grade DESC, id LIMIT 4");
+    let sorted = ids_of("SELECT grade, id FROM t ORDER BY grade DESC, id");
+    assert_eq!(heap, vec![2, 5, 8, 11]);
+    assert_eq!(heap, sorted[..4]);
+
+    // Without a secondary key, ties keep row order
+    let stable = ids_of("SELECT grade, id FROM t ORDER BY grade DESC LIMIT 3");
+    assert_eq!(stable, vec![14, 35, 56]);
+
+    let plan = engine.parse("SELECT grade, id FROM t ORDER BY grade DESC, id LIMIT 4").unwrap();
+    let explain = executor.explain(&plan, &storage);
+    assert!(explain.contains("TopK: grade DESC, id ASC k=4"), "{explain}");
+}
+
+// Property-based tests using proptest
+#[cfg(test)]
+mod property_tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn create_property_test_data(values: Vec<f64>) -> StorageEngine {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("value", DataType::Float64, false),
+        ]));
+
+        let ids: Vec<i32> = (0..values.len() as i32).collect();
+
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![Arc::new(Int32Array::from(ids)), Arc::new(Float64Array::from(values))],
+        )
+        .unwrap();
+
+        let mut storage = StorageEngine::new(vec![]);
+        storage.append_batch(batch).unwrap();
+        storage
+    }
+
+    proptest! {
+        /// Property: COUNT(*) returns correct number of rows
+        #[test]
+        fn prop_count_returns_correct_rows(
+            values in prop::collection::vec(0.0f64..1000.0, 1..1000)
+        ) {
+            let storage = create_property_test_data(values.clone());
+            let engine = QueryEngine::new();
+            let executor = QueryExecutor::new();
+
+            let plan = engine.parse("SELECT COUNT(*) FROM table1").unwrap();
+            let result = executor.execute(&plan, &storage).unwrap();
+
+            let count = result.column(0).as_any().downcast_ref::<arrow::array::Int64Array>().unwrap().value(0);
+            prop_assert_eq!(count as usize, values.len());
+        }
+
+        /// Property: SUM aggregation matches scalar sum
+        #[test]
+        fn prop_sum_matches_scalar(
+            values in prop::collection::vec(0.0f64..1000.0, 1..1000)
+        ) {
+            let storage = create_property_test_data(values.clone());
+            let engine = QueryEngine::new();
+            let executor = QueryExecutor::new();
+
+            let plan = engine.parse("SELECT SUM(value) FROM table1").unwrap();
+            let result = executor.execute(&plan, &storage).unwrap();
+
+            let db_sum = result.column(0).as_any().downcast_ref::<Float64Array>().unwrap().value(0);
+            let expected_sum: f64 = values.iter().sum();
+
+            prop_assert!((db_sum - expected_sum).abs() < 0.001, "DB sum: {}, Expected: {}", db_sum, expected_sum);
+        }
+
+        /// Property: AVG aggregation matches scalar average
+        #[test]
+        fn prop_avg_matches_scalar(
+            values in prop::collection::vec(1.0f64..1000.0, 1..1000)
+        ) {
+            let storage = create_property_test_data(values.clone());
+            let engine = QueryEngine::new();
+            let executor = QueryExecutor::new();
+
+            let plan = engine.parse("SELECT AVG(value) FROM table1").unwrap();
+            let result = executor.execute(&plan, &storage).unwrap();
+
+            let db_avg = result.column(0).as_any().downcast_ref::<Float64Array>().unwrap().value(0);
+            let expected_avg: f64 = values.iter().sum::<f64>() / values.len() as f64;
+
+            prop_assert!((db_avg - expected_avg).abs() < 0.001);
+        }
+
+        /// Property: ORDER BY + LIMIT returns values in correct order
+        #[test]
+        fn prop_order_by_limit_sorted(
+            values in prop::collection::vec(0.0f64..1000.0, 10..1000),
+            limit in 1usize..50
+        ) {
+            let storage = create_property_test_data(values.clone());
+            let engine = QueryEngine::new();
+            let executor = QueryExecutor::new();
+
+            let sql = format!("SELECT value FROM table1 ORDER BY value DESC LIMIT {limit}");
+            let plan = engine.parse(&sql).unwrap();
+            let result = executor.execute(&plan, &storage).unwrap();
+
+            // Verify descending order
             let result_col = result.column(0).as_any().downcast_ref::<Float64Array>().unwrap();
             for i in 0..result.num_rows().saturating_sub(1) {
                 prop_assert!(
@@ -359,3 +1695,667 @@ mod property_tests {
         }
     }
 }
+
+#[test]
+fn test_variance_and_stddev_aggregates() {
+    let storage = create_test_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine
+        .parse(
+            "SELECT VAR_POP(value), VARIANCE(value), STDDEV_POP(quantity), STDDEV(quantity) \
+             FROM table1",
+        )
+        .unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+    let value =
+        |i: usize| result.column(i).as_any().downcast_ref::<Float64Array>().unwrap().value(0);
+
+    assert!((value(0) - 200.0).abs() < 1e-9);
+    assert!((value(1) - 250.0).abs() < 1e-9);
+    assert!((value(2) - 20_000_f64.sqrt()).abs() < 1e-9);
+    assert!((value(3) - 25_000_f64.sqrt()).abs() < 1e-9);
+}
+
+#[test]
+fn test_sample_variance_of_single_row_is_null() {
+    let storage = create_test_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan =
+        engine.parse("SELECT VAR_SAMP(value), VAR_POP(value) FROM table1 WHERE id = 1").unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+
+    assert!(result.column(0).is_null(0));
+    assert!(result.schema().field(0).is_nullable());
+    let pop = result.column(1).as_any().downcast_ref::<Float64Array>().unwrap();
+    assert!(pop.value(0).abs() < f64::EPSILON);
+}
+
+#[test]
+fn test_group_by_single_key() {
+    let storage = create_test_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine
+        .parse("SELECT category, SUM(value), COUNT(*), AVG(quantity) FROM table1 GROUP BY category")
+        .unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+
+    assert_eq!(result.num_rows(), 3);
+    assert_eq!(result.schema().field(0).name(), "category");
+
+    let category = result.column(0).as_any().downcast_ref::<StringArray>().unwrap();
+    let sum = result.column(1).as_any().downcast_ref::<Float64Array>().unwrap();
+    let count = result.column(2).as_any().downcast_ref::<Int64Array>().unwrap();
+    let avg = result.column(3).as_any().downcast_ref::<Float64Array>().unwrap();
+
+    // Groups in order of first appearance: A (ids 1, 3), B (2, 5), C (4)
+    assert_eq!(category.iter().flatten().collect::<Vec<_>>(), vec!["A", "B", "C"]);
+    assert_eq!(sum.values().to_vec(), vec![40.0, 70.0, 40.0]);
+    assert_eq!(count.values().to_vec(), vec![2, 2, 1]);
+    assert_eq!(avg.values().to_vec(), vec![200.0, 350.0, 400.0]);
+}
+
+#[test]
+fn test_group_by_multiple_keys_with_order_by() {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("region", DataType::Utf8, true),
+        Field::new("year", DataType::Int64, false),
+        Field::new("amount", DataType::Float64, false),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(StringArray::from(vec![Some("eu"), Some("us"), Some("eu"), None, Some("eu")])),
+            Arc::new(Int64Array::from(vec![2023, 2023, 2024, 2024, 2023])),
+            Arc::new(Float64Array::from(vec![1.0, 2.0, 4.0, 8.0, 16.0])),
+        ],
+    )
+    .unwrap();
+    let mut storage = StorageEngine::new(vec![]);
+    storage.append_batch(batch).unwrap();
+
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+    let plan = engine
+        .parse(
+            "SELECT region, year, SUM(amount) AS total FROM sales \
+             GROUP BY region, year ORDER BY total DESC LIMIT 3",
+        )
+        .unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+
+    assert_eq!(result.num_rows(), 3);
+    let region = result.column(0).as_any().downcast_ref::<StringArray>().unwrap();
+    let year = result.column(1).as_any().downcast_ref::<Int64Array>().unwrap();
+    let total = result.column(2).as_any().downcast_ref::<Float64Array>().unwrap();
+
+    assert_eq!(result.schema().field(2).name(), "total");
+    assert_eq!(total.values().to_vec(), vec![17.0, 8.0, 4.0]);
+    assert_eq!((region.value(0), year.value(0)), ("eu", 2023));
+    assert!(region.is_null(1));
+    assert_eq!((region.value(2), year.value(2)), ("eu", 2024));
+}
+
+#[test]
+fn test_group_by_without_aggregates_returns_distinct_keys() {
+    let storage = create_test_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine.parse("SELECT category FROM table1 GROUP BY category").unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+
+    assert_eq!(result.num_columns(), 1);
+    let category = result.column(0).as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(category.iter().flatten().collect::<Vec<_>>(), vec!["A", "B", "C"]);
+}
+
+#[test]
+fn test_group_by_with_no_matching_rows_is_empty() {
+    let storage = create_test_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine
+        .parse("SELECT category, SUM(value) FROM table1 WHERE id > 100 GROUP BY category")
+        .unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+
+    assert_eq!(result.num_rows(), 0);
+    assert_eq!(result.num_columns(), 2);
+    assert_eq!(result.schema().field(1).data_type(), &DataType::Float64);
+}
+
+#[test]
+fn test_execute_batch_matches_individual_execution() {
+    let storage = create_test_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new().with_query_stats(true);
+
+    let plans: Vec<_> = [
+        "SELECT SUM(value) FROM table1 WHERE quantity > 150",
+        "SELECT category, COUNT(*) FROM table1 WHERE quantity > 150 GROUP BY category",
+        "SELECT id FROM table1 ORDER BY id DESC LIMIT 2",
+    ]
+    .iter()
+    .map(|sql| engine.parse(sql).unwrap())
+    .collect();
+
+    let results = executor.execute_batch(&plans, &storage).unwrap();
+    assert_eq!(results.len(), plans.len());
+    for (plan, batched) in plans.iter().zip(&results) {
+        let single = executor.execute(plan, &storage).unwrap();
+        assert_eq!(batched.columns(), single.columns());
+        assert_eq!(QueryStats::from_batch(batched).unwrap().rows_scanned, 5);
+    }
+}
+
+#[test]
+fn test_repeated_aggregate_evaluated_once() {
+    let storage = create_test_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine
+        .parse("SELECT SUM(value), SUM(value) AS total, COUNT(*) FROM table1 WHERE id > 1")
+        .unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+
+    assert_eq!(result.schema().field(1).name(), "total");
+    // The second SUM(value) reuses the first one's array
+    assert!(Arc::ptr_eq(result.column(0), result.column(1)));
+    let total = result.column(1).as_any().downcast_ref::<Float64Array>().unwrap();
+    assert!((total.value(0) - 140.0).abs() < f64::EPSILON);
+}
+
+#[test]
+fn test_repeated_grouped_aggregate_evaluated_once() {
+    let storage = create_test_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine
+        .parse(
+            "SELECT category, MAX(value), MIN(value), MAX(value) AS top FROM table1 \
+             GROUP BY category",
+        )
+        .unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+
+    assert!(Arc::ptr_eq(result.column(1), result.column(3)));
+    let min = result.column(2).as_any().downcast_ref::<Float64Array>().unwrap();
+    let top = result.column(3).as_any().downcast_ref::<Float64Array>().unwrap();
+    assert_eq!(min.values().to_vec(), vec![10.0, 20.0, 40.0]);
+    assert_eq!(top.values().to_vec(), vec![30.0, 50.0, 40.0]);
+}
+
+#[test]
+fn test_repeated_select_expression_evaluated_once() {
+    let storage = create_test_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine
+        .parse(
+            "SELECT value * quantity AS revenue, value * quantity AS gross, \
+             ROUND(value * quantity / 1000) AS thousands FROM table1 WHERE id > 3",
+        )
+        .unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+
+    // `gross` is the array computed for `revenue`
+    assert!(Arc::ptr_eq(result.column(0), result.column(1)));
+    let revenue = result.column(0).as_any().downcast_ref::<Float64Array>().unwrap();
+    let thousands = result.column(2).as_any().downcast_ref::<Float64Array>().unwrap();
+    assert_eq!(revenue.values().to_vec(), vec![16_000.0, 25_000.0]);
+    assert_eq!(thousands.values().to_vec(), vec![16.0, 25.0]);
+}
+
+fn create_customers() -> StorageEngine {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("category", DataType::Utf8, false),
+        Field::new("name", DataType::Utf8, true),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3, 9])),
+            Arc::new(StringArray::from(vec!["A", "B", "A", "C"])),
+            Arc::new(StringArray::from(vec![Some("ada"), Some("bob"), None, Some("eve")])),
+        ],
+    )
+    .unwrap();
+    StorageEngine::new(vec![batch])
+}
+
+#[test]
+fn test_inner_join_with_aggregation() {
+    let orders = create_test_data();
+    let customers = create_customers();
+    let tables = HashMap::from([("customers", &customers)]);
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new().with_query_stats(true);
+
+    // table1.id (Int32) joins customers.id (Int64); ids 4 and 5 have no customer
+    let plan = engine
+        .parse(
+            "SELECT c.category, SUM(t.value) AS total FROM table1 t \
+             JOIN customers c ON c.id = t.id GROUP BY c.category",
+        )
+        .unwrap();
+    let result = executor.execute_with_tables(&plan, &orders, &tables).unwrap();
+
+    assert_eq!(result.schema().field(0).name(), "c.category");
+    let category = result.column(0).as_any().downcast_ref::<StringArray>().unwrap();
+    let total = result.column(1).as_any().downcast_ref::<Float64Array>().unwrap();
+    assert_eq!(category.iter().flatten().collect::<Vec<_>>(), vec!["A", "B"]);
+    assert_eq!(total.values().to_vec(), vec![40.0, 20.0]);
+    assert_eq!(QueryStats::from_batch(&result).unwrap().rows_scanned, 9);
+}
+
+#[test]
+fn test_inner_join_projects_and_filters_joined_columns() {
+    let orders = create_test_data();
+    let customers = create_customers();
+    let tables = HashMap::from([("customers", &customers)]);
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine
+        .parse(
+            "SELECT name, quantity FROM table1 JOIN customers \
+             ON table1.category = customers.category WHERE quantity > 100",
+        )
+        .unwrap();
+    let result = executor.execute_with_tables(&plan, &orders, &tables).unwrap();
+
+    // Rows 2..5 of table1 (B, A, C, B) against customers by category
+    let name = result.column(0).as_any().downcast_ref::<StringArray>().unwrap();
+    let quantity = result.column(1).as_any().downcast_ref::<Int32Array>().unwrap();
+    assert_eq!(quantity.values().to_vec(), vec![200, 300, 300, 400, 500]);
+    assert_eq!(
+        name.iter().collect::<Vec<_>>(),
+        vec![Some("bob"), Some("ada"), None, Some("eve"), Some("bob")]
+    );
+}
+
+#[test]
+fn test_join_errors() {
+    let orders = create_test_data();
+    let customers = create_customers();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan =
+        engine.parse("SELECT id FROM table1 JOIN customers ON table1.id = customers.id").unwrap();
+    let missing = executor.execute(&plan, &orders).unwrap_err();
+    assert!(missing.to_string().contains("Table not found: customers"));
+
+    let tables = HashMap::from([("customers", &customers)]);
+    let ambiguous = executor.execute_with_tables(&plan, &orders, &tables).unwrap_err();
+    assert!(ambiguous.to_string().contains("Ambiguous column"));
+}
+
+#[test]
+fn test_explain_join_shows_backend() {
+    let orders = create_test_data();
+    let customers = create_customers();
+    let tables = HashMap::from([("customers", &customers)]);
+
+    let plan = QueryEngine::new()
+        .parse("SELECT name FROM table1 t JOIN customers c ON t.id = c.id")
+        .unwrap();
+    let explain = QueryExecutor::new().explain_with_tables(&plan, &orders, &tables);
+
+    assert!(explain.contains("HashJoin: customers ON t.id = c.id (~4 build rows) backend=simd"));
+}
+
+#[test]
+fn test_row_accessor_reads_grouped_results() {
+    let storage = create_test_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+
+    let plan = engine
+        .parse("SELECT category, COUNT(*) AS n, SUM(value) AS total FROM table1 GROUP BY category")
+        .unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+
+    let rows: Vec<(String, i64, f64)> = RowAccessor::new(&result)
+        .rows()
+        .map(|row| {
+            (
+                row.get("category").unwrap().unwrap(),
+                row.get("n").unwrap().unwrap(),
+                row.get("total").unwrap().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        rows,
+        vec![("A".to_string(), 2, 40.0), ("B".to_string(), 2, 70.0), ("C".to_string(), 1, 40.0)]
+    );
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn test_execute_async_matches_sync() {
+    let mut storage = create_test_data();
+    storage.append_batch(create_test_data().batches()[0].clone()).unwrap();
+    let engine = QueryEngine::new();
+    let token = trueno_db::CancellationToken::new();
+
+    for threads in [1, 4] {
+        let executor = QueryExecutor::new().with_parallelism(threads).with_query_stats(true);
+        for sql in [
+            "SELECT category, SUM(value) FROM table1 WHERE quantity > 100 GROUP BY category",
+            "SELECT id, value FROM table1 WHERE value > 10.0 ORDER BY value DESC LIMIT 3",
+            "SELECT * FROM table1",
+        ] {
+            let plan = engine.parse(sql).unwrap();
+            let expected = executor.execute(&plan, &storage).unwrap();
+            let actual = executor.execute_async(&plan, &storage, &token).await.unwrap();
+            assert_eq!(actual.columns(), expected.columns(), "{sql}");
+
+            let stats = QueryStats::from_batch(&actual).unwrap();
+            assert_eq!(stats.rows_scanned, 10);
+            assert_eq!(stats.rows_returned, expected.num_rows());
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn test_execute_async_cancelled() {
+    let storage = create_test_data();
+    let plan = QueryEngine::new().parse("SELECT COUNT(*) FROM table1").unwrap();
+    let token = trueno_db::CancellationToken::new();
+    token.cancel();
+
+    let err = QueryExecutor::new().execute_async(&plan, &storage, &token).await.unwrap_err();
+    assert!(matches!(err, trueno_db::Error::Cancelled(_)), "{err}");
+    assert!(err.to_string().contains("after scanning 0 of 5 rows"), "{err}");
+
+    let empty = StorageEngine::new(vec![]);
+    let result = QueryExecutor::new()
+        .execute_async(&plan, &empty, &trueno_db::CancellationToken::new())
+        .await;
+    assert!(result.is_err());
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_execute_async_with_tables_joins() {
+    let orders = create_test_data();
+    let customers = create_customers();
+    let tables = HashMap::from([("customers", &customers)]);
+    let executor = QueryExecutor::new();
+    let token = trueno_db::CancellationToken::new();
+
+    let plan = QueryEngine::new()
+        .parse("SELECT name FROM table1 t JOIN customers c ON t.id = c.id")
+        .unwrap();
+    let expected = executor.execute_with_tables(&plan, &orders, &tables).unwrap();
+    let actual = executor.execute_async_with_tables(&plan, &orders, &tables, &token).await.unwrap();
+    assert_eq!(actual.columns(), expected.columns());
+
+    let missing = executor.execute_async(&plan, &orders, &token).await.unwrap_err();
+    assert!(missing.to_string().contains("Table not found: customers"));
+}
+
+#[test]
+fn test_zone_maps_skip_unmatched_zones() {
+    // Two batches of sorted timestamps, each spanning two zones
+    let schema = Arc::new(Schema::new(vec![Field::new("ts", DataType::Int64, false)]));
+    let mut storage = StorageEngine::new(Vec::new());
+    for start in [0_i64, 100_000] {
+        let values: Vec<i64> = (start..start + 100_000).collect();
+        let batch =
+            RecordBatch::try_new(schema.clone(), vec![Arc::new(Int64Array::from(values))]).unwrap();
+        storage.append_batch(batch).unwrap();
+        // Built after the first append, then extended by the second
+        assert_eq!(storage.zone_maps().len(), storage.batches().len());
+    }
+    assert_eq!(storage.zone_maps().iter().map(Vec::len).sum::<usize>(), 4);
+
+    let plan = QueryEngine::new().parse("SELECT COUNT(*) FROM t WHERE ts >= 150000").unwrap();
+    for threads in [1, 4] {
+        let executor = QueryExecutor::new().with_parallelism(threads);
+        let (result, report) = executor.execute_with_report(&plan, &storage).unwrap();
+        let count = result.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(count.value(0), 50_000);
+        // The first batch tops out at 99999 and is never read
+        assert_eq!(report.rows_scanned, 100_000);
+    }
+
+    let plan = QueryEngine::new().parse("SELECT COUNT(*) FROM t WHERE ts < 0").unwrap();
+    let (result, report) = QueryExecutor::new().execute_with_report(&plan, &storage).unwrap();
+    assert_eq!(report.rows_scanned, 0);
+    assert_eq!(report.operator("Scan").unwrap().detail, "t (4 zones skipped)");
+    assert_eq!(result.column(0).as_any().downcast_ref::<Int64Array>().unwrap().value(0), 0);
+}
+
+#[test]
+fn test_query_limits() {
+    use std::time::Duration;
+    use trueno_db::query::{QueryLimits, ResourceLimit};
+
+    let schema = Arc::new(Schema::new(vec![Field::new("v", DataType::Int64, false)]));
+    let values = Int64Array::from_iter_values(0..300_000);
+    let batch = RecordBatch::try_new(schema, vec![Arc::new(values)]).unwrap();
+    let storage = StorageEngine::new(vec![batch]);
+    let engine = QueryEngine::new();
+    let tripped = |limits: QueryLimits, threads: usize, sql: &str| {
+        let executor = QueryExecutor::new().with_parallelism(threads).with_limits(limits);
+        match executor.execute(&engine.parse(sql).unwrap(), &storage) {
+            Err(trueno_db::Error::ResourceLimitExceeded { limit, .. }) => Some(limit),
+            Err(e) => panic!("{e}"),
+            Ok(_) => None,
+        }
+    };
+
+    for threads in [1, 4] {
+        let rows = QueryLimits::new().with_max_output_rows(1_000);
+        assert_eq!(tripped(rows, threads, "SELECT v FROM t"), Some(ResourceLimit::OutputRows));
+        assert_eq!(tripped(rows, threads, "SELECT v FROM t LIMIT 1000"), None);
+        assert_eq!(tripped(rows, threads, "SELECT SUM(v) FROM t"), None);
+
+        let memory = QueryLimits::new().with_max_memory_bytes(64 * 1024);
+        assert_eq!(
+            tripped(memory, threads, "SELECT v FROM t WHERE v > 10"),
+            Some(ResourceLimit::Memory)
+        );
+        assert_eq!(tripped(memory, threads, "SELECT v FROM t WHERE v < 10"), None);
+
+        let time = QueryLimits::new().with_max_execution_time(Duration::ZERO);
+        assert_eq!(
+            tripped(time, threads, "SELECT COUNT(*) FROM t"),
+            Some(ResourceLimit::ExecutionTime)
+        );
+    }
+
+    // EXPLAIN describes the plan without running it
+    let rows = QueryLimits::new().with_max_output_rows(0);
+    assert_eq!(tripped(rows, 1, "EXPLAIN SELECT v FROM t"), None);
+}
+
+#[test]
+fn test_optimizer_rewrites() {
+    let storage = create_test_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+    let run = |sql: &str| executor.execute(&engine.parse(sql).unwrap(), &storage).unwrap();
+
+    // Literal-only expressions are folded and constant predicates eliminated
+    assert_eq!(run("SELECT id FROM t WHERE quantity > 100 * 2 AND 1 = 1").num_rows(), 3);
+    assert_eq!(run("SELECT id FROM t WHERE 2 > 1 OR quantity < 0").num_rows(), 5);
+    let result = run("SELECT COUNT(*) FROM t WHERE quantity > 0 AND 1 > 2");
+    let count_col = result.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(count_col.value(0), 0);
+
+    let explain =
+        executor.explain(&engine.parse("SELECT id FROM t WHERE id > 2 * 2").unwrap(), &storage);
+    assert!(explain.starts_with("Rewrite: fold constants: id > 2 * 2 => id > 4\n"), "{explain}");
+    assert!(explain.contains("\nFilter: id > 4\n"), "{explain}");
+
+    // ORDER BY + LIMIT over a plain CTE ranks inside the CTE
+    let sql = "WITH big AS (SELECT id, quantity FROM t WHERE quantity > 100) \
+               SELECT id, quantity FROM big ORDER BY quantity DESC LIMIT 2";
+    let plan = engine.parse(sql).unwrap();
+    let unoptimized = QueryEngine::new().with_optimizer(false).parse(sql).unwrap();
+    assert_eq!(plan.ctes[0].1.limit, Some(2));
+    assert_eq!(unoptimized.ctes[0].1.limit, None);
+    assert!(unoptimized.rewrites.is_empty());
+    let result = executor.execute(&plan, &storage).unwrap();
+    assert_eq!(result.columns(), executor.execute(&unoptimized, &storage).unwrap().columns());
+    let ids = result.column(0).as_any().downcast_ref::<Int32Array>().unwrap();
+    assert_eq!(ids.values(), &[5, 4]);
+    assert!(executor
+        .explain(&plan, &storage)
+        .contains("Rewrite: push ORDER BY + LIMIT 2 into CTE big"));
+
+    // Repeated aggregates share one partial per morsel when run in parallel
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("k", DataType::Int64, false),
+        Field::new("v", DataType::Int64, false),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(Int64Array::from_iter_values((0..200_000).map(|i| i % 3))),
+            Arc::new(Int64Array::from_iter_values(0..200_000)),
+        ],
+    )
+    .unwrap();
+    let storage = StorageEngine::new(vec![batch]);
+    let plan = engine
+        .parse("SELECT k, SUM(v), AVG(v), SUM(v) AS total FROM t GROUP BY k ORDER BY k")
+        .unwrap();
+    let parallel = QueryExecutor::new().with_parallelism(4).execute(&plan, &storage).unwrap();
+    let serial = executor.execute(&plan, &storage).unwrap();
+    assert_eq!(parallel.columns(), serial.columns());
+    assert_eq!(parallel.schema().field(3).name(), "total");
+    assert_eq!(parallel.column(1), parallel.column(3));
+}
+
+#[test]
+fn test_uncorrelated_subqueries() {
+    let storage = create_test_data();
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+    let ids_of = |sql: &str| -> Vec<i32> {
+        let result = executor.execute(&engine.parse(sql).unwrap(), &storage).unwrap();
+        result.column(0).as_any().downcast_ref::<Int32Array>().unwrap().values().to_vec()
+    };
+
+    // Scalar subquery in a comparison (AVG(value) = 30)
+    assert_eq!(
+        ids_of("SELECT id FROM table1 WHERE value > (SELECT AVG(value) FROM table1) ORDER BY id"),
+        vec![4, 5]
+    );
+
+    // IN / NOT IN over the categories of large orders (C and B)
+    let large = "SELECT category FROM table1 WHERE quantity >= 400";
+    assert_eq!(
+        ids_of(&format!("SELECT id FROM table1 WHERE category IN ({large}) ORDER BY id")),
+        vec![2, 4, 5]
+    );
+    assert_eq!(
+        ids_of(&format!("SELECT id FROM table1 WHERE category NOT IN ({large}) ORDER BY id")),
+        vec![1, 3]
+    );
+
+    // An empty result: nothing is IN it, everything is NOT IN it
+    let none = "SELECT id FROM table1 WHERE value > 100";
+    assert!(ids_of(&format!("SELECT id FROM table1 WHERE id IN ({none})")).is_empty());
+    assert_eq!(ids_of(&format!("SELECT id FROM table1 WHERE id NOT IN ({none})")).len(), 5);
+
+    // A scalar subquery must return at most one row
+    let plan =
+        engine.parse("SELECT id FROM table1 WHERE value > (SELECT value FROM table1)").unwrap();
+    assert!(executor.execute(&plan, &storage).is_err());
+
+    // Subqueries read other tables and CTEs
+    let vip = RecordBatch::try_new(
+        Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)])),
+        vec![Arc::new(Int32Array::from(vec![2, 5]))],
+    )
+    .unwrap();
+    let vip = StorageEngine::new(vec![vip]);
+    let plan =
+        engine.parse("SELECT id FROM table1 WHERE id IN (SELECT id FROM vip) ORDER BY id").unwrap();
+    assert_eq!(plan.subqueries.len(), 1);
+    assert_eq!(plan.base_tables(), vec!["table1", "vip"]);
+    let tables = HashMap::from([("vip", &vip)]);
+    let result = executor.execute_with_tables(&plan, &storage, &tables).unwrap();
+    let ids = result.column(0).as_any().downcast_ref::<Int32Array>().unwrap();
+    assert_eq!(ids.values(), &[2, 5]);
+
+    assert_eq!(
+        ids_of(
+            "WITH big AS (SELECT id FROM table1 WHERE value >= 40) \
+             SELECT id FROM table1 WHERE id IN (SELECT id FROM big) ORDER BY id"
+        ),
+        vec![4, 5]
+    );
+
+    // Aggregates over the filtered rows
+    let plan = engine
+        .parse("SELECT COUNT(*) FROM table1 WHERE value >= (SELECT MAX(value) FROM table1)")
+        .unwrap();
+    let result = executor.execute(&plan, &storage).unwrap();
+    assert_eq!(result.column(0).as_any().downcast_ref::<Int64Array>().unwrap().value(0), 1);
+}
+
+#[test]
+fn test_subqueries_across_numeric_types() {
+    let batch = RecordBatch::try_new(
+        Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("small", DataType::Int32, false),
+            Field::new("big", DataType::Int64, false),
+            Field::new("f", DataType::Float64, false),
+        ])),
+        vec![
+            Arc::new(Int32Array::from(vec![1, 2, 3, 4, 5])),
+            Arc::new(Int32Array::from(vec![1, 5, 10, 14, 19])),
+            Arc::new(Int64Array::from(vec![100, 200, 300, 400, 500])),
+            Arc::new(Float64Array::from(vec![200.0, 450.0, 0.5, 500.0, 1.5])),
+        ],
+    )
+    .unwrap();
+    let storage = StorageEngine::new(vec![batch]);
+    let engine = QueryEngine::new();
+    let executor = QueryExecutor::new();
+    let ids_of = |sql: &str| -> Vec<i32> {
+        let result = executor.execute(&engine.parse(sql).unwrap(), &storage).unwrap();
+        result.column(0).as_any().downcast_ref::<Int32Array>().unwrap().values().to_vec()
+    };
+
+    // AVG is Float64: 9.8 for the Int32 column, a whole 300.0 for the Int64 one
+    assert_eq!(
+        ids_of("SELECT id FROM table1 WHERE small > (SELECT AVG(small) FROM table1) ORDER BY id"),
+        vec![3, 4, 5]
+    );
+    assert_eq!(
+        ids_of("SELECT id FROM table1 WHERE big > (SELECT AVG(big) FROM table1) ORDER BY id"),
+        vec![4, 5]
+    );
+    assert_eq!(ids_of("SELECT id FROM table1 WHERE big = (SELECT AVG(big) FROM table1)"), vec![3]);
+
+    // Integer values IN a set of floats
+    assert_eq!(
+        ids_of("SELECT id FROM table1 WHERE big IN (SELECT f FROM table1) ORDER BY id"),
+        vec![2, 5]
+    );
+    assert_eq!(
+        ids_of("SELECT id FROM table1 WHERE small NOT IN (SELECT f FROM table1) ORDER BY id"),
+        vec![1, 2, 3, 4, 5]
+    );
+}
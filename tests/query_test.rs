@@ -1,6 +1,6 @@
 //! Tests for query engine
 
-use trueno_db::query::{AggregateFunction, OrderDirection, QueryEngine};
+use trueno_db::query::{AggregateFunction, JoinClause, OrderDirection, QueryEngine};
 
 #[test]
 fn test_query_engine_parse() {
@@ -88,10 +88,80 @@ fn test_all_aggregations() {
 }
 
 #[test]
-fn test_reject_joins() {
+fn test_variance_aggregations() {
     let engine = QueryEngine::new();
-    let result = engine.parse("SELECT * FROM users JOIN orders ON users.id = orders.user_id");
-    assert!(result.is_err(), "JOINs should be rejected");
+    let sql = "SELECT VARIANCE(a), VAR_POP(a), STDDEV(a), stddev_pop(a), VAR_SAMP(a), STDDEV_SAMP(a) FROM t";
+    let plan = engine.parse(sql).unwrap();
+
+    let functions: Vec<_> = plan.aggregations.iter().map(|(f, _, _)| *f).collect();
+    assert_eq!(
+        functions,
+        vec![
+            AggregateFunction::VarSamp,
+            AggregateFunction::VarPop,
+            AggregateFunction::StddevSamp,
+            AggregateFunction::StddevPop,
+            AggregateFunction::VarSamp,
+            AggregateFunction::StddevSamp,
+        ]
+    );
+    assert_eq!(AggregateFunction::StddevPop.sql_name(), "STDDEV_POP");
+}
+
+#[test]
+fn test_parse_inner_join() {
+    let engine = QueryEngine::new();
+    let plan = engine
+        .parse(
+            "SELECT u.name, SUM(o.total) FROM users u JOIN orders o \
+             ON o.user_id = u.id AND o.region = u.region GROUP BY u.name",
+        )
+        .unwrap();
+
+    assert_eq!(plan.table, "users");
+    assert_eq!(plan.table_alias.as_deref(), Some("u"));
+    assert_eq!(
+        plan.joins,
+        vec![JoinClause {
+            table: "orders".to_string(),
+            alias: Some("o".to_string()),
+            // Oriented as (rows read so far, joined table)
+            on: vec![
+                ("u.id".to_string(), "o.user_id".to_string()),
+                ("u.region".to_string(), "o.region".to_string()),
+            ],
+        }]
+    );
+}
+
+#[test]
+fn test_plan_base_tables_skip_cte_names() {
+    let engine = QueryEngine::new();
+    let plan = engine
+        .parse(
+            "WITH big AS (SELECT * FROM orders WHERE total > 100), \
+             vip AS (SELECT * FROM big) \
+             SELECT u.name FROM users u JOIN vip v ON v.user_id = u.id JOIN orders o \
+             ON o.user_id = u.id",
+        )
+        .unwrap();
+
+    assert_eq!(plan.base_tables(), vec!["orders", "users"]);
+    assert_eq!(plan.source_table(), "users");
+
+    let plan = engine.parse("WITH big AS (SELECT * FROM orders) SELECT * FROM big").unwrap();
+    assert_eq!(plan.base_tables(), vec!["orders"]);
+    assert_eq!(plan.source_table(), "orders");
+}
+
+#[test]
+fn test_reject_unsupported_joins() {
+    let engine = QueryEngine::new();
+    let outer = engine.parse("SELECT * FROM users LEFT JOIN orders ON users.id = orders.user_id");
+    assert!(outer.is_err(), "Outer JOINs should be rejected");
+
+    let non_equi = engine.parse("SELECT * FROM users JOIN orders ON users.id > orders.user_id");
+    assert!(non_equi.is_err(), "Non-equality JOIN conditions should be rejected");
 }
 
 #[test]
@@ -168,3 +238,140 @@ fn test_invalid_sql_syntax() {
     assert!(result.is_err(), "Invalid SQL should fail");
     assert!(result.unwrap_err().to_string().contains("parse error"));
 }
+
+#[test]
+fn test_with_clause_cte() {
+    let engine = QueryEngine::new();
+    let plan = engine
+        .parse("WITH big AS (SELECT id, score FROM data WHERE score > 10) SELECT id FROM big")
+        .unwrap();
+
+    assert_eq!(plan.table, "big");
+    assert_eq!(plan.ctes.len(), 1);
+    assert_eq!(plan.ctes[0].0, "big");
+    assert_eq!(plan.ctes[0].1.table, "data");
+    assert!(plan.ctes[0].1.filter.is_some());
+}
+
+#[test]
+fn test_reject_recursive_cte() {
+    let engine = QueryEngine::new();
+    let result = engine.parse("WITH RECURSIVE t AS (SELECT id FROM data) SELECT * FROM t");
+    assert!(result.is_err(), "Recursive CTEs should be rejected");
+    assert!(result.unwrap_err().to_string().contains("Recursive CTEs"));
+}
+
+#[test]
+fn test_parse_count_if() {
+    let engine = QueryEngine::new();
+    let plan = engine.parse("SELECT COUNT_IF(is_error) AS errors FROM events").unwrap();
+
+    assert_eq!(
+        plan.aggregations,
+        vec![(AggregateFunction::CountIf, "is_error".to_string(), Some("errors".to_string()))]
+    );
+}
+
+#[test]
+fn test_parse_sum_case_when_as_count_if() {
+    let engine = QueryEngine::new();
+    let plan = engine
+        .parse(
+            "SELECT SUM(CASE WHEN is_error THEN 1 ELSE 0 END), SUM(CASE WHEN x THEN 2 END) FROM t",
+        )
+        .unwrap();
+
+    assert_eq!(plan.aggregations[0].0, AggregateFunction::CountIf);
+    assert_eq!(plan.aggregations[0].1, "is_error");
+    // Anything other than THEN 1 / ELSE 0 is left as a plain SUM
+    assert_eq!(plan.aggregations[1].0, AggregateFunction::Sum);
+}
+
+#[test]
+fn test_parse_explain() {
+    let engine = QueryEngine::new();
+    let plan = engine.parse("EXPLAIN SELECT SUM(x) FROM t WHERE x > 1").unwrap();
+    assert!(plan.explain);
+    assert_eq!(plan.table, "t");
+    assert_eq!(plan.filter.as_deref(), Some("x > 1"));
+    assert!(!engine.parse("SELECT SUM(x) FROM t").unwrap().explain);
+
+    let err = engine.parse("EXPLAIN ANALYZE SELECT x FROM t").unwrap_err();
+    assert!(err.to_string().contains("EXPLAIN ANALYZE not supported"));
+    assert!(engine.parse("EXPLAIN DROP TABLE t").is_err());
+}
+
+#[test]
+fn test_quoted_identifiers_bind_like_unquoted() {
+    let engine = QueryEngine::new();
+    let plan = engine
+        .parse(
+            r#"WITH "Recent" AS (SELECT "id", "order" FROM "Sales") SELECT "order", SUM("id") FROM "Recent" GROUP BY "order" ORDER BY "order" DESC"#,
+        )
+        .unwrap();
+
+    assert_eq!(plan.table, "Recent");
+    assert_eq!(plan.ctes[0].0, "Recent");
+    assert_eq!(plan.ctes[0].1.table, "Sales");
+    assert_eq!(plan.ctes[0].1.columns, vec!["id", "order"]);
+    assert_eq!(plan.columns, vec!["order"]);
+    assert_eq!(plan.aggregations[0].1, "id");
+    assert_eq!(plan.group_by, vec!["order"]);
+    assert_eq!(plan.order_by[0].0, "order");
+}
+
+#[test]
+fn test_quoted_filter_is_canonical() {
+    let engine = QueryEngine::new();
+    let plan = engine.parse(r#"SELECT * FROM t WHERE "order" > 5"#).unwrap();
+    assert_eq!(plan.filter.as_deref(), Some("order > 5"));
+
+    let plan = engine.parse("SELECT * FROM t WHERE `unit price` >= 2.5").unwrap();
+    assert_eq!(plan.filter.as_deref(), Some(r#""unit price" >= 2.5"#));
+}
+
+#[test]
+fn test_dialect_selection() {
+    use trueno_db::query::SqlDialect;
+
+    assert_eq!(QueryEngine::new().dialect(), SqlDialect::Generic);
+
+    // ANSI identifiers must start with a letter; Generic also allows `_`
+    let sql = "SELECT _id FROM t";
+    assert!(QueryEngine::new().parse(sql).is_ok());
+    assert!(QueryEngine::with_dialect(SqlDialect::Ansi).parse(sql).is_err());
+
+    let plan =
+        QueryEngine::with_dialect(SqlDialect::DuckDb).parse(r#"SELECT "id" FROM t"#).unwrap();
+    assert_eq!(plan.columns, vec!["id"]);
+}
+
+#[test]
+fn test_access_policy_checks_every_clause() {
+    use std::sync::Arc;
+    use trueno_db::query::access::DenyList;
+    use trueno_db::Error;
+
+    let policy =
+        DenyList::new().deny_column("agent", "users", "ssn").deny_table("agent", "payroll");
+    let engine = QueryEngine::new().with_access_policy(Arc::new(policy));
+    let denied = |sql: &str| matches!(engine.parse_as(sql, "agent"), Err(Error::AccessDenied(_)));
+
+    assert!(engine.parse_as("SELECT id, name FROM users WHERE age > 18", "agent").is_ok());
+    assert!(engine.parse_as("SELECT COUNT(*) FROM users", "agent").is_ok());
+    assert!(denied("SELECT ssn FROM users"));
+    assert!(denied("SELECT ssn AS x FROM users"));
+    assert!(denied("SELECT id FROM users WHERE ssn = '123'"));
+    assert!(denied("SELECT MAX(ssn) FROM users"));
+    assert!(denied("SELECT id FROM users ORDER BY ssn"));
+    assert!(denied("SELECT * FROM users"));
+    assert!(denied("SELECT id FROM payroll"));
+
+    // CTE bodies are checked; references to the CTE are not re-checked
+    assert!(denied("WITH p AS (SELECT id FROM payroll) SELECT id FROM p"));
+    assert!(engine.parse_as("WITH u AS (SELECT id FROM users) SELECT * FROM u", "agent").is_ok());
+
+    // Other principals and the anonymous parse are unaffected by agent's rules
+    assert!(engine.parse_as("SELECT ssn FROM users", "admin").is_ok());
+    assert!(engine.parse("SELECT * FROM payroll").is_ok());
+}
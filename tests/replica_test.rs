@@ -0,0 +1,147 @@
+//! Integration tests for the shared database directory (one writer, many readers)
+//!
+//! Toyota Way: Poka-Yoke (readers only ever observe committed snapshots)
+
+use arrow::array::Int32Array;
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use parquet::basic::Compression;
+use parquet::file::metadata::ColumnChunkMetaData;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use trueno_db::replica::{DatabaseReader, DatabaseWriter};
+use trueno_db::storage::{ColumnCodec, ParquetWriteOptions, StorageEngine};
+use trueno_db::Catalog;
+
+fn create_storage(values: Vec<i32>) -> StorageEngine {
+    let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+    let batch = RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(values))]).unwrap();
+    StorageEngine::new(vec![batch])
+}
+
+fn catalog_with(values: Vec<i32>) -> Catalog {
+    let mut catalog = Catalog::new();
+    catalog.register("events", create_storage(values));
+    catalog.register("empty", StorageEngine::new(Vec::new()));
+    catalog
+}
+
+fn fresh_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(format!("/tmp/trueno_test_replica_{name}"));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn row_count(reader: &DatabaseReader, table: &str) -> usize {
+    reader.catalog().get(table).unwrap().batches().iter().map(RecordBatch::num_rows).sum()
+}
+
+#[test]
+fn test_reader_sees_committed_snapshot() {
+    let dir = fresh_dir("commit");
+    let mut writer = DatabaseWriter::open(&dir).unwrap();
+    assert_eq!(writer.current_snapshot().unwrap(), None);
+    assert!(DatabaseReader::open(&dir).is_err());
+
+    let id = writer.commit(&catalog_with(vec![1, 2, 3])).unwrap();
+    assert_eq!(id, 1);
+
+    let reader = DatabaseReader::open(&dir).unwrap();
+    assert_eq!(reader.snapshot_id(), 1);
+    assert_eq!(reader.catalog().table_names(), vec!["empty", "events"]);
+    assert_eq!(row_count(&reader, "events"), 3);
+    assert_eq!(row_count(&reader, "empty"), 0);
+}
+
+#[test]
+fn test_second_writer_is_rejected() {
+    let dir = fresh_dir("lock");
+    let writer = DatabaseWriter::open(&dir).unwrap();
+    assert!(DatabaseWriter::open(&dir).is_err());
+
+    drop(writer);
+    assert!(DatabaseWriter::open(&dir).is_ok());
+}
+
+#[test]
+fn test_reader_keeps_snapshot_until_refresh() {
+    let dir = fresh_dir("refresh");
+    let mut writer = DatabaseWriter::open(&dir).unwrap().with_retained_snapshots(1);
+    writer.commit(&catalog_with(vec![1])).unwrap();
+
+    let mut reader = DatabaseReader::open(&dir).unwrap();
+
+    // Pinned snapshot survives GC even though only one snapshot is retained
+    writer.commit(&catalog_with(vec![1, 2])).unwrap();
+    writer.commit(&catalog_with(vec![1, 2, 3])).unwrap();
+    assert_eq!(reader.snapshot_id(), 1);
+    assert_eq!(row_count(&reader, "events"), 1);
+    assert_eq!(DatabaseReader::open(&dir).unwrap().snapshot_id(), 3);
+
+    assert!(reader.refresh().unwrap());
+    assert_eq!(reader.snapshot_id(), 3);
+    assert_eq!(row_count(&reader, "events"), 3);
+    assert!(!reader.refresh().unwrap());
+
+    // Snapshot 1 is unpinned now and collected on the next commit
+    writer.commit(&catalog_with(vec![0])).unwrap();
+    assert!(!dir.join("snapshots").join(format!("{:020}", 1)).exists());
+}
+
+#[test]
+fn test_temporary_tables_are_not_committed() {
+    let dir = fresh_dir("temporary");
+    let mut writer = DatabaseWriter::open(&dir).unwrap();
+
+    let mut catalog = catalog_with(vec![1]);
+    catalog.register_temporary("scratch", create_storage(vec![9, 9])).unwrap();
+    writer.commit(&catalog).unwrap();
+
+    let reader = DatabaseReader::open(&dir).unwrap();
+    assert_eq!(reader.catalog().table_names(), vec!["empty", "events"]);
+}
+
+fn column_chunk_metadata(dir: &Path) -> ColumnChunkMetaData {
+    let file =
+        fs::File::open(dir.join("snapshots/00000000000000000001/table_0000.parquet")).unwrap();
+    let reader = SerializedFileReader::new(file).unwrap();
+    reader.metadata().row_group(0).column(0).clone()
+}
+
+#[test]
+fn test_write_options_control_column_encoding() {
+    let dir = fresh_dir("write_options");
+    let mut catalog = Catalog::new();
+    catalog.register("events", create_storage(vec![1, 1, 2, 2]));
+
+    let options = ParquetWriteOptions::new()
+        .with_column_codec("id", ColumnCodec::Uncompressed)
+        .with_column_dictionary("id", false);
+    let mut writer = DatabaseWriter::open(&dir).unwrap().with_write_options(options);
+    writer.commit(&catalog).unwrap();
+
+    let column = column_chunk_metadata(&dir);
+    assert_eq!(column.compression(), Compression::UNCOMPRESSED);
+    assert_eq!(column.dictionary_page_offset(), None);
+
+    let reader = DatabaseReader::open(&dir).unwrap();
+    assert_eq!(row_count(&reader, "events"), 4);
+}
+
+#[cfg(feature = "parquet-zstd")]
+#[test]
+fn test_write_options_zstd_column() {
+    let dir = fresh_dir("write_options_zstd");
+    let mut catalog = Catalog::new();
+    catalog.register("events", create_storage((0..1000).collect()));
+
+    let options = ParquetWriteOptions::new().with_column_codec("id", ColumnCodec::Zstd(9));
+    let mut writer = DatabaseWriter::open(&dir).unwrap().with_write_options(options);
+    writer.commit(&catalog).unwrap();
+
+    assert!(matches!(column_chunk_metadata(&dir).compression(), Compression::ZSTD(_)));
+    let reader = DatabaseReader::open(&dir).unwrap();
+    assert_eq!(row_count(&reader, "events"), 1000);
+}